@@ -0,0 +1,25 @@
+//! An RC low pass exported as the binary of a co-simulation FMU.
+//!
+//! Build it with `cargo build --example rc_filter_fmu --features fmu` and package the library
+//! with `rice::fmi::package_fmu`, giving the slave, the GUID and the library's bytes.
+
+use rice::components::{Capacitor, Netlist, Resistor, VoltageSource};
+use rice::fmi::CircuitSlave;
+use rice::results::Probe;
+
+/// Identifies the model description the library is packaged with.
+const GUID: &str = "{8c4e810f-3df3-4a00-8276-176fa3c9f000}";
+
+/// A 1ms RC low pass driven by its input `u`, with the capacitor voltage as its output `y`.
+fn rc_filter() -> CircuitSlave {
+    let mut netlist = Netlist::new();
+    netlist
+        .add_component(VoltageSource::new(1, 0, 0.0))
+        .add_component(Resistor::new(1, 2, 1e3))
+        .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+    CircuitSlave::new(&netlist, 10e-6)
+        .with_input("u", 0)
+        .with_output("y", Probe::NodeVoltage(2))
+}
+
+rice::export_fmu!(GUID, rc_filter);
@@ -0,0 +1,487 @@
+mod network;
+mod small_signal;
+mod state_space;
+mod sweep;
+
+pub use small_signal::SmallSignalError;
+pub use state_space::StateSpace;
+pub use sweep::AdaptiveSweep;
+
+use std::f64::consts::PI;
+
+use nalgebra::{Complex, DMatrix, Dyn, LU};
+
+use crate::be_solver::matrix_view::{ABMatrixView, ViewEquationIndex, node_voltages};
+use crate::be_solver::stampable::Stampable;
+use crate::be_solver::{LOCAL_REFERENCE_CONDUCTANCE, stamp_local_references};
+use crate::components::Netlist;
+use crate::{ConvergenceReport, Remedy, SolverError, SolverOptions};
+
+/// Relative frequency step used to differentiate the phase when computing group delay.
+const GROUP_DELAY_RELATIVE_STEP: f64 = 1e-4;
+
+/// A small signal AC solver.
+///
+/// Every component is linearized around its present state, so nonlinear circuits should first be
+/// brought to their operating point with a transient solver. Sources are excited with the phasors
+/// set by their `with_ac` builders.
+pub struct ACSolver<'n> {
+    netlist: &'n Netlist,
+    options: SolverOptions,
+}
+
+impl<'n> ACSolver<'n> {
+    pub fn new(netlist: &'n Netlist) -> Self {
+        Self {
+            netlist,
+            options: SolverOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn get_netlist(&self) -> &Netlist {
+        self.netlist
+    }
+
+    /// Solves the circuit at the given frequency in hertz.
+    ///
+    /// Fails with [`SolverError::SingularMatrix`] if the system can't be solved, e.g. at DC
+    /// (0Hz) for a node connected only through capacitors.
+    pub fn solve(&self, frequency: f64) -> Result<ACSolution, SolverError> {
+        self.solve_with_injections(frequency, &[], true)
+    }
+
+    /// Solves the circuit at each of the given frequencies.
+    pub fn sweep(&self, frequencies: &[f64]) -> Result<Vec<ACSolution>, SolverError> {
+        frequencies.iter().map(|&f| self.solve(f)).collect()
+    }
+
+    /// Computes the group delay -dphi/domega of a node voltage at the given frequency. As the
+    /// phases of the sources don't depend on frequency, this is the delay from the sources to
+    /// the node.
+    pub fn group_delay(&self, node: usize, frequency: f64) -> Result<f64, SolverError> {
+        let delta = frequency * GROUP_DELAY_RELATIVE_STEP;
+        let below = self.solve(frequency - delta)?.get_node_voltage(node);
+        let above = self.solve(frequency + delta)?.get_node_voltage(node);
+
+        // The phase difference is taken from the ratio so it doesn't wrap.
+        Ok(-(above / below).arg() / (2.0 * PI * 2.0 * delta))
+    }
+
+    /// Computes the impedance looking into the port between two nodes with every independent
+    /// source zeroed (voltage sources shorted, current sources opened).
+    pub fn port_impedance(
+        &self,
+        positive_node: usize,
+        negative_node: usize,
+        frequency: f64,
+    ) -> Result<Complex<f64>, SolverError> {
+        // A 1A test current is pushed into the positive node, so the port voltage is the
+        // impedance.
+        let solution = self.solve_with_injections(
+            frequency,
+            &[(positive_node, negative_node, Complex::from(-1.0))],
+            false,
+        )?;
+        Ok(solution.get_voltage_between(positive_node, negative_node))
+    }
+
+    /// Computes the impedance seen by the source at the given index, looking into the circuit
+    /// from its terminals with every other source zeroed.
+    pub fn input_impedance(
+        &self,
+        source: usize,
+        frequency: f64,
+    ) -> Result<Complex<f64>, SolverError> {
+        let mut netlist = self.netlist.clone();
+        let source = netlist.remove_component(source);
+
+        ACSolver::new(&netlist)
+            .with_options(self.options)
+            .port_impedance(
+                source.get_positive_node(),
+                source.get_negative_node(),
+                frequency,
+            )
+    }
+
+    /// Solves the circuit at the given frequency with additional currents flowing out of the
+    /// positive node and into the negative node of each injection. If `include_sources` is false
+    /// the independent sources are zeroed.
+    pub(crate) fn solve_with_injections(
+        &self,
+        frequency: f64,
+        injections: &[(usize, usize, Complex<f64>)],
+        include_sources: bool,
+    ) -> Result<ACSolution, SolverError> {
+        Ok(self
+            .factorize(frequency)?
+            .solve_with_injections(injections, include_sources))
+    }
+
+    /// Stamps and factorizes the system at the given frequency so it can be solved for several
+    /// excitations without factorizing again.
+    pub(crate) fn factorize(&self, frequency: f64) -> Result<FactorizedSystem, SolverError> {
+        let omega = 2.0 * PI * frequency;
+
+        let num_nodes = self.netlist.get_num_nodes();
+        let reference = self.netlist.get_reference_node();
+        let num_variables: usize = self
+            .netlist
+            .get_components()
+            .iter()
+            .map(|c| c.num_variables())
+            .sum();
+
+        let dimension = num_nodes + num_variables;
+
+        let mut a = DMatrix::zeros(dimension, dimension);
+        let mut b = DMatrix::zeros(dimension, 1);
+
+        self.netlist
+            .get_components()
+            .iter()
+            .fold(num_nodes, |variables_start, c| {
+                let mut view = ABMatrixView::new(
+                    &mut a,
+                    &mut b,
+                    num_nodes,
+                    reference,
+                    c.num_variables(),
+                    variables_start,
+                );
+                c.stamp_ac(&mut view, omega);
+
+                for (positive_node, negative_node) in c.junctions() {
+                    view.conductance_add(
+                        positive_node,
+                        negative_node,
+                        Complex::from(self.options.gmin),
+                    );
+                }
+
+                variables_start + c.num_variables()
+            });
+
+        let mut view = ABMatrixView::new(&mut a, &mut b, num_nodes, reference, 0, 0);
+        stamp_local_references(
+            self.netlist,
+            &mut view,
+            Complex::from(LOCAL_REFERENCE_CONDUCTANCE),
+        );
+
+        let lu = a.lu();
+        if !lu.is_invertible() {
+            return Err(self.singular(frequency));
+        }
+
+        Ok(FactorizedSystem {
+            frequency,
+            num_nodes,
+            reference,
+            lu,
+            sources: b,
+        })
+    }
+
+    /// Reports a system that couldn't be factorized at the given frequency. At DC the culprit
+    /// is usually a node only reached through capacitors or current sources.
+    fn singular(&self, frequency: f64) -> SolverError {
+        let floating = if frequency == 0.0 {
+            self.netlist.find_floating_nodes()
+        } else {
+            Vec::new()
+        };
+        let remedies = if floating.is_empty() {
+            Vec::new()
+        } else {
+            vec![Remedy::TieFloatingNodes { nodes: floating }]
+        };
+
+        SolverError::from(Box::new(ConvergenceReport {
+            time: 0.0,
+            dt: 0.0,
+            iterations: 0,
+            singular: true,
+            worst_variables: Vec::new(),
+            culprit: None,
+            largest_stamps: Vec::new(),
+            extreme_nodes: Vec::new(),
+            remedies,
+        }))
+    }
+}
+
+/// The LU factorization of the system at a single frequency.
+pub(crate) struct FactorizedSystem {
+    frequency: f64,
+    num_nodes: usize,
+    reference: usize,
+    lu: LU<Complex<f64>, Dyn, Dyn>,
+    sources: DMatrix<Complex<f64>>,
+}
+
+impl FactorizedSystem {
+    /// Solves the system with additional current injections, see
+    /// [`ACSolver::solve_with_injections`].
+    pub(crate) fn solve_with_injections(
+        &self,
+        injections: &[(usize, usize, Complex<f64>)],
+        include_sources: bool,
+    ) -> ACSolution {
+        let mut b = if include_sources {
+            self.sources.clone()
+        } else {
+            DMatrix::zeros(self.sources.nrows(), 1)
+        };
+
+        // The injected currents are constants, so they move to the result with flipped signs.
+        for &(positive_node, negative_node, current) in injections {
+            for (node, sign) in [(positive_node, -1.0), (negative_node, 1.0)] {
+                let equation = ViewEquationIndex::NodalEquation(node);
+                if let Some(row) = equation.into_global_index(self.num_nodes, self.reference, 0, 0)
+                {
+                    b[(row, 0)] += current * sign;
+                }
+            }
+        }
+
+        let x = self
+            .lu
+            .solve(&b)
+            .expect("the system was checked to be invertible when factorized");
+
+        let node_voltages = node_voltages(&x, self.num_nodes, self.reference);
+
+        ACSolution {
+            frequency: self.frequency,
+            node_voltages,
+            x,
+        }
+    }
+}
+
+/// The solution of the circuit at a single frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ACSolution {
+    frequency: f64,
+    node_voltages: Vec<Complex<f64>>,
+    x: DMatrix<Complex<f64>>,
+}
+
+impl ACSolution {
+    pub fn get_frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Gets the voltage phasors of every node relative to the reference node, indexed by node
+    /// number.
+    pub fn get_node_voltages(&self) -> &Vec<Complex<f64>> {
+        &self.node_voltages
+    }
+
+    pub fn get_node_voltage(&self, node: usize) -> Complex<f64> {
+        self.node_voltages[node]
+    }
+
+    /// Gets the voltage phasor between two nodes.
+    pub fn get_voltage_between(&self, positive_node: usize, negative_node: usize) -> Complex<f64> {
+        self.get_node_voltage(positive_node) - self.get_node_voltage(negative_node)
+    }
+
+    /// Gets the magnitude of a node voltage in decibels.
+    pub fn get_gain_db(&self, node: usize) -> f64 {
+        20.0 * self.get_node_voltage(node).norm().log10()
+    }
+
+    /// Gets the phase of a node voltage in degrees.
+    pub fn get_phase(&self, node: usize) -> f64 {
+        self.get_node_voltage(node).arg().to_degrees()
+    }
+
+    /// Gets the full solution vector, node voltages followed by the component variables.
+    pub fn get_solution(&self) -> &DMatrix<Complex<f64>> {
+        &self.x
+    }
+}
+
+/// Computes the reflection coefficient of an impedance against a reference impedance.
+pub fn reflection_coefficient(impedance: Complex<f64>, reference: f64) -> Complex<f64> {
+    (impedance - reference) / (impedance + reference)
+}
+
+/// Computes the voltage standing wave ratio of an impedance against a reference impedance.
+pub fn vswr(impedance: Complex<f64>, reference: f64) -> f64 {
+    let gamma = reflection_coefficient(impedance, reference).norm();
+    (1.0 + gamma) / (1.0 - gamma)
+}
+
+/// Generates `points_per_decade` logarithmically spaced frequencies per decade from `start` to
+/// `stop`, inclusive.
+pub fn log_frequencies(start: f64, stop: f64, points_per_decade: usize) -> Vec<f64> {
+    let decades = (stop / start).log10();
+    let points = (decades * points_per_decade as f64).round() as usize;
+
+    (0..=points)
+        .map(|k| start * 10f64.powf(decades * k as f64 / points.max(1) as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::components::{Capacitor, Inductor, Netlist, Resistor, VoltageSource};
+
+    use super::*;
+
+    #[test]
+    fn rc_lowpass_corner() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let corner = 1.0 / (2.0 * PI * 1e3 * 1e-6);
+        let solution = ACSolver::new(&netlist).solve(corner).unwrap();
+
+        assert_relative_eq!(
+            solution.get_node_voltage(2).norm(),
+            1.0 / 2f64.sqrt(),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(solution.get_phase(2), -45.0, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn rl_highpass_branch_current() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Inductor::new(2, 0, 1e-3, 0.0).with_branch_current());
+
+        let corner = 1e3 / (2.0 * PI * 1e-3);
+        let solution = ACSolver::new(&netlist).solve(corner).unwrap();
+
+        assert_relative_eq!(
+            solution.get_node_voltage(2).norm(),
+            1.0 / 2f64.sqrt(),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(solution.get_phase(2), 45.0, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn rc_group_delay() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        // The group delay of a first order lowpass is RC/(1 + (wRC)^2).
+        let solver = ACSolver::new(&netlist);
+        let rc = 1e-3;
+        for frequency in [10.0, 159.0, 1e3] {
+            let wrc = 2.0 * PI * frequency * rc;
+            assert_relative_eq!(
+                solver.group_delay(2, frequency).unwrap(),
+                rc / (1.0 + wrc * wrc),
+                max_relative = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn impedances() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let solver = ACSolver::new(&netlist);
+        let frequency = 100.0;
+        let zc = Complex::new(0.0, 2.0 * PI * frequency * 1e-6).inv();
+
+        // Looking into the source terminals: the resistor in series with the capacitor.
+        let expected = zc + 1e3;
+        assert_relative_eq!(
+            (solver.input_impedance(0, frequency).unwrap() - expected).norm(),
+            0.0,
+            epsilon = 1e-9 * expected.norm()
+        );
+
+        // Looking into the output with the source shorted: the two in parallel.
+        let expected = zc * 1e3 / (zc + 1e3);
+        assert_relative_eq!(
+            (solver.port_impedance(2, 0, frequency).unwrap() - expected).norm(),
+            0.0,
+            epsilon = 1e-9 * expected.norm()
+        );
+    }
+
+    #[test]
+    fn dc() {
+        // At DC the capacitor leaves node 2 floating, which is reported rather than solved.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Capacitor::new(1, 2, 1e-6, 0.0));
+        match ACSolver::new(&netlist).solve(0.0) {
+            Err(SolverError::SingularMatrix { node, report }) => {
+                assert_eq!(node, Some(2));
+                assert!(report.singular);
+            }
+            result => panic!("Expected a singular matrix, got {result:?}"),
+        }
+        assert!(ACSolver::new(&netlist).sweep(&[0.0, 1e3]).is_err());
+        assert!(ACSolver::new(&netlist).sweep(&[1e3]).is_ok());
+
+        // An inductor without a branch current shorts its terminals, as with one.
+        for branch_current in [false, true] {
+            let inductor = Inductor::new(2, 0, 1e-3, 0.0);
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(if branch_current {
+                    inductor.with_branch_current()
+                } else {
+                    inductor
+                });
+
+            let solver = ACSolver::new(&netlist);
+            let voltage = solver.solve(0.0).unwrap().get_node_voltage(2);
+            assert!(voltage.norm().is_finite());
+            assert_relative_eq!(voltage.norm(), 0.0, epsilon = 1e-2);
+            let impedance = solver.port_impedance(2, 0, 0.0).unwrap();
+            assert_relative_eq!(impedance.norm(), 0.0, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn reflection() {
+        assert_relative_eq!(vswr(Complex::from(100.0), 50.0), 2.0);
+        assert_relative_eq!(vswr(Complex::from(25.0), 50.0), 2.0);
+        assert_relative_eq!(
+            reflection_coefficient(Complex::from(50.0), 50.0).norm(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn log_frequencies_spacing() {
+        let frequencies = log_frequencies(10.0, 1e4, 2);
+        assert_eq!(frequencies.len(), 7);
+        assert_relative_eq!(frequencies[0], 10.0);
+        assert_relative_eq!(frequencies[2], 100.0, max_relative = 1e-12);
+        assert_relative_eq!(frequencies[6], 1e4, max_relative = 1e-12);
+    }
+}
@@ -0,0 +1,76 @@
+use nalgebra::{Complex, DMatrix};
+
+use super::ACSolver;
+use crate::SolverError;
+
+impl<'n> ACSolver<'n> {
+    /// Extracts the impedance (Z) parameters of the network seen from the given ports, each a
+    /// (positive, negative) node pair, with every independent source zeroed.
+    ///
+    /// Column j holds the port voltages when a 1A test current is pushed into port j with every
+    /// other port open.
+    pub fn z_parameters(
+        &self,
+        ports: &[(usize, usize)],
+        frequency: f64,
+    ) -> Result<DMatrix<Complex<f64>>, SolverError> {
+        let system = self.factorize(frequency)?;
+        let mut z = DMatrix::zeros(ports.len(), ports.len());
+
+        for (j, &(positive_node, negative_node)) in ports.iter().enumerate() {
+            let solution = system.solve_with_injections(
+                &[(positive_node, negative_node, Complex::from(-1.0))],
+                false,
+            );
+
+            for (i, &(p, n)) in ports.iter().enumerate() {
+                z[(i, j)] = solution.get_voltage_between(p, n);
+            }
+        }
+
+        Ok(z)
+    }
+
+    /// Extracts the scattering (S) parameters of the network seen from the given ports against
+    /// a real reference impedance, S = (Z - Z0)(Z + Z0)^-1.
+    pub fn s_parameters(
+        &self,
+        ports: &[(usize, usize)],
+        frequency: f64,
+        reference: f64,
+    ) -> Result<DMatrix<Complex<f64>>, SolverError> {
+        let z = self.z_parameters(ports, frequency)?;
+        let z0 =
+            DMatrix::<Complex<f64>>::identity(ports.len(), ports.len()) * Complex::from(reference);
+
+        Ok((&z - &z0) * (&z + &z0).try_inverse().expect("Z + Z0 is singular"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::components::{Netlist, Resistor};
+
+    use super::*;
+
+    #[test]
+    fn attenuator_s_parameters() {
+        // A 6dB pi attenuator matched to 50 ohms.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 150.48))
+            .add_component(Resistor::new(1, 2, 37.35))
+            .add_component(Resistor::new(2, 0, 150.48));
+
+        let s = ACSolver::new(&netlist)
+            .s_parameters(&[(1, 0), (2, 0)], 1e6, 50.0)
+            .unwrap();
+
+        assert_relative_eq!(s[(0, 0)].norm(), 0.0, epsilon = 1e-3);
+        assert_relative_eq!(s[(1, 1)].norm(), 0.0, epsilon = 1e-3);
+        assert_relative_eq!(s[(1, 0)].re, 10f64.powf(-6.0 / 20.0), epsilon = 1e-3);
+        assert_relative_eq!(s[(0, 1)].re, s[(1, 0)].re, epsilon = 1e-12);
+    }
+}
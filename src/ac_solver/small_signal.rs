@@ -0,0 +1,360 @@
+use nalgebra::{Complex, DMatrix};
+
+use super::ACSolver;
+use crate::be_solver::matrix_view::ABMatrixView;
+use crate::be_solver::stampable::Stampable;
+use crate::components::{
+    Capacitor, Component, ControlledSource, CurrentSource, Inductor, Netlist, Resistor,
+    VoltageSource,
+};
+
+/// Angular frequency the admittance of a component is checked at to be linear in j*omega.
+const CHECK_OMEGA: f64 = 1e3;
+
+/// Relative tolerance on the admittances of a component when checking whether it is linear in
+/// j*omega and whether its transfer between two nodes is reciprocal.
+const TOLERANCE: f64 = 1e-9;
+
+/// Why the small-signal equivalent of a circuit couldn't be extracted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmallSignalError {
+    /// The component at the given index has internal variables or an admittance that isn't
+    /// made of conductances, capacitances and transconductances (e.g. a delay or a pole),
+    /// so it has no equivalent in lumped elements.
+    UnsupportedComponent(usize),
+}
+
+/// Gets the node of a row of the nodal matrix, the inverse of
+/// [`ViewEquationIndex::into_global_index`](crate::be_solver::matrix_view::ViewEquationIndex).
+fn node_of(row: usize, reference: usize) -> usize {
+    if row + 1 == reference { 0 } else { row + 1 }
+}
+
+/// A lumped element of the admittance of a component: a conductance or capacitance between
+/// two nodes, or a transconductance drawing current out of the negative node into the positive
+/// one, controlled by the voltage between the control nodes.
+struct Element {
+    positive_node: usize,
+    negative_node: usize,
+    control: Option<(usize, usize)>,
+    value: f64,
+    capacitive: bool,
+}
+
+/// Builds the elements equivalent to a real nodal matrix of conductances (or of capacitances
+/// if `capacitive` is set): elements between the node pairs it couples reciprocally and to the
+/// reference node, and transconductances for the coupling which isn't reciprocal. Returns
+/// `None` for capacitances which aren't reciprocal.
+fn decompose(mut matrix: DMatrix<f64>, reference: usize, capacitive: bool) -> Option<Vec<Element>> {
+    let transconductances = !capacitive;
+    let scale = matrix.amax();
+    let mut elements = Vec::new();
+    let n = matrix.nrows();
+
+    for i in 0..n {
+        for j in i + 1..n {
+            let (forward, backward) = (matrix[(i, j)], matrix[(j, i)]);
+            if forward == 0.0 && backward == 0.0 {
+                continue;
+            }
+            if (forward - backward).abs() <= TOLERANCE * scale {
+                // A conductance g between the nodes stamps -g off the diagonal and g on it.
+                let g = -(forward + backward) / 2.0;
+                elements.push(Element {
+                    positive_node: node_of(i, reference),
+                    negative_node: node_of(j, reference),
+                    control: None,
+                    value: g,
+                    capacitive,
+                });
+                matrix[(i, i)] -= g;
+                matrix[(j, j)] -= g;
+            } else if transconductances {
+                // Row i draws matrix[(i, j)]*v_j out of node i into the reference node.
+                for (row, column, value) in [(i, j, forward), (j, i, backward)] {
+                    if value != 0.0 {
+                        elements.push(Element {
+                            positive_node: reference,
+                            negative_node: node_of(row, reference),
+                            control: Some((node_of(column, reference), reference)),
+                            value,
+                            capacitive,
+                        });
+                    }
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+    for i in 0..n {
+        if matrix[(i, i)].abs() > TOLERANCE * scale {
+            elements.push(Element {
+                positive_node: node_of(i, reference),
+                negative_node: reference,
+                control: None,
+                value: matrix[(i, i)],
+                capacitive,
+            });
+        }
+    }
+
+    Some(elements)
+}
+
+impl<'n> ACSolver<'n> {
+    /// Extracts the small-signal equivalent of the circuit: a linear netlist of resistors,
+    /// capacitors, inductors and controlled sources behaving like the circuit linearized
+    /// around the present state of its components (as in the AC analysis), so nonlinear
+    /// circuits should first be brought to their operating point.
+    ///
+    /// Resistors, capacitors, inductors and current probes are kept, with the capacitance
+    /// and inductance they have at the operating point. Independent sources are zeroed but
+    /// keep their AC excitation, and controlled sources have the gain of their slope at the
+    /// operating point, along with the output resistance of a saturating current output.
+    /// Every other component is replaced by the conductances (resistors), capacitances and
+    /// transconductances (voltage controlled current sources, the gm elements) making up its
+    /// admittance, e.g. a diode by its incremental resistance.
+    ///
+    /// Each element is named after the component it comes from (see
+    /// [`Netlist::get_component_name`]), with a suffix numbering the elements of a replaced
+    /// component, e.g. `D3.r0`, `X5.c0` and `X5.gm1`.
+    pub fn small_signal_netlist(&self) -> Result<Netlist, SmallSignalError> {
+        let netlist = self.netlist;
+        let num_nodes = netlist.get_num_nodes();
+        let reference = netlist.get_reference_node();
+
+        let mut equivalent = Netlist::new();
+        equivalent.set_reference_node(reference);
+        for node in 0..=num_nodes {
+            let name = netlist.get_node_name(node);
+            if name != node.to_string() {
+                equivalent.set_node_name(node, name);
+            }
+        }
+        let mut add = |component: Component, name: String| {
+            equivalent.add_component(component);
+            let index = equivalent.get_components().len() - 1;
+            equivalent.set_component_name(index, name);
+        };
+
+        for (index, component) in netlist.get_components().iter().enumerate() {
+            let name = netlist.get_component_name(index);
+            match component {
+                Component::Resistor(_) | Component::CurrentProbe(_) => add(component.clone(), name),
+                Component::Capacitor(c) => add(
+                    Capacitor::new(
+                        c.get_positive_node(),
+                        c.get_negative_node(),
+                        c.get_capacitance(),
+                        0.0,
+                    )
+                    .into(),
+                    name,
+                ),
+                Component::Inductor(l) => add(
+                    Inductor::new(
+                        l.get_positive_node(),
+                        l.get_negative_node(),
+                        l.incremental_inductance(l.get_current()),
+                        0.0,
+                    )
+                    .into(),
+                    name,
+                ),
+                Component::VoltageSource(v) => {
+                    let phasor = v.get_ac_phasor();
+                    add(
+                        VoltageSource::new(v.get_positive_node(), v.get_negative_node(), 0.0)
+                            .with_ac(phasor.norm(), phasor.arg().to_degrees())
+                            .into(),
+                        name,
+                    )
+                }
+                Component::CurrentSource(i) => {
+                    let phasor = i.get_ac_phasor();
+                    add(
+                        CurrentSource::new(i.get_positive_node(), i.get_negative_node(), 0.0)
+                            .with_ac(phasor.norm(), phasor.arg().to_degrees())
+                            .into(),
+                        name,
+                    )
+                }
+                Component::ControlledSource(c) => {
+                    // The same slope and output conductance as its AC stamp.
+                    let (y, dy) = c.evaluate(c.get_control());
+                    let (h, dh) = c.compliance(y, -c.get_voltage());
+                    add(
+                        ControlledSource::new(
+                            c.get_kind(),
+                            c.get_positive_node(),
+                            c.get_negative_node(),
+                            c.get_control_positive_node(),
+                            c.get_control_negative_node(),
+                            dy * h,
+                        )
+                        .into(),
+                        name.clone(),
+                    );
+                    if y * dh != 0.0 {
+                        add(
+                            Resistor::new(
+                                c.get_positive_node(),
+                                c.get_negative_node(),
+                                1.0 / (y * dh),
+                            )
+                            .into(),
+                            format!("{name}.r0"),
+                        );
+                    }
+                }
+                _ => {
+                    let elements = Self::equivalent_elements(component, num_nodes, reference)
+                        .ok_or(SmallSignalError::UnsupportedComponent(index))?;
+                    let (mut resistors, mut capacitors, mut transconductances) = (0, 0, 0);
+                    for element in elements {
+                        let (p, n, value) =
+                            (element.positive_node, element.negative_node, element.value);
+                        match (element.control, element.capacitive) {
+                            (Some((cp, cn)), _) => {
+                                // Flipped so the transconductance is positive.
+                                let (p, n) = if value < 0.0 { (n, p) } else { (p, n) };
+                                add(
+                                    ControlledSource::vccs(p, n, cp, cn, value.abs()).into(),
+                                    format!("{name}.gm{transconductances}"),
+                                );
+                                transconductances += 1;
+                            }
+                            (None, false) => {
+                                add(
+                                    Resistor::new(p, n, 1.0 / value).into(),
+                                    format!("{name}.r{resistors}"),
+                                );
+                                resistors += 1;
+                            }
+                            (None, true) => {
+                                add(
+                                    Capacitor::new(p, n, value, 0.0).into(),
+                                    format!("{name}.c{capacitors}"),
+                                );
+                                capacitors += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(equivalent)
+    }
+
+    /// Decomposes the admittance of a component without internal variables into lumped
+    /// elements, or returns `None` if it can't be.
+    fn equivalent_elements(
+        component: &Component,
+        num_nodes: usize,
+        reference: usize,
+    ) -> Option<Vec<Element>> {
+        if component.num_variables() != 0 {
+            return None;
+        }
+        let admittance = |omega: f64| {
+            let mut a = DMatrix::zeros(num_nodes, num_nodes);
+            let mut b = DMatrix::zeros(num_nodes, 1);
+            let mut view = ABMatrixView::new(&mut a, &mut b, num_nodes, reference, 0, num_nodes);
+            component.stamp_ac(&mut view, omega);
+            a
+        };
+
+        // The admittance must be G + j*omega*C.
+        let conductance = admittance(0.0);
+        let capacitance = admittance(1.0).map(|y: Complex<f64>| y.im);
+        let check = admittance(CHECK_OMEGA);
+        let expected = conductance.map(|g: Complex<f64>| g.re).map(Complex::from)
+            + capacitance.map(|c| Complex::new(0.0, CHECK_OMEGA * c));
+        if (&check - &expected).norm() > TOLERANCE * check.norm() {
+            return None;
+        }
+
+        let mut elements = decompose(conductance.map(|g| g.re), reference, false)?;
+        elements.extend(decompose(capacitance, reference, true)?);
+        Some(elements)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::BESolver;
+    use crate::components::{Diode, DiodeModel, TransmissionGate};
+
+    use super::*;
+
+    #[test]
+    fn small_signal_equivalent() {
+        // A diode biased from 5V through 1kΩ, driving a limited transconductor through a
+        // transmission gate with feedthrough capacitance, loaded by 1kΩ.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ))
+            .add_component(TransmissionGate::new(2, 3, 4, 0, 100.0).with_off_capacitance(1e-9))
+            .add_component(VoltageSource::new(4, 0, 5.0))
+            .add_component(ControlledSource::vccs(5, 0, 3, 0, 1e-3).with_limits(-1e-3, 1e-3))
+            .add_component(Resistor::new(5, 0, 1e3))
+            .add_component(Resistor::new(3, 0, 1e6));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve_dc().unwrap();
+
+        let original = ACSolver::new(&netlist);
+        let equivalent = original.small_signal_netlist().unwrap();
+        let names: Vec<String> = (0..equivalent.get_components().len())
+            .map(|k| equivalent.get_component_name(k))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "V0", "R1", "D2.r0", "S3.r0", "S3.c0", "V4", "G5", "R6", "R7"
+            ]
+        );
+
+        // The diode's incremental resistance is n*Vt over its current.
+        let current = (5.0 - netlist.get_components()[2].get_voltage()) / 1e3;
+        let diode = equivalent.get_components()[2].get_value().unwrap();
+        assert_relative_eq!(diode, 0.025852 / current, max_relative = 1e-3);
+
+        for frequency in [1e3, 1e6] {
+            let expected = original.solve(frequency).unwrap();
+            let solution = ACSolver::new(&equivalent).solve(frequency).unwrap();
+            for node in 1..=5 {
+                assert_relative_eq!(
+                    solution.get_node_voltage(node).re,
+                    expected.get_node_voltage(node).re,
+                    epsilon = 1e-9
+                );
+                assert_relative_eq!(
+                    solution.get_node_voltage(node).im,
+                    expected.get_node_voltage(node).im,
+                    epsilon = 1e-9
+                );
+            }
+        }
+
+        // The error amplifier of a shunt reference is an internal variable.
+        let mut netlist = Netlist::new();
+        netlist.add_component(crate::components::ShuntReference::new(1, 0, 2));
+        assert_eq!(
+            ACSolver::new(&netlist).small_signal_netlist().err(),
+            Some(SmallSignalError::UnsupportedComponent(0))
+        );
+    }
+}
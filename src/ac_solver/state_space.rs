@@ -0,0 +1,355 @@
+use nalgebra::DMatrix;
+
+use super::{ACSolution, ACSolver};
+use crate::SolverError;
+use crate::be_solver::stampable::Stampable;
+use crate::components::{Component, CurrentSource, Netlist, VoltageSource};
+use crate::matlab::MatValue;
+
+/// A linear state-space model of a circuit,
+///
+/// dx/dt = A x + B u, y = C x + D u
+///
+/// in continuous time, or `x[k+1] = A x[k] + B u[k]`, `y[k] = C x[k] + D u[k]` once discretized
+/// at a sample period, e.g. for designing a Kalman filter or a state feedback controller for
+/// the circuit (see [`ACSolver::state_space`]).
+///
+/// States, inputs and outputs are named after the components and nodes they belong to, as in
+/// the `StateName`, `InputName` and `OutputName` of a MATLAB `ss` model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSpace {
+    a: DMatrix<f64>,
+    b: DMatrix<f64>,
+    c: DMatrix<f64>,
+    d: DMatrix<f64>,
+    sample_period: Option<f64>,
+    state_names: Vec<String>,
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+}
+
+impl StateSpace {
+    pub fn get_a(&self) -> &DMatrix<f64> {
+        &self.a
+    }
+
+    pub fn get_b(&self) -> &DMatrix<f64> {
+        &self.b
+    }
+
+    pub fn get_c(&self) -> &DMatrix<f64> {
+        &self.c
+    }
+
+    pub fn get_d(&self) -> &DMatrix<f64> {
+        &self.d
+    }
+
+    /// Gets the sample period of a discrete-time model, `None` in continuous time.
+    pub fn get_sample_period(&self) -> Option<f64> {
+        self.sample_period
+    }
+
+    /// Gets the names of the states: `v(C2)` for the voltage of the capacitor named C2, `i(L3)`
+    /// for the current of the inductor named L3 (see
+    /// [`Netlist::get_component_name`](crate::components::Netlist::get_component_name)).
+    pub fn get_state_names(&self) -> &Vec<String> {
+        &self.state_names
+    }
+
+    /// Gets the names of the sources driving the inputs.
+    pub fn get_input_names(&self) -> &Vec<String> {
+        &self.input_names
+    }
+
+    /// Gets the names of the outputs: `v(2)` for the voltage of node 2 (see
+    /// [`Netlist::get_node_name`](crate::components::Netlist::get_node_name)).
+    pub fn get_output_names(&self) -> &Vec<String> {
+        &self.output_names
+    }
+
+    /// Finds the index of the state with the given name.
+    pub fn find_state(&self, name: &str) -> Option<usize> {
+        self.state_names.iter().position(|n| n == name)
+    }
+
+    /// Discretizes a continuous-time model at the given sample period, holding the inputs
+    /// constant over each sample (zero-order hold) as a DAC or a PWM duty cycle update does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the model is already discrete.
+    pub fn discretize(&self, sample_period: f64) -> StateSpace {
+        assert!(
+            self.sample_period.is_none(),
+            "The model is already discrete"
+        );
+        let states = self.a.nrows();
+        let inputs = self.b.ncols();
+
+        // exp([A B; 0 0] T) = [Ad Bd; 0 I], with Ad = exp(A T) and Bd the integral of
+        // exp(A t) B over the sample.
+        let mut augmented = DMatrix::zeros(states + inputs, states + inputs);
+        augmented
+            .view_mut((0, 0), (states, states))
+            .copy_from(&self.a);
+        augmented
+            .view_mut((0, states), (states, inputs))
+            .copy_from(&self.b);
+        let exponential = (augmented * sample_period).exp();
+
+        StateSpace {
+            a: exponential.view((0, 0), (states, states)).into_owned(),
+            b: exponential.view((0, states), (states, inputs)).into_owned(),
+            sample_period: Some(sample_period),
+            ..self.clone()
+        }
+    }
+
+    /// Exports the model as a MATLAB struct with the fields of an `ss` model (`A`, `B`, `C`,
+    /// `D`, `Ts`, `StateName`, `InputName` and `OutputName`), with a `Ts` of 0 in continuous
+    /// time. `ss(m.A, m.B, m.C, m.D, m.Ts, 'StateName', m.StateName, ...)` rebuilds it.
+    pub fn to_mat(&self) -> MatValue {
+        let matrix = |m: &DMatrix<f64>| MatValue::Numeric {
+            rows: m.nrows(),
+            columns: m.ncols(),
+            data: m.as_slice().to_vec(),
+        };
+        MatValue::Struct(vec![
+            ("A".to_string(), matrix(&self.a)),
+            ("B".to_string(), matrix(&self.b)),
+            ("C".to_string(), matrix(&self.c)),
+            ("D".to_string(), matrix(&self.d)),
+            (
+                "Ts".to_string(),
+                MatValue::scalar(self.sample_period.unwrap_or(0.0)),
+            ),
+            (
+                "StateName".to_string(),
+                MatValue::strings(&self.state_names),
+            ),
+            (
+                "InputName".to_string(),
+                MatValue::strings(&self.input_names),
+            ),
+            (
+                "OutputName".to_string(),
+                MatValue::strings(&self.output_names),
+            ),
+        ])
+    }
+}
+
+/// A state of the circuit: the voltage of a capacitor or the current of an inductor, by the
+/// index of the component.
+enum State {
+    CapacitorVoltage(usize, f64),
+    InductorCurrent(usize, f64),
+}
+
+impl<'n> ACSolver<'n> {
+    /// Extracts a continuous-time state-space model of the circuit, linearized around the
+    /// present state of its components like the AC analysis, so nonlinear circuits should
+    /// first be brought to their operating point. Discretize it with
+    /// [`StateSpace::discretize`].
+    ///
+    /// The states are the voltages of the capacitors and the currents of the inductors, in the
+    /// order of the components. The inputs are the values of the given voltage and current
+    /// sources (deviations from their present values, as the model is linear), and the
+    /// outputs the voltages of the given nodes. Every other source is zeroed, and every other
+    /// reactive element (e.g. the feedthrough capacitance of a transmission gate) is taken as
+    /// it stands at DC.
+    ///
+    /// Each column of the model is the response of the circuit at DC with the capacitors
+    /// replaced by voltage sources and the inductors by current sources, one of which (or one
+    /// of the inputs) is set to 1.
+    ///
+    /// Fails with [`SolverError::SingularMatrix`] if the circuit has a loop of capacitors and
+    /// voltage sources or a cut set of inductors and current sources, whose states aren't
+    /// independent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input isn't a voltage or current source.
+    pub fn state_space(
+        &self,
+        inputs: &[usize],
+        outputs: &[usize],
+    ) -> Result<StateSpace, SolverError> {
+        let mut states = Vec::new();
+        let mut base = self.netlist.clone();
+        for (index, component) in base.get_components_mut().iter_mut().enumerate() {
+            match component {
+                Component::Capacitor(c) => {
+                    states.push(State::CapacitorVoltage(index, c.get_capacitance()));
+                    *component =
+                        VoltageSource::new(c.get_positive_node(), c.get_negative_node(), 0.0)
+                            .into();
+                }
+                // The current source drives the inductor current from its positive node through
+                // the circuit into its negative node.
+                Component::Inductor(l) => {
+                    states.push(State::InductorCurrent(
+                        index,
+                        l.incremental_inductance(l.get_current()),
+                    ));
+                    *component =
+                        CurrentSource::new(l.get_negative_node(), l.get_positive_node(), 0.0)
+                            .into();
+                }
+                Component::VoltageSource(v) => *component = v.clone().with_ac(0.0, 0.0).into(),
+                Component::CurrentSource(i) => *component = i.clone().with_ac(0.0, 0.0).into(),
+                _ => {}
+            }
+        }
+
+        // Excites the source at the given index with a unit phasor and solves at DC.
+        let excite = |index: usize| -> Result<(Netlist, ACSolution), SolverError> {
+            let mut netlist = base.clone();
+            let component = netlist.get_component_mut(index);
+            *component = match &*component {
+                Component::VoltageSource(v) => v.clone().with_ac(1.0, 0.0).into(),
+                Component::CurrentSource(i) => i.clone().with_ac(1.0, 0.0).into(),
+                _ => panic!("Component {index} isn't a voltage or current source"),
+            };
+            let solution = ACSolver::new(&netlist)
+                .with_options(self.options)
+                .solve(0.0)?;
+            Ok((netlist, solution))
+        };
+        let variable = |netlist: &Netlist, index: usize| {
+            netlist.get_num_nodes()
+                + netlist.get_components()[..index]
+                    .iter()
+                    .map(|c| c.num_variables())
+                    .sum::<usize>()
+        };
+
+        let columns = states.len() + inputs.len();
+        let mut derivatives = DMatrix::zeros(states.len(), columns);
+        let mut responses = DMatrix::zeros(outputs.len(), columns);
+        let excitations = states
+            .iter()
+            .map(|state| match state {
+                State::CapacitorVoltage(index, _) | State::InductorCurrent(index, _) => *index,
+            })
+            .chain(inputs.iter().copied());
+        for (column, index) in excitations.enumerate() {
+            let (netlist, solution) = excite(index)?;
+            for (row, state) in states.iter().enumerate() {
+                derivatives[(row, column)] = match *state {
+                    // The source delivers the current the capacitor would draw out of its
+                    // positive node, C dv/dt = i.
+                    State::CapacitorVoltage(index, capacitance) => {
+                        -solution.get_solution()[variable(&netlist, index)].re / capacitance
+                    }
+                    // L di/dt = v, with the current source's nodes reversed.
+                    State::InductorCurrent(index, inductance) => {
+                        let source = &netlist.get_components()[index];
+                        let voltage = solution.get_voltage_between(
+                            source.get_negative_node(),
+                            source.get_positive_node(),
+                        );
+                        voltage.re / inductance
+                    }
+                };
+            }
+            for (row, &node) in outputs.iter().enumerate() {
+                responses[(row, column)] = solution.get_node_voltage(node).re;
+            }
+        }
+
+        let netlist = self.netlist;
+        Ok(StateSpace {
+            a: derivatives.columns(0, states.len()).into_owned(),
+            b: derivatives.columns(states.len(), inputs.len()).into_owned(),
+            c: responses.columns(0, states.len()).into_owned(),
+            d: responses.columns(states.len(), inputs.len()).into_owned(),
+            sample_period: None,
+            state_names: states
+                .iter()
+                .map(|state| match *state {
+                    State::CapacitorVoltage(index, _) => {
+                        format!("v({})", netlist.get_component_name(index))
+                    }
+                    State::InductorCurrent(index, _) => {
+                        format!("i({})", netlist.get_component_name(index))
+                    }
+                })
+                .collect(),
+            input_names: inputs
+                .iter()
+                .map(|&index| netlist.get_component_name(index))
+                .collect(),
+            output_names: outputs
+                .iter()
+                .map(|&node| format!("v({})", netlist.get_node_name(node)))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::components::{Capacitor, Inductor, Resistor};
+
+    use super::*;
+
+    #[test]
+    fn rlc_state_space() {
+        // A series RLC driven by a source, 10Ω, 1mH and 1uF.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(Inductor::new(2, 3, 1e-3, 0.0))
+            .add_component(Capacitor::new(3, 0, 1e-6, 0.0));
+
+        let model = ACSolver::new(&netlist).state_space(&[0], &[3, 1]).unwrap();
+        assert_eq!(model.get_state_names(), &vec!["i(L2)", "v(C3)"]);
+        assert_eq!(model.get_input_names(), &vec!["V0"]);
+        assert_eq!(model.get_output_names(), &vec!["v(3)", "v(1)"]);
+
+        // L di/dt = u - R i - v, C dv/dt = i.
+        let a = model.get_a();
+        assert_relative_eq!(a[(0, 0)], -1e4, max_relative = 1e-9);
+        assert_relative_eq!(a[(0, 1)], -1e3, max_relative = 1e-9);
+        assert_relative_eq!(a[(1, 0)], 1e6, max_relative = 1e-9);
+        assert_relative_eq!(a[(1, 1)], 0.0, epsilon = 1e-6);
+        assert_relative_eq!(model.get_b()[(0, 0)], 1e3, max_relative = 1e-9);
+        assert_relative_eq!(model.get_b()[(1, 0)], 0.0, epsilon = 1e-6);
+        assert_relative_eq!(model.get_c()[(0, 1)], 1.0, max_relative = 1e-9);
+        assert_relative_eq!(model.get_d()[(1, 0)], 1.0, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn rc_discretization() {
+        // A 1ms RC lowpass sampled every 100us.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let model = ACSolver::new(&netlist)
+            .state_space(&[0], &[2])
+            .unwrap()
+            .discretize(1e-4);
+        let pole = (-0.1f64).exp();
+        assert_eq!(model.get_sample_period(), Some(1e-4));
+        assert_relative_eq!(model.get_a()[(0, 0)], pole, max_relative = 1e-9);
+        assert_relative_eq!(model.get_b()[(0, 0)], 1.0 - pole, max_relative = 1e-9);
+        assert_relative_eq!(model.get_c()[(0, 0)], 1.0, max_relative = 1e-9);
+
+        let MatValue::Struct(fields) = model.to_mat() else {
+            panic!("Expected a struct");
+        };
+        assert_eq!(fields[4], ("Ts".to_string(), MatValue::scalar(1e-4)));
+        assert_eq!(
+            fields[5],
+            ("StateName".to_string(), MatValue::strings(&["v(C2)"]))
+        );
+    }
+}
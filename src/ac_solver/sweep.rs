@@ -0,0 +1,138 @@
+use super::{ACSolution, ACSolver, log_frequencies};
+use crate::SolverError;
+
+/// A frequency sweep that refines its grid where the response changes quickly.
+///
+/// The sweep starts from a coarse logarithmic grid and repeatedly bisects (geometrically) every
+/// interval over which the gain or phase of the observed node changes by more than the allowed
+/// step, so narrow high Q peaks are resolved without a dense grid everywhere else.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSweep {
+    node: usize,
+    points_per_decade: usize,
+    max_gain_step: f64,
+    max_phase_step: f64,
+    max_points: usize,
+}
+
+impl AdaptiveSweep {
+    /// Creates a sweep observing the given node, starting from a grid with the given number of
+    /// points per decade.
+    pub fn new(node: usize, points_per_decade: usize) -> Self {
+        Self {
+            node,
+            points_per_decade,
+            max_gain_step: 1.0,
+            max_phase_step: 5.0,
+            max_points: 10000,
+        }
+    }
+
+    /// Sets the largest gain change in decibels and phase change in degrees allowed between
+    /// neighbouring points.
+    pub fn with_max_steps(mut self, gain_db: f64, phase: f64) -> Self {
+        self.max_gain_step = gain_db;
+        self.max_phase_step = phase;
+        self
+    }
+
+    /// Sets the number of points after which refinement stops.
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = max_points;
+        self
+    }
+
+    /// Sweeps from `start` to `stop` hertz, returning the solutions in increasing frequency.
+    pub fn run(
+        &self,
+        solver: &ACSolver,
+        start: f64,
+        stop: f64,
+    ) -> Result<Vec<ACSolution>, SolverError> {
+        let mut solutions = solver.sweep(&log_frequencies(start, stop, self.points_per_decade))?;
+
+        loop {
+            let mut refined = Vec::with_capacity(solutions.len() * 2);
+            let mut added = 0;
+
+            for pair in solutions.windows(2) {
+                refined.push(pair[0].clone());
+
+                let budget = solutions.len() + added < self.max_points;
+                if budget && self.needs_refinement(&pair[0], &pair[1]) {
+                    let frequency = (pair[0].get_frequency() * pair[1].get_frequency()).sqrt();
+                    refined.push(solver.solve(frequency)?);
+                    added += 1;
+                }
+            }
+            refined.extend(solutions.last().cloned());
+            solutions = refined;
+
+            if added == 0 {
+                return Ok(solutions);
+            }
+        }
+    }
+
+    fn needs_refinement(&self, a: &ACSolution, b: &ACSolution) -> bool {
+        // Stop before the interval gets too narrow to be represented.
+        if b.get_frequency() / a.get_frequency() < 1.0 + 1e-9 {
+            return false;
+        }
+
+        let va = a.get_node_voltage(self.node);
+        let vb = b.get_node_voltage(self.node);
+
+        let gain_step = 20.0 * (vb.norm() / va.norm()).log10();
+        let phase_step = (vb / va).arg().to_degrees();
+
+        gain_step.abs() > self.max_gain_step || phase_step.abs() > self.max_phase_step
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::components::{Capacitor, Inductor, Netlist, Resistor, VoltageSource};
+
+    use super::*;
+
+    #[test]
+    fn resolves_high_q_peak() {
+        // A series RLC with Q = 100 resonating at 1/(2*pi*sqrt(LC)) ~ 5033Hz, observed across
+        // the resistor.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_component(Inductor::new(1, 2, 1e-3, 0.0))
+            .add_component(Capacitor::new(2, 3, 1e-6, 0.0))
+            .add_component(Resistor::new(3, 0, 0.316));
+
+        let solver = ACSolver::new(&netlist);
+
+        let peak = |solutions: &[ACSolution]| {
+            solutions
+                .iter()
+                .map(|s| s.get_node_voltage(3).norm())
+                .fold(0.0, f64::max)
+        };
+
+        // A coarse fixed grid misses most of the peak.
+        let coarse = solver.sweep(&log_frequencies(100.0, 1e6, 5)).unwrap();
+        assert!(peak(&coarse) < 0.5);
+
+        let solutions = AdaptiveSweep::new(3, 5).run(&solver, 100.0, 1e6).unwrap();
+        assert!(peak(&solutions) > 0.99);
+        assert!(solutions.len() < 300);
+        assert!(
+            solutions
+                .windows(2)
+                .all(|w| w[0].get_frequency() < w[1].get_frequency())
+        );
+
+        let capped = AdaptiveSweep::new(3, 5)
+            .with_max_points(40)
+            .run(&solver, 100.0, 1e6)
+            .unwrap();
+        assert!(capped.len() <= 41);
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+use crate::components::{Component, DiodeModel, Netlist};
+
+/// How a component changes over its time in service.
+#[derive(Debug, Clone, Copy)]
+pub enum AgingModel {
+    /// The primary value (see [`Component::get_value`]) changes by the relative drift every
+    /// given number of hours, linearly.
+    LinearDrift { drift: f64, hours: f64 },
+    /// The primary value is multiplied by the factor every given number of hours, compounding.
+    ExponentialDrift { factor: f64, hours: f64 },
+    /// The saturation current of a Shockley diode is multiplied by the factor every given
+    /// number of hours, e.g. the rising leakage of a degrading LED.
+    SaturationCurrentDrift { factor: f64, hours: f64 },
+    /// Any other aging: the function ages the component by the given number of hours.
+    Custom(fn(&mut Component, f64)),
+}
+
+impl AgingModel {
+    /// The capacitance of an electrolytic capacitor, which falls by 20% (a common end of life
+    /// criterion) over its rated lifetime at its operating temperature.
+    pub fn electrolytic_capacitance(lifetime: f64) -> Self {
+        Self::LinearDrift {
+            drift: -0.2,
+            hours: lifetime,
+        }
+    }
+
+    /// The ESR of an electrolytic capacitor, modelled as a separate resistor, which doubles
+    /// over its rated lifetime at its operating temperature.
+    pub fn electrolytic_esr(lifetime: f64) -> Self {
+        Self::ExponentialDrift {
+            factor: 2.0,
+            hours: lifetime,
+        }
+    }
+
+    /// Ages the component at the given index of the netlist by the given number of hours,
+    /// leaving it untouched if the model doesn't apply to it.
+    pub fn age(&self, netlist: &mut Netlist, index: usize, hours: f64) -> Result<(), AgingError> {
+        let component = netlist
+            .get_components()
+            .get(index)
+            .ok_or(AgingError::UnknownComponent(index))?;
+        let change = match *self {
+            Self::LinearDrift {
+                drift,
+                hours: period,
+            } => Change::Value(drifted(component, index, 1.0 + drift * hours / period)?),
+            Self::ExponentialDrift {
+                factor,
+                hours: period,
+            } => Change::Value(drifted(component, index, factor.powf(hours / period))?),
+            Self::SaturationCurrentDrift {
+                factor,
+                hours: period,
+            } => {
+                let Component::Diode(diode) = component else {
+                    return Err(AgingError::NotShockleyDiode(index));
+                };
+                let DiodeModel::Shockley {
+                    saturation_current,
+                    emission_coefficient,
+                } = diode.get_model()
+                else {
+                    return Err(AgingError::NotShockleyDiode(index));
+                };
+                Change::Diode(DiodeModel::Shockley {
+                    saturation_current: saturation_current * factor.powf(hours / period),
+                    emission_coefficient,
+                })
+            }
+            Self::Custom(age) => Change::Custom(age),
+        };
+
+        let component = netlist.get_component_mut(index);
+        match change {
+            Change::Value(value) => {
+                component.set_value(value);
+            }
+            Change::Diode(model) => {
+                if let Component::Diode(diode) = component {
+                    diode.set_model(model);
+                }
+            }
+            Change::Custom(age) => age(component, hours),
+        }
+        Ok(())
+    }
+}
+
+/// Why an [`AgingModel`] can't age a component.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgingError {
+    /// The netlist has no component at the index.
+    UnknownComponent(usize),
+    /// A drift of the primary value of a component without one, by its index.
+    NoValue(usize),
+    /// A saturation current drift of a component that isn't a Shockley diode, by its index.
+    NotShockleyDiode(usize),
+}
+
+/// The change an [`AgingModel`] makes, worked out before the component is changed.
+enum Change {
+    Value(f64),
+    Diode(DiodeModel),
+    Custom(fn(&mut Component, f64)),
+}
+
+/// Gets the primary value of the component scaled by the factor.
+fn drifted(component: &Component, index: usize, factor: f64) -> Result<f64, AgingError> {
+    let value = component.get_value().ok_or(AgingError::NoValue(index))?;
+    Ok(value * factor)
+}
+
+/// The result of the analysis at one aging point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgingPoint<T> {
+    pub hours: f64,
+    pub result: T,
+}
+
+/// Re-simulates a circuit with its components aged to a series of times in service, to see how
+/// its performance holds up to the end of its life.
+#[derive(Debug, Clone)]
+pub struct AgingSweep {
+    hours: Vec<f64>,
+    models: BTreeMap<usize, Vec<AgingModel>>,
+    acceleration: f64,
+}
+
+impl AgingSweep {
+    /// Creates a sweep over the given times in service, in hours.
+    pub fn new(hours: Vec<f64>) -> Self {
+        Self {
+            hours,
+            models: BTreeMap::new(),
+            acceleration: 1.0,
+        }
+    }
+
+    /// Ages the component at the given index with the model. A component can have several
+    /// models, applied in the order they were added.
+    pub fn with_aging(mut self, component: usize, model: AgingModel) -> Self {
+        self.models.entry(component).or_default().push(model);
+        self
+    }
+
+    /// Multiplies every time in service by the factor, e.g. 2 for every 10°C an electrolytic
+    /// runs above the temperature its lifetime is rated at.
+    pub fn with_acceleration(mut self, acceleration: f64) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    pub fn get_hours(&self) -> &Vec<f64> {
+        &self.hours
+    }
+
+    /// Returns a copy of the netlist with its components aged by the given number of hours,
+    /// or why a model doesn't apply to its component.
+    pub fn aged(&self, netlist: &Netlist, hours: f64) -> Result<Netlist, AgingError> {
+        let mut aged = netlist.clone();
+        for (&index, models) in &self.models {
+            for model in models {
+                model.age(&mut aged, index, hours * self.acceleration)?;
+            }
+        }
+        Ok(aged)
+    }
+
+    /// Runs the analysis on the netlist aged to each time in service. Every model is checked
+    /// against its component before anything is run.
+    pub fn run<T>(
+        &self,
+        netlist: &Netlist,
+        analysis: impl Fn(&mut Netlist) -> T,
+    ) -> Result<Vec<AgingPoint<T>>, AgingError> {
+        self.aged(netlist, 0.0)?;
+        self.hours
+            .iter()
+            .map(|&hours| {
+                Ok(AgingPoint {
+                    hours,
+                    result: analysis(&mut self.aged(netlist, hours)?),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Capacitor, Diode, Resistor, VoltageSource},
+    };
+
+    #[test]
+    fn test_electrolytic_aging() {
+        // An RC filter whose electrolytic ages over a 5000 hour life, run 10°C hot.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Capacitor::new(2, 3, 1e-3, 0.0))
+            .add_component(Resistor::new(3, 0, 0.1));
+
+        let sweep = AgingSweep::new(vec![0.0, 1250.0, 2500.0])
+            .with_aging(2, AgingModel::electrolytic_capacitance(5000.0))
+            .with_aging(3, AgingModel::electrolytic_esr(5000.0))
+            .with_acceleration(2.0);
+
+        let end_of_life = sweep.aged(&netlist, 2500.0).unwrap();
+        assert_relative_eq!(end_of_life.get_components()[2].get_value().unwrap(), 0.8e-3);
+        assert_relative_eq!(end_of_life.get_components()[3].get_value().unwrap(), 0.2);
+        assert_eq!(netlist.get_components()[2].get_value(), Some(1e-3));
+
+        // The charge after one time constant of the new part.
+        let points = sweep
+            .run(&netlist, |netlist| {
+                let mut solver = BESolver::new(netlist);
+                for _ in 0..1000 {
+                    solver.solve(1e-3).unwrap();
+                }
+                let c: Capacitor = solver.get_netlist().get_components()[2]
+                    .clone()
+                    .try_into()
+                    .unwrap();
+                c.get_voltage()
+            })
+            .unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].hours, 1250.0);
+        assert!(points[0].result < points[1].result && points[1].result < points[2].result);
+        assert_relative_eq!(
+            points[2].result,
+            1.0 - (-1.0 / 0.8f64).exp(),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn test_diode_aging() {
+        let mut netlist = Netlist::new();
+        netlist.add_component(Diode::new(
+            1,
+            0,
+            DiodeModel::Shockley {
+                saturation_current: 1e-14,
+                emission_coefficient: 2.0,
+            },
+        ));
+
+        let sweep = AgingSweep::new(vec![20000.0])
+            .with_aging(
+                0,
+                AgingModel::SaturationCurrentDrift {
+                    factor: 10.0,
+                    hours: 10000.0,
+                },
+            )
+            .with_aging(
+                0,
+                AgingModel::Custom(|component, hours| {
+                    let Component::Diode(diode) = component else {
+                        return;
+                    };
+                    if let DiodeModel::Shockley {
+                        saturation_current, ..
+                    } = diode.get_model()
+                    {
+                        diode.set_model(DiodeModel::Shockley {
+                            saturation_current,
+                            emission_coefficient: 2.0 + hours / 1e5,
+                        });
+                    }
+                }),
+            );
+
+        let aged: Diode = sweep.aged(&netlist, 20000.0).unwrap().get_components()[0]
+            .clone()
+            .try_into()
+            .unwrap();
+        let DiodeModel::Shockley {
+            saturation_current,
+            emission_coefficient,
+        } = aged.get_model()
+        else {
+            panic!("The diode model changed kind");
+        };
+        assert_relative_eq!(saturation_current, 1e-12, max_relative = 1e-12);
+        assert_relative_eq!(emission_coefficient, 2.2);
+    }
+
+    #[test]
+    fn test_invalid_aging() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 1000.0))
+            .add_component(Diode::new(1, 0, DiodeModel::Ideal));
+        let drift = AgingModel::LinearDrift {
+            drift: 0.1,
+            hours: 1000.0,
+        };
+        let leakage = AgingModel::SaturationCurrentDrift {
+            factor: 10.0,
+            hours: 1000.0,
+        };
+
+        assert_eq!(
+            drift.age(&mut netlist, 2, 1000.0),
+            Err(AgingError::UnknownComponent(2))
+        );
+        assert_eq!(
+            drift.age(&mut netlist, 1, 1000.0),
+            Err(AgingError::NoValue(1))
+        );
+        assert_eq!(
+            leakage.age(&mut netlist, 0, 1000.0),
+            Err(AgingError::NotShockleyDiode(0))
+        );
+        assert_eq!(
+            leakage.age(&mut netlist, 1, 1000.0),
+            Err(AgingError::NotShockleyDiode(1))
+        );
+        assert_eq!(netlist.get_components()[0].get_value(), Some(1000.0));
+
+        let sweep = AgingSweep::new(vec![0.0, 1000.0]).with_aging(1, drift);
+        assert_eq!(
+            sweep.run(&netlist, |_| ()).err(),
+            Some(AgingError::NoValue(1))
+        );
+    }
+}
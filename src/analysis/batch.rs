@@ -0,0 +1,300 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::components::{Netlist, ParamChange, ParamError};
+
+/// The result of running an analysis for one parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchPoint<T> {
+    pub parameters: Vec<ParamChange>,
+    pub result: T,
+}
+
+impl<T> BatchPoint<T> {
+    /// Gets the value the given component had for this point, if it was one of the parameters.
+    pub fn get_parameter(&self, component: usize) -> Option<f64> {
+        self.parameters
+            .iter()
+            .find(|p| p.component == component)
+            .map(|p| p.value)
+    }
+}
+
+/// A scalar response over two parameters. `z[j][i]` is the response at `x[i]`, `y[j]`, NaN if
+/// that combination wasn't simulated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseSurface {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub z: Vec<Vec<f64>>,
+}
+
+/// The results of a batch run, in the order of the parameter sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResults<T> {
+    points: Vec<BatchPoint<T>>,
+}
+
+impl<T> BatchResults<T> {
+    pub(crate) fn new(points: Vec<BatchPoint<T>>) -> Self {
+        Self { points }
+    }
+
+    pub fn get_points(&self) -> &Vec<BatchPoint<T>> {
+        &self.points
+    }
+
+    pub fn into_points(self) -> Vec<BatchPoint<T>> {
+        self.points
+    }
+
+    /// Gets the result of the parameter set with exactly the given values, in the order the
+    /// parameters were given.
+    pub fn get(&self, values: &[f64]) -> Option<&T> {
+        self.points
+            .iter()
+            .find(|p| {
+                p.parameters
+                    .iter()
+                    .map(|p| p.value)
+                    .eq(values.iter().copied())
+            })
+            .map(|p| &p.result)
+    }
+
+    /// Extracts a scalar response against the value of one component.
+    pub fn response(&self, component: usize, f: impl Fn(&T) -> f64) -> Vec<(f64, f64)> {
+        self.points
+            .iter()
+            .filter_map(|p| Some((p.get_parameter(component)?, f(&p.result))))
+            .collect()
+    }
+
+    /// Extracts a scalar response surface against the values of two components.
+    ///
+    /// If several points share the same pair of values the last one is used. Points with a
+    /// value that isn't finite have no place on the axes and are left out.
+    pub fn surface(
+        &self,
+        x_component: usize,
+        y_component: usize,
+        f: impl Fn(&T) -> f64,
+    ) -> ResponseSurface {
+        let values = |p: &BatchPoint<T>| {
+            let px = p.get_parameter(x_component)?;
+            let py = p.get_parameter(y_component)?;
+            (px.is_finite() && py.is_finite()).then_some((px, py))
+        };
+
+        let mut x: Vec<f64> = Vec::new();
+        let mut y: Vec<f64> = Vec::new();
+        for (px, py) in self.points.iter().filter_map(values) {
+            x.push(px);
+            y.push(py);
+        }
+
+        x.sort_by(f64::total_cmp);
+        x.dedup();
+        y.sort_by(f64::total_cmp);
+        y.dedup();
+
+        let mut z = vec![vec![f64::NAN; x.len()]; y.len()];
+        for p in &self.points {
+            if let Some((px, py)) = values(p) {
+                let i = x
+                    .binary_search_by(|v| v.total_cmp(&px))
+                    .expect("every value is on the axis");
+                let j = y
+                    .binary_search_by(|v| v.total_cmp(&py))
+                    .expect("every value is on the axis");
+                z[j][i] = f(&p.result);
+            }
+        }
+
+        ResponseSurface { x, y, z }
+    }
+}
+
+/// Runs an analysis over many parameter sets in parallel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRunner {
+    parameter_sets: Vec<Vec<ParamChange>>,
+    threads: usize,
+}
+
+impl BatchRunner {
+    /// Creates a runner over an explicit list of parameter sets.
+    pub fn list(parameter_sets: Vec<Vec<ParamChange>>) -> Self {
+        Self {
+            parameter_sets,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    /// Creates a runner over the cartesian product of the values of each component. Each axis
+    /// is a component index and the values it takes.
+    pub fn grid(axes: &[(usize, Vec<f64>)]) -> Self {
+        let parameter_sets = axes
+            .iter()
+            .fold(vec![Vec::new()], |sets, (component, values)| {
+                sets.iter()
+                    .flat_map(|set| {
+                        values.iter().map(move |&value| {
+                            let mut set = set.clone();
+                            set.push(ParamChange::new(*component, value));
+                            set
+                        })
+                    })
+                    .collect()
+            });
+
+        Self::list(parameter_sets)
+    }
+
+    /// Sets the number of worker threads. Defaults to the available parallelism.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn get_parameter_sets(&self) -> &Vec<Vec<ParamChange>> {
+        &self.parameter_sets
+    }
+
+    /// Runs the analysis for every parameter set.
+    ///
+    /// For every parameter set a copy of the netlist is made with the parameters applied and
+    /// passed to `analysis`. Every change is checked against the netlist before anything is
+    /// run, failing on the first one that can't be applied.
+    pub fn run<T: Send>(
+        &self,
+        netlist: &Netlist,
+        analysis: impl Fn(&mut Netlist) -> T + Sync,
+    ) -> Result<BatchResults<T>, ParamError> {
+        for change in self.parameter_sets.iter().flatten() {
+            change.check(netlist)?;
+        }
+
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<T>>> =
+            Mutex::new((0..self.parameter_sets.len()).map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(self.parameter_sets.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(parameters) = self.parameter_sets.get(index) else {
+                            break;
+                        };
+
+                        let mut trial = netlist.clone();
+                        for change in parameters {
+                            change.apply(&mut trial).expect("the changes were checked");
+                        }
+
+                        let result = analysis(&mut trial);
+                        results.lock().unwrap()[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        let points = self
+            .parameter_sets
+            .iter()
+            .cloned()
+            .zip(results.into_inner().unwrap())
+            .map(|(parameters, result)| BatchPoint {
+                parameters,
+                result: result.unwrap(),
+            })
+            .collect();
+
+        Ok(BatchResults { points })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Resistor, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_grid_divider() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0));
+
+        let runner = BatchRunner::grid(&[(0, vec![1.0, 2.0, 3.0]), (2, vec![1000.0, 3000.0])])
+            .with_threads(4);
+        assert_eq!(runner.get_parameter_sets().len(), 6);
+
+        let results = runner
+            .run(&netlist, |netlist| {
+                let mut solver = BESolver::new(netlist);
+                solver.solve(0.001).unwrap();
+                solver.get_node_voltage(2)
+            })
+            .unwrap();
+
+        assert_relative_eq!(*results.get(&[2.0, 3000.0]).unwrap(), 1.5);
+        assert_relative_eq!(*results.get(&[3.0, 1000.0]).unwrap(), 1.5);
+        assert!(results.get(&[4.0, 1000.0]).is_none());
+
+        let surface = results.surface(0, 2, |v| *v);
+        assert_eq!(surface.x, [1.0, 2.0, 3.0]);
+        assert_eq!(surface.y, [1000.0, 3000.0]);
+        assert_relative_eq!(surface.z[1][0], 0.75);
+        assert_relative_eq!(surface.z[0][2], 1.5);
+
+        let response = results.response(0, |v| *v);
+        assert_eq!(response.len(), 6);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let mut netlist = Netlist::new();
+        netlist.add_component(Resistor::new(1, 0, 1000.0));
+
+        let runner = BatchRunner::list(vec![
+            vec![ParamChange::new(0, 2000.0)],
+            vec![ParamChange::new(1, 2000.0)],
+        ]);
+        assert_eq!(
+            runner.run(&netlist, |_| ()).err(),
+            Some(ParamError::UnknownComponent(1))
+        );
+    }
+
+    #[test]
+    fn test_surface_skips_non_finite() {
+        let point = |x: f64, y: f64| BatchPoint {
+            parameters: vec![ParamChange::new(0, x), ParamChange::new(1, y)],
+            result: x * y,
+        };
+        let results = BatchResults::new(vec![
+            point(1.0, 2.0),
+            point(f64::NAN, 2.0),
+            point(f64::NAN, f64::NAN),
+            point(3.0, f64::INFINITY),
+        ]);
+
+        let surface = results.surface(0, 1, |v| *v);
+        assert_eq!(surface.x, [1.0]);
+        assert_eq!(surface.y, [2.0]);
+        assert_eq!(surface.z, [[2.0]]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::f64::consts::PI;
+
+use nalgebra::Complex;
+
+use crate::ac_solver::ACSolver;
+use crate::components::Netlist;
+use crate::{SolverError, SolverOptions};
+
+/// Gain and phase of a transfer function over a frequency sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodeData {
+    pub frequencies: Vec<f64>,
+    /// Complex transfer function at each frequency.
+    pub transfer: Vec<Complex<f64>>,
+    /// Gain in decibels at each frequency.
+    pub gain_db: Vec<f64>,
+    /// Phase in degrees at each frequency, unwrapped so it is continuous over the sweep.
+    pub phase: Vec<f64>,
+}
+
+impl BodeData {
+    /// Gets the first frequency at which the gain falls through 0dB.
+    pub fn gain_crossover(&self) -> Option<f64> {
+        self.crossing(&self.gain_db, 0.0)
+    }
+
+    /// Gets the first frequency at which the phase falls through -180 degrees.
+    pub fn phase_crossover(&self) -> Option<f64> {
+        self.crossing(&self.phase, -180.0)
+    }
+
+    /// Gets how far the phase is above -180 degrees at the gain crossover, in degrees.
+    pub fn phase_margin(&self) -> Option<f64> {
+        let frequency = self.gain_crossover()?;
+        Some(180.0 + self.interpolate(&self.phase, frequency))
+    }
+
+    /// Gets how far the gain is below 0dB at the phase crossover, in decibels.
+    pub fn gain_margin(&self) -> Option<f64> {
+        let frequency = self.phase_crossover()?;
+        Some(-self.interpolate(&self.gain_db, frequency))
+    }
+
+    /// Computes the group delay -dphi/domega at each frequency from the phase, using central
+    /// differences inside the sweep and one sided differences at its ends.
+    pub fn group_delay(&self) -> Vec<f64> {
+        let n = self.frequencies.len();
+        (0..n)
+            .map(|k| {
+                if n < 2 {
+                    return 0.0;
+                }
+
+                let (a, b) = (k.saturating_sub(1), (k + 1).min(n - 1));
+                let dphi = (self.phase[b] - self.phase[a]).to_radians();
+                let domega = 2.0 * PI * (self.frequencies[b] - self.frequencies[a]);
+                -dphi / domega
+            })
+            .collect()
+    }
+
+    /// Finds the first frequency at which the values fall through the level, interpolating
+    /// linearly against log frequency.
+    fn crossing(&self, values: &[f64], level: f64) -> Option<f64> {
+        (1..values.len())
+            .find(|&k| values[k - 1] >= level && values[k] < level)
+            .map(|k| {
+                let (f0, f1) = (self.frequencies[k - 1].ln(), self.frequencies[k].ln());
+                let fraction = (level - values[k - 1]) / (values[k] - values[k - 1]);
+                (f0 + fraction * (f1 - f0)).exp()
+            })
+    }
+
+    /// Interpolates the values linearly against log frequency.
+    fn interpolate(&self, values: &[f64], frequency: f64) -> f64 {
+        let k = self
+            .frequencies
+            .iter()
+            .position(|&f| f >= frequency)
+            .unwrap_or(self.frequencies.len() - 1)
+            .max(1);
+        let (f0, f1) = (self.frequencies[k - 1].ln(), self.frequencies[k].ln());
+        let fraction = (frequency.ln() - f0) / (f1 - f0);
+        values[k - 1] + fraction * (values[k] - values[k - 1])
+    }
+}
+
+/// Computes Bode plot data for a transfer path of a circuit using AC analysis.
+///
+/// The transfer function is the output node voltage divided by the input node voltage, or by 1
+/// if no input node is set (so the AC magnitude of the exciting source sets the reference). For
+/// loop stability the path should be the loop gain, broken open and driven by an AC source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodePlot {
+    output_node: usize,
+    input_node: Option<usize>,
+    options: SolverOptions,
+}
+
+impl BodePlot {
+    pub fn new(output_node: usize) -> Self {
+        Self {
+            output_node,
+            input_node: None,
+            options: SolverOptions::default(),
+        }
+    }
+
+    /// Divides the output voltage by the voltage of the given node.
+    pub fn with_input(mut self, input_node: usize) -> Self {
+        self.input_node = Some(input_node);
+        self
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sweeps the given frequencies, which should be increasing.
+    pub fn run(&self, netlist: &Netlist, frequencies: &[f64]) -> Result<BodeData, SolverError> {
+        let solver = ACSolver::new(netlist).with_options(self.options);
+
+        let transfer: Vec<Complex<f64>> = solver
+            .sweep(frequencies)?
+            .iter()
+            .map(|solution| {
+                let output = solution.get_node_voltage(self.output_node);
+                match self.input_node {
+                    Some(input_node) => output / solution.get_node_voltage(input_node),
+                    None => output,
+                }
+            })
+            .collect();
+
+        let gain_db = transfer.iter().map(|h| 20.0 * h.norm().log10()).collect();
+
+        let mut phase: Vec<f64> = Vec::with_capacity(transfer.len());
+        for h in &transfer {
+            let mut p = h.arg().to_degrees();
+            if let Some(&previous) = phase.last() {
+                p += 360.0 * ((previous - p) / 360.0).round();
+            }
+            phase.push(p);
+        }
+
+        Ok(BodeData {
+            frequencies: frequencies.to_vec(),
+            transfer,
+            gain_db,
+            phase,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::components::{Capacitor, Resistor, VoltageSource};
+    use crate::log_frequencies;
+
+    use super::*;
+
+    #[test]
+    fn rc_ladder_margins() {
+        // A gain of 10 followed by three RC sections:
+        // H = 10/(x^3 + 5x^2 + 6x + 1) with x = sRC.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(10.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0))
+            .add_component(Resistor::new(2, 3, 1e3))
+            .add_component(Capacitor::new(3, 0, 1e-6, 0.0))
+            .add_component(Resistor::new(3, 4, 1e3))
+            .add_component(Capacitor::new(4, 0, 1e-6, 0.0));
+
+        let bode = BodePlot::new(4)
+            .run(&netlist, &log_frequencies(1.0, 1e5, 200))
+            .unwrap();
+
+        assert_relative_eq!(bode.gain_db[0], 20.0, epsilon = 1e-2);
+        assert_relative_eq!(
+            bode.phase_crossover().unwrap(),
+            6f64.sqrt() / (2.0 * PI * 1e-3),
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(
+            bode.gain_margin().unwrap(),
+            20.0 * 2.9f64.log10(),
+            epsilon = 1e-2
+        );
+        assert_relative_eq!(bode.gain_crossover().unwrap(), 216.527, max_relative = 1e-3);
+        assert_relative_eq!(bode.phase_margin().unwrap(), 34.366, epsilon = 1e-2);
+
+        // The phase keeps falling to -270 degrees without wrapping.
+        assert_relative_eq!(*bode.phase.last().unwrap(), -270.0, epsilon = 1.0);
+    }
+
+    #[test]
+    fn input_node_divides() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(5.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Resistor::new(2, 0, 1e3));
+
+        let bode = BodePlot::new(2)
+            .with_input(1)
+            .run(&netlist, &[1.0, 10.0])
+            .unwrap();
+        assert_relative_eq!(bode.gain_db[0], 20.0 * 0.5f64.log10(), epsilon = 1e-9);
+        assert_eq!(bode.group_delay(), vec![0.0, 0.0]);
+        assert_eq!(bode.gain_crossover(), None);
+        assert_eq!(bode.phase_margin(), None);
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+use crate::components::{Netlist, ParamChange};
+use crate::{BESolver, SolverError};
+
+/// Returns the values from start to stop in increments of step, including stop when it lands
+/// on an increment. The sign of step is taken from the direction of the sweep.
+fn sweep_values(start: f64, stop: f64, step: f64) -> Vec<f64> {
+    let step = step.abs().copysign(stop - start);
+    let count = if step == 0.0 {
+        0
+    } else {
+        ((stop - start) / step * (1.0 + 1e-9)).floor() as usize
+    };
+    (0..=count).map(|k| start + k as f64 * step).collect()
+}
+
+/// The operating points of a [`DCSweep`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DCSweepResult {
+    points: Vec<(f64, Option<f64>)>,
+    node_voltages: Vec<Vec<f64>>,
+    component_currents: Vec<Vec<f64>>,
+}
+
+impl DCSweepResult {
+    /// Gets the values of the swept source and, in a two-source sweep, the second source at
+    /// each operating point.
+    pub fn get_points(&self) -> &Vec<(f64, Option<f64>)> {
+        &self.points
+    }
+
+    /// Gets the voltage of a node at each operating point.
+    pub fn get_node_voltages(&self, node: usize) -> Vec<f64> {
+        self.node_voltages
+            .iter()
+            .map(|voltages| voltages.get(node).copied().unwrap_or(0.0))
+            .collect()
+    }
+
+    /// Gets the current through a component at each operating point.
+    pub fn get_component_currents(&self, component: usize) -> Vec<f64> {
+        self.component_currents
+            .iter()
+            .map(|currents| currents[component])
+            .collect()
+    }
+
+    /// Exports the table as CSV, with a column for each swept source, each node voltage and
+    /// each component current.
+    pub fn to_csv(&self) -> String {
+        let two_source = self.points.iter().any(|(_, second)| second.is_some());
+        let nodes = self.node_voltages.first().map_or(0, Vec::len);
+        let components = self.component_currents.first().map_or(0, Vec::len);
+
+        let mut csv = String::from("sweep");
+        if two_source {
+            csv.push_str(",second sweep");
+        }
+        for node in 1..nodes {
+            write!(csv, ",v({node}) [V]").unwrap();
+        }
+        for component in 0..components {
+            write!(csv, ",i({component}) [A]").unwrap();
+        }
+        csv.push('\n');
+
+        for (k, &(value, second)) in self.points.iter().enumerate() {
+            write!(csv, "{value}").unwrap();
+            if two_source {
+                write!(csv, ",{}", second.unwrap_or(f64::NAN)).unwrap();
+            }
+            for voltage in &self.node_voltages[k][1..] {
+                write!(csv, ",{voltage}").unwrap();
+            }
+            for current in &self.component_currents[k] {
+                write!(csv, ",{current}").unwrap();
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Steps the primary value of a source (or any other component, see
+/// [`Component::get_value`](crate::components::Component::get_value)) over a range, solving the
+/// DC operating point at each step, e.g. for the I-V curve of a diode or the transfer
+/// characteristic of an amplifier.
+///
+/// A second component can be stepped as well, the first sweep being repeated for each of its
+/// values, e.g. for a family of curves. Each operating point starts its Newton-Raphson iteration
+/// from the previous one, so the steps should be small where the circuit is strongly nonlinear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DCSweep {
+    component: usize,
+    values: Vec<f64>,
+    second: Option<(usize, Vec<f64>)>,
+}
+
+impl DCSweep {
+    /// Creates a sweep of the value of the component at the given index from start to stop in
+    /// increments of step.
+    pub fn new(component: usize, start: f64, stop: f64, step: f64) -> Self {
+        Self::from_values(component, &sweep_values(start, stop, step))
+    }
+
+    /// Creates a sweep of the value of the component through the given values.
+    pub fn from_values(component: usize, values: &[f64]) -> Self {
+        Self {
+            component,
+            values: values.to_vec(),
+            second: None,
+        }
+    }
+
+    /// Steps a second component from start to stop in increments of step, repeating the sweep
+    /// for each of its values.
+    pub fn with_second_sweep(mut self, component: usize, start: f64, stop: f64, step: f64) -> Self {
+        self.second = Some((component, sweep_values(start, stop, step)));
+        self
+    }
+
+    /// Gets the values the component is swept through.
+    pub fn get_values(&self) -> &Vec<f64> {
+        &self.values
+    }
+
+    /// Runs the sweep on a copy of the netlist, returning the error of the first operating
+    /// point that couldn't be solved. The swept components are checked before anything is
+    /// solved.
+    pub fn run(&self, netlist: &Netlist) -> Result<DCSweepResult, SolverError> {
+        ParamChange::new(self.component, 0.0).check(netlist)?;
+        if let Some((component, _)) = &self.second {
+            ParamChange::new(*component, 0.0).check(netlist)?;
+        }
+
+        let mut netlist = netlist.clone();
+        let mut solver = BESolver::new(&mut netlist);
+
+        let seconds: Vec<Option<f64>> = match &self.second {
+            Some((_, values)) => values.iter().copied().map(Some).collect(),
+            None => vec![None],
+        };
+        let mut result = DCSweepResult {
+            points: Vec::new(),
+            node_voltages: Vec::new(),
+            component_currents: Vec::new(),
+        };
+        for second in seconds {
+            if let (Some((component, _)), Some(value)) = (&self.second, second) {
+                ParamChange::new(*component, value).apply(solver.get_netlist_mut())?;
+            }
+            for &value in &self.values {
+                ParamChange::new(self.component, value).apply(solver.get_netlist_mut())?;
+                solver.try_solve_dc()?;
+
+                result.points.push((value, second));
+                result
+                    .node_voltages
+                    .push(solver.get_node_voltages().clone());
+                result.component_currents.push(
+                    solver
+                        .get_netlist()
+                        .get_components()
+                        .iter()
+                        .map(|c| c.get_current())
+                        .collect(),
+                );
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Diode, DiodeModel, Resistor, VoltageSource};
+
+    #[test]
+    fn test_diode_curve() {
+        // A diode behind 10Ω, swept from 0.1V to 2V. Below conduction the diode current is
+        // comparable to that of the 1pS gmin shunting the diode.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let sweep = DCSweep::new(0, 0.1, 2.0, 0.1);
+        assert_eq!(sweep.get_values().len(), 20);
+        let result = sweep.run(&netlist).unwrap();
+
+        let voltages = result.get_node_voltages(2);
+        let currents = result.get_component_currents(2);
+        for (k, &(value, _)) in result.get_points().iter().enumerate() {
+            assert_relative_eq!(
+                currents[k],
+                (value - voltages[k]) / 10.0,
+                max_relative = 1e-6,
+                epsilon = 1e-11
+            );
+            assert_relative_eq!(
+                currents[k],
+                1e-14 * ((voltages[k] / 0.025852).exp() - 1.0),
+                max_relative = 1e-6,
+                epsilon = 1e-15
+            );
+        }
+        // Conducting, the diode voltage rises by ln(10)*Vt per decade of current.
+        assert!(voltages[19] > 0.7 && voltages[19] < 0.8);
+    }
+
+    #[test]
+    fn test_two_source_sweep() {
+        // Two sources averaged by equal resistors, swept over a 3x2 grid.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(VoltageSource::new(2, 0, 0.0))
+            .add_component(Resistor::new(1, 3, 1e3))
+            .add_component(Resistor::new(2, 3, 1e3));
+
+        let result = DCSweep::new(0, 1.0, 3.0, 1.0)
+            .with_second_sweep(1, 5.0, 4.0, 1.0)
+            .run(&netlist)
+            .unwrap();
+
+        let points = result.get_points();
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], (1.0, Some(5.0)));
+        assert_eq!(points[5], (3.0, Some(4.0)));
+        for (&(first, second), voltage) in points.iter().zip(result.get_node_voltages(3)) {
+            assert_relative_eq!(
+                voltage,
+                (first + second.unwrap()) / 2.0,
+                max_relative = 1e-9
+            );
+        }
+
+        let csv = result.to_csv();
+        assert!(csv.starts_with("sweep,second sweep,v(1) [V],v(2) [V],v(3) [V],i(0) [A]"));
+        assert_eq!(csv.lines().count(), 7);
+    }
+}
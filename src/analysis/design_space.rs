@@ -0,0 +1,402 @@
+use std::fmt::Write;
+
+use crate::analysis::{BatchRunner, Measurement};
+use crate::components::{Netlist, ParamChange, ParamError};
+
+/// Whether an objective is better lower or higher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Minimize,
+    Maximize,
+}
+
+/// A parameter set explored by a [`DesignSpaceExploration`], with the result of its analysis and
+/// the value of every objective.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesignPoint<T> {
+    /// The value of every axis, in the order of the axes.
+    pub parameters: Vec<ParamChange>,
+    pub result: T,
+    /// The value of every objective, in the order they were added.
+    pub objectives: Vec<f64>,
+    /// Whether every objective is within the limits of its measurement.
+    pub feasible: bool,
+}
+
+impl<T> DesignPoint<T> {
+    /// Gets the value the given component had for this point, if it was one of the axes.
+    pub fn get_parameter(&self, component: usize) -> Option<f64> {
+        self.parameters
+            .iter()
+            .find(|p| p.component == component)
+            .map(|p| p.value)
+    }
+}
+
+/// Explores the values of several components against several objectives, for example the
+/// efficiency, ripple and cost of a converter, finding the designs no other design beats on
+/// every objective at once (the Pareto front).
+///
+/// The axes are swept over a grid with a [`BatchRunner`]. Each objective is a [`Measurement`] of
+/// the analysis result along with whether it should be minimized or maximized, and the limits
+/// of the measurement are constraints: points outside of them are infeasible and never on the
+/// front. Costs can be measured from the metadata of the simulated netlist, e.g. with a
+/// [`BillOfMaterials`](crate::reports::BillOfMaterials) built in the analysis.
+///
+/// Refinement rounds (see [`DesignSpaceExploration::with_refinements`]) then explore around the
+/// front, trying each front point with one axis moved halfway to the neighboring values tried
+/// on that axis: the geometric mean for values of the same sign, so logarithmic grids stay
+/// logarithmic, and the arithmetic mean otherwise.
+pub struct DesignSpaceExploration<T> {
+    axes: Vec<(usize, Vec<f64>)>,
+    objectives: Vec<(Measurement<T>, Goal)>,
+    refinements: usize,
+    threads: Option<usize>,
+}
+
+impl<T: Send> DesignSpaceExploration<T> {
+    /// Creates an exploration over the cartesian product of the values of each component, like
+    /// [`BatchRunner::grid`], without objectives or refinement.
+    pub fn new(axes: Vec<(usize, Vec<f64>)>) -> Self {
+        Self {
+            axes,
+            objectives: Vec::new(),
+            refinements: 0,
+            threads: None,
+        }
+    }
+
+    pub fn with_objective(mut self, measurement: Measurement<T>, goal: Goal) -> Self {
+        self.objectives.push((measurement, goal));
+        self
+    }
+
+    /// Sets the number of rounds refining the grid around the front after the sweep.
+    pub fn with_refinements(mut self, refinements: usize) -> Self {
+        self.refinements = refinements;
+        self
+    }
+
+    /// Sets the number of worker threads. Defaults to the available parallelism.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Runs the exploration.
+    ///
+    /// For every parameter set a copy of the netlist is made with the parameters applied and
+    /// passed to `analysis`, as in [`BatchRunner::run`], which fails the same way on an axis
+    /// that can't be applied to the netlist.
+    pub fn run(
+        &self,
+        netlist: &Netlist,
+        analysis: impl Fn(&mut Netlist) -> T + Sync,
+    ) -> Result<ParetoFront<T>, ParamError> {
+        let mut points = self.evaluate(BatchRunner::grid(&self.axes), netlist, &analysis)?;
+        let mut axes: Vec<Vec<f64>> = self
+            .axes
+            .iter()
+            .map(|(_, values)| {
+                let mut values = values.clone();
+                values.sort_by(f64::total_cmp);
+                values.dedup();
+                values
+            })
+            .collect();
+
+        for _ in 0..self.refinements {
+            let mut candidates: Vec<Vec<f64>> = Vec::new();
+            for index in self.front(&points) {
+                let values: Vec<f64> = points[index].parameters.iter().map(|p| p.value).collect();
+                for (k, axis) in axes.iter().enumerate() {
+                    let position = axis.iter().position(|&v| v == values[k]).unwrap();
+                    let neighbors = [position.checked_sub(1), Some(position + 1)];
+                    for neighbor in neighbors.into_iter().flatten() {
+                        let Some(&neighbor) = axis.get(neighbor) else {
+                            continue;
+                        };
+                        let mut candidate = values.clone();
+                        candidate[k] = midpoint(values[k], neighbor);
+                        let explored = points.iter().any(|p| {
+                            p.parameters
+                                .iter()
+                                .map(|p| p.value)
+                                .eq(candidate.iter().copied())
+                        });
+                        if !explored && !candidates.contains(&candidate) {
+                            candidates.push(candidate);
+                        }
+                    }
+                }
+            }
+            if candidates.is_empty() {
+                break;
+            }
+
+            for candidate in &candidates {
+                for (axis, &value) in axes.iter_mut().zip(candidate) {
+                    if !axis.contains(&value) {
+                        axis.push(value);
+                        axis.sort_by(f64::total_cmp);
+                    }
+                }
+            }
+            let parameter_sets = candidates
+                .into_iter()
+                .map(|values| {
+                    self.axes
+                        .iter()
+                        .zip(values)
+                        .map(|(&(component, _), value)| ParamChange::new(component, value))
+                        .collect()
+                })
+                .collect();
+            points.extend(self.evaluate(BatchRunner::list(parameter_sets), netlist, &analysis)?);
+        }
+
+        Ok(ParetoFront {
+            parameter_names: self
+                .axes
+                .iter()
+                .map(|&(component, _)| netlist.get_component_name(component))
+                .collect(),
+            objective_names: self
+                .objectives
+                .iter()
+                .map(|(measurement, _)| measurement.get_name().to_string())
+                .collect(),
+            front: self.front(&points),
+            points,
+        })
+    }
+
+    fn evaluate(
+        &self,
+        runner: BatchRunner,
+        netlist: &Netlist,
+        analysis: &(impl Fn(&mut Netlist) -> T + Sync),
+    ) -> Result<Vec<DesignPoint<T>>, ParamError> {
+        let runner = match self.threads {
+            Some(threads) => runner.with_threads(threads),
+            None => runner,
+        };
+        Ok(runner
+            .run(netlist, analysis)?
+            .into_points()
+            .into_iter()
+            .map(|point| {
+                let objectives: Vec<f64> = self
+                    .objectives
+                    .iter()
+                    .map(|(measurement, _)| measurement.measure(&point.result))
+                    .collect();
+                let feasible = self
+                    .objectives
+                    .iter()
+                    .zip(&objectives)
+                    .all(|((measurement, _), &value)| measurement.passes(value));
+                DesignPoint {
+                    parameters: point.parameters,
+                    result: point.result,
+                    objectives,
+                    feasible,
+                }
+            })
+            .collect())
+    }
+
+    /// Finds the feasible points no other feasible point dominates, in the order they were
+    /// explored.
+    fn front(&self, points: &[DesignPoint<T>]) -> Vec<usize> {
+        // Every objective turned into one to minimize.
+        let costs: Vec<Vec<f64>> = points
+            .iter()
+            .map(|point| {
+                point
+                    .objectives
+                    .iter()
+                    .zip(&self.objectives)
+                    .map(|(&value, (_, goal))| match goal {
+                        Goal::Minimize => value,
+                        Goal::Maximize => -value,
+                    })
+                    .collect()
+            })
+            .collect();
+        let dominates = |a: &[f64], b: &[f64]| {
+            a.iter().zip(b).all(|(a, b)| a <= b) && a.iter().zip(b).any(|(a, b)| a < b)
+        };
+
+        (0..points.len())
+            .filter(|&i| points[i].feasible)
+            .filter(|&i| {
+                !(0..points.len()).any(|j| points[j].feasible && dominates(&costs[j], &costs[i]))
+            })
+            .collect()
+    }
+}
+
+/// Gets the value halfway between two values of an axis, geometrically if they have the same
+/// sign.
+fn midpoint(a: f64, b: f64) -> f64 {
+    if a * b > 0.0 {
+        a.signum() * (a * b).sqrt()
+    } else {
+        (a + b) / 2.0
+    }
+}
+
+/// The outcome of a [`DesignSpaceExploration`]: every point explored and the Pareto front among
+/// them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParetoFront<T> {
+    parameter_names: Vec<String>,
+    objective_names: Vec<String>,
+    points: Vec<DesignPoint<T>>,
+    front: Vec<usize>,
+}
+
+impl<T> ParetoFront<T> {
+    /// Gets every point explored, the grid first and then the points of each refinement round.
+    pub fn get_points(&self) -> &Vec<DesignPoint<T>> {
+        &self.points
+    }
+
+    /// Gets the points on the front, in the order they were explored.
+    pub fn get_front(&self) -> Vec<&DesignPoint<T>> {
+        self.front.iter().map(|&i| &self.points[i]).collect()
+    }
+
+    /// Gets whether the explored point at the given index is on the front.
+    pub fn is_on_front(&self, index: usize) -> bool {
+        self.front.contains(&index)
+    }
+
+    /// Gets the names of the components of the axes.
+    pub fn get_parameter_names(&self) -> &Vec<String> {
+        &self.parameter_names
+    }
+
+    pub fn get_objective_names(&self) -> &Vec<String> {
+        &self.objective_names
+    }
+
+    /// Exports the points on the front as CSV, a column per axis (named after its component)
+    /// and per objective.
+    pub fn to_csv(&self) -> String {
+        let mut csv = self
+            .parameter_names
+            .iter()
+            .chain(&self.objective_names)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+        for point in self.get_front() {
+            let values: Vec<String> = point
+                .parameters
+                .iter()
+                .map(|p| p.value)
+                .chain(point.objectives.iter().copied())
+                .map(|v| v.to_string())
+                .collect();
+            writeln!(csv, "{}", values.join(",")).unwrap();
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Resistor, VoltageSource},
+    };
+
+    #[test]
+    fn test_divider_pareto_front() {
+        // A divider from 10V trading its quiescent power against its output resistance, with
+        // the output between 2.5V and 5V.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Resistor::new(2, 0, 1e3));
+        netlist
+            .set_component_name(1, "Rtop")
+            .set_component_name(2, "Rbottom");
+
+        let values = vec![1e3, 2e3, 5e3, 10e3, 20e3, 50e3];
+        let exploration = DesignSpaceExploration::new(vec![(1, values.clone()), (2, values)])
+            .with_objective(
+                Measurement::new("power", |&(_, power, _): &(f64, f64, f64)| power),
+                Goal::Minimize,
+            )
+            .with_objective(
+                Measurement::new("resistance", |&(_, _, resistance)| resistance),
+                Goal::Minimize,
+            )
+            .with_objective(
+                Measurement::new("output", |&(output, _, _)| output).with_limits(2.5, 5.0),
+                Goal::Maximize,
+            )
+            .with_threads(4);
+        let analysis = |netlist: &mut Netlist| {
+            let top = netlist.get_components()[1].get_value().unwrap();
+            let bottom = netlist.get_components()[2].get_value().unwrap();
+            let mut solver = BESolver::new(netlist);
+            solver.solve_dc().unwrap();
+            let power = netlist.get_components()[0].get_power();
+            (
+                netlist.get_components()[2].get_voltage(),
+                power,
+                top * bottom / (top + bottom),
+            )
+        };
+
+        let front = exploration.run(&netlist, analysis).unwrap();
+        assert_eq!(front.get_points().len(), 36);
+        let on_front = |top: f64, bottom: f64| {
+            front
+                .get_front()
+                .iter()
+                .any(|p| p.get_parameter(1) == Some(top) && p.get_parameter(2) == Some(bottom))
+        };
+        // The stiffest and the most frugal feasible dividers, but nothing outputting under 2.5V.
+        assert!(on_front(1e3, 1e3));
+        assert!(on_front(50e3, 50e3));
+        assert!(!on_front(50e3, 1e3));
+
+        // No point of the front is dominated by a feasible point, and every other feasible
+        // point is dominated by one on the front.
+        let dominates = |a: &DesignPoint<(f64, f64, f64)>, b: &DesignPoint<(f64, f64, f64)>| {
+            let [a, b] = [a, b].map(|p| [p.objectives[0], p.objectives[1], -p.objectives[2]]);
+            a.iter().zip(&b).all(|(a, b)| a <= b) && a != b
+        };
+        for (index, point) in front.get_points().iter().enumerate() {
+            let dominated = front
+                .get_points()
+                .iter()
+                .any(|other| other.feasible && dominates(other, point));
+            assert_eq!(front.is_on_front(index), point.feasible && !dominated);
+        }
+
+        let csv = front.to_csv();
+        assert!(csv.starts_with("Rtop,Rbottom,power,resistance,output\n"));
+        assert_eq!(csv.lines().count(), front.get_front().len() + 1);
+
+        // Refinement explores between the grid values around the front.
+        let refined = exploration
+            .with_refinements(2)
+            .run(&netlist, analysis)
+            .unwrap();
+        assert!(refined.get_points().len() > 36);
+        assert!(refined.get_front().len() > front.get_front().len());
+        assert!(refined.get_points()[36..].iter().any(|p| {
+            let top = p.get_parameter(1).unwrap();
+            !(top == 1e3 || top == 2e3 || top == 5e3 || top == 10e3 || top == 20e3 || top == 50e3)
+        }));
+    }
+}
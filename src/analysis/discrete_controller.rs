@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+
+use crate::components::{ParamChange, ParamError};
+use crate::results::{Probe, TransientResult};
+use crate::{BESolver, SolverError};
+
+/// The computation of a [`DiscreteController`], executed once per sample.
+///
+/// Implemented for closures taking the sample time, the inputs and the outputs to write, e.g.
+/// firmware control code ported to Rust.
+pub trait DiscreteTimeBlock {
+    /// Computes the outputs from the inputs sampled at the given time. The outputs hold the
+    /// values of the previous sample, or zeros before the first one.
+    fn execute(&mut self, time: f64, inputs: &[f64], outputs: &mut [f64]);
+}
+
+impl<F: FnMut(f64, &[f64], &mut [f64])> DiscreteTimeBlock for F {
+    fn execute(&mut self, time: f64, inputs: &[f64], outputs: &mut [f64]) {
+        self(time, inputs, outputs)
+    }
+}
+
+/// A linear difference equation from the first input to the first output, the transfer
+/// function
+///
+/// H(z) = (b0 + b1 z^-1 + ... + bn z^-n) / (a0 + a1 z^-1 + ... + am z^-m),
+///
+/// e.g. a digital filter or compensator. The output can be clamped to limits, which are
+/// applied to the output history as well, so an integrating compensator doesn't wind up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifferenceEquation {
+    numerator: Vec<f64>,
+    denominator: Vec<f64>,
+    output_limits: (f64, f64),
+    inputs: VecDeque<f64>,
+    outputs: VecDeque<f64>,
+}
+
+impl DifferenceEquation {
+    /// Creates the difference equation of the transfer function with the given coefficients
+    /// of z^-k, starting from a zero history. The leading denominator coefficient must not be
+    /// zero.
+    pub fn new(numerator: &[f64], denominator: &[f64]) -> Self {
+        let a0 = denominator[0];
+        Self {
+            numerator: numerator.iter().map(|b| b / a0).collect(),
+            denominator: denominator.iter().map(|a| a / a0).collect(),
+            output_limits: (f64::NEG_INFINITY, f64::INFINITY),
+            inputs: VecDeque::from(vec![0.0; numerator.len()]),
+            outputs: VecDeque::from(vec![0.0; denominator.len() - 1]),
+        }
+    }
+
+    /// Creates a PID compensator of the error at the first input sampled with the given
+    /// period, integrating with backward Euler and differentiating with backward differences.
+    pub fn pid(
+        proportional_gain: f64,
+        integral_gain: f64,
+        derivative_gain: f64,
+        sample_period: f64,
+    ) -> Self {
+        let integral = integral_gain * sample_period;
+        let derivative = derivative_gain / sample_period;
+        Self::new(
+            &[
+                proportional_gain + integral + derivative,
+                -proportional_gain - 2.0 * derivative,
+                derivative,
+            ],
+            &[1.0, -1.0],
+        )
+    }
+
+    pub fn with_output_limits(mut self, min: f64, max: f64) -> Self {
+        self.output_limits = (min, max);
+        self
+    }
+
+    /// Computes the output for the next input sample.
+    pub fn next(&mut self, input: f64) -> f64 {
+        self.inputs.pop_back();
+        self.inputs.push_front(input);
+
+        let forward: f64 = self
+            .numerator
+            .iter()
+            .zip(&self.inputs)
+            .map(|(b, x)| b * x)
+            .sum();
+        let feedback: f64 = self.denominator[1..]
+            .iter()
+            .zip(&self.outputs)
+            .map(|(a, y)| a * y)
+            .sum();
+        let (min, max) = self.output_limits;
+        let output = (forward - feedback).clamp(min, max);
+
+        if !self.outputs.is_empty() {
+            self.outputs.pop_back();
+            self.outputs.push_front(output);
+        }
+        output
+    }
+}
+
+impl DiscreteTimeBlock for DifferenceEquation {
+    fn execute(&mut self, _time: f64, inputs: &[f64], outputs: &mut [f64]) {
+        outputs[0] = self.next(inputs[0]);
+    }
+}
+
+/// The resolution and full scale range of a converter bridging a [`DiscreteController`] to
+/// the circuit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    pub bits: u32,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Quantization {
+    pub fn new(bits: u32, min: f64, max: f64) -> Self {
+        Self { bits, min, max }
+    }
+
+    /// Gets the step between adjacent codes.
+    pub fn lsb(&self) -> f64 {
+        (self.max - self.min) / ((1u64 << self.bits) - 1) as f64
+    }
+
+    /// Converts a value to the value of the nearest code, clamped to the full scale range.
+    pub fn quantize(&self, value: f64) -> f64 {
+        let lsb = self.lsb();
+        let code = ((value.clamp(self.min, self.max) - self.min) / lsb).round();
+        self.min + code * lsb
+    }
+}
+
+/// A sampled-data block closing a digital control loop around the circuit, e.g. a PID loop
+/// running in the firmware of a microcontroller regulating a converter.
+///
+/// Every sample period the block's inputs are sampled from probes of the circuit through ADC
+/// bridges, the block is executed, and the computation delay later its outputs are applied to
+/// the primary values (see [`Component::get_value`](crate::components::Component::get_value))
+/// of the components they drive through DAC bridges, which hold them until the next update.
+/// Bridges without a [`Quantization`] are ideal. Timesteps are cut to land on the samples and
+/// updates.
+///
+/// Blocks must be `Send` so a controller can run on a worker thread.
+pub struct DiscreteController {
+    sample_period: f64,
+    computation_delay: f64,
+    block: Box<dyn DiscreteTimeBlock + Send>,
+    inputs: Vec<(Probe, Option<Quantization>)>,
+    outputs: Vec<(usize, Option<Quantization>)>,
+    output_values: Vec<f64>,
+}
+
+impl DiscreteController {
+    /// Creates a controller executing the block at the given sample period, updating its
+    /// outputs as soon as it has sampled its inputs.
+    pub fn new(sample_period: f64, block: impl DiscreteTimeBlock + Send + 'static) -> Self {
+        Self {
+            sample_period,
+            computation_delay: 0.0,
+            block: Box::new(block),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            output_values: Vec::new(),
+        }
+    }
+
+    /// Sets the delay between sampling the inputs and updating the outputs, at most the sample
+    /// period.
+    pub fn with_computation_delay(mut self, delay: f64) -> Self {
+        self.computation_delay = delay;
+        self
+    }
+
+    /// Adds an input sampled from a probe, in the order inputs are added.
+    pub fn with_input(mut self, probe: Probe) -> Self {
+        self.inputs.push((probe, None));
+        self
+    }
+
+    /// Adds an input sampled from a probe by an ADC.
+    pub fn with_adc_input(mut self, probe: Probe, quantization: Quantization) -> Self {
+        self.inputs.push((probe, Some(quantization)));
+        self
+    }
+
+    /// Adds an output driving the primary value of a component, in the order outputs are added.
+    pub fn with_output(mut self, component: usize) -> Self {
+        self.outputs.push((component, None));
+        self.output_values.push(0.0);
+        self
+    }
+
+    /// Adds an output driving the primary value of a component through a DAC.
+    pub fn with_dac_output(mut self, component: usize, quantization: Quantization) -> Self {
+        self.outputs.push((component, Some(quantization)));
+        self.output_values.push(0.0);
+        self
+    }
+
+    pub fn get_sample_period(&self) -> f64 {
+        self.sample_period
+    }
+
+    /// Gets the outputs computed by the last sample, before the DACs.
+    pub fn get_outputs(&self) -> &[f64] {
+        &self.output_values
+    }
+
+    fn sample(&mut self, solver: &BESolver) {
+        let inputs: Vec<f64> = self
+            .inputs
+            .iter()
+            .map(|(probe, quantization)| {
+                let value = probe.evaluate(solver.get_netlist(), solver.get_node_voltages());
+                quantization.map_or(value, |q| q.quantize(value))
+            })
+            .collect();
+        self.block
+            .execute(solver.get_time(), &inputs, &mut self.output_values);
+    }
+
+    fn update(&self, solver: &mut BESolver) -> Result<(), ParamError> {
+        for (&(component, quantization), &value) in self.outputs.iter().zip(&self.output_values) {
+            let value = quantization.map_or(value, |q| q.quantize(value));
+            ParamChange::new(component, value).apply(solver.get_netlist_mut())?;
+        }
+        Ok(())
+    }
+
+    /// Simulates for the duration with timesteps of at most dt, recording the circuit after
+    /// every timestep. The first sample is taken at the solver's time. The timestep and the
+    /// output components are checked before anything is simulated.
+    pub fn run(
+        &mut self,
+        solver: &mut BESolver,
+        dt: f64,
+        duration: f64,
+        result: &mut TransientResult,
+    ) -> Result<(), SolverError> {
+        SolverError::check_timestep(dt)?;
+        for &(component, _) in &self.outputs {
+            ParamChange::new(component, 0.0).check(solver.get_netlist())?;
+        }
+
+        let start = solver.get_time();
+        let end = start + duration;
+        let epsilon = dt.min(self.sample_period) * 1e-9;
+
+        let mut samples = 0u64;
+        let mut pending_update = None;
+        while solver.get_time() < end - epsilon {
+            let time = solver.get_time();
+            let sample_time = start + samples as f64 * self.sample_period;
+            if time >= sample_time - epsilon {
+                self.sample(solver);
+                samples += 1;
+                pending_update = Some(sample_time + self.computation_delay);
+            }
+            if pending_update.is_some_and(|update| time >= update - epsilon) {
+                self.update(solver)?;
+                pending_update = None;
+            }
+
+            let next_event = pending_update
+                .unwrap_or(f64::INFINITY)
+                .min(start + samples as f64 * self.sample_period);
+            let step = dt.min(next_event - time).min(end - time);
+            solver.try_solve(step)?;
+            result.record(solver);
+        }
+
+        result.finish();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Capacitor, Netlist, Resistor, VoltageSource};
+
+    #[test]
+    fn test_difference_equation() {
+        // A PI compensator with 1ms samples: the output steps by kp on a unit error, then
+        // ramps by ki*T per sample up to its limit.
+        let mut pid = DifferenceEquation::pid(2.0, 100.0, 0.0, 1e-3).with_output_limits(0.0, 3.0);
+        assert_relative_eq!(pid.next(1.0), 2.1);
+        assert_relative_eq!(pid.next(1.0), 2.2);
+        for _ in 0..20 {
+            pid.next(1.0);
+        }
+        assert_relative_eq!(pid.next(1.0), 3.0);
+        // Clamped, the output leaves the limit as soon as the error reverses.
+        assert!(pid.next(-0.1) < 3.0);
+
+        let adc = Quantization::new(8, 0.0, 2.55);
+        assert_relative_eq!(adc.lsb(), 0.01);
+        assert_relative_eq!(adc.quantize(1.234), 1.23);
+        assert_relative_eq!(adc.quantize(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_digital_control_loop() {
+        // A 10kHz firmware PI loop regulating an RC plant (1ms time constant) to 2V through a
+        // 10-bit ADC and a 12-bit DAC with a 5V range.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let mut pid = DifferenceEquation::pid(0.5, 500.0, 0.0, 1e-4).with_output_limits(0.0, 5.0);
+        let mut controller =
+            DiscreteController::new(1e-4, move |_time, inputs: &[f64], outputs: &mut [f64]| {
+                outputs[0] = pid.next(2.0 - inputs[0]);
+            })
+            .with_computation_delay(2e-5)
+            .with_adc_input(Probe::NodeVoltage(2), Quantization::new(10, 0.0, 5.0))
+            .with_dac_output(0, Quantization::new(12, 0.0, 5.0));
+
+        let mut result = TransientResult::new();
+        result.add_probe("output", Probe::NodeVoltage(2));
+        result.add_probe("drive", Probe::NodeVoltage(1));
+        let mut solver = BESolver::new(&mut netlist);
+        controller
+            .run(&mut solver, 1e-5, 20e-3, &mut result)
+            .unwrap();
+
+        // Settled to within an ADC step of the setpoint, with the integrator holding the
+        // source at the setpoint.
+        assert_relative_eq!(solver.get_node_voltage(2), 2.0, epsilon = 5.0 / 1023.0);
+        assert_relative_eq!(controller.get_outputs()[0], 2.0, epsilon = 0.02);
+        // The first output, kp*2V + ki*T*2V, only drove the plant from its update 20us after
+        // the first sample.
+        let first = Quantization::new(12, 0.0, 5.0).quantize(1.1);
+        assert_relative_eq!(result.value_at(1, 20e-6).unwrap(), 0.0);
+        assert_relative_eq!(
+            result.value_at(1, 30e-6).unwrap(),
+            first,
+            max_relative = 1e-9
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use nalgebra::Complex;
+
+use crate::ac_solver::{ACSolution, ACSolver};
+use crate::be_solver::stampable::{JunctionExpansion, Stampable};
+use crate::components::{Component, Netlist};
+use crate::{SolverError, SolverOptions};
+
+/// Harmonic distortion of a single tone at the output node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicDistortion {
+    pub frequency: f64,
+    /// Output phasor at the fundamental frequency.
+    pub fundamental: Complex<f64>,
+    /// Output phasor at twice the fundamental frequency.
+    pub second_harmonic: Complex<f64>,
+    /// Output phasor at three times the fundamental frequency.
+    pub third_harmonic: Complex<f64>,
+}
+
+impl HarmonicDistortion {
+    /// Second harmonic relative to the fundamental.
+    pub fn hd2(&self) -> f64 {
+        self.second_harmonic.norm() / self.fundamental.norm()
+    }
+
+    /// Third harmonic relative to the fundamental.
+    pub fn hd3(&self) -> f64 {
+        self.third_harmonic.norm() / self.fundamental.norm()
+    }
+}
+
+/// Intermodulation products of two tones at the output node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intermodulation {
+    pub first_frequency: f64,
+    pub second_frequency: f64,
+    /// Output phasor at the first tone.
+    pub first_tone: Complex<f64>,
+    /// Output phasor at the sum of the tones.
+    pub sum: Complex<f64>,
+    /// Output phasor at the difference of the tones.
+    pub difference: Complex<f64>,
+    /// Output phasor at twice the first tone minus the second.
+    pub third_order: Complex<f64>,
+}
+
+impl Intermodulation {
+    /// Second order intermodulation (sum product) relative to the first tone.
+    pub fn im2(&self) -> f64 {
+        self.sum.norm() / self.first_tone.norm()
+    }
+
+    /// Third order intermodulation (2*f1 - f2 product) relative to the first tone.
+    pub fn im3(&self) -> f64 {
+        self.third_order.norm() / self.first_tone.norm()
+    }
+}
+
+/// Small signal distortion analysis of mildly nonlinear circuits.
+///
+/// Every nonlinear junction is expanded as i = g1*v + g2*v^2 + g3*v^3 around its present state
+/// and the harmonics are computed by solving the linearized circuit once per order with the
+/// lower order nonlinear currents injected as sources (Volterra series). The netlist should
+/// first be brought to its operating point with a transient solver and excited with the `with_ac`
+/// phasors of its sources, whose magnitudes are peak amplitudes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistortionAnalysis {
+    output_node: usize,
+    options: SolverOptions,
+}
+
+impl DistortionAnalysis {
+    pub fn new(output_node: usize) -> Self {
+        Self {
+            output_node,
+            options: SolverOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Computes the harmonic distortion with every source excited at the given frequency.
+    pub fn harmonics(
+        &self,
+        netlist: &Netlist,
+        frequency: f64,
+    ) -> Result<HarmonicDistortion, SolverError> {
+        let solver = ACSolver::new(netlist).with_options(self.options);
+        let expansions = junction_expansions(netlist);
+
+        let first = solver.solve(frequency)?;
+        let v1 = junction_voltages(&first, &expansions);
+
+        // Squaring a tone of peak amplitude V gives V^2/2 at twice the frequency.
+        let second = solver.solve_with_injections(
+            2.0 * frequency,
+            &injections(&expansions, |e, k| e.g2 * v1[k] * v1[k] / 2.0),
+            false,
+        )?;
+        let v2 = junction_voltages(&second, &expansions);
+
+        let third = solver.solve_with_injections(
+            3.0 * frequency,
+            &injections(&expansions, |e, k| {
+                e.g3 * v1[k] * v1[k] * v1[k] / 4.0 + e.g2 * v1[k] * v2[k]
+            }),
+            false,
+        )?;
+
+        Ok(HarmonicDistortion {
+            frequency,
+            fundamental: first.get_node_voltage(self.output_node),
+            second_harmonic: second.get_node_voltage(self.output_node),
+            third_harmonic: third.get_node_voltage(self.output_node),
+        })
+    }
+
+    /// Computes the harmonic distortion at each of the given frequencies.
+    pub fn harmonic_sweep(
+        &self,
+        netlist: &Netlist,
+        frequencies: &[f64],
+    ) -> Result<Vec<HarmonicDistortion>, SolverError> {
+        frequencies
+            .iter()
+            .map(|&f| self.harmonics(netlist, f))
+            .collect()
+    }
+
+    /// Computes the intermodulation products of two tones. The source at index
+    /// `second_tone_source` is excited at `second_frequency` and every other source at
+    /// `first_frequency`.
+    pub fn intermodulation(
+        &self,
+        netlist: &Netlist,
+        first_frequency: f64,
+        second_frequency: f64,
+        second_tone_source: usize,
+    ) -> Result<Intermodulation, SolverError> {
+        let mut first_netlist = netlist.clone();
+        let mut second_netlist = netlist.clone();
+        for (index, component) in first_netlist.get_components_mut().iter_mut().enumerate() {
+            if index == second_tone_source {
+                remove_excitation(component);
+            }
+        }
+        for (index, component) in second_netlist.get_components_mut().iter_mut().enumerate() {
+            if index != second_tone_source {
+                remove_excitation(component);
+            }
+        }
+
+        let solver = ACSolver::new(netlist).with_options(self.options);
+        let expansions = junction_expansions(netlist);
+
+        let first = ACSolver::new(&first_netlist)
+            .with_options(self.options)
+            .solve(first_frequency)?;
+        let second = ACSolver::new(&second_netlist)
+            .with_options(self.options)
+            .solve(second_frequency)?;
+        let va = junction_voltages(&first, &expansions);
+        let vb = junction_voltages(&second, &expansions);
+
+        // The product of two tones Va and Vb gives Va*Vb/2 at both the sum and the difference,
+        // doubled by the cross term of the square.
+        let sum = solver.solve_with_injections(
+            first_frequency + second_frequency,
+            &injections(&expansions, |e, k| e.g2 * va[k] * vb[k]),
+            false,
+        )?;
+        let difference = solver.solve_with_injections(
+            first_frequency - second_frequency,
+            &injections(&expansions, |e, k| e.g2 * va[k] * vb[k].conj()),
+            false,
+        )?;
+        let double = solver.solve_with_injections(
+            2.0 * first_frequency,
+            &injections(&expansions, |e, k| e.g2 * va[k] * va[k] / 2.0),
+            false,
+        )?;
+        let v_difference = junction_voltages(&difference, &expansions);
+        let v_double = junction_voltages(&double, &expansions);
+
+        let third_order = solver.solve_with_injections(
+            2.0 * first_frequency - second_frequency,
+            &injections(&expansions, |e, k| {
+                e.g3 * va[k] * va[k] * vb[k].conj() * 0.75
+                    + e.g2 * (va[k] * v_difference[k] + vb[k].conj() * v_double[k])
+            }),
+            false,
+        )?;
+
+        Ok(Intermodulation {
+            first_frequency,
+            second_frequency,
+            first_tone: first.get_node_voltage(self.output_node),
+            sum: sum.get_node_voltage(self.output_node),
+            difference: difference.get_node_voltage(self.output_node),
+            third_order: third_order.get_node_voltage(self.output_node),
+        })
+    }
+}
+
+fn junction_expansions(netlist: &Netlist) -> Vec<JunctionExpansion> {
+    netlist
+        .get_components()
+        .iter()
+        .flat_map(|c| c.junction_expansions())
+        .collect()
+}
+
+fn junction_voltages(solution: &ACSolution, expansions: &[JunctionExpansion]) -> Vec<Complex<f64>> {
+    expansions
+        .iter()
+        .map(|e| solution.get_voltage_between(e.positive_node, e.negative_node))
+        .collect()
+}
+
+fn injections(
+    expansions: &[JunctionExpansion],
+    current: impl Fn(&JunctionExpansion, usize) -> Complex<f64>,
+) -> Vec<(usize, usize, Complex<f64>)> {
+    expansions
+        .iter()
+        .enumerate()
+        .map(|(k, e)| (e.positive_node, e.negative_node, current(e, k)))
+        .collect()
+}
+
+fn remove_excitation(component: &mut Component) {
+    match component {
+        Component::VoltageSource(s) => *s = s.clone().with_ac(0.0, 0.0),
+        Component::CurrentSource(s) => *s = s.clone().with_ac(0.0, 0.0),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::BESolver;
+    use crate::components::{CurrentSource, Diode, DiodeModel};
+
+    use super::*;
+
+    const THERMAL_VOLTAGE: f64 = 0.025852;
+
+    fn biased_diode(netlist: &mut Netlist) {
+        netlist.add_component(Diode::new(
+            1,
+            0,
+            DiodeModel::Shockley {
+                saturation_current: 1e-14,
+                emission_coefficient: 1.0,
+            },
+        ));
+
+        let mut solver = BESolver::new(netlist);
+        solver.solve(1e-6).unwrap();
+    }
+
+    #[test]
+    fn diode_harmonics() {
+        // For a diode driven by a current I0 + I*cos(wt) the voltage is
+        // nVt*ln(1 + a*cos(wt)) + const with a = I/I0, so HD2 = a/4 and HD3 = a^2/12.
+        let mut netlist = Netlist::new();
+        netlist.add_component(CurrentSource::new(1, 0, 1e-3).with_ac(1e-5, 0.0));
+        biased_diode(&mut netlist);
+
+        let distortion = DistortionAnalysis::new(1).harmonics(&netlist, 1e3).unwrap();
+        let a = 1e-2;
+
+        assert_relative_eq!(
+            distortion.fundamental.norm(),
+            1e-5 * THERMAL_VOLTAGE / 1e-3,
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(distortion.hd2(), a / 4.0, max_relative = 1e-3);
+        assert_relative_eq!(distortion.hd3(), a * a / 12.0, max_relative = 1e-3);
+    }
+
+    #[test]
+    fn diode_intermodulation() {
+        // Two equal tones give IM2 = a/2 and IM3 = a^2/4.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 1e-3).with_ac(1e-5, 0.0))
+            .add_component(CurrentSource::new(1, 0, 0.0).with_ac(1e-5, 0.0));
+        biased_diode(&mut netlist);
+
+        let distortion = DistortionAnalysis::new(1)
+            .intermodulation(&netlist, 1e3, 1.1e3, 1)
+            .unwrap();
+        let a = 1e-2;
+
+        assert_relative_eq!(distortion.im2(), a / 2.0, max_relative = 1e-3);
+        assert_relative_eq!(
+            distortion.difference.norm(),
+            distortion.sum.norm(),
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(distortion.im3(), a * a / 4.0, max_relative = 1e-3);
+    }
+}
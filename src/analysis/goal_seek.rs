@@ -0,0 +1,316 @@
+use crate::components::{Netlist, ParamChange, ParamError};
+
+/// Why a goal seek failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoalSeekError {
+    /// The measurement minus the target has the same sign at both bounds, so the bounds don't
+    /// bracket a solution.
+    NotBracketed { lower: f64, upper: f64 },
+    /// The target wasn't reached within the maximum number of iterations.
+    NoConvergence { value: f64, measurement: f64 },
+    /// The component can't be changed, checked before the circuit is simulated.
+    InvalidChange(ParamError),
+}
+
+/// The outcome of a successful goal seek.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalSeekResult {
+    /// The parameter value that achieves the target.
+    pub value: f64,
+    /// The measurement at that value.
+    pub measurement: f64,
+    /// Number of times the circuit was simulated.
+    pub evaluations: usize,
+}
+
+/// Adjusts the primary value of one component until a measurement hits a target.
+///
+/// The value is searched for between two bounds bracketing the solution using Brent's method
+/// (secant and inverse quadratic interpolation steps safeguarded by bisection).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalSeek {
+    component: usize,
+    lower: f64,
+    upper: f64,
+    target: f64,
+    tolerance: f64,
+    max_evaluations: usize,
+}
+
+impl GoalSeek {
+    /// Creates a goal seek for the value of the component at the given index, searching
+    /// between `lower` and `upper` for a measurement equal to `target`.
+    pub fn new(component: usize, lower: f64, upper: f64, target: f64) -> Self {
+        Self {
+            component,
+            lower,
+            upper,
+            target,
+            tolerance: 1e-6,
+            max_evaluations: 100,
+        }
+    }
+
+    /// Sets how close to the target the measurement has to be.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the maximum number of times the circuit is simulated.
+    pub fn with_max_evaluations(mut self, max_evaluations: usize) -> Self {
+        self.max_evaluations = max_evaluations;
+        self
+    }
+
+    /// Runs the goal seek.
+    ///
+    /// For every trial value a copy of the netlist is made with the value applied and passed to
+    /// `measure`, which should simulate it and return the measurement.
+    pub fn run(
+        &self,
+        netlist: &Netlist,
+        mut measure: impl FnMut(&mut Netlist) -> f64,
+    ) -> Result<GoalSeekResult, GoalSeekError> {
+        ParamChange::new(self.component, self.lower)
+            .check(netlist)
+            .map_err(GoalSeekError::InvalidChange)?;
+
+        let mut evaluations = 0;
+        let mut error = |value: f64| {
+            let mut trial = netlist.clone();
+            ParamChange::new(self.component, value)
+                .apply(&mut trial)
+                .expect("the component was checked");
+            evaluations += 1;
+            measure(&mut trial) - self.target
+        };
+
+        let (mut a, mut b) = (self.lower, self.upper);
+        let (mut fa, mut fb) = (error(a), error(b));
+
+        // Either bound may already meet the target, whether or not the two bracket it.
+        if fa.abs() <= self.tolerance {
+            return Ok(GoalSeekResult {
+                value: a,
+                measurement: fa + self.target,
+                evaluations: 2,
+            });
+        }
+        if fb.abs() <= self.tolerance {
+            return Ok(GoalSeekResult {
+                value: b,
+                measurement: fb + self.target,
+                evaluations: 2,
+            });
+        }
+
+        if fa * fb > 0.0 {
+            return Err(GoalSeekError::NotBracketed {
+                lower: fa + self.target,
+                upper: fb + self.target,
+            });
+        }
+
+        // Brent's method, b is the best estimate and a the contrapoint.
+        let (mut c, mut fc) = (a, fa);
+        let mut d = b - a;
+        let mut e = d;
+
+        for _ in 2..self.max_evaluations {
+            if fb.abs() <= self.tolerance {
+                break;
+            }
+
+            if fb * fc > 0.0 {
+                c = a;
+                fc = fa;
+                d = b - a;
+                e = d;
+            }
+
+            if fc.abs() < fb.abs() {
+                a = b;
+                b = c;
+                c = a;
+                fa = fb;
+                fb = fc;
+                fc = fa;
+            }
+
+            let tolerance = 2.0 * f64::EPSILON * b.abs();
+            let m = 0.5 * (c - b);
+            if m.abs() <= tolerance {
+                break;
+            }
+
+            if e.abs() >= tolerance && fa.abs() > fb.abs() {
+                // Attempt interpolation.
+                let s = fb / fa;
+                let (mut p, mut q) = if a == c {
+                    // Secant step.
+                    (2.0 * m * s, 1.0 - s)
+                } else {
+                    // Inverse quadratic interpolation.
+                    let q = fa / fc;
+                    let r = fb / fc;
+                    (
+                        s * (2.0 * m * q * (q - r) - (b - a) * (r - 1.0)),
+                        (q - 1.0) * (r - 1.0) * (s - 1.0),
+                    )
+                };
+
+                if p > 0.0 {
+                    q = -q;
+                } else {
+                    p = -p;
+                }
+
+                if 2.0 * p < (3.0 * m * q - (tolerance * q).abs()).min((e * q).abs()) {
+                    e = d;
+                    d = p / q;
+                } else {
+                    d = m;
+                    e = m;
+                }
+            } else {
+                // Fall back to bisection.
+                d = m;
+                e = m;
+            }
+
+            a = b;
+            fa = fb;
+            b += if d.abs() > tolerance {
+                d
+            } else {
+                tolerance.copysign(m)
+            };
+            fb = error(b);
+        }
+
+        if fb.abs() <= self.tolerance {
+            Ok(GoalSeekResult {
+                value: b,
+                measurement: fb + self.target,
+                evaluations,
+            })
+        } else {
+            Err(GoalSeekError::NoConvergence {
+                value: b,
+                measurement: fb + self.target,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Diode, DiodeModel, Resistor, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    fn output_voltage(netlist: &mut Netlist) -> f64 {
+        let mut solver = BESolver::new(netlist);
+        solver.solve(0.001).unwrap();
+        solver.get_node_voltage(2)
+    }
+
+    #[test]
+    fn test_goal_seek_divider() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 100.0));
+
+        let result = GoalSeek::new(2, 1.0, 1e6, 2.5)
+            .run(&netlist, output_voltage)
+            .unwrap();
+
+        assert_relative_eq!(result.value, 1000.0, max_relative = 1e-4);
+        assert_relative_eq!(result.measurement, 2.5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_goal_seek_diode_bias() {
+        // Find the supply voltage giving 1mA through a resistor and diode.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let result = GoalSeek::new(0, 0.0, 10.0, 1.0)
+            .run(&netlist, |netlist| {
+                let mut solver = BESolver::new(netlist);
+                solver.solve(0.001).unwrap();
+                let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+                r.get_current() * 1000.0
+            })
+            .unwrap();
+
+        assert_relative_eq!(result.measurement, 1.0, epsilon = 1e-6);
+        assert!(result.value > 1.6 && result.value < 1.7);
+    }
+
+    #[test]
+    fn test_goal_seek_not_bracketed() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 100.0));
+
+        let result = GoalSeek::new(2, 1.0, 10.0, 2.5).run(&netlist, output_voltage);
+
+        assert!(matches!(result, Err(GoalSeekError::NotBracketed { .. })));
+    }
+
+    #[test]
+    fn test_goal_seek_invalid_component() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 100.0));
+
+        assert_eq!(
+            GoalSeek::new(3, 1.0, 10.0, 2.5).run(&netlist, output_voltage),
+            Err(GoalSeekError::InvalidChange(ParamError::UnknownComponent(
+                3
+            )))
+        );
+    }
+
+    #[test]
+    fn test_goal_seek_upper_bound_meets_target() {
+        // The upper bound gives 2.5V, just below the target like the lower bound but within
+        // the tolerance.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 100.0));
+
+        let result = GoalSeek::new(2, 1.0, 1000.0, 2.5 + 1e-7)
+            .with_tolerance(1e-6)
+            .run(&netlist, output_voltage)
+            .unwrap();
+
+        assert_eq!(result.value, 1000.0);
+        assert_eq!(result.evaluations, 2);
+        assert_relative_eq!(result.measurement, 2.5, epsilon = 1e-9);
+    }
+}
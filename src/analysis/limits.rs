@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+
+use crate::results::TransientResult;
+use crate::{BESolver, ConvergenceReport};
+
+/// Why a run guarded by [`ResourceLimits`] was aborted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbortReason {
+    /// The run took longer than the maximum wall-clock time.
+    WallClock { limit: Duration, elapsed: Duration },
+    /// The samples stored in the result took more than the maximum memory, in bytes.
+    Memory { limit: usize, used: usize },
+    /// The solver took more than the maximum number of timesteps, counting substeps.
+    Steps { limit: usize },
+    /// A timestep failed to converge.
+    NotConverged(Box<ConvergenceReport>),
+}
+
+/// A run aborted by [`ResourceLimits`]. The result holds everything recorded up to the abort.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aborted {
+    /// Simulation time reached when the run was aborted.
+    pub time: f64,
+    pub reason: AbortReason,
+}
+
+/// Limits on the resources a transient run may consume, so that an application embedding the
+/// simulator (e.g. a server running netlists it was sent) can't be brought down by a
+/// pathological netlist.
+///
+/// The limits are checked after every timestep, either by running with [`ResourceLimits::run`]
+/// or by checking a [`LimitWatch`] from a custom loop. Every limit is off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ResourceLimits {
+    max_duration: Option<Duration>,
+    max_memory: Option<usize>,
+    max_steps: Option<usize>,
+}
+
+impl ResourceLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the wall-clock time of the run.
+    pub fn with_max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Limits the memory taken by the samples stored in the result, in bytes (see
+    /// [`TransientResult::get_memory_usage`]).
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Limits the number of timesteps the solver takes, counting the substeps of refined
+    /// timesteps.
+    pub fn with_max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Starts watching the limits from the current state of the solver.
+    pub fn watch(&self, solver: &BESolver) -> LimitWatch {
+        LimitWatch {
+            limits: *self,
+            started: Instant::now(),
+            start_steps: solver.get_statistics().steps,
+        }
+    }
+
+    /// Solves timesteps of dt until the duration has passed, recording each one in the result,
+    /// and aborts as soon as a limit is exceeded or a timestep fails to converge. The result is
+    /// finished either way, so it holds everything recorded up to the abort.
+    pub fn run(
+        &self,
+        solver: &mut BESolver,
+        dt: f64,
+        duration: f64,
+        result: &mut TransientResult,
+    ) -> Result<(), Aborted> {
+        let watch = self.watch(solver);
+        let end = solver.get_time() + duration;
+        let outcome = loop {
+            if solver.get_time() >= end - dt / 2.0 {
+                break Ok(());
+            }
+            if let Err(report) = solver.try_solve(dt) {
+                break Err(Aborted {
+                    time: solver.get_time(),
+                    reason: AbortReason::NotConverged(report),
+                });
+            }
+            result.record(solver);
+            if let Err(aborted) = watch.check(solver, result) {
+                break Err(aborted);
+            }
+        };
+
+        result.finish();
+        outcome
+    }
+}
+
+/// The limits of a run being watched, see [`ResourceLimits::watch`].
+#[derive(Debug, Clone)]
+pub struct LimitWatch {
+    limits: ResourceLimits,
+    started: Instant,
+    start_steps: usize,
+}
+
+impl LimitWatch {
+    /// Checks every limit, returning the first one exceeded.
+    pub fn check(&self, solver: &BESolver, result: &TransientResult) -> Result<(), Aborted> {
+        let time = solver.get_time();
+        let abort = |reason| Err(Aborted { time, reason });
+
+        if let Some(limit) = self.limits.max_steps
+            && solver.get_statistics().steps - self.start_steps > limit
+        {
+            return abort(AbortReason::Steps { limit });
+        }
+        if let Some(limit) = self.limits.max_memory {
+            let used = result.get_memory_usage();
+            if used > limit {
+                return abort(AbortReason::Memory { limit, used });
+            }
+        }
+        if let Some(limit) = self.limits.max_duration {
+            let elapsed = self.started.elapsed();
+            if elapsed > limit {
+                return abort(AbortReason::WallClock { limit, elapsed });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{Capacitor, Netlist, Resistor, VoltageSource};
+    use crate::results::Probe;
+
+    fn rc() -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        netlist
+    }
+
+    fn run(limits: ResourceLimits) -> (Result<(), Aborted>, TransientResult) {
+        let mut netlist = rc();
+        let mut solver = BESolver::new(&mut netlist);
+        let mut result = TransientResult::new();
+        result.add_probe("out", Probe::NodeVoltage(2));
+        let outcome = limits.run(&mut solver, 1e-5, 10e-3, &mut result);
+        (outcome, result)
+    }
+
+    #[test]
+    fn test_limits() {
+        let (outcome, result) = run(ResourceLimits::new());
+        assert_eq!(outcome, Ok(()));
+        assert_eq!(result.get_times().len(), 1000);
+
+        // The partial result holds every step up to the abort.
+        let (outcome, result) = run(ResourceLimits::new().with_max_steps(100));
+        let aborted = outcome.unwrap_err();
+        assert_eq!(aborted.reason, AbortReason::Steps { limit: 100 });
+        assert_eq!(result.get_times().len(), 101);
+        assert!((aborted.time - 101e-5).abs() < 1e-12);
+
+        let (outcome, result) = run(ResourceLimits::new().with_max_memory(4096));
+        let aborted = outcome.unwrap_err();
+        let AbortReason::Memory { limit, used } = aborted.reason else {
+            panic!("{aborted:?}");
+        };
+        assert_eq!(limit, 4096);
+        assert_eq!(used, result.get_memory_usage());
+        assert!(used > 4096 && result.get_times().len() < 1000);
+
+        let (outcome, result) = run(ResourceLimits::new().with_max_duration(Duration::ZERO));
+        assert!(matches!(
+            outcome.unwrap_err().reason,
+            AbortReason::WallClock { .. }
+        ));
+        assert_eq!(result.get_times().len(), 1);
+    }
+}
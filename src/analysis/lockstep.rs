@@ -0,0 +1,240 @@
+use nalgebra::DMatrix;
+
+use crate::analysis::{BatchPoint, BatchResults};
+use crate::backend::{DenseBackend, System};
+use crate::be_solver::{
+    VariableKind, assemble, row_kinds, stampable::Discretization, update_components,
+};
+use crate::components::{Netlist, ParamChange, ParamError};
+use crate::results::TransientResult;
+use crate::{SolverOptions, SystemLayout};
+
+/// Why a run of a [`LockstepBatch`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockstepFailure {
+    /// The system of the timestep ending at the time was singular.
+    Singular { time: f64 },
+    /// The timestep ending at the time didn't converge within the maximum number of iterations.
+    NotConverged { time: f64 },
+}
+
+/// Runs the transient of many variants of a circuit in lockstep, such as the circuits drawn by
+/// a [`MonteCarlo`](crate::analysis::MonteCarlo) analysis, solving the Newton-Raphson
+/// iterations of every run still iterating together as one batch on a [`DenseBackend`].
+///
+/// Each run integrates like a [`BESolver`](crate::BESolver) with the same options, except that timesteps aren't refined, systems aren't scaled and sources aren't
+/// ramped. A run that fails stops there without holding the others back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockstepBatch {
+    parameter_sets: Vec<Vec<ParamChange>>,
+    options: SolverOptions,
+}
+
+impl LockstepBatch {
+    pub fn new(parameter_sets: Vec<Vec<ParamChange>>) -> Self {
+        Self {
+            parameter_sets,
+            options: SolverOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn get_parameter_sets(&self) -> &Vec<Vec<ParamChange>> {
+        &self.parameter_sets
+    }
+
+    /// Runs every parameter set applied to a copy of the netlist for the duration with timesteps
+    /// of dt, recording each in a copy of the given result, which holds the probes to record.
+    /// Fails before anything is simulated if a parameter set can't be applied.
+    pub fn run(
+        &self,
+        netlist: &Netlist,
+        backend: &dyn DenseBackend,
+        dt: f64,
+        duration: f64,
+        result: &TransientResult,
+    ) -> Result<BatchResults<Result<TransientResult, LockstepFailure>>, ParamError> {
+        let mut netlists: Vec<Netlist> = self
+            .parameter_sets
+            .iter()
+            .map(|parameters| {
+                let mut trial = netlist.clone();
+                for change in parameters {
+                    change.apply(&mut trial)?;
+                }
+                Ok(trial)
+            })
+            .collect::<Result<_, _>>()?;
+        let layouts: Vec<SystemLayout> = netlists.iter().map(SystemLayout::new).collect();
+        let kinds: Vec<Vec<VariableKind>> = netlists.iter().map(row_kinds).collect();
+        let mut solutions: Vec<DMatrix<f64>> = layouts
+            .iter()
+            .map(|layout| DMatrix::zeros(layout.get_dimension(), 1))
+            .collect();
+        let mut results = vec![result.clone(); netlists.len()];
+        let mut failures: Vec<Option<LockstepFailure>> = vec![None; netlists.len()];
+
+        let steps = (duration / dt).round() as usize;
+        for step in 1..=steps {
+            let time = step as f64 * dt;
+            let mut iterating: Vec<usize> = (0..netlists.len())
+                .filter(|&k| failures[k].is_none())
+                .collect();
+            let running = iterating.clone();
+
+            let mut iterations = 0;
+            while !iterating.is_empty() {
+                let systems: Vec<System> = iterating
+                    .iter()
+                    .map(|&k| {
+                        assemble(
+                            &netlists[k],
+                            &layouts[k],
+                            &solutions[k],
+                            Discretization::Timestep(dt, self.options.integration),
+                            self.options.gmin,
+                        )
+                    })
+                    .collect();
+                let solved = backend.solve_batch(&systems);
+                iterations += 1;
+
+                let mut still_iterating = Vec::new();
+                for (&k, x) in iterating.iter().zip(solved) {
+                    let Some(x) = x else {
+                        failures[k] = Some(LockstepFailure::Singular { time });
+                        continue;
+                    };
+
+                    let converged = x.iter().zip(solutions[k].iter()).zip(&kinds[k]).all(
+                        |((&new, &old), &kind)| {
+                            self.options.convergence_excess(kind, new, old) <= 0.0
+                        },
+                    );
+                    solutions[k] = x;
+                    if converged {
+                        continue;
+                    }
+                    if iterations >= self.options.max_iterations {
+                        failures[k] = Some(LockstepFailure::NotConverged { time });
+                    } else {
+                        still_iterating.push(k);
+                    }
+                }
+                iterating = still_iterating;
+            }
+
+            for k in running {
+                if failures[k].is_none() {
+                    let node_voltages = update_components(
+                        &mut netlists[k],
+                        &layouts[k],
+                        &solutions[k],
+                        Discretization::Timestep(dt, self.options.integration),
+                    );
+                    results[k].record_state(time, &netlists[k], &node_voltages);
+                }
+            }
+        }
+
+        let points = self
+            .parameter_sets
+            .iter()
+            .cloned()
+            .zip(results.into_iter().zip(failures))
+            .map(|(parameters, (mut result, failure))| {
+                result.finish();
+                BatchPoint {
+                    parameters,
+                    result: failure.map_or(Ok(result), Err),
+                }
+            })
+            .collect();
+
+        Ok(BatchResults::new(points))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::BESolver;
+    use crate::analysis::MonteCarlo;
+    use crate::backend::CpuBackend;
+    use crate::components::{
+        Capacitor, Diode, DiodeModel, Distribution, Resistor, Tolerance, VoltageSource,
+    };
+    use crate::results::Probe;
+
+    #[test]
+    fn test_lockstep_monte_carlo() {
+        // A diode clamped RC with 10% resistors and capacitors, run in lockstep and one by one.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0))
+            .add_component(Diode::new(2, 0, DiodeModel::Ideal))
+            .add_component(Resistor::new(2, 0, 2e3))
+            .set_tolerance(1, Tolerance::new(Distribution::Uniform(0.1)))
+            .set_tolerance(2, Tolerance::new(Distribution::Uniform(0.1)));
+
+        let mut result = TransientResult::new();
+        result.add_probe("out", Probe::NodeVoltage(2));
+        let monte_carlo = MonteCarlo::new(20).with_seed(1);
+        let batch = LockstepBatch::new(monte_carlo.sample(&netlist).unwrap());
+        let results = batch
+            .run(&netlist, &CpuBackend, 1e-5, 5e-3, &result)
+            .unwrap();
+
+        for point in results.get_points() {
+            let lockstep = point.result.as_ref().unwrap();
+            assert_eq!(lockstep.get_times().len(), 500);
+
+            let mut trial = netlist.clone();
+            for change in &point.parameters {
+                change.apply(&mut trial).unwrap();
+            }
+            let mut solver = BESolver::new(&mut trial);
+            for _ in 0..500 {
+                solver.solve(1e-5).unwrap();
+            }
+            assert_relative_eq!(
+                *lockstep.get_values(0).last().unwrap(),
+                solver.get_node_voltage(2),
+                max_relative = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_failed_run() {
+        // Shorting the source out makes the system of that run singular.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 0, 1e3));
+        let batch = LockstepBatch::new(vec![
+            vec![ParamChange::new(1, 2e3)],
+            vec![ParamChange::new(1, 0.0)],
+        ]);
+        let mut result = TransientResult::new();
+        result.add_probe("i", Probe::ComponentCurrent(1));
+        let results = batch
+            .run(&netlist, &CpuBackend, 1e-3, 10e-3, &result)
+            .unwrap();
+
+        let points = results.get_points();
+        assert_eq!(points[0].result.as_ref().unwrap().get_times().len(), 10);
+        assert_eq!(
+            points[1].result.as_ref().unwrap_err(),
+            &LockstepFailure::Singular { time: 1e-3 }
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crate::components::ParamChange;
+use crate::results::TransientResult;
+use crate::{BESolver, SolverError};
+
+/// A condition on the circuit which, when it becomes true, is an event of a [`LongHorizon`]
+/// run, e.g. a thermostat reaching its upper threshold.
+pub struct Trigger {
+    name: String,
+    condition: Box<dyn Fn(&BESolver) -> bool + Send + Sync>,
+    changes: Vec<ParamChange>,
+}
+
+impl Trigger {
+    pub fn new(
+        name: impl Into<String>,
+        condition: impl Fn(&BESolver) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            condition: Box::new(condition),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Sets changes applied to the netlist when the trigger fires, e.g. switching a heater off.
+    pub fn with_changes(mut self, changes: Vec<ParamChange>) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What started a [`DetailWindow`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventCause {
+    /// The start of the run, so the circuit settles before being advanced quasi-statically.
+    Start,
+    /// A scheduled event, by its index in the order they were added.
+    Scheduled(usize),
+    /// A breakpoint scheduled by a component, such as a relay contact changing state.
+    Breakpoint,
+    /// A trigger firing, by its name.
+    Trigger(String),
+}
+
+/// An interval simulated in detail around an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailWindow {
+    pub start: f64,
+    pub end: f64,
+    pub cause: EventCause,
+}
+
+/// Simulates over very long time scales (hours to days), such as battery charge cycles or
+/// thermostat controlled systems, by only simulating in detail around events.
+///
+/// Around every event the circuit is simulated with the detailed timestep for the detail
+/// duration. Between events it is advanced quasi-statically with timesteps doubling up to the
+/// maximum step: backward Euler settles the fast states at their equilibrium on such steps
+/// while integrating the slow states (e.g. a battery's state of charge or a room's temperature)
+/// through them. A quasi-static step is halved, down to the detailed timestep, when it changes
+/// a node voltage by more than the maximum change or makes a trigger fire, so triggers are
+/// located to within the detailed timestep.
+pub struct LongHorizon {
+    detail_dt: f64,
+    detail_duration: f64,
+    max_dt: f64,
+    max_change: f64,
+    events: Vec<(f64, Vec<ParamChange>)>,
+    triggers: Vec<Trigger>,
+}
+
+impl LongHorizon {
+    /// Creates a run simulating the given duration around each event with the detailed
+    /// timestep, with unlimited quasi-static steps changing node voltages by at most 10mV.
+    pub fn new(detail_dt: f64, detail_duration: f64) -> Self {
+        Self {
+            detail_dt,
+            detail_duration,
+            max_dt: f64::INFINITY,
+            max_change: 10e-3,
+            events: Vec::new(),
+            triggers: Vec::new(),
+        }
+    }
+
+    /// Limits the quasi-static timestep.
+    pub fn with_max_step(mut self, max_dt: f64) -> Self {
+        self.max_dt = max_dt;
+        self
+    }
+
+    /// Sets how much a quasi-static step may change any node voltage.
+    pub fn with_max_change(mut self, max_change: f64) -> Self {
+        self.max_change = max_change;
+        self
+    }
+
+    /// Schedules an event at the given time, applying the changes to the netlist, e.g. a
+    /// charger being plugged in.
+    pub fn with_event(mut self, time: f64, changes: Vec<ParamChange>) -> Self {
+        self.events.push((time, changes));
+        self
+    }
+
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.triggers.push(trigger);
+        self
+    }
+
+    /// Runs the solver for the duration, recording every timestep, detailed or quasi-static,
+    /// in the result, and returns the windows simulated in detail, or the error of the first
+    /// timestep that couldn't be solved. The changes of every event and trigger are checked
+    /// against the netlist before the run starts.
+    pub fn run(
+        &self,
+        solver: &mut BESolver,
+        duration: f64,
+        result: &mut TransientResult,
+    ) -> Result<Vec<DetailWindow>, SolverError> {
+        let changes = self.events.iter().map(|(_, changes)| changes);
+        for change in changes
+            .chain(self.triggers.iter().map(|t| &t.changes))
+            .flatten()
+        {
+            change.check(solver.get_netlist())?;
+        }
+
+        let start = solver.get_time();
+        let end = start + duration;
+        let epsilon = self.detail_dt * 1e-6;
+
+        let mut events: Vec<usize> = (0..self.events.len())
+            .filter(|&k| self.events[k].0 >= start)
+            .collect();
+        events.sort_by(|&a, &b| self.events[b].0.total_cmp(&self.events[a].0));
+
+        let mut states: Vec<bool> = self
+            .triggers
+            .iter()
+            .map(|t| (t.condition)(solver))
+            .collect();
+        let mut windows = vec![DetailWindow {
+            start,
+            end: (start + self.detail_duration).min(end),
+            cause: EventCause::Start,
+        }];
+        let mut window_end = windows[0].end;
+        let mut dt = self.detail_dt;
+
+        while solver.get_time() < end - epsilon {
+            let time = solver.get_time();
+            let mut causes = Vec::new();
+            while events
+                .last()
+                .is_some_and(|&k| self.events[k].0 <= time + epsilon)
+            {
+                let k = events.pop().unwrap();
+                for change in &self.events[k].1 {
+                    change.apply(solver.get_netlist_mut())?;
+                }
+                causes.push(EventCause::Scheduled(k));
+            }
+            self.open_windows(causes, time, end, &mut windows, &mut window_end, &mut dt);
+
+            let breakpoint = solver.next_breakpoint();
+            let mut limit = end - time;
+            if let Some(&k) = events.last() {
+                limit = limit.min(self.events[k].0 - time);
+            }
+            if let Some(breakpoint) = breakpoint {
+                limit = limit.min(breakpoint - time);
+            }
+
+            let fired = if time < window_end - epsilon {
+                solver.solve(self.detail_dt.min(window_end - time).min(limit))?;
+                self.fired(solver, &states)
+            } else {
+                let before = solver.get_node_voltages().clone();
+                let mut step = dt.min(self.max_dt).min(limit);
+                solver.solve(step)?;
+                let fired = loop {
+                    let fired = self.fired(solver, &states);
+                    let change = solver
+                        .get_node_voltages()
+                        .iter()
+                        .zip(&before)
+                        .map(|(after, before)| (after - before).abs())
+                        .fold(0.0, f64::max);
+
+                    if (change <= self.max_change && fired.is_empty()) || step <= self.detail_dt {
+                        break fired;
+                    }
+                    step = (step / 2.0).max(self.detail_dt);
+                    solver.redo_step(step)?;
+                };
+                dt = 2.0 * step;
+                fired
+            };
+            result.record(solver);
+            let time = solver.get_time();
+
+            let mut causes = Vec::new();
+            if breakpoint.is_some_and(|b| b <= time + epsilon) {
+                causes.push(EventCause::Breakpoint);
+            }
+            for k in fired {
+                for change in &self.triggers[k].changes {
+                    change.apply(solver.get_netlist_mut())?;
+                }
+                causes.push(EventCause::Trigger(self.triggers[k].name.clone()));
+            }
+            for (state, trigger) in states.iter_mut().zip(&self.triggers) {
+                *state = (trigger.condition)(solver);
+            }
+            self.open_windows(causes, time, end, &mut windows, &mut window_end, &mut dt);
+        }
+
+        result.finish();
+        Ok(windows)
+    }
+
+    /// Opens a detail window at the time for each of the events, restarting the quasi-static
+    /// timestep from the detailed one after them.
+    fn open_windows(
+        &self,
+        causes: Vec<EventCause>,
+        time: f64,
+        end: f64,
+        windows: &mut Vec<DetailWindow>,
+        window_end: &mut f64,
+        dt: &mut f64,
+    ) {
+        for cause in causes {
+            *window_end = window_end.max(time + self.detail_duration).min(end);
+            windows.push(DetailWindow {
+                start: time,
+                end: *window_end,
+                cause,
+            });
+            *dt = self.detail_dt;
+        }
+    }
+
+    /// Gets the indices of the triggers whose conditions became true over the last timestep.
+    fn fired(&self, solver: &BESolver, states: &[bool]) -> Vec<usize> {
+        (0..self.triggers.len())
+            .filter(|&k| !states[k] && (self.triggers[k].condition)(solver))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{Capacitor, CurrentSource, Netlist, Resistor, VoltageSource};
+    use crate::results::Probe;
+
+    #[test]
+    fn test_thermostat_day() {
+        // A room (node 1, in degrees) with an hour long time constant to the 10 degree outside
+        // (node 2), heated by 2kW towards 30 degrees, with a thermostat keeping it within 18 to
+        // 22 degrees. Half way through the day the outside drops to 5 degrees.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(2, 0, 10.0))
+            .add_component(Resistor::new(2, 1, 0.01))
+            .add_component(Capacitor::new(1, 0, 360e3, 10.0))
+            .add_component(CurrentSource::new(1, 0, 2000.0));
+
+        let day = 24.0 * 3600.0;
+        let run = LongHorizon::new(10e-3, 1.0)
+            .with_max_change(0.1)
+            .with_event(day / 2.0, vec![ParamChange::new(0, 5.0)])
+            .with_trigger(
+                Trigger::new("off", |s| s.get_node_voltage(1) > 22.0)
+                    .with_changes(vec![ParamChange::new(3, 0.0)]),
+            )
+            .with_trigger(
+                Trigger::new("on", |s| s.get_node_voltage(1) < 18.0)
+                    .with_changes(vec![ParamChange::new(3, 2000.0)]),
+            );
+
+        let mut result = TransientResult::new();
+        result.add_probe("room", Probe::NodeVoltage(1));
+        let mut solver = BESolver::new(&mut netlist);
+        let windows = run.run(&mut solver, day, &mut result).unwrap();
+
+        assert!((solver.get_time() - day).abs() < 1e-6);
+        assert_eq!(windows[0].cause, EventCause::Start);
+        let scheduled = windows
+            .iter()
+            .find(|w| w.cause == EventCause::Scheduled(0))
+            .unwrap();
+        assert!((scheduled.start - day / 2.0).abs() < 1e-6);
+
+        // The thermostat cycles every 49 minutes or so while it is 10 degrees outside and every
+        // 67 once it is colder, holding the room within a hair of its thresholds.
+        let offs = windows
+            .iter()
+            .filter(|w| w.cause == EventCause::Trigger("off".into()))
+            .count();
+        assert!((20..=35).contains(&offs));
+        let times = result.get_times();
+        let room = result.get_values(0);
+        let first_off = windows[1].start;
+        assert!(
+            times
+                .iter()
+                .zip(room)
+                .filter(|&(&t, _)| t > first_off)
+                .all(|(_, &v)| (17.9..=22.1).contains(&v))
+        );
+
+        // A tiny fraction of the steps a 10ms timestep would take.
+        assert!(solver.get_statistics().steps < 100_000);
+    }
+}
@@ -0,0 +1,54 @@
+mod batch;
+pub use batch::{BatchPoint, BatchResults, BatchRunner, ResponseSurface};
+
+mod monte_carlo;
+pub use monte_carlo::MonteCarlo;
+
+mod yield_analysis;
+pub use yield_analysis::{
+    Measurement, MeasurementStatistics, YieldAnalysis, YieldEstimate, YieldReport,
+};
+
+mod aging;
+pub use aging::{AgingError, AgingModel, AgingPoint, AgingSweep};
+
+mod goal_seek;
+pub use goal_seek::{GoalSeek, GoalSeekError, GoalSeekResult};
+
+mod design_space;
+pub use design_space::{DesignPoint, DesignSpaceExploration, Goal, ParetoFront};
+
+mod distortion;
+pub use distortion::{DistortionAnalysis, HarmonicDistortion, Intermodulation};
+
+mod oscillator;
+pub use oscillator::{OscillatorAnalysis, OscillatorError, OscillatorResult};
+
+mod bode;
+pub use bode::{BodeData, BodePlot};
+
+mod runaway;
+pub use runaway::{RunawayCause, RunawayEvent, ThermalWatchdog};
+
+mod long_horizon;
+pub use long_horizon::{DetailWindow, EventCause, LongHorizon, Trigger};
+
+mod limits;
+pub use limits::{AbortReason, Aborted, LimitWatch, ResourceLimits};
+
+mod lockstep;
+pub use lockstep::{LockstepBatch, LockstepFailure};
+
+mod discrete_controller;
+pub use discrete_controller::{
+    DifferenceEquation, DiscreteController, DiscreteTimeBlock, Quantization,
+};
+
+mod dc_sweep;
+pub use dc_sweep::{DCSweep, DCSweepResult};
+
+mod transient;
+pub use transient::{Timestep, TransientAnalysis, TransientError};
+
+mod sensitivity;
+pub use sensitivity::{SensitivityResult, TransientSensitivity, WaveformMetric};
@@ -0,0 +1,211 @@
+use crate::Rng;
+use crate::analysis::BatchRunner;
+use crate::components::{Netlist, ParamChange, ParamError};
+
+/// Key of the stream the deviates of individual components are drawn from.
+const COMPONENT_STREAM: u64 = 0;
+
+/// Key of the stream the shared deviates of correlation groups are drawn from.
+const GROUP_STREAM: u64 = 1;
+
+/// Draws random circuits from the tolerances of a netlist (see
+/// [`Netlist::set_tolerance`]), to be run with a [`BatchRunner`].
+///
+/// Each component draws a standard normal deviate of its own. Components in a correlation group
+/// mix it with a deviate shared by the group, `sqrt(c)*shared + sqrt(1 - c)*own` for a
+/// correlation c, before it is mapped through their distribution, so matched parts track each
+/// other instead of spreading independently.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarlo {
+    runs: usize,
+    seed: u64,
+}
+
+impl MonteCarlo {
+    pub fn new(runs: usize) -> Self {
+        Self { runs, seed: 0 }
+    }
+
+    /// Sets the seed the runs are drawn from. The same seed draws the same circuits.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn get_runs(&self) -> usize {
+        self.runs
+    }
+
+    /// Draws the values of every toleranced component for each run, failing on the first
+    /// toleranced component that isn't in the netlist or has no primary value to vary.
+    pub fn sample(&self, netlist: &Netlist) -> Result<Vec<Vec<ParamChange>>, ParamError> {
+        let components = netlist.get_toleranced_components();
+        let nominals = components
+            .iter()
+            .map(|&index| {
+                netlist
+                    .get_components()
+                    .get(index)
+                    .ok_or(ParamError::UnknownComponent(index))?
+                    .get_value()
+                    .ok_or(ParamError::NoValue(index))
+            })
+            .collect::<Result<Vec<f64>, _>>()?;
+
+        let sets = (0..self.runs)
+            .map(|run| {
+                let rng = Rng::new(self.seed).stream(run as u64);
+
+                components
+                    .iter()
+                    .zip(&nominals)
+                    .map(|(&index, &nominal)| {
+                        let tolerance = netlist
+                            .get_tolerance(index)
+                            .expect("the component has a tolerance");
+
+                        let own = rng.stream(COMPONENT_STREAM).stream(index as u64).gaussian();
+                        let z = match tolerance.group {
+                            Some((group, correlation)) => {
+                                let shared =
+                                    rng.stream(GROUP_STREAM).stream(group as u64).gaussian();
+                                correlation.sqrt() * shared + (1.0 - correlation).sqrt() * own
+                            }
+                            None => own,
+                        };
+
+                        ParamChange::new(
+                            index,
+                            nominal * (1.0 + tolerance.distribution.deviation(z)),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(sets)
+    }
+
+    /// Creates a runner over the drawn circuits, failing like [`MonteCarlo::sample`].
+    pub fn runner(&self, netlist: &Netlist) -> Result<BatchRunner, ParamError> {
+        Ok(BatchRunner::list(self.sample(netlist)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Bin, CurrentProbe, Distribution, Resistor, Tolerance, VoltageSource},
+    };
+
+    fn divider(top: Tolerance, bottom: Tolerance) -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0))
+            .set_tolerance(1, top)
+            .set_tolerance(2, bottom);
+        netlist
+    }
+
+    fn spread(values: &[f64]) -> (f64, f64) {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        (mean, variance.sqrt())
+    }
+
+    #[test]
+    fn test_distributions() {
+        let netlist = divider(
+            Tolerance::new(Distribution::Uniform(0.05)),
+            Tolerance::new(Distribution::Bins(vec![
+                Bin::new(-0.05, -0.01, 1.0),
+                Bin::new(0.01, 0.05, 1.0),
+            ])),
+        );
+        let sets = MonteCarlo::new(2000).with_seed(3).sample(&netlist).unwrap();
+        assert_eq!(
+            sets,
+            MonteCarlo::new(2000).with_seed(3).sample(&netlist).unwrap()
+        );
+
+        let top: Vec<f64> = sets.iter().map(|set| set[0].value).collect();
+        assert!(top.iter().all(|&r| (950.0..=1050.0).contains(&r)));
+        let (mean, sigma) = spread(&top);
+        assert!((mean - 1000.0).abs() < 2.0);
+        assert!((sigma - 50.0 / 3f64.sqrt()).abs() < 1.5);
+
+        let bottom: Vec<f64> = sets.iter().map(|set| set[1].value).collect();
+        assert!(bottom.iter().all(|&r| (r - 1000.0).abs() >= 10.0 - 1e-9));
+        assert!(bottom.iter().all(|&r| (r - 1000.0).abs() <= 50.0 + 1e-9));
+        let low = bottom.iter().filter(|&&r| r < 1000.0).count();
+        assert!((900..1100).contains(&low));
+    }
+
+    #[test]
+    fn test_correlated_divider() {
+        let ratio = |netlist: &Netlist| {
+            let results = MonteCarlo::new(200)
+                .runner(netlist)
+                .unwrap()
+                .with_threads(4)
+                .run(netlist, |netlist| {
+                    let mut solver = BESolver::new(netlist);
+                    solver.solve(1e-3).unwrap();
+                    solver.get_node_voltage(2)
+                })
+                .unwrap();
+            let outputs: Vec<f64> = results.get_points().iter().map(|p| p.result).collect();
+            spread(&outputs)
+        };
+
+        // Independent 1% resistors spread the output by about 0.5/sqrt(2) percent.
+        let gaussian = Tolerance::new(Distribution::Gaussian(0.01));
+        let (mean, sigma) = ratio(&divider(gaussian.clone(), gaussian.clone()));
+        assert!((mean - 5.0).abs() < 0.01);
+        assert!(sigma > 0.025 && sigma < 0.045);
+
+        // Resistors from one array track together and the ratio barely moves.
+        let matched = gaussian.clone().with_group(0, 0.99);
+        let (_, matched_sigma) = ratio(&divider(matched.clone(), matched.clone()));
+        assert!(matched_sigma < sigma / 5.0);
+
+        let tracking = gaussian.with_group(0, 1.0);
+        let (_, tracking_sigma) = ratio(&divider(tracking.clone(), tracking));
+        assert!(tracking_sigma < 1e-9);
+
+        // The correlation is that of the drawn values.
+        let netlist = divider(matched.clone(), matched.with_group(0, 0.5));
+        let sets = MonteCarlo::new(4000).sample(&netlist).unwrap();
+        let (top, bottom): (Vec<f64>, Vec<f64>) =
+            sets.iter().map(|set| (set[0].value, set[1].value)).unzip();
+        let ((top_mean, top_sigma), (bottom_mean, bottom_sigma)) = (spread(&top), spread(&bottom));
+        let covariance = top
+            .iter()
+            .zip(&bottom)
+            .map(|(a, b)| (a - top_mean) * (b - bottom_mean))
+            .sum::<f64>()
+            / top.len() as f64;
+        let correlation = covariance / (top_sigma * bottom_sigma);
+        assert!((correlation - (0.99f64 * 0.5).sqrt()).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_valueless_component() {
+        let mut netlist = divider(
+            Tolerance::new(Distribution::Uniform(0.05)),
+            Tolerance::new(Distribution::Uniform(0.05)),
+        );
+        netlist
+            .add_component(CurrentProbe::new(2, 3))
+            .set_tolerance(3, Tolerance::new(Distribution::Uniform(0.05)));
+
+        assert_eq!(
+            MonteCarlo::new(10).sample(&netlist),
+            Err(ParamError::NoValue(3))
+        );
+        assert!(MonteCarlo::new(10).runner(&netlist).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::components::{CurrentSource, Netlist};
+use crate::{BESolver, SolverError, SolverOptions};
+
+/// Why an oscillator analysis failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscillatorError {
+    /// The amplitude didn't settle before the maximum simulation time.
+    NotSettled { time: f64, amplitude: f64 },
+    /// Fewer than two full cycles were found while measuring.
+    NoOscillation,
+    /// A timestep couldn't be solved.
+    Solver(SolverError),
+}
+
+/// Steady state figures of an oscillator, measured after its amplitude settled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OscillatorResult {
+    /// Mean oscillation frequency in hertz.
+    pub frequency: f64,
+    /// Peak amplitude around the offset.
+    pub amplitude: f64,
+    /// Voltage the oscillation is centered on.
+    pub offset: f64,
+    /// Standard deviation of the cycle periods relative to the mean period, a proxy for phase
+    /// noise.
+    pub period_jitter: f64,
+    /// Time at which the amplitude was considered settled.
+    pub settle_time: f64,
+    /// Number of cycles measured.
+    pub cycles: usize,
+}
+
+/// Simulates an oscillator until its amplitude settles and measures it.
+///
+/// A circuit started at its (unstable) equilibrium never leaves it in simulation, so a small
+/// current pulse is injected into the observed node at the start. The amplitude is tracked
+/// between consecutive extrema of the node voltage and is considered settled once it stops
+/// changing, after which a number of cycles are measured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OscillatorAnalysis {
+    node: usize,
+    dt: f64,
+    max_time: f64,
+    options: SolverOptions,
+    kick_current: f64,
+    kick_duration: f64,
+    settle_tolerance: f64,
+    settle_cycles: usize,
+    measure_cycles: usize,
+}
+
+impl OscillatorAnalysis {
+    /// Creates an analysis observing the voltage of the given node, simulating with timestep
+    /// `dt` for at most `max_time` seconds.
+    pub fn new(node: usize, dt: f64, max_time: f64) -> Self {
+        Self {
+            node,
+            dt,
+            max_time,
+            options: SolverOptions::default(),
+            kick_current: 1e-3,
+            kick_duration: dt,
+            settle_tolerance: 1e-3,
+            settle_cycles: 5,
+            measure_cycles: 10,
+        }
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the current injected into the node at the start of the simulation and for how long.
+    /// A zero current disables the kick.
+    pub fn with_kick(mut self, current: f64, duration: f64) -> Self {
+        self.kick_current = current;
+        self.kick_duration = duration;
+        self
+    }
+
+    /// Sets the relative amplitude change allowed over the last `cycles` cycles for the
+    /// oscillation to be considered settled.
+    pub fn with_settling(mut self, tolerance: f64, cycles: usize) -> Self {
+        self.settle_tolerance = tolerance;
+        self.settle_cycles = cycles;
+        self
+    }
+
+    /// Sets the number of cycles measured after settling.
+    pub fn with_measure_cycles(mut self, cycles: usize) -> Self {
+        self.measure_cycles = cycles;
+        self
+    }
+
+    pub fn run(&self, netlist: &Netlist) -> Result<OscillatorResult, OscillatorError> {
+        let mut netlist = netlist.clone();
+        let kick = netlist.get_components().len();
+        netlist.add_component(CurrentSource::new(self.node, 0, self.kick_current));
+
+        let mut solver = BESolver::new(&mut netlist).with_options(self.options);
+        let mut extrema = ExtremaTracker::default();
+        let mut samples = Vec::new();
+        let mut settle_time = None;
+
+        loop {
+            if solver.get_time() >= self.kick_duration {
+                solver
+                    .get_netlist_mut()
+                    .get_component_mut(kick)
+                    .set_value(0.0);
+            }
+
+            solver.solve(self.dt).map_err(OscillatorError::Solver)?;
+            let time = solver.get_time();
+            let voltage = solver.get_node_voltage(self.node);
+            extrema.push(voltage);
+
+            match settle_time {
+                None => {
+                    if extrema.is_settled(2 * self.settle_cycles, self.settle_tolerance) {
+                        settle_time = Some(time);
+                        extrema.count = 0;
+                    }
+                }
+                Some(_) => {
+                    samples.push((time, voltage));
+                    if extrema.count > 2 * self.measure_cycles {
+                        break;
+                    }
+                }
+            }
+
+            if settle_time.is_none() && time >= self.max_time {
+                return Err(OscillatorError::NotSettled {
+                    time,
+                    amplitude: extrema.amplitudes.last().copied().unwrap_or(0.0),
+                });
+            }
+        }
+
+        let (min, max) = samples
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(_, v)| {
+                (min.min(v), max.max(v))
+            });
+        let offset = (max + min) / 2.0;
+
+        // Rising crossings of the offset, linearly interpolated between samples.
+        let crossings: Vec<f64> = samples
+            .windows(2)
+            .filter(|w| w[0].1 < offset && w[1].1 >= offset)
+            .map(|w| w[0].0 + (w[1].0 - w[0].0) * (offset - w[0].1) / (w[1].1 - w[0].1))
+            .collect();
+        if crossings.len() < 3 {
+            return Err(OscillatorError::NoOscillation);
+        }
+
+        let periods: Vec<f64> = crossings.windows(2).map(|w| w[1] - w[0]).collect();
+        let mean = periods.iter().sum::<f64>() / periods.len() as f64;
+        let variance =
+            periods.iter().map(|p| (p - mean) * (p - mean)).sum::<f64>() / periods.len() as f64;
+
+        Ok(OscillatorResult {
+            frequency: 1.0 / mean,
+            amplitude: (max - min) / 2.0,
+            offset,
+            period_jitter: variance.sqrt() / mean,
+            settle_time: settle_time.unwrap(),
+            cycles: periods.len(),
+        })
+    }
+}
+
+/// Finds alternating maxima and minima of a waveform one sample at a time.
+#[derive(Default)]
+struct ExtremaTracker {
+    previous: [Option<f64>; 2],
+    last_extremum: Option<(bool, f64)>,
+    /// Half the swing between each pair of consecutive extrema.
+    amplitudes: Vec<f64>,
+    /// Number of extrema found since last reset.
+    count: usize,
+}
+
+impl ExtremaTracker {
+    fn push(&mut self, value: f64) {
+        if let [Some(before), Some(middle)] = self.previous {
+            let is_max = middle > before && middle >= value;
+            let is_min = middle < before && middle <= value;
+
+            if is_max || is_min {
+                match self.last_extremum {
+                    Some((last_is_max, last)) if last_is_max != is_max => {
+                        self.amplitudes.push((middle - last).abs() / 2.0);
+                        self.last_extremum = Some((is_max, middle));
+                        self.count += 1;
+                    }
+                    // Keep only the most extreme of consecutive extrema of the same kind.
+                    Some((_, last)) => {
+                        if (is_max && middle > last) || (is_min && middle < last) {
+                            self.last_extremum = Some((is_max, middle));
+                        }
+                    }
+                    None => {
+                        self.last_extremum = Some((is_max, middle));
+                        self.count += 1;
+                    }
+                }
+            }
+        }
+
+        self.previous = [self.previous[1], Some(value)];
+    }
+
+    /// Returns true if the last `n` amplitudes are all within the relative tolerance of the
+    /// latest one.
+    fn is_settled(&self, n: usize, tolerance: f64) -> bool {
+        if self.amplitudes.len() < n.max(1) {
+            return false;
+        }
+
+        let latest = *self.amplitudes.last().unwrap();
+        latest > 0.0
+            && self.amplitudes[self.amplitudes.len() - n..]
+                .iter()
+                .all(|a| (a - latest).abs() <= tolerance * latest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+
+    use crate::components::{Capacitor, Diode, DiodeModel, Inductor, Resistor};
+
+    use super::*;
+
+    const DIODE: DiodeModel = DiodeModel::Shockley {
+        saturation_current: 1e-14,
+        emission_coefficient: 1.0,
+    };
+
+    /// An LC tank undamped by a negative resistance, with its amplitude limited by a pair of
+    /// anti-parallel diodes.
+    fn negative_resistance_oscillator() -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Capacitor::new(1, 0, 1e-6, 0.0))
+            .add_component(Inductor::new(1, 0, 1e-3, 0.0))
+            .add_component(Resistor::new(1, 0, -200.0))
+            .add_component(Resistor::new(1, 2, 50.0))
+            .add_component(Diode::new(2, 0, DIODE))
+            .add_component(Diode::new(0, 2, DIODE));
+        netlist
+    }
+
+    #[test]
+    fn oscillator_settles() {
+        let netlist = negative_resistance_oscillator();
+        let result = OscillatorAnalysis::new(1, 1e-6, 0.05)
+            .run(&netlist)
+            .unwrap();
+
+        let f0 = 1.0 / (2.0 * PI * (1e-3f64 * 1e-6).sqrt());
+        assert_relative_eq!(result.frequency, f0, max_relative = 0.05);
+        assert!(result.amplitude > 0.5 && result.amplitude < 2.0);
+        assert!(result.offset.abs() < 1e-2 * result.amplitude);
+        assert!(result.period_jitter < 1e-2);
+        assert_eq!(result.cycles, 9);
+    }
+
+    #[test]
+    fn no_kick_stays_at_equilibrium() {
+        let netlist = negative_resistance_oscillator();
+        let result = OscillatorAnalysis::new(1, 1e-6, 1e-3)
+            .with_kick(0.0, 0.0)
+            .run(&netlist);
+
+        assert!(matches!(
+            result,
+            Err(OscillatorError::NotSettled { amplitude: 0.0, .. })
+        ));
+    }
+}
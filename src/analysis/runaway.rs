@@ -0,0 +1,297 @@
+use crate::{BESolver, SolverError};
+
+/// Why a [`ThermalWatchdog`] flagged a runaway.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunawayCause {
+    /// A monitored temperature exceeded its limit.
+    TemperatureLimit(f64),
+    /// The power of a monitored component exceeded its limit.
+    PowerLimit(f64),
+    /// A monitored temperature kept rising faster and faster, the signature of heating feeding
+    /// back on itself.
+    Acceleration,
+}
+
+/// A runaway found by a [`ThermalWatchdog`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunawayEvent {
+    pub time: f64,
+    pub cause: RunawayCause,
+    /// The thermal node whose temperature ran away, None for a power limit.
+    pub node: Option<usize>,
+    /// The temperature of the node, or the power of the component for a power limit.
+    pub value: f64,
+    /// Index of the component held responsible: the one heating the node if it was given,
+    /// otherwise the one whose power rose the most over the steps leading to the runaway, out
+    /// of the components with monitored powers if there are any.
+    pub component: usize,
+    /// Name of the component in the netlist.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TemperatureMonitor {
+    node: usize,
+    component: Option<usize>,
+    max_temperature: f64,
+}
+
+/// Watches device temperatures and powers during an electro-thermal transient, where thermal
+/// networks are circuits whose node voltages are temperatures, for the device running away.
+///
+/// [`ThermalWatchdog::check`] should be called after every timestep, or the simulation run with
+/// [`ThermalWatchdog::run`] to halt on the first runaway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalWatchdog {
+    temperatures: Vec<TemperatureMonitor>,
+    powers: Vec<(usize, f64)>,
+    min_rate: f64,
+    acceleration_steps: usize,
+    events: Vec<RunawayEvent>,
+
+    // Per temperature monitor: the temperature after the last step, the rate it rose at over
+    // it and how many steps in a row the rate has grown.
+    history: Vec<(f64, f64, usize)>,
+    // Power of every component when the growing rate was first seen, per temperature monitor.
+    baseline_powers: Vec<Vec<f64>>,
+    time: f64,
+}
+
+impl ThermalWatchdog {
+    /// Creates a watchdog flagging temperatures rising at least 1 degree per second faster for
+    /// 20 steps in a row.
+    pub fn new() -> Self {
+        Self {
+            temperatures: Vec::new(),
+            powers: Vec::new(),
+            min_rate: 1.0,
+            acceleration_steps: 20,
+            events: Vec::new(),
+            history: Vec::new(),
+            baseline_powers: Vec::new(),
+            time: 0.0,
+        }
+    }
+
+    /// Monitors the temperature of a thermal node against a limit (e.g. the maximum junction
+    /// temperature) and for accelerating rises. The component heating the node, if given, is
+    /// the one held responsible for a runaway.
+    pub fn with_temperature(
+        mut self,
+        node: usize,
+        component: Option<usize>,
+        max_temperature: f64,
+    ) -> Self {
+        self.temperatures.push(TemperatureMonitor {
+            node,
+            component,
+            max_temperature,
+        });
+        self.history.push((f64::NAN, f64::NAN, 0));
+        self.baseline_powers.push(Vec::new());
+        self
+    }
+
+    /// Monitors the power of a component against a limit.
+    ///
+    /// Components with monitored powers are the devices a runaway of a temperature monitored
+    /// without its component is blamed on, as thermal networks (whose capacitors heat up with
+    /// the device) would otherwise be candidates too.
+    pub fn with_power(mut self, component: usize, max_power: f64) -> Self {
+        self.powers.push((component, max_power));
+        self
+    }
+
+    /// Sets how fast, in degrees per second, and for how many steps in a row a temperature must
+    /// rise faster and faster to be flagged.
+    pub fn with_acceleration(mut self, min_rate: f64, steps: usize) -> Self {
+        self.min_rate = min_rate;
+        self.acceleration_steps = steps.max(1);
+        self
+    }
+
+    /// Gets every runaway flagged so far.
+    pub fn get_events(&self) -> &Vec<RunawayEvent> {
+        &self.events
+    }
+
+    /// Checks the state of the solver after a timestep, returning the first runaway found.
+    ///
+    /// A monitor that flagged a runaway is reset, so it flags again only if it runs away anew.
+    pub fn check(&mut self, solver: &BESolver) -> Option<RunawayEvent> {
+        let time = solver.get_time();
+        let dt = time - self.time;
+        self.time = time;
+
+        let netlist = solver.get_netlist();
+        let components = netlist.get_components();
+        let powers: Vec<f64> = components.iter().map(|c| c.get_power()).collect();
+
+        let mut found = Vec::new();
+
+        for (k, monitor) in self.temperatures.iter().enumerate() {
+            let temperature = solver.get_node_voltage(monitor.node);
+            let (previous, previous_rate, steps) = self.history[k];
+            let rate = (temperature - previous) / dt;
+
+            let steps = if dt > 0.0 && rate >= self.min_rate && rate > previous_rate {
+                steps + 1
+            } else {
+                0
+            };
+            if steps == 1 {
+                self.baseline_powers[k] = powers.clone();
+            }
+            self.history[k] = (temperature, rate, steps);
+
+            let cause = if temperature > monitor.max_temperature {
+                RunawayCause::TemperatureLimit(monitor.max_temperature)
+            } else if steps >= self.acceleration_steps {
+                RunawayCause::Acceleration
+            } else {
+                continue;
+            };
+
+            let component = monitor.component.unwrap_or_else(|| {
+                let baseline = &self.baseline_powers[k];
+                let candidates: Vec<usize> = if self.powers.is_empty() {
+                    (0..powers.len()).collect()
+                } else {
+                    self.powers.iter().map(|&(c, _)| c).collect()
+                };
+                candidates
+                    .into_iter()
+                    .max_by(|&a, &b| {
+                        let rise = |i: usize| powers[i] - baseline.get(i).copied().unwrap_or(0.0);
+                        rise(a).total_cmp(&rise(b))
+                    })
+                    .unwrap_or(0)
+            });
+            self.history[k].2 = 0;
+
+            found.push(RunawayEvent {
+                time,
+                cause,
+                node: Some(monitor.node),
+                value: temperature,
+                component,
+                name: netlist.get_component_name(component),
+            });
+        }
+
+        for &(component, max_power) in &self.powers {
+            if powers[component].abs() > max_power {
+                found.push(RunawayEvent {
+                    time,
+                    cause: RunawayCause::PowerLimit(max_power),
+                    node: None,
+                    value: powers[component],
+                    component,
+                    name: netlist.get_component_name(component),
+                });
+            }
+        }
+
+        let first = found.first().cloned();
+        self.events.extend(found);
+        first
+    }
+
+    /// Solves timesteps of dt until the duration has passed, checking each one, and halts on
+    /// the first runaway, returning it, or on the first timestep that can't be solved.
+    pub fn run(
+        &mut self,
+        solver: &mut BESolver,
+        dt: f64,
+        duration: f64,
+    ) -> Result<Option<RunawayEvent>, SolverError> {
+        SolverError::check_timestep(dt)?;
+        let end = solver.get_time() + duration;
+        while solver.get_time() < end - dt / 2.0 {
+            solver.solve(dt)?;
+            if let Some(event) = self.check(solver) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for ThermalWatchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{
+        Capacitor, ControlledSource, CurrentSource, Netlist, Resistor, VoltageSource,
+    };
+
+    /// A device on a 10V supply drawing 100mA plus `coefficient` amps per degree of its
+    /// temperature rise, on node 2, through a 10K/W, 10mJ/K thermal network. The loop gain is
+    /// 10V * coefficient * 10K/W.
+    fn device(coefficient: f64) -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 100.0))
+            .add_component(ControlledSource::vccs(1, 0, 2, 0, coefficient))
+            .add_component(Resistor::new(2, 0, 10.0))
+            .add_component(Capacitor::new(2, 0, 10e-3, 0.0))
+            .add_component(CurrentSource::new(2, 0, 1.0))
+            .add_component(ControlledSource::vccs(2, 0, 2, 0, 10.0 * coefficient))
+            .set_component_name(2, "Q1");
+        netlist
+    }
+
+    #[test]
+    fn test_stable_device() {
+        // A loop gain of 0.5 settles at twice the 10 degree open loop rise.
+        let mut netlist = device(5e-3);
+        let mut solver = BESolver::new(&mut netlist);
+        let mut watchdog = ThermalWatchdog::new().with_temperature(2, None, 150.0);
+        assert_eq!(watchdog.run(&mut solver, 1e-3, 2.0), Ok(None));
+        assert!((solver.get_node_voltage(2) - 20.0).abs() < 0.5);
+        assert!(watchdog.get_events().is_empty());
+    }
+
+    #[test]
+    fn test_runaway_device() {
+        // A loop gain of 2 runs away, caught by its acceleration long before the limit.
+        let mut netlist = device(20e-3);
+        let mut solver = BESolver::new(&mut netlist);
+        let mut watchdog = ThermalWatchdog::new()
+            .with_temperature(2, None, 150.0)
+            .with_power(1, 2.0)
+            .with_power(2, 50.0);
+        let event = watchdog.run(&mut solver, 1e-3, 2.0).unwrap().unwrap();
+
+        assert_eq!(event.cause, RunawayCause::Acceleration);
+        assert_eq!(event.node, Some(2));
+        assert_eq!(event.name, "Q1");
+        assert!(event.value < 150.0);
+        assert!(event.time < 0.5);
+        assert_eq!(watchdog.get_events().len(), 1);
+
+        // Without the acceleration check it trips the limit instead, and a power limit names
+        // the device.
+        let mut netlist = device(20e-3);
+        let mut solver = BESolver::new(&mut netlist);
+        let mut watchdog = ThermalWatchdog::new()
+            .with_temperature(2, Some(2), 150.0)
+            .with_acceleration(f64::INFINITY, 1);
+        let event = watchdog.run(&mut solver, 1e-3, 2.0).unwrap().unwrap();
+        assert_eq!(event.cause, RunawayCause::TemperatureLimit(150.0));
+        assert_eq!(event.component, 2);
+
+        let mut netlist = device(20e-3);
+        let mut solver = BESolver::new(&mut netlist);
+        let mut watchdog = ThermalWatchdog::new().with_power(2, 5.0);
+        let event = watchdog.run(&mut solver, 1e-3, 2.0).unwrap().unwrap();
+        assert_eq!(event.cause, RunawayCause::PowerLimit(5.0));
+        assert!(event.value > 5.0);
+    }
+}
@@ -0,0 +1,453 @@
+use nalgebra::DMatrix;
+
+use crate::be_solver::{assemble, stampable::Discretization, update_components};
+use crate::components::{Component, Netlist};
+use crate::results::Probe;
+use crate::{BESolver, ConvergenceReport, IntegrationMethod, SolverOptions, SystemLayout};
+
+/// Relative size of the perturbations differentiating the residual of a timestep.
+const PERTURBATION: f64 = 1e-6;
+
+/// A scalar measure of a waveform, the quantity a [`TransientSensitivity`] differentiates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaveformMetric {
+    /// The value of the probe at the stop time.
+    Final(Probe),
+    /// The integral of the probe over the run.
+    Integral(Probe),
+    /// The mean of the probe over the run.
+    Mean(Probe),
+    /// The integral of the square of the probe over the run, e.g. the energy of an error signal.
+    IntegralSquared(Probe),
+}
+
+impl WaveformMetric {
+    pub fn get_probe(&self) -> &Probe {
+        match self {
+            Self::Final(probe)
+            | Self::Integral(probe)
+            | Self::Mean(probe)
+            | Self::IntegralSquared(probe) => probe,
+        }
+    }
+
+    /// Gets the contribution of a timestep of dt ending on the probed value to the metric, and
+    /// its derivative with respect to the probed value.
+    fn contribution(&self, value: f64, dt: f64, last: bool, duration: f64) -> (f64, f64) {
+        match self {
+            Self::Final(_) if last => (value, 1.0),
+            Self::Final(_) => (0.0, 0.0),
+            Self::Integral(_) => (value * dt, dt),
+            Self::Mean(_) => (value * dt / duration, dt / duration),
+            Self::IntegralSquared(_) => (value * value * dt, 2.0 * value * dt),
+        }
+    }
+}
+
+/// The derivatives of a [`WaveformMetric`] with respect to the parameters of a circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityResult {
+    value: f64,
+    parameters: Vec<(usize, f64)>,
+    sensitivities: Vec<f64>,
+}
+
+impl SensitivityResult {
+    /// Gets the value of the metric.
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    /// Gets the index of each component whose value was differentiated against, along with the
+    /// derivative of the metric with respect to that value.
+    pub fn get_sensitivities(&self) -> Vec<(usize, f64)> {
+        self.parameters
+            .iter()
+            .zip(&self.sensitivities)
+            .map(|(&(component, _), &sensitivity)| (component, sensitivity))
+            .collect()
+    }
+
+    /// Gets the derivative of the metric with respect to the value of a component, `None` if it
+    /// wasn't differentiated against.
+    pub fn get_sensitivity(&self, component: usize) -> Option<f64> {
+        self.parameters
+            .iter()
+            .position(|&(c, _)| c == component)
+            .map(|k| self.sensitivities[k])
+    }
+
+    /// Gets the relative change of the metric per relative change of the value of a component,
+    /// (dm/m)/(dp/p).
+    pub fn get_normalized_sensitivity(&self, component: usize) -> Option<f64> {
+        let k = self.parameters.iter().position(|&(c, _)| c == component)?;
+        Some(self.sensitivities[k] * self.parameters[k].1 / self.value)
+    }
+}
+
+/// A solved timestep (or operating point), as needed to differentiate its residual.
+struct Stage {
+    /// The components before the timestep.
+    components: Vec<Component>,
+    discretization: Discretization,
+    /// The solution of the timestep.
+    x: DMatrix<f64>,
+    /// The probed value at the end of the timestep.
+    value: f64,
+}
+
+/// Computes the derivatives of a [`WaveformMetric`] of a transient with respect to the values of
+/// every component in one forward and one backward pass, rather than a transient per parameter.
+///
+/// The forward pass is a backward Euler transient (see [`BESolver`]) keeping the solution of
+/// every timestep. The backward pass then integrates the adjoint of the discretized circuit
+/// equations from the stop time back to the start, each timestep costing one solve of the
+/// transposed Jacobian, and accumulates the sensitivity to every parameter from the derivative
+/// of the residual of each timestep with respect to it.
+///
+/// Inductors are given branch currents so every timestep depends on the ones before it only
+/// through the solution of the last one. State that components carry between timesteps other
+/// than through the circuit's variables, such as the position of a relay, is held on its
+/// forward trajectory. The timesteps are fixed by the forward pass, so the sensitivities are
+/// those of the discretized circuit, breakpoints included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientSensitivity {
+    stop_time: f64,
+    dt: f64,
+    metric: WaveformMetric,
+    options: SolverOptions,
+    operating_point: bool,
+    parameters: Option<Vec<usize>>,
+}
+
+impl TransientSensitivity {
+    /// Creates an analysis of the metric of a transient up to the stop time with timesteps of
+    /// dt.
+    pub fn new(stop_time: f64, dt: f64, metric: WaveformMetric) -> Self {
+        Self {
+            stop_time,
+            dt,
+            metric,
+            options: SolverOptions::default(),
+            operating_point: false,
+            parameters: None,
+        }
+    }
+
+    /// Sets the options of the solver. The integration method is always backward Euler, and
+    /// timesteps are neither refined nor sources ramped.
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Starts the transient from the DC operating point, which the sensitivities then account
+    /// for as well.
+    pub fn with_operating_point(mut self) -> Self {
+        self.operating_point = true;
+        self
+    }
+
+    /// Differentiates only against the values of the components at the given indices, rather
+    /// than every component with a primary value (see [`Component::get_value`]).
+    pub fn with_parameters(mut self, components: &[usize]) -> Self {
+        self.parameters = Some(components.to_vec());
+        self
+    }
+
+    pub fn get_metric(&self) -> &WaveformMetric {
+        &self.metric
+    }
+
+    /// Runs the analysis on a copy of the netlist, returning the report of the first timestep
+    /// of the forward pass that couldn't be solved.
+    pub fn run(&self, netlist: &Netlist) -> Result<SensitivityResult, Box<ConvergenceReport>> {
+        let mut netlist = netlist.clone();
+        for component in netlist.get_components_mut() {
+            if let Component::Inductor(inductor) = component {
+                *inductor = inductor.clone().with_branch_current();
+            }
+        }
+        let parameters: Vec<(usize, f64)> = match &self.parameters {
+            Some(components) => components
+                .iter()
+                .filter_map(|&c| Some((c, netlist.get_components()[c].get_value()?)))
+                .collect(),
+            None => netlist
+                .get_components()
+                .iter()
+                .enumerate()
+                .filter_map(|(c, component)| Some((c, component.get_value()?)))
+                .collect(),
+        };
+
+        let (stages, layout) = self.forward(&mut netlist)?;
+        let mut scratch = netlist.clone();
+        let sensitivities = self.backward(&stages, &layout, &parameters, &mut scratch);
+
+        let value = stages
+            .iter()
+            .enumerate()
+            .map(|(k, stage)| self.contribution(&stages, k, stage.value).0)
+            .sum();
+        Ok(SensitivityResult {
+            value,
+            parameters,
+            sensitivities,
+        })
+    }
+
+    /// Runs the transient, keeping every timestep.
+    fn forward(
+        &self,
+        netlist: &mut Netlist,
+    ) -> Result<(Vec<Stage>, SystemLayout), Box<ConvergenceReport>> {
+        let options = SolverOptions {
+            integration: IntegrationMethod::BackwardEuler,
+            refinement: None,
+            source_ramp: None,
+            ..self.options
+        };
+        let probe = self.metric.get_probe();
+        let mut solver = BESolver::new(netlist).with_options(options);
+        let mut stages = Vec::new();
+        let mut record = |solver: &BESolver, components, discretization| {
+            stages.push(Stage {
+                components,
+                discretization,
+                x: solver.get_solution().unwrap().clone(),
+                value: probe.evaluate(solver.get_netlist(), solver.get_node_voltages()),
+            });
+        };
+
+        if self.operating_point {
+            let components = solver.get_netlist().get_components().clone();
+            solver.try_solve_dc()?;
+            record(&solver, components, Discretization::Dc);
+        }
+
+        let end = self.stop_time;
+        while solver.get_time() < end * (1.0 - 1e-12) {
+            // Each timestep ends on or before the next breakpoint, so it is solved in one step.
+            let mut dt = self.dt.min(end - solver.get_time());
+            if let Some(breakpoint) = solver.next_breakpoint() {
+                dt = dt.min(breakpoint - solver.get_time());
+            }
+            let components = solver.get_netlist().get_components().clone();
+            solver.try_solve(dt)?;
+            record(
+                &solver,
+                components,
+                Discretization::Timestep(dt, IntegrationMethod::BackwardEuler),
+            );
+        }
+
+        let layout = solver.get_layout().clone();
+        Ok((stages, layout))
+    }
+
+    /// Gets the contribution of the kth stage to the metric and its derivative with respect to
+    /// the probed value.
+    fn contribution(&self, stages: &[Stage], k: usize, value: f64) -> (f64, f64) {
+        let dt = stages[k].discretization.dt();
+        self.metric
+            .contribution(value, dt, k + 1 == stages.len(), self.stop_time)
+    }
+
+    /// Integrates the adjoint from the last stage back to the first, returning the derivative of
+    /// the metric with respect to each parameter.
+    fn backward(
+        &self,
+        stages: &[Stage],
+        layout: &SystemLayout,
+        parameters: &[(usize, f64)],
+        scratch: &mut Netlist,
+    ) -> Vec<f64> {
+        let gmin = self.options.gmin;
+        let probe = self.metric.get_probe();
+        let dimension = layout.get_dimension();
+
+        // The residual A(x)x - b(x) of a stage solved from the given components.
+        let residual = |scratch: &Netlist, stage: &Stage| {
+            let (a, b) = assemble(scratch, layout, &stage.x, stage.discretization, gmin);
+            a * &stage.x - b
+        };
+        // The probed value and the residual of the next stage after updating the components
+        // from the stage's solution x.
+        let advance =
+            |scratch: &mut Netlist, k: usize, components: &[Component], x: &DMatrix<f64>| {
+                *scratch.get_components_mut() = components.to_vec();
+                let node_voltages = update_components(scratch, layout, x, stages[k].discretization);
+                let value = probe.evaluate(scratch, &node_voltages);
+                let next = stages.get(k + 1).map(|next| residual(scratch, next));
+                (value, next)
+            };
+
+        let mut sensitivities = vec![0.0; parameters.len()];
+        if parameters.is_empty() {
+            return sensitivities;
+        }
+        let mut adjoint: Option<DMatrix<f64>> = None;
+        for (k, stage) in stages.iter().enumerate().rev() {
+            let (_, weight) = self.contribution(stages, k, stage.value);
+
+            // The derivative of the metric with respect to the solution of this stage, through
+            // the probed value and the stages after it.
+            let mut gradient = DMatrix::zeros(dimension, 1);
+            if weight != 0.0 || adjoint.is_some() {
+                for row in 0..dimension {
+                    let h = PERTURBATION * stage.x[row].abs().max(1e-3);
+                    let mut x = stage.x.clone();
+                    x[row] += h;
+                    let (value_up, next_up) = advance(scratch, k, &stage.components, &x);
+                    x[row] -= 2.0 * h;
+                    let (value_down, next_down) = advance(scratch, k, &stage.components, &x);
+
+                    gradient[row] = weight * (value_up - value_down) / (2.0 * h);
+                    if let (Some(lambda), Some(up), Some(down)) = (&adjoint, next_up, next_down) {
+                        gradient[row] += lambda.dot(&(up - down)) / (2.0 * h);
+                    }
+                }
+            }
+
+            // The Jacobian of a solved stage is the matrix of its last Newton-Raphson iteration.
+            *scratch.get_components_mut() = stage.components.clone();
+            let (a, _) = assemble(scratch, layout, &stage.x, stage.discretization, gmin);
+            let lambda = a
+                .transpose()
+                .lu()
+                .solve(&-gradient)
+                .expect("the Jacobian of a solved timestep is regular");
+
+            for (&(component, value), sensitivity) in parameters.iter().zip(&mut sensitivities) {
+                let h = PERTURBATION * value.abs().max(f64::MIN_POSITIVE);
+                let mut perturbed = |value: f64| {
+                    let mut components = stage.components.clone();
+                    components[component].set_value(value);
+                    *scratch.get_components_mut() = components.clone();
+                    let residual = residual(scratch, stage);
+                    let (probed, _) = advance(scratch, k, &components, &stage.x);
+                    (residual, probed)
+                };
+                let (residual_up, value_up) = perturbed(value + h);
+                let (residual_down, value_down) = perturbed(value - h);
+
+                *sensitivity += (lambda.dot(&(residual_up - residual_down))
+                    + weight * (value_up - value_down))
+                    / (2.0 * h);
+            }
+
+            adjoint = Some(lambda);
+        }
+
+        sensitivities
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Capacitor, Diode, DiodeModel, Inductor, Resistor, VoltageSource};
+
+    /// Simulates the circuit with the value of a component changed and returns the metric.
+    fn simulate(netlist: &Netlist, change: Option<(usize, f64)>, metric: &WaveformMetric) -> f64 {
+        let mut netlist = netlist.clone();
+        if let Some((component, value)) = change {
+            netlist.get_component_mut(component).set_value(value);
+        }
+        TransientSensitivity::new(2e-3, 2e-5, metric.clone())
+            .with_options(SolverOptions {
+                relative_tolerance: 1e-12,
+                ..SolverOptions::default()
+            })
+            .with_parameters(&[])
+            .run(&netlist)
+            .unwrap()
+            .get_value()
+    }
+
+    #[test]
+    fn test_adjoint_matches_finite_differences() {
+        // A diode clamping an RC lowpass, with an RL branch, driven by a 2V step.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 2.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-12,
+                    emission_coefficient: 1.0,
+                },
+            ))
+            .add_component(Resistor::new(2, 3, 100.0))
+            .add_component(Inductor::new(3, 0, 10e-3, 0.0));
+
+        for metric in [
+            WaveformMetric::Final(Probe::NodeVoltage(2)),
+            WaveformMetric::IntegralSquared(Probe::ComponentCurrent(5)),
+        ] {
+            let result = TransientSensitivity::new(2e-3, 2e-5, metric.clone())
+                .with_options(SolverOptions {
+                    relative_tolerance: 1e-12,
+                    ..SolverOptions::default()
+                })
+                .run(&netlist)
+                .unwrap();
+            assert_relative_eq!(
+                result.get_value(),
+                simulate(&netlist, None, &metric),
+                max_relative = 1e-9
+            );
+
+            let components: Vec<usize> = result.get_sensitivities().iter().map(|s| s.0).collect();
+            assert_eq!(components, vec![0, 1, 2, 4, 5]);
+            for (component, sensitivity) in result.get_sensitivities() {
+                let value = netlist.get_components()[component].get_value().unwrap();
+                let h = 1e-4 * value;
+                let expected = (simulate(&netlist, Some((component, value + h)), &metric)
+                    - simulate(&netlist, Some((component, value - h)), &metric))
+                    / (2.0 * h);
+                assert_relative_eq!(sensitivity, expected, max_relative = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_operating_point_sensitivity() {
+        // From the operating point a divider stays put, so the final voltage is v*R2/(R1 + R2).
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 3.0))
+            .add_component(Resistor::new(1, 2, 2e3))
+            .add_component(Resistor::new(2, 0, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let result =
+            TransientSensitivity::new(1e-3, 1e-4, WaveformMetric::Final(Probe::NodeVoltage(2)))
+                .with_operating_point()
+                .run(&netlist)
+                .unwrap();
+        assert_relative_eq!(result.get_value(), 1.0, max_relative = 1e-9);
+        assert_relative_eq!(
+            result.get_sensitivity(0).unwrap(),
+            1.0 / 3.0,
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(
+            result.get_sensitivity(1).unwrap(),
+            -3.0 * 1e3 / 9e6,
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(
+            result.get_normalized_sensitivity(2).unwrap(),
+            2.0 / 3.0,
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(result.get_sensitivity(3).unwrap(), 0.0, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::components::Netlist;
+use crate::faults::{FaultError, FaultSchedule};
+use crate::results::{Probe, StoragePolicy, StoredProbe, TransientResult};
+use crate::{AdaptiveTimestep, BESolver, SolverError, SolverOptions};
+
+/// How a [`TransientAnalysis`] chooses its timesteps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timestep {
+    /// Timesteps of the given length, cut short to land on breakpoints (see
+    /// [`BESolver::solve`]).
+    Fixed(f64),
+    /// Timesteps chosen from the local truncation error (see [`BESolver::solve_adaptive`]).
+    Adaptive(AdaptiveTimestep),
+}
+
+/// Why a [`TransientAnalysis`] run failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransientError {
+    /// A fault of the schedule can't be injected into the netlist.
+    Fault(FaultError),
+    /// The fixed timestep is invalid, or a timestep couldn't be solved.
+    Solver(SolverError),
+    /// The stop time is negative or not finite.
+    InvalidStopTime { stop_time: f64 },
+}
+
+/// A signal to record, named when the analysis is run if it wasn't given a name.
+#[derive(Debug, Clone, PartialEq)]
+enum Recording {
+    Named(String, StoredProbe),
+    NodeVoltage(usize),
+    ComponentCurrent(usize),
+}
+
+/// Simulates a circuit from the start to the stop time, recording the requested signals after
+/// every timestep into a [`TransientResult`].
+///
+/// The simulation starts from the initial conditions of the components, or from the DC
+/// operating point with [`TransientAnalysis::with_operating_point`], in which case the
+/// operating point is recorded at time 0 as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientAnalysis {
+    stop_time: f64,
+    timestep: Timestep,
+    options: SolverOptions,
+    operating_point: bool,
+    storage: StoragePolicy,
+    recordings: Vec<Recording>,
+}
+
+impl TransientAnalysis {
+    /// Creates an analysis up to the stop time with timesteps of dt.
+    pub fn new(stop_time: f64, dt: f64) -> Self {
+        Self {
+            stop_time,
+            timestep: Timestep::Fixed(dt),
+            options: SolverOptions::default(),
+            operating_point: false,
+            storage: StoragePolicy::default(),
+            recordings: Vec::new(),
+        }
+    }
+
+    /// Creates an analysis up to the stop time with adaptive timesteps.
+    pub fn adaptive(stop_time: f64, adaptive: AdaptiveTimestep) -> Self {
+        Self {
+            timestep: Timestep::Adaptive(adaptive),
+            ..Self::new(stop_time, 0.0)
+        }
+    }
+
+    /// Sets the options of the solver. The adaptive timestep configuration of an adaptive
+    /// analysis takes the place of [`SolverOptions::adaptive`].
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Starts the simulation from the DC operating point (see [`BESolver::solve_dc`]).
+    pub fn with_operating_point(mut self) -> Self {
+        self.operating_point = true;
+        self
+    }
+
+    /// Sets the storage policy of the result, see [`TransientResult::with_storage`].
+    pub fn with_storage(mut self, storage: StoragePolicy) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Records a named probe.
+    pub fn with_probe(mut self, name: impl Into<String>, probe: impl Into<StoredProbe>) -> Self {
+        self.recordings
+            .push(Recording::Named(name.into(), probe.into()));
+        self
+    }
+
+    /// Records the voltage of a node, named `v(node)` after the node's name (see
+    /// [`Netlist::get_node_name`]).
+    pub fn with_node_voltage(mut self, node: usize) -> Self {
+        self.recordings.push(Recording::NodeVoltage(node));
+        self
+    }
+
+    /// Records the current through a component, named `i(component)` after the component's
+    /// name (see [`Netlist::get_component_name`]).
+    pub fn with_component_current(mut self, component: usize) -> Self {
+        self.recordings.push(Recording::ComponentCurrent(component));
+        self
+    }
+
+    pub fn get_stop_time(&self) -> f64 {
+        self.stop_time
+    }
+
+    pub fn get_timestep(&self) -> Timestep {
+        self.timestep
+    }
+
+    /// Runs the simulation on the netlist, leaving it in its state at the stop time, and
+    /// returns the recorded signals or why the first timestep that couldn't be solved failed.
+    /// Without a fault schedule the error is never a [`TransientError::Fault`].
+    pub fn run(&self, netlist: &mut Netlist) -> Result<TransientResult, TransientError> {
+        self.run_with_faults(netlist, &mut FaultSchedule::new())
+    }
+
+    /// Runs the simulation like [`TransientAnalysis::run`], injecting the faults of the schedule
+    /// as their triggers fire and recording their activations in the result (see
+    /// [`TransientResult::get_fault_activations`]).
+    ///
+    /// Faults are injected before the timestep starting at or after their trigger, and
+    /// timesteps end on the times of the timed faults, so those are injected on time. The
+    /// schedule is checked against the netlist, like the stop time and timestep, before the
+    /// simulation starts.
+    pub fn run_with_faults(
+        &self,
+        netlist: &mut Netlist,
+        faults: &mut FaultSchedule,
+    ) -> Result<TransientResult, TransientError> {
+        if !(self.stop_time >= 0.0 && self.stop_time.is_finite()) {
+            return Err(TransientError::InvalidStopTime {
+                stop_time: self.stop_time,
+            });
+        }
+        if let Timestep::Fixed(dt) = self.timestep {
+            SolverError::check_timestep(dt).map_err(TransientError::Solver)?;
+        }
+        faults.check(netlist).map_err(TransientError::Fault)?;
+
+        let mut result = TransientResult::new().with_storage(self.storage);
+        for recording in &self.recordings {
+            match recording {
+                Recording::Named(name, probe) => result.add_probe(name.clone(), probe.clone()),
+                Recording::NodeVoltage(node) => result.add_probe(
+                    format!("v({})", netlist.get_node_name(*node)),
+                    Probe::NodeVoltage(*node),
+                ),
+                Recording::ComponentCurrent(component) => result.add_probe(
+                    format!("i({})", netlist.get_component_name(*component)),
+                    Probe::ComponentCurrent(*component),
+                ),
+            };
+        }
+
+        let mut options = self.options;
+        if let Timestep::Adaptive(adaptive) = self.timestep {
+            options.adaptive = adaptive;
+        }
+        let mut solver = BESolver::new(netlist).with_options(options);
+        let mut inject = |solver: &mut BESolver, result: &mut TransientResult| {
+            // Timesteps ending on a fault may fall short of it by rounding.
+            let time = solver.get_time() + self.stop_time * 1e-12;
+            let injected = faults.get_activations().len();
+            faults
+                .apply(solver.get_netlist_mut(), time)
+                .map_err(TransientError::Fault)?;
+            for activation in &faults.get_activations()[injected..] {
+                result.record_fault(activation.clone());
+            }
+            Ok(faults.next_time(time))
+        };
+
+        if self.operating_point {
+            inject(&mut solver, &mut result)?;
+            solver
+                .try_solve_dc()
+                .map_err(|report| TransientError::Solver(report.into()))?;
+            result.record(&solver);
+        }
+
+        let end = self.stop_time;
+        while solver.get_time() < end * (1.0 - 1e-12) {
+            let next_fault = inject(&mut solver, &mut result)?;
+            let remaining = next_fault.unwrap_or(end).min(end) - solver.get_time();
+            match self.timestep {
+                Timestep::Fixed(dt) => solver.try_solve(dt.min(remaining)),
+                Timestep::Adaptive(_) => solver.try_solve_adaptive(remaining).map(|_| ()),
+            }
+            .map_err(|report| TransientError::Solver(report.into()))?;
+            result.record(&solver);
+        }
+
+        result.finish();
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Capacitor, Resistor, VoltageSource};
+    use crate::faults::{Fault, FaultKind};
+
+    #[test]
+    fn test_transient_analysis() {
+        // A 1ms RC lowpass charging to 1V.
+        let netlist = {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+            netlist
+        };
+
+        let fixed = TransientAnalysis::new(5e-3, 1e-6)
+            .with_node_voltage(2)
+            .with_component_current(1)
+            .with_probe("vr", Probe::DifferentialVoltage(1, 2));
+        let result = fixed.run(&mut netlist.clone()).unwrap();
+        assert_eq!(result.get_names(), &vec!["v(2)", "i(R1)", "vr"]);
+        assert_eq!(result.get_num_recorded(), 5000);
+        assert_relative_eq!(
+            *result.get_times().last().unwrap(),
+            5e-3,
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(
+            result.value_at(0, 1e-3).unwrap(),
+            1.0 - (-1.0f64).exp(),
+            max_relative = 1e-3
+        );
+
+        // Adaptive timesteps follow the same curve in far fewer steps.
+        let adaptive = TransientAnalysis::adaptive(5e-3, AdaptiveTimestep::new(1e-4, 1e-6))
+            .with_node_voltage(2);
+        let result = adaptive.run(&mut netlist.clone()).unwrap();
+        assert!(result.get_num_recorded() < 1000);
+        assert_relative_eq!(
+            result.value_at(0, 1e-3).unwrap(),
+            1.0 - (-1.0f64).exp(),
+            max_relative = 1e-2
+        );
+
+        // From the operating point the capacitor starts charged.
+        let result = TransientAnalysis::new(1e-3, 1e-5)
+            .with_operating_point()
+            .with_node_voltage(2)
+            .run(&mut netlist.clone())
+            .unwrap();
+        assert_eq!(result.get_times()[0], 0.0);
+        assert_relative_eq!(result.get_values(0)[0], 1.0, max_relative = 1e-9);
+        assert_relative_eq!(
+            *result.get_values(0).last().unwrap(),
+            1.0,
+            max_relative = 1e-9
+        );
+
+        // Timesteps and stop times that would never reach the end are rejected.
+        assert_eq!(
+            TransientAnalysis::new(1e-3, 0.0)
+                .run(&mut netlist.clone())
+                .err(),
+            Some(TransientError::Solver(SolverError::InvalidTimestep {
+                dt: 0.0
+            }))
+        );
+        assert!(matches!(
+            TransientAnalysis::new(1e-3, f64::NAN).run(&mut netlist.clone()),
+            Err(TransientError::Solver(SolverError::InvalidTimestep { .. }))
+        ));
+        assert_eq!(
+            TransientAnalysis::new(f64::INFINITY, 1e-6)
+                .run(&mut netlist.clone())
+                .err(),
+            Some(TransientError::InvalidStopTime {
+                stop_time: f64::INFINITY
+            })
+        );
+    }
+
+    #[test]
+    fn test_faulted_run() {
+        // A divider whose lower resistor fails open halfway through a timestep.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Resistor::new(2, 0, 1e3));
+        let analysis = TransientAnalysis::new(5e-3, 1e-3).with_node_voltage(2);
+
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("R2 open", 2.5e-3, FaultKind::Open(2)));
+        let result = analysis
+            .run_with_faults(&mut netlist.clone(), &mut faults)
+            .unwrap();
+        assert_eq!(result.get_fault_activations().len(), 1);
+        assert_relative_eq!(
+            result.get_fault_activations()[0].time,
+            2.5e-3,
+            max_relative = 1e-9
+        );
+        assert!(result.get_active_faults(2e-3).is_empty());
+        assert_eq!(result.get_active_faults(3e-3), vec!["R2 open"]);
+        assert_relative_eq!(
+            result.value_at(0, 2.5e-3).unwrap(),
+            5.0,
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(
+            result.value_at(0, 3.5e-3).unwrap(),
+            10.0,
+            max_relative = 1e-6
+        );
+
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("R9 open", 1e-3, FaultKind::Open(9)));
+        assert_eq!(
+            analysis.run_with_faults(&mut netlist, &mut faults).err(),
+            Some(TransientError::Fault(FaultError::UnknownComponent(9)))
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use crate::analysis::BatchResults;
+use crate::components::normal_cdf;
+use crate::reports::{Histogram, percentile};
+use crate::results::Unit;
+
+/// A named scalar measured from the result of each run, with the limits it must stay within
+/// for the run to pass.
+pub struct Measurement<T> {
+    name: String,
+    unit: Unit,
+    function: Box<dyn Fn(&T) -> f64 + Send + Sync>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl<T> Measurement<T> {
+    pub fn new(
+        name: impl Into<String>,
+        function: impl Fn(&T) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            unit: Unit::Dimensionless,
+            function: Box::new(function),
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn with_max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn with_limits(self, min: f64, max: f64) -> Self {
+        self.with_min(min).with_max(max)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_unit(&self) -> Unit {
+        self.unit
+    }
+
+    /// Measures the result of a run.
+    pub fn measure(&self, result: &T) -> f64 {
+        (self.function)(result)
+    }
+
+    /// Returns whether the value is within the limits. NaN never is.
+    pub fn passes(&self, value: f64) -> bool {
+        !value.is_nan()
+            && self.min.is_none_or(|min| value >= min)
+            && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Measures the results of a statistical run (see [`MonteCarlo`](crate::analysis::MonteCarlo))
+/// against pass/fail limits to find the fraction of circuits that would pass.
+pub struct YieldAnalysis<T> {
+    measurements: Vec<Measurement<T>>,
+    confidence: f64,
+}
+
+impl<T> YieldAnalysis<T> {
+    /// Creates an analysis without measurements, reporting 95% confidence intervals.
+    pub fn new() -> Self {
+        Self {
+            measurements: Vec::new(),
+            confidence: 0.95,
+        }
+    }
+
+    pub fn with_measurement(mut self, measurement: Measurement<T>) -> Self {
+        self.measurements.push(measurement);
+        self
+    }
+
+    /// Sets the confidence level of the reported yield intervals, e.g. 0.95.
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Measures every run. A run passes if every measurement is within its limits.
+    pub fn evaluate(&self, results: &BatchResults<T>) -> YieldReport {
+        let points = results.get_points();
+        let z = normal_quantile(0.5 + self.confidence / 2.0);
+
+        let measurements: Vec<MeasurementStatistics> = self
+            .measurements
+            .iter()
+            .map(|measurement| {
+                let values: Vec<f64> = points
+                    .iter()
+                    .map(|p| measurement.measure(&p.result))
+                    .collect();
+                let passes: Vec<bool> = values.iter().map(|&v| measurement.passes(v)).collect();
+                MeasurementStatistics {
+                    name: measurement.name.clone(),
+                    unit: measurement.unit,
+                    yield_estimate: YieldEstimate::new(
+                        passes.iter().filter(|&&p| p).count(),
+                        passes.len(),
+                        z,
+                    ),
+                    values,
+                    passes,
+                }
+            })
+            .collect();
+
+        let passes: Vec<bool> = (0..points.len())
+            .map(|run| measurements.iter().all(|m| m.passes[run]))
+            .collect();
+
+        YieldReport {
+            yield_estimate: YieldEstimate::new(
+                passes.iter().filter(|&&p| p).count(),
+                passes.len(),
+                z,
+            ),
+            measurements,
+            passes,
+        }
+    }
+}
+
+impl<T> Default for YieldAnalysis<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A yield measured from a number of runs, with its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YieldEstimate {
+    pub passed: usize,
+    pub runs: usize,
+    /// The fraction of runs that passed.
+    pub value: f64,
+    /// The Wilson score interval of the yield, which stays within 0 and 1 and is meaningful
+    /// even when every run passed.
+    pub low: f64,
+    pub high: f64,
+}
+
+impl YieldEstimate {
+    /// Estimates the yield from the runs, with an interval of z standard deviations.
+    fn new(passed: usize, runs: usize, z: f64) -> Self {
+        if runs == 0 {
+            return Self {
+                passed,
+                runs,
+                value: f64::NAN,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+
+        let n = runs as f64;
+        let p = passed as f64 / n;
+        let z2 = z * z;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half_width = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+
+        Self {
+            passed,
+            runs,
+            value: p,
+            low: (center - half_width).max(0.0),
+            high: (center + half_width).min(1.0),
+        }
+    }
+}
+
+/// The values of one measurement over every run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementStatistics {
+    pub name: String,
+    pub unit: Unit,
+    /// The value measured for each run.
+    pub values: Vec<f64>,
+    /// Whether each run was within the limits of this measurement.
+    pub passes: Vec<bool>,
+    /// The yield if this were the only measurement.
+    pub yield_estimate: YieldEstimate,
+}
+
+impl MeasurementStatistics {
+    pub fn mean(&self) -> f64 {
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    /// Gets the sample standard deviation.
+    pub fn standard_deviation(&self) -> f64 {
+        let mean = self.mean();
+        let sum: f64 = self.values.iter().map(|v| (v - mean).powi(2)).sum();
+        (sum / (self.values.len() as f64 - 1.0)).sqrt()
+    }
+
+    /// Gets the given percentile (0 to 100) of the measured values.
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        self::percentile(&self.values, percentile)
+    }
+
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        Histogram::new(&self.values, bins)
+    }
+}
+
+/// The outcome of a [`YieldAnalysis`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct YieldReport {
+    /// The fraction of runs that passed every measurement.
+    pub yield_estimate: YieldEstimate,
+    pub measurements: Vec<MeasurementStatistics>,
+    /// Whether each run passed every measurement.
+    pub passes: Vec<bool>,
+}
+
+impl YieldReport {
+    pub fn get_measurement(&self, name: &str) -> Option<&MeasurementStatistics> {
+        self.measurements.iter().find(|m| m.name == name)
+    }
+
+    /// Gets the indices of the runs that failed, to look at their parameters.
+    pub fn get_failures(&self) -> Vec<usize> {
+        (0..self.passes.len())
+            .filter(|&run| !self.passes[run])
+            .collect()
+    }
+}
+
+/// Finds the standard normal deviate below which the given fraction of the distribution lies.
+fn normal_quantile(probability: f64) -> f64 {
+    let (mut low, mut high) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let middle = 0.5 * (low + high);
+        if normal_cdf(middle) < probability {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    0.5 * (low + high)
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::{
+        BESolver,
+        analysis::MonteCarlo,
+        components::{Distribution, Netlist, Resistor, Tolerance, VoltageSource},
+    };
+
+    #[test]
+    fn test_divider_yield() {
+        // A 5V divider from 10V with 5% resistors, which must stay within 2%.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0))
+            .set_tolerance(1, Tolerance::new(Distribution::Uniform(0.05)))
+            .set_tolerance(2, Tolerance::new(Distribution::Uniform(0.05)));
+
+        let results = MonteCarlo::new(500)
+            .runner(&netlist)
+            .unwrap()
+            .run(&netlist, |netlist| {
+                let mut solver = BESolver::new(netlist);
+                solver.solve(1e-3).unwrap();
+                let r: Resistor = solver.get_netlist().get_components()[1]
+                    .clone()
+                    .try_into()
+                    .unwrap();
+                (solver.get_node_voltage(2), r.get_current())
+            })
+            .unwrap();
+
+        let report = YieldAnalysis::new()
+            .with_measurement(
+                Measurement::new("vout", |r: &(f64, f64)| r.0)
+                    .with_unit(Unit::Volt)
+                    .with_limits(4.9, 5.1),
+            )
+            .with_measurement(Measurement::new("current", |r: &(f64, f64)| r.1).with_max(1.0))
+            .evaluate(&results);
+
+        // The output moves by half the difference of the two deviations, which is triangular
+        // within 10%, so it stays within 2% for 1 - 0.6^2 = 64% of the runs.
+        let estimate = report.yield_estimate;
+        assert_eq!(estimate.runs, 500);
+        assert!(estimate.low < 0.64 && estimate.high > 0.64);
+        assert!(estimate.high - estimate.low < 0.1);
+        assert_eq!(report.get_failures().len(), 500 - estimate.passed);
+
+        let current = report.get_measurement("current").unwrap();
+        assert_eq!(current.yield_estimate.value, 1.0);
+        assert!(current.yield_estimate.low > 0.99);
+
+        let vout = report.get_measurement("vout").unwrap();
+        assert_eq!(vout.unit, Unit::Volt);
+        assert!((vout.mean() - 5.0).abs() < 0.01);
+        assert!(vout.percentile(0.0) >= 4.75 && vout.percentile(100.0) <= 5.25);
+        assert!(vout.percentile(5.0) < vout.percentile(50.0));
+        assert_eq!(vout.histogram(10).counts.iter().sum::<usize>(), 500);
+        assert!(report.get_measurement("power").is_none());
+    }
+
+    #[test]
+    fn test_wilson_interval() {
+        assert_relative_eq!(normal_quantile(0.975), 1.959964, epsilon = 1e-5);
+
+        let estimate = YieldEstimate::new(8, 10, 1.959964);
+        assert_relative_eq!(estimate.value, 0.8);
+        assert_relative_eq!(estimate.low, 0.49016, epsilon = 1e-4);
+        assert_relative_eq!(estimate.high, 0.94332, epsilon = 1e-4);
+
+        let estimate = YieldEstimate::new(0, 0, 1.96);
+        assert!(estimate.value.is_nan());
+    }
+}
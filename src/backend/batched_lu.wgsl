@@ -0,0 +1,68 @@
+// Solves a batch of dense systems A x = b, one per invocation, by Gaussian elimination with
+// partial pivoting. Each system is stored as its augmented matrix [A | b], row by row, and the
+// solution overwrites the last column. The status of a singular system is set to 1.
+
+struct Params {
+    dimension: u32,
+    count: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> systems: array<f64>;
+@group(0) @binding(2) var<storage, read_write> status: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let system = id.x;
+    if system >= params.count {
+        return;
+    }
+
+    let n = params.dimension;
+    let width = n + 1u;
+    let base = system * n * width;
+
+    for (var column = 0u; column < n; column++) {
+        var pivot = column;
+        var largest = abs(systems[base + column * width + column]);
+        for (var row = column + 1u; row < n; row++) {
+            let value = abs(systems[base + row * width + column]);
+            if value > largest {
+                largest = value;
+                pivot = row;
+            }
+        }
+        if largest == 0.0lf {
+            status[system] = 1u;
+            return;
+        }
+
+        if pivot != column {
+            for (var j = column; j < width; j++) {
+                let swapped = systems[base + column * width + j];
+                systems[base + column * width + j] = systems[base + pivot * width + j];
+                systems[base + pivot * width + j] = swapped;
+            }
+        }
+
+        let diagonal = systems[base + column * width + column];
+        for (var row = column + 1u; row < n; row++) {
+            let factor = systems[base + row * width + column] / diagonal;
+            if factor != 0.0lf {
+                for (var j = column; j < width; j++) {
+                    systems[base + row * width + j] -= factor * systems[base + column * width + j];
+                }
+            }
+        }
+    }
+
+    for (var i = n; i > 0u; i--) {
+        let row = i - 1u;
+        var sum = systems[base + row * width + n];
+        for (var j = row + 1u; j < n; j++) {
+            sum -= systems[base + row * width + j] * systems[base + j * width + n];
+        }
+        systems[base + row * width + n] = sum / systems[base + row * width + row];
+    }
+    status[system] = 0u;
+}
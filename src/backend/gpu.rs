@@ -0,0 +1,251 @@
+use std::sync::mpsc;
+
+use nalgebra::DMatrix;
+use wgpu::BufferUsages as Usage;
+
+use crate::backend::{CpuBackend, DenseBackend, System};
+
+/// Systems solved per invocation workgroup, as declared by the shader.
+const WORKGROUP_SIZE: usize = 64;
+
+/// Solves batches of dense systems on the GPU, one system per shader invocation, so a whole
+/// batch of equally sized systems is factorized by a single kernel launch.
+///
+/// Systems are solved in double precision by Gaussian elimination with partial pivoting, which
+/// needs a GPU supporting 64-bit floats in shaders (most desktop GPUs through Vulkan, Metal or
+/// DirectX 12). Batches are split into launches by dimension, and again when they exceed the
+/// buffer or dispatch limits of the device. Worth it for batches of hundreds of systems of a few
+/// dozen variables; small batches are faster on the [`CpuBackend`]. Launches whose results
+/// can't be read back, e.g. because the device was lost, are solved on the [`CpuBackend`]
+/// instead.
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    max_buffer_size: u64,
+    max_workgroups: u32,
+}
+
+impl GpuBackend {
+    /// Connects to the preferred GPU, returning None if there is none supporting 64-bit floats
+    /// in shaders.
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .ok()?;
+        if !adapter.features().contains(wgpu::Features::SHADER_F64) {
+            return None;
+        }
+
+        let limits = adapter.limits();
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("rice"),
+            required_features: wgpu::Features::SHADER_F64,
+            required_limits: limits.clone(),
+            ..Default::default()
+        }))
+        .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("batched_lu"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("batched_lu.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("batched_lu"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(Self {
+            device,
+            queue,
+            pipeline,
+            max_buffer_size: (limits.max_storage_buffer_binding_size as u64)
+                .min(limits.max_buffer_size),
+            max_workgroups: limits.max_compute_workgroups_per_dimension,
+        })
+    }
+
+    /// Solves systems all of dimension n with a single kernel launch, returning None if the
+    /// results can't be read back.
+    fn launch(&self, n: usize, systems: &[&System]) -> Option<Vec<Option<DMatrix<f64>>>> {
+        // Each system as its augmented matrix [A | b], row by row.
+        let width = n + 1;
+        let mut data = Vec::with_capacity(systems.len() * n * width * 8);
+        for (a, b) in systems {
+            for row in 0..n {
+                for column in 0..n {
+                    data.extend_from_slice(&a[(row, column)].to_le_bytes());
+                }
+                data.extend_from_slice(&b[row].to_le_bytes());
+            }
+        }
+
+        let params = [n as u32, systems.len() as u32, 0, 0];
+        let params: Vec<u8> = params.iter().flat_map(|p| p.to_le_bytes()).collect();
+        let status_size = (systems.len() * 4) as u64;
+
+        let buffer = |label, size, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let params_buffer = buffer("params", 16, Usage::UNIFORM | Usage::COPY_DST);
+        let systems_buffer = buffer(
+            "systems",
+            data.len() as u64,
+            Usage::STORAGE | Usage::COPY_DST | Usage::COPY_SRC,
+        );
+        let status_buffer = buffer("status", status_size, Usage::STORAGE | Usage::COPY_SRC);
+        let systems_readback = buffer(
+            "systems_readback",
+            data.len() as u64,
+            Usage::MAP_READ | Usage::COPY_DST,
+        );
+        let status_readback = buffer(
+            "status_readback",
+            status_size,
+            Usage::MAP_READ | Usage::COPY_DST,
+        );
+        self.queue.write_buffer(&params_buffer, 0, &params);
+        self.queue.write_buffer(&systems_buffer, 0, &data);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("batched_lu"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: systems_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: status_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(systems.len().div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&systems_buffer, 0, &systems_readback, 0, data.len() as u64);
+        encoder.copy_buffer_to_buffer(&status_buffer, 0, &status_readback, 0, status_size);
+        self.queue.submit([encoder.finish()]);
+
+        let solved = self.read(&systems_readback)?;
+        let status = self.read(&status_readback)?;
+
+        let solutions = (0..systems.len())
+            .map(|k| {
+                if status[4 * k..4 * k + 4] != [0; 4] {
+                    return None;
+                }
+                Some(DMatrix::from_fn(n, 1, |row, _| {
+                    let start = ((k * n + row) * width + n) * 8;
+                    f64::from_le_bytes(solved[start..start + 8].try_into().unwrap())
+                }))
+            })
+            .collect();
+        Some(solutions)
+    }
+
+    /// Waits for the GPU and reads back a buffer, returning None if the device was lost or
+    /// the buffer couldn't be mapped.
+    fn read(&self, buffer: &wgpu::Buffer) -> Option<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel();
+        buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+            // The receiver is only gone once the read was given up on.
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).ok()?;
+        receiver.recv().ok()?.ok()?;
+
+        let data = buffer.get_mapped_range(..).to_vec();
+        buffer.unmap();
+        Some(data)
+    }
+}
+
+impl DenseBackend for GpuBackend {
+    fn solve_batch(&self, systems: &[System]) -> Vec<Option<DMatrix<f64>>> {
+        let mut solutions = vec![None; systems.len()];
+
+        let mut dimensions: Vec<usize> = systems.iter().map(|(a, _)| a.nrows()).collect();
+        dimensions.sort_unstable();
+        dimensions.dedup();
+
+        for n in dimensions {
+            let indices: Vec<usize> = (0..systems.len())
+                .filter(|&k| systems[k].0.nrows() == n)
+                .collect();
+            if n == 0 {
+                for k in indices {
+                    solutions[k] = Some(DMatrix::zeros(0, 1));
+                }
+                continue;
+            }
+
+            let system_size = (n * (n + 1) * 8) as u64;
+            let per_launch = ((self.max_buffer_size / system_size) as usize)
+                .min(self.max_workgroups as usize * WORKGROUP_SIZE)
+                .max(1);
+            for chunk in indices.chunks(per_launch) {
+                let batch: Vec<&System> = chunk.iter().map(|&k| &systems[k]).collect();
+                let solved = self.launch(n, &batch).unwrap_or_else(|| {
+                    let batch: Vec<System> = batch.into_iter().cloned().collect();
+                    CpuBackend.solve_batch(&batch)
+                });
+                for (&k, solution) in chunk.iter().zip(solved) {
+                    solutions[k] = solution;
+                }
+            }
+        }
+
+        solutions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::backend::test::systems;
+
+    #[test]
+    fn test_gpu_backend() {
+        // Nothing to test against without a GPU supporting 64-bit floats.
+        let Some(backend) = GpuBackend::new() else {
+            return;
+        };
+
+        let mut dimensions = vec![12; 300];
+        dimensions.extend([1, 4, 30]);
+        let systems = systems(&dimensions);
+        let solutions = backend.solve_batch(&systems);
+
+        for ((a, b), x) in systems.iter().zip(&solutions).take(dimensions.len()) {
+            assert_relative_eq!((a * x.as_ref().unwrap() - b).amax(), 0.0, epsilon = 1e-10);
+        }
+        assert_eq!(solutions.last().unwrap(), &None);
+    }
+}
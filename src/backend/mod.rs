@@ -0,0 +1,73 @@
+//! Backends solving batches of dense linear systems, used to solve the Newton-Raphson
+//! iterations of many variants of a circuit at once (see
+//! [`LockstepBatch`](crate::analysis::LockstepBatch)).
+
+use nalgebra::DMatrix;
+
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuBackend;
+
+/// A system A x = b, with b a single column.
+pub type System = (DMatrix<f64>, DMatrix<f64>);
+
+/// Solves batches of dense linear systems.
+pub trait DenseBackend {
+    /// Solves every system of the batch, returning None for the singular ones. The systems
+    /// may have different dimensions.
+    fn solve_batch(&self, systems: &[System]) -> Vec<Option<DMatrix<f64>>>;
+}
+
+/// Solves each system of a batch in turn by LU decomposition on the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CpuBackend;
+
+impl DenseBackend for CpuBackend {
+    fn solve_batch(&self, systems: &[System]) -> Vec<Option<DMatrix<f64>>> {
+        systems
+            .iter()
+            .map(|(a, b)| a.clone().lu().solve(b))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::Rng;
+
+    /// Random diagonally dominant systems of the given dimensions, and a singular one.
+    pub(crate) fn systems(dimensions: &[usize]) -> Vec<System> {
+        let rng = Rng::new(5);
+        let mut systems: Vec<System> = dimensions
+            .iter()
+            .enumerate()
+            .map(|(k, &n)| {
+                let mut rng = rng.stream(k as u64);
+                let mut a = DMatrix::from_fn(n, n, |_, _| rng.uniform() - 0.5);
+                for i in 0..n {
+                    a[(i, i)] += n as f64;
+                }
+                let b = DMatrix::from_fn(n, 1, |_, _| rng.uniform());
+                (a, b)
+            })
+            .collect();
+        systems.push((DMatrix::zeros(3, 3), DMatrix::zeros(3, 1)));
+        systems
+    }
+
+    #[test]
+    fn test_cpu_backend() {
+        let systems = systems(&[1, 4, 12, 12]);
+        let solutions = CpuBackend.solve_batch(&systems);
+
+        assert_eq!(solutions.len(), 5);
+        for ((a, b), x) in systems.iter().zip(&solutions).take(4) {
+            assert_relative_eq!((a * x.as_ref().unwrap() - b).amax(), 0.0, epsilon = 1e-12);
+        }
+        assert_eq!(solutions[4], None);
+    }
+}
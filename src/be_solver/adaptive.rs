@@ -0,0 +1,98 @@
+use crate::{be_solver::stampable::Stampable, components::Component};
+
+/// Fraction of the timestep that would just meet the tolerance the next timestep is set to, so
+/// timesteps aren't rejected as soon as the error grows a little.
+const SAFETY: f64 = 0.9;
+
+/// Largest factor a timestep grows by over the previous one.
+const MAX_GROWTH: f64 = 2.0;
+
+/// Smallest factor a rejected timestep shrinks by.
+const MIN_SHRINK: f64 = 0.1;
+
+/// Configures the timesteps of [`BESolver::solve_adaptive`](crate::BESolver::solve_adaptive).
+///
+/// After every timestep the local truncation error of each capacitor and inductor is estimated
+/// from its history: the second derivative of its charge or flux linkage over the timestep and
+/// the one before it, times dt^2/2 (the error of backward Euler, which over-estimates the error
+/// of the second order methods, erring on the side of shorter timesteps). The error is converted
+/// into a voltage or current and compared to `relative_tolerance` times the capacitor voltage or
+/// inductor current plus `absolute_tolerance`. The timestep is rejected and shrunk if any
+/// component exceeds its tolerance, and the next one grows when all are well within theirs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveTimestep {
+    pub relative_tolerance: f64,
+    /// Tolerance on the error of the capacitor voltages (in V) and inductor currents (in A)
+    /// near zero.
+    pub absolute_tolerance: f64,
+    /// Timesteps aren't shrunk below this, the error notwithstanding.
+    pub min_step: f64,
+    pub max_step: f64,
+    /// The first timestep, before any history to estimate the error from.
+    pub initial_step: f64,
+}
+
+impl Default for AdaptiveTimestep {
+    fn default() -> Self {
+        Self::new(1e-3, 1e-6)
+    }
+}
+
+impl AdaptiveTimestep {
+    /// Creates a configuration with timesteps between 1fs and unlimited, the first of 1ns.
+    pub fn new(relative_tolerance: f64, absolute_tolerance: f64) -> Self {
+        Self {
+            relative_tolerance,
+            absolute_tolerance,
+            min_step: 1e-15,
+            max_step: f64::INFINITY,
+            initial_step: 1e-9,
+        }
+    }
+
+    pub fn with_step_range(mut self, min_step: f64, max_step: f64) -> Self {
+        self.min_step = min_step;
+        self.max_step = max_step;
+        self
+    }
+
+    pub fn with_initial_step(mut self, initial_step: f64) -> Self {
+        self.initial_step = initial_step;
+        self
+    }
+
+    /// Returns the largest ratio of a component's estimated local truncation error over a
+    /// timestep dt to its tolerance, between its state `before` and `after` the timestep. The
+    /// timestep is accurate enough if it is at most 1.
+    pub(crate) fn error_ratio(&self, before: &[Component], after: &[Component], dt: f64) -> f64 {
+        before
+            .iter()
+            .zip(after.iter())
+            .filter_map(|(b, a)| {
+                let old = b.integrated_quantity()?;
+                let new = a.integrated_quantity()?;
+                let (oldest, previous_dt) = old.previous?;
+
+                // The second derivative is the change of the slope over the two timesteps
+                // divided by the time between their midpoints.
+                let previous_slope = (old.value - oldest) / previous_dt;
+                let slope = (new.value - old.value) / dt;
+                let second_derivative = 2.0 * (slope - previous_slope) / (dt + previous_dt);
+
+                let error = dt * dt / 2.0 * second_derivative.abs() / new.scale.abs();
+                let state = b.state()?.abs().max(a.state()?.abs());
+                Some(error / (self.relative_tolerance * state + self.absolute_tolerance))
+            })
+            .filter(|ratio| !ratio.is_nan())
+            .fold(0.0, f64::max)
+    }
+
+    /// Computes the timestep following a timestep dt with the given error ratio (see
+    /// [`AdaptiveTimestep::error_ratio`]), or the timestep to retry it with if it was rejected.
+    /// The error of backward Euler grows with dt^2, so the timestep is scaled by the inverse of
+    /// the square root of the ratio.
+    pub(crate) fn next_step(&self, dt: f64, error_ratio: f64) -> f64 {
+        let factor = (SAFETY / error_ratio.sqrt()).clamp(MIN_SHRINK, MAX_GROWTH);
+        (dt * factor).clamp(self.min_step, self.max_step)
+    }
+}
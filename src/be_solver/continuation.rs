@@ -0,0 +1,100 @@
+/// Factor between consecutive gmin stepping stages below which [`GminStepping`] gives up.
+pub(crate) const MIN_GMIN_FACTOR: f64 = 1.01;
+
+/// Configures gmin stepping, the fallback when the Newton-Raphson iteration of a timestep or
+/// operating point fails.
+///
+/// The solve is retried with a conductance of `initial_gmin` across every nonlinear junction,
+/// which makes the circuit nearly linear so it converges easily, then with the conductance
+/// divided by `factor` at each stage, every stage starting from the solution of the one before,
+/// until it reaches [`SolverOptions::gmin`](crate::SolverOptions::gmin). A stage failing is
+/// retried with the square root of the factor, and the factor grows back over the stages
+/// succeeding, so the stepping slows down only where the circuit is hard to follow. Each stage
+/// is allowed [`SolverOptions::max_iterations`](crate::SolverOptions::max_iterations), and
+/// only the solution at the target gmin is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GminStepping {
+    initial_gmin: f64,
+    factor: f64,
+}
+
+impl GminStepping {
+    /// # Panics
+    ///
+    /// Panics if `initial_gmin` isn't positive and finite, or if `factor` isn't above the
+    /// factor at which the stepping gives up (1.01), as the conductance would then never reach
+    /// the target.
+    pub fn new(initial_gmin: f64, factor: f64) -> Self {
+        assert!(
+            initial_gmin > 0.0 && initial_gmin.is_finite(),
+            "Initial gmin {initial_gmin} isn't positive and finite"
+        );
+        assert!(
+            factor > MIN_GMIN_FACTOR,
+            "Gmin stepping factor {factor} isn't above {MIN_GMIN_FACTOR}"
+        );
+        Self {
+            initial_gmin,
+            factor,
+        }
+    }
+
+    pub fn get_initial_gmin(&self) -> f64 {
+        self.initial_gmin
+    }
+
+    pub fn get_factor(&self) -> f64 {
+        self.factor
+    }
+}
+
+impl Default for GminStepping {
+    /// From 1S down by a decade per stage.
+    fn default() -> Self {
+        Self::new(1.0, 10.0)
+    }
+}
+
+/// Smallest fraction of their values by which [`SourceStepping`] advances the sources before it
+/// gives up.
+pub(crate) const MIN_SOURCE_STEP: f64 = 1e-4;
+
+/// Configures source stepping, the fallback when both the Newton-Raphson iteration and gmin
+/// stepping of a timestep or operating point fail.
+///
+/// The solve is retried with every independent voltage and current source scaled to zero,
+/// where the circuit is at rest and converges easily, then with the sources scaled up by
+/// `1/steps` of their values at each stage, every stage starting from the solution of the one
+/// before, until they reach their full values. A stage failing is retried with half the step,
+/// and the step grows back over the stages succeeding. Each stage is allowed
+/// [`SolverOptions::max_iterations`](crate::SolverOptions::max_iterations), and only the
+/// solution at the full source values is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceStepping {
+    pub steps: usize,
+}
+
+impl SourceStepping {
+    pub fn new(steps: usize) -> Self {
+        Self { steps }
+    }
+}
+
+impl Default for SourceStepping {
+    /// From 0% to 100% in steps of 10%.
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+/// Which strategy solved a timestep or operating point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvergenceStrategy {
+    /// The plain Newton-Raphson iteration.
+    NewtonRaphson,
+    /// Gmin stepping (see [`GminStepping`]), after the Newton-Raphson iteration failed.
+    GminStepping,
+    /// Source stepping (see [`SourceStepping`]), after gmin stepping failed too or was
+    /// disabled.
+    SourceStepping,
+}
@@ -0,0 +1,479 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use nalgebra::DMatrix;
+
+use crate::be_solver::matrix_view::{ABMatrixView, XMatrixView};
+use crate::be_solver::stampable::Discretization;
+use crate::be_solver::stampable::Stampable;
+use crate::components::{Component, Netlist, ParamError};
+
+/// Number of entries kept in each ranking of a [`ConvergenceReport`].
+const REPORT_LENGTH: usize = 5;
+
+/// Number of iterations over which the history of the culprit of a [`ConvergenceReport`] is
+/// kept.
+const HISTORY_LENGTH: usize = 20;
+
+/// An unknown of the system solved at every timestep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variable {
+    NodeVoltage(usize),
+    /// An additional variable of a component (e.g. the current through a voltage source),
+    /// identified by the component's index and the variable's index within it.
+    ComponentVariable {
+        component: usize,
+        index: usize,
+    },
+}
+
+/// What an unknown of the system stands for, choosing the absolute tolerance of its
+/// convergence test (see [`SolverOptions`](crate::be_solver::SolverOptions)). Node voltages are
+/// voltages (temperatures of thermal nodes included), additional variables of components are
+/// classified by the component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VariableKind {
+    Voltage,
+    Current,
+}
+
+/// The variable which changed the most during one Newton-Raphson iteration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationChange {
+    pub iteration: usize,
+    pub variable: Variable,
+    pub change: f64,
+}
+
+/// The variable most often missing its tolerance by the most during a failed Newton-Raphson
+/// iteration, and the components that drive it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Culprit {
+    pub variable: Variable,
+    /// The name of the node, or of the component followed by the index of the variable.
+    pub name: String,
+    /// The names of the components connected to the node, those with nonlinear junctions first,
+    /// or of the component owning the variable.
+    pub components: Vec<String>,
+    /// Number of iterations in which the variable missed its tolerance by the most.
+    pub violations: usize,
+    /// The value of the variable after each of the last iterations, oldest first.
+    pub history: Vec<f64>,
+}
+
+/// The largest entry a component stamps into the system matrix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StampMagnitude {
+    pub component: usize,
+    pub magnitude: f64,
+}
+
+/// The ratio between the largest and smallest conductance in the equation of a node. Huge
+/// ratios lose precision in the linear solve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConductanceRatio {
+    pub node: usize,
+    pub ratio: f64,
+}
+
+/// A change likely to make a failing simulation converge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Remedy {
+    /// Connect these nodes to the reference node (e.g. with a large resistor or a local
+    /// reference), as nothing defines their DC voltage.
+    TieFloatingNodes { nodes: Vec<usize> },
+    /// Raise [`SolverOptions::gmin`](crate::SolverOptions::gmin) to this value.
+    IncreaseGmin { gmin: f64 },
+    /// Solve with this smaller timestep, keeping the Newton-Raphson starting point closer to the
+    /// solution.
+    ReduceTimestep { dt: f64 },
+    /// Ramp the sources up at the start with
+    /// [`SolverOptions::source_ramp`](crate::SolverOptions::source_ramp).
+    EnableSourceRamp { ramp_time: f64 },
+    /// The iteration was still converging when it gave up: raise
+    /// [`SolverOptions::max_iterations`](crate::SolverOptions::max_iterations) to this value.
+    IncreaseMaxIterations { max_iterations: usize },
+}
+
+impl Display for Remedy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TieFloatingNodes { nodes } => {
+                write!(f, "tie floating nodes {nodes:?} to the reference")
+            }
+            Self::IncreaseGmin { gmin } => write!(f, "increase gmin to {gmin}"),
+            Self::ReduceTimestep { dt } => write!(f, "reduce the timestep to {dt}"),
+            Self::EnableSourceRamp { ramp_time } => {
+                write!(f, "ramp the sources up over {ramp_time}s")
+            }
+            Self::IncreaseMaxIterations { max_iterations } => {
+                write!(f, "allow up to {max_iterations} iterations")
+            }
+        }
+    }
+}
+
+/// Why a timestep failed to solve, and what might fix it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvergenceReport {
+    /// Time at the start of the failed timestep, zero for the AC analysis.
+    pub time: f64,
+    pub dt: f64,
+    /// Number of Newton-Raphson iterations done before giving up.
+    pub iterations: usize,
+    /// True if the system matrix couldn't be inverted, rather than the iteration not settling.
+    pub singular: bool,
+    /// The variable which changed the most in each of the last iterations.
+    pub worst_variables: Vec<IterationChange>,
+    /// The variable most likely keeping the iteration from converging, None if the system was
+    /// singular before the first iteration.
+    pub culprit: Option<Culprit>,
+    /// The components stamping the largest entries into the system matrix, largest first.
+    pub largest_stamps: Vec<StampMagnitude>,
+    /// The nodes with the most extreme conductance ratios, most extreme first.
+    pub extreme_nodes: Vec<ConductanceRatio>,
+    pub remedies: Vec<Remedy>,
+}
+
+impl Display for ConvergenceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.singular {
+            writeln!(f, "Singular system at t={} (dt={})", self.time, self.dt)?;
+        } else {
+            writeln!(
+                f,
+                "Solver failed to converge after {} iterations at t={} (dt={})",
+                self.iterations, self.time, self.dt
+            )?;
+        }
+
+        for change in &self.worst_variables {
+            writeln!(
+                f,
+                "  iteration {}: {:?} changed by {}",
+                change.iteration, change.variable, change.change
+            )?;
+        }
+        if let Some(culprit) = &self.culprit {
+            writeln!(
+                f,
+                "  culprit: {} ({}) missed its tolerance the most in {} iterations, last values {:?}",
+                culprit.name,
+                culprit.components.join(", "),
+                culprit.violations,
+                culprit.history
+            )?;
+        }
+        for stamp in &self.largest_stamps {
+            writeln!(
+                f,
+                "  component {} stamps up to {}",
+                stamp.component, stamp.magnitude
+            )?;
+        }
+        for node in &self.extreme_nodes {
+            writeln!(f, "  node {} conductance ratio {}", node.node, node.ratio)?;
+        }
+        for remedy in &self.remedies {
+            writeln!(f, "  suggestion: {remedy}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConvergenceReport {}
+
+/// Why the solver couldn't advance. Failures of the solve itself carry the
+/// [`ConvergenceReport`] with the details and suggestions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// The Newton-Raphson iteration didn't settle within the iteration limit.
+    NonConvergence {
+        iterations: usize,
+        /// Largest change of a variable over the last iteration.
+        worst_residual: f64,
+        report: Box<ConvergenceReport>,
+    },
+    /// The system matrix couldn't be inverted, with the node most likely responsible (a
+    /// floating node, or else the one with the most extreme conductance ratio) if one stands
+    /// out.
+    SingularMatrix {
+        node: Option<usize>,
+        report: Box<ConvergenceReport>,
+    },
+    /// The timestep is zero, negative or not finite.
+    InvalidTimestep { dt: f64 },
+    /// A change to a component value can't be applied to the netlist.
+    InvalidChange(ParamError),
+}
+
+impl SolverError {
+    /// Gets the report of a failed solve.
+    pub fn get_report(&self) -> Option<&ConvergenceReport> {
+        match self {
+            Self::NonConvergence { report, .. } | Self::SingularMatrix { report, .. } => {
+                Some(report)
+            }
+            Self::InvalidTimestep { .. } | Self::InvalidChange(_) => None,
+        }
+    }
+
+    /// Checks that a timestep can be solved.
+    pub(crate) fn check_timestep(dt: f64) -> Result<(), Self> {
+        if dt > 0.0 && dt.is_finite() {
+            Ok(())
+        } else {
+            Err(Self::InvalidTimestep { dt })
+        }
+    }
+}
+
+impl From<Box<ConvergenceReport>> for SolverError {
+    fn from(report: Box<ConvergenceReport>) -> Self {
+        if report.singular {
+            let floating = report.remedies.iter().find_map(|remedy| match remedy {
+                Remedy::TieFloatingNodes { nodes } => nodes.first().copied(),
+                _ => None,
+            });
+            let node = floating.or(report.extreme_nodes.first().map(|node| node.node));
+            Self::SingularMatrix { node, report }
+        } else {
+            Self::NonConvergence {
+                iterations: report.iterations,
+                worst_residual: report
+                    .worst_variables
+                    .last()
+                    .map_or(f64::NAN, |change| change.change.abs()),
+                report,
+            }
+        }
+    }
+}
+
+impl From<ParamError> for SolverError {
+    fn from(error: ParamError) -> Self {
+        Self::InvalidChange(error)
+    }
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonConvergence { report, .. } | Self::SingularMatrix { report, .. } => {
+                write!(f, "{report}")
+            }
+            Self::InvalidTimestep { dt } => write!(f, "Invalid timestep {dt}"),
+            Self::InvalidChange(error) => write!(f, "Invalid parameter change: {error:?}"),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.get_report()
+            .map(|report| report as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// Maps a row of the system to the variable it solves for.
+pub(crate) fn row_variable(netlist: &Netlist, row: usize) -> Variable {
+    let num_nodes = netlist.get_num_nodes();
+    let reference = netlist.get_reference_node();
+
+    if row < num_nodes {
+        // Node 0 takes the place of the reference node when it isn't the reference.
+        let node = row + 1;
+        return Variable::NodeVoltage(if node == reference { 0 } else { node });
+    }
+
+    let mut start = num_nodes;
+    for (component, c) in netlist.get_components().iter().enumerate() {
+        if row < start + c.num_variables() {
+            return Variable::ComponentVariable {
+                component,
+                index: row - start,
+            };
+        }
+        start += c.num_variables();
+    }
+    unreachable!("Row {row} is outside the system")
+}
+
+/// Gets the kind of every row of the system of the netlist, in the order of
+/// [`row_variable`].
+pub(crate) fn row_kinds(netlist: &Netlist) -> Vec<VariableKind> {
+    let mut kinds = vec![VariableKind::Voltage; netlist.get_num_nodes()];
+    for c in netlist.get_components() {
+        kinds.extend((0..c.num_variables()).map(|index| c.variable_kind(index)));
+    }
+    kinds
+}
+
+/// What a Newton-Raphson iteration went through, reported if it fails: the variable which
+/// changed the most in every iteration, and the last iterates.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IterationHistory {
+    pub changes: Vec<IterationChange>,
+    iterates: VecDeque<DMatrix<f64>>,
+}
+
+impl IterationHistory {
+    pub fn push(&mut self, change: IterationChange, x: &DMatrix<f64>) {
+        self.changes.push(change);
+        if self.iterates.len() == HISTORY_LENGTH {
+            self.iterates.pop_front();
+        }
+        self.iterates.push_back(x.clone());
+    }
+}
+
+/// Finds the variable which missed its tolerance by the most in the most iterations, the latest
+/// on ties, with its values over the last iterates.
+pub(crate) fn find_culprit(netlist: &Netlist, history: &IterationHistory) -> Option<Culprit> {
+    let IterationHistory { changes, iterates } = history;
+    let mut counts: Vec<(Variable, usize)> = Vec::new();
+    for change in changes {
+        match counts.iter_mut().find(|(v, _)| *v == change.variable) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((change.variable, 1)),
+        }
+    }
+    let (variable, violations) = changes
+        .iter()
+        .rev()
+        .filter_map(|change| counts.iter().find(|(v, _)| *v == change.variable))
+        .copied()
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })?;
+
+    let dimension = iterates.front().map_or(0, |x| x.nrows());
+    let row = (0..dimension).find(|&row| row_variable(netlist, row) == variable);
+    let history = row.map_or_else(Vec::new, |row| iterates.iter().map(|x| x[row]).collect());
+
+    let (name, components) = match variable {
+        Variable::NodeVoltage(node) => {
+            let mut connected: Vec<(usize, bool)> = netlist
+                .get_components()
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| {
+                    let mut connected = false;
+                    let mut c = (*c).clone();
+                    c.map_nodes(|terminal| {
+                        connected |= terminal == node;
+                        terminal
+                    });
+                    connected
+                })
+                .map(|(index, c)| (index, !c.junctions().is_empty()))
+                .collect();
+            // Stable, so the components stay in netlist order within each group.
+            connected.sort_by_key(|&(_, nonlinear)| !nonlinear);
+            let names = connected
+                .into_iter()
+                .map(|(index, _)| netlist.get_component_name(index))
+                .collect();
+            (format!("node {}", netlist.get_node_name(node)), names)
+        }
+        Variable::ComponentVariable { component, index } => {
+            let name = netlist.get_component_name(component);
+            (format!("{name}[{index}]"), vec![name])
+        }
+    };
+
+    Some(Culprit {
+        variable,
+        name,
+        components,
+        violations,
+        history,
+    })
+}
+
+/// Ranks the components by the largest entry each stamps into the system matrix.
+pub(crate) fn largest_stamps(
+    netlist: &Netlist,
+    x: &DMatrix<f64>,
+    discretization: Discretization,
+) -> Vec<StampMagnitude> {
+    let num_nodes = netlist.get_num_nodes();
+    let reference = netlist.get_reference_node();
+    let dimension = x.nrows();
+
+    let mut stamps: Vec<StampMagnitude> = Vec::new();
+    let mut variables_start = num_nodes;
+    for (component, c) in netlist.get_components().iter().enumerate() {
+        let mut a = DMatrix::zeros(dimension, dimension);
+        let mut b = DMatrix::zeros(dimension, 1);
+        let mut view = ABMatrixView::new(
+            &mut a,
+            &mut b,
+            num_nodes,
+            reference,
+            c.num_variables(),
+            variables_start,
+        );
+        let guess = XMatrixView::new(x, num_nodes, reference, c.num_variables(), variables_start);
+        discretization.stamp(c, &mut view, &guess);
+        variables_start += c.num_variables();
+
+        stamps.push(StampMagnitude {
+            component,
+            magnitude: a.amax(),
+        });
+    }
+
+    stamps.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+    stamps.truncate(REPORT_LENGTH);
+    stamps
+}
+
+/// Ranks the nodes by the ratio between the largest and smallest nonzero entry of their
+/// equation.
+pub(crate) fn extreme_nodes(
+    netlist: &Netlist,
+    entries: &[(usize, usize, f64)],
+) -> Vec<ConductanceRatio> {
+    // The smallest and largest nonzero magnitude on the row of each node.
+    let mut ranges = vec![(f64::INFINITY, 0.0f64); netlist.get_num_nodes()];
+    for &(row, _, value) in entries {
+        if let Some((min, max)) = ranges.get_mut(row)
+            && value != 0.0
+        {
+            *min = min.min(value.abs());
+            *max = max.max(value.abs());
+        }
+    }
+
+    let mut ratios: Vec<ConductanceRatio> = ranges
+        .into_iter()
+        .enumerate()
+        .filter_map(|(row, (min, max))| {
+            let Variable::NodeVoltage(node) = row_variable(netlist, row) else {
+                unreachable!()
+            };
+            (max > 0.0).then_some(ConductanceRatio {
+                node,
+                ratio: max / min,
+            })
+        })
+        .collect();
+
+    ratios.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+    ratios.truncate(REPORT_LENGTH);
+    ratios
+}
+
+/// Keeps only the last iterations for a report.
+pub(crate) fn last_changes(mut changes: Vec<IterationChange>) -> Vec<IterationChange> {
+    let excess = changes.len().saturating_sub(REPORT_LENGTH);
+    changes.drain(..excess);
+    changes
+}
+
+/// Returns true if any component is a source, which a soft start would help.
+pub(crate) fn has_sources(netlist: &Netlist) -> bool {
+    netlist
+        .get_components()
+        .iter()
+        .any(|c| matches!(c, Component::VoltageSource(_) | Component::CurrentSource(_)))
+}
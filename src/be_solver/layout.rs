@@ -0,0 +1,401 @@
+use std::collections::{BTreeSet, HashMap};
+
+use nalgebra::DMatrix;
+
+use crate::be_solver::matrix_view::{ABMatrixView, XMatrixView};
+use crate::be_solver::stampable::Stampable;
+use crate::be_solver::{LOCAL_REFERENCE_CONDUCTANCE, stamp_local_references};
+use crate::components::{Component, Netlist};
+
+/// Where the variables and equations of a netlist sit in the system A x = b, and which
+/// coefficients of A can be nonzero (its sparsity pattern).
+///
+/// The layout is kept up to date with edits to the netlist through its [`DirtyRegion`]
+/// (see [`SystemLayout::update`]): components added or edited in place only have their own
+/// stamps refreshed, along with those of any components whose variables they move, while
+/// removals, new nodes and changes of reference lay the system out again from scratch.
+///
+/// [`DirtyRegion`]: crate::components::DirtyRegion
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemLayout {
+    num_nodes: usize,
+    reference: usize,
+    /// Index of the first variable of each component.
+    variable_starts: Vec<usize>,
+    num_variables: Vec<usize>,
+    /// Positions each component stamps in A, and those of the local references.
+    entries: Vec<Vec<(usize, usize)>>,
+    reference_entries: Vec<(usize, usize)>,
+    /// Number of stamps landing on each position of A.
+    pattern: HashMap<(usize, usize), usize>,
+    dirty_equations: Vec<usize>,
+    /// Ranges of variables which kept their meaning over the last update, as the start before
+    /// the update, the start after it and their number.
+    carried: Vec<(usize, usize, usize)>,
+}
+
+impl SystemLayout {
+    /// Lays out the system of the netlist.
+    pub fn new(netlist: &Netlist) -> Self {
+        let mut layout = Self {
+            num_nodes: netlist.get_num_nodes(),
+            reference: netlist.get_reference_node(),
+            variable_starts: Vec::new(),
+            num_variables: Vec::new(),
+            entries: Vec::new(),
+            reference_entries: Vec::new(),
+            pattern: HashMap::new(),
+            dirty_equations: Vec::new(),
+            carried: Vec::new(),
+        };
+
+        let mut start = layout.num_nodes;
+        for component in netlist.get_components() {
+            layout.variable_starts.push(start);
+            layout.num_variables.push(component.num_variables());
+            start += component.num_variables();
+        }
+        let x = DMatrix::zeros(layout.get_dimension(), 1);
+        for (index, component) in netlist.get_components().iter().enumerate() {
+            let entries = layout.stamp_pattern(component, index, &x);
+            layout.add_entries(&entries);
+            layout.entries.push(entries);
+        }
+
+        let mut a = DMatrix::zeros(0, 0);
+        let mut b = DMatrix::zeros(0, 1);
+        let mut view = ABMatrixView::new(&mut a, &mut b, layout.num_nodes, layout.reference, 0, 0)
+            .with_pattern(&mut layout.reference_entries);
+        stamp_local_references(netlist, &mut view, LOCAL_REFERENCE_CONDUCTANCE);
+        let reference_entries = layout.reference_entries.clone();
+        layout.add_entries(&reference_entries);
+
+        layout.dirty_equations = (0..layout.get_dimension()).collect();
+        layout
+    }
+
+    /// Catches up with the edits made to the netlist since the last update, taking its
+    /// [`DirtyRegion`](crate::components::DirtyRegion). Returns whether anything changed.
+    ///
+    /// Components whose number of variables changed are laid out again even if the edit
+    /// wasn't recorded in the dirty region (e.g. when made through
+    /// [`Netlist::get_components_mut`]), so a missed edit can't leave stale equations behind.
+    pub fn update(&mut self, netlist: &mut Netlist) -> bool {
+        let dirty = netlist.take_dirty_region();
+        self.dirty_equations.clear();
+        let components = netlist.get_components();
+        let resized = components.len() != self.num_variables.len()
+            || components
+                .iter()
+                .zip(&self.num_variables)
+                .any(|(component, &count)| component.num_variables() != count);
+        if dirty.is_empty() && !resized {
+            return false;
+        }
+
+        if dirty.restructured
+            || netlist.get_num_nodes() != self.num_nodes
+            || netlist.get_reference_node() != self.reference
+            || components.len() < self.variable_starts.len()
+        {
+            // Components may have been removed, so only the node voltages are known to keep
+            // their place, and only while the reference stays the same.
+            let (num_nodes, reference) = (self.num_nodes, self.reference);
+            *self = Self::new(netlist);
+            if self.reference == reference {
+                self.carried = vec![(0, 0, num_nodes.min(self.num_nodes))];
+            }
+            return true;
+        }
+        self.carried = vec![(0, 0, self.num_nodes)];
+
+        // Components are only ever added at the end, so the ones already laid out keep their
+        // indices. Edits changing a component's number of variables move the variables of
+        // every component after it.
+        let mut refresh: BTreeSet<usize> = dirty.components.iter().copied().collect();
+        let mut start = self.num_nodes;
+        let mut moved = false;
+        for (index, component) in components.iter().enumerate() {
+            if index == self.variable_starts.len() {
+                self.variable_starts.push(start);
+                self.num_variables.push(component.num_variables());
+                self.entries.push(Vec::new());
+                refresh.insert(index);
+            } else if self.variable_starts[index] != start
+                || self.num_variables[index] != component.num_variables()
+            {
+                moved = true;
+                if self.num_variables[index] == component.num_variables() {
+                    self.carried.push((
+                        self.variable_starts[index],
+                        start,
+                        component.num_variables(),
+                    ));
+                } else {
+                    // The equations of its old variables go away even if it has none left.
+                    refresh.insert(index);
+                }
+                self.variable_starts[index] = start;
+                self.num_variables[index] = component.num_variables();
+            } else {
+                self.carried.push((start, start, component.num_variables()));
+            }
+            if moved && component.num_variables() > 0 {
+                refresh.insert(index);
+            }
+            start += component.num_variables();
+        }
+
+        let x = DMatrix::zeros(self.get_dimension(), 1);
+        let mut equations = BTreeSet::new();
+        for index in refresh {
+            let old = std::mem::take(&mut self.entries[index]);
+            self.remove_entries(&old);
+            let new = self.stamp_pattern(&components[index], index, &x);
+            self.add_entries(&new);
+            equations.extend(old.iter().chain(&new).map(|&(row, _)| row));
+            self.entries[index] = new;
+        }
+
+        self.dirty_equations = equations.into_iter().collect();
+        true
+    }
+
+    /// Gets the number of variables, and equations, of the system.
+    pub fn get_dimension(&self) -> usize {
+        self.num_nodes + self.num_variables.iter().sum::<usize>()
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    /// Gets the index of the first variable of the component at the given index.
+    pub fn get_variables_start(&self, component: usize) -> usize {
+        self.variable_starts[component]
+    }
+
+    /// Gets the positions in A that can be nonzero, by row and then column.
+    pub fn get_pattern(&self) -> Vec<(usize, usize)> {
+        let mut pattern: Vec<(usize, usize)> = self.pattern.keys().copied().collect();
+        pattern.sort();
+        pattern
+    }
+
+    /// Gets the number of positions in A that can be nonzero.
+    pub fn get_num_nonzeros(&self) -> usize {
+        self.pattern.len()
+    }
+
+    /// Carries a solution of the system as laid out before the last update over to the current
+    /// layout, e.g. to start the Newton-Raphson iteration from it after the netlist was edited.
+    /// Node voltages and the variables of the components that are still there keep their
+    /// values, new variables start at zero.
+    pub(crate) fn carry_over(&self, x: &DMatrix<f64>) -> DMatrix<f64> {
+        let mut carried = DMatrix::zeros(self.get_dimension(), 1);
+        for &(previous, start, count) in &self.carried {
+            if previous + count <= x.nrows() && start + count <= carried.nrows() {
+                carried
+                    .rows_mut(start, count)
+                    .copy_from(&x.rows(previous, count));
+            }
+        }
+        carried
+    }
+
+    /// Gets the equations (rows of A) stamped differently since the previous update, every
+    /// equation after a full layout.
+    pub fn get_dirty_equations(&self) -> &Vec<usize> {
+        &self.dirty_equations
+    }
+
+    /// Finds the positions a component stamps by stamping it, with its junctions, into empty
+    /// matrices around the guess x.
+    fn stamp_pattern(
+        &self,
+        component: &Component,
+        index: usize,
+        x: &DMatrix<f64>,
+    ) -> Vec<(usize, usize)> {
+        let mut entries = Vec::new();
+        let mut a = DMatrix::zeros(0, 0);
+        let mut b = DMatrix::zeros(0, 1);
+
+        let start = self.variable_starts[index];
+        let num_variables = self.num_variables[index];
+        let mut view = ABMatrixView::new(
+            &mut a,
+            &mut b,
+            self.num_nodes,
+            self.reference,
+            num_variables,
+            start,
+        )
+        .with_pattern(&mut entries);
+        let guess = XMatrixView::new(x, self.num_nodes, self.reference, num_variables, start);
+        component.stamp(&mut view, &guess, 1.0);
+        for (positive_node, negative_node) in component.junctions() {
+            view.conductance_add(positive_node, negative_node, 0.0);
+        }
+
+        entries.sort();
+        entries.dedup();
+        entries
+    }
+
+    fn add_entries(&mut self, entries: &[(usize, usize)]) {
+        for &entry in entries {
+            *self.pattern.entry(entry).or_insert(0) += 1;
+        }
+    }
+
+    fn remove_entries(&mut self, entries: &[(usize, usize)]) {
+        for entry in entries {
+            if let Some(count) = self.pattern.get_mut(entry) {
+                *count -= 1;
+                if *count == 0 {
+                    self.pattern.remove(entry);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{Capacitor, Diode, DiodeModel, Resistor, VoltageSource};
+
+    #[test]
+    fn test_incremental_layout() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        let mut layout = SystemLayout::new(&netlist);
+        netlist.take_dirty_region();
+
+        // Two node equations and the source's current.
+        assert_eq!(layout.get_dimension(), 3);
+        assert_eq!(layout.get_variables_start(0), 2);
+        assert!(!layout.update(&mut netlist));
+        assert!(layout.get_dirty_equations().is_empty());
+
+        // Changing a value only dirties the equations of the component's nodes.
+        crate::components::ParamChange::new(2, 2e-6)
+            .apply(&mut netlist)
+            .unwrap();
+        assert!(layout.update(&mut netlist));
+        assert_eq!(layout.get_dirty_equations(), &vec![1]);
+
+        // Adding a source on existing nodes appends its variable.
+        netlist.add_component(VoltageSource::new(2, 1, 0.5));
+        assert!(layout.update(&mut netlist));
+        assert_eq!(layout.get_dimension(), 4);
+        assert_eq!(layout.get_variables_start(3), 3);
+        assert_eq!(layout.get_dirty_equations(), &vec![0, 1, 3]);
+        let x = DMatrix::from_column_slice(3, 1, &[1.0, 0.5, -1e-3]);
+        assert_eq!(layout.carry_over(&x).as_slice(), &[1.0, 0.5, -1e-3, 0.0]);
+
+        // Every update matches laying the system out from scratch.
+        netlist.replace_component(1, Resistor::new(1, 0, 1e3));
+        layout.update(&mut netlist);
+        assert_eq!(
+            layout.get_pattern(),
+            SystemLayout::new(&netlist).get_pattern()
+        );
+        netlist.remove_component(0);
+        layout.update(&mut netlist);
+        let fresh = SystemLayout::new(&netlist);
+        assert_eq!(layout.get_pattern(), fresh.get_pattern());
+        assert_eq!(layout.get_dimension(), 3);
+
+        // Only the node voltages survive a removal.
+        let x = DMatrix::from_column_slice(4, 1, &[1.0, 0.5, -1e-3, 2e-3]);
+        assert_eq!(layout.carry_over(&x).as_slice(), &[1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_unrecorded_edit() {
+        // A source replaced without going through the dirty region still loses its current.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(VoltageSource::new(2, 0, 5.0));
+        let mut layout = SystemLayout::new(&netlist);
+        netlist.take_dirty_region();
+
+        netlist.get_components_mut()[2] = Resistor::new(2, 0, 1e12).into();
+        assert!(netlist.get_dirty_region().is_empty());
+        assert!(layout.update(&mut netlist));
+        let fresh = SystemLayout::new(&netlist);
+        assert_eq!(layout.get_dimension(), fresh.get_dimension());
+        assert_eq!(layout.get_pattern(), fresh.get_pattern());
+    }
+
+    #[test]
+    fn test_solver_follows_edits() {
+        // A solver keeps going after a load is added and then replaced mid-simulation.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        let mut solver = crate::BESolver::new(&mut netlist);
+        for _ in 0..100 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert!((solver.get_node_voltage(2) - 1.0).abs() < 1e-3);
+
+        solver
+            .get_netlist_mut()
+            .add_component(Resistor::new(2, 0, 1e3));
+        for _ in 0..100 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert!((solver.get_node_voltage(2) - 0.5).abs() < 1e-3);
+        assert_eq!(solver.get_layout().get_dimension(), 3);
+
+        solver
+            .get_netlist_mut()
+            .replace_component(3, Resistor::new(2, 0, 3e3));
+        for _ in 0..100 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert!((solver.get_node_voltage(2) - 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_warm_start_after_edit() {
+        // A diode forward biased through a resistor, which takes a few iterations to solve
+        // from scratch.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+        let mut solver = crate::BESolver::new(&mut netlist);
+        solver.solve(1e-4).unwrap();
+        let cold = solver.get_statistics().newton_iterations;
+        assert!(cold > 3);
+
+        // Adding a source elsewhere moves nothing the diode depends on, so the next step starts
+        // from the previous operating point instead of from zero.
+        solver
+            .get_netlist_mut()
+            .add_component(VoltageSource::new(3, 0, 1.0))
+            .add_component(Resistor::new(3, 0, 1e3));
+        solver.solve(1e-4).unwrap();
+        assert!(solver.get_statistics().newton_iterations - cold <= 2);
+        assert!((solver.get_node_voltage(3) - 1.0).abs() < 1e-9);
+    }
+}
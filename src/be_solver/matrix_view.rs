@@ -1,4 +1,6 @@
-use nalgebra::DMatrix;
+use std::ops::AddAssign;
+
+use nalgebra::{DMatrix, Scalar};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewEquationIndex {
@@ -7,15 +9,16 @@ pub enum ViewEquationIndex {
 }
 
 impl ViewEquationIndex {
-    fn into_global_index(
+    pub(crate) fn into_global_index(
         self,
         num_nodes: usize,
+        reference: usize,
         num_variables: usize,
         variables_start: usize,
     ) -> Option<usize> {
         match self {
             Self::NodalEquation(idx) => {
-                if idx == 0 {
+                if idx == reference {
                     return None;
                 }
 
@@ -23,6 +26,11 @@ impl ViewEquationIndex {
                     return None;
                 }
 
+                // Node 0 takes the place of the reference node when it isn't the reference.
+                if idx == 0 {
+                    return Some(reference - 1);
+                }
+
                 Some(idx - 1)
             }
             Self::SpecificEquation(idx) => {
@@ -43,15 +51,16 @@ pub enum ViewVariableIndex {
 }
 
 impl ViewVariableIndex {
-    fn into_global_index(
+    pub(crate) fn into_global_index(
         self,
         num_nodes: usize,
+        reference: usize,
         num_variables: usize,
         variables_start: usize,
     ) -> Option<usize> {
         match self {
             Self::NodeVoltage(idx) => {
-                if idx == 0 {
+                if idx == reference {
                     return None;
                 }
 
@@ -59,6 +68,11 @@ impl ViewVariableIndex {
                     return None;
                 }
 
+                // Node 0 takes the place of the reference node when it isn't the reference.
+                if idx == 0 {
+                    return Some(reference - 1);
+                }
+
                 Some(idx - 1)
             }
             Self::SpecificVariable(idx) => {
@@ -72,97 +86,242 @@ impl ViewVariableIndex {
     }
 }
 
-pub struct ABMatrixView<'a> {
-    a: &'a mut DMatrix<f64>,
-    b: &'a mut DMatrix<f64>,
+/// Where the coefficients stamped through an [`ABMatrixView`] go.
+enum Coefficients<'a, T> {
+    Dense(&'a mut DMatrix<T>),
+    /// Appended as (row, column, value) triplets for assembling a sparse matrix of the given
+    /// dimension, duplicates summing up.
+    Triplets(&'a mut Vec<(usize, usize, T)>, usize),
+}
+
+/// A view of the coefficient (A) and result (b) matrices of the system Ax = b for a single
+/// component. The scalar type is f64 for transient analysis and complex for AC analysis.
+///
+/// A is either dense or collected as triplets for a sparse matrix, which makes no difference to
+/// the components stamping through the view.
+pub struct ABMatrixView<'a, T: Scalar + AddAssign + Copy = f64> {
+    a: Coefficients<'a, T>,
+    b: &'a mut DMatrix<T>,
     num_nodes: usize,
+    reference: usize,
     num_variables: usize,
     variables_start: usize,
+    pattern: Option<&'a mut Vec<(usize, usize)>>,
 }
 
-impl<'a> ABMatrixView<'a> {
+impl<'a, T: Scalar + AddAssign + Copy> ABMatrixView<'a, T> {
     pub fn new(
-        a: &'a mut DMatrix<f64>,
-        b: &'a mut DMatrix<f64>,
+        a: &'a mut DMatrix<T>,
+        b: &'a mut DMatrix<T>,
+        num_nodes: usize,
+        reference: usize,
+        num_variables: usize,
+        variables_start: usize,
+    ) -> Self {
+        Self {
+            a: Coefficients::Dense(a),
+            b,
+            num_nodes,
+            reference,
+            num_variables,
+            variables_start,
+            pattern: None,
+        }
+    }
+
+    /// Creates a view appending the coefficients of A to a list of triplets instead, for a
+    /// system of the dimension of b.
+    pub(crate) fn with_triplets(
+        triplets: &'a mut Vec<(usize, usize, T)>,
+        b: &'a mut DMatrix<T>,
         num_nodes: usize,
+        reference: usize,
         num_variables: usize,
         variables_start: usize,
     ) -> Self {
+        let dimension = b.nrows();
         Self {
-            a,
+            a: Coefficients::Triplets(triplets, dimension),
             b,
             num_nodes,
+            reference,
             num_variables,
             variables_start,
+            pattern: None,
         }
     }
 
-    fn get_coefficient_mut(
+    /// Points the view at the variables of another component, so one view can stamp a whole
+    /// system.
+    pub(crate) fn select_component(&mut self, num_variables: usize, variables_start: usize) {
+        self.num_variables = num_variables;
+        self.variables_start = variables_start;
+    }
+
+    /// Records the position in A of every coefficient stamped through the view, whether or not
+    /// it lands in the matrix, e.g. to find the sparsity pattern of a component by stamping it
+    /// into empty matrices.
+    pub(crate) fn with_pattern(mut self, pattern: &'a mut Vec<(usize, usize)>) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    fn get_position(
         &mut self,
         equation: ViewEquationIndex,
         variable: ViewVariableIndex,
-    ) -> Option<&mut f64> {
-        self.a.get_mut((
-            equation.into_global_index(self.num_nodes, self.num_variables, self.variables_start)?,
-            variable.into_global_index(self.num_nodes, self.num_variables, self.variables_start)?,
-        ))
+    ) -> Option<(usize, usize)> {
+        let position = (
+            equation.into_global_index(
+                self.num_nodes,
+                self.reference,
+                self.num_variables,
+                self.variables_start,
+            )?,
+            variable.into_global_index(
+                self.num_nodes,
+                self.reference,
+                self.num_variables,
+                self.variables_start,
+            )?,
+        );
+        if let Some(pattern) = &mut self.pattern {
+            pattern.push(position);
+        }
+        Some(position)
     }
 
     pub fn coefficient_add(
         &mut self,
         equation: ViewEquationIndex,
         variable: ViewVariableIndex,
-        value: f64,
+        value: T,
     ) {
-        if let Some(a) = self.get_coefficient_mut(equation, variable) {
-            *a += value;
+        let Some((row, column)) = self.get_position(equation, variable) else {
+            return;
+        };
+        match &mut self.a {
+            Coefficients::Dense(a) => {
+                if let Some(a) = a.get_mut((row, column)) {
+                    *a += value;
+                }
+            }
+            Coefficients::Triplets(triplets, dimension) => {
+                if row < *dimension && column < *dimension {
+                    triplets.push((row, column, value));
+                }
+            }
         }
     }
 
-    fn get_result_mut(&mut self, equation: ViewEquationIndex) -> Option<&mut f64> {
+    /// Adds a conductance between two nodes.
+    pub fn conductance_add(&mut self, positive_node: usize, negative_node: usize, g: T)
+    where
+        T: std::ops::Neg<Output = T>,
+    {
+        self.terminal_conductance_add(
+            (
+                ViewEquationIndex::NodalEquation(positive_node),
+                ViewVariableIndex::NodeVoltage(positive_node),
+            ),
+            (
+                ViewEquationIndex::NodalEquation(negative_node),
+                ViewVariableIndex::NodeVoltage(negative_node),
+            ),
+            g,
+        );
+    }
+
+    /// Adds a conductance between two terminals, each given by the equation summing the
+    /// currents leaving it and the variable holding its voltage, so internal nodes of a
+    /// component can be stamped like external ones.
+    pub fn terminal_conductance_add(
+        &mut self,
+        positive: (ViewEquationIndex, ViewVariableIndex),
+        negative: (ViewEquationIndex, ViewVariableIndex),
+        g: T,
+    ) where
+        T: std::ops::Neg<Output = T>,
+    {
+        self.coefficient_add(positive.0, positive.1, g);
+        self.coefficient_add(positive.0, negative.1, -g);
+        self.coefficient_add(negative.0, positive.1, -g);
+        self.coefficient_add(negative.0, negative.1, g);
+    }
+
+    fn get_result_mut(&mut self, equation: ViewEquationIndex) -> Option<&mut T> {
         self.b.get_mut((
-            equation.into_global_index(self.num_nodes, self.num_variables, self.variables_start)?,
+            equation.into_global_index(
+                self.num_nodes,
+                self.reference,
+                self.num_variables,
+                self.variables_start,
+            )?,
             0,
         ))
     }
 
-    pub fn result_add(&mut self, equation: ViewEquationIndex, value: f64) {
+    pub fn result_add(&mut self, equation: ViewEquationIndex, value: T) {
         if let Some(a) = self.get_result_mut(equation) {
             *a += value;
         }
     }
 }
 
-pub struct XMatrixView<'a> {
-    x: &'a DMatrix<f64>,
+/// A view of the solution (x) of the system Ax = b for a single component.
+pub struct XMatrixView<'a, T: Scalar + Copy = f64> {
+    x: &'a DMatrix<T>,
     num_nodes: usize,
+    reference: usize,
     num_variables: usize,
     variables_start: usize,
 }
 
-impl<'a> XMatrixView<'a> {
+impl<'a, T: Scalar + Copy + Default> XMatrixView<'a, T> {
     pub fn new(
-        x: &'a DMatrix<f64>,
+        x: &'a DMatrix<T>,
         num_nodes: usize,
+        reference: usize,
         num_variables: usize,
         variables_start: usize,
     ) -> Self {
         Self {
             x,
             num_nodes,
+            reference,
             num_variables,
             variables_start,
         }
     }
 
-    pub fn get_variable(&self, variable: ViewVariableIndex) -> Option<f64> {
+    /// Returns whether the node is the reference, whose voltage is zero rather than a variable
+    /// of the system.
+    pub fn is_reference(&self, node: usize) -> bool {
+        node == self.reference
+    }
+
+    /// Gets the row of the variable in the solution, None for the reference node's voltage.
+    pub(crate) fn global_index(&self, variable: ViewVariableIndex) -> Option<usize> {
+        match variable {
+            ViewVariableIndex::NodeVoltage(node) if node == self.reference => None,
+            _ => variable.into_global_index(
+                self.num_nodes,
+                self.reference,
+                self.num_variables,
+                self.variables_start,
+            ),
+        }
+    }
+
+    pub fn get_variable(&self, variable: ViewVariableIndex) -> Option<T> {
         match variable {
-            ViewVariableIndex::NodeVoltage(0) => Some(0.0),
+            ViewVariableIndex::NodeVoltage(node) if node == self.reference => Some(T::default()),
             _ => self
                 .x
                 .get((
                     variable.into_global_index(
                         self.num_nodes,
+                        self.reference,
                         self.num_variables,
                         self.variables_start,
                     )?,
@@ -172,3 +331,18 @@ impl<'a> XMatrixView<'a> {
         }
     }
 }
+
+/// Gets the voltage of every node from the solution of the system, indexed by node number.
+pub fn node_voltages<T: Scalar + Copy + Default>(
+    x: &DMatrix<T>,
+    num_nodes: usize,
+    reference: usize,
+) -> Vec<T> {
+    let view = XMatrixView::new(x, num_nodes, reference, 0, 0);
+    (0..=num_nodes)
+        .map(|node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        })
+        .collect()
+}
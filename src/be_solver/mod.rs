@@ -1,83 +1,1098 @@
-mod matrix_view;
-mod stampable;
+mod adaptive;
+mod continuation;
+mod convergence;
+mod layout;
+pub(crate) mod matrix_view;
+mod model_check;
+mod options;
+mod refinement;
+mod scaling;
+mod sparse;
+pub(crate) mod stampable;
+mod statistics;
+
+pub use adaptive::AdaptiveTimestep;
+pub use continuation::{ConvergenceStrategy, GminStepping, SourceStepping};
+pub use convergence::{
+    ConductanceRatio, ConvergenceReport, Culprit, IterationChange, Remedy, SolverError,
+    StampMagnitude, Variable,
+};
+pub use layout::SystemLayout;
+pub use model_check::{ModelCheck, ModelReport, ModelViolation};
+pub use options::{Damping, IntegrationMethod, OptionPresets, SolverOptions};
+pub use refinement::{Refinement, StepRejection};
+pub use statistics::SolverStatistics;
 
 use nalgebra::DMatrix;
 
+use convergence::IterationHistory;
+pub(crate) use convergence::{VariableKind, row_kinds};
 use matrix_view::{ABMatrixView, XMatrixView};
-use stampable::Stampable;
+use sparse::{ColumnOrdering, SparseMatrix};
+use stampable::{Discretization, Stampable};
+
+use crate::Rng;
+use crate::components::{Component, Netlist, ParamChange, RatingViolation};
+
+/// Fraction of a timestep within which a breakpoint counts as falling on its start or end rather
+/// than splitting it.
+const BREAKPOINT_TOLERANCE: f64 = 1e-9;
+
+/// Conductance tying each local reference to the reference node.
+pub(crate) const LOCAL_REFERENCE_CONDUCTANCE: f64 = 1e-9;
+
+/// Stamps the conductances tying the local references of the netlist to its reference node.
+///
+/// Node 0 is also tied to the reference when it isn't the reference and no component uses it,
+/// as it still has a place in the system.
+pub(crate) fn stamp_local_references<T>(netlist: &Netlist, view: &mut ABMatrixView<T>, g: T)
+where
+    T: nalgebra::Scalar + std::ops::AddAssign + std::ops::Neg<Output = T> + Copy,
+{
+    let reference = netlist.get_reference_node();
+
+    let node_0_unused = reference != 0
+        && netlist
+            .get_components()
+            .iter()
+            .all(|c| c.get_positive_node() != 0 && c.get_negative_node() != 0);
+
+    for &node in netlist.get_local_references() {
+        view.conductance_add(node, reference, g);
+    }
+    if node_0_unused {
+        view.conductance_add(0, reference, g);
+    }
+}
+
+/// Assembles the system A x = b laid out by the layout, with the nonlinear components
+/// linearized around the guess x and the reactive ones discretized as given.
+pub(crate) fn assemble(
+    netlist: &Netlist,
+    layout: &SystemLayout,
+    x: &DMatrix<f64>,
+    discretization: Discretization,
+    gmin: f64,
+) -> (DMatrix<f64>, DMatrix<f64>) {
+    let dimension = layout.get_dimension();
+    let mut a = DMatrix::zeros(dimension, dimension);
+    let mut b = DMatrix::zeros(dimension, 1);
+
+    let mut view = ABMatrixView::new(
+        &mut a,
+        &mut b,
+        layout.get_num_nodes(),
+        netlist.get_reference_node(),
+        0,
+        0,
+    );
+    stamp_system(netlist, layout, x, discretization, gmin, &mut view);
+
+    (a, b)
+}
+
+/// Assembles the system A x = b like [`assemble`], with A as a sparse matrix.
+pub(crate) fn assemble_sparse(
+    netlist: &Netlist,
+    layout: &SystemLayout,
+    x: &DMatrix<f64>,
+    discretization: Discretization,
+    gmin: f64,
+) -> (SparseMatrix, DMatrix<f64>) {
+    let dimension = layout.get_dimension();
+    let mut triplets = Vec::with_capacity(layout.get_num_nonzeros());
+    let mut b = DMatrix::zeros(dimension, 1);
+
+    let mut view = ABMatrixView::with_triplets(
+        &mut triplets,
+        &mut b,
+        layout.get_num_nodes(),
+        netlist.get_reference_node(),
+        0,
+        0,
+    );
+    stamp_system(netlist, layout, x, discretization, gmin, &mut view);
+
+    (SparseMatrix::from_triplets(dimension, &triplets), b)
+}
+
+/// The coefficient matrix of an assembled system, sparse from
+/// [`SolverOptions::sparse_threshold`] on.
+pub(crate) enum SystemMatrix {
+    Dense(DMatrix<f64>),
+    Sparse(SparseMatrix),
+}
+
+impl SystemMatrix {
+    /// Gets every entry as a (row, column, value) triplet, including the zeros of a dense matrix.
+    pub(crate) fn entries(&self) -> Vec<(usize, usize, f64)> {
+        match self {
+            Self::Dense(a) => a
+                .column_iter()
+                .enumerate()
+                .flat_map(|(column, values)| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(move |(row, &value)| (row, column, value))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            Self::Sparse(a) => a.entries().collect(),
+        }
+    }
+
+    /// Gets the norm of the residual A*x - b, zero at the solution of the system.
+    pub(crate) fn residual_norm(&self, b: &DMatrix<f64>, x: &DMatrix<f64>) -> f64 {
+        match self {
+            Self::Dense(a) => (a * x - b).norm(),
+            Self::Sparse(a) => {
+                let mut residual = -b;
+                for (row, column, value) in a.entries() {
+                    residual[row] += value * x[column];
+                }
+                residual.norm()
+            }
+        }
+    }
+}
+
+/// Stamps every component of the netlist, the gmin of their junctions and the local references
+/// through the view.
+fn stamp_system(
+    netlist: &Netlist,
+    layout: &SystemLayout,
+    x: &DMatrix<f64>,
+    discretization: Discretization,
+    gmin: f64,
+    view: &mut ABMatrixView,
+) {
+    let num_nodes = layout.get_num_nodes();
+    let reference = netlist.get_reference_node();
+
+    for (index, c) in netlist.get_components().iter().enumerate() {
+        let variables_start = layout.get_variables_start(index);
+        view.select_component(c.num_variables(), variables_start);
+        let guess = XMatrixView::new(x, num_nodes, reference, c.num_variables(), variables_start);
+        discretization.stamp(c, view, &guess);
+
+        for (positive_node, negative_node) in c.junctions() {
+            view.conductance_add(positive_node, negative_node, gmin);
+        }
+    }
+
+    view.select_component(0, 0);
+    stamp_local_references(netlist, view, LOCAL_REFERENCE_CONDUCTANCE);
+}
 
-use crate::components::Netlist;
+/// Updates the state of every component from the converged solution x of a system discretized
+/// as given, returning the voltage of every node.
+pub(crate) fn update_components(
+    netlist: &mut Netlist,
+    layout: &SystemLayout,
+    x: &DMatrix<f64>,
+    discretization: Discretization,
+) -> Vec<f64> {
+    let num_nodes = layout.get_num_nodes();
+    let reference = netlist.get_reference_node();
+
+    for (index, c) in netlist.get_components_mut().iter_mut().enumerate() {
+        let view = XMatrixView::new(
+            x,
+            num_nodes,
+            reference,
+            c.num_variables(),
+            layout.get_variables_start(index),
+        );
+        discretization.update(c, &view);
+    }
+
+    matrix_view::node_voltages(x, num_nodes, reference)
+}
 
 /// A Backward Euler method solver for solving transient circuits.
+///
+/// Capacitors and inductors can be integrated with the trapezoidal rule instead, which keeps
+/// resonant circuits ringing at their amplitude, or the second order Gear method, which allows
+/// longer timesteps through stiff circuits (see [`SolverOptions::integration`]).
+///
+/// Large systems are assembled as sparse matrices and factorized by sparse LU instead of being
+/// inverted densely (see [`SolverOptions::sparse_threshold`]).
+///
+/// A solver borrows its netlist mutably, so it can be moved to a worker thread along with it
+/// (e.g. in [`std::thread::scope`]), and is `Send` and `Sync`.
 pub struct BESolver<'n> {
     netlist: &'n mut Netlist,
+    time: f64,
+    options: SolverOptions,
+    statistics: SolverStatistics,
+    rating_violations: Vec<RatingViolation>,
+    node_voltages: Vec<f64>,
+    last_solution: Option<DMatrix<f64>>,
+    last_step: Option<LastStep>,
+    warm_start: Option<DMatrix<f64>>,
+    adaptive_step: Option<f64>,
+    last_strategy: Option<ConvergenceStrategy>,
+    layout: SystemLayout,
+    ordering: ColumnOrdering,
+}
+
+/// What is needed to re-solve the most recent timestep.
+struct LastStep {
+    components: Vec<Component>,
+    dt: f64,
+    solution: DMatrix<f64>,
 }
 
 impl<'n> BESolver<'n> {
     /// Creates a new BESolver with a given number of nodes.
     pub fn new(netlist: &'n mut Netlist) -> Self {
-        Self { netlist }
+        netlist.take_dirty_region();
+        let layout = SystemLayout::new(netlist);
+        Self {
+            netlist,
+            time: 0.0,
+            options: SolverOptions::default(),
+            statistics: SolverStatistics::default(),
+            rating_violations: Vec::new(),
+            node_voltages: Vec::new(),
+            last_solution: None,
+            last_step: None,
+            warm_start: None,
+            adaptive_step: None,
+            last_strategy: None,
+            layout,
+            ordering: ColumnOrdering::default(),
+        }
+    }
+
+    /// Sets the options used to solve the circuit, replacing any refinement set before.
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn get_options(&self) -> SolverOptions {
+        self.options
+    }
+
+    /// Replaces the options between timesteps, e.g. loosening the tolerances over a quiet
+    /// stretch of a long simulation and tightening them again around an event. The source
+    /// ramp and the seed only take effect at the start of the simulation.
+    pub fn set_options(&mut self, options: SolverOptions) {
+        self.options = options;
+    }
+
+    /// Enables automatic timestep refinement around fast transients, a shorthand for setting
+    /// [`SolverOptions::refinement`].
+    pub fn with_refinement(mut self, refinement: Refinement) -> Self {
+        self.options.refinement = Some(refinement);
+        self
+    }
+
+    /// Sets the method integrating the capacitors and inductors, a shorthand for setting
+    /// [`SolverOptions::integration`].
+    pub fn with_integration(mut self, integration: IntegrationMethod) -> Self {
+        self.options.integration = integration;
+        self
+    }
+
+    /// Sets the tolerances and limits of the timesteps chosen by [`BESolver::solve_adaptive`],
+    /// a shorthand for setting [`SolverOptions::adaptive`].
+    pub fn with_adaptive_timestep(mut self, adaptive: AdaptiveTimestep) -> Self {
+        self.options.adaptive = adaptive;
+        self
+    }
+
+    /// Gets the layout of the system, as of the last timestep.
+    pub fn get_layout(&self) -> &SystemLayout {
+        &self.layout
+    }
+
+    /// Gets the counters describing the work done by the solver so far.
+    pub fn get_statistics(&self) -> SolverStatistics {
+        self.statistics
+    }
+
+    /// Gets the strategy that solved the most recent timestep or operating point, None before
+    /// the first.
+    pub fn get_last_strategy(&self) -> Option<ConvergenceStrategy> {
+        self.last_strategy
+    }
+
+    /// Gets the simulation time, the sum of all the timesteps solved so far.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn get_netlist(&self) -> &Netlist {
+        self.netlist
+    }
+
+    /// Gets a mutable reference to the netlist being solved, for editing it between timesteps.
+    pub fn get_netlist_mut(&mut self) -> &mut Netlist {
+        self.netlist
+    }
+
+    /// Gets the voltage of every node relative to the reference node at the end of the last
+    /// timestep, indexed by node number.
+    pub fn get_node_voltages(&self) -> &Vec<f64> {
+        &self.node_voltages
+    }
+
+    /// Gets the voltage of a node at the end of the last timestep.
+    pub fn get_node_voltage(&self, node: usize) -> f64 {
+        self.node_voltages.get(node).copied().unwrap_or(0.0)
+    }
+
+    /// Gets the solution of the system at the most recent timestep or operating point, laid out
+    /// by the layout.
+    pub(crate) fn get_solution(&self) -> Option<&DMatrix<f64>> {
+        self.warm_start.as_ref()
+    }
+
+    /// Gets every violation of a component rating found after a timestep so far. Violations
+    /// don't stop the simulation.
+    pub fn get_rating_violations(&self) -> &Vec<RatingViolation> {
+        &self.rating_violations
+    }
+
+    /// Gets the earliest discontinuity a component has scheduled after the current time, such
+    /// as a relay contact changing state.
+    pub fn next_breakpoint(&self) -> Option<f64> {
+        self.breakpoint_after(self.time)
+    }
+
+    /// Gets the earliest breakpoint scheduled after the given time.
+    fn breakpoint_after(&self, time: f64) -> Option<f64> {
+        self.netlist
+            .get_components()
+            .iter()
+            .filter_map(|c| c.breakpoint())
+            .filter(|&t| t > time)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Solves the next timestep of at most dt, cut short to end on the next breakpoint if one
+    /// falls within it, and returns the timestep taken.
+    pub fn step_to_breakpoint(&mut self, dt: f64) -> Result<f64, SolverError> {
+        SolverError::check_timestep(dt)?;
+        let dt = match self.next_breakpoint() {
+            Some(breakpoint) => dt.min(breakpoint - self.time),
+            None => dt,
+        };
+        self.solve(dt)?;
+        Ok(dt)
     }
 
     /// Solves the system for the next timestep dt.
-    pub fn solve(&mut self, dt: f64) {
+    ///
+    /// The timestep is split into substeps ending on every breakpoint falling within it (see
+    /// [`BESolver::next_breakpoint`]), so clocked components switch on time whatever the
+    /// timestep. The solver is left as it was before the timestep on failure (see
+    /// [`BESolver::try_solve`]).
+    pub fn solve(&mut self, dt: f64) -> Result<(), SolverError> {
+        SolverError::check_timestep(dt)?;
+        Ok(self.try_solve(dt)?)
+    }
+
+    /// Solves the system for the next timestep dt, returning a report of why and suggestions
+    /// of what to change if it can't be solved. The solver is left as it was before the
+    /// timestep on failure, so it can be retried after following a suggestion.
+    pub fn try_solve(&mut self, dt: f64) -> Result<(), Box<ConvergenceReport>> {
+        if self.time == 0.0 {
+            self.apply_source_ramp();
+            self.apply_seed();
+        }
+
+        let components = self.netlist.get_components().clone();
+        let time = self.time;
+
+        if let Err(report) = self.solve_to_breakpoints(dt) {
+            *self.netlist.get_components_mut() = components;
+            self.time = time;
+            return Err(report);
+        }
+
+        self.last_step = self.last_solution.take().map(|solution| LastStep {
+            components,
+            dt,
+            solution,
+        });
+
+        let violations = self.netlist.check_ratings(self.time);
+        self.rating_violations.extend(violations);
+        Ok(())
+    }
+
+    /// Solves the DC operating point of the circuit at the present time, with capacitors open
+    /// and inductors shorted, and writes it back into the components (see
+    /// [`BESolver::try_solve_dc`]).
+    pub fn solve_dc(&mut self) -> Result<(), SolverError> {
+        Ok(self.try_solve_dc()?)
+    }
+
+    /// Solves the DC operating point of the circuit at the present time, returning a report of
+    /// why and suggestions of what to change if it can't be solved.
+    ///
+    /// Capacitors are open and inductors shorted, and sources and switches stand as they are at
+    /// the present time. The operating point is written back into the components (capacitors
+    /// charged to their bias voltage, inductors carrying their bias current) and the node
+    /// voltages, and the next timestep starts its Newton-Raphson iteration from it, so a
+    /// transient run can begin from a consistent bias rather than from the initial conditions
+    /// of the components. The simulation time doesn't advance. The solver is left as it was
+    /// on failure.
+    pub fn try_solve_dc(&mut self) -> Result<(), Box<ConvergenceReport>> {
+        self.newton(Discretization::Dc)?;
+
+        // The operating point isn't a timestep, so there is none to roll back or re-solve.
+        self.last_solution = None;
+        self.last_step = None;
+        Ok(())
+    }
+
+    /// Solves the next timestep, choosing its length from the local truncation error of the
+    /// capacitors and inductors (see [`AdaptiveTimestep`], configured by
+    /// [`SolverOptions::adaptive`]), and returns the timestep taken.
+    ///
+    /// Timesteps whose error exceeds the tolerance are rolled back and solved again with a
+    /// shorter timestep, and the next timestep grows while the error stays well within it. The
+    /// timestep is at most max_dt (e.g. the time left until the end of the simulation) and is
+    /// cut short to end on the next breakpoint.
+    pub fn solve_adaptive(&mut self, max_dt: f64) -> Result<f64, SolverError> {
+        SolverError::check_timestep(max_dt)?;
+        Ok(self.try_solve_adaptive(max_dt)?)
+    }
+
+    /// Solves the next timestep like [`BESolver::solve_adaptive`], returning a report of why
+    /// and suggestions of what to change if it can't be solved.
+    pub fn try_solve_adaptive(&mut self, max_dt: f64) -> Result<f64, Box<ConvergenceReport>> {
+        let adaptive = self.options.adaptive;
+        let mut dt = self
+            .adaptive_step
+            .unwrap_or(adaptive.initial_step)
+            .min(adaptive.max_step);
+
+        loop {
+            // Ending on the breakpoint rather than splitting the timestep at it keeps the
+            // history of the components consistent with the timestep the error is estimated
+            // over.
+            let limit = match self.next_breakpoint() {
+                Some(breakpoint) => max_dt.min(breakpoint - self.time),
+                None => max_dt,
+            };
+            let step = dt.min(limit);
+
+            let before = self.netlist.get_components().clone();
+            self.try_solve(step)?;
+
+            let error_ratio = adaptive.error_ratio(&before, self.netlist.get_components(), step);
+            if error_ratio <= 1.0 || step <= adaptive.min_step {
+                // A timestep cut short by the limit keeps the next timestep as it was.
+                self.adaptive_step = Some(if step < dt {
+                    dt
+                } else {
+                    adaptive.next_step(step, error_ratio)
+                });
+                return Ok(step);
+            }
+
+            self.roll_back();
+            dt = adaptive.next_step(step, error_ratio);
+        }
+    }
+
+    /// Re-solves the most recent timestep after changing some component values.
+    ///
+    /// The timestep is rolled back and solved again starting the Newton-Raphson iteration from
+    /// its previous solution, which converges much faster than solving from scratch when the
+    /// changes are small (for example a knob being turned in a GUI). The column order of a
+    /// sparse system is reused too, as changing values doesn't change its pattern. Does nothing
+    /// but apply the changes if no timestep has been solved yet. Changes that can't be applied
+    /// are rejected before anything is rolled back or changed.
+    pub fn resolve_with_changes(&mut self, changes: &[ParamChange]) -> Result<(), SolverError> {
+        for change in changes {
+            change.check(self.netlist)?;
+        }
+        let Some(last_step) = self.last_step.take() else {
+            for change in changes {
+                change.apply(self.netlist)?;
+            }
+            return Ok(());
+        };
+
+        *self.netlist.get_components_mut() = last_step.components;
+        self.time -= last_step.dt;
+        self.rating_violations.retain(|v| v.time <= self.time);
+
+        for change in changes {
+            change.apply(self.netlist)?;
+        }
+
+        self.warm_start = Some(last_step.solution);
+        self.solve(last_step.dt)
+    }
+
+    /// Rolls back the most recent timestep and solves it again with the timestep dt instead,
+    /// e.g. a shorter one when the step turned out to change the circuit too much. Just solves
+    /// the next timestep if none has been solved yet.
+    pub fn redo_step(&mut self, dt: f64) -> Result<(), SolverError> {
+        self.roll_back();
+        self.solve(dt)
+    }
+
+    /// Rolls back the most recent timestep, if any.
+    fn roll_back(&mut self) {
+        if let Some(last_step) = self.last_step.take() {
+            *self.netlist.get_components_mut() = last_step.components;
+            self.time -= last_step.dt;
+            self.rating_violations.retain(|v| v.time <= self.time);
+            self.statistics.rollbacks += 1;
+        }
+    }
+
+    /// Gives every source without a ramp of its own the global soft start ramp, if enabled.
+    fn apply_source_ramp(&mut self) {
+        let Some(ramp_time) = self.options.source_ramp else {
+            return;
+        };
+
+        for component in self.netlist.get_components_mut() {
+            match component {
+                Component::VoltageSource(s) if s.get_ramp_time().is_none() => {
+                    *s = s.clone().with_ramp(ramp_time)
+                }
+                Component::CurrentSource(s) if s.get_ramp_time().is_none() => {
+                    *s = s.clone().with_ramp(ramp_time)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn apply_seed(&mut self) {
+        let Some(seed) = self.options.seed else {
+            return;
+        };
+
+        let rng = Rng::new(seed);
+        for (index, component) in self.netlist.get_components_mut().iter_mut().enumerate() {
+            let seed = rng.stream(index as u64).next_u64();
+            match component {
+                Component::Switch(s) => {
+                    if let Some(bounce) = s.get_bounce() {
+                        *s = s
+                            .clone()
+                            .with_bounce(bounce.with_jitter(bounce.jitter, seed));
+                    }
+                }
+                Component::NoiseSource(s) => *s = s.clone().with_seed(seed),
+                _ => {}
+            }
+        }
+    }
+
+    /// Solves the system for the next timestep dt in substeps ending on every breakpoint falling
+    /// within it.
+    fn solve_to_breakpoints(&mut self, dt: f64) -> Result<(), Box<ConvergenceReport>> {
+        let end = self.time + dt;
+        // Breakpoints this close to the start or the end of the timestep count as landed on.
+        let tolerance = BREAKPOINT_TOLERANCE * dt.abs();
+        loop {
+            let step_end = match self.breakpoint_after(self.time + tolerance) {
+                Some(breakpoint) if breakpoint < end - tolerance => breakpoint,
+                _ => end,
+            };
+            self.solve_refined(step_end - self.time)?;
+            if step_end == end {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Solves the system for the next timestep dt, refining the timestep if enabled.
+    fn solve_refined(&mut self, dt: f64) -> Result<(), Box<ConvergenceReport>> {
+        let Some(refinement) = self.options.refinement else {
+            return self.step(dt);
+        };
+
+        let snapshot = self.netlist.get_components().clone();
+        self.step(dt)?;
+
+        if refinement.is_fast_transient(&snapshot, self.netlist.get_components()) {
+            // Roll back and re-integrate the interval with a finer timestep.
+            *self.netlist.get_components_mut() = snapshot;
+            self.time -= dt;
+            self.statistics.rollbacks += 1;
+
+            let substep = dt / refinement.get_subdivisions() as f64;
+            for _ in 0..refinement.get_subdivisions() {
+                self.step(substep)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Solves a single timestep dt, rejecting it and solving it in two halves if it fails to
+    /// converge and step rejection is enabled. A singular system fails at once, as a shorter
+    /// timestep doesn't make it solvable.
+    fn step(&mut self, dt: f64) -> Result<(), Box<ConvergenceReport>> {
+        let Some(rejection) = self.options.step_rejection else {
+            return self.solve_step(dt);
+        };
+
+        let snapshot = self.netlist.get_components().clone();
+        match self.solve_step(dt) {
+            Err(report) if !report.singular && dt / 2.0 >= rejection.min_step => {
+                *self.netlist.get_components_mut() = snapshot;
+                self.statistics.rejected_steps += 1;
+                self.step(dt / 2.0)?;
+                self.step(dt / 2.0)
+            }
+            result => result,
+        }
+    }
+
+    /// Solves a single timestep dt.
+    fn solve_step(&mut self, dt: f64) -> Result<(), Box<ConvergenceReport>> {
+        self.newton(Discretization::Timestep(dt, self.options.integration))?;
+
+        self.time += dt;
+        self.statistics.steps += 1;
+        Ok(())
+    }
+
+    /// Solves the system discretized as given by Newton-Raphson iteration and updates the
+    /// components from the solution.
+    fn newton(&mut self, discretization: Discretization) -> Result<(), Box<ConvergenceReport>> {
         // Compute the dimensionality of the matrix we are to solve.
         //
-        // This is the number of nodes plus the number of voltages sources.
+        // This is the number of nodes plus the number of additional variables of the components.
         //
         // This is because for each node we have a variable (node voltages) and an equation (KCL at
         // that node).
         //
-        // For each voltages sources we have a variable (current through the voltage source) and an
-        // equation (setting the voltage potential between the two nodes).
-        let num_nodes = self.netlist.get_num_nodes();
-        let num_variables: usize = self
+        // For each additional variable we have a variable (e.g. current through a voltage source)
+        // and an equation (e.g. setting the voltage potential between the two nodes).
+        //
+        // The layout is kept from step to step, catching up with any edits to the netlist.
+        let changed = self.layout.update(self.netlist);
+        let dimension = self.layout.get_dimension();
+
+        // Nonlinear components are linearized around the previous guess so the system is solved
+        // repeatedly (Newton-Raphson) until the solution stops changing. The first guess is the
+        // solution of the previous timestep, which keeps circuits with several operating points
+        // on the one they are following, carried over to the new layout if the netlist was
+        // edited in between.
+        let x = match self.warm_start.take() {
+            Some(x) if changed => self.layout.carry_over(&x),
+            Some(x) if x.nrows() == dimension => x,
+            _ => DMatrix::zeros(dimension, 1),
+        };
+        let (x, strategy) = match self.iterate(discretization, self.options.gmin, x.clone()) {
+            Ok(x) => (x, ConvergenceStrategy::NewtonRaphson),
+            Err(report) => {
+                if let Some(x) = self.gmin_stepping(discretization, x.clone()) {
+                    self.statistics.gmin_steppings += 1;
+                    (x, ConvergenceStrategy::GminStepping)
+                } else if let Some(x) = self.source_stepping(discretization, x.clone()) {
+                    self.statistics.source_steppings += 1;
+                    (x, ConvergenceStrategy::SourceStepping)
+                } else {
+                    // Kept so a retry (e.g. with a shorter timestep) starts from the same guess.
+                    self.warm_start = Some(x);
+                    return Err(report);
+                }
+            }
+        };
+        self.last_strategy = Some(strategy);
+
+        self.node_voltages = update_components(self.netlist, &self.layout, &x, discretization);
+
+        // Kept so the step can be re-solved from this solution by resolve_with_changes.
+        self.warm_start = Some(x.clone());
+        self.last_solution = Some(x);
+        Ok(())
+    }
+
+    /// Solves the system by Newton-Raphson iteration from the guess x with the given gmin
+    /// across the nonlinear junctions, returning the solution.
+    fn iterate(
+        &mut self,
+        discretization: Discretization,
+        gmin: f64,
+        mut x: DMatrix<f64>,
+    ) -> Result<DMatrix<f64>, Box<ConvergenceReport>> {
+        let mut iterations = 0;
+        let mut history = IterationHistory::default();
+        let kinds = convergence::row_kinds(self.netlist);
+        loop {
+            let (a, b) = self.assemble_system(discretization, gmin, &x);
+            // The residual of the nonlinear system at x, which a line search has to reduce.
+            let residual = matches!(self.options.damping, Some(Damping::LineSearch { .. }))
+                .then(|| a.residual_norm(&b, &x));
+            let solution = match &a {
+                SystemMatrix::Sparse(a) => {
+                    if self.ordering.update(a) {
+                        self.statistics.orderings += 1;
+                    }
+                    let order = self.ordering.get_column_order();
+                    sparse::solve_linear(a, b, self.options.scaling, order)
+                }
+                SystemMatrix::Dense(a) => scaling::solve_linear(a.clone(), b, self.options.scaling),
+            };
+
+            let Some(solution) = solution else {
+                return Err(Box::new(self.convergence_report(
+                    discretization,
+                    iterations,
+                    true,
+                    history,
+                    &x,
+                    &a,
+                )));
+            };
+            let mut x_new = self.damp(discretization, gmin, &x, solution.x, residual);
+            if self.options.junction_limiting {
+                self.limit_junctions(&x, &mut x_new);
+            }
+            self.statistics.max_condition_number = self
+                .statistics
+                .max_condition_number
+                .max(solution.condition_number);
+
+            // The variable missing the convergence test by the most.
+            let (worst, excess) = x_new
+                .iter()
+                .zip(x.iter())
+                .zip(&kinds)
+                .enumerate()
+                .map(|(row, ((&new, &old), &kind))| {
+                    (row, self.options.convergence_excess(kind, new, old))
+                })
+                .fold((0, f64::NEG_INFINITY), |worst, (row, excess)| {
+                    if excess > worst.1 {
+                        (row, excess)
+                    } else {
+                        worst
+                    }
+                });
+            let converged = excess <= 0.0;
+
+            history.push(
+                IterationChange {
+                    iteration: iterations,
+                    variable: convergence::row_variable(self.netlist, worst),
+                    change: (x_new[worst] - x[worst]).abs(),
+                },
+                &x_new,
+            );
+            x = x_new;
+
+            iterations += 1;
+            self.statistics.newton_iterations += 1;
+
+            if converged {
+                return Ok(x);
+            }
+
+            if iterations >= self.options.max_iterations {
+                return Err(Box::new(self.convergence_report(
+                    discretization,
+                    iterations,
+                    false,
+                    history,
+                    &x,
+                    &a,
+                )));
+            }
+        }
+    }
+
+    /// Assembles the system linearized around x, sparse from the sparse threshold on.
+    fn assemble_system(
+        &self,
+        discretization: Discretization,
+        gmin: f64,
+        x: &DMatrix<f64>,
+    ) -> (SystemMatrix, DMatrix<f64>) {
+        if self.layout.get_dimension() >= self.options.sparse_threshold {
+            let (a, b) = assemble_sparse(self.netlist, &self.layout, x, discretization, gmin);
+            (SystemMatrix::Sparse(a), b)
+        } else {
+            let (a, b) = assemble(self.netlist, &self.layout, x, discretization, gmin);
+            (SystemMatrix::Dense(a), b)
+        }
+    }
+
+    /// Damps the Newton-Raphson update from x to x_new as configured by
+    /// [`SolverOptions::damping`], given the residual of the system at x for a line search.
+    fn damp(
+        &mut self,
+        discretization: Discretization,
+        gmin: f64,
+        x: &DMatrix<f64>,
+        x_new: DMatrix<f64>,
+        residual: Option<f64>,
+    ) -> DMatrix<f64> {
+        match self.options.damping {
+            None => x_new,
+            Some(Damping::StepLimit { max_voltage_step }) => {
+                let num_nodes = self.netlist.get_num_nodes();
+                let mut damped = x_new;
+                let mut limited = false;
+                // Only the node voltages are limited, the first rows of the solution.
+                for (new, &old) in damped.iter_mut().zip(x.iter()).take(num_nodes) {
+                    let change = *new - old;
+                    if change.abs() > max_voltage_step {
+                        *new = old + max_voltage_step.copysign(change);
+                        limited = true;
+                    }
+                }
+                if limited {
+                    self.statistics.damped_iterations += 1;
+                }
+                damped
+            }
+            Some(Damping::LineSearch { max_halvings }) => {
+                let Some(residual) = residual else {
+                    return x_new;
+                };
+                let step = &x_new - x;
+                let mut trial = x_new;
+                let mut lambda = 1.0;
+                for _ in 0..max_halvings {
+                    let (a, b) = self.assemble_system(discretization, gmin, &trial);
+                    if a.residual_norm(&b, &trial) < residual {
+                        break;
+                    }
+                    lambda /= 2.0;
+                    trial = x + &step * lambda;
+                }
+                if lambda < 1.0 {
+                    self.statistics.damped_iterations += 1;
+                }
+                trial
+            }
+        }
+    }
+
+    /// Lets every component limit its junction voltages in the update from x to x_new (see
+    /// [`Stampable::limit`]).
+    fn limit_junctions(&mut self, x: &DMatrix<f64>, x_new: &mut DMatrix<f64>) {
+        let num_nodes = self.layout.get_num_nodes();
+        let reference = self.netlist.get_reference_node();
+
+        let mut overrides = Vec::new();
+        for (index, c) in self.netlist.get_components().iter().enumerate() {
+            let variables_start = self.layout.get_variables_start(index);
+            let proposed = XMatrixView::new(
+                x_new,
+                num_nodes,
+                reference,
+                c.num_variables(),
+                variables_start,
+            );
+            let previous =
+                XMatrixView::new(x, num_nodes, reference, c.num_variables(), variables_start);
+            overrides.extend(c.limit(&proposed, &previous).into_iter().filter_map(
+                |(variable, value)| proposed.global_index(variable).map(|row| (row, value)),
+            ));
+        }
+
+        if !overrides.is_empty() {
+            self.statistics.limited_iterations += 1;
+        }
+        for (row, value) in overrides {
+            x_new[row] = value;
+        }
+    }
+
+    /// Solves the system by gmin stepping from the guess x (see [`GminStepping`]), if enabled,
+    /// returning the solution at the target gmin, or None if the steps became too small.
+    fn gmin_stepping(
+        &mut self,
+        discretization: Discretization,
+        x: DMatrix<f64>,
+    ) -> Option<DMatrix<f64>> {
+        let stepping = self.options.gmin_stepping?;
+        let target = self.options.gmin;
+
+        let mut gmin = stepping.get_initial_gmin().max(target);
+        let mut x = self.iterate(discretization, gmin, x).ok()?;
+        let mut factor = stepping.get_factor();
+        while gmin > target {
+            let next = (gmin / factor).max(target);
+            if next >= gmin {
+                return None;
+            }
+            match self.iterate(discretization, next, x.clone()) {
+                Ok(solution) => {
+                    x = solution;
+                    gmin = next;
+                    factor = (factor * factor).min(stepping.get_factor());
+                }
+                Err(_) => {
+                    factor = factor.sqrt();
+                    if factor < continuation::MIN_GMIN_FACTOR {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(x)
+    }
+
+    /// Solves the system by source stepping from the guess x (see [`SourceStepping`]), if
+    /// enabled, returning the solution at the full source values, or None if the steps became
+    /// too small. The sources are left at their full values either way.
+    fn source_stepping(
+        &mut self,
+        discretization: Discretization,
+        x: DMatrix<f64>,
+    ) -> Option<DMatrix<f64>> {
+        let stepping = self.options.source_stepping?;
+        let sources: Vec<(usize, f64)> = self
             .netlist
             .get_components()
             .iter()
-            .map(|c| c.num_variables())
-            .sum();
+            .enumerate()
+            .filter(|(_, c)| matches!(c, Component::VoltageSource(_) | Component::CurrentSource(_)))
+            .filter_map(|(index, c)| Some((index, c.get_value()?)))
+            .collect();
+        if sources.is_empty() {
+            return None;
+        }
 
-        let mut a = DMatrix::zeros(num_nodes + num_variables, num_nodes + num_variables);
+        let max_step = 1.0 / stepping.steps.max(1) as f64;
+        let mut step = max_step;
+        let mut scale = 0.0;
+        let mut solution = self.iterate_scaled(&sources, scale, discretization, x);
+        while let Some(x) = &solution {
+            if scale >= 1.0 {
+                break;
+            }
+            let next = (scale + step).min(1.0);
+            match self.iterate_scaled(&sources, next, discretization, x.clone()) {
+                Some(x) => {
+                    solution = Some(x);
+                    scale = next;
+                    step = (2.0 * step).min(max_step);
+                }
+                None => {
+                    step /= 2.0;
+                    if step < continuation::MIN_SOURCE_STEP {
+                        solution = None;
+                    }
+                }
+            }
+        }
 
-        let mut b = DMatrix::zeros(num_nodes + num_variables, 1);
+        for &(index, value) in &sources {
+            self.netlist.get_components_mut()[index].set_value(value);
+        }
+        solution
+    }
 
-        self.netlist
-            .get_components()
-            .iter()
-            .fold(num_nodes, |variables_start, c| {
-                let mut view = ABMatrixView::new(
-                    &mut a,
-                    &mut b,
-                    num_nodes,
-                    c.num_variables(),
-                    variables_start,
-                );
-                c.stamp(&mut view, dt);
-                variables_start + c.num_variables()
-            });
+    /// Solves the system by Newton-Raphson iteration from the guess x with the sources scaled
+    /// to the given fraction of their values.
+    fn iterate_scaled(
+        &mut self,
+        sources: &[(usize, f64)],
+        scale: f64,
+        discretization: Discretization,
+        x: DMatrix<f64>,
+    ) -> Option<DMatrix<f64>> {
+        for &(index, value) in sources {
+            self.netlist.get_components_mut()[index].set_value(scale * value);
+        }
+        self.iterate(discretization, self.options.gmin, x).ok()
+    }
 
-        let x = a.try_inverse().unwrap() * b;
+    /// Builds the report of a failed timestep (or operating point) from the last iteration's
+    /// solution and system matrix.
+    fn convergence_report(
+        &self,
+        discretization: Discretization,
+        iterations: usize,
+        singular: bool,
+        history: IterationHistory,
+        x: &DMatrix<f64>,
+        a: &SystemMatrix,
+    ) -> ConvergenceReport {
+        let dt = discretization.dt();
+        let mut remedies = Vec::new();
 
-        self.netlist
-            .get_components_mut()
-            .iter_mut()
-            .fold(num_nodes, |variables_start, c| {
-                let view = XMatrixView::new(&x, num_nodes, c.num_variables(), variables_start);
-                c.update(&view, dt);
-                variables_start + c.num_variables()
-            });
+        if singular {
+            let nodes = self.netlist.find_floating_nodes();
+            if !nodes.is_empty() {
+                remedies.push(Remedy::TieFloatingNodes { nodes });
+            }
+        }
+        if self.options.gmin < 1e-9 {
+            remedies.push(Remedy::IncreaseGmin { gmin: 1e-9 });
+        }
+        // The operating point has no timestep to shorten nor sources to ramp.
+        if !singular && discretization != Discretization::Dc {
+            remedies.push(Remedy::ReduceTimestep { dt: dt / 10.0 });
+
+            if self.time == 0.0
+                && self.options.source_ramp.is_none()
+                && convergence::has_sources(self.netlist)
+            {
+                remedies.push(Remedy::EnableSourceRamp {
+                    ramp_time: 10.0 * dt,
+                });
+            }
+
+            // Still converging if the changes kept shrinking over the second half.
+            let changes = &history.changes;
+            let middle = changes[changes.len() / 2].change;
+            if changes.last().is_some_and(|last| last.change < middle) {
+                remedies.push(Remedy::IncreaseMaxIterations {
+                    max_iterations: 10 * self.options.max_iterations,
+                });
+            }
+        }
+
+        ConvergenceReport {
+            time: self.time,
+            dt,
+            iterations,
+            singular,
+            culprit: convergence::find_culprit(self.netlist, &history),
+            worst_variables: convergence::last_changes(history.changes),
+            largest_stamps: convergence::largest_stamps(self.netlist, x, discretization),
+            extreme_nodes: convergence::extreme_nodes(self.netlist, &a.entries()),
+            remedies,
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        BESolver,
-        components::{Capacitor, CurrentSource, Inductor, Netlist, Resistor, VoltageSource},
+        ACSolver, AdaptiveTimestep, BESolver, ConvergenceStrategy, Damping, GminStepping,
+        IntegrationMethod, OptionPresets, Refinement, Remedy, SolverError, SolverOptions,
+        SourceStepping, StepRejection, Variable,
+        be_solver::{VariableKind, row_kinds},
+        components::{
+            AutomotivePulse, BenchSupply, Bjt, BjtPolarity, CapacitanceModel, Capacitor,
+            ClockedComparator, Compensator, Component, ConstantPhaseElement, ContactBounce,
+            ControlledSource, CurrentProbe, CurrentSource, CurrentTransformer, Diode, DiodeModel,
+            ElectronicLoad, FittedImpedance, FrequencyDivider, HallSensor, Inductor,
+            InductorSaturation, LoadMode, Netlist, NoiseSource, ParamChange, ParamError,
+            PhaseFrequencyDetector, PowerSpectralDensity, PulseGenerator, PvDatasheet, PvModule,
+            PvParameters, PwmController, RandlesCell, RatedQuantity, Ratings, Relay, Resistor,
+            SampleAndHold, Setpoint, ShuntReference, Switch, ThermoelectricModule,
+            ThermoelectricParameters, TransmissionGate, Vco, VoltageSource,
+        },
     };
 
+    use std::f64::consts::PI;
+
     use approx::assert_relative_eq;
+    use nalgebra::Complex;
 
     #[test]
     fn test_voltage_source_resistor() {
@@ -87,12 +1102,12 @@ mod test {
             .add_component(Resistor::new(1, 0, 2.0));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.001);
+        solver.solve(0.001).unwrap();
 
         println!("{:?}", netlist);
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let r: Resistor = netlist.get_components()[1].try_into().unwrap();
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
 
         assert_relative_eq!(v.get_voltage(), 10.0, max_relative = 0.001);
         assert_relative_eq!(v.get_current(), 5.0, max_relative = 0.001);
@@ -109,13 +1124,13 @@ mod test {
             .add_component(VoltageSource::new(2, 0, 5.0));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.001);
+        solver.solve(0.001).unwrap();
 
         println!("{:?}", netlist);
 
-        let v1: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let r: Resistor = netlist.get_components()[1].try_into().unwrap();
-        let v2: VoltageSource = netlist.get_components()[2].try_into().unwrap();
+        let v1: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        let v2: VoltageSource = netlist.get_components()[2].clone().try_into().unwrap();
 
         assert_relative_eq!(v1.get_voltage(), 10.0, max_relative = 0.001);
         assert_relative_eq!(v1.get_current(), 2.5, max_relative = 0.001);
@@ -134,13 +1149,13 @@ mod test {
             .add_component(Resistor::new(2, 0, 1.0));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.001);
+        solver.solve(0.001).unwrap();
 
         println!("{:?}", netlist);
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let r1: Resistor = netlist.get_components()[1].try_into().unwrap();
-        let r2: Resistor = netlist.get_components()[2].try_into().unwrap();
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r1: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        let r2: Resistor = netlist.get_components()[2].clone().try_into().unwrap();
 
         assert_relative_eq!(v.get_voltage(), 5.0, max_relative = 0.001);
         assert_relative_eq!(v.get_current(), 1.0, max_relative = 0.001);
@@ -151,145 +1166,2338 @@ mod test {
     }
 
     #[test]
-    fn test_current_source_resistor() {
+    fn test_voltage_source_series_resistance() {
         let mut netlist = Netlist::new();
         netlist
-            .add_component(CurrentSource::new(1, 0, 5.0))
-            .add_component(Resistor::new(1, 0, 2.0));
+            .add_component(VoltageSource::new(1, 0, 5.0).with_series_resistance(4.0))
+            .add_component(Resistor::new(1, 0, 1.0))
+            .add_component(VoltageSource::from_norton(2, 0, 2.5, 2.0))
+            .add_component(Resistor::new(2, 0, 2.0));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.001);
+        solver.solve(0.001).unwrap();
 
-        println!("{:?}", netlist);
+        assert_relative_eq!(solver.get_node_voltage(1), 1.0, max_relative = 1e-9);
+        assert_relative_eq!(solver.get_node_voltage(2), 2.5, max_relative = 1e-9);
 
-        let i: CurrentSource = netlist.get_components()[0].try_into().unwrap();
-        let r: Resistor = netlist.get_components()[1].try_into().unwrap();
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        assert_relative_eq!(v.get_current(), 1.0, max_relative = 1e-9);
+        assert_relative_eq!(v.get_terminal_voltage(), 1.0, max_relative = 1e-9);
+        assert_relative_eq!(v.get_power(), 1.0, max_relative = 1e-9);
+        assert_relative_eq!(v.get_norton_current().unwrap(), 1.25);
 
-        assert_relative_eq!(i.get_voltage(), 10.0, max_relative = 0.001);
-        assert_relative_eq!(i.get_current(), 5.0, max_relative = 0.001);
-        assert_relative_eq!(r.get_voltage(), 10.0, max_relative = 0.001);
-        assert_relative_eq!(r.get_current(), 5.0, max_relative = 0.001);
+        let norton: VoltageSource = netlist.get_components()[2].clone().try_into().unwrap();
+        assert_relative_eq!(norton.get_current(), 1.25, max_relative = 1e-9);
+        assert_eq!(VoltageSource::new(1, 0, 1.0).get_norton_current(), None);
     }
 
     #[test]
-    fn test_current_source_capacitor() {
+    fn test_electronic_load() {
+        // A 10W constant power load on a 12V source with 10 mohm of internal resistance settles
+        // on the higher solution of v * (12 - v) / 0.01 = 10.
         let mut netlist = Netlist::new();
         netlist
-            .add_component(CurrentSource::new(1, 0, 1.0))
-            .add_component(Capacitor::new(1, 0, 0.5, 0.0));
+            .add_component(VoltageSource::new(1, 0, 12.0).with_series_resistance(0.01))
+            .add_component(ElectronicLoad::new(
+                1,
+                0,
+                LoadMode::ConstantPower,
+                Setpoint::Constant(10.0),
+            ));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.25);
-
-        println!("{:?}", netlist);
-
-        let i: CurrentSource = netlist.get_components()[0].try_into().unwrap();
-        let c: Capacitor = netlist.get_components()[1].try_into().unwrap();
+        solver.solve(1e-4).unwrap();
+        assert_relative_eq!(
+            solver.get_node_voltage(1),
+            (12.0 + 143.6f64.sqrt()) / 2.0,
+            max_relative = 1e-6
+        );
+        let load: ElectronicLoad = netlist.get_components()[1].clone().try_into().unwrap();
+        assert_relative_eq!(load.get_power(), 10.0, max_relative = 1e-6);
 
-        assert_relative_eq!(i.get_voltage(), 0.50, max_relative = 0.001);
-        assert_relative_eq!(i.get_current(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(c.get_voltage(), 0.50, max_relative = 0.001);
-        assert_relative_eq!(c.get_current(), 1.0, max_relative = 0.001);
+        // A pulsed constant current load stepping from 0.5A to 2A.
+        let setpoint = Setpoint::Pulse {
+            initial: 0.5,
+            pulsed: 2.0,
+            delay: 1e-3,
+            rise_time: 1e-3,
+            fall_time: 1e-3,
+            width: 2e-3,
+            period: 0.0,
+        };
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0).with_series_resistance(0.1))
+            .add_component(ElectronicLoad::new(
+                1,
+                0,
+                LoadMode::ConstantCurrent,
+                setpoint,
+            ));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.75);
-
-        println!("{:?}", netlist);
-
-        let i: CurrentSource = netlist.get_components()[0].try_into().unwrap();
-        let c: Capacitor = netlist.get_components()[1].try_into().unwrap();
+        let mut voltages = Vec::new();
+        for _ in 0..60 {
+            solver.solve(1e-4).unwrap();
+            voltages.push(solver.get_node_voltage(1));
+        }
+        assert_relative_eq!(voltages[5], 4.95, max_relative = 1e-9);
+        assert_relative_eq!(voltages[14], 4.875, max_relative = 1e-9);
+        assert_relative_eq!(voltages[29], 4.8, max_relative = 1e-9);
+        assert_relative_eq!(voltages[59], 4.95, max_relative = 1e-9);
 
-        assert_relative_eq!(i.get_voltage(), 2.0, max_relative = 0.001);
-        assert_relative_eq!(i.get_current(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(c.get_voltage(), 2.0, max_relative = 0.001);
-        assert_relative_eq!(c.get_current(), 1.0, max_relative = 0.001);
+        let pwl = Setpoint::pwl(&[(0.0, 1.0), (1.0, 3.0), (2.0, 3.0)]);
+        assert_eq!(pwl.value_at(-1.0), 1.0);
+        assert_eq!(pwl.value_at(0.5), 2.0);
+        assert_eq!(pwl.value_at(5.0), 3.0);
     }
 
     #[test]
-    fn test_rc_step() {
+    fn test_warm_start() {
+        // A linear circuit starting from the previous solution converges at once.
         let mut netlist = Netlist::new();
         netlist
-            .add_component(VoltageSource::new(1, 0, 1.0))
-            .add_component(Resistor::new(1, 2, 1000.0))
-            .add_component(Capacitor::new(2, 0, 0.001, 0.0));
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Resistor::new(2, 0, 1e3));
 
         let mut solver = BESolver::new(&mut netlist);
-        for _ in 0..1000 {
-            solver.solve(0.001);
-        }
-
-        println!("{:?}", netlist);
+        solver.solve(1e-3).unwrap();
+        let cold = solver.get_statistics().newton_iterations;
+        solver.solve(1e-3).unwrap();
+        assert_eq!(solver.get_statistics().newton_iterations - cold, 1);
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let r: Resistor = netlist.get_components()[1].try_into().unwrap();
-        let c: Capacitor = netlist.get_components()[2].try_into().unwrap();
+        // A 10W constant power load on a 12V source with 1 ohm of internal resistance, slewed up
+        // from zero, follows the higher solution of v * (12 - v) = 10 instead of collapsing.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0).with_series_resistance(1.0))
+            .add_component(ElectronicLoad::new(
+                1,
+                0,
+                LoadMode::ConstantPower,
+                Setpoint::pwl(&[(1e-3, 0.0), (2e-3, 10.0)]),
+            ));
 
-        assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(v.get_current(), 0.000367879441171, max_relative = 0.001);
-        assert_relative_eq!(r.get_voltage(), 0.367879441171, max_relative = 0.001);
-        assert_relative_eq!(r.get_current(), 0.000367879441171, max_relative = 0.001);
-        assert_relative_eq!(c.get_voltage(), 0.632120558829, max_relative = 0.001);
-        assert_relative_eq!(c.get_current(), 0.000367879441171, max_relative = 0.001);
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..30 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert_relative_eq!(
+            solver.get_node_voltage(1),
+            (12.0 + 104f64.sqrt()) / 2.0,
+            max_relative = 1e-6
+        );
     }
 
     #[test]
-    fn test_voltage_source_inductor() {
-        let mut netlist = Netlist::new();
-        netlist
-            .add_component(VoltageSource::new(1, 0, 1.0))
-            .add_component(Inductor::new(1, 0, 0.5, 0.0));
+    fn test_pv_module() {
+        // Kyocera KC200GT datasheet figures.
+        let datasheet = PvDatasheet {
+            short_circuit_current: 8.21,
+            open_circuit_voltage: 32.9,
+            mpp_current: 7.61,
+            mpp_voltage: 26.3,
+            cells_in_series: 54,
+            current_temperature_coefficient: 3.18e-3,
+        };
+        let parameters = PvParameters::from_datasheet(&datasheet, 1.3).unwrap();
+        assert!(parameters.series_resistance > 0.1 && parameters.series_resistance < 0.4);
+        assert!(parameters.shunt_resistance > 50.0);
 
-        let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.25);
+        let operating_point = |module: PvModule, load: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(module)
+                .add_component(Resistor::new(1, 0, load));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
 
-        println!("{:?}", netlist);
+            let module: PvModule = netlist.get_components()[0].clone().try_into().unwrap();
+            (module.get_voltage(), module.get_current())
+        };
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let l: Inductor = netlist.get_components()[1].try_into().unwrap();
+        let module = PvModule::new(1, 0, parameters);
+        let (v, i) = operating_point(module.clone(), 26.3 / 7.61);
+        assert_relative_eq!(v, 26.3, max_relative = 1e-4);
+        assert_relative_eq!(i, 7.61, max_relative = 1e-4);
 
-        assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(v.get_current(), 0.5, max_relative = 0.001);
-        assert_relative_eq!(l.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(l.get_current(), 0.5, max_relative = 0.001);
+        let (_, isc) = operating_point(module.clone(), 1e-6);
+        assert_relative_eq!(isc, 8.21, max_relative = 1e-4);
+        let (voc, _) = operating_point(module.clone(), 1e9);
+        assert_relative_eq!(voc, 32.9, max_relative = 1e-4);
+
+        // Half the sun halves the short circuit current, and heat lowers the open circuit
+        // voltage.
+        let shaded = module.clone().with_irradiance(Setpoint::Constant(500.0));
+        assert_relative_eq!(operating_point(shaded, 1e-6).1, 4.105, max_relative = 1e-4);
+        let hot = module.with_temperature(60.0);
+        assert!(operating_point(hot, 1e9).0 < 30.0);
+    }
+
+    #[test]
+    fn test_randles_cell() {
+        let double_layer = ConstantPhaseElement::new(1.0, 0.9);
+
+        // The RC branches match the constant phase element well inside the band.
+        let omega = 2.0 * PI;
+        let ladder: Complex<f64> = double_layer
+            .branches(1e-3, 1e4)
+            .iter()
+            .map(|&(g, tau)| g * Complex::new(0.0, omega * tau) / Complex::new(1.0, omega * tau))
+            .sum();
+        assert_relative_eq!(
+            (ladder / double_layer.admittance(omega)).norm(),
+            1.0,
+            epsilon = 1e-2
+        );
+
+        // AC analysis uses the exact impedance.
+        let cell = RandlesCell::new(1, 0, 0.01, 0.02, double_layer).with_warburg(0.005);
+        let mut netlist = Netlist::new();
+        netlist.add_component(cell.clone());
+        let impedance = ACSolver::new(&netlist).port_impedance(1, 0, 1.0).unwrap();
+        assert_relative_eq!(impedance.re, cell.impedance(omega).re, epsilon = 1e-9);
+        assert_relative_eq!(impedance.im, cell.impedance(omega).im, epsilon = 1e-9);
+
+        // A current step first drops across the series resistance only, then charges the double
+        // layer until all of it flows through the charge transfer resistance.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 1.0))
+            .add_component(RandlesCell::new(1, 0, 0.01, 0.02, double_layer));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-4).unwrap();
+        assert!(solver.get_node_voltage(1) < 0.011);
+
+        for _ in 0..2000 {
+            solver.solve(1e-3).unwrap();
+        }
+        assert_relative_eq!(solver.get_node_voltage(1), 0.03, max_relative = 1e-2);
+        let cell: RandlesCell = netlist.get_components()[1].clone().try_into().unwrap();
+        assert_relative_eq!(cell.get_current(), 1.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_fitted_impedance() {
+        // A motor winding measured from 100Hz to 100kHz: 10 ohms of core loss in parallel with
+        // the 1 ohm, 1mH winding and its 1uF interwinding capacitance in series.
+        let frequencies: Vec<f64> = (0..=60)
+            .map(|k| 10f64.powf(2.0 + k as f64 / 20.0))
+            .collect();
+        let impedances: Vec<Complex<f64>> = frequencies
+            .iter()
+            .map(|f| {
+                let s = Complex::new(0.0, 2.0 * PI * f);
+                1.0 / (0.1 + 1.0 / (1.0 + s * 1e-3 + 1.0 / (s * 1e-6)))
+            })
+            .collect();
+        let magnitudes: Vec<f64> = impedances.iter().map(|z| z.norm()).collect();
+        let phases: Vec<f64> = impedances.iter().map(|z| z.arg().to_degrees()).collect();
+        let winding =
+            FittedImpedance::from_measurements(2, 0, &frequencies, &magnitudes, &phases, 2)
+                .unwrap();
+
+        // AC analysis uses the fitted admittance.
+        let mut netlist = Netlist::new();
+        netlist.add_component(FittedImpedance::new(1, 0, winding.get_model()));
+        let impedance = ACSolver::new(&netlist).port_impedance(1, 0, 5e3).unwrap();
+        let expected = winding.get_model().impedance(2.0 * PI * 5e3);
+        assert_relative_eq!(impedance.re, expected.re, epsilon = 1e-9);
+        assert_relative_eq!(impedance.im, expected.im, epsilon = 1e-9);
+
+        // Stepped through a 10 ohm resistor, the fitted winding rings like the network it was
+        // measured from.
+        let mut fitted = Netlist::new();
+        fitted
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(winding);
+        let mut network = Netlist::new();
+        network
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(Resistor::new(2, 0, 10.0))
+            .add_component(Resistor::new(2, 3, 1.0))
+            .add_component(Inductor::new(3, 4, 1e-3, 0.0))
+            .add_component(Capacitor::new(4, 0, 1e-6, 0.0));
+
+        let mut fitted_solver = BESolver::new(&mut fitted);
+        let mut network_solver = BESolver::new(&mut network);
+        for _ in 0..2000 {
+            fitted_solver.solve(1e-7).unwrap();
+            network_solver.solve(1e-7).unwrap();
+            assert_relative_eq!(
+                fitted_solver.get_node_voltage(2),
+                network_solver.get_node_voltage(2),
+                epsilon = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_thermoelectric_module() {
+        // A TEC1-12706 style Peltier module rated at a hot side of 27C.
+        let parameters = ThermoelectricParameters::from_ratings(6.4, 15.4, 68.0, 300.15);
+
+        // Driven at its maximum current with the hot side held at 27C and no heat load, the cold
+        // side settles at the rated maximum temperature difference. Node 2 is the hot side and
+        // node 3 the cold side, in kelvin.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 6.4))
+            .add_component(ThermoelectricModule::new(1, 0, 2, 3, parameters))
+            .add_component(VoltageSource::new(2, 0, 300.15));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        assert_relative_eq!(solver.get_node_voltage(3), 300.15 - 68.0, epsilon = 1e-6);
+        let tec: ThermoelectricModule = netlist.get_components()[1].clone().try_into().unwrap();
+        assert_relative_eq!(tec.get_voltage(), 15.4, epsilon = 1e-6);
+        assert_relative_eq!(tec.get_heat_absorbed(), 0.0, epsilon = 1e-6);
+        // Energy is conserved: the hot side gets the pumped heat plus the electrical power.
+        assert_relative_eq!(tec.get_heat_released(), tec.get_power(), epsilon = 1e-6);
+
+        // Held across a 10K difference and loaded with a matched resistance, it generates half
+        // its Seebeck voltage.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(ThermoelectricModule::new(1, 0, 2, 3, parameters))
+            .add_component(Resistor::new(1, 0, parameters.resistance))
+            .add_component(VoltageSource::new(2, 0, 310.0))
+            .add_component(VoltageSource::new(3, 0, 300.0));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        assert_relative_eq!(
+            solver.get_node_voltage(1),
+            5.0 * parameters.seebeck,
+            epsilon = 1e-9
+        );
+        let teg: ThermoelectricModule = netlist.get_components()[0].clone().try_into().unwrap();
+        assert!(teg.get_power() < 0.0);
+    }
+
+    #[test]
+    fn test_hall_sensor() {
+        // An ACS712-5A: 185mV/A centered on half of a 5V supply.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 2.0))
+            .add_component(HallSensor::new(2, 0, 3, 0, 0.185).with_offset(2.5))
+            .add_component(Resistor::new(3, 0, 10e3));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        let current = 5.0 / 2.001;
+        assert_relative_eq!(
+            solver.get_node_voltage(3),
+            2.5 + 0.185 * current,
+            epsilon = 1e-9
+        );
+        let sensor: HallSensor = netlist.get_components()[2].clone().try_into().unwrap();
+        assert_relative_eq!(sensor.get_current(), current, epsilon = 1e-9);
+        assert_relative_eq!(sensor.get_voltage(), 1e-3 * current, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_current_transformer() {
+        // A 1:100 transformer with 1H of magnetizing inductance into a 10 ohm burden.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 1.0).with_ac(1.0, 0.0))
+            .add_component(CurrentTransformer::new(1, 0, 2, 0, 100.0, 1.0).with_burden(10.0));
+
+        // At 50Hz nearly all of the primary current is transferred.
+        let omega = 2.0 * PI * 50.0;
+        let expected = 0.01 * Complex::new(0.0, omega) / Complex::new(10.0, omega);
+        let v = ACSolver::new(&netlist)
+            .solve(50.0)
+            .unwrap()
+            .get_node_voltage(2);
+        assert_relative_eq!(v.re, 10.0 * expected.re, epsilon = 1e-9);
+        assert_relative_eq!(v.im, 10.0 * expected.im, epsilon = 1e-9);
+
+        // A DC step is transferred at first, then droops as the magnetizing current takes over
+        // with the time constant L/R_burden = 0.1s.
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-5).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(2), 0.1, max_relative = 1e-3);
+        for _ in 0..1000 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert_relative_eq!(
+            solver.get_node_voltage(2),
+            0.1 * (-1f64).exp(),
+            max_relative = 1e-2
+        );
+        let ct: CurrentTransformer = netlist.get_components()[1].clone().try_into().unwrap();
+        assert_relative_eq!(
+            ct.get_secondary_current() + ct.get_magnetizing_current(),
+            0.01,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_controlled_sources() {
+        // Each source is controlled by 2V across, or 2mA through, the control branch of node 1.
+        let output = |source: ControlledSource| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 2.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(source)
+                .add_component(Resistor::new(3, 0, 100.0));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+            solver.get_node_voltage(3)
+        };
+
+        assert_relative_eq!(output(ControlledSource::vcvs(3, 0, 1, 0, 3.0)), 6.0);
+        assert_relative_eq!(output(ControlledSource::vccs(3, 0, 1, 0, 0.01)), 2.0);
+        assert_relative_eq!(output(ControlledSource::ccvs(3, 0, 2, 0, 1e3)), 2.0);
+        assert_relative_eq!(output(ControlledSource::cccs(3, 0, 2, 0, 10.0)), 2.0);
+
+        // A high gain limited source acts as a comparator.
+        let comparator = ControlledSource::vcvs(3, 0, 1, 0, 1e5).with_limits(0.0, 5.0);
+        assert_relative_eq!(output(comparator.clone()), 5.0);
+        assert_relative_eq!(output(comparator.with_limits(-5.0, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn test_shunt_reference() {
+        // A 5V shunt regulator: 10k/10k divider on the reference pin, fed from 12V through 470
+        // ohms. The cathode current i satisfies v = 2*(2.495 + i/2A/V) = 4.99 + i and
+        // i = (12 - v)/470 - v/20k, so i = 14.63mA.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(1, 2, 470.0))
+            .add_component(Resistor::new(2, 3, 10e3))
+            .add_component(Resistor::new(3, 0, 10e3))
+            .add_component(ShuntReference::new(2, 0, 3));
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..100 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert_relative_eq!(solver.get_node_voltage(2), 5.00463, epsilon = 1e-4);
+        let reference: ShuntReference = netlist.get_components()[4].clone().try_into().unwrap();
+        assert_relative_eq!(reference.get_current(), 0.0146335, max_relative = 1e-3);
+
+        // Open loop into 1k, the gain from the reference pin to the cathode is 2A/V * 1k
+        // rolling off above the 1kHz compensation pole.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(VoltageSource::new(3, 0, 2.5).with_ac(1.0, 0.0))
+            .add_component(ShuntReference::new(2, 0, 3));
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..100 {
+            solver.solve(1e-4).unwrap();
+        }
+        assert_relative_eq!(solver.get_node_voltage(2), 2.0, epsilon = 1e-6);
+
+        let ac = ACSolver::new(&netlist);
+        assert_relative_eq!(
+            ac.solve(1.0).unwrap().get_node_voltage(2).norm(),
+            2000.0,
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(
+            ac.solve(100e3).unwrap().get_node_voltage(2).norm(),
+            20.0,
+            max_relative = 1e-3
+        );
+
+        // Without the error reaching the reference voltage, no current flows.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 2.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(ShuntReference::new(2, 0, 2).with_compensation(None));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(2), 2.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_relay() {
+        // A 100 ohm, 1H coil driven from 12V rises to 60mA pull-in at tau*ln(2) = 6.93ms and
+        // closes the contact 5ms later. Its drive is then opened and the coil current freewheels
+        // through a flyback diode down to the 30mA drop-out.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(1, 2, 1e-3))
+            .add_component(Relay::new(2, 0, 3, 0, 100.0, 1.0, 60e-3, 30e-3).with_delays(5e-3, 2e-3))
+            .add_component(Diode::new(
+                0,
+                2,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ))
+            .add_component(VoltageSource::new(4, 0, 5.0))
+            .add_component(Resistor::new(4, 3, 1e3));
+
+        let relay = |solver: &BESolver| -> Relay {
+            solver.get_netlist().get_components()[2]
+                .clone()
+                .try_into()
+                .unwrap()
+        };
+
+        let mut solver = BESolver::new(&mut netlist);
+        while solver.get_time() < 8e-3 {
+            solver.step_to_breakpoint(1e-4).unwrap();
+        }
+        assert!(relay(&solver).is_pulled_in());
+        assert!(!relay(&solver).is_closed());
+        assert_relative_eq!(solver.get_node_voltage(3), 5.0, epsilon = 1e-3);
+
+        // The timestep is cut to land on the contact closing.
+        let breakpoint = solver.next_breakpoint().unwrap();
+        assert_relative_eq!(breakpoint, 2f64.ln() * 1e-2 + 5e-3, epsilon = 1e-4);
+        while solver.get_time() < 20e-3 {
+            solver.step_to_breakpoint(1e-4).unwrap();
+            if solver.get_time() < breakpoint - 1e-12 {
+                assert!(!relay(&solver).is_closed());
+            }
+        }
+        assert!(relay(&solver).is_closed());
+        assert_relative_eq!(
+            solver.get_node_voltage(3),
+            5.0 * 0.05 / 1e3,
+            max_relative = 1e-2
+        );
+        assert_eq!(solver.next_breakpoint(), None);
+
+        // Open the drive: the diode clamps the coil instead of letting it fly back.
+        solver.get_netlist_mut().get_components_mut()[1].set_value(1e9);
+        let mut minimum: f64 = 0.0;
+        while relay(&solver).is_closed() {
+            solver.step_to_breakpoint(1e-4).unwrap();
+            minimum = minimum.min(solver.get_node_voltage(2));
+        }
+        assert!(minimum > -1.0);
+        // The current decays faster than the coil time constant alone, as the diode adds its
+        // forward voltage: less than tau*ln(4) plus the release time.
+        let released = solver.get_time() - 20e-3;
+        assert!(released > 2e-3 && released < 4f64.ln() * 1e-2 + 2e-3);
+        assert_relative_eq!(solver.get_node_voltage(3), 5.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_bench_supply() {
+        let solve = |supply: BenchSupply, load: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(supply)
+                .add_component(Resistor::new(1, 0, load));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+            let supply: BenchSupply = netlist.get_components()[0].clone().try_into().unwrap();
+            supply
+        };
+        let supply = BenchSupply::new(1, 0, 12.0, 1.0);
+
+        // A light load sees the setpoint.
+        let cv = solve(supply.clone(), 100.0);
+        assert_relative_eq!(cv.get_voltage(), 12.0, max_relative = 1e-6);
+        assert_relative_eq!(cv.get_current(), 0.12, max_relative = 1e-6);
+        assert!(!cv.is_current_limited());
+
+        // A heavy load is fed the current limit, at whatever voltage that takes.
+        let cc = solve(supply.clone(), 5.0);
+        assert_relative_eq!(cc.get_current(), 1.0, max_relative = 2e-3);
+        assert_relative_eq!(cc.get_voltage(), 5.0, max_relative = 2e-3);
+        assert!(cc.is_current_limited());
+
+        // With foldback a short circuit only draws the short-circuit current, while loads below
+        // the limit still see the setpoint.
+        let foldback = supply.with_foldback(0.2);
+        let short = solve(foldback.clone(), 1e-2);
+        assert_relative_eq!(short.get_current(), 0.2, max_relative = 1e-2);
+        assert!(short.is_current_limited());
+        let cv = solve(foldback, 24.0);
+        assert_relative_eq!(cv.get_voltage(), 12.0, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn test_pwm_controller() {
+        // A 12V to 5V buck converter switching at 100kHz into 5 ohms, with a 0.05 ohm sense
+        // resistor in series with the switch sensed on node 6.
+        let buck = |controller: PwmController| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 12.0))
+                .add_component(Resistor::new(1, 5, 0.05))
+                .add_component(ControlledSource::vcvs(6, 0, 1, 5, 1.0))
+                .add_component(Switch::new(5, 2, 0.01, 1e6, 0.0).with_gate(4, 0, 5.0))
+                .add_component(Diode::new(0, 2, DiodeModel::Ideal))
+                .add_component(Inductor::new(2, 3, 47e-6, 0.0))
+                .add_component(Capacitor::new(3, 0, 47e-6, 0.0))
+                .add_component(Resistor::new(3, 0, 5.0))
+                .add_component(controller);
+            netlist
+        };
+        let run = |netlist: &mut Netlist, duration: f64, dt: f64| {
+            let mut solver = BESolver::new(netlist);
+            let mut samples = Vec::new();
+            while solver.get_time() < duration {
+                solver.step_to_breakpoint(dt).unwrap();
+                samples.push(solver.get_node_voltage(3));
+            }
+            samples
+        };
+
+        let mut netlist = buck(
+            PwmController::voltage_mode(3, 4, 0, 5.0, 100e3, 1.0).with_compensator(
+                Compensator::TypeII {
+                    gain: 0.008,
+                    zero_frequency: 3e3,
+                    pole_frequency: 20e3,
+                },
+            ),
+        );
+        // Voltage mode lands every edge on a breakpoint, settling at a duty cycle a little
+        // above 5/12 to make up for the losses.
+        let samples = run(&mut netlist, 5e-3, 2e-6);
+        for v in &samples[samples.len() - 500..] {
+            assert_relative_eq!(*v, 5.0, max_relative = 1e-2);
+        }
+        let controller: PwmController = netlist.get_components()[8].clone().try_into().unwrap();
+        assert!(controller.get_control() > 5.0 / 12.0 && controller.get_control() < 0.45);
+
+        // Peak current mode turns off up to a timestep late, sitting slightly off the reference.
+        let mut netlist = buck(
+            PwmController::peak_current_mode(3, 6, 4, 0, 5.0, 100e3, 0.2)
+                .with_slope_compensation(3e3)
+                .with_compensator(Compensator::TypeII {
+                    gain: 0.01,
+                    zero_frequency: 500.0,
+                    pole_frequency: 20e3,
+                }),
+        );
+        let samples = run(&mut netlist, 4e-3, 5e-7);
+        for v in &samples[samples.len() - 500..] {
+            assert_relative_eq!(*v, 5.0, max_relative = 2e-2);
+        }
+    }
+
+    #[test]
+    fn test_pll_lock() {
+        // A 1MHz reference and a VCO at 9MHz + 2MHz/V divided by 10, locking at 10MHz with a
+        // 100uA charge pump into a 22k/200pF/20pF loop filter (about 50kHz bandwidth).
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Vco::clock(1, 0, 1e6))
+            .add_component(Vco::new(4, 2, 0, 9e6, 2e6))
+            .add_component(FrequencyDivider::new(2, 3, 0, 10))
+            .add_component(PhaseFrequencyDetector::new(1, 3, 4, 0, 100e-6))
+            .add_component(Resistor::new(4, 5, 22e3))
+            .add_component(Capacitor::new(5, 0, 200e-12, 0.0))
+            .add_component(Capacitor::new(4, 0, 20e-12, 0.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        while solver.get_time() < 60e-6 {
+            solver.step_to_breakpoint(5e-9).unwrap();
+        }
+
+        let vco: Vco = solver.get_netlist().get_components()[1]
+            .clone()
+            .try_into()
+            .unwrap();
+        assert_relative_eq!(vco.get_frequency(), 10e6, max_relative = 0.005);
+        assert_relative_eq!(solver.get_node_voltage(5), 0.5, max_relative = 0.01);
+
+        // Locked, the divided VCO rises within a few timesteps of the reference.
+        let mut skew = 0.0f64;
+        let (mut reference_rise, mut feedback_rise) = (None, None);
+        let (mut reference_high, mut feedback_high) = (true, true);
+        while solver.get_time() < 70e-6 {
+            solver.step_to_breakpoint(5e-9).unwrap();
+            let reference = solver.get_node_voltage(1) > 0.5;
+            let feedback = solver.get_node_voltage(3) > 0.5;
+            if reference && !reference_high {
+                reference_rise = Some(solver.get_time());
+            }
+            if feedback && !feedback_high {
+                feedback_rise = Some(solver.get_time());
+            }
+            if let (Some(r), Some(f)) = (reference_rise, feedback_rise) {
+                skew = skew.max((r - f).abs());
+                (reference_rise, feedback_rise) = (None, None);
+            }
+            (reference_high, feedback_high) = (reference, feedback);
+        }
+        assert!(skew < 20e-9);
+    }
+
+    #[test]
+    fn test_sample_and_hold() {
+        // A 1MHz clock sampling a 100mV/us ramp 200ns after every rising edge. Edges are seen
+        // at the end of the timestep after them, 10ns late.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Vco::clock(1, 0, 1e6))
+            .add_component(VoltageSource::new(2, 0, 1.0).with_ramp(10e-6))
+            .add_component(SampleAndHold::new(2, 3, 1, 0).with_aperture_delay(200e-9))
+            .add_component(Resistor::new(3, 0, 1e6));
+
+        let mut solver = BESolver::new(&mut netlist);
+        for edge in 1..=3 {
+            while solver.get_time() < edge as f64 * 1e-6 + 500e-9 {
+                solver.step_to_breakpoint(10e-9).unwrap();
+            }
+            let expected = (edge as f64 * 1e-6 + 210e-9) / 10e-6;
+            let sample_and_hold: SampleAndHold = solver.get_netlist().get_components()[2]
+                .clone()
+                .try_into()
+                .unwrap();
+            assert_relative_eq!(
+                sample_and_hold.get_held_voltage(),
+                expected,
+                max_relative = 1e-6
+            );
+            assert_relative_eq!(solver.get_node_voltage(3), expected, max_relative = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_clocked_comparator() {
+        // A 100mV/us ramp against 0.5V, sampled 100ns after the rising edges of a 1MHz clock
+        // (seen 10ns late) by a latch regenerating with a 10ns time constant.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Vco::clock(1, 0, 1e6))
+            .add_component(VoltageSource::new(2, 0, 1.0).with_ramp(10e-6))
+            .add_component(VoltageSource::new(3, 0, 0.5))
+            .add_component(
+                ClockedComparator::new(2, 3, 1, 4, 0)
+                    .with_aperture_delay(100e-9)
+                    .with_regeneration(10e-9, 1e-3),
+            )
+            .add_component(Resistor::new(4, 0, 1e6));
+
+        let comparator = |solver: &BESolver| -> ClockedComparator {
+            solver.get_netlist().get_components()[3]
+                .clone()
+                .try_into()
+                .unwrap()
+        };
+        let mut solver = BESolver::new(&mut netlist);
+        while solver.get_time() < 5.5e-6 && !comparator(&solver).is_high() {
+            solver.step_to_breakpoint(10e-9).unwrap();
+        }
+
+        // The sample at 5.11us is 11mV above the reference, deciding after 10ns*ln(1V/11mV).
+        let decision = 5.11e-6 + 10e-9 * (1.0 / 0.011f64).ln();
+        assert_relative_eq!(solver.get_time(), decision, max_relative = 1e-6);
+        assert_relative_eq!(solver.get_node_voltage(4), 0.0, epsilon = 1e-5);
+        solver.step_to_breakpoint(10e-9).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(4), 1.0, max_relative = 1e-5);
+
+        // An offset of 10mV leaves the next sample, at 6.11us, within the metastability window.
+        solver
+            .get_netlist_mut()
+            .get_component_mut(3)
+            .set_value(0.1105);
+        while solver.get_time() < 6.5e-6 {
+            solver.step_to_breakpoint(10e-9).unwrap();
+        }
+        assert!(comparator(&solver).is_metastable());
+        assert_relative_eq!(solver.get_node_voltage(4), 0.5, max_relative = 1e-5);
+    }
+
+    #[test]
+    fn test_transmission_gate() {
+        // 10uA drawn through a 100Ω gate on a 5V supply: the drop follows the on-resistance
+        // at the signal level, flat over the middle of the supply and lower near the rails.
+        for input in [0.2, 2.5, 4.8] {
+            let gate = TransmissionGate::new(1, 2, 3, 0, 100.0);
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, input))
+                .add_component(VoltageSource::new(3, 0, 5.0))
+                .add_component(gate.clone())
+                .add_component(CurrentSource::new(0, 2, 1e-5));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-6).unwrap();
+            let resistance = (input - solver.get_node_voltage(2)) / 1e-5;
+            assert_relative_eq!(
+                resistance,
+                gate.on_resistance_at(input),
+                max_relative = 1e-3
+            );
+        }
+        let gate = TransmissionGate::new(1, 2, 3, 0, 100.0);
+        assert_relative_eq!(gate.on_resistance_at(2.5), 100.0, max_relative = 1e-6);
+        assert!(gate.on_resistance_at(0.2) < 90.0);
+        assert!(gate.on_resistance_at(4.8) < 90.0);
+
+        // Sampling 2V onto 1nF: turning the gate off injects half the -2pC channel charge into
+        // the capacitor, a 1mV pedestal, and the capacitor then holds as the input moves.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 2.0))
+            .add_component(VoltageSource::new(3, 0, 5.0))
+            .add_component(
+                TransmissionGate::new(1, 2, 3, 0, 100.0)
+                    .with_charge_injection(-2e-12)
+                    .with_off_capacitance(1e-12),
+            )
+            .add_component(Capacitor::new(2, 0, 1e-9, 0.0));
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..100 {
+            solver.solve(1e-8).unwrap();
+        }
+        let sampled = solver.get_node_voltage(2);
+        assert_relative_eq!(sampled, 2.0, max_relative = 1e-3);
+
+        solver.get_netlist_mut().get_component_mut(1).set_value(0.0);
+        solver.solve(1e-8).unwrap();
+        let held = solver.get_node_voltage(2);
+        assert_relative_eq!(held, sampled - 1e-3, epsilon = 1e-5);
+
+        // The feedthrough capacitance couples 1pF/1nF of the input step across the off gate.
+        solver.get_netlist_mut().get_component_mut(0).set_value(0.0);
+        for _ in 0..100 {
+            solver.solve(1e-8).unwrap();
+        }
+        assert_relative_eq!(
+            solver.get_node_voltage(2),
+            held - 2.0 * 1e-12 / 1.001e-9,
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_bjt() {
+        // A common emitter stage: 10V through 1kΩ to the collector, the base driven from Vbb
+        // through Rb.
+        let stage = |polarity: BjtPolarity, sign: f64, vbb: f64, rb: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, sign * 10.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(VoltageSource::new(3, 0, sign * vbb).with_ac(1.0, 0.0))
+                .add_component(Resistor::new(3, 4, rb))
+                .add_component(Bjt::new(2, 4, 0, polarity));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve_dc().unwrap();
+            netlist
+        };
+
+        // In the forward active region the collector current is beta times the base current.
+        let netlist = stage(BjtPolarity::Npn, 1.0, 2.0, 100e3);
+        let Component::Bjt(bjt) = &netlist.get_components()[4] else {
+            unreachable!()
+        };
+        let ib = (2.0 - bjt.get_base_emitter_voltage()) / 100e3;
+        assert_relative_eq!(bjt.get_base_current(), ib, max_relative = 1e-6);
+        assert_relative_eq!(bjt.get_current(), 100.0 * ib, max_relative = 1e-6);
+        assert_relative_eq!(
+            bjt.get_voltage(),
+            10.0 - 1e3 * bjt.get_current(),
+            epsilon = 1e-6
+        );
+        assert!(bjt.get_base_emitter_voltage() > 0.6 && bjt.get_base_emitter_voltage() < 0.7);
+        assert_relative_eq!(
+            bjt.get_power(),
+            bjt.get_voltage() * bjt.get_current()
+                + bjt.get_base_emitter_voltage() * bjt.get_base_current(),
+            max_relative = 1e-12
+        );
+
+        // The small signal gain is -gm*Rc over the divider of Rb and r_pi.
+        let gm = bjt.get_current() / 0.025852;
+        let r_pi = 100.0 / gm;
+        let gain = ACSolver::new(&netlist)
+            .solve(1e3)
+            .unwrap()
+            .get_node_voltage(2);
+        assert_relative_eq!(
+            gain.re,
+            -gm * 1e3 * r_pi / (r_pi + 100e3),
+            max_relative = 1e-3
+        );
+
+        // A PNP mirrors the NPN.
+        let mirrored = stage(BjtPolarity::Pnp, -1.0, 2.0, 100e3);
+        let Component::Bjt(pnp) = &mirrored.get_components()[4] else {
+            unreachable!()
+        };
+        assert_relative_eq!(pnp.get_current(), -bjt.get_current(), max_relative = 1e-9);
+        assert_relative_eq!(pnp.get_voltage(), -bjt.get_voltage(), max_relative = 1e-9);
+        assert_relative_eq!(pnp.get_power(), bjt.get_power(), max_relative = 1e-9);
+
+        // Overdriving the base saturates the transistor, the collector current falling short of
+        // beta times the base current.
+        let netlist = stage(BjtPolarity::Npn, 1.0, 5.0, 10e3);
+        let Component::Bjt(bjt) = &netlist.get_components()[4] else {
+            unreachable!()
+        };
+        assert!(bjt.get_voltage() < 0.2);
+        assert!(bjt.get_current() < 50.0 * bjt.get_base_current());
+        assert_relative_eq!(
+            bjt.get_emitter_current(),
+            bjt.get_current() + bjt.get_base_current(),
+            max_relative = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_current_source_resistor() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 0, 2.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+
+        println!("{:?}", netlist);
+
+        let i: CurrentSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(i.get_voltage(), 10.0, max_relative = 0.001);
+        assert_relative_eq!(i.get_current(), 5.0, max_relative = 0.001);
+        assert_relative_eq!(r.get_voltage(), 10.0, max_relative = 0.001);
+        assert_relative_eq!(r.get_current(), 5.0, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_current_source_capacitor() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 1.0))
+            .add_component(Capacitor::new(1, 0, 0.5, 0.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.25).unwrap();
+
+        println!("{:?}", netlist);
+
+        let i: CurrentSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let c: Capacitor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(i.get_voltage(), 0.50, max_relative = 0.001);
+        assert_relative_eq!(i.get_current(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(c.get_voltage(), 0.50, max_relative = 0.001);
+        assert_relative_eq!(c.get_current(), 1.0, max_relative = 0.001);
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.75).unwrap();
+
+        println!("{:?}", netlist);
+
+        let i: CurrentSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let c: Capacitor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(i.get_voltage(), 2.0, max_relative = 0.001);
+        assert_relative_eq!(i.get_current(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(c.get_voltage(), 2.0, max_relative = 0.001);
+        assert_relative_eq!(c.get_current(), 1.0, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_rc_step() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Capacitor::new(2, 0, 0.001, 0.0));
 
         let mut solver = BESolver::new(&mut netlist);
-        solver.solve(0.75);
+        for _ in 0..1000 {
+            solver.solve(0.001).unwrap();
+        }
 
         println!("{:?}", netlist);
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let l: Inductor = netlist.get_components()[1].try_into().unwrap();
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        let c: Capacitor = netlist.get_components()[2].clone().try_into().unwrap();
 
         assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(v.get_current(), 2.0, max_relative = 0.001);
-        assert_relative_eq!(l.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(l.get_current(), 2.0, max_relative = 0.001);
+        assert_relative_eq!(v.get_current(), 0.000367879441171, max_relative = 0.001);
+        assert_relative_eq!(r.get_voltage(), 0.367879441171, max_relative = 0.001);
+        assert_relative_eq!(r.get_current(), 0.000367879441171, max_relative = 0.001);
+        assert_relative_eq!(c.get_voltage(), 0.632120558829, max_relative = 0.001);
+        assert_relative_eq!(c.get_current(), 0.000367879441171, max_relative = 0.001);
     }
 
     #[test]
-    fn test_rl_step() {
+    fn test_rc_step_branch_current() {
         let mut netlist = Netlist::new();
         netlist
             .add_component(VoltageSource::new(1, 0, 1.0))
-            .add_component(Resistor::new(1, 2, 0.001))
-            .add_component(Inductor::new(2, 0, 0.01, 0.0));
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Capacitor::new(2, 0, 0.001, 0.0).with_branch_current());
 
         let mut solver = BESolver::new(&mut netlist);
         for _ in 0..1000 {
-            solver.solve(0.001);
+            solver.solve(0.001).unwrap();
         }
 
         println!("{:?}", netlist);
 
-        let v: VoltageSource = netlist.get_components()[0].try_into().unwrap();
-        let r: Resistor = netlist.get_components()[1].try_into().unwrap();
-        let l: Inductor = netlist.get_components()[2].try_into().unwrap();
+        let c: Capacitor = netlist.get_components()[2].clone().try_into().unwrap();
+
+        assert_relative_eq!(c.get_voltage(), 0.632120558829, max_relative = 0.001);
+        assert_relative_eq!(c.get_current(), 0.000367879441171, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_voltage_source_inductor() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Inductor::new(1, 0, 0.5, 0.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.25).unwrap();
+
+        println!("{:?}", netlist);
+
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let l: Inductor = netlist.get_components()[1].clone().try_into().unwrap();
 
         assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
-        assert_relative_eq!(v.get_current(), 95.162581964, max_relative = 0.001);
-        assert_relative_eq!(r.get_voltage(), 0.095162581964, max_relative = 0.001);
-        assert_relative_eq!(r.get_current(), 95.162581964, max_relative = 0.001);
-        assert_relative_eq!(l.get_voltage(), 0.904837418036, max_relative = 0.001);
-        assert_relative_eq!(l.get_current(), 95.162581964, max_relative = 0.001);
+        assert_relative_eq!(v.get_current(), 0.5, max_relative = 0.001);
+        assert_relative_eq!(l.get_voltage(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(l.get_current(), 0.5, max_relative = 0.001);
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.75).unwrap();
+
+        println!("{:?}", netlist);
+
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let l: Inductor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(v.get_current(), 2.0, max_relative = 0.001);
+        assert_relative_eq!(l.get_voltage(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(l.get_current(), 2.0, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_rl_step() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 0.001))
+            .add_component(Inductor::new(2, 0, 0.01, 0.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..1000 {
+            solver.solve(0.001).unwrap();
+        }
+
+        println!("{:?}", netlist);
+
+        let v: VoltageSource = netlist.get_components()[0].clone().try_into().unwrap();
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        let l: Inductor = netlist.get_components()[2].clone().try_into().unwrap();
+
+        assert_relative_eq!(v.get_voltage(), 1.0, max_relative = 0.001);
+        assert_relative_eq!(v.get_current(), 95.162581964, max_relative = 0.001);
+        assert_relative_eq!(r.get_voltage(), 0.095162581964, max_relative = 0.001);
+        assert_relative_eq!(r.get_current(), 95.162581964, max_relative = 0.001);
+        assert_relative_eq!(l.get_voltage(), 0.904837418036, max_relative = 0.001);
+        assert_relative_eq!(l.get_current(), 95.162581964, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_rl_step_branch_current() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 0.001))
+            .add_component(Inductor::new(2, 0, 0.01, 0.0).with_branch_current());
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..1000 {
+            solver.solve(0.001).unwrap();
+        }
+
+        println!("{:?}", netlist);
+
+        let l: Inductor = netlist.get_components()[2].clone().try_into().unwrap();
+
+        assert_relative_eq!(l.get_voltage(), 0.904837418036, max_relative = 0.001);
+        assert_relative_eq!(l.get_current(), 95.162581964, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_lc_tank_trapezoidal() {
+        // A 1uF capacitor charged to 1V ringing with a 1mH inductor at about 5kHz, with 100
+        // timesteps per period. Backward Euler damps the ringing, the trapezoidal rule keeps its
+        // amplitude.
+        let period = 2.0 * PI * (1e-3f64 * 1e-6).sqrt();
+        let dt = period / 100.0;
+        let peak_after = |integration: IntegrationMethod, branch_current: bool| {
+            let mut netlist = Netlist::new();
+            let (capacitor, inductor) = if branch_current {
+                (
+                    Capacitor::new(1, 0, 1e-6, 1.0).with_branch_current(),
+                    Inductor::new(1, 0, 1e-3, 0.0).with_branch_current(),
+                )
+            } else {
+                (
+                    Capacitor::new(1, 0, 1e-6, 1.0),
+                    Inductor::new(1, 0, 1e-3, 0.0),
+                )
+            };
+            netlist.add_component(capacitor).add_component(inductor);
+
+            let mut solver = BESolver::new(&mut netlist).with_integration(integration);
+            for _ in 0..1900 {
+                solver.solve(dt).unwrap();
+            }
+            // The peak over the 20th period.
+            (0..100).fold(0.0f64, |peak, _| {
+                solver.solve(dt).unwrap();
+                peak.max(solver.get_node_voltage(1).abs())
+            })
+        };
+
+        for branch_current in [false, true] {
+            assert!(peak_after(IntegrationMethod::BackwardEuler, branch_current) < 0.05);
+            assert_relative_eq!(
+                peak_after(IntegrationMethod::Trapezoidal, branch_current),
+                1.0,
+                max_relative = 0.01
+            );
+        }
+    }
+
+    #[test]
+    fn test_gear2() {
+        // An RC (tau = 1ms) charging to 1V and an RL (tau = 1ms) fluxing up to 1A, with timesteps
+        // of a tenth of tau. BDF2 is second order, so it lands much closer to the exact
+        // responses than backward Euler.
+        let response_error = |integration: IntegrationMethod, branch_current: bool| {
+            let mut netlist = Netlist::new();
+            let (capacitor, inductor) = if branch_current {
+                (
+                    Capacitor::new(2, 0, 1e-6, 0.0).with_branch_current(),
+                    Inductor::new(3, 0, 1e-3, 0.0).with_branch_current(),
+                )
+            } else {
+                (
+                    Capacitor::new(2, 0, 1e-6, 0.0),
+                    Inductor::new(3, 0, 1e-3, 0.0),
+                )
+            };
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(capacitor)
+                .add_component(Resistor::new(1, 3, 1.0))
+                .add_component(inductor);
+
+            let mut solver = BESolver::new(&mut netlist).with_integration(integration);
+            for _ in 0..20 {
+                solver.solve(1e-4).unwrap();
+            }
+            let exact = 1.0 - (-2.0f64).exp();
+            let inductor: Inductor = solver.get_netlist().get_components()[4]
+                .clone()
+                .try_into()
+                .unwrap();
+            (solver.get_node_voltage(2) - exact)
+                .abs()
+                .max((inductor.get_current() - exact).abs())
+        };
+
+        for branch_current in [false, true] {
+            assert!(response_error(IntegrationMethod::BackwardEuler, branch_current) > 5e-3);
+            assert!(response_error(IntegrationMethod::Gear2, branch_current) < 1e-3);
+        }
+
+        // With timesteps of ten times tau, the trapezoidal rule keeps ringing around 1V while
+        // BDF2 settles.
+        let settling_error = |integration: IntegrationMethod| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+            let mut solver = BESolver::new(&mut netlist).with_integration(integration);
+            for _ in 0..10 {
+                solver.solve(1e-2).unwrap();
+            }
+            (solver.get_node_voltage(2) - 1.0).abs()
+        };
+        assert!(settling_error(IntegrationMethod::Trapezoidal) > 1e-3);
+        assert!(settling_error(IntegrationMethod::Gear2) < 1e-5);
+    }
+
+    #[test]
+    fn test_dc_operating_point() {
+        // 10V through 1kΩ into 1kΩ in parallel with an inductor in series with 1kΩ, and a
+        // capacitor across: at DC the divider sees 500Ω, biasing node 2 at 10/3V.
+        for branch_current in [false, true] {
+            let (capacitor, inductor) = if branch_current {
+                (
+                    Capacitor::new(2, 0, 1e-6, 0.0).with_branch_current(),
+                    Inductor::new(2, 3, 1e-3, 0.0).with_branch_current(),
+                )
+            } else {
+                (
+                    Capacitor::new(2, 0, 1e-6, 0.0),
+                    Inductor::new(2, 3, 1e-3, 0.0),
+                )
+            };
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 10.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(Resistor::new(2, 0, 1e3))
+                .add_component(capacitor)
+                .add_component(inductor)
+                .add_component(Resistor::new(3, 0, 1e3))
+                .add_component(Resistor::new(1, 4, 1e3))
+                .add_component(Diode::new(
+                    4,
+                    0,
+                    DiodeModel::Shockley {
+                        saturation_current: 1e-14,
+                        emission_coefficient: 1.0,
+                    },
+                ));
+
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve_dc().unwrap();
+            assert_eq!(solver.get_time(), 0.0);
+
+            let bias = 10.0 / 3.0;
+            assert_relative_eq!(solver.get_node_voltage(2), bias, max_relative = 1e-6);
+            assert_relative_eq!(solver.get_node_voltage(3), bias, max_relative = 1e-6);
+            let capacitor: Capacitor = solver.get_netlist().get_components()[3]
+                .clone()
+                .try_into()
+                .unwrap();
+            let inductor: Inductor = solver.get_netlist().get_components()[4]
+                .clone()
+                .try_into()
+                .unwrap();
+            assert_relative_eq!(capacitor.get_voltage(), bias, max_relative = 1e-6);
+            assert_relative_eq!(capacitor.get_current(), 0.0, epsilon = 1e-12);
+            assert_relative_eq!(inductor.get_current(), bias / 1e3, max_relative = 1e-6);
+            let diode_voltage = solver.get_node_voltage(4);
+            assert!(diode_voltage > 0.5 && diode_voltage < 0.9);
+
+            // Starting from the operating point, the transient stays put.
+            for _ in 0..10 {
+                solver.solve(1e-4).unwrap();
+            }
+            assert_relative_eq!(solver.get_node_voltage(2), bias, max_relative = 1e-6);
+            assert_relative_eq!(solver.get_node_voltage(3), bias, max_relative = 1e-6);
+            assert_relative_eq!(
+                solver.get_node_voltage(4),
+                diode_voltage,
+                max_relative = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_adaptive_timestep() {
+        // An RC (tau = 1ms) charging to 1V over 10 tau: the timesteps start short where the
+        // voltage curves sharply and grow as it settles.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        let mut solver = BESolver::new(&mut netlist)
+            .with_adaptive_timestep(AdaptiveTimestep::new(1e-3, 1e-6).with_initial_step(1e-4));
+
+        let mut timesteps = Vec::new();
+        while solver.get_time() < 1e-2 {
+            timesteps.push(solver.solve_adaptive(1e-2 - solver.get_time()).unwrap());
+            let exact = 1.0 - (-solver.get_time() / 1e-3).exp();
+            assert!((solver.get_node_voltage(2) - exact).abs() < 1e-2);
+        }
+        println!("{} timesteps: {timesteps:?}", timesteps.len());
+        println!("{:?}", solver.get_statistics());
+
+        assert!((solver.get_time() - 1e-2).abs() < 1e-12);
+        assert!(timesteps.len() < 100);
+        assert!(timesteps[timesteps.len() - 2] > 10.0 * timesteps[1]);
+        // The initial timestep is too long for the tolerance once there is history to judge it.
+        assert!(solver.get_statistics().rollbacks > 0);
+    }
+
+    #[test]
+    fn test_voltage_source_resistor_diode() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+
+        println!("{:?}", netlist);
+
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        let d: Diode = netlist.get_components()[2].clone().try_into().unwrap();
+
+        assert_relative_eq!(r.get_voltage(), 4.307456366819, max_relative = 0.001);
+        assert_relative_eq!(r.get_current(), 0.004307456366819, max_relative = 0.001);
+        assert_relative_eq!(d.get_voltage(), 0.692543633181, max_relative = 0.001);
+        assert_relative_eq!(d.get_current(), 0.004307456366819, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_switch_bounce() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(
+                Switch::new(1, 2, 0.001, 1e9, 0.010)
+                    .with_bounce(ContactBounce::new(2, 0.002, 0.001, 0.5)),
+            )
+            .add_component(Resistor::new(2, 0, 1.0));
+
+        // Closed from 10ms to 12ms, open until 13ms, closed until 14ms, open until 14.5ms and
+        // closed from then on.
+        let expected = [
+            (0.005, false),
+            (0.011, true),
+            (0.0125, false),
+            (0.0135, true),
+            (0.01425, false),
+            (0.020, true),
+        ];
+
+        let mut solver = BESolver::new(&mut netlist);
+        let mut time = 0.0;
+        for (t, closed) in expected {
+            while time < t - 1e-9 {
+                solver.solve(0.00025).unwrap();
+                time += 0.00025;
+            }
+
+            let r: Resistor = solver.netlist.get_components()[2]
+                .clone()
+                .try_into()
+                .unwrap();
+            let expected_voltage = if closed { 1.0 } else { 0.0 };
+            assert_relative_eq!(r.get_voltage(), expected_voltage, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn test_rc_step_refinement() {
+        let rc_netlist = || {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Switch::new(1, 2, 1e-6, 1e12, 0.0045))
+                .add_component(Resistor::new(2, 3, 1000.0))
+                .add_component(Capacitor::new(3, 0, 0.000001, 0.0));
+            netlist
+        };
+
+        let mut coarse_netlist = rc_netlist();
+        let mut coarse = BESolver::new(&mut coarse_netlist);
+        for _ in 0..6 {
+            coarse.solve(0.001).unwrap();
+        }
+        assert_eq!(coarse.get_statistics().rollbacks, 0);
+        assert_eq!(coarse.get_statistics().steps, 6);
+
+        let mut refined_netlist = rc_netlist();
+        let mut refined =
+            BESolver::new(&mut refined_netlist).with_refinement(Refinement::new(0.2, 100));
+        for _ in 0..6 {
+            refined.solve(0.001).unwrap();
+        }
+        assert!(refined.get_statistics().rollbacks > 0);
+        assert_relative_eq!(refined.get_time(), 0.006, max_relative = 0.001);
+
+        // The switch closes at 4.5ms, so by 6ms the capacitor should have charged for 1.5ms.
+        let expected = 1.0 - (-1.5f64).exp();
+        let coarse_c: Capacitor = coarse_netlist.get_components()[3]
+            .clone()
+            .try_into()
+            .unwrap();
+        let refined_c: Capacitor = refined_netlist.get_components()[3]
+            .clone()
+            .try_into()
+            .unwrap();
+
+        assert_relative_eq!(refined_c.get_voltage(), expected, max_relative = 0.02);
+        assert!(
+            (refined_c.get_voltage() - expected).abs() < (coarse_c.get_voltage() - expected).abs()
+        );
+    }
+
+    #[test]
+    fn test_refinement_from_rest() {
+        // A capacitor charging from zero in small timesteps never changes fast enough to refine.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        let mut solver = BESolver::new(&mut netlist).with_refinement(Refinement::new(0.2, 10));
+        for _ in 0..100 {
+            solver.solve(1e-6).unwrap();
+        }
+        assert_eq!(solver.get_statistics().rollbacks, 0);
+        assert_eq!(solver.get_statistics().steps, 100);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_refinement_without_subdivisions() {
+        Refinement::new(0.2, 0);
+    }
+
+    #[test]
+    fn test_current_source_junction_capacitor_charge() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(CurrentSource::new(1, 0, 0.001))
+            .add_component(Capacitor::nonlinear(
+                0,
+                1,
+                CapacitanceModel::Junction {
+                    zero_bias_capacitance: 0.001,
+                    junction_potential: 0.7,
+                    grading_coefficient: 0.5,
+                },
+                0.0,
+            ));
+
+        // Alternate between timesteps so a naive C*dv/dt formulation would drift.
+        let mut solver = BESolver::new(&mut netlist);
+        for i in 0..100 {
+            solver.solve(if i % 2 == 0 { 0.01 } else { 0.03 }).unwrap();
+        }
+
+        println!("{:?}", netlist);
+
+        let c: Capacitor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(c.get_charge(), -0.002, max_relative = 1e-6);
+        assert_relative_eq!(
+            c.get_model().charge(c.get_voltage()),
+            -0.002,
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(c.get_current(), -0.001, max_relative = 1e-3);
+    }
+
+    #[test]
+    fn test_diode_isolated_node_gmin() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Diode::new(
+                2,
+                1,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            gmin: 1e-9,
+            ..Default::default()
+        });
+        solver.solve(0.001).unwrap();
+
+        println!("{:?}", netlist);
+
+        // With the diode off node 2 is only held by gmin, pulling it up to node 1.
+        let d: Diode = netlist.get_components()[1].clone().try_into().unwrap();
+
+        assert_relative_eq!(d.get_voltage(), 0.0, epsilon = 1e-6);
+        assert_relative_eq!(d.get_current(), 0.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_voltage_source_saturating_inductor() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(
+                Inductor::new(1, 0, 0.001, 0.0)
+                    .with_saturation(InductorSaturation::new(1.0, 0.0001)),
+            );
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..100 {
+            solver.solve(0.0001).unwrap();
+        }
+
+        println!("{:?}", netlist);
+
+        let l: Inductor = netlist.get_components()[1].clone().try_into().unwrap();
+
+        // After 10ms at 1V the flux linkage is 10mWb. An unsaturated inductor would be at 10A,
+        // the saturated one carries much more.
+        assert_relative_eq!(l.flux(l.get_current()), 0.01, max_relative = 1e-6);
+        assert!(l.get_current() > 50.0);
+        assert!(l.incremental_inductance(l.get_current()) < 0.00011);
+    }
+
+    #[test]
+    fn test_rating_violations() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(Resistor::new(2, 0, 10.0))
+            .set_ratings(1, Ratings::new().with_max_power(0.25))
+            .set_ratings(2, Ratings::new().with_max_voltage(10.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+        solver.solve(0.001).unwrap();
+
+        // Each resistor dissipates 2.5W at 5V, so only the power rating is exceeded.
+        let violations = solver.get_rating_violations();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].component, 1);
+        assert_eq!(violations[0].quantity, RatedQuantity::Power);
+        assert_relative_eq!(violations[0].value, 2.5, max_relative = 0.001);
+        assert_relative_eq!(violations[0].limit, 0.25);
+        assert_relative_eq!(violations[0].time, 0.001);
+        assert_relative_eq!(violations[1].time, 0.002);
+    }
+
+    #[test]
+    fn test_resolve_with_changes() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+        let cold_iterations = solver.get_statistics().newton_iterations;
+
+        solver
+            .resolve_with_changes(&[ParamChange::new(1, 1010.0)])
+            .unwrap();
+        let warm_iterations = solver.get_statistics().newton_iterations - cold_iterations;
+
+        assert!(warm_iterations < cold_iterations);
+        assert_relative_eq!(solver.get_time(), 0.001);
+
+        // A change to the diode, which has no primary value, leaves the step as it was.
+        assert_eq!(
+            solver.resolve_with_changes(&[ParamChange::new(1, 1020.0), ParamChange::new(2, 1.0)]),
+            Err(SolverError::InvalidChange(ParamError::NoValue(2)))
+        );
+        assert_relative_eq!(solver.get_time(), 0.001);
+        assert_eq!(
+            solver.get_netlist().get_components()[1].get_value(),
+            Some(1010.0)
+        );
+
+        // Solving the changed circuit from scratch gives the same answer.
+        let resolved = solver.get_netlist().get_components().clone();
+        let mut netlist = solver.get_netlist().clone();
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+
+        let d: Diode = resolved[2].clone().try_into().unwrap();
+        let expected: Diode = netlist.get_components()[2].clone().try_into().unwrap();
+        assert_relative_eq!(d.get_current(), expected.get_current(), max_relative = 1e-4);
+    }
+
+    #[test]
+    fn test_source_soft_start() {
+        let peak_capacitor_current = |options: SolverOptions, source: VoltageSource| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(source)
+                .add_component(Resistor::new(1, 2, 1.0))
+                .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+            let mut solver = BESolver::new(&mut netlist).with_options(options);
+            let mut peak: f64 = 0.0;
+            for _ in 0..200 {
+                solver.solve(1e-6).unwrap();
+                peak = peak.max(solver.get_netlist().get_components()[2].get_current().abs());
+            }
+
+            assert_relative_eq!(solver.get_node_voltage(2), 5.0, max_relative = 1e-3);
+            peak
+        };
+
+        let options = SolverOptions::default();
+        let step = peak_capacitor_current(options, VoltageSource::new(1, 0, 5.0));
+        assert!(step > 1.0);
+
+        // Ramping over 100us limits the current to about C*dV/dt = 50mA.
+        let ramped = peak_capacitor_current(options, VoltageSource::new(1, 0, 5.0).with_ramp(1e-4));
+        assert!(ramped < 0.06);
+
+        let global = SolverOptions {
+            source_ramp: Some(1e-4),
+            ..Default::default()
+        };
+        assert_relative_eq!(
+            peak_capacitor_current(global, VoltageSource::new(1, 0, 5.0)),
+            ramped
+        );
+    }
+
+    #[test]
+    fn test_reference_node() {
+        // Nothing is connected to node 0, node 2 is the reference.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 2, 10.0))
+            .add_component(Resistor::new(1, 3, 1e3))
+            .add_component(Resistor::new(3, 2, 1e3));
+        netlist.set_reference_node(2);
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        assert_relative_eq!(solver.get_node_voltage(1), 10.0, max_relative = 1e-6);
+        assert_relative_eq!(solver.get_node_voltage(2), 0.0);
+        assert_relative_eq!(solver.get_node_voltage(3), 5.0, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn test_local_reference() {
+        // An isolated secondary coupled to the primary through a capacitor.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Capacitor::new(1, 2, 1e-6, 0.0))
+            .add_component(VoltageSource::new(3, 2, 5.0))
+            .add_component(Resistor::new(3, 2, 1e3));
+        netlist.add_local_reference(2);
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..10 {
+            solver.solve(1e-3).unwrap();
+        }
+
+        assert_relative_eq!(
+            solver.get_node_voltage(3) - solver.get_node_voltage(2),
+            5.0,
+            max_relative = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_current_probe() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(CurrentProbe::new(1, 2))
+            .add_component(Resistor::new(2, 0, 5.0))
+            .set_component_name(1, "i_load");
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        let probe = netlist.find_component("i_load").unwrap();
+        let probe: CurrentProbe = netlist.get_components()[probe].clone().try_into().unwrap();
+        assert_relative_eq!(probe.get_current(), 2.0, max_relative = 1e-9);
+        assert_eq!(netlist.find_component("R2"), Some(2));
+        assert_eq!(netlist.find_component("missing"), None);
+    }
+
+    #[test]
+    fn test_global_seed() {
+        let bounce_seed = |seed: Option<u64>| {
+            let bounce = ContactBounce::new(3, 1e-4, 5e-5, 0.5).with_jitter(0.5, 7);
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Switch::new(1, 2, 0.1, 1e6, 0.0).with_bounce(bounce))
+                .add_component(Resistor::new(2, 0, 1e3));
+
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                seed,
+                ..Default::default()
+            });
+            solver.solve(1e-6).unwrap();
+
+            let switch: Switch = netlist.get_components()[1].clone().try_into().unwrap();
+            switch.get_bounce().unwrap().seed
+        };
+
+        // Without a global seed the component keeps its own.
+        assert_eq!(bounce_seed(None), 7);
+        assert_eq!(bounce_seed(Some(1)), bounce_seed(Some(1)));
+        assert_ne!(bounce_seed(Some(1)), bounce_seed(Some(2)));
+    }
+
+    #[test]
+    fn test_sparse_ladder() {
+        // A 150 section RC ladder clamped by diodes every tenth node, solved with the sparse LU
+        // and with the dense inverse.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Inductor::new(1, 2, 1e-6, 0.0).with_branch_current());
+        for node in 2..152 {
+            netlist
+                .add_component(Resistor::new(node, node + 1, 10.0))
+                .add_component(Capacitor::new(node + 1, 0, 1e-9, 0.0));
+            if node % 10 == 0 {
+                netlist.add_component(Diode::new(
+                    node,
+                    0,
+                    DiodeModel::Shockley {
+                        saturation_current: 1e-14,
+                        emission_coefficient: 1.0,
+                    },
+                ));
+            }
+        }
+
+        let run = |sparse_threshold: usize| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                sparse_threshold,
+                ..SolverOptions::default()
+            });
+            for _ in 0..5 {
+                solver.solve(1e-7).unwrap();
+            }
+            // Re-solving a step after a value change keeps the layout and so the ordering.
+            solver
+                .resolve_with_changes(&[ParamChange::new(3, 20.0)])
+                .unwrap();
+            solver
+                .resolve_with_changes(&[ParamChange::new(3, 10.0)])
+                .unwrap();
+            let statistics = solver.get_statistics();
+            assert!(statistics.max_condition_number > 1.0);
+            assert_eq!(statistics.orderings, usize::from(sparse_threshold == 0));
+            solver.get_node_voltages().clone()
+        };
+        let sparse = run(0);
+        let dense = run(usize::MAX);
+
+        // The first diode clamps its node.
+        assert!(sparse[10] > 0.6 && sparse[10] < 0.8);
+        for (sparse, dense) in sparse.iter().zip(&dense) {
+            assert_relative_eq!(sparse, dense, max_relative = 1e-9, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_option_presets() {
+        let presets = OptionPresets::new();
+
+        let diode_voltage = |options: SolverOptions| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 5.0))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(Diode::new(
+                    2,
+                    0,
+                    DiodeModel::Shockley {
+                        saturation_current: 1e-14,
+                        emission_coefficient: 1.0,
+                    },
+                ));
+
+            let mut solver = BESolver::new(&mut netlist).with_options(options);
+            for _ in 0..10 {
+                solver.solve(1e-6).unwrap();
+            }
+            solver.get_node_voltage(2)
+        };
+
+        let reference = diode_voltage(presets.get("accurate").unwrap());
+        for name in presets.names() {
+            let voltage = diode_voltage(presets.get(name).unwrap());
+            assert_relative_eq!(voltage, reference, max_relative = 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_convergence_report() {
+        // Node 2 is only driven by a current source, so nothing defines its voltage.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .add_component(CurrentSource::new(2, 0, 1e-3));
+
+        let mut solver = BESolver::new(&mut netlist);
+        let report = solver.try_solve(1e-3).unwrap_err();
+        assert!(report.singular);
+        assert_eq!(report.iterations, 0);
+        assert!(
+            report
+                .remedies
+                .contains(&Remedy::TieFloatingNodes { nodes: vec![2] })
+        );
+        assert_eq!(solver.get_time(), 0.0);
+
+        // A diode which can't converge in a couple of iterations of raw Newton-Raphson.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e-3))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            max_iterations: 2,
+            junction_limiting: false,
+            step_rejection: None,
+            ..Default::default()
+        });
+        let report = solver.try_solve(1e-3).unwrap_err();
+        assert!(!report.singular);
+        assert_eq!(report.iterations, 2);
+        assert_eq!(report.worst_variables.len(), 2);
+        assert_eq!(
+            report.worst_variables[1].variable,
+            Variable::ComponentVariable {
+                component: 0,
+                index: 0
+            }
+        );
+        // The diode, linearized far up its exponential, stamps the largest conductance.
+        assert_eq!(report.largest_stamps[0].component, 2);
+        assert!(
+            report
+                .remedies
+                .contains(&Remedy::ReduceTimestep { dt: 1e-4 })
+        );
+        assert!(report.to_string().starts_with("Solver failed to converge"));
+    }
+
+    #[test]
+    fn test_variable_kinds() {
+        use VariableKind::{Current, Voltage};
+
+        // The internal nodes of a Randles cell and the error of a shunt reference are voltages,
+        // the other additional variables currents.
+        let double_layer = ConstantPhaseElement::new(1.0, 0.9);
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(RandlesCell::new(1, 0, 0.01, 0.02, double_layer).with_warburg(0.005))
+            .add_component(Resistor::new(1, 2, 470.0))
+            .add_component(ShuntReference::new(2, 0, 2))
+            .add_component(CurrentProbe::new(2, 0));
+
+        assert_eq!(
+            row_kinds(&netlist),
+            [
+                Voltage, Voltage, Current, Voltage, Voltage, Voltage, Current
+            ]
+        );
+
+        // So a microvolt change of an internal node passes where a microamp change would not.
+        let options = SolverOptions::default();
+        assert!(options.convergence_excess(row_kinds(&netlist)[3], 1e-6, 1.5e-6) <= 0.0);
+        assert!(options.convergence_excess(row_kinds(&netlist)[6], 1e-6, 1.5e-6) > 0.0);
+    }
+
+    #[test]
+    fn test_convergence_culprit() {
+        // A diode fed from 5V through 1kΩ, its anode swinging up and down its exponential.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ))
+            .set_component_name(2, "D3")
+            .set_node_name(2, "anode");
+
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            max_iterations: 8,
+            junction_limiting: false,
+            gmin_stepping: None,
+            source_stepping: None,
+            step_rejection: None,
+            ..Default::default()
+        });
+        let report = solver.try_solve(1e-3).unwrap_err();
+        let culprit = report.culprit.clone().unwrap();
+        assert_eq!(culprit.variable, Variable::NodeVoltage(2));
+        assert_eq!(culprit.name, "node anode");
+        // The nonlinear diode comes before the resistor.
+        assert_eq!(culprit.components, vec!["D3", "R1"]);
+        assert_eq!(culprit.history.len(), 8);
+        assert!(report.to_string().contains("culprit: node anode (D3, R1)"));
+    }
+
+    #[test]
+    fn test_solver_error() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .add_component(CurrentSource::new(2, 0, 1e-3));
+
+        let mut solver = BESolver::new(&mut netlist);
+        assert_eq!(
+            solver.solve(-1e-3),
+            Err(SolverError::InvalidTimestep { dt: -1e-3 })
+        );
+        assert_eq!(
+            solver.solve_adaptive(f64::NAN).unwrap_err().get_report(),
+            None
+        );
+        match solver.solve(1e-3) {
+            Err(SolverError::SingularMatrix { node, .. }) => assert_eq!(node, Some(2)),
+            other => panic!("expected a singular matrix, got {other:?}"),
+        }
+
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e-3))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            max_iterations: 2,
+            ..Default::default()
+        });
+        let Err(SolverError::NonConvergence {
+            iterations,
+            worst_residual,
+            report,
+        }) = solver.solve(1e-3)
+        else {
+            panic!("expected a non-convergence");
+        };
+        assert_eq!(iterations, 2);
+        assert_eq!(worst_residual, report.worst_variables[1].change.abs());
+        assert_eq!(solver.get_time(), 0.0);
+    }
+
+    #[test]
+    fn test_scaling_condition_number() {
+        let condition_number = |scaling: bool| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Resistor::new(1, 2, 1e-3))
+                .add_component(Resistor::new(2, 3, 1e9))
+                .add_component(Capacitor::new(3, 0, 1e-15, 0.0));
+
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                scaling,
+                ..Default::default()
+            });
+            solver.solve(1e-6).unwrap();
+            assert_relative_eq!(solver.get_node_voltage(2), 1.0, max_relative = 1e-9);
+            solver.get_statistics().max_condition_number
+        };
+
+        let unscaled = condition_number(false);
+        let scaled = condition_number(true);
+        assert!(unscaled > 1e12);
+        assert!(scaled < unscaled / 1e6);
+    }
+
+    #[test]
+    fn test_noise_source() {
+        let noise_netlist = || {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(NoiseSource::new(
+                    1,
+                    0,
+                    5.0,
+                    PowerSpectralDensity::flat(1e-6, 100.0, 10e3),
+                ))
+                .add_component(Resistor::new(1, 2, 1e3))
+                .add_component(Resistor::new(2, 0, 1e3));
+            netlist
+        };
+
+        let run = |seed| {
+            let mut netlist = noise_netlist();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                seed,
+                ..Default::default()
+            });
+            let mut divided = Vec::new();
+            for _ in 0..100 {
+                solver.solve(1e-5).unwrap();
+                let source: NoiseSource = solver.netlist.get_components()[0]
+                    .clone()
+                    .try_into()
+                    .unwrap();
+                let r: Resistor = solver.netlist.get_components()[2]
+                    .clone()
+                    .try_into()
+                    .unwrap();
+                assert_relative_eq!(
+                    r.get_voltage(),
+                    source.get_voltage() / 2.0,
+                    max_relative = 1e-9
+                );
+                assert_relative_eq!(
+                    source.get_current(),
+                    source.get_voltage() / 2e3,
+                    max_relative = 1e-9
+                );
+                divided.push(r.get_voltage());
+            }
+            divided
+        };
+
+        // The divider follows the noise, which the solver's seed reproduces.
+        let first = run(Some(7));
+        assert_eq!(first, run(Some(7)));
+        assert_ne!(first, run(Some(8)));
+        assert!(first.iter().any(|v| (v - 2.5).abs() > 0.01));
+    }
+
+    #[test]
+    fn test_pulse_generator() {
+        // Pulse 2a into a load matching the 2Ω of the generator.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(
+                PulseGenerator::new(1, 0, 13.5, AutomotivePulse::iso7637_pulse_2a(50.0))
+                    .with_delay(1e-3),
+            )
+            .add_component(Resistor::new(1, 0, 2.0));
+
+        // Timesteps far longer than the pulse still land on its peak.
+        let mut solver = BESolver::new(&mut netlist);
+        let mut peak: f64 = 0.0;
+        while solver.get_time() < 2e-3 {
+            solver.step_to_breakpoint(1e-4).unwrap();
+            let r: Resistor = solver.netlist.get_components()[1]
+                .clone()
+                .try_into()
+                .unwrap();
+            peak = peak.max(r.get_voltage());
+        }
+        assert_relative_eq!(peak, 63.5 / 2.0, max_relative = 1e-9);
+
+        let generator: PulseGenerator = solver.netlist.get_components()[0]
+            .clone()
+            .try_into()
+            .unwrap();
+        assert_relative_eq!(generator.get_voltage(), 13.5 / 2.0, max_relative = 1e-6);
+        assert_relative_eq!(
+            generator.get_breakpoint().unwrap(),
+            0.501,
+            max_relative = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_gmin_stepping() {
+        // A stack of five diodes fed through 1kΩ, too far from the starting guess to converge
+        // in ten iterations.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3));
+        for k in 0..5 {
+            let next = if k == 4 { 0 } else { k + 3 };
+            netlist.add_component(Diode::new(
+                k + 2,
+                next,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+        }
+
+        let solve = |max_iterations, gmin_stepping| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                max_iterations,
+                gmin_stepping,
+                source_stepping: None,
+                ..Default::default()
+            });
+            let result = solver.solve_dc().map(|_| solver.get_node_voltage(2));
+            (result, solver.get_statistics().gmin_steppings)
+        };
+
+        let (result, _) = solve(10, None);
+        assert!(matches!(result, Err(SolverError::NonConvergence { .. })));
+
+        let (stepped, steppings) = solve(10, Some(GminStepping::default()));
+        assert_eq!(steppings, 1);
+        let (direct, steppings) = solve(1000, Some(GminStepping::default()));
+        assert_eq!(steppings, 0);
+        assert_relative_eq!(stepped.unwrap(), direct.unwrap(), max_relative = 1e-4);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't above")]
+    fn test_gmin_stepping_without_progress() {
+        // A factor of 1 would never lower gmin.
+        GminStepping::new(1.0, 1.0);
+    }
+
+    #[test]
+    fn test_source_stepping() {
+        // The diode stack of test_gmin_stepping, with gmin stepping disabled.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3));
+        for k in 0..5 {
+            let next = if k == 4 { 0 } else { k + 3 };
+            netlist.add_component(Diode::new(
+                k + 2,
+                next,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+        }
+
+        let solve = |max_iterations, source_stepping| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                max_iterations,
+                gmin_stepping: None,
+                source_stepping,
+                ..Default::default()
+            });
+            let result = solver.solve_dc().map(|_| solver.get_node_voltage(2));
+            (
+                result,
+                solver.get_last_strategy(),
+                solver.get_statistics().source_steppings,
+            )
+        };
+
+        let (result, strategy, _) = solve(10, None);
+        assert!(matches!(result, Err(SolverError::NonConvergence { .. })));
+        assert_eq!(strategy, None);
+
+        let (stepped, strategy, steppings) = solve(10, Some(SourceStepping::default()));
+        assert_eq!(strategy, Some(ConvergenceStrategy::SourceStepping));
+        assert_eq!(steppings, 1);
+        let (direct, strategy, steppings) = solve(1000, Some(SourceStepping::default()));
+        assert_eq!(strategy, Some(ConvergenceStrategy::NewtonRaphson));
+        assert_eq!(steppings, 0);
+        assert_relative_eq!(stepped.unwrap(), direct.unwrap(), max_relative = 1e-4);
+
+        // The source is left at its full value.
+        let mut netlist = netlist.clone();
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            max_iterations: 10,
+            gmin_stepping: None,
+            ..Default::default()
+        });
+        solver.solve_dc().unwrap();
+        assert_eq!(netlist.get_components()[0].get_value(), Some(10.0));
+    }
+
+    #[test]
+    fn test_damping() {
+        // Three diodes in series fed from 10V through 1kΩ.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1e3));
+        for k in 0..3 {
+            let next = if k == 2 { 0 } else { k + 3 };
+            netlist.add_component(Diode::new(
+                k + 2,
+                next,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+        }
+
+        let solve = |damping| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                damping,
+                ..Default::default()
+            });
+            solver.solve_dc().unwrap();
+            (solver.get_node_voltage(2), solver.get_statistics())
+        };
+
+        let (raw, raw_statistics) = solve(None);
+        assert_eq!(raw_statistics.damped_iterations, 0);
+
+        // Backtracking keeps the junctions from overshooting, converging in fewer iterations.
+        let (searched, statistics) = solve(Some(Damping::LineSearch { max_halvings: 10 }));
+        assert!(statistics.damped_iterations > 0);
+        assert!(statistics.newton_iterations < raw_statistics.newton_iterations);
+        assert_relative_eq!(searched, raw, max_relative = 1e-4);
+
+        // Limiting the steps takes at least 20 iterations to bring node 1 up to 10V.
+        let (limited, statistics) = solve(Some(Damping::StepLimit {
+            max_voltage_step: 0.5,
+        }));
+        assert!(statistics.newton_iterations > 20);
+        assert_relative_eq!(limited, raw, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn test_junction_limiting() {
+        // A diode in series with two antiparallel ones, fed from 20V through 100Ω.
+        let model = DiodeModel::Shockley {
+            saturation_current: 1e-14,
+            emission_coefficient: 1.0,
+        };
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 20.0))
+            .add_component(Resistor::new(1, 2, 100.0))
+            .add_component(Diode::new(2, 3, model))
+            .add_component(Diode::new(3, 0, model))
+            .add_component(Diode::new(0, 3, model));
+
+        let solve = |junction_limiting| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                junction_limiting,
+                ..Default::default()
+            });
+            solver.solve_dc().unwrap();
+            (solver.get_node_voltage(2), solver.get_statistics())
+        };
+
+        let (raw, raw_statistics) = solve(false);
+        assert_eq!(raw_statistics.limited_iterations, 0);
+
+        // Limiting keeps the junctions from overshooting up their exponentials.
+        let (limited, statistics) = solve(true);
+        assert!(statistics.limited_iterations > 0);
+        assert!(statistics.newton_iterations < raw_statistics.newton_iterations);
+        assert_relative_eq!(limited, raw, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn test_step_rejection() {
+        // A capacitor in parallel with a diode charged from 20V through 10Ω. Over a long
+        // timestep the diode jumps far up its exponential, over a short one the capacitor holds
+        // it close to where it was.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 20.0))
+            .add_component(Resistor::new(1, 2, 10.0))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0))
+            .add_component(Diode::new(
+                2,
+                0,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            ));
+
+        let solve = |step_rejection| {
+            let mut netlist = netlist.clone();
+            let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+                max_iterations: 10,
+                junction_limiting: false,
+                gmin_stepping: None,
+                source_stepping: None,
+                step_rejection,
+                ..Default::default()
+            });
+            let result = solver.solve(1e-4);
+            (
+                result,
+                solver.get_time(),
+                solver.get_node_voltage(2),
+                solver.get_statistics(),
+            )
+        };
+
+        let (result, time, _, _) = solve(None);
+        assert!(result.is_err());
+        assert_eq!(time, 0.0);
+
+        let (result, time, voltage, statistics) = solve(Some(StepRejection::default()));
+        assert!(result.is_ok());
+        assert_relative_eq!(time, 1e-4, max_relative = 1e-12);
+        assert!(statistics.rejected_steps > 0);
+        assert!(voltage > 0.6 && voltage < 0.9);
+
+        // The timestep can't be halved below the minimum.
+        let (result, time, _, _) = solve(Some(StepRejection::new(1e-5)));
+        assert!(result.is_err());
+        assert_eq!(time, 0.0);
+
+        // A singular system isn't retried with shorter timesteps.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .add_component(CurrentSource::new(2, 0, 1e-3));
+        let mut solver = BESolver::new(&mut netlist).with_options(SolverOptions {
+            step_rejection: Some(StepRejection::default()),
+            ..Default::default()
+        });
+        assert!(matches!(
+            solver.solve(1e-3),
+            Err(SolverError::SingularMatrix { .. })
+        ));
+        assert_eq!(solver.get_statistics().rejected_steps, 0);
     }
 }
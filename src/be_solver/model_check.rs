@@ -0,0 +1,389 @@
+use std::f64::consts::PI;
+use std::fmt::Display;
+
+use nalgebra::DMatrix;
+
+use crate::Rng;
+use crate::be_solver::Variable;
+use crate::be_solver::matrix_view::{ABMatrixView, XMatrixView};
+use crate::be_solver::stampable::Stampable;
+use crate::components::{Component, Netlist, VoltageSource};
+use crate::{BESolver, SolverError};
+
+/// A way a component model failed a [`ModelCheck`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelViolation {
+    /// The stamp at a sample point holds values that aren't finite.
+    NonFinite { sample: usize },
+    /// A coefficient of the stamp differs from the derivative of the component's currents (or
+    /// of its own equations) measured by finite differences, which keeps Newton-Raphson from
+    /// converging quadratically, or at all.
+    JacobianMismatch {
+        sample: usize,
+        equation: Variable,
+        variable: Variable,
+        stamped: f64,
+        finite_difference: f64,
+    },
+    /// The component delivered more energy to its terminals than it had absorbed by the given
+    /// time of the test bench.
+    EnergyCreated { time: f64, energy: f64 },
+    /// The test bench failed to solve at the given time.
+    BenchFailed { time: f64, error: SolverError },
+}
+
+impl Display for ModelViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonFinite { sample } => write!(f, "sample {sample}: stamp isn't finite"),
+            Self::JacobianMismatch {
+                sample,
+                equation,
+                variable,
+                stamped,
+                finite_difference,
+            } => write!(
+                f,
+                "sample {sample}: d{equation:?}/d{variable:?} stamped as {stamped} but is {finite_difference}"
+            ),
+            Self::EnergyCreated { time, energy } => {
+                write!(f, "created {} J by t={time}", -energy)
+            }
+            Self::BenchFailed { time, error } => {
+                write!(f, "test bench failed at t={time}: {error:?}")
+            }
+        }
+    }
+}
+
+/// The outcome of a [`ModelCheck`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelReport {
+    pub violations: Vec<ModelViolation>,
+}
+
+impl ModelReport {
+    /// Gets whether the model passed every check.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for ModelReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_ok() {
+            return writeln!(f, "Model passed");
+        }
+        writeln!(f, "Model failed {} checks", self.violations.len())?;
+        for violation in &self.violations {
+            writeln!(f, "  {violation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies that a component model is consistent, and passive if it should be, before trusting
+/// it in a larger circuit.
+///
+/// Two checks are run on the component alone, with its highest node numbering its terminals and
+/// node 0 as the reference:
+///
+/// - The Jacobian check stamps the component at random sample points (node voltages and
+///   variables within the voltage range) and compares every coefficient of A with the
+///   derivative of the residual A*x - b of the stamp, the currents out of the nodes and the
+///   component's own equations, by central finite differences.
+/// - The energy check drives every node with a sine of its own frequency (and phase) from an
+///   ideal voltage source for a number of periods of the lowest one, and accumulates the energy
+///   the component absorbs. A passive component never gives back more than it has absorbed,
+///   as long as it starts without stored energy. Sources and other active components should
+///   skip this check (see [`ModelCheck::with_active`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelCheck {
+    samples: usize,
+    voltage_range: f64,
+    dt: f64,
+    tolerance: f64,
+    frequency: f64,
+    periods: usize,
+    passive: bool,
+    seed: u64,
+}
+
+impl ModelCheck {
+    /// Creates a check over 20 sample points within ±1V with a timestep of 1us and a relative
+    /// tolerance of 1e-3, driving the energy bench from 1kHz over 5 periods.
+    pub fn new() -> Self {
+        Self {
+            samples: 20,
+            voltage_range: 1.0,
+            dt: 1e-6,
+            tolerance: 1e-3,
+            frequency: 1e3,
+            periods: 5,
+            passive: true,
+            seed: 0,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Sets the range the node voltages (and variables) of the sample points and the amplitude
+    /// of the bench sines are drawn from.
+    pub fn with_voltage_range(mut self, voltage_range: f64) -> Self {
+        self.voltage_range = voltage_range;
+        self
+    }
+
+    /// Sets the timestep the component is stamped with and the bench is run at.
+    pub fn with_timestep(mut self, dt: f64) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    /// Sets the relative tolerance of the Jacobian coefficients, and of the energy created
+    /// relative to the energy exchanged.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the lowest frequency of the energy bench and the number of its periods run.
+    pub fn with_bench(mut self, frequency: f64, periods: usize) -> Self {
+        self.frequency = frequency;
+        self.periods = periods;
+        self
+    }
+
+    /// Skips the energy check, for components meant to deliver power.
+    pub fn with_active(mut self) -> Self {
+        self.passive = false;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Checks the component.
+    pub fn check(&self, component: impl Into<Component>) -> ModelReport {
+        let component = component.into();
+        let num_nodes = component.max_node();
+        let dimension = num_nodes + component.num_variables();
+        let mut violations = self.jacobian_violations(num_nodes, dimension, |x| {
+            let mut a = DMatrix::zeros(dimension, dimension);
+            let mut b = DMatrix::zeros(dimension, 1);
+            let num_variables = component.num_variables();
+            let mut view =
+                ABMatrixView::new(&mut a, &mut b, num_nodes, 0, num_variables, num_nodes);
+            let guess = XMatrixView::new(x, num_nodes, 0, num_variables, num_nodes);
+            component.stamp(&mut view, &guess, self.dt);
+            (a, b)
+        });
+        if self.passive {
+            violations.extend(self.energy_violation(component));
+        }
+        ModelReport { violations }
+    }
+
+    /// Compares the stamps of a system of the given dimension, whose first rows are the nodal
+    /// equations of nodes 1 to num_nodes, with the finite differences of their residuals.
+    fn jacobian_violations(
+        &self,
+        num_nodes: usize,
+        dimension: usize,
+        stamp: impl Fn(&DMatrix<f64>) -> (DMatrix<f64>, DMatrix<f64>),
+    ) -> Vec<ModelViolation> {
+        let residual = |x: &DMatrix<f64>| {
+            let (a, b) = stamp(x);
+            &a * x - b
+        };
+        let variable = |row: usize| {
+            if row < num_nodes {
+                Variable::NodeVoltage(row + 1)
+            } else {
+                Variable::ComponentVariable {
+                    component: 0,
+                    index: row - num_nodes,
+                }
+            }
+        };
+
+        let mut rng = Rng::new(self.seed);
+        let mut violations = Vec::new();
+        for sample in 0..self.samples {
+            let x = DMatrix::from_fn(dimension, 1, |_, _| {
+                rng.uniform_range(-self.voltage_range, self.voltage_range)
+            });
+            let (a, b) = stamp(&x);
+            if a.iter().chain(b.iter()).any(|v| !v.is_finite()) {
+                violations.push(ModelViolation::NonFinite { sample });
+                continue;
+            }
+            let scale = a.amax();
+
+            for column in 0..dimension {
+                let h = 1e-6 * x[column].abs().max(self.voltage_range);
+                let mut forward = x.clone();
+                forward[column] += h;
+                let mut backward = x.clone();
+                backward[column] -= h;
+                let (forward, backward) = (residual(&forward), residual(&backward));
+                let derivative = (&forward - &backward) / (2.0 * h);
+
+                for row in 0..dimension {
+                    let (stamped, finite_difference) = (a[(row, column)], derivative[row]);
+                    // The difference of the residuals loses their rounding errors over h.
+                    let roundoff =
+                        16.0 * f64::EPSILON * (forward[row].abs() + backward[row].abs()) / h;
+                    let allowed = self.tolerance * stamped.abs().max(finite_difference.abs())
+                        + 1e-9 * scale
+                        + roundoff;
+                    if (stamped - finite_difference).abs() > allowed {
+                        violations.push(ModelViolation::JacobianMismatch {
+                            sample,
+                            equation: variable(row),
+                            variable: variable(column),
+                            stamped,
+                            finite_difference,
+                        });
+                    }
+                }
+            }
+        }
+        violations
+    }
+
+    /// Runs the energy bench, returning the first time the component has given back more energy
+    /// than it absorbed.
+    fn energy_violation(&self, component: Component) -> Option<ModelViolation> {
+        let num_nodes = component.max_node();
+        let mut netlist = Netlist::new();
+        netlist.add_component(component);
+        for node in 1..=num_nodes {
+            netlist.add_component(VoltageSource::new(node, 0, 0.0));
+        }
+
+        // Incommensurate frequencies and spread phases, so every pair of terminals sees a
+        // varying voltage.
+        let drive = |node: usize, time: f64| {
+            let frequency = self.frequency * (1.0 + 0.618 * (node - 1) as f64);
+            let phase = 2.0 * PI * (node - 1) as f64 / num_nodes as f64;
+            self.voltage_range * (2.0 * PI * frequency * time + phase).sin()
+        };
+
+        let mut solver = BESolver::new(&mut netlist);
+        let steps = (self.periods as f64 / self.frequency / self.dt).ceil() as usize;
+        let (mut absorbed, mut exchanged) = (0.0, 0.0);
+        for step in 1..=steps {
+            let time = step as f64 * self.dt;
+            for node in 1..=num_nodes {
+                solver
+                    .get_netlist_mut()
+                    .get_component_mut(node)
+                    .set_value(drive(node, time));
+            }
+            if let Err(error) = solver.solve(self.dt) {
+                return Some(ModelViolation::BenchFailed { time, error });
+            }
+
+            let powers = solver.get_netlist().get_components()[1..]
+                .iter()
+                .map(|source| source.get_power());
+            for power in powers {
+                absorbed += power * self.dt;
+                exchanged += power.abs() * self.dt;
+            }
+            if absorbed < -self.tolerance * exchanged - f64::EPSILON {
+                return Some(ModelViolation::EnergyCreated {
+                    time,
+                    energy: absorbed,
+                });
+            }
+        }
+        None
+    }
+}
+
+impl Default for ModelCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{
+        Bjt, BjtPolarity, Capacitor, ControlledSource, Diode, DiodeModel, Inductor, Resistor,
+        TransmissionGate,
+    };
+
+    #[test]
+    fn test_model_check() {
+        let check = ModelCheck::new().with_timestep(1e-5);
+        let passive: [Component; 7] = [
+            Resistor::new(1, 2, 100.0).into(),
+            Capacitor::new(1, 0, 1e-6, 0.0).into(),
+            Inductor::new(2, 1, 1e-3, 0.0).into(),
+            Diode::new(1, 2, DiodeModel::Ideal).into(),
+            Diode::new(
+                2,
+                1,
+                DiodeModel::Shockley {
+                    saturation_current: 1e-14,
+                    emission_coefficient: 1.0,
+                },
+            )
+            .into(),
+            Bjt::new(1, 2, 3, BjtPolarity::Pnp).into(),
+            TransmissionGate::new(1, 2, 3, 0, 100.0).into(),
+        ];
+        for component in passive {
+            let report = check.check(component.clone());
+            assert!(report.is_ok(), "{component:?}: {report}");
+        }
+
+        // A controlled source is consistent but creates energy.
+        let vccs = ControlledSource::vccs(2, 0, 1, 0, 1e-3);
+        assert!(check.with_active().check(vccs.clone()).is_ok());
+        let report = check.check(vccs);
+        assert!(matches!(
+            report.violations[..],
+            [ModelViolation::EnergyCreated { .. }]
+        ));
+        assert!(
+            check
+                .check(Resistor::new(1, 0, -100.0))
+                .to_string()
+                .starts_with("Model failed 1 checks\n  created ")
+        );
+
+        // A cubic conductance i = g*v^3 stamped as if it were linear.
+        let violations = check.jacobian_violations(1, 1, |x| {
+            let v = x[0];
+            (
+                DMatrix::from_element(1, 1, 1e-3),
+                DMatrix::from_element(1, 1, 1e-3 * (v - v.powi(3))),
+            )
+        });
+        assert_eq!(violations.len(), check.samples);
+        let ModelViolation::JacobianMismatch {
+            equation,
+            variable,
+            stamped,
+            finite_difference,
+            ..
+        } = violations[0]
+        else {
+            unreachable!()
+        };
+        assert_eq!(equation, Variable::NodeVoltage(1));
+        assert_eq!(variable, Variable::NodeVoltage(1));
+        assert_eq!(stamped, 1e-3);
+        assert!((finite_difference - stamped).abs() > 1e-5);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::be_solver::VariableKind;
+use crate::{AdaptiveTimestep, GminStepping, Refinement, SourceStepping, StepRejection};
+
+/// How the solver discretizes the derivatives of capacitors and inductors over a timestep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntegrationMethod {
+    /// Backward Euler: first order and damping, so it never rings numerically but makes
+    /// resonant circuits lose amplitude over time.
+    #[default]
+    BackwardEuler,
+    /// The trapezoidal rule: second order and preserving the energy of lossless resonant
+    /// circuits, but it can ring numerically after abrupt changes (e.g. a step at t=0 charging a
+    /// capacitor from zero current). Components without a trapezoidal companion model integrate
+    /// with backward Euler.
+    Trapezoidal,
+    /// The second order Gear method (BDF2): damping like backward Euler, so stiff circuits
+    /// don't ring numerically, but second order, so it allows longer timesteps for the same
+    /// accuracy. It integrates from the two previous timesteps, the first timestep falling back
+    /// to backward Euler.
+    Gear2,
+}
+
+/// How the solver damps the Newton-Raphson updates, so that nonlinear components far from the
+/// solution (e.g. junction voltages overshooting up the exponential of a diode) don't throw the
+/// iteration off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Damping {
+    /// Limits the change of each node voltage per iteration to the given voltage. Nodes driven
+    /// far from the starting guess by sources take as many iterations to get there.
+    StepLimit { max_voltage_step: f64 },
+    /// Backtracking line search: an update that doesn't reduce the norm of the residual of the
+    /// system is halved until it does, at most the given number of times.
+    LineSearch { max_halvings: usize },
+}
+
+/// Options controlling how a solver solves the circuit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverOptions {
+    /// Change between Newton-Raphson iterations, relative to the larger of the two values,
+    /// below which a variable is considered converged, on top of its absolute tolerance.
+    pub relative_tolerance: f64,
+    /// Absolute change of a node voltage between Newton-Raphson iterations allowed on top of
+    /// the relative tolerance (SPICE VNTOL), so node voltages crossing zero converge.
+    pub voltage_tolerance: f64,
+    /// Absolute change of the additional variables of the components (e.g. the current
+    /// through a voltage source) allowed on top of the relative tolerance (SPICE ABSTOL).
+    pub current_tolerance: f64,
+    /// Maximum number of Newton-Raphson iterations before giving up.
+    pub max_iterations: usize,
+    /// Scales the rows and columns of the system before inverting it so circuits mixing very
+    /// large and very small values (e.g. milliohms and gigaohms) keep their accuracy.
+    pub scaling: bool,
+    /// Minimum conductance stamped across every nonlinear junction so that nodes isolated by an
+    /// off junction don't make the system singular (SPICE GMIN).
+    pub gmin: f64,
+    /// Damping of the Newton-Raphson updates, the raw update if None.
+    pub damping: Option<Damping>,
+    /// Lets nonlinear components limit the change of their junction voltages between
+    /// Newton-Raphson iterations (SPICE pnjlim for diodes), so exponential junctions don't
+    /// overshoot up their exponential.
+    pub junction_limiting: bool,
+    /// Gmin stepping, tried when the Newton-Raphson iteration fails, disabled if None.
+    pub gmin_stepping: Option<GminStepping>,
+    /// Source stepping, tried when gmin stepping fails too or is disabled, disabled if None.
+    pub source_stepping: Option<SourceStepping>,
+    /// Ramps every independent source without a ramp of its own up over this time at the start
+    /// of the simulation (soft start), avoiding the huge currents of stepping them on at t=0.
+    pub source_ramp: Option<f64>,
+    /// Automatic timestep refinement around fast transients, disabled if None.
+    pub refinement: Option<Refinement>,
+    /// Halving of the timesteps that fail to converge, disabled if None.
+    pub step_rejection: Option<StepRejection>,
+    /// Seeds every stochastic feature of the circuit (contact bounce jitter, noise sources) from
+    /// one [`Rng`](crate::Rng), overriding their own seeds, so the whole run can be reproduced
+    /// from this seed. Each component gets its own stream keyed on its index.
+    pub seed: Option<u64>,
+    /// Method integrating the capacitors and inductors over each timestep.
+    pub integration: IntegrationMethod,
+    /// Tolerances and limits of the timesteps chosen by
+    /// [`BESolver::solve_adaptive`](crate::BESolver::solve_adaptive).
+    pub adaptive: AdaptiveTimestep,
+    /// Dimension of the system from which it is assembled as a sparse matrix and factorized by
+    /// sparse LU rather than inverted densely, which scales to circuits of thousands of nodes.
+    pub sparse_threshold: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            relative_tolerance: 1e-4,
+            voltage_tolerance: 1e-6,
+            current_tolerance: 1e-12,
+            max_iterations: 1000,
+            scaling: true,
+            gmin: 1e-12,
+            damping: None,
+            junction_limiting: true,
+            gmin_stepping: Some(GminStepping::default()),
+            source_stepping: Some(SourceStepping::default()),
+            source_ramp: None,
+            refinement: None,
+            step_rejection: Some(StepRejection::default()),
+            seed: None,
+            integration: IntegrationMethod::BackwardEuler,
+            adaptive: AdaptiveTimestep::default(),
+            sparse_threshold: 200,
+        }
+    }
+}
+
+impl SolverOptions {
+    /// Gets by how much the change of a variable of the system over a Newton-Raphson iteration
+    /// exceeds its tolerance, zero or less if it converged. The absolute tolerance is the one of
+    /// the kind of the variable.
+    pub(crate) fn convergence_excess(&self, kind: VariableKind, new: f64, old: f64) -> f64 {
+        let absolute_tolerance = match kind {
+            VariableKind::Voltage => self.voltage_tolerance,
+            VariableKind::Current => self.current_tolerance,
+        };
+        (new - old).abs() - self.relative_tolerance * new.abs().max(old.abs()) - absolute_tolerance
+    }
+
+    /// Tight tolerances and timestep refinement, for results to compare against measurements.
+    /// Integrates with Gear2, second order without numerical ringing, and keeps the local
+    /// truncation error of adaptive timesteps to a tenth of the default.
+    pub fn accurate() -> Self {
+        Self {
+            relative_tolerance: 1e-6,
+            refinement: Some(Refinement::new(0.05, 10)),
+            integration: IntegrationMethod::Gear2,
+            adaptive: AdaptiveTimestep::new(1e-4, 1e-7),
+            ..Default::default()
+        }
+    }
+
+    /// Loose tolerances and no refinement, for quick looks at large circuits. Integrates with
+    /// Gear2, whose second order allows longer timesteps, and lets adaptive timesteps grow
+    /// until their local truncation error is ten times the default.
+    pub fn fast() -> Self {
+        Self {
+            relative_tolerance: 1e-3,
+            max_iterations: 200,
+            integration: IntegrationMethod::Gear2,
+            adaptive: AdaptiveTimestep::new(1e-2, 1e-5),
+            ..Default::default()
+        }
+    }
+
+    /// Every convergence aid enabled, for circuits that fail to converge otherwise: a larger
+    /// GMIN, more iterations, a short soft start of the sources and timestep refinement.
+    /// Integrates with backward Euler, the most damping method, and starts adaptive timesteps
+    /// at 1ps so the first step doesn't overshoot.
+    pub fn robust() -> Self {
+        Self {
+            max_iterations: 5000,
+            gmin: 1e-9,
+            source_ramp: Some(1e-6),
+            refinement: Some(Refinement::new(0.2, 10)),
+            integration: IntegrationMethod::BackwardEuler,
+            adaptive: AdaptiveTimestep {
+                initial_step: 1e-12,
+                ..AdaptiveTimestep::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Named sets of solver options, so an analysis can be configured by name (e.g. from a settings
+/// file or a GUI) with "accurate", "fast" and "robust" built in and custom presets added on top.
+///
+/// Each preset bundles tolerances, convergence aids, the integration method and the timestep
+/// policy of adaptive solves, used by [`BESolver::solve_adaptive`](crate::BESolver::solve_adaptive)
+/// and by a [`TransientAnalysis`](crate::analysis::TransientAnalysis) run with
+/// [`Timestep::Adaptive`](crate::analysis::Timestep::Adaptive) of the preset's `adaptive`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionPresets {
+    presets: Vec<(String, SolverOptions)>,
+}
+
+impl Default for OptionPresets {
+    fn default() -> Self {
+        Self {
+            presets: vec![
+                ("default".to_string(), SolverOptions::default()),
+                ("accurate".to_string(), SolverOptions::accurate()),
+                ("fast".to_string(), SolverOptions::fast()),
+                ("robust".to_string(), SolverOptions::robust()),
+            ],
+        }
+    }
+}
+
+impl OptionPresets {
+    /// Creates the built in presets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a preset, replacing any existing preset with the same name.
+    pub fn define(&mut self, name: impl Into<String>, options: SolverOptions) -> &mut Self {
+        let name = name.into();
+        match self.presets.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = options,
+            None => self.presets.push((name, options)),
+        }
+        self
+    }
+
+    /// Gets the options of the preset with the given name.
+    pub fn get(&self, name: &str) -> Option<SolverOptions> {
+        self.presets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, options)| *options)
+    }
+
+    /// Gets the names of every preset in the order they were defined.
+    pub fn names(&self) -> Vec<&str> {
+        self.presets.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn custom_presets() {
+        let mut presets = OptionPresets::new();
+        assert_eq!(presets.get("fast"), Some(SolverOptions::fast()));
+        assert_eq!(presets.get("missing"), None);
+
+        let custom = SolverOptions {
+            gmin: 1e-6,
+            ..SolverOptions::robust()
+        };
+        presets
+            .define("power", custom)
+            .define("fast", SolverOptions::default());
+
+        assert_eq!(presets.get("power"), Some(custom));
+        assert_eq!(presets.get("fast"), Some(SolverOptions::default()));
+        assert_eq!(
+            presets.names(),
+            vec!["default", "accurate", "fast", "robust", "power"]
+        );
+    }
+
+    #[test]
+    fn preset_integration_and_timesteps() {
+        let accurate = SolverOptions::accurate();
+        let fast = SolverOptions::fast();
+        let robust = SolverOptions::robust();
+
+        assert_eq!(accurate.integration, IntegrationMethod::Gear2);
+        assert_eq!(fast.integration, IntegrationMethod::Gear2);
+        assert_eq!(robust.integration, IntegrationMethod::BackwardEuler);
+
+        let default = AdaptiveTimestep::default();
+        assert!(accurate.adaptive.relative_tolerance < default.relative_tolerance);
+        assert!(fast.adaptive.relative_tolerance > default.relative_tolerance);
+        assert!(robust.adaptive.initial_step < default.initial_step);
+    }
+
+    #[test]
+    fn convergence_tolerances() {
+        let options = SolverOptions::default();
+
+        // A voltage crossing zero converges on the absolute tolerance, where a purely relative
+        // test would never be met.
+        let (voltage, current) = (VariableKind::Voltage, VariableKind::Current);
+        assert!(options.convergence_excess(voltage, 2e-7, -3e-7) <= 0.0);
+        assert!(options.convergence_excess(voltage, 2e-5, -3e-5) > 0.0);
+        // The relative tolerance is taken from the larger of the two values.
+        assert!(options.convergence_excess(voltage, 1e3, 1e3 * (1.0 + 1e-5)) <= 0.0);
+        assert!(options.convergence_excess(voltage, 1e3, 1e3 * (1.0 + 1e-3)) > 0.0);
+        // A microamp change of a current is far outside the current tolerance of a current
+        // near zero, though it would pass as a voltage.
+        assert!(options.convergence_excess(current, 1e-6, 1.5e-6) > 0.0);
+        assert!(options.convergence_excess(voltage, 1e-6, 1.5e-6) <= 0.0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{be_solver::stampable::Stampable, components::Component};
+
+/// Configures automatic timestep refinement around fast transients.
+///
+/// After every timestep the state of each reactive component (capacitor voltages, inductor
+/// currents, switch conductances) is compared to its state before the timestep. If any changed
+/// by more than `max_relative_change` of the larger of the two, plus an absolute `min_change`
+/// so states starting from zero don't count as fast, the timestep is rolled back and
+/// re-integrated in `subdivisions` smaller timesteps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Refinement {
+    max_relative_change: f64,
+    min_change: f64,
+    subdivisions: usize,
+}
+
+impl Refinement {
+    /// Creates a refinement with a minimum change of 1e-3 (a millivolt for a capacitor, a
+    /// milliamp for an inductor).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `subdivisions` is zero.
+    pub fn new(max_relative_change: f64, subdivisions: usize) -> Self {
+        assert!(
+            subdivisions > 0,
+            "A refinement needs at least one subdivision"
+        );
+        Self {
+            max_relative_change,
+            min_change: 1e-3,
+            subdivisions,
+        }
+    }
+
+    /// Sets the absolute change a state may always make within a timestep, in its own unit.
+    pub fn with_min_change(mut self, min_change: f64) -> Self {
+        self.min_change = min_change;
+        self
+    }
+
+    pub fn get_max_relative_change(&self) -> f64 {
+        self.max_relative_change
+    }
+
+    pub fn get_min_change(&self) -> f64 {
+        self.min_change
+    }
+
+    pub fn get_subdivisions(&self) -> usize {
+        self.subdivisions
+    }
+
+    /// Returns true if any component's state changed too much between `before` and `after`.
+    pub(crate) fn is_fast_transient(&self, before: &[Component], after: &[Component]) -> bool {
+        before
+            .iter()
+            .zip(after.iter())
+            .any(|(b, a)| match (b.state(), a.state()) {
+                (Some(old), Some(new)) => {
+                    (new - old).abs()
+                        > self.max_relative_change * old.abs().max(new.abs()) + self.min_change
+                }
+                _ => false,
+            })
+    }
+}
+
+/// Configures timestep rejection, the fallback when a timestep can't be solved even by gmin and
+/// source stepping.
+///
+/// A timestep failing to converge is rejected, the components restored to their state before
+/// it, and the interval integrated in two timesteps of half the length instead, each of which
+/// is rejected in turn if it fails. The solve only fails once a timestep shorter than
+/// `min_step` would be needed, or at once if the system is singular, which a shorter timestep
+/// doesn't fix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepRejection {
+    pub min_step: f64,
+}
+
+impl StepRejection {
+    pub fn new(min_step: f64) -> Self {
+        Self { min_step }
+    }
+}
+
+impl Default for StepRejection {
+    /// Down to a picosecond.
+    fn default() -> Self {
+        Self::new(1e-12)
+    }
+}
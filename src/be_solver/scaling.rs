@@ -0,0 +1,85 @@
+use nalgebra::DMatrix;
+
+/// The solution of a linear system and the estimated condition number of the matrix inverted.
+pub(crate) struct LinearSolution {
+    pub x: DMatrix<f64>,
+    pub condition_number: f64,
+}
+
+/// Solves a x = b, returning None if a is singular.
+///
+/// With scaling enabled the rows and then the columns of a are first scaled so their largest
+/// entry is about one (equilibration), which keeps circuits mixing milliohms and gigaohms from
+/// losing accuracy in the inversion. The scale factors are powers of two so scaling itself
+/// introduces no rounding.
+pub(crate) fn solve_linear(
+    mut a: DMatrix<f64>,
+    mut b: DMatrix<f64>,
+    scaling: bool,
+) -> Option<LinearSolution> {
+    let n = a.nrows();
+    let mut column_scales = vec![1.0; n];
+
+    if scaling {
+        for row in 0..n {
+            let scale = power_of_two_scale(a.row(row).amax());
+            a.row_mut(row).scale_mut(scale);
+            b.row_mut(row).scale_mut(scale);
+        }
+        for (column, column_scale) in column_scales.iter_mut().enumerate() {
+            *column_scale = power_of_two_scale(a.column(column).amax());
+            a.column_mut(column).scale_mut(*column_scale);
+        }
+    }
+
+    let norm = one_norm(&a);
+    let inverse = a.try_inverse()?;
+    let condition_number = norm * one_norm(&inverse);
+
+    let mut x = inverse * b;
+    for (row, scale) in column_scales.into_iter().enumerate() {
+        x[row] *= scale;
+    }
+
+    Some(LinearSolution {
+        x,
+        condition_number,
+    })
+}
+
+/// Returns the power of two bringing the value closest to one, or one for zero.
+pub(super) fn power_of_two_scale(value: f64) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return 1.0;
+    }
+    2f64.powi(-(value.log2().round() as i32))
+}
+
+/// The largest absolute column sum.
+fn one_norm(a: &DMatrix<f64>) -> f64 {
+    a.column_iter()
+        .map(|column| column.iter().map(|v| v.abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn scaling_improves_conditioning() {
+        let a = DMatrix::from_row_slice(2, 2, &[1e9, 1.0, 1.0, 1e-9 + 1e-9]);
+        let b = DMatrix::from_column_slice(2, 1, &[1e9, 1e-9]);
+
+        let plain = solve_linear(a.clone(), b.clone(), false).unwrap();
+        let scaled = solve_linear(a.clone(), b.clone(), true).unwrap();
+
+        assert!(scaled.condition_number < plain.condition_number / 1e6);
+        assert_relative_eq!((&a * &scaled.x - &b).amax(), 0.0, epsilon = 1e-9);
+
+        let singular = DMatrix::from_row_slice(2, 2, &[1.0, 1.0, 1.0, 1.0]);
+        assert!(solve_linear(singular, b, true).is_none());
+    }
+}
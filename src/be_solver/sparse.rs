@@ -0,0 +1,500 @@
+use std::collections::VecDeque;
+
+use nalgebra::DMatrix;
+
+use crate::be_solver::scaling::{LinearSolution, power_of_two_scale};
+
+/// Fraction of the largest candidate a diagonal entry must reach to be chosen as the pivot of
+/// its column, keeping the fill-reducing ordering where it is numerically safe to.
+const PIVOT_THRESHOLD: f64 = 0.1;
+
+/// A square matrix in compressed sparse column (CSC) form.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SparseMatrix {
+    dimension: usize,
+    /// Index in `rows` and `values` of the first entry of each column, and one past the last.
+    column_starts: Vec<usize>,
+    rows: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SparseMatrix {
+    /// Builds the matrix from (row, column, value) triplets, summing the values of triplets at
+    /// the same position.
+    pub(crate) fn from_triplets(dimension: usize, triplets: &[(usize, usize, f64)]) -> Self {
+        let mut sorted = triplets.to_vec();
+        sorted.sort_by_key(|&(row, column, _)| (column, row));
+
+        let mut column_starts = vec![0; dimension + 1];
+        let mut rows = Vec::with_capacity(sorted.len());
+        let mut values: Vec<f64> = Vec::with_capacity(sorted.len());
+        let mut last = None;
+        for (row, column, value) in sorted {
+            if last == Some((row, column)) {
+                *values.last_mut().unwrap() += value;
+                continue;
+            }
+            last = Some((row, column));
+            rows.push(row);
+            values.push(value);
+            column_starts[column + 1] = rows.len();
+        }
+        // Empty columns start where the one before them ended.
+        for column in 1..=dimension {
+            column_starts[column] = column_starts[column].max(column_starts[column - 1]);
+        }
+
+        Self {
+            dimension,
+            column_starts,
+            rows,
+            values,
+        }
+    }
+
+    pub(crate) fn get_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Gets the rows and values of the entries of a column.
+    fn column(&self, column: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.column_starts[column]..self.column_starts[column + 1];
+        self.rows[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    /// Gets every stored entry as a (row, column, value) triplet.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        (0..self.dimension)
+            .flat_map(move |column| self.column(column).map(move |(row, v)| (row, column, v)))
+    }
+
+    /// The largest absolute column sum.
+    fn one_norm(&self) -> f64 {
+        (0..self.dimension)
+            .map(|column| self.column(column).map(|(_, v)| v.abs()).sum::<f64>())
+            .fold(0.0, f64::max)
+    }
+
+    /// Orders the columns by reverse Cuthill-McKee on the pattern of A + A^T, which keeps the
+    /// nonzeros of circuits made of chains and meshes of components close to the diagonal and
+    /// so limits the fill of their factorization.
+    fn reverse_cuthill_mckee(&self) -> Vec<usize> {
+        let mut neighbours = vec![Vec::new(); self.dimension];
+        for (row, column, _) in self.entries() {
+            if row != column {
+                neighbours[row].push(column);
+                neighbours[column].push(row);
+            }
+        }
+        for list in &mut neighbours {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        let mut by_degree: Vec<usize> = (0..self.dimension).collect();
+        by_degree.sort_by_key(|&node| neighbours[node].len());
+
+        let mut visited = vec![false; self.dimension];
+        let mut order = Vec::with_capacity(self.dimension);
+        for start in by_degree {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                let mut next: Vec<usize> = neighbours[node]
+                    .iter()
+                    .copied()
+                    .filter(|&n| !visited[n])
+                    .collect();
+                next.sort_by_key(|&n| neighbours[n].len());
+                for n in next {
+                    visited[n] = true;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        order.reverse();
+        order
+    }
+}
+
+/// A fill-reducing column order, kept with the sparsity pattern it was computed for so the
+/// systems of successive Newton-Raphson iterations and timesteps, which share their pattern
+/// until the layout of the system changes, reuse it instead of ordering every one again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ColumnOrdering {
+    column_starts: Vec<usize>,
+    rows: Vec<usize>,
+    column_order: Vec<usize>,
+}
+
+impl ColumnOrdering {
+    /// Gets the column order of the matrix, recomputing it only if the pattern of the matrix
+    /// differs from the one it was last computed for. Returns whether it was recomputed.
+    pub(crate) fn update(&mut self, a: &SparseMatrix) -> bool {
+        if self.column_starts == a.column_starts && self.rows == a.rows {
+            return false;
+        }
+        self.column_order = a.reverse_cuthill_mckee();
+        self.column_starts.clone_from(&a.column_starts);
+        self.rows.clone_from(&a.rows);
+        true
+    }
+
+    pub(crate) fn get_column_order(&self) -> &[usize] {
+        &self.column_order
+    }
+}
+
+/// The LU factorization P A Q = L U of a sparse matrix by the left-looking algorithm of
+/// Gilbert and Peierls, with the columns permuted to reduce fill (Q) and the rows by partial
+/// pivoting (P).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SparseLu {
+    /// The column of A eliminated at each step.
+    column_order: Vec<usize>,
+    /// The row of A pivoted on at each step, and the step each row was pivoted on.
+    pivot_rows: Vec<usize>,
+    pivot_steps: Vec<usize>,
+    /// The multipliers below the unit diagonal of each column of L, by row of A.
+    l: Vec<Vec<(usize, f64)>>,
+    /// The entries above the diagonal of each column of U, by step, and the diagonal.
+    u: Vec<Vec<(usize, f64)>>,
+    u_diagonal: Vec<f64>,
+}
+
+impl SparseLu {
+    /// Factorizes the matrix eliminating its columns in the given order, returning None if it
+    /// is singular.
+    pub(crate) fn factorize(a: &SparseMatrix, column_order: &[usize]) -> Option<Self> {
+        let n = a.get_dimension();
+        let mut lu = Self {
+            column_order: column_order.to_vec(),
+            pivot_rows: Vec::with_capacity(n),
+            pivot_steps: vec![usize::MAX; n],
+            l: Vec::with_capacity(n),
+            u: Vec::with_capacity(n),
+            u_diagonal: Vec::with_capacity(n),
+        };
+
+        let mut x = vec![0.0; n];
+        let mut marks = vec![usize::MAX; n];
+        for step in 0..n {
+            let column = lu.column_order[step];
+
+            // The rows the solution of L x = A[:, column] can be nonzero on, in an order
+            // eliminating each before the rows it updates.
+            let reach = lu.reach(a.column(column).map(|(row, _)| row), step, &mut marks);
+            for (row, value) in a.column(column) {
+                x[row] = value;
+            }
+            for &row in &reach {
+                let pivot_step = lu.pivot_steps[row];
+                if pivot_step != usize::MAX {
+                    let value = x[row];
+                    for &(below, multiplier) in &lu.l[pivot_step] {
+                        x[below] -= multiplier * value;
+                    }
+                }
+            }
+
+            let candidates = reach
+                .iter()
+                .copied()
+                .filter(|&row| lu.pivot_steps[row] == usize::MAX);
+            let largest = candidates
+                .clone()
+                .max_by(|&a, &b| x[a].abs().total_cmp(&x[b].abs()))?;
+            let magnitude = x[largest].abs();
+            if magnitude == 0.0 || !magnitude.is_finite() {
+                return None;
+            }
+            let pivot = if lu.pivot_steps[column] == usize::MAX
+                && x[column].abs() >= PIVOT_THRESHOLD * magnitude
+            {
+                column
+            } else {
+                largest
+            };
+            let diagonal = x[pivot];
+
+            lu.u.push(
+                reach
+                    .iter()
+                    .filter(|&&row| lu.pivot_steps[row] != usize::MAX)
+                    .map(|&row| (lu.pivot_steps[row], x[row]))
+                    .filter(|&(_, value)| value != 0.0)
+                    .collect(),
+            );
+            lu.l.push(
+                candidates
+                    .filter(|&row| row != pivot && x[row] != 0.0)
+                    .map(|row| (row, x[row] / diagonal))
+                    .collect(),
+            );
+            lu.u_diagonal.push(diagonal);
+            lu.pivot_rows.push(pivot);
+            lu.pivot_steps[pivot] = step;
+
+            for &row in &reach {
+                x[row] = 0.0;
+            }
+        }
+
+        Some(lu)
+    }
+
+    /// Finds the rows reachable from the given ones through the columns of L factorized so far
+    /// by depth-first search, returned in topological order.
+    fn reach(
+        &self,
+        start: impl Iterator<Item = usize>,
+        step: usize,
+        marks: &mut [usize],
+    ) -> Vec<usize> {
+        let mut finished = Vec::new();
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for row in start {
+            if marks[row] == step {
+                continue;
+            }
+            marks[row] = step;
+            stack.push((row, 0));
+            while let Some((row, child)) = stack.pop() {
+                let pivot_step = self.pivot_steps[row];
+                let below = if pivot_step == usize::MAX {
+                    &[][..]
+                } else {
+                    &self.l[pivot_step][..]
+                };
+                match below[child..].iter().position(|&(r, _)| marks[r] != step) {
+                    Some(offset) => {
+                        let next = below[child + offset].0;
+                        marks[next] = step;
+                        stack.push((row, child + offset + 1));
+                        stack.push((next, 0));
+                    }
+                    None => finished.push(row),
+                }
+            }
+        }
+        finished.reverse();
+        finished
+    }
+
+    /// Solves A x = b.
+    pub(crate) fn solve(&self, b: &DMatrix<f64>) -> DMatrix<f64> {
+        let n = self.column_order.len();
+        let mut w: Vec<f64> = b.iter().copied().collect();
+        let mut z = vec![0.0; n];
+        for step in 0..n {
+            let value = w[self.pivot_rows[step]];
+            z[step] = value;
+            for &(row, multiplier) in &self.l[step] {
+                w[row] -= multiplier * value;
+            }
+        }
+        for step in (0..n).rev() {
+            z[step] /= self.u_diagonal[step];
+            let value = z[step];
+            for &(above, u) in &self.u[step] {
+                z[above] -= u * value;
+            }
+        }
+
+        let mut x = DMatrix::zeros(n, 1);
+        for (step, &column) in self.column_order.iter().enumerate() {
+            x[column] = z[step];
+        }
+        x
+    }
+
+    /// Solves A^T x = b.
+    pub(crate) fn solve_transpose(&self, b: &DMatrix<f64>) -> DMatrix<f64> {
+        let n = self.column_order.len();
+        let mut w: Vec<f64> = self.column_order.iter().map(|&column| b[column]).collect();
+        for step in 0..n {
+            let sum: f64 = self.u[step].iter().map(|&(above, u)| u * w[above]).sum();
+            w[step] = (w[step] - sum) / self.u_diagonal[step];
+        }
+        for step in (0..n).rev() {
+            let sum: f64 = self.l[step]
+                .iter()
+                .map(|&(row, multiplier)| multiplier * w[self.pivot_steps[row]])
+                .sum();
+            w[step] -= sum;
+        }
+
+        let mut x = DMatrix::zeros(n, 1);
+        for (step, &row) in self.pivot_rows.iter().enumerate() {
+            x[row] = w[step];
+        }
+        x
+    }
+
+    /// Estimates the 1-norm of the inverse of the factorized matrix by Hager's method, which
+    /// needs a handful of solves rather than the inverse itself.
+    fn inverse_one_norm(&self) -> f64 {
+        let n = self.column_order.len();
+        let mut x = DMatrix::from_element(n, 1, 1.0 / n as f64);
+        let mut estimate = 0.0;
+        for _ in 0..5 {
+            let y = self.solve(&x);
+            estimate = y.lp_norm(1);
+            let signs = y.map(|v| if v < 0.0 { -1.0 } else { 1.0 });
+            let z = self.solve_transpose(&signs);
+            let (largest, _) = z.iamax_full();
+            if z[largest].abs() <= z.dot(&x) {
+                break;
+            }
+            x.fill(0.0);
+            x[largest] = 1.0;
+        }
+        estimate
+    }
+}
+
+/// Solves a x = b for a sparse a, returning None if a is singular. Scaling is as for dense
+/// systems (see [`solve_linear`](super::scaling::solve_linear)), and the condition number is
+/// estimated from the factorization. The columns are eliminated in the given order, which must
+/// be a permutation of them.
+pub(crate) fn solve_linear(
+    a: &SparseMatrix,
+    mut b: DMatrix<f64>,
+    scaling: bool,
+    column_order: &[usize],
+) -> Option<LinearSolution> {
+    let n = a.get_dimension();
+    let mut a = a.clone();
+    let mut column_scales = vec![1.0; n];
+
+    if scaling {
+        let mut row_max = vec![0.0f64; n];
+        for (&row, &value) in a.rows.iter().zip(&a.values) {
+            row_max[row] = row_max[row].max(value.abs());
+        }
+        let row_scales: Vec<f64> = row_max.into_iter().map(power_of_two_scale).collect();
+        for (&row, value) in a.rows.iter().zip(&mut a.values) {
+            *value *= row_scales[row];
+        }
+        for (row, scale) in row_scales.into_iter().enumerate() {
+            b[row] *= scale;
+        }
+        for (column, column_scale) in column_scales.iter_mut().enumerate() {
+            let range = a.column_starts[column]..a.column_starts[column + 1];
+            let largest = a.values[range.clone()]
+                .iter()
+                .fold(0.0f64, |largest, v| largest.max(v.abs()));
+            *column_scale = power_of_two_scale(largest);
+            for value in &mut a.values[range] {
+                *value *= *column_scale;
+            }
+        }
+    }
+
+    let lu = SparseLu::factorize(&a, column_order)?;
+    let condition_number = a.one_norm() * lu.inverse_one_norm();
+
+    let mut x = lu.solve(&b);
+    for (row, scale) in column_scales.into_iter().enumerate() {
+        x[row] *= scale;
+    }
+
+    Some(LinearSolution {
+        x,
+        condition_number,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::Rng;
+
+    #[test]
+    fn sparse_lu_matches_dense() {
+        // A random sparse system with zeros on part of the diagonal, as the branch equations of
+        // voltage sources have, so rows must be pivoted.
+        let rng = Rng::new(3);
+        let n = 40;
+        let mut rng = rng.stream(0);
+        let mut triplets = Vec::new();
+        for k in 0..n {
+            if k % 5 != 0 {
+                triplets.push((k, k, 4.0 + rng.uniform()));
+            }
+            for _ in 0..3 {
+                let other = (rng.uniform() * n as f64) as usize % n;
+                triplets.push((k, other, rng.uniform() - 0.5));
+                triplets.push((other, k, rng.uniform() - 0.5));
+            }
+        }
+        let a = SparseMatrix::from_triplets(n, &triplets);
+        let mut dense = DMatrix::zeros(n, n);
+        for &(row, column, value) in &triplets {
+            dense[(row, column)] += value;
+        }
+        let b = DMatrix::from_fn(n, 1, |row, _| row as f64 - 10.0);
+
+        let mut ordering = ColumnOrdering::default();
+        assert!(ordering.update(&a));
+        let lu = SparseLu::factorize(&a, ordering.get_column_order()).unwrap();
+        let x = lu.solve(&b);
+        assert_relative_eq!((&dense * &x - &b).amax(), 0.0, epsilon = 1e-9);
+        let y = lu.solve_transpose(&b);
+        assert_relative_eq!((dense.transpose() * &y - &b).amax(), 0.0, epsilon = 1e-9);
+
+        // The estimate is a lower bound of the condition number, usually equal to it.
+        let inverse = dense.clone().try_inverse().unwrap();
+        let exact = a.one_norm()
+            * inverse
+                .column_iter()
+                .map(|c| c.lp_norm(1))
+                .fold(0.0, f64::max);
+        let solution = solve_linear(&a, b.clone(), true, ordering.get_column_order()).unwrap();
+        assert_relative_eq!((&dense * &solution.x - &b).amax(), 0.0, epsilon = 1e-9);
+        assert!(lu.inverse_one_norm() * a.one_norm() <= exact * (1.0 + 1e-9));
+        assert!(lu.inverse_one_norm() * a.one_norm() > exact / 10.0);
+
+        let singular =
+            SparseMatrix::from_triplets(2, &[(0, 0, 1.0), (0, 1, 1.0), (1, 0, 1.0), (1, 1, 1.0)]);
+        assert!(SparseLu::factorize(&singular, &[1, 0]).is_none());
+    }
+
+    #[test]
+    fn column_ordering_follows_pattern() {
+        let chain = |conductance: f64, extra: bool| {
+            let mut triplets = Vec::new();
+            for k in 0..5 {
+                triplets.push((k, k, 2.0 * conductance));
+                if k > 0 {
+                    triplets.push((k, k - 1, -conductance));
+                    triplets.push((k - 1, k, -conductance));
+                }
+            }
+            if extra {
+                triplets.extend([(0, 4, -conductance), (4, 0, -conductance)]);
+            }
+            SparseMatrix::from_triplets(5, &triplets)
+        };
+
+        let mut ordering = ColumnOrdering::default();
+        assert!(ordering.update(&chain(1.0, false)));
+        let order = ordering.get_column_order().to_vec();
+
+        // New values in the same pattern keep the order, a new entry orders the columns again.
+        assert!(!ordering.update(&chain(3.0, false)));
+        assert_eq!(ordering.get_column_order(), order);
+        assert!(ordering.update(&chain(3.0, true)));
+        assert!(!ordering.update(&chain(1.0, true)));
+    }
+}
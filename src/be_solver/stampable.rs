@@ -1,17 +1,196 @@
+use std::ops::{AddAssign, Mul, Neg};
+
+use nalgebra::{Complex, Scalar};
+
 use crate::{
+    be_solver::IntegrationMethod,
+    be_solver::VariableKind,
     be_solver::matrix_view::{ABMatrixView, ViewEquationIndex, ViewVariableIndex, XMatrixView},
-    components::{Capacitor, Component, CurrentSource, Inductor, Resistor, VoltageSource},
+    components::{
+        BenchSupply, Bjt, BjtPolarity, CPE_BRANCHES, Capacitor, ClockedComparator, Component,
+        ConstantPhaseElement, ControlledSource, CurrentProbe, CurrentSource, CurrentTransformer,
+        Diode, ElectronicLoad, FittedImpedance, FrequencyDivider, HallSensor, Inductor,
+        MAX_FITTED_ORDER, NoiseSource, NonOverlappingClock, PhaseFrequencyDetector, PulseGenerator,
+        PvModule, PwmController, PwmMode, RandlesCell, Relay, Resistor, SampleAndHold,
+        ShuntReference, Switch, ThermoelectricModule, TransmissionGate, Vco, VoltageSource,
+    },
 };
 
 pub trait Stampable {
     /// Returns the number of additional variables this component will add to the matrix.
     fn num_variables(&self) -> usize;
 
+    /// Returns what the additional variable at `index` stands for, which decides the absolute
+    /// tolerance its convergence is judged against. Most additional variables are branch
+    /// currents, so that is the default.
+    fn variable_kind(&self, _index: usize) -> VariableKind {
+        VariableKind::Current
+    }
+
     /// Stamps the coefficients of the component.
-    fn stamp(&self, view: &mut ABMatrixView, dt: f64);
+    ///
+    /// `guess` is the current Newton-Raphson estimate of the solution. Nonlinear components
+    /// linearize themselves around it, linear components can ignore it.
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64);
 
     /// Updates the component state based on the given solution.
     fn update(&mut self, view: &XMatrixView, dt: f64);
+
+    /// Stamps the coefficients of the component with its derivatives discretized by the given
+    /// integration method. Components without a companion model for the method stamp their
+    /// backward Euler one.
+    fn stamp_with_integration(
+        &self,
+        view: &mut ABMatrixView,
+        guess: &XMatrixView,
+        dt: f64,
+        _integration: IntegrationMethod,
+    ) {
+        self.stamp(view, guess, dt);
+    }
+
+    /// Updates the component state based on the given solution of a timestep integrated with
+    /// the given method (see [`Stampable::stamp_with_integration`]).
+    fn update_with_integration(
+        &mut self,
+        view: &XMatrixView,
+        dt: f64,
+        _integration: IntegrationMethod,
+    ) {
+        self.update(view, dt);
+    }
+
+    /// Stamps the coefficients of the component at its DC operating point: capacitances open,
+    /// inductances shorted and time standing still. By default this is the stamp of a timestep
+    /// of zero, holding time-dependent components at the present time; components storing
+    /// energy override it.
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        self.stamp(view, guess, 0.0);
+    }
+
+    /// Updates the component state from the solution of the DC operating point (see
+    /// [`Stampable::stamp_dc`]), leaving it in a steady state a transient can start from.
+    fn update_dc(&mut self, view: &XMatrixView) {
+        self.update(view, 0.0);
+    }
+
+    /// Stamps the small signal admittances of the component at angular frequency omega,
+    /// linearized around its present state, for AC analysis.
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64);
+
+    /// Returns the value of the state the component carries between timesteps (for example the
+    /// voltage of a capacitor), used to detect fast transients. Stateless components return
+    /// `None`.
+    fn state(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns the quantity a reactive component integrates (the charge of a capacitor, the
+    /// flux linkage of an inductor), used to estimate the local truncation error of a timestep.
+    /// Components integrating nothing return `None`.
+    fn integrated_quantity(&self) -> Option<IntegratedQuantity> {
+        None
+    }
+
+    /// Returns the node pairs of the component's nonlinear junctions. The solver stamps a
+    /// minimum conductance (gmin) across each of them.
+    fn junctions(&self) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+
+    /// Returns the Taylor expansion of the current of each nonlinear junction around the
+    /// component's present state, used for distortion analysis.
+    fn junction_expansions(&self) -> Vec<JunctionExpansion> {
+        Vec::new()
+    }
+
+    /// Returns the time of the next discontinuity the component has scheduled, if any, so the
+    /// timestep can be split to land on it (see [`BESolver::solve`](crate::BESolver::solve)).
+    fn breakpoint(&self) -> Option<f64> {
+        None
+    }
+
+    /// Limits the controlling voltages of the component's exponential junctions between two
+    /// Newton-Raphson iterations (SPICE pnjlim), returning the variables of the proposed
+    /// solution to replace and their limited values, applied before the next iteration stamps.
+    /// Components without junctions to limit return nothing.
+    fn limit(
+        &self,
+        _proposed: &XMatrixView,
+        _previous: &XMatrixView,
+    ) -> Vec<(ViewVariableIndex, f64)> {
+        Vec::new()
+    }
+}
+
+/// Conductance shorting an inductor without a branch current at DC.
+const DC_SHORT_CONDUCTANCE: f64 = 1e6;
+
+/// How the derivatives of the reactive components are treated when stamping a system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Discretization {
+    /// Discretized over a timestep dt by the integration method.
+    Timestep(f64, IntegrationMethod),
+    /// Zero, for the DC operating point.
+    Dc,
+}
+
+impl Discretization {
+    /// Gets the timestep, zero for the DC operating point.
+    pub(crate) fn dt(self) -> f64 {
+        match self {
+            Self::Timestep(dt, _) => dt,
+            Self::Dc => 0.0,
+        }
+    }
+
+    pub(crate) fn stamp(
+        self,
+        component: &impl Stampable,
+        view: &mut ABMatrixView,
+        guess: &XMatrixView,
+    ) {
+        match self {
+            Self::Timestep(dt, integration) => {
+                component.stamp_with_integration(view, guess, dt, integration)
+            }
+            Self::Dc => component.stamp_dc(view, guess),
+        }
+    }
+
+    pub(crate) fn update(self, component: &mut impl Stampable, view: &XMatrixView) {
+        match self {
+            Self::Timestep(dt, integration) => {
+                component.update_with_integration(view, dt, integration)
+            }
+            Self::Dc => component.update_dc(view),
+        }
+    }
+}
+
+/// The quantity a reactive component integrates over a timestep, along with what is needed
+/// to estimate its local truncation error (see [`AdaptiveTimestep`](crate::AdaptiveTimestep)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegratedQuantity {
+    /// The quantity at the end of the last timestep.
+    pub value: f64,
+    /// The quantity before the last timestep and the length of that timestep, `None` before the
+    /// first timestep.
+    pub previous: Option<(f64, f64)>,
+    /// The derivative of the quantity with respect to the state (the capacitance, the
+    /// inductance), converting errors of the quantity into errors of the state.
+    pub scale: f64,
+}
+
+/// The expansion i = g1*v + g2*v^2 + g3*v^3 of the small signal current flowing from the
+/// positive to the negative node of a nonlinear junction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JunctionExpansion {
+    pub positive_node: usize,
+    pub negative_node: usize,
+    pub g1: f64,
+    pub g2: f64,
+    pub g3: f64,
 }
 
 impl Stampable for Resistor {
@@ -19,7 +198,7 @@ impl Stampable for Resistor {
         0
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, _dt: f64) {
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
         let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
         let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
 
@@ -47,58 +226,265 @@ impl Stampable for Resistor {
                 - view.get_variable(negative_voltage_index).unwrap(),
         );
     }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let g = Complex::from(1.0 / self.get_resistance());
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), g);
+    }
+}
+
+/// The companion model discretizing dx/dt = y (the charge and current of a capacitor, the flux
+/// and voltage of an inductor) over a timestep as y = (x - x_past)/h - y_past.
+struct Companion {
+    h: f64,
+    x_past: f64,
+    y_past: f64,
+}
+
+impl Companion {
+    /// Builds the companion model of a timestep dt from x and y at the start of the timestep and,
+    /// for two-step methods, x before the previous timestep along with that timestep's length.
+    fn new(
+        integration: IntegrationMethod,
+        dt: f64,
+        x: f64,
+        y: f64,
+        history: Option<(f64, f64)>,
+    ) -> Self {
+        let backward_euler = Self {
+            h: dt,
+            x_past: x,
+            y_past: 0.0,
+        };
+        match integration {
+            IntegrationMethod::BackwardEuler => backward_euler,
+            // Averaging y at both ends of the timestep, (y + y_old)/2 = (x - x_old)/dt, is
+            // backward Euler over half the timestep less the old y.
+            IntegrationMethod::Trapezoidal => Self {
+                h: dt / 2.0,
+                x_past: x,
+                y_past: y,
+            },
+            // BDF2 with a variable timestep: with w = dt/dt_previous,
+            // y*dt = a0*x_new - a1*x_old + a2*x_previous, a0 = (1 + 2w)/(1 + w), a1 = 1 + w and
+            // a2 = w^2/(1 + w). The first timestep has no history and falls back to backward
+            // Euler.
+            IntegrationMethod::Gear2 => match history {
+                Some((x_previous, dt_previous)) if dt_previous > 0.0 => {
+                    let w = dt / dt_previous;
+                    let a0 = (1.0 + 2.0 * w) / (1.0 + w);
+                    let a1 = 1.0 + w;
+                    let a2 = w * w / (1.0 + w);
+                    Self {
+                        h: dt / a0,
+                        x_past: (a1 * x - a2 * x_previous) / a0,
+                        y_past: 0.0,
+                    }
+                }
+                _ => backward_euler,
+            },
+        }
+    }
 }
 
 impl Stampable for Capacitor {
     fn num_variables(&self) -> usize {
-        0
+        if self.has_branch_current() { 1 } else { 0 }
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        self.stamp_with_integration(view, guess, dt, IntegrationMethod::BackwardEuler);
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, dt: f64) {
+    fn stamp_with_integration(
+        &self,
+        view: &mut ABMatrixView,
+        guess: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
         let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
         let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
 
         let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
         let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
 
-        let c = self.get_capacitance();
+        let v_guess = guess.get_variable(positive_voltage_index).unwrap()
+            - guess.get_variable(negative_voltage_index).unwrap();
 
-        // The differential equation describing a capacitor is i = C*dv/dt.
-        // Discretizing we get i = C*(v_new - v_old)/dt.
-        // Further expanding this we get i = C*(v_positive - v_negative)/dt - C*v_old/dt.
+        // The differential equation describing a capacitor is i = dq/dt.
+        // Discretizing we get i = (q(v_new) - q_old)/dt.
+        // Stamping the charge rather than C*dv/dt keeps charge conserved when the capacitance
+        // depends on the voltage.
+        // Other integration methods have the same form, i = (q(v_new) - q_past)/h - i_past (see
+        // Companion).
+        let Companion { h, x_past, y_past } = Companion::new(
+            integration,
+            dt,
+            self.get_charge(),
+            self.get_current(),
+            self.get_previous_charge(),
+        );
+        // Linearizing q around the guessed voltage, q(v) = q(v_guess) + C(v_guess)*(v - v_guess),
+        // we get i = C*(v_positive - v_negative)/h + (q(v_guess) - C*v_guess - q_past)/h - i_past.
+        let model = self.get_model();
+        let c = model.capacitance(v_guess);
+        let i_eq = (model.charge(v_guess) - c * v_guess - x_past) / h - y_past;
+
+        if self.has_branch_current() {
+            let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+
+            // Current flowing out of positive node is i
+            view.coefficient_add(positive_equation_index, current_index, 1.0);
+            // Current flowing out of negative node is -i
+            view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+            // Branch equation is i - C*v_positive/h + C*v_negative/h = i_eq
+            view.coefficient_add(specific_equation_index, current_index, 1.0);
+            view.coefficient_add(specific_equation_index, positive_voltage_index, -c / h);
+            view.coefficient_add(specific_equation_index, negative_voltage_index, c / h);
+            view.result_add(specific_equation_index, i_eq);
+            return;
+        }
 
-        // Current flowing out of the positive node is C*v_positive/dt - C*v_negative/dt - C*v_old/dt.
-        view.coefficient_add(positive_equation_index, positive_voltage_index, c / dt);
-        view.coefficient_add(positive_equation_index, negative_voltage_index, -c / dt);
-        view.result_add(positive_equation_index, c * self.get_voltage() / dt);
+        // Current flowing out of the positive node is C*v_positive/h - C*v_negative/h + i_eq.
+        view.coefficient_add(positive_equation_index, positive_voltage_index, c / h);
+        view.coefficient_add(positive_equation_index, negative_voltage_index, -c / h);
+        view.result_add(positive_equation_index, -i_eq);
 
-        // Current flowing out of the negative node is -C*v_positive/dt + C*v_negative/dt + C*v_old/dt.
-        view.coefficient_add(negative_equation_index, positive_voltage_index, -c / dt);
-        view.coefficient_add(negative_equation_index, negative_voltage_index, c / dt);
-        view.result_add(negative_equation_index, -c * self.get_voltage() / dt);
+        // Current flowing out of the negative node is -C*v_positive/h + C*v_negative/h - i_eq.
+        view.coefficient_add(negative_equation_index, positive_voltage_index, -c / h);
+        view.coefficient_add(negative_equation_index, negative_voltage_index, c / h);
+        view.result_add(negative_equation_index, i_eq);
     }
 
     fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.update_with_integration(view, dt, IntegrationMethod::BackwardEuler);
+    }
+
+    fn update_with_integration(
+        &mut self,
+        view: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
         let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
         let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
 
         let new_voltage = view.get_variable(positive_voltage_index).unwrap()
             - view.get_variable(negative_voltage_index).unwrap();
 
-        // Discretized equation is i = C*(v_new - v_old)/dt (see capacitor stamping function).
+        let companion = Companion::new(
+            integration,
+            dt,
+            self.get_charge(),
+            self.get_current(),
+            self.get_previous_charge(),
+        );
+        self.set_previous_charge(Some((self.get_charge(), dt)));
+        self.set_voltage(new_voltage);
+
+        if self.has_branch_current() {
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+            self.set_current(view.get_variable(current_index).unwrap());
+            return;
+        }
+
+        // Discretized equation is i = (q(v_new) - q_past)/h - i_past (see capacitor stamping
+        // function).
+        self.set_current((self.get_charge() - companion.x_past) / companion.h - companion.y_past);
+    }
 
-        self.set_current(self.get_capacitance() * (new_voltage - self.get_voltage()) / dt);
+    fn state(&self) -> Option<f64> {
+        Some(self.get_voltage())
+    }
 
-        self.set_voltage(new_voltage);
+    fn integrated_quantity(&self) -> Option<IntegratedQuantity> {
+        Some(IntegratedQuantity {
+            value: self.get_charge(),
+            previous: self.get_previous_charge(),
+            scale: self.get_capacitance(),
+        })
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, _guess: &XMatrixView) {
+        // A capacitor is open at DC, only its branch current (held at zero) is stamped.
+        if self.has_branch_current() {
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+            view.coefficient_add(
+                ViewEquationIndex::NodalEquation(self.get_positive_node()),
+                current_index,
+                1.0,
+            );
+            view.coefficient_add(
+                ViewEquationIndex::NodalEquation(self.get_negative_node()),
+                current_index,
+                -1.0,
+            );
+            view.coefficient_add(ViewEquationIndex::SpecificEquation(0), current_index, 1.0);
+        }
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        // Charged to the bias with no current flowing, and no history before it.
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_current(0.0);
+        self.set_previous_charge(None);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        // The admittance of a capacitor is j*omega*C.
+        let y = Complex::new(0.0, omega * self.get_capacitance());
+
+        if self.has_branch_current() {
+            let positive_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_positive_node());
+            let negative_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+            let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+            let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+            let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+
+            view.coefficient_add(positive_equation_index, current_index, Complex::from(1.0));
+            view.coefficient_add(negative_equation_index, current_index, Complex::from(-1.0));
+
+            // Branch equation is i - y*v_positive + y*v_negative = 0
+            view.coefficient_add(specific_equation_index, current_index, Complex::from(1.0));
+            view.coefficient_add(specific_equation_index, positive_voltage_index, -y);
+            view.coefficient_add(specific_equation_index, negative_voltage_index, y);
+            return;
+        }
+
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), y);
     }
 }
 
 impl Stampable for Inductor {
     fn num_variables(&self) -> usize {
-        0
+        if self.has_branch_current() { 1 } else { 0 }
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        self.stamp_with_integration(view, guess, dt, IntegrationMethod::BackwardEuler);
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, dt: f64) {
+    fn stamp_with_integration(
+        &self,
+        view: &mut ABMatrixView,
+        guess: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
         let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
         let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
 
@@ -112,30 +498,204 @@ impl Stampable for Inductor {
         // Further expanding this we get v_positve - v_negative = L*(i_new - i_old)/dt.
         // Doing some algebra to solver for i_new we get:
         // i_new = v_positive*dt/L - v_negative*dt/L + i_old.
+        // Other integration methods have the same form in terms of the flux L*i (see
+        // Companion), v = L*(i_new - i_past)/h - v_past, so i_new = (v + v_past)*h/L + i_past.
+        let Companion { h, x_past, y_past } = Companion::new(
+            integration,
+            dt,
+            self.flux(self.get_current()),
+            self.get_voltage(),
+            self.get_previous_current()
+                .map(|(i, dt)| (self.flux(i), dt)),
+        );
 
-        // Current flowing out of the positive node is v_positive*dt/L - v_negative*dt/L + i_old.
-        view.coefficient_add(positive_equation_index, positive_voltage_index, dt / l);
-        view.coefficient_add(positive_equation_index, negative_voltage_index, -dt / l);
-        view.result_add(positive_equation_index, -self.get_current());
+        if self.has_branch_current() {
+            let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let current_index = ViewVariableIndex::SpecificVariable(0);
 
-        // Current flowing out of the negative node is -v_positive*dt/L + v_negative*dt/L - i_old.
-        view.coefficient_add(negative_equation_index, positive_voltage_index, -dt / l);
-        view.coefficient_add(negative_equation_index, negative_voltage_index, dt / l);
-        view.result_add(negative_equation_index, self.get_current());
+            // Current flowing out of positive node is i_new
+            view.coefficient_add(positive_equation_index, current_index, 1.0);
+            // Current flowing out of negative node is -i_new
+            view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+            // In terms of flux the inductor equation is v = dflux/dt, discretized to
+            // v = (flux(i_new) - flux_past)/h - v_past.
+            // Linearizing the flux around the guessed current,
+            // flux(i) = flux(i_guess) + L(i_guess)*(i - i_guess), the branch equation is
+            // v_positive - v_negative - L*i_new/h
+            //     = (flux(i_guess) - L*i_guess - flux_past)/h - v_past.
+            let i_guess = guess.get_variable(current_index).unwrap();
+            let l = self.incremental_inductance(i_guess);
+            let flux_eq = self.flux(i_guess) - l * i_guess - x_past;
+
+            view.coefficient_add(specific_equation_index, positive_voltage_index, 1.0);
+            view.coefficient_add(specific_equation_index, negative_voltage_index, -1.0);
+            view.coefficient_add(specific_equation_index, current_index, -l / h);
+            view.result_add(specific_equation_index, flux_eq / h - y_past);
+            return;
+        }
+
+        let i_eq = (x_past + y_past * h) / l;
+
+        // Current flowing out of the positive node is v_positive*h/L - v_negative*h/L + i_eq.
+        view.coefficient_add(positive_equation_index, positive_voltage_index, h / l);
+        view.coefficient_add(positive_equation_index, negative_voltage_index, -h / l);
+        view.result_add(positive_equation_index, -i_eq);
+
+        // Current flowing out of the negative node is -v_positive*h/L + v_negative*h/L - i_eq.
+        view.coefficient_add(negative_equation_index, positive_voltage_index, -h / l);
+        view.coefficient_add(negative_equation_index, negative_voltage_index, h / l);
+        view.result_add(negative_equation_index, i_eq);
     }
 
     fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.update_with_integration(view, dt, IntegrationMethod::BackwardEuler);
+    }
+
+    fn update_with_integration(
+        &mut self,
+        view: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
         let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
         let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
 
+        let companion = Companion::new(
+            integration,
+            dt,
+            self.flux(self.get_current()),
+            self.get_voltage(),
+            self.get_previous_current()
+                .map(|(i, dt)| (self.flux(i), dt)),
+        );
+        self.set_previous_current(Some((self.get_current(), dt)));
         self.set_voltage(
             view.get_variable(positive_voltage_index).unwrap()
                 - view.get_variable(negative_voltage_index).unwrap(),
         );
 
-        // Discretized equation is i_new = v_positive*dt/L - v_negative*dt/L + i_old (see inductor stamping function).
+        if self.has_branch_current() {
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+            self.set_current(view.get_variable(current_index).unwrap());
+            return;
+        }
+
+        // Discretized equation is i_new = (v + v_past)*h/L + i_past (see inductor stamping
+        // function).
+        let Companion { h, x_past, y_past } = companion;
+        self.set_current((x_past + (self.get_voltage() + y_past) * h) / self.get_inductance());
+    }
+
+    fn state(&self) -> Option<f64> {
+        Some(self.get_current())
+    }
+
+    fn integrated_quantity(&self) -> Option<IntegratedQuantity> {
+        Some(IntegratedQuantity {
+            value: self.flux(self.get_current()),
+            previous: self
+                .get_previous_current()
+                .map(|(i, dt)| (self.flux(i), dt)),
+            scale: self.incremental_inductance(self.get_current()),
+        })
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, _guess: &XMatrixView) {
+        // An inductor is a short at DC.
+        if self.has_branch_current() {
+            let positive_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_positive_node());
+            let negative_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_negative_node());
+            let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+
+            view.coefficient_add(positive_equation_index, current_index, 1.0);
+            view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+            // Branch equation is v_positive - v_negative = 0
+            view.coefficient_add(
+                specific_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+                1.0,
+            );
+            view.coefficient_add(
+                specific_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+                -1.0,
+            );
+            return;
+        }
+
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            DC_SHORT_CONDUCTANCE,
+        );
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        let voltage = view.get_variable(positive_voltage_index).unwrap()
+            - view.get_variable(negative_voltage_index).unwrap();
+        let current = if self.has_branch_current() {
+            view.get_variable(ViewVariableIndex::SpecificVariable(0))
+                .unwrap()
+        } else {
+            DC_SHORT_CONDUCTANCE * voltage
+        };
+
+        // Fluxed up to the bias current, with no history before it.
+        self.set_voltage(voltage);
+        self.set_current(current);
+        self.set_previous_current(None);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        // The impedance of an inductor is j*omega*L.
+        let z = Complex::new(0.0, omega * self.incremental_inductance(self.get_current()));
+
+        if self.has_branch_current() {
+            let positive_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_positive_node());
+            let negative_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_negative_node());
 
-        self.set_current(self.get_voltage() * dt / self.get_inductance() + self.get_current());
+            let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+            let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+            let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let current_index = ViewVariableIndex::SpecificVariable(0);
+
+            view.coefficient_add(positive_equation_index, current_index, Complex::from(1.0));
+            view.coefficient_add(negative_equation_index, current_index, Complex::from(-1.0));
+
+            // Branch equation is v_positive - v_negative - z*i = 0
+            view.coefficient_add(
+                specific_equation_index,
+                positive_voltage_index,
+                Complex::from(1.0),
+            );
+            view.coefficient_add(
+                specific_equation_index,
+                negative_voltage_index,
+                Complex::from(-1.0),
+            );
+            view.coefficient_add(specific_equation_index, current_index, -z);
+            return;
+        }
+
+        // At DC the inductor is a short, stamped as a large conductance as for the operating
+        // point.
+        let y = if z == Complex::from(0.0) {
+            Complex::from(DC_SHORT_CONDUCTANCE)
+        } else {
+            z.inv()
+        };
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), y);
     }
 }
 
@@ -144,7 +704,7 @@ impl Stampable for VoltageSource {
         1
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, _dt: f64) {
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
         let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
         let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
         let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
@@ -158,16 +718,58 @@ impl Stampable for VoltageSource {
         // Current flowing out of negative node is i_source
         view.coefficient_add(negative_equation_index, current_index, 1.0);
 
-        // Source equation is v_positive - v_negative = v_source
+        // Source equation is v_positive - v_negative + r_series * i_source = v_source
         view.coefficient_add(specific_equation_index, positive_voltage_index, 1.0);
         view.coefficient_add(specific_equation_index, negative_voltage_index, -1.0);
-        view.result_add(specific_equation_index, self.get_voltage());
+        view.coefficient_add(
+            specific_equation_index,
+            current_index,
+            self.get_series_resistance(),
+        );
+        // The source is evaluated at the end of the timestep.
+        view.result_add(
+            specific_equation_index,
+            self.get_voltage_at(self.get_time() + dt),
+        );
     }
 
-    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+
         let current_index = ViewVariableIndex::SpecificVariable(0);
         self.set_current(view.get_variable(current_index).unwrap());
     }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Same as the transient stamp with the source voltage replaced by its AC phasor.
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(-1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(1.0));
+
+        view.coefficient_add(
+            specific_equation_index,
+            positive_voltage_index,
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            negative_voltage_index,
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            current_index,
+            Complex::from(self.get_series_resistance()),
+        );
+        view.result_add(specific_equation_index, self.get_ac_phasor());
+    }
 }
 
 impl Stampable for CurrentSource {
@@ -175,17 +777,73 @@ impl Stampable for CurrentSource {
         0
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, _dt: f64) {
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
         let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
         let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
 
         // NOTE: the signs are flipped here because they take the form of constants, not
         // coefficients.
 
+        // The source is evaluated at the end of the timestep.
+        let current = self.get_current_at(self.get_time() + dt);
+
         // Current flowing out of positive node is -i_source
-        view.result_add(positive_equation_index, self.get_current());
+        view.result_add(positive_equation_index, current);
         // Current flowing out of negative node is i_source
-        view.result_add(negative_equation_index, -self.get_current());
+        view.result_add(negative_equation_index, -current);
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        // Same as the transient stamp with the source current replaced by its AC phasor.
+        view.result_add(positive_equation_index, self.get_ac_phasor());
+        view.result_add(negative_equation_index, -self.get_ac_phasor());
+    }
+}
+
+impl Stampable for Diode {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, _dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        let v_guess = guess.get_variable(positive_voltage_index).unwrap()
+            - guess.get_variable(negative_voltage_index).unwrap();
+
+        // The diode is linearized around the guessed voltage as a conductance in parallel with a
+        // current source: i = g*v + (i_guess - g*v_guess).
+        let (i_guess, g) = self.get_model().evaluate(v_guess);
+        let i_eq = i_guess - g * v_guess;
+
+        // Current flowing out of the positive node is g*v_positive - g*v_negative + i_eq.
+        view.coefficient_add(positive_equation_index, positive_voltage_index, g);
+        view.coefficient_add(positive_equation_index, negative_voltage_index, -g);
+        view.result_add(positive_equation_index, -i_eq);
+
+        // Current flowing out of the negative node is -g*v_positive + g*v_negative - i_eq.
+        view.coefficient_add(negative_equation_index, positive_voltage_index, -g);
+        view.coefficient_add(negative_equation_index, negative_voltage_index, g);
+        view.result_add(negative_equation_index, i_eq);
     }
 
     fn update(&mut self, view: &XMatrixView, _dt: f64) {
@@ -196,37 +854,2900 @@ impl Stampable for CurrentSource {
             view.get_variable(positive_voltage_index).unwrap()
                 - view.get_variable(negative_voltage_index).unwrap(),
         );
+
+        let (current, _) = self.get_model().evaluate(self.get_voltage());
+        self.set_current(current);
+    }
+
+    fn junctions(&self) -> Vec<(usize, usize)> {
+        vec![(self.get_positive_node(), self.get_negative_node())]
+    }
+
+    fn limit(
+        &self,
+        proposed: &XMatrixView,
+        previous: &XMatrixView,
+    ) -> Vec<(ViewVariableIndex, f64)> {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let voltage = |view: &XMatrixView| {
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap()
+        };
+
+        let v_proposed = voltage(proposed);
+        let v_limited = self
+            .get_model()
+            .limit_voltage(v_proposed, voltage(previous));
+        if v_limited == v_proposed {
+            return Vec::new();
+        }
+
+        // The limited voltage is reached by moving the positive node, or the negative node if
+        // the positive one is the reference.
+        if proposed.is_reference(self.get_positive_node()) {
+            vec![(negative_voltage_index, -v_limited)]
+        } else {
+            let v_negative = proposed.get_variable(negative_voltage_index).unwrap();
+            vec![(positive_voltage_index, v_negative + v_limited)]
+        }
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The small signal model of a diode is its conductance at the operating point.
+        let (_, g) = self.get_model().evaluate(self.get_voltage());
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(g),
+        );
+    }
+
+    fn junction_expansions(&self) -> Vec<JunctionExpansion> {
+        let [g1, g2, g3] = self.get_model().taylor_coefficients(self.get_voltage());
+        vec![JunctionExpansion {
+            positive_node: self.get_positive_node(),
+            negative_node: self.get_negative_node(),
+            g1,
+            g2,
+            g3,
+        }]
     }
 }
 
-impl Stampable for Component {
+impl Bjt {
+    /// Gets the base-emitter and base-collector voltages of the transistor in a solution.
+    fn terminal_voltages(&self, view: &XMatrixView) -> (f64, f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let base_voltage = node_voltage(self.get_base_node());
+        (
+            base_voltage - node_voltage(self.get_emitter_node()),
+            base_voltage - node_voltage(self.get_collector_node()),
+        )
+    }
+
+    /// Gets the current flowing into each terminal (collector, base and emitter) along with its
+    /// derivatives with respect to the base-emitter and base-collector voltages.
+    fn terminal_currents(&self, vbe: f64, vbc: f64) -> [(usize, f64, [f64; 2]); 3] {
+        let (ic, ib, [dic, dib]) = self.evaluate(vbe, vbc);
+        [
+            (self.get_collector_node(), ic, dic),
+            (self.get_base_node(), ib, dib),
+            (
+                self.get_emitter_node(),
+                -(ic + ib),
+                [-(dic[0] + dib[0]), -(dic[1] + dib[1])],
+            ),
+        ]
+    }
+
+    /// Turns derivatives with respect to the base-emitter and base-collector voltages into
+    /// derivatives with respect to the collector, base and emitter voltages: with vbe = vb - ve
+    /// and vbc = vb - vc, d/dvc = -d/dvbc, d/dvb = d/dvbe + d/dvbc and d/dve = -d/dvbe.
+    fn node_partials(&self, [d_vbe, d_vbc]: [f64; 2]) -> [(ViewVariableIndex, f64); 3] {
+        [
+            (
+                ViewVariableIndex::NodeVoltage(self.get_collector_node()),
+                -d_vbc,
+            ),
+            (
+                ViewVariableIndex::NodeVoltage(self.get_base_node()),
+                d_vbe + d_vbc,
+            ),
+            (
+                ViewVariableIndex::NodeVoltage(self.get_emitter_node()),
+                -d_vbe,
+            ),
+        ]
+    }
+}
+
+impl Stampable for Bjt {
     fn num_variables(&self) -> usize {
-        match self {
-            Self::Resistor(c) => c.num_variables(),
-            Self::Capacitor(c) => c.num_variables(),
-            Self::Inductor(c) => c.num_variables(),
-            Self::VoltageSource(c) => c.num_variables(),
-            Self::CurrentSource(c) => c.num_variables(),
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, _dt: f64) {
+        let (vbe, vbc) = self.terminal_voltages(guess);
+
+        // Each terminal current is linearized around the guessed voltages as
+        // i = di/dvbe*vbe + di/dvbc*vbc + (i_guess - di/dvbe*vbe_guess - di/dvbc*vbc_guess),
+        // flowing out of the terminal's node.
+        for (node, current, derivatives) in self.terminal_currents(vbe, vbc) {
+            let equation_index = ViewEquationIndex::NodalEquation(node);
+            for (voltage_index, partial) in self.node_partials(derivatives) {
+                view.coefficient_add(equation_index, voltage_index, partial);
+            }
+            let i_eq = current - derivatives[0] * vbe - derivatives[1] * vbc;
+            view.result_add(equation_index, -i_eq);
         }
     }
 
-    fn stamp(&self, view: &mut ABMatrixView, dt: f64) {
-        match self {
-            Self::Resistor(c) => c.stamp(view, dt),
-            Self::Capacitor(c) => c.stamp(view, dt),
-            Self::Inductor(c) => c.stamp(view, dt),
-            Self::VoltageSource(c) => c.stamp(view, dt),
-            Self::CurrentSource(c) => c.stamp(view, dt),
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let (vbe, vbc) = self.terminal_voltages(view);
+        let (ic, ib, _) = self.evaluate(vbe, vbc);
+        self.set_junction_voltages(vbe, vbc);
+        self.set_currents(ic, ib);
+    }
+
+    fn junctions(&self) -> Vec<(usize, usize)> {
+        let (base, collector, emitter) = (
+            self.get_base_node(),
+            self.get_collector_node(),
+            self.get_emitter_node(),
+        );
+        match self.get_polarity() {
+            BjtPolarity::Npn => vec![(base, emitter), (base, collector)],
+            BjtPolarity::Pnp => vec![(emitter, base), (collector, base)],
         }
     }
 
-    fn update(&mut self, view: &XMatrixView, dt: f64) {
-        match self {
-            Self::Resistor(c) => c.update(view, dt),
-            Self::Capacitor(c) => c.update(view, dt),
-            Self::Inductor(c) => c.update(view, dt),
-            Self::VoltageSource(c) => c.update(view, dt),
-            Self::CurrentSource(c) => c.update(view, dt),
+    fn limit(
+        &self,
+        proposed: &XMatrixView,
+        previous: &XMatrixView,
+    ) -> Vec<(ViewVariableIndex, f64)> {
+        let node_voltage = |node| {
+            proposed
+                .get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let sign = self.get_polarity().sign();
+        let model = self.junction_model();
+        let (previous_vbe, previous_vbc) = self.terminal_voltages(previous);
+        let (previous_vbe, previous_vbc) = self.junction_voltages(previous_vbe, previous_vbc);
+        let mut limited = Vec::new();
+
+        // The base-emitter junction is limited by moving the base, or the emitter if the base
+        // is the reference, and the base-collector junction then by moving the collector.
+        let mut base_voltage = node_voltage(self.get_base_node());
+        let emitter_voltage = node_voltage(self.get_emitter_node());
+        let vbe = sign * (base_voltage - emitter_voltage);
+        let vbe_limited = model.limit_voltage(vbe, previous_vbe);
+        if vbe_limited != vbe {
+            if proposed.is_reference(self.get_base_node()) {
+                limited.push((
+                    ViewVariableIndex::NodeVoltage(self.get_emitter_node()),
+                    base_voltage - sign * vbe_limited,
+                ));
+            } else {
+                base_voltage = emitter_voltage + sign * vbe_limited;
+                limited.push((
+                    ViewVariableIndex::NodeVoltage(self.get_base_node()),
+                    base_voltage,
+                ));
+            }
+        }
+
+        let vbc = sign * (base_voltage - node_voltage(self.get_collector_node()));
+        let vbc_limited = model.limit_voltage(vbc, previous_vbc);
+        if vbc_limited != vbc && !proposed.is_reference(self.get_collector_node()) {
+            limited.push((
+                ViewVariableIndex::NodeVoltage(self.get_collector_node()),
+                base_voltage - sign * vbc_limited,
+            ));
+        }
+        limited
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The small signal model is the hybrid-pi model without capacitances: the derivatives
+        // of the terminal currents at the operating point.
+        let currents = self.terminal_currents(
+            self.get_base_emitter_voltage(),
+            self.get_base_collector_voltage(),
+        );
+        for (node, _, derivatives) in currents {
+            let equation_index = ViewEquationIndex::NodalEquation(node);
+            for (voltage_index, partial) in self.node_partials(derivatives) {
+                view.coefficient_add(equation_index, voltage_index, Complex::from(partial));
+            }
+        }
+    }
+}
+
+impl Stampable for Switch {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        // The switch is a resistor whose conductance is evaluated at the end of the timestep,
+        // from the guessed gate voltage for a gated switch.
+        let g = match self.get_gate() {
+            Some((gate_positive_node, gate_negative_node, _)) => self.get_conductance_for_gate(
+                guess
+                    .get_variable(ViewVariableIndex::NodeVoltage(gate_positive_node))
+                    .unwrap()
+                    - guess
+                        .get_variable(ViewVariableIndex::NodeVoltage(gate_negative_node))
+                        .unwrap(),
+            ),
+            None => self.get_conductance_at(self.get_time() + dt),
+        };
+
+        view.coefficient_add(positive_equation_index, positive_voltage_index, g);
+        view.coefficient_add(positive_equation_index, negative_voltage_index, -g);
+
+        view.coefficient_add(negative_equation_index, positive_voltage_index, -g);
+        view.coefficient_add(negative_equation_index, negative_voltage_index, g);
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        self.set_time(self.get_time() + dt);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        if let Some((gate_positive_node, gate_negative_node, _)) = self.get_gate() {
+            self.set_gate_voltage(
+                view.get_variable(ViewVariableIndex::NodeVoltage(gate_positive_node))
+                    .unwrap()
+                    - view
+                        .get_variable(ViewVariableIndex::NodeVoltage(gate_negative_node))
+                        .unwrap(),
+            );
+        }
+    }
+
+    fn state(&self) -> Option<f64> {
+        Some(self.get_conductance())
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(self.get_conductance()),
+        );
+    }
+}
+
+impl Stampable for CurrentProbe {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Current flowing out of positive node is i_probe
+        view.coefficient_add(positive_equation_index, current_index, 1.0);
+        // Current flowing out of negative node is -i_probe
+        view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+        // Probe equation is v_positive - v_negative = 0
+        view.coefficient_add(specific_equation_index, positive_voltage_index, 1.0);
+        view.coefficient_add(specific_equation_index, negative_voltage_index, -1.0);
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+        self.set_current(view.get_variable(current_index).unwrap());
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(-1.0));
+
+        view.coefficient_add(
+            specific_equation_index,
+            positive_voltage_index,
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            negative_voltage_index,
+            Complex::from(-1.0),
+        );
+    }
+}
+
+impl Stampable for ElectronicLoad {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        let v_guess = guess.get_variable(positive_voltage_index).unwrap()
+            - guess.get_variable(negative_voltage_index).unwrap();
+
+        // Linearized around the guessed voltage like a diode, with the setpoint evaluated at the
+        // end of the timestep. Constant current and resistance modes are exact after one
+        // iteration.
+        let (i_guess, g) = self.evaluate(v_guess, self.get_time() + dt);
+        let i_eq = i_guess - g * v_guess;
+
+        // Current flowing out of the positive node is g*v_positive - g*v_negative + i_eq.
+        view.coefficient_add(positive_equation_index, positive_voltage_index, g);
+        view.coefficient_add(positive_equation_index, negative_voltage_index, -g);
+        view.result_add(positive_equation_index, -i_eq);
+
+        // Current flowing out of the negative node is -g*v_positive + g*v_negative - i_eq.
+        view.coefficient_add(negative_equation_index, positive_voltage_index, -g);
+        view.coefficient_add(negative_equation_index, negative_voltage_index, g);
+        view.result_add(negative_equation_index, i_eq);
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+
+        let (current, _) = self.evaluate(self.get_voltage(), self.get_time());
+        self.set_current(current);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The incremental conductance at the operating point, negative for a constant power
+        // load.
+        let (_, g) = self.evaluate(self.get_voltage(), self.get_time());
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(g),
+        );
+    }
+}
+
+impl Stampable for PvModule {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Current flowing out of positive node is -i_module
+        view.coefficient_add(positive_equation_index, current_index, -1.0);
+        // Current flowing out of negative node is i_module
+        view.coefficient_add(negative_equation_index, current_index, 1.0);
+
+        // The series resistance makes the model implicit in the current, so the model equation
+        // f(v, i) = 0 is linearized in both around the guess:
+        // df/dv*v + df/di*i = df/dv*v_guess + df/di*i_guess - f(v_guess, i_guess)
+        let v_guess = guess.get_variable(positive_voltage_index).unwrap()
+            - guess.get_variable(negative_voltage_index).unwrap();
+        let i_guess = guess.get_variable(current_index).unwrap();
+        let (f, dfdv, dfdi) = self.evaluate(v_guess, i_guess, self.get_time() + dt);
+
+        view.coefficient_add(specific_equation_index, positive_voltage_index, dfdv);
+        view.coefficient_add(specific_equation_index, negative_voltage_index, -dfdv);
+        view.coefficient_add(specific_equation_index, current_index, dfdi);
+        view.result_add(specific_equation_index, dfdv * v_guess + dfdi * i_guess - f);
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_current(view.get_variable(current_index).unwrap());
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // The model equation linearized at the operating point, with no excitation.
+        let (_, dfdv, dfdi) =
+            self.evaluate(self.get_voltage(), self.get_current(), self.get_time());
+
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(-1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(1.0));
+        view.coefficient_add(
+            specific_equation_index,
+            positive_voltage_index,
+            Complex::from(dfdv),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            negative_voltage_index,
+            Complex::from(-dfdv),
+        );
+        view.coefficient_add(specific_equation_index, current_index, Complex::from(dfdi));
+    }
+}
+
+impl RandlesCell {
+    /// Terminals of the cell: the positive node, the node between the series resistance and
+    /// the double layer, the node between the charge transfer resistance and the Warburg
+    /// element (the negative node without one) and the negative node.
+    fn terminals(&self) -> [(ViewEquationIndex, ViewVariableIndex); 4] {
+        let node = |n| {
+            (
+                ViewEquationIndex::NodalEquation(n),
+                ViewVariableIndex::NodeVoltage(n),
+            )
+        };
+        let internal = |k| {
+            (
+                ViewEquationIndex::SpecificEquation(k),
+                ViewVariableIndex::SpecificVariable(k),
+            )
+        };
+
+        let negative = node(self.get_negative_node());
+        let faradaic = if self.get_warburg().is_some() {
+            internal(1)
+        } else {
+            negative
+        };
+        [
+            node(self.get_positive_node()),
+            internal(0),
+            faradaic,
+            negative,
+        ]
+    }
+
+    /// Companion conductances of the RC branches approximating a constant phase element, paired
+    /// with the capacitance of each branch.
+    fn companion_branches(
+        &self,
+        element: ConstantPhaseElement,
+        dt: f64,
+    ) -> [(f64, f64); CPE_BRANCHES] {
+        let (min_frequency, max_frequency) = self.get_band();
+        element
+            .branches(min_frequency, max_frequency)
+            .map(|(g, tau)| {
+                // A series RC discretized with backward Euler: i = (v - v_c)/(R + dt/C).
+                let c = tau * g;
+                (1.0 / (1.0 / g + dt / c), c)
+            })
+    }
+}
+
+impl Stampable for RandlesCell {
+    fn num_variables(&self) -> usize {
+        if self.get_warburg().is_some() { 2 } else { 1 }
+    }
+
+    fn variable_kind(&self, _index: usize) -> VariableKind {
+        // The variables are the voltages of the internal nodes (see RandlesCell::terminals).
+        VariableKind::Voltage
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        let [positive, double_layer, faradaic, negative] = self.terminals();
+
+        view.terminal_conductance_add(positive, double_layer, 1.0 / self.get_series_resistance());
+        view.terminal_conductance_add(
+            double_layer,
+            faradaic,
+            1.0 / self.get_charge_transfer_resistance(),
+        );
+
+        let mut elements = vec![(
+            double_layer,
+            self.get_double_layer(),
+            self.get_double_layer_voltages(),
+        )];
+        if let Some(warburg) = self.get_warburg() {
+            elements.push((faradaic, warburg, self.get_warburg_voltages()));
+        }
+
+        for (terminal, element, voltages) in elements {
+            for ((g, _), v_c) in self
+                .companion_branches(element, dt)
+                .into_iter()
+                .zip(voltages)
+            {
+                // Current flowing out of the terminal is g*(v_terminal - v_negative - v_c)
+                view.terminal_conductance_add(terminal, negative, g);
+                view.result_add(terminal.0, g * v_c);
+                view.result_add(negative.0, -g * v_c);
+            }
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let [positive, double_layer, faradaic, negative] = self.terminals();
+        let voltage = |terminal: (ViewEquationIndex, ViewVariableIndex)| {
+            view.get_variable(terminal.1).unwrap()
+        };
+
+        let advance = |element, v: f64, voltages: [f64; CPE_BRANCHES]| {
+            let mut voltages = voltages;
+            for ((g, c), v_c) in self
+                .companion_branches(element, dt)
+                .iter()
+                .zip(&mut voltages)
+            {
+                *v_c += g * (v - *v_c) * dt / c;
+            }
+            voltages
+        };
+
+        let v_negative = voltage(negative);
+        let double_layer_voltages = advance(
+            self.get_double_layer(),
+            voltage(double_layer) - v_negative,
+            self.get_double_layer_voltages(),
+        );
+        if let Some(warburg) = self.get_warburg() {
+            let warburg_voltages = advance(
+                warburg,
+                voltage(faradaic) - v_negative,
+                self.get_warburg_voltages(),
+            );
+            self.set_warburg_voltages(warburg_voltages);
+        }
+        self.set_double_layer_voltages(double_layer_voltages);
+
+        self.set_voltage(voltage(positive) - v_negative);
+        self.set_current(
+            (voltage(positive) - voltage(double_layer)) / self.get_series_resistance(),
+        );
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        // Over an infinitely long timestep the RC branches of the constant phase elements are
+        // open.
+        self.stamp(view, guess, f64::INFINITY);
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        let [positive, double_layer, faradaic, negative] = self.terminals();
+        let voltage = |terminal: (ViewEquationIndex, ViewVariableIndex)| {
+            view.get_variable(terminal.1).unwrap()
+        };
+
+        // The branches of the constant phase elements are charged to their voltage with no
+        // current flowing.
+        let v_negative = voltage(negative);
+        self.set_double_layer_voltages([voltage(double_layer) - v_negative; CPE_BRANCHES]);
+        if self.get_warburg().is_some() {
+            self.set_warburg_voltages([voltage(faradaic) - v_negative; CPE_BRANCHES]);
+        }
+
+        self.set_voltage(voltage(positive) - v_negative);
+        self.set_current(
+            (voltage(positive) - voltage(double_layer)) / self.get_series_resistance(),
+        );
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        let [positive, double_layer, faradaic, negative] = self.terminals();
+
+        view.terminal_conductance_add(
+            positive,
+            double_layer,
+            Complex::from(1.0 / self.get_series_resistance()),
+        );
+        view.terminal_conductance_add(
+            double_layer,
+            faradaic,
+            Complex::from(1.0 / self.get_charge_transfer_resistance()),
+        );
+        // AC analysis uses the exact admittances of the constant phase elements.
+        view.terminal_conductance_add(
+            double_layer,
+            negative,
+            self.get_double_layer().admittance(omega),
+        );
+        if let Some(warburg) = self.get_warburg() {
+            view.terminal_conductance_add(faradaic, negative, warburg.admittance(omega));
+        }
+    }
+}
+
+impl Stampable for ThermoelectricModule {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, _dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let hot_equation_index = ViewEquationIndex::NodalEquation(self.get_hot_node());
+        let cold_equation_index = ViewEquationIndex::NodalEquation(self.get_cold_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let hot_temperature_index = ViewVariableIndex::NodeVoltage(self.get_hot_node());
+        let cold_temperature_index = ViewVariableIndex::NodeVoltage(self.get_cold_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        let parameters = self.get_parameters();
+
+        // Current flowing out of positive node is i
+        view.coefficient_add(positive_equation_index, current_index, 1.0);
+        // Current flowing out of negative node is -i
+        view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+        // v_positive - v_negative - S*(t_hot - t_cold) - R*i = 0
+        view.coefficient_add(specific_equation_index, positive_voltage_index, 1.0);
+        view.coefficient_add(specific_equation_index, negative_voltage_index, -1.0);
+        view.coefficient_add(
+            specific_equation_index,
+            hot_temperature_index,
+            -parameters.seebeck,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            cold_temperature_index,
+            parameters.seebeck,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            current_index,
+            -parameters.resistance,
+        );
+
+        // The heat flows depend on products of the current and temperatures, so each is
+        // linearized around the guess: q = q_guess + dq/di*(i - i_guess) + ...
+        let i_guess = guess.get_variable(current_index).unwrap();
+        let th_guess = guess.get_variable(hot_temperature_index).unwrap();
+        let tc_guess = guess.get_variable(cold_temperature_index).unwrap();
+        let (absorbed, released) = self.heat_flows(i_guess, th_guess, tc_guess);
+
+        // Heat flowing out of the cold node is the heat absorbed, heat flowing out of the hot
+        // node minus the heat released.
+        for (equation_index, (q, dqdi, dqdth, dqdtc), sign) in [
+            (cold_equation_index, absorbed, 1.0),
+            (hot_equation_index, released, -1.0),
+        ] {
+            view.coefficient_add(equation_index, current_index, sign * dqdi);
+            view.coefficient_add(equation_index, hot_temperature_index, sign * dqdth);
+            view.coefficient_add(equation_index, cold_temperature_index, sign * dqdtc);
+
+            let q_eq = q - dqdi * i_guess - dqdth * th_guess - dqdtc * tc_guess;
+            view.result_add(equation_index, -sign * q_eq);
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let hot_temperature_index = ViewVariableIndex::NodeVoltage(self.get_hot_node());
+        let cold_temperature_index = ViewVariableIndex::NodeVoltage(self.get_cold_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_current(view.get_variable(current_index).unwrap());
+        self.set_temperatures(
+            view.get_variable(hot_temperature_index).unwrap(),
+            view.get_variable(cold_temperature_index).unwrap(),
+        );
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let hot_equation_index = ViewEquationIndex::NodalEquation(self.get_hot_node());
+        let cold_equation_index = ViewEquationIndex::NodalEquation(self.get_cold_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let hot_temperature_index = ViewVariableIndex::NodeVoltage(self.get_hot_node());
+        let cold_temperature_index = ViewVariableIndex::NodeVoltage(self.get_cold_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        let parameters = self.get_parameters();
+
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(-1.0));
+
+        for (variable_index, value) in [
+            (positive_voltage_index, 1.0),
+            (negative_voltage_index, -1.0),
+            (hot_temperature_index, -parameters.seebeck),
+            (cold_temperature_index, parameters.seebeck),
+            (current_index, -parameters.resistance),
+        ] {
+            view.coefficient_add(
+                specific_equation_index,
+                variable_index,
+                Complex::from(value),
+            );
+        }
+
+        // The heat flows linearized at the operating point.
+        let (absorbed, released) = self.heat_flows(
+            self.get_current(),
+            self.get_hot_temperature(),
+            self.get_cold_temperature(),
+        );
+        for (equation_index, (_, dqdi, dqdth, dqdtc), sign) in [
+            (cold_equation_index, absorbed, 1.0),
+            (hot_equation_index, released, -1.0),
+        ] {
+            view.coefficient_add(equation_index, current_index, Complex::from(sign * dqdi));
+            view.coefficient_add(
+                equation_index,
+                hot_temperature_index,
+                Complex::from(sign * dqdth),
+            );
+            view.coefficient_add(
+                equation_index,
+                cold_temperature_index,
+                Complex::from(sign * dqdtc),
+            );
+        }
+    }
+}
+
+impl Stampable for HallSensor {
+    fn num_variables(&self) -> usize {
+        2
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let output_equation_index = ViewEquationIndex::NodalEquation(self.get_output_node());
+        let reference_equation_index = ViewEquationIndex::NodalEquation(self.get_reference_node());
+        let primary_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let output_specific_equation_index = ViewEquationIndex::SpecificEquation(1);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let output_voltage_index = ViewVariableIndex::NodeVoltage(self.get_output_node());
+        let reference_voltage_index = ViewVariableIndex::NodeVoltage(self.get_reference_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+        let output_current_index = ViewVariableIndex::SpecificVariable(1);
+
+        // Current flowing out of positive node is i_primary
+        view.coefficient_add(positive_equation_index, primary_current_index, 1.0);
+        // Current flowing out of negative node is -i_primary
+        view.coefficient_add(negative_equation_index, primary_current_index, -1.0);
+
+        // Current flowing out of output node is -i_output
+        view.coefficient_add(output_equation_index, output_current_index, -1.0);
+        // Current flowing out of reference node is i_output
+        view.coefficient_add(reference_equation_index, output_current_index, 1.0);
+
+        // v_positive - v_negative - R_primary*i_primary = 0
+        view.coefficient_add(primary_equation_index, positive_voltage_index, 1.0);
+        view.coefficient_add(primary_equation_index, negative_voltage_index, -1.0);
+        view.coefficient_add(
+            primary_equation_index,
+            primary_current_index,
+            -self.get_primary_resistance(),
+        );
+
+        // v_output - v_reference - k*i_primary + R_output*i_output = offset
+        view.coefficient_add(output_specific_equation_index, output_voltage_index, 1.0);
+        view.coefficient_add(
+            output_specific_equation_index,
+            reference_voltage_index,
+            -1.0,
+        );
+        view.coefficient_add(
+            output_specific_equation_index,
+            primary_current_index,
+            -self.get_sensitivity(),
+        );
+        view.coefficient_add(
+            output_specific_equation_index,
+            output_current_index,
+            self.get_output_resistance(),
+        );
+        view.result_add(output_specific_equation_index, self.get_offset());
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let output_voltage_index = ViewVariableIndex::NodeVoltage(self.get_output_node());
+        let reference_voltage_index = ViewVariableIndex::NodeVoltage(self.get_reference_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_current(view.get_variable(primary_current_index).unwrap());
+        self.set_output_voltage(
+            view.get_variable(output_voltage_index).unwrap()
+                - view.get_variable(reference_voltage_index).unwrap(),
+        );
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let output_equation_index = ViewEquationIndex::NodalEquation(self.get_output_node());
+        let reference_equation_index = ViewEquationIndex::NodalEquation(self.get_reference_node());
+        let primary_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let output_specific_equation_index = ViewEquationIndex::SpecificEquation(1);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let output_voltage_index = ViewVariableIndex::NodeVoltage(self.get_output_node());
+        let reference_voltage_index = ViewVariableIndex::NodeVoltage(self.get_reference_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+        let output_current_index = ViewVariableIndex::SpecificVariable(1);
+
+        // The same equations as the transient stamp, without the offset.
+        for (equation_index, variable_index, value) in [
+            (positive_equation_index, primary_current_index, 1.0),
+            (negative_equation_index, primary_current_index, -1.0),
+            (output_equation_index, output_current_index, -1.0),
+            (reference_equation_index, output_current_index, 1.0),
+            (primary_equation_index, positive_voltage_index, 1.0),
+            (primary_equation_index, negative_voltage_index, -1.0),
+            (
+                primary_equation_index,
+                primary_current_index,
+                -self.get_primary_resistance(),
+            ),
+            (output_specific_equation_index, output_voltage_index, 1.0),
+            (
+                output_specific_equation_index,
+                reference_voltage_index,
+                -1.0,
+            ),
+            (
+                output_specific_equation_index,
+                primary_current_index,
+                -self.get_sensitivity(),
+            ),
+            (
+                output_specific_equation_index,
+                output_current_index,
+                self.get_output_resistance(),
+            ),
+        ] {
+            view.coefficient_add(equation_index, variable_index, Complex::from(value));
+        }
+    }
+}
+
+impl Stampable for CurrentTransformer {
+    fn num_variables(&self) -> usize {
+        3
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        let primary_positive_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let primary_negative_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let secondary_positive_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_secondary_positive_node());
+        let secondary_negative_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_secondary_negative_node());
+        let winding_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let ampere_turns_equation_index = ViewEquationIndex::SpecificEquation(1);
+        let magnetizing_equation_index = ViewEquationIndex::SpecificEquation(2);
+
+        let primary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let primary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let secondary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_positive_node());
+        let secondary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_negative_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+        let secondary_current_index = ViewVariableIndex::SpecificVariable(1);
+        let magnetizing_current_index = ViewVariableIndex::SpecificVariable(2);
+
+        let n = self.get_turns();
+        let l = self.get_magnetizing_inductance();
+
+        // Current flowing out of the primary positive node is i_primary
+        view.coefficient_add(primary_positive_equation_index, primary_current_index, 1.0);
+        // Current flowing out of the primary negative node is -i_primary
+        view.coefficient_add(primary_negative_equation_index, primary_current_index, -1.0);
+        // Current flowing out of the secondary positive node is -i_secondary
+        view.coefficient_add(
+            secondary_positive_equation_index,
+            secondary_current_index,
+            -1.0,
+        );
+        // Current flowing out of the secondary negative node is i_secondary
+        view.coefficient_add(
+            secondary_negative_equation_index,
+            secondary_current_index,
+            1.0,
+        );
+
+        // The winding voltage is N times the primary voltage:
+        // N*(v_primary_positive - v_primary_negative) - (v_secondary_positive -
+        // v_secondary_negative) - R*i_secondary = 0
+        view.coefficient_add(winding_equation_index, primary_positive_voltage_index, n);
+        view.coefficient_add(winding_equation_index, primary_negative_voltage_index, -n);
+        view.coefficient_add(
+            winding_equation_index,
+            secondary_positive_voltage_index,
+            -1.0,
+        );
+        view.coefficient_add(
+            winding_equation_index,
+            secondary_negative_voltage_index,
+            1.0,
+        );
+        view.coefficient_add(
+            winding_equation_index,
+            secondary_current_index,
+            -self.get_winding_resistance(),
+        );
+
+        // The ampere turns balance: i_primary - N*i_secondary - N*i_magnetizing = 0
+        view.coefficient_add(ampere_turns_equation_index, primary_current_index, 1.0);
+        view.coefficient_add(ampere_turns_equation_index, secondary_current_index, -n);
+        view.coefficient_add(ampere_turns_equation_index, magnetizing_current_index, -n);
+
+        // The magnetizing inductance sees the winding voltage, discretized like an inductor:
+        // N*(v_primary_positive - v_primary_negative) - L*i_magnetizing/dt = -L*i_old/dt
+        view.coefficient_add(
+            magnetizing_equation_index,
+            primary_positive_voltage_index,
+            n,
+        );
+        view.coefficient_add(
+            magnetizing_equation_index,
+            primary_negative_voltage_index,
+            -n,
+        );
+        view.coefficient_add(
+            magnetizing_equation_index,
+            magnetizing_current_index,
+            -l / dt,
+        );
+        view.result_add(
+            magnetizing_equation_index,
+            -l * self.get_magnetizing_current() / dt,
+        );
+
+        if let Some(burden) = self.get_burden() {
+            view.conductance_add(
+                self.get_secondary_positive_node(),
+                self.get_secondary_negative_node(),
+                1.0 / burden,
+            );
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let primary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let primary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let secondary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_positive_node());
+        let secondary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_negative_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+        let secondary_current_index = ViewVariableIndex::SpecificVariable(1);
+        let magnetizing_current_index = ViewVariableIndex::SpecificVariable(2);
+
+        self.set_voltage(
+            view.get_variable(primary_positive_voltage_index).unwrap()
+                - view.get_variable(primary_negative_voltage_index).unwrap(),
+        );
+        self.set_current(view.get_variable(primary_current_index).unwrap());
+        self.set_secondary_voltage(
+            view.get_variable(secondary_positive_voltage_index).unwrap()
+                - view.get_variable(secondary_negative_voltage_index).unwrap(),
+        );
+        self.set_secondary_current(view.get_variable(secondary_current_index).unwrap());
+        self.set_magnetizing_current(view.get_variable(magnetizing_current_index).unwrap());
+    }
+
+    fn state(&self) -> Option<f64> {
+        Some(self.get_magnetizing_current())
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        // Over an infinitely long timestep the magnetizing inductance is a short.
+        self.stamp(view, guess, f64::INFINITY);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        let primary_positive_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let primary_negative_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let secondary_positive_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_secondary_positive_node());
+        let secondary_negative_equation_index =
+            ViewEquationIndex::NodalEquation(self.get_secondary_negative_node());
+        let winding_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let ampere_turns_equation_index = ViewEquationIndex::SpecificEquation(1);
+        let magnetizing_equation_index = ViewEquationIndex::SpecificEquation(2);
+
+        let primary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let primary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let secondary_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_positive_node());
+        let secondary_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_secondary_negative_node());
+        let primary_current_index = ViewVariableIndex::SpecificVariable(0);
+        let secondary_current_index = ViewVariableIndex::SpecificVariable(1);
+        let magnetizing_current_index = ViewVariableIndex::SpecificVariable(2);
+
+        let n = Complex::from(self.get_turns());
+        let one = Complex::from(1.0);
+
+        // The same equations as the transient stamp, with the magnetizing inductance as the
+        // impedance j*omega*L.
+        for (equation_index, variable_index, value) in [
+            (primary_positive_equation_index, primary_current_index, one),
+            (primary_negative_equation_index, primary_current_index, -one),
+            (
+                secondary_positive_equation_index,
+                secondary_current_index,
+                -one,
+            ),
+            (
+                secondary_negative_equation_index,
+                secondary_current_index,
+                one,
+            ),
+            (winding_equation_index, primary_positive_voltage_index, n),
+            (winding_equation_index, primary_negative_voltage_index, -n),
+            (
+                winding_equation_index,
+                secondary_positive_voltage_index,
+                -one,
+            ),
+            (
+                winding_equation_index,
+                secondary_negative_voltage_index,
+                one,
+            ),
+            (
+                winding_equation_index,
+                secondary_current_index,
+                Complex::from(-self.get_winding_resistance()),
+            ),
+            (ampere_turns_equation_index, primary_current_index, one),
+            (ampere_turns_equation_index, secondary_current_index, -n),
+            (ampere_turns_equation_index, magnetizing_current_index, -n),
+            (
+                magnetizing_equation_index,
+                primary_positive_voltage_index,
+                n,
+            ),
+            (
+                magnetizing_equation_index,
+                primary_negative_voltage_index,
+                -n,
+            ),
+            (
+                magnetizing_equation_index,
+                magnetizing_current_index,
+                Complex::new(0.0, -omega * self.get_magnetizing_inductance()),
+            ),
+        ] {
+            view.coefficient_add(equation_index, variable_index, value);
+        }
+
+        if let Some(burden) = self.get_burden() {
+            view.conductance_add(
+                self.get_secondary_positive_node(),
+                self.get_secondary_negative_node(),
+                Complex::from(1.0 / burden),
+            );
+        }
+    }
+}
+
+impl ControlledSource {
+    /// Returns the variables the control voltage or current is a combination of, with their
+    /// coefficients.
+    fn control_terms(&self) -> Vec<(ViewVariableIndex, f64)> {
+        if self.get_kind().is_current_controlled() {
+            vec![(ViewVariableIndex::SpecificVariable(0), 1.0)]
+        } else {
+            vec![
+                (
+                    ViewVariableIndex::NodeVoltage(self.get_control_positive_node()),
+                    1.0,
+                ),
+                (
+                    ViewVariableIndex::NodeVoltage(self.get_control_negative_node()),
+                    -1.0,
+                ),
+            ]
+        }
+    }
+
+    /// Stamps the source with its output linearized as y = d*x + g*v + c, where v is the voltage
+    /// from the negative to the positive node (only current outputs depend on it), shared by the
+    /// transient and AC stamps.
+    fn stamp_linearized<T>(&self, view: &mut ABMatrixView<T>, d: T, g: T, c: T)
+    where
+        T: Scalar + AddAssign + Copy + Neg<Output = T> + Mul<Output = T> + From<f64>,
+    {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let one = T::from(1.0);
+
+        let kind = self.get_kind();
+        if kind.is_current_controlled() {
+            let control_positive_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_control_positive_node());
+            let control_negative_equation_index =
+                ViewEquationIndex::NodalEquation(self.get_control_negative_node());
+            let sense_equation_index = ViewEquationIndex::SpecificEquation(0);
+            let sense_current_index = ViewVariableIndex::SpecificVariable(0);
+
+            // Current flowing out of the control positive node is i_sense
+            view.coefficient_add(control_positive_equation_index, sense_current_index, one);
+            // Current flowing out of the control negative node is -i_sense
+            view.coefficient_add(control_negative_equation_index, sense_current_index, -one);
+
+            // The sense branch is a zero volt source: v_control_positive - v_control_negative = 0
+            view.coefficient_add(
+                sense_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_control_positive_node()),
+                one,
+            );
+            view.coefficient_add(
+                sense_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_control_negative_node()),
+                -one,
+            );
+        }
+
+        if kind.has_voltage_output() {
+            let k = kind.is_current_controlled() as usize;
+            let output_equation_index = ViewEquationIndex::SpecificEquation(k);
+            let output_current_index = ViewVariableIndex::SpecificVariable(k);
+
+            // Current flowing out of positive node is -i_output
+            view.coefficient_add(positive_equation_index, output_current_index, -one);
+            // Current flowing out of negative node is i_output
+            view.coefficient_add(negative_equation_index, output_current_index, one);
+
+            // Output equation is v_positive - v_negative - d*x = c
+            view.coefficient_add(
+                output_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+                one,
+            );
+            view.coefficient_add(
+                output_equation_index,
+                ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+                -one,
+            );
+            for (variable_index, coefficient) in self.control_terms() {
+                view.coefficient_add(
+                    output_equation_index,
+                    variable_index,
+                    -d * T::from(coefficient),
+                );
+            }
+            view.result_add(output_equation_index, c);
+        } else {
+            // Current flowing out of positive node is -(d*x + g*v + c), out of negative node
+            // d*x + g*v + c
+            for (variable_index, coefficient) in self.control_terms() {
+                let gx = d * T::from(coefficient);
+                view.coefficient_add(positive_equation_index, variable_index, -gx);
+                view.coefficient_add(negative_equation_index, variable_index, gx);
+            }
+            view.conductance_add(self.get_negative_node(), self.get_positive_node(), g);
+            view.result_add(positive_equation_index, c);
+            view.result_add(negative_equation_index, -c);
+        }
+    }
+}
+
+impl Stampable for ControlledSource {
+    fn num_variables(&self) -> usize {
+        let kind = self.get_kind();
+        kind.is_current_controlled() as usize + kind.has_voltage_output() as usize
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, _dt: f64) {
+        let x_guess: f64 = self
+            .control_terms()
+            .iter()
+            .map(|&(variable_index, coefficient)| {
+                coefficient * guess.get_variable(variable_index).unwrap()
+            })
+            .sum();
+        let v_guess = guess
+            .get_variable(ViewVariableIndex::NodeVoltage(self.get_negative_node()))
+            .unwrap()
+            - guess
+                .get_variable(ViewVariableIndex::NodeVoltage(self.get_positive_node()))
+                .unwrap();
+
+        // The output y(x)*h(v) is linearized around the guess:
+        // y*h + y'*h*(x - x_guess) + y*h'*(v - v_guess)
+        let (y, dy) = self.evaluate(x_guess);
+        let (h, dh) = self.compliance(y, v_guess);
+        let (d, g) = (dy * h, y * dh);
+
+        self.stamp_linearized(view, d, g, y * h - d * x_guess - g * v_guess);
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+
+        let control: f64 = self
+            .control_terms()
+            .iter()
+            .map(|&(variable_index, coefficient)| {
+                coefficient * view.get_variable(variable_index).unwrap()
+            })
+            .sum();
+        self.set_control(control);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+
+        let kind = self.get_kind();
+        if kind.has_voltage_output() {
+            let output_current_index =
+                ViewVariableIndex::SpecificVariable(kind.is_current_controlled() as usize);
+            self.set_current(view.get_variable(output_current_index).unwrap());
+        } else {
+            let output = self.evaluate(control).0;
+            self.set_current(output * self.compliance(output, -self.get_voltage()).0);
+        }
+
+        self.set_triggered_from(control);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The output linearized at the operating point, with no excitation.
+        let (y, dy) = self.evaluate(self.get_control());
+        let (h, dh) = self.compliance(y, -self.get_voltage());
+        self.stamp_linearized(
+            view,
+            Complex::from(dy * h),
+            Complex::from(y * dh),
+            Complex::from(0.0),
+        );
+    }
+}
+
+impl Stampable for ShuntReference {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn variable_kind(&self, _index: usize) -> VariableKind {
+        // The variable is the voltage e of the error amplifier.
+        VariableKind::Voltage
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        let cathode_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let anode_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let error_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let anode_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let reference_voltage_index = ViewVariableIndex::NodeVoltage(self.get_reference_node());
+        let error_index = ViewVariableIndex::SpecificVariable(0);
+
+        // The cathode current i(e) is linearized around the guess: i + i'*(e - e_guess)
+        let e_guess = guess.get_variable(error_index).unwrap();
+        let (i, di) = self.cathode_current(e_guess);
+
+        // Current flowing out of cathode is i(e), out of anode -i(e)
+        view.coefficient_add(cathode_equation_index, error_index, di);
+        view.coefficient_add(anode_equation_index, error_index, -di);
+        view.result_add(cathode_equation_index, di * e_guess - i);
+        view.result_add(anode_equation_index, i - di * e_guess);
+
+        // The error follows v_reference - v_anode - V_ref through the pole:
+        // (1 + tau/dt)*e - v_reference + v_anode = -V_ref + tau/dt*e_previous
+        let k = self.get_time_constant() / dt;
+        view.coefficient_add(error_equation_index, error_index, 1.0 + k);
+        view.coefficient_add(error_equation_index, reference_voltage_index, -1.0);
+        view.coefficient_add(error_equation_index, anode_voltage_index, 1.0);
+        view.result_add(
+            error_equation_index,
+            k * self.get_error() - self.get_reference_voltage(),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let cathode_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let anode_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let error_index = ViewVariableIndex::SpecificVariable(0);
+
+        let error = view.get_variable(error_index).unwrap();
+        self.set_error(error);
+        self.set_current(self.cathode_current(error).0);
+        self.set_voltage(
+            view.get_variable(cathode_voltage_index).unwrap()
+                - view.get_variable(anode_voltage_index).unwrap(),
+        );
+    }
+
+    fn state(&self) -> Option<f64> {
+        Some(self.get_error())
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        // Over an infinitely long timestep the error settles through the pole.
+        self.stamp(view, guess, f64::INFINITY);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        let cathode_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let anode_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let error_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let anode_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let reference_voltage_index = ViewVariableIndex::NodeVoltage(self.get_reference_node());
+        let error_index = ViewVariableIndex::SpecificVariable(0);
+
+        let (_, di) = self.cathode_current(self.get_error());
+        view.coefficient_add(cathode_equation_index, error_index, Complex::from(di));
+        view.coefficient_add(anode_equation_index, error_index, Complex::from(-di));
+
+        // (1 + j*omega*tau)*e = v_reference - v_anode
+        view.coefficient_add(
+            error_equation_index,
+            error_index,
+            Complex::new(1.0, omega * self.get_time_constant()),
+        );
+        view.coefficient_add(
+            error_equation_index,
+            reference_voltage_index,
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            error_equation_index,
+            anode_voltage_index,
+            Complex::from(1.0),
+        );
+    }
+}
+
+impl Relay {
+    /// Stamps the coil with the companion resistance k of its inductance, and the contact as it
+    /// stands at the given time.
+    fn stamp_coil_and_contact(&self, view: &mut ABMatrixView, k: f64, contact_time: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let coil_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Current flowing out of positive coil node is i_new, out of negative coil node -i_new
+        view.coefficient_add(positive_equation_index, current_index, 1.0);
+        view.coefficient_add(negative_equation_index, current_index, -1.0);
+
+        // The coil is v = R*i + L*di/dt, discretized to
+        // v_positive - v_negative - (R + k)*i_new = -k*i_old with k = L/dt
+        view.coefficient_add(coil_equation_index, positive_voltage_index, 1.0);
+        view.coefficient_add(coil_equation_index, negative_voltage_index, -1.0);
+        view.coefficient_add(
+            coil_equation_index,
+            current_index,
+            -(self.get_coil_resistance() + k),
+        );
+        view.result_add(coil_equation_index, -k * self.get_current());
+
+        view.conductance_add(
+            self.get_contact_positive_node(),
+            self.get_contact_negative_node(),
+            self.get_contact_conductance_at(contact_time),
+        );
+    }
+}
+
+impl Stampable for Relay {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        // Like a switch, the contact is evaluated at the end of the timestep.
+        self.stamp_coil_and_contact(view, self.get_coil_inductance() / dt, self.get_time() + dt);
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, _guess: &XMatrixView) {
+        // The coil inductance is a short at DC.
+        self.stamp_coil_and_contact(view, 0.0, self.get_time());
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let contact_positive_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_contact_positive_node());
+        let contact_negative_voltage_index =
+            ViewVariableIndex::NodeVoltage(self.get_contact_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        self.advance(dt, view.get_variable(current_index).unwrap());
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_contact_voltage(
+            view.get_variable(contact_positive_voltage_index).unwrap()
+                - view.get_variable(contact_negative_voltage_index).unwrap(),
+        );
+    }
+
+    fn state(&self) -> Option<f64> {
+        Some(self.get_current())
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let coil_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(-1.0));
+
+        // Branch equation is v_positive - v_negative - (R + j*omega*L)*i = 0
+        view.coefficient_add(
+            coil_equation_index,
+            positive_voltage_index,
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            coil_equation_index,
+            negative_voltage_index,
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            coil_equation_index,
+            current_index,
+            -Complex::new(
+                self.get_coil_resistance(),
+                omega * self.get_coil_inductance(),
+            ),
+        );
+
+        view.conductance_add(
+            self.get_contact_positive_node(),
+            self.get_contact_negative_node(),
+            Complex::from(self.get_contact_conductance_at(self.get_time())),
+        );
+    }
+}
+
+impl Stampable for BenchSupply {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, _dt: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Current flowing out of positive node is -i_supply
+        view.coefficient_add(positive_equation_index, current_index, -1.0);
+        // Current flowing out of negative node is i_supply
+        view.coefficient_add(negative_equation_index, current_index, 1.0);
+
+        // The supply equation f(v, i) = v + r_out * i + drop(v, i) - v_set = 0 is linearized
+        // around the guess like the PV module's:
+        // df/dv*v + df/di*i = df/dv*v_guess + df/di*i_guess - f(v_guess, i_guess)
+        let v_guess = guess.get_variable(positive_voltage_index).unwrap()
+            - guess.get_variable(negative_voltage_index).unwrap();
+        let i_guess = guess.get_variable(current_index).unwrap();
+        let (drop, d_drop_dv, d_drop_di) = self.limit_drop(v_guess, i_guess);
+        let f =
+            v_guess + self.get_output_resistance() * i_guess + drop - self.get_voltage_setpoint();
+        let df_dv = 1.0 + d_drop_dv;
+        let df_di = self.get_output_resistance() + d_drop_di;
+
+        view.coefficient_add(specific_equation_index, positive_voltage_index, df_dv);
+        view.coefficient_add(specific_equation_index, negative_voltage_index, -df_dv);
+        view.coefficient_add(specific_equation_index, current_index, df_di);
+        view.result_add(
+            specific_equation_index,
+            df_dv * v_guess + df_di * i_guess - f,
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        self.set_voltage(
+            view.get_variable(positive_voltage_index).unwrap()
+                - view.get_variable(negative_voltage_index).unwrap(),
+        );
+        self.set_current(view.get_variable(current_index).unwrap());
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // The supply equation linearized at the operating point, with no excitation: a short in
+        // constant voltage mode, nearly an open circuit in constant current mode.
+        let (_, d_drop_dv, d_drop_di) = self.limit_drop(self.get_voltage(), self.get_current());
+        let df_dv = Complex::from(1.0 + d_drop_dv);
+        let df_di = Complex::from(self.get_output_resistance() + d_drop_di);
+
+        view.coefficient_add(positive_equation_index, current_index, Complex::from(-1.0));
+        view.coefficient_add(negative_equation_index, current_index, Complex::from(1.0));
+        view.coefficient_add(specific_equation_index, positive_voltage_index, df_dv);
+        view.coefficient_add(specific_equation_index, negative_voltage_index, -df_dv);
+        view.coefficient_add(specific_equation_index, current_index, df_di);
+    }
+}
+
+impl Stampable for PwmController {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        let gate_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let ground_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        // The gate is driven through the gate resistance by a source switched between the gate
+        // voltage and zero (a Norton equivalent), as the controller left it after the previous
+        // timestep.
+        let (gate_voltage, gate_resistance) = self.get_gate_drive();
+        let g = 1.0 / gate_resistance;
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), g);
+        if self.is_on() {
+            view.result_add(gate_equation_index, g * gate_voltage);
+            view.result_add(ground_equation_index, -g * gate_voltage);
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let sense_voltage = match self.get_mode() {
+            PwmMode::PeakCurrentMode { sense_node, .. } => {
+                node_voltage(sense_node) - ground_voltage
+            }
+            PwmMode::VoltageMode { .. } => 0.0,
+        };
+        let gate_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        // The gate current flowed with the drive of the timestep, before it is advanced.
+        let (drive_voltage, gate_resistance) = self.get_gate_drive();
+        let drive = if self.is_on() { drive_voltage } else { 0.0 };
+        self.set_voltage(gate_voltage);
+        self.set_current((drive - gate_voltage) / gate_resistance);
+
+        self.advance(
+            dt,
+            node_voltage(self.get_feedback_node()) - ground_voltage,
+            sense_voltage,
+        );
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The modulator doesn't respond to small signals, leaving the gate resistance.
+        let (_, gate_resistance) = self.get_gate_drive();
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(1.0 / gate_resistance),
+        );
+    }
+}
+
+/// Stamps the Norton equivalent of a logic output driven high to the output voltage or low to
+/// the ground node through the output resistance.
+fn stamp_logic_output(
+    view: &mut ABMatrixView,
+    output_node: usize,
+    ground_node: usize,
+    (output_voltage, output_resistance): (f64, f64),
+    high: bool,
+) {
+    let g = 1.0 / output_resistance;
+    view.conductance_add(output_node, ground_node, g);
+    if high {
+        view.result_add(
+            ViewEquationIndex::NodalEquation(output_node),
+            g * output_voltage,
+        );
+        view.result_add(
+            ViewEquationIndex::NodalEquation(ground_node),
+            -g * output_voltage,
+        );
+    }
+}
+
+impl Stampable for Vco {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        // The output is driven as the oscillator left it after the previous timestep.
+        stamp_logic_output(
+            view,
+            self.get_positive_node(),
+            self.get_negative_node(),
+            self.get_output_drive(),
+            self.is_high(),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let output_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        // The output current flowed with the drive of the timestep, before it is advanced.
+        let (drive_voltage, output_resistance) = self.get_output_drive();
+        let drive = if self.is_high() { drive_voltage } else { 0.0 };
+        self.set_voltage(output_voltage);
+        self.set_current((drive - output_voltage) / output_resistance);
+
+        self.advance(dt, node_voltage(self.get_control_node()) - ground_voltage);
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The oscillator doesn't respond to small signals, leaving the output resistance.
+        let (_, output_resistance) = self.get_output_drive();
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(1.0 / output_resistance),
+        );
+    }
+}
+
+impl Stampable for FrequencyDivider {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        stamp_logic_output(
+            view,
+            self.get_positive_node(),
+            self.get_negative_node(),
+            self.get_output_drive(),
+            self.is_high(),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let output_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        let (drive_voltage, output_resistance) = self.get_output_drive();
+        let drive = if self.is_high() { drive_voltage } else { 0.0 };
+        self.set_voltage(output_voltage);
+        self.set_current((drive - output_voltage) / output_resistance);
+
+        self.advance(node_voltage(self.get_input_node()) - ground_voltage);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let (_, output_resistance) = self.get_output_drive();
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(1.0 / output_resistance),
+        );
+    }
+}
+
+impl Stampable for PhaseFrequencyDetector {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        // The charge pump is an ideal current source out of the output node, switched as the
+        // detector was left after the previous timestep.
+        let current = self.get_pump_output();
+        view.result_add(
+            ViewEquationIndex::NodalEquation(self.get_positive_node()),
+            current,
+        );
+        view.result_add(
+            ViewEquationIndex::NodalEquation(self.get_negative_node()),
+            -current,
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, _dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+
+        self.set_voltage(node_voltage(self.get_positive_node()) - ground_voltage);
+        self.set_current(self.get_pump_output());
+
+        self.advance(
+            node_voltage(self.get_reference_node()) - ground_voltage,
+            node_voltage(self.get_feedback_node()) - ground_voltage,
+        );
+    }
+
+    fn stamp_ac(&self, _view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The charge pump is an ideal current source, open to small signals.
+    }
+}
+
+impl Stampable for NonOverlappingClock {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        stamp_logic_output(
+            view,
+            self.get_positive_node(),
+            self.get_negative_node(),
+            self.get_output_drive(),
+            self.is_phase1_high(),
+        );
+        stamp_logic_output(
+            view,
+            self.get_phase2_node(),
+            self.get_negative_node(),
+            self.get_output_drive(),
+            self.is_phase2_high(),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let phase1_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        // The output current flowed with the phases of the timestep, before time moves on.
+        let (drive_voltage, output_resistance) = self.get_output_drive();
+        let drive = if self.is_phase1_high() {
+            drive_voltage
+        } else {
+            0.0
+        };
+        self.set_voltage(phase1_voltage);
+        self.set_current((drive - phase1_voltage) / output_resistance);
+        self.set_phase2_voltage(node_voltage(self.get_phase2_node()) - ground_voltage);
+
+        self.set_time(self.get_time() + dt);
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let g = Complex::from(1.0 / self.get_output_drive().1);
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), g);
+        view.conductance_add(self.get_phase2_node(), self.get_negative_node(), g);
+    }
+}
+
+impl Stampable for TransmissionGate {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            guess
+                .get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let positive_voltage = node_voltage(self.get_positive_node());
+        let negative_voltage = node_voltage(self.get_negative_node());
+        let ground_voltage = node_voltage(self.get_ground_node());
+        let control_voltage = node_voltage(self.get_control_node()) - ground_voltage;
+        let signal_voltage = (positive_voltage + negative_voltage) / 2.0 - ground_voltage;
+        let v = positive_voltage - negative_voltage;
+
+        let positive_equation_index = ViewEquationIndex::NodalEquation(self.get_positive_node());
+        let negative_equation_index = ViewEquationIndex::NodalEquation(self.get_negative_node());
+
+        let positive_voltage_index = ViewVariableIndex::NodeVoltage(self.get_positive_node());
+        let negative_voltage_index = ViewVariableIndex::NodeVoltage(self.get_negative_node());
+        let ground_voltage_index = ViewVariableIndex::NodeVoltage(self.get_ground_node());
+
+        // The channel current is i = g(v_signal)*v, with v_signal = (v_positive + v_negative)/2
+        // - v_ground. Linearizing around the guess, di/dv_positive = g + g'*v/2, di/dv_negative
+        // = -g + g'*v/2 and di/dv_ground = -g'*v, leaving i - g*v = 0 as the equivalent current
+        // after substituting the guess.
+        let (g, dg) = self.channel_conductance(control_voltage, signal_voltage);
+        let g = g + 1.0 / self.get_off_resistance();
+        let partials = [
+            (positive_voltage_index, g + dg * v / 2.0),
+            (negative_voltage_index, -g + dg * v / 2.0),
+            (ground_voltage_index, -dg * v),
+        ];
+        let i_eq =
+            -(dg * v / 2.0 * (positive_voltage + negative_voltage) - dg * v * ground_voltage);
+        for (index, partial) in partials {
+            view.coefficient_add(positive_equation_index, index, partial);
+            view.coefficient_add(negative_equation_index, index, -partial);
+        }
+        view.result_add(positive_equation_index, -i_eq);
+        view.result_add(negative_equation_index, i_eq);
+
+        // The feedthrough capacitance is discretized with backward Euler, i = C*(v - v_old)/dt.
+        let c = self.get_off_capacitance() / dt;
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), c);
+        view.result_add(positive_equation_index, c * self.get_voltage());
+        view.result_add(negative_equation_index, -c * self.get_voltage());
+
+        // The channel charge is released into the terminals over the timestep the gate turns
+        // off in.
+        if self.is_on() && !self.is_on_for_control(control_voltage) {
+            let injected = self.get_charge_injection() / 2.0 / dt;
+            view.result_add(positive_equation_index, injected);
+            view.result_add(negative_equation_index, injected);
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let positive_voltage = node_voltage(self.get_positive_node());
+        let negative_voltage = node_voltage(self.get_negative_node());
+        let ground_voltage = node_voltage(self.get_ground_node());
+        let control_voltage = node_voltage(self.get_control_node()) - ground_voltage;
+        let signal_voltage = (positive_voltage + negative_voltage) / 2.0 - ground_voltage;
+        let v = positive_voltage - negative_voltage;
+
+        let (g, _) = self.channel_conductance(control_voltage, signal_voltage);
+        let g = g + 1.0 / self.get_off_resistance();
+        let feedthrough = self.get_off_capacitance() * (v - self.get_voltage()) / dt;
+        self.set_current(g * v + feedthrough);
+        self.set_voltage(v);
+        self.set_signal_voltage(signal_voltage);
+        self.set_control_voltage(control_voltage);
+    }
+
+    fn state(&self) -> Option<f64> {
+        let (g, _) =
+            self.channel_conductance(self.get_control_voltage(), self.get_signal_voltage());
+        Some(g + 1.0 / self.get_off_resistance())
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        // Over an infinitely long timestep the feedthrough capacitance is open and no charge is
+        // injected.
+        self.stamp(view, guess, f64::INFINITY);
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        self.update(view, f64::INFINITY);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        // The channel is linearized at the present signal level, the small signal varying the
+        // voltage across it rather than the level.
+        let (g, _) =
+            self.channel_conductance(self.get_control_voltage(), self.get_signal_voltage());
+        let y = Complex::new(
+            g + 1.0 / self.get_off_resistance(),
+            omega * self.get_off_capacitance(),
+        );
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), y);
+    }
+}
+
+impl Stampable for SampleAndHold {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        // The output is driven with the sample held as of the previous timestep.
+        stamp_logic_output(
+            view,
+            self.get_positive_node(),
+            self.get_negative_node(),
+            (self.get_held_voltage(), self.get_output_resistance()),
+            true,
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let output_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        // The output current flowed with the sample of the timestep, before it is retaken.
+        self.set_voltage(output_voltage);
+        self.set_current((self.get_held_voltage() - output_voltage) / self.get_output_resistance());
+
+        self.advance(
+            dt,
+            node_voltage(self.get_clock_node()) - ground_voltage,
+            node_voltage(self.get_input_node()) - ground_voltage,
+        );
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // Held, the output doesn't follow the input.
+        view.conductance_add(
+            self.get_positive_node(),
+            self.get_negative_node(),
+            Complex::from(1.0 / self.get_output_resistance()),
+        );
+    }
+}
+
+impl Stampable for ClockedComparator {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, _dt: f64) {
+        // The output is driven with the decision as of the previous timestep.
+        let (_, output_resistance) = self.get_output_drive();
+        stamp_logic_output(
+            view,
+            self.get_positive_node(),
+            self.get_negative_node(),
+            (self.get_drive_voltage(), output_resistance),
+            true,
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let node_voltage = |node| {
+            view.get_variable(ViewVariableIndex::NodeVoltage(node))
+                .unwrap()
+        };
+        let ground_voltage = node_voltage(self.get_negative_node());
+        let output_voltage = node_voltage(self.get_positive_node()) - ground_voltage;
+
+        // The output current flowed with the decision of the timestep, before it is retaken.
+        let (_, output_resistance) = self.get_output_drive();
+        self.set_voltage(output_voltage);
+        self.set_current((self.get_drive_voltage() - output_voltage) / output_resistance);
+
+        self.advance(
+            dt,
+            node_voltage(self.get_clock_node()) - ground_voltage,
+            node_voltage(self.get_positive_input_node())
+                - node_voltage(self.get_negative_input_node()),
+        );
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        let g = Complex::from(1.0 / self.get_output_drive().1);
+        view.conductance_add(self.get_positive_node(), self.get_negative_node(), g);
+    }
+}
+
+impl Stampable for NoiseSource {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Stamped like an ideal voltage source, the noise evaluated at the end of the timestep.
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_positive_node()),
+            current_index,
+            -1.0,
+        );
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_negative_node()),
+            current_index,
+            1.0,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+            1.0,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+            -1.0,
+        );
+        view.result_add(
+            specific_equation_index,
+            self.get_voltage_at(self.get_time() + dt),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+        self.set_current(
+            view.get_variable(ViewVariableIndex::SpecificVariable(0))
+                .unwrap(),
+        );
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // A short, the noise having no small signal excitation.
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_positive_node()),
+            current_index,
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_negative_node()),
+            current_index,
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+            Complex::from(-1.0),
+        );
+    }
+}
+
+impl Stampable for PulseGenerator {
+    fn num_variables(&self) -> usize {
+        1
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+
+        // Stamped like a voltage source behind its series resistance.
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_positive_node()),
+            current_index,
+            -1.0,
+        );
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_negative_node()),
+            current_index,
+            1.0,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+            1.0,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+            -1.0,
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            current_index,
+            self.get_internal_resistance(),
+        );
+        view.result_add(
+            specific_equation_index,
+            self.get_voltage_at(self.get_time() + dt),
+        );
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        self.set_time(self.get_time() + dt);
+        self.set_current(
+            view.get_variable(ViewVariableIndex::SpecificVariable(0))
+                .unwrap(),
+        );
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        self.get_breakpoint()
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, _omega: f64) {
+        // The battery behind the internal resistance, without excitation.
+        let specific_equation_index = ViewEquationIndex::SpecificEquation(0);
+        let current_index = ViewVariableIndex::SpecificVariable(0);
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_positive_node()),
+            current_index,
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            ViewEquationIndex::NodalEquation(self.get_negative_node()),
+            current_index,
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_positive_node()),
+            Complex::from(1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            ViewVariableIndex::NodeVoltage(self.get_negative_node()),
+            Complex::from(-1.0),
+        );
+        view.coefficient_add(
+            specific_equation_index,
+            current_index,
+            Complex::from(self.get_internal_resistance()),
+        );
+    }
+}
+
+impl FittedImpedance {
+    /// Gets the conductance and the current at zero voltage of the backward Euler companion
+    /// model of the fit over a timestep, and the factor 1/(1 - dt*p) each state is scaled by.
+    fn companion(&self, dt: f64) -> (f64, f64, [Complex<f64>; MAX_FITTED_ORDER]) {
+        let model = self.get_model();
+        let mut g = model.get_constant() + model.get_proportional() / dt;
+        let mut i_eq = -model.get_proportional() * self.get_previous_voltage() / dt;
+        let mut factors = [Complex::from(0.0); MAX_FITTED_ORDER];
+
+        // Integrating dx/dt = p*x + v over the timestep gives x = (x_old + dt*v)/(1 - dt*p),
+        // and the current of a real pole r*x, of a complex pair 2*Re(r*x).
+        for (k, (p, r)) in model.get_terms().enumerate() {
+            let weight = if p.im > 0.0 { 2.0 } else { 1.0 };
+            factors[k] = 1.0 / (1.0 - dt * p);
+            g += weight * (r * factors[k] * dt).re;
+            i_eq += weight * (r * factors[k] * self.get_states()[k]).re;
+        }
+        (g, i_eq, factors)
+    }
+
+    /// Gets the DC conductance of the fit, with the capacitance open and each state settled at
+    /// x = -v/p.
+    fn dc_conductance(&self) -> f64 {
+        let model = self.get_model();
+        model.get_constant()
+            + model
+                .get_terms()
+                .map(|(p, r)| {
+                    let weight = if p.im > 0.0 { 2.0 } else { 1.0 };
+                    weight * (-r / p).re
+                })
+                .sum::<f64>()
+    }
+
+    fn terminals(&self) -> [(ViewEquationIndex, ViewVariableIndex); 2] {
+        [self.get_positive_node(), self.get_negative_node()].map(|n| {
+            (
+                ViewEquationIndex::NodalEquation(n),
+                ViewVariableIndex::NodeVoltage(n),
+            )
+        })
+    }
+}
+
+impl Stampable for FittedImpedance {
+    fn num_variables(&self) -> usize {
+        0
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, _guess: &XMatrixView, dt: f64) {
+        let [positive, negative] = self.terminals();
+        let (g, i_eq, _) = self.companion(dt);
+
+        // Current flowing out of the positive node is g*(v_positive - v_negative) + i_eq.
+        view.terminal_conductance_add(positive, negative, g);
+        view.result_add(positive.0, -i_eq);
+        view.result_add(negative.0, i_eq);
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        let [positive, negative] = self.terminals();
+        let v = view.get_variable(positive.1).unwrap() - view.get_variable(negative.1).unwrap();
+        let (g, i_eq, factors) = self.companion(dt);
+
+        let mut states = self.get_states();
+        for (state, factor) in states.iter_mut().zip(factors) {
+            *state = (*state + dt * v) * factor;
+        }
+        self.set_states(states);
+        self.set_previous_voltage(v);
+        self.set_voltage(v);
+        self.set_current(g * v + i_eq);
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, _guess: &XMatrixView) {
+        let [positive, negative] = self.terminals();
+        view.terminal_conductance_add(positive, negative, self.dc_conductance());
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        let [positive, negative] = self.terminals();
+        let v = view.get_variable(positive.1).unwrap() - view.get_variable(negative.1).unwrap();
+
+        let mut states = [Complex::from(0.0); MAX_FITTED_ORDER];
+        for (state, (p, _)) in states.iter_mut().zip(self.get_model().get_terms()) {
+            *state = -v / p;
+        }
+        self.set_states(states);
+        self.set_previous_voltage(v);
+        self.set_voltage(v);
+        self.set_current(self.dc_conductance() * v);
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        let [positive, negative] = self.terminals();
+        view.terminal_conductance_add(positive, negative, self.get_model().admittance(omega));
+    }
+}
+
+impl Stampable for Component {
+    fn num_variables(&self) -> usize {
+        match self {
+            Self::Resistor(c) => c.num_variables(),
+            Self::Capacitor(c) => c.num_variables(),
+            Self::Inductor(c) => c.num_variables(),
+            Self::VoltageSource(c) => c.num_variables(),
+            Self::CurrentSource(c) => c.num_variables(),
+            Self::Diode(c) => c.num_variables(),
+            Self::Switch(c) => c.num_variables(),
+            Self::CurrentProbe(c) => c.num_variables(),
+            Self::ElectronicLoad(c) => c.num_variables(),
+            Self::PvModule(c) => c.num_variables(),
+            Self::RandlesCell(c) => c.num_variables(),
+            Self::ThermoelectricModule(c) => c.num_variables(),
+            Self::HallSensor(c) => c.num_variables(),
+            Self::CurrentTransformer(c) => c.num_variables(),
+            Self::ControlledSource(c) => c.num_variables(),
+            Self::ShuntReference(c) => c.num_variables(),
+            Self::Relay(c) => c.num_variables(),
+            Self::BenchSupply(c) => c.num_variables(),
+            Self::PwmController(c) => c.num_variables(),
+            Self::Vco(c) => c.num_variables(),
+            Self::FrequencyDivider(c) => c.num_variables(),
+            Self::PhaseFrequencyDetector(c) => c.num_variables(),
+            Self::NonOverlappingClock(c) => c.num_variables(),
+            Self::TransmissionGate(c) => c.num_variables(),
+            Self::SampleAndHold(c) => c.num_variables(),
+            Self::ClockedComparator(c) => c.num_variables(),
+            Self::NoiseSource(c) => c.num_variables(),
+            Self::PulseGenerator(c) => c.num_variables(),
+            Self::FittedImpedance(c) => c.num_variables(),
+            Self::Bjt(c) => c.num_variables(),
+        }
+    }
+
+    fn variable_kind(&self, index: usize) -> VariableKind {
+        match self {
+            Self::Resistor(c) => c.variable_kind(index),
+            Self::Capacitor(c) => c.variable_kind(index),
+            Self::Inductor(c) => c.variable_kind(index),
+            Self::VoltageSource(c) => c.variable_kind(index),
+            Self::CurrentSource(c) => c.variable_kind(index),
+            Self::Diode(c) => c.variable_kind(index),
+            Self::Switch(c) => c.variable_kind(index),
+            Self::CurrentProbe(c) => c.variable_kind(index),
+            Self::ElectronicLoad(c) => c.variable_kind(index),
+            Self::PvModule(c) => c.variable_kind(index),
+            Self::RandlesCell(c) => c.variable_kind(index),
+            Self::ThermoelectricModule(c) => c.variable_kind(index),
+            Self::HallSensor(c) => c.variable_kind(index),
+            Self::CurrentTransformer(c) => c.variable_kind(index),
+            Self::ControlledSource(c) => c.variable_kind(index),
+            Self::ShuntReference(c) => c.variable_kind(index),
+            Self::Relay(c) => c.variable_kind(index),
+            Self::BenchSupply(c) => c.variable_kind(index),
+            Self::PwmController(c) => c.variable_kind(index),
+            Self::Vco(c) => c.variable_kind(index),
+            Self::FrequencyDivider(c) => c.variable_kind(index),
+            Self::PhaseFrequencyDetector(c) => c.variable_kind(index),
+            Self::NonOverlappingClock(c) => c.variable_kind(index),
+            Self::TransmissionGate(c) => c.variable_kind(index),
+            Self::SampleAndHold(c) => c.variable_kind(index),
+            Self::ClockedComparator(c) => c.variable_kind(index),
+            Self::NoiseSource(c) => c.variable_kind(index),
+            Self::PulseGenerator(c) => c.variable_kind(index),
+            Self::FittedImpedance(c) => c.variable_kind(index),
+            Self::Bjt(c) => c.variable_kind(index),
+        }
+    }
+
+    fn stamp(&self, view: &mut ABMatrixView, guess: &XMatrixView, dt: f64) {
+        match self {
+            Self::Resistor(c) => c.stamp(view, guess, dt),
+            Self::Capacitor(c) => c.stamp(view, guess, dt),
+            Self::Inductor(c) => c.stamp(view, guess, dt),
+            Self::VoltageSource(c) => c.stamp(view, guess, dt),
+            Self::CurrentSource(c) => c.stamp(view, guess, dt),
+            Self::Diode(c) => c.stamp(view, guess, dt),
+            Self::Switch(c) => c.stamp(view, guess, dt),
+            Self::CurrentProbe(c) => c.stamp(view, guess, dt),
+            Self::ElectronicLoad(c) => c.stamp(view, guess, dt),
+            Self::PvModule(c) => c.stamp(view, guess, dt),
+            Self::RandlesCell(c) => c.stamp(view, guess, dt),
+            Self::ThermoelectricModule(c) => c.stamp(view, guess, dt),
+            Self::HallSensor(c) => c.stamp(view, guess, dt),
+            Self::CurrentTransformer(c) => c.stamp(view, guess, dt),
+            Self::ControlledSource(c) => c.stamp(view, guess, dt),
+            Self::ShuntReference(c) => c.stamp(view, guess, dt),
+            Self::Relay(c) => c.stamp(view, guess, dt),
+            Self::BenchSupply(c) => c.stamp(view, guess, dt),
+            Self::PwmController(c) => c.stamp(view, guess, dt),
+            Self::Vco(c) => c.stamp(view, guess, dt),
+            Self::FrequencyDivider(c) => c.stamp(view, guess, dt),
+            Self::PhaseFrequencyDetector(c) => c.stamp(view, guess, dt),
+            Self::NonOverlappingClock(c) => c.stamp(view, guess, dt),
+            Self::TransmissionGate(c) => c.stamp(view, guess, dt),
+            Self::SampleAndHold(c) => c.stamp(view, guess, dt),
+            Self::ClockedComparator(c) => c.stamp(view, guess, dt),
+            Self::NoiseSource(c) => c.stamp(view, guess, dt),
+            Self::PulseGenerator(c) => c.stamp(view, guess, dt),
+            Self::FittedImpedance(c) => c.stamp(view, guess, dt),
+            Self::Bjt(c) => c.stamp(view, guess, dt),
+        }
+    }
+
+    fn update(&mut self, view: &XMatrixView, dt: f64) {
+        match self {
+            Self::Resistor(c) => c.update(view, dt),
+            Self::Capacitor(c) => c.update(view, dt),
+            Self::Inductor(c) => c.update(view, dt),
+            Self::VoltageSource(c) => c.update(view, dt),
+            Self::CurrentSource(c) => c.update(view, dt),
+            Self::Diode(c) => c.update(view, dt),
+            Self::Switch(c) => c.update(view, dt),
+            Self::CurrentProbe(c) => c.update(view, dt),
+            Self::ElectronicLoad(c) => c.update(view, dt),
+            Self::PvModule(c) => c.update(view, dt),
+            Self::RandlesCell(c) => c.update(view, dt),
+            Self::ThermoelectricModule(c) => c.update(view, dt),
+            Self::HallSensor(c) => c.update(view, dt),
+            Self::CurrentTransformer(c) => c.update(view, dt),
+            Self::ControlledSource(c) => c.update(view, dt),
+            Self::ShuntReference(c) => c.update(view, dt),
+            Self::Relay(c) => c.update(view, dt),
+            Self::BenchSupply(c) => c.update(view, dt),
+            Self::PwmController(c) => c.update(view, dt),
+            Self::Vco(c) => c.update(view, dt),
+            Self::FrequencyDivider(c) => c.update(view, dt),
+            Self::PhaseFrequencyDetector(c) => c.update(view, dt),
+            Self::NonOverlappingClock(c) => c.update(view, dt),
+            Self::TransmissionGate(c) => c.update(view, dt),
+            Self::SampleAndHold(c) => c.update(view, dt),
+            Self::ClockedComparator(c) => c.update(view, dt),
+            Self::NoiseSource(c) => c.update(view, dt),
+            Self::PulseGenerator(c) => c.update(view, dt),
+            Self::FittedImpedance(c) => c.update(view, dt),
+            Self::Bjt(c) => c.update(view, dt),
+        }
+    }
+
+    fn state(&self) -> Option<f64> {
+        match self {
+            Self::Resistor(c) => c.state(),
+            Self::Capacitor(c) => c.state(),
+            Self::Inductor(c) => c.state(),
+            Self::VoltageSource(c) => c.state(),
+            Self::CurrentSource(c) => c.state(),
+            Self::Diode(c) => c.state(),
+            Self::Switch(c) => c.state(),
+            Self::CurrentProbe(c) => c.state(),
+            Self::ElectronicLoad(c) => c.state(),
+            Self::PvModule(c) => c.state(),
+            Self::RandlesCell(c) => c.state(),
+            Self::ThermoelectricModule(c) => c.state(),
+            Self::HallSensor(c) => c.state(),
+            Self::CurrentTransformer(c) => c.state(),
+            Self::ControlledSource(c) => c.state(),
+            Self::ShuntReference(c) => c.state(),
+            Self::Relay(c) => c.state(),
+            Self::BenchSupply(c) => c.state(),
+            Self::PwmController(c) => c.state(),
+            Self::Vco(c) => c.state(),
+            Self::FrequencyDivider(c) => c.state(),
+            Self::PhaseFrequencyDetector(c) => c.state(),
+            Self::NonOverlappingClock(c) => c.state(),
+            Self::TransmissionGate(c) => c.state(),
+            Self::SampleAndHold(c) => c.state(),
+            Self::ClockedComparator(c) => c.state(),
+            Self::NoiseSource(c) => c.state(),
+            Self::PulseGenerator(c) => c.state(),
+            Self::FittedImpedance(c) => c.state(),
+            Self::Bjt(c) => c.state(),
+        }
+    }
+
+    fn integrated_quantity(&self) -> Option<IntegratedQuantity> {
+        match self {
+            Self::Resistor(c) => c.integrated_quantity(),
+            Self::Capacitor(c) => c.integrated_quantity(),
+            Self::Inductor(c) => c.integrated_quantity(),
+            Self::VoltageSource(c) => c.integrated_quantity(),
+            Self::CurrentSource(c) => c.integrated_quantity(),
+            Self::Diode(c) => c.integrated_quantity(),
+            Self::Switch(c) => c.integrated_quantity(),
+            Self::CurrentProbe(c) => c.integrated_quantity(),
+            Self::ElectronicLoad(c) => c.integrated_quantity(),
+            Self::PvModule(c) => c.integrated_quantity(),
+            Self::RandlesCell(c) => c.integrated_quantity(),
+            Self::ThermoelectricModule(c) => c.integrated_quantity(),
+            Self::HallSensor(c) => c.integrated_quantity(),
+            Self::CurrentTransformer(c) => c.integrated_quantity(),
+            Self::ControlledSource(c) => c.integrated_quantity(),
+            Self::ShuntReference(c) => c.integrated_quantity(),
+            Self::Relay(c) => c.integrated_quantity(),
+            Self::BenchSupply(c) => c.integrated_quantity(),
+            Self::PwmController(c) => c.integrated_quantity(),
+            Self::Vco(c) => c.integrated_quantity(),
+            Self::FrequencyDivider(c) => c.integrated_quantity(),
+            Self::PhaseFrequencyDetector(c) => c.integrated_quantity(),
+            Self::NonOverlappingClock(c) => c.integrated_quantity(),
+            Self::TransmissionGate(c) => c.integrated_quantity(),
+            Self::SampleAndHold(c) => c.integrated_quantity(),
+            Self::ClockedComparator(c) => c.integrated_quantity(),
+            Self::NoiseSource(c) => c.integrated_quantity(),
+            Self::PulseGenerator(c) => c.integrated_quantity(),
+            Self::FittedImpedance(c) => c.integrated_quantity(),
+            Self::Bjt(c) => c.integrated_quantity(),
+        }
+    }
+
+    fn junctions(&self) -> Vec<(usize, usize)> {
+        match self {
+            Self::Resistor(c) => c.junctions(),
+            Self::Capacitor(c) => c.junctions(),
+            Self::Inductor(c) => c.junctions(),
+            Self::VoltageSource(c) => c.junctions(),
+            Self::CurrentSource(c) => c.junctions(),
+            Self::Diode(c) => c.junctions(),
+            Self::Switch(c) => c.junctions(),
+            Self::CurrentProbe(c) => c.junctions(),
+            Self::ElectronicLoad(c) => c.junctions(),
+            Self::PvModule(c) => c.junctions(),
+            Self::RandlesCell(c) => c.junctions(),
+            Self::ThermoelectricModule(c) => c.junctions(),
+            Self::HallSensor(c) => c.junctions(),
+            Self::CurrentTransformer(c) => c.junctions(),
+            Self::ControlledSource(c) => c.junctions(),
+            Self::ShuntReference(c) => c.junctions(),
+            Self::Relay(c) => c.junctions(),
+            Self::BenchSupply(c) => c.junctions(),
+            Self::PwmController(c) => c.junctions(),
+            Self::Vco(c) => c.junctions(),
+            Self::FrequencyDivider(c) => c.junctions(),
+            Self::PhaseFrequencyDetector(c) => c.junctions(),
+            Self::NonOverlappingClock(c) => c.junctions(),
+            Self::TransmissionGate(c) => c.junctions(),
+            Self::SampleAndHold(c) => c.junctions(),
+            Self::ClockedComparator(c) => c.junctions(),
+            Self::NoiseSource(c) => c.junctions(),
+            Self::PulseGenerator(c) => c.junctions(),
+            Self::FittedImpedance(c) => c.junctions(),
+            Self::Bjt(c) => c.junctions(),
+        }
+    }
+
+    fn stamp_dc(&self, view: &mut ABMatrixView, guess: &XMatrixView) {
+        match self {
+            Self::Resistor(c) => c.stamp_dc(view, guess),
+            Self::Capacitor(c) => c.stamp_dc(view, guess),
+            Self::Inductor(c) => c.stamp_dc(view, guess),
+            Self::VoltageSource(c) => c.stamp_dc(view, guess),
+            Self::CurrentSource(c) => c.stamp_dc(view, guess),
+            Self::Diode(c) => c.stamp_dc(view, guess),
+            Self::Switch(c) => c.stamp_dc(view, guess),
+            Self::CurrentProbe(c) => c.stamp_dc(view, guess),
+            Self::ElectronicLoad(c) => c.stamp_dc(view, guess),
+            Self::PvModule(c) => c.stamp_dc(view, guess),
+            Self::RandlesCell(c) => c.stamp_dc(view, guess),
+            Self::ThermoelectricModule(c) => c.stamp_dc(view, guess),
+            Self::HallSensor(c) => c.stamp_dc(view, guess),
+            Self::CurrentTransformer(c) => c.stamp_dc(view, guess),
+            Self::ControlledSource(c) => c.stamp_dc(view, guess),
+            Self::ShuntReference(c) => c.stamp_dc(view, guess),
+            Self::Relay(c) => c.stamp_dc(view, guess),
+            Self::BenchSupply(c) => c.stamp_dc(view, guess),
+            Self::PwmController(c) => c.stamp_dc(view, guess),
+            Self::Vco(c) => c.stamp_dc(view, guess),
+            Self::FrequencyDivider(c) => c.stamp_dc(view, guess),
+            Self::PhaseFrequencyDetector(c) => c.stamp_dc(view, guess),
+            Self::NonOverlappingClock(c) => c.stamp_dc(view, guess),
+            Self::TransmissionGate(c) => c.stamp_dc(view, guess),
+            Self::SampleAndHold(c) => c.stamp_dc(view, guess),
+            Self::ClockedComparator(c) => c.stamp_dc(view, guess),
+            Self::NoiseSource(c) => c.stamp_dc(view, guess),
+            Self::PulseGenerator(c) => c.stamp_dc(view, guess),
+            Self::FittedImpedance(c) => c.stamp_dc(view, guess),
+            Self::Bjt(c) => c.stamp_dc(view, guess),
+        }
+    }
+
+    fn update_dc(&mut self, view: &XMatrixView) {
+        match self {
+            Self::Resistor(c) => c.update_dc(view),
+            Self::Capacitor(c) => c.update_dc(view),
+            Self::Inductor(c) => c.update_dc(view),
+            Self::VoltageSource(c) => c.update_dc(view),
+            Self::CurrentSource(c) => c.update_dc(view),
+            Self::Diode(c) => c.update_dc(view),
+            Self::Switch(c) => c.update_dc(view),
+            Self::CurrentProbe(c) => c.update_dc(view),
+            Self::ElectronicLoad(c) => c.update_dc(view),
+            Self::PvModule(c) => c.update_dc(view),
+            Self::RandlesCell(c) => c.update_dc(view),
+            Self::ThermoelectricModule(c) => c.update_dc(view),
+            Self::HallSensor(c) => c.update_dc(view),
+            Self::CurrentTransformer(c) => c.update_dc(view),
+            Self::ControlledSource(c) => c.update_dc(view),
+            Self::ShuntReference(c) => c.update_dc(view),
+            Self::Relay(c) => c.update_dc(view),
+            Self::BenchSupply(c) => c.update_dc(view),
+            Self::PwmController(c) => c.update_dc(view),
+            Self::Vco(c) => c.update_dc(view),
+            Self::FrequencyDivider(c) => c.update_dc(view),
+            Self::PhaseFrequencyDetector(c) => c.update_dc(view),
+            Self::NonOverlappingClock(c) => c.update_dc(view),
+            Self::TransmissionGate(c) => c.update_dc(view),
+            Self::SampleAndHold(c) => c.update_dc(view),
+            Self::ClockedComparator(c) => c.update_dc(view),
+            Self::NoiseSource(c) => c.update_dc(view),
+            Self::PulseGenerator(c) => c.update_dc(view),
+            Self::FittedImpedance(c) => c.update_dc(view),
+            Self::Bjt(c) => c.update_dc(view),
+        }
+    }
+
+    fn stamp_ac(&self, view: &mut ABMatrixView<Complex<f64>>, omega: f64) {
+        match self {
+            Self::Resistor(c) => c.stamp_ac(view, omega),
+            Self::Capacitor(c) => c.stamp_ac(view, omega),
+            Self::Inductor(c) => c.stamp_ac(view, omega),
+            Self::VoltageSource(c) => c.stamp_ac(view, omega),
+            Self::CurrentSource(c) => c.stamp_ac(view, omega),
+            Self::Diode(c) => c.stamp_ac(view, omega),
+            Self::Switch(c) => c.stamp_ac(view, omega),
+            Self::CurrentProbe(c) => c.stamp_ac(view, omega),
+            Self::ElectronicLoad(c) => c.stamp_ac(view, omega),
+            Self::PvModule(c) => c.stamp_ac(view, omega),
+            Self::RandlesCell(c) => c.stamp_ac(view, omega),
+            Self::ThermoelectricModule(c) => c.stamp_ac(view, omega),
+            Self::HallSensor(c) => c.stamp_ac(view, omega),
+            Self::CurrentTransformer(c) => c.stamp_ac(view, omega),
+            Self::ControlledSource(c) => c.stamp_ac(view, omega),
+            Self::ShuntReference(c) => c.stamp_ac(view, omega),
+            Self::Relay(c) => c.stamp_ac(view, omega),
+            Self::BenchSupply(c) => c.stamp_ac(view, omega),
+            Self::PwmController(c) => c.stamp_ac(view, omega),
+            Self::Vco(c) => c.stamp_ac(view, omega),
+            Self::FrequencyDivider(c) => c.stamp_ac(view, omega),
+            Self::PhaseFrequencyDetector(c) => c.stamp_ac(view, omega),
+            Self::NonOverlappingClock(c) => c.stamp_ac(view, omega),
+            Self::TransmissionGate(c) => c.stamp_ac(view, omega),
+            Self::SampleAndHold(c) => c.stamp_ac(view, omega),
+            Self::ClockedComparator(c) => c.stamp_ac(view, omega),
+            Self::NoiseSource(c) => c.stamp_ac(view, omega),
+            Self::PulseGenerator(c) => c.stamp_ac(view, omega),
+            Self::FittedImpedance(c) => c.stamp_ac(view, omega),
+            Self::Bjt(c) => c.stamp_ac(view, omega),
+        }
+    }
+
+    fn junction_expansions(&self) -> Vec<JunctionExpansion> {
+        match self {
+            Self::Resistor(c) => c.junction_expansions(),
+            Self::Capacitor(c) => c.junction_expansions(),
+            Self::Inductor(c) => c.junction_expansions(),
+            Self::VoltageSource(c) => c.junction_expansions(),
+            Self::CurrentSource(c) => c.junction_expansions(),
+            Self::Diode(c) => c.junction_expansions(),
+            Self::Switch(c) => c.junction_expansions(),
+            Self::CurrentProbe(c) => c.junction_expansions(),
+            Self::ElectronicLoad(c) => c.junction_expansions(),
+            Self::PvModule(c) => c.junction_expansions(),
+            Self::RandlesCell(c) => c.junction_expansions(),
+            Self::ThermoelectricModule(c) => c.junction_expansions(),
+            Self::HallSensor(c) => c.junction_expansions(),
+            Self::CurrentTransformer(c) => c.junction_expansions(),
+            Self::ControlledSource(c) => c.junction_expansions(),
+            Self::ShuntReference(c) => c.junction_expansions(),
+            Self::Relay(c) => c.junction_expansions(),
+            Self::BenchSupply(c) => c.junction_expansions(),
+            Self::PwmController(c) => c.junction_expansions(),
+            Self::Vco(c) => c.junction_expansions(),
+            Self::FrequencyDivider(c) => c.junction_expansions(),
+            Self::PhaseFrequencyDetector(c) => c.junction_expansions(),
+            Self::NonOverlappingClock(c) => c.junction_expansions(),
+            Self::TransmissionGate(c) => c.junction_expansions(),
+            Self::SampleAndHold(c) => c.junction_expansions(),
+            Self::ClockedComparator(c) => c.junction_expansions(),
+            Self::NoiseSource(c) => c.junction_expansions(),
+            Self::PulseGenerator(c) => c.junction_expansions(),
+            Self::FittedImpedance(c) => c.junction_expansions(),
+            Self::Bjt(c) => c.junction_expansions(),
+        }
+    }
+
+    fn breakpoint(&self) -> Option<f64> {
+        match self {
+            Self::Resistor(c) => c.breakpoint(),
+            Self::Capacitor(c) => c.breakpoint(),
+            Self::Inductor(c) => c.breakpoint(),
+            Self::VoltageSource(c) => c.breakpoint(),
+            Self::CurrentSource(c) => c.breakpoint(),
+            Self::Diode(c) => c.breakpoint(),
+            Self::Switch(c) => c.breakpoint(),
+            Self::CurrentProbe(c) => c.breakpoint(),
+            Self::ElectronicLoad(c) => c.breakpoint(),
+            Self::PvModule(c) => c.breakpoint(),
+            Self::RandlesCell(c) => c.breakpoint(),
+            Self::ThermoelectricModule(c) => c.breakpoint(),
+            Self::HallSensor(c) => c.breakpoint(),
+            Self::CurrentTransformer(c) => c.breakpoint(),
+            Self::ControlledSource(c) => c.breakpoint(),
+            Self::ShuntReference(c) => c.breakpoint(),
+            Self::Relay(c) => c.breakpoint(),
+            Self::BenchSupply(c) => c.breakpoint(),
+            Self::PwmController(c) => c.breakpoint(),
+            Self::Vco(c) => c.breakpoint(),
+            Self::FrequencyDivider(c) => c.breakpoint(),
+            Self::PhaseFrequencyDetector(c) => c.breakpoint(),
+            Self::NonOverlappingClock(c) => c.breakpoint(),
+            Self::TransmissionGate(c) => c.breakpoint(),
+            Self::SampleAndHold(c) => c.breakpoint(),
+            Self::ClockedComparator(c) => c.breakpoint(),
+            Self::NoiseSource(c) => c.breakpoint(),
+            Self::PulseGenerator(c) => c.breakpoint(),
+            Self::FittedImpedance(c) => c.breakpoint(),
+            Self::Bjt(c) => c.breakpoint(),
+        }
+    }
+
+    fn limit(
+        &self,
+        proposed: &XMatrixView,
+        previous: &XMatrixView,
+    ) -> Vec<(ViewVariableIndex, f64)> {
+        match self {
+            Self::Resistor(c) => c.limit(proposed, previous),
+            Self::Capacitor(c) => c.limit(proposed, previous),
+            Self::Inductor(c) => c.limit(proposed, previous),
+            Self::VoltageSource(c) => c.limit(proposed, previous),
+            Self::CurrentSource(c) => c.limit(proposed, previous),
+            Self::Diode(c) => c.limit(proposed, previous),
+            Self::Switch(c) => c.limit(proposed, previous),
+            Self::CurrentProbe(c) => c.limit(proposed, previous),
+            Self::ElectronicLoad(c) => c.limit(proposed, previous),
+            Self::PvModule(c) => c.limit(proposed, previous),
+            Self::RandlesCell(c) => c.limit(proposed, previous),
+            Self::ThermoelectricModule(c) => c.limit(proposed, previous),
+            Self::HallSensor(c) => c.limit(proposed, previous),
+            Self::CurrentTransformer(c) => c.limit(proposed, previous),
+            Self::ControlledSource(c) => c.limit(proposed, previous),
+            Self::ShuntReference(c) => c.limit(proposed, previous),
+            Self::Relay(c) => c.limit(proposed, previous),
+            Self::BenchSupply(c) => c.limit(proposed, previous),
+            Self::PwmController(c) => c.limit(proposed, previous),
+            Self::Vco(c) => c.limit(proposed, previous),
+            Self::FrequencyDivider(c) => c.limit(proposed, previous),
+            Self::PhaseFrequencyDetector(c) => c.limit(proposed, previous),
+            Self::NonOverlappingClock(c) => c.limit(proposed, previous),
+            Self::TransmissionGate(c) => c.limit(proposed, previous),
+            Self::SampleAndHold(c) => c.limit(proposed, previous),
+            Self::ClockedComparator(c) => c.limit(proposed, previous),
+            Self::NoiseSource(c) => c.limit(proposed, previous),
+            Self::PulseGenerator(c) => c.limit(proposed, previous),
+            Self::FittedImpedance(c) => c.limit(proposed, previous),
+            Self::Bjt(c) => c.limit(proposed, previous),
+        }
+    }
+
+    fn stamp_with_integration(
+        &self,
+        view: &mut ABMatrixView,
+        guess: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
+        match self {
+            Self::Resistor(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Capacitor(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Inductor(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::VoltageSource(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::CurrentSource(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Diode(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Switch(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::CurrentProbe(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::ElectronicLoad(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::PvModule(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::RandlesCell(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::ThermoelectricModule(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::HallSensor(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::CurrentTransformer(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::ControlledSource(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::ShuntReference(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Relay(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::BenchSupply(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::PwmController(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Vco(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::FrequencyDivider(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::PhaseFrequencyDetector(c) => {
+                c.stamp_with_integration(view, guess, dt, integration)
+            }
+            Self::NonOverlappingClock(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::TransmissionGate(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::SampleAndHold(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::ClockedComparator(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::NoiseSource(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::PulseGenerator(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::FittedImpedance(c) => c.stamp_with_integration(view, guess, dt, integration),
+            Self::Bjt(c) => c.stamp_with_integration(view, guess, dt, integration),
+        }
+    }
+
+    fn update_with_integration(
+        &mut self,
+        view: &XMatrixView,
+        dt: f64,
+        integration: IntegrationMethod,
+    ) {
+        match self {
+            Self::Resistor(c) => c.update_with_integration(view, dt, integration),
+            Self::Capacitor(c) => c.update_with_integration(view, dt, integration),
+            Self::Inductor(c) => c.update_with_integration(view, dt, integration),
+            Self::VoltageSource(c) => c.update_with_integration(view, dt, integration),
+            Self::CurrentSource(c) => c.update_with_integration(view, dt, integration),
+            Self::Diode(c) => c.update_with_integration(view, dt, integration),
+            Self::Switch(c) => c.update_with_integration(view, dt, integration),
+            Self::CurrentProbe(c) => c.update_with_integration(view, dt, integration),
+            Self::ElectronicLoad(c) => c.update_with_integration(view, dt, integration),
+            Self::PvModule(c) => c.update_with_integration(view, dt, integration),
+            Self::RandlesCell(c) => c.update_with_integration(view, dt, integration),
+            Self::ThermoelectricModule(c) => c.update_with_integration(view, dt, integration),
+            Self::HallSensor(c) => c.update_with_integration(view, dt, integration),
+            Self::CurrentTransformer(c) => c.update_with_integration(view, dt, integration),
+            Self::ControlledSource(c) => c.update_with_integration(view, dt, integration),
+            Self::ShuntReference(c) => c.update_with_integration(view, dt, integration),
+            Self::Relay(c) => c.update_with_integration(view, dt, integration),
+            Self::BenchSupply(c) => c.update_with_integration(view, dt, integration),
+            Self::PwmController(c) => c.update_with_integration(view, dt, integration),
+            Self::Vco(c) => c.update_with_integration(view, dt, integration),
+            Self::FrequencyDivider(c) => c.update_with_integration(view, dt, integration),
+            Self::PhaseFrequencyDetector(c) => c.update_with_integration(view, dt, integration),
+            Self::NonOverlappingClock(c) => c.update_with_integration(view, dt, integration),
+            Self::TransmissionGate(c) => c.update_with_integration(view, dt, integration),
+            Self::SampleAndHold(c) => c.update_with_integration(view, dt, integration),
+            Self::ClockedComparator(c) => c.update_with_integration(view, dt, integration),
+            Self::NoiseSource(c) => c.update_with_integration(view, dt, integration),
+            Self::PulseGenerator(c) => c.update_with_integration(view, dt, integration),
+            Self::FittedImpedance(c) => c.update_with_integration(view, dt, integration),
+            Self::Bjt(c) => c.update_with_integration(view, dt, integration),
         }
     }
 }
@@ -0,0 +1,33 @@
+/// Counters describing the work done by a solver.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SolverStatistics {
+    /// Number of timesteps solved, including the substeps of refined timesteps.
+    pub steps: usize,
+    /// Total number of Newton-Raphson iterations over all timesteps.
+    pub newton_iterations: usize,
+    /// Number of timesteps that were rolled back and re-integrated with a finer timestep.
+    pub rollbacks: usize,
+    /// Number of timesteps that failed to converge and were integrated in two halves instead
+    /// (see [`SolverOptions::step_rejection`](crate::SolverOptions::step_rejection)).
+    pub rejected_steps: usize,
+    /// Number of Newton-Raphson iterations whose update was damped (see
+    /// [`SolverOptions::damping`](crate::SolverOptions::damping)).
+    pub damped_iterations: usize,
+    /// Number of Newton-Raphson iterations in which a component limited its junction voltages
+    /// (see [`SolverOptions::junction_limiting`](crate::SolverOptions::junction_limiting)).
+    pub limited_iterations: usize,
+    /// Number of timesteps and operating points solved by gmin stepping after the
+    /// Newton-Raphson iteration failed.
+    pub gmin_steppings: usize,
+    /// Number of timesteps and operating points solved by source stepping after the
+    /// Newton-Raphson iteration and gmin stepping failed.
+    pub source_steppings: usize,
+    /// Largest estimated condition number (in the 1-norm) of the linear systems inverted so far,
+    /// after scaling if enabled. Each power of ten costs about one digit of accuracy.
+    pub max_condition_number: f64,
+    /// Number of fill-reducing column orders computed for sparse systems (see
+    /// [`SolverOptions::sparse_threshold`](crate::SolverOptions::sparse_threshold)). An order
+    /// is reused until the sparsity pattern of the system changes, e.g. by an edit to the
+    /// netlist.
+    pub orderings: usize,
+}
@@ -0,0 +1,210 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Fraction of the current limit over which a [`BenchSupply`] goes from regulating its voltage
+/// to regulating its current.
+const KNEE_FRACTION: f64 = 1e-3;
+
+/// A bench power supply: regulates its output to the voltage setpoint until the load draws the
+/// current limit, then regulates the current instead, letting the voltage fall (constant
+/// voltage to constant current crossover).
+///
+/// With foldback the current limit falls linearly with the output voltage, from the limit at
+/// the setpoint down to the short-circuit current at 0V, as in linear supplies protecting their
+/// pass transistor.
+///
+/// The crossover is smooth, over 0.1% of the current limit, so the model converges from any
+/// starting point: just below the limit the output sags slightly under the setpoint, and a short
+/// circuit draws slightly more than the limit. The output can sink current, like an ideal source,
+/// and regulates instantly; the setpoint should be positive.
+#[derive(Clone, PartialEq)]
+pub struct BenchSupply {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`BenchSupply`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    voltage_setpoint: f64,
+    current_limit: f64,
+    short_circuit_current: Option<f64>,
+    output_resistance: f64,
+}
+
+impl BenchSupply {
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        voltage_setpoint: f64,
+        current_limit: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                voltage_setpoint,
+                current_limit,
+                short_circuit_current: None,
+                output_resistance: 0.0,
+            }),
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Folds the current limit back to the given current into a short circuit.
+    pub fn with_foldback(mut self, short_circuit_current: f64) -> Self {
+        self.shared.short_circuit_current = Some(short_circuit_current);
+        self
+    }
+
+    /// Sets the output resistance in constant voltage mode, e.g. the resistance of the leads.
+    pub fn with_output_resistance(mut self, resistance: f64) -> Self {
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared.positive_node.max(self.shared.negative_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_voltage_setpoint(&self) -> f64 {
+        self.shared.voltage_setpoint
+    }
+
+    pub fn set_voltage_setpoint(&mut self, voltage: f64) {
+        self.shared.voltage_setpoint = voltage;
+    }
+
+    pub fn get_current_limit(&self) -> f64 {
+        self.shared.current_limit
+    }
+
+    pub fn set_current_limit(&mut self, current: f64) {
+        self.shared.current_limit = current;
+    }
+
+    pub fn get_short_circuit_current(&self) -> Option<f64> {
+        self.shared.short_circuit_current
+    }
+
+    pub fn get_output_resistance(&self) -> f64 {
+        self.shared.output_resistance
+    }
+
+    /// Computes the current limit at the given output voltage, and its derivative with respect
+    /// to it, which are only nonzero with foldback.
+    pub fn limit_at(&self, voltage: f64) -> (f64, f64) {
+        let Some(short_circuit_current) = self.shared.short_circuit_current else {
+            return (self.shared.current_limit, 0.0);
+        };
+        if self.shared.voltage_setpoint <= 0.0 || voltage >= self.shared.voltage_setpoint {
+            return (self.shared.current_limit, 0.0);
+        }
+        let slope =
+            (self.shared.current_limit - short_circuit_current) / self.shared.voltage_setpoint;
+        if voltage <= 0.0 {
+            return (short_circuit_current, 0.0);
+        }
+        (short_circuit_current + slope * voltage, slope)
+    }
+
+    /// Computes how far the current limit pulls the output below the setpoint at the given
+    /// output voltage and current, along with the derivatives of the drop with respect to the
+    /// voltage and the current.
+    pub fn limit_drop(&self, voltage: f64, current: f64) -> (f64, f64, f64) {
+        let (limit, limit_slope) = self.limit_at(voltage);
+        let knee = KNEE_FRACTION * self.shared.current_limit.abs().max(f64::MIN_POSITIVE);
+
+        // A softplus of the excess current: nothing well below the limit, then a drop steep
+        // enough to pin the current to the limit.
+        let x = (current - limit) / knee;
+        let (softplus, sigmoid) = if x > 30.0 {
+            (x, 1.0)
+        } else {
+            let e = x.exp();
+            (e.ln_1p(), e / (1.0 + e))
+        };
+        let scale = self.shared.voltage_setpoint / knee;
+        (
+            self.shared.voltage_setpoint * softplus,
+            -scale * sigmoid * limit_slope,
+            scale * sigmoid,
+        )
+    }
+
+    /// Whether the supply is limiting its current, pulling the output more than 1% below the
+    /// setpoint.
+    pub fn is_current_limited(&self) -> bool {
+        let (drop, _, _) = self.limit_drop(self.voltage, self.current);
+        drop > 0.01 * self.shared.voltage_setpoint
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the positive terminal.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    /// Gets the power delivered to the circuit.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for BenchSupply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, cc: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_current_limited()
+        )
+    }
+}
+
+impl TryFrom<Component> for BenchSupply {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::BenchSupply(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, DiodeModel, Shared};
+
+/// The doping of a bipolar junction transistor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BjtPolarity {
+    Npn,
+    Pnp,
+}
+
+impl BjtPolarity {
+    /// Gets the sign turning the voltages and currents of the transistor into those of an NPN.
+    pub(crate) fn sign(self) -> f64 {
+        match self {
+            Self::Npn => 1.0,
+            Self::Pnp => -1.0,
+        }
+    }
+}
+
+/// A bipolar junction transistor following the transport form of the Ebers-Moll model.
+///
+/// For an NPN, the forward and reverse transport currents iF = Is*(exp(vbe/Vt) - 1) and
+/// iR = Is*(exp(vbc/Vt) - 1) give the collector current ic = iF - iR*(1 + 1/βR) and the base
+/// current ib = iF/βF + iR/βR, the emitter carrying the rest. A PNP follows the same equations
+/// with every voltage and current reversed. The junctions have no capacitance, so the
+/// transistor switches instantly.
+#[derive(Clone, PartialEq)]
+pub struct Bjt {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    base_emitter_voltage: f64,
+    base_collector_voltage: f64,
+    collector_current: f64,
+    base_current: f64,
+}
+
+/// The static variables of a [`Bjt`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    collector_node: usize,
+    base_node: usize,
+    emitter_node: usize,
+    polarity: BjtPolarity,
+    saturation_current: f64,
+    forward_beta: f64,
+    reverse_beta: f64,
+}
+
+impl Bjt {
+    /// Creates a transistor with a saturation current of 1e-14A, a forward beta of 100 and a
+    /// reverse beta of 1.
+    pub fn new(
+        collector_node: usize,
+        base_node: usize,
+        emitter_node: usize,
+        polarity: BjtPolarity,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                collector_node,
+                base_node,
+                emitter_node,
+                polarity,
+                saturation_current: 1e-14,
+                forward_beta: 100.0,
+                reverse_beta: 1.0,
+            }),
+            base_emitter_voltage: 0.0,
+            base_collector_voltage: 0.0,
+            collector_current: 0.0,
+            base_current: 0.0,
+        }
+    }
+
+    pub fn with_saturation_current(mut self, saturation_current: f64) -> Self {
+        self.shared.saturation_current = saturation_current;
+        self
+    }
+
+    /// Sets the current gain in the forward active region, collector current over base current.
+    pub fn with_forward_beta(mut self, forward_beta: f64) -> Self {
+        self.shared.forward_beta = forward_beta;
+        self
+    }
+
+    /// Sets the current gain with the collector and emitter swapped.
+    pub fn with_reverse_beta(mut self, reverse_beta: f64) -> Self {
+        self.shared.reverse_beta = reverse_beta;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.collector_node = map(self.shared.collector_node);
+        self.shared.base_node = map(self.shared.base_node);
+        self.shared.emitter_node = map(self.shared.emitter_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .collector_node
+            .max(self.shared.base_node)
+            .max(self.shared.emitter_node)
+    }
+
+    pub fn get_collector_node(&self) -> usize {
+        self.shared.collector_node
+    }
+
+    pub fn get_base_node(&self) -> usize {
+        self.shared.base_node
+    }
+
+    pub fn get_emitter_node(&self) -> usize {
+        self.shared.emitter_node
+    }
+
+    /// Gets the collector, which is the positive node of the transistor.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.collector_node
+    }
+
+    /// Gets the emitter, which is the negative node of the transistor.
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.emitter_node
+    }
+
+    pub fn get_polarity(&self) -> BjtPolarity {
+        self.shared.polarity
+    }
+
+    pub fn get_saturation_current(&self) -> f64 {
+        self.shared.saturation_current
+    }
+
+    pub fn get_forward_beta(&self) -> f64 {
+        self.shared.forward_beta
+    }
+
+    pub fn get_reverse_beta(&self) -> f64 {
+        self.shared.reverse_beta
+    }
+
+    /// Gets the model of the junctions, in the direction they conduct for an NPN.
+    pub(crate) fn junction_model(&self) -> DiodeModel {
+        DiodeModel::Shockley {
+            saturation_current: self.shared.saturation_current,
+            emission_coefficient: 1.0,
+        }
+    }
+
+    /// Gets the voltages of the junctions in the direction they conduct for an NPN, the
+    /// base-emitter and base-collector voltages of an NPN and their opposites for a PNP.
+    pub(crate) fn junction_voltages(
+        &self,
+        base_emitter_voltage: f64,
+        base_collector_voltage: f64,
+    ) -> (f64, f64) {
+        let sign = self.shared.polarity.sign();
+        (sign * base_emitter_voltage, sign * base_collector_voltage)
+    }
+
+    /// Computes the currents flowing into the collector and the base for the given base-emitter
+    /// and base-collector voltages, along with their derivatives with respect to those voltages
+    /// as `[[dic/dvbe, dic/dvbc], [dib/dvbe, dib/dvbc]]`.
+    pub fn evaluate(
+        &self,
+        base_emitter_voltage: f64,
+        base_collector_voltage: f64,
+    ) -> (f64, f64, [[f64; 2]; 2]) {
+        let sign = self.shared.polarity.sign();
+        let (vbe, vbc) = self.junction_voltages(base_emitter_voltage, base_collector_voltage);
+        let model = self.junction_model();
+        let (forward, g_forward) = model.evaluate(vbe);
+        let (reverse, g_reverse) = model.evaluate(vbc);
+
+        let collector_current = forward - reverse * (1.0 + 1.0 / self.shared.reverse_beta);
+        let base_current = forward / self.shared.forward_beta + reverse / self.shared.reverse_beta;
+        // Reversing both the voltages and the currents of a PNP leaves the derivatives as they
+        // are.
+        let derivatives = [
+            [
+                g_forward,
+                -g_reverse * (1.0 + 1.0 / self.shared.reverse_beta),
+            ],
+            [
+                g_forward / self.shared.forward_beta,
+                g_reverse / self.shared.reverse_beta,
+            ],
+        ];
+        (sign * collector_current, sign * base_current, derivatives)
+    }
+
+    pub fn get_base_emitter_voltage(&self) -> f64 {
+        self.base_emitter_voltage
+    }
+
+    pub fn get_base_collector_voltage(&self) -> f64 {
+        self.base_collector_voltage
+    }
+
+    pub(crate) fn set_junction_voltages(
+        &mut self,
+        base_emitter_voltage: f64,
+        base_collector_voltage: f64,
+    ) {
+        self.base_emitter_voltage = base_emitter_voltage;
+        self.base_collector_voltage = base_collector_voltage;
+    }
+
+    /// Gets the collector-emitter voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.base_emitter_voltage - self.base_collector_voltage
+    }
+
+    /// Gets the current flowing into the collector.
+    pub fn get_current(&self) -> f64 {
+        self.collector_current
+    }
+
+    /// Gets the current flowing into the base.
+    pub fn get_base_current(&self) -> f64 {
+        self.base_current
+    }
+
+    /// Gets the current flowing out of the emitter.
+    pub fn get_emitter_current(&self) -> f64 {
+        self.collector_current + self.base_current
+    }
+
+    pub(crate) fn set_currents(&mut self, collector_current: f64, base_current: f64) {
+        self.collector_current = collector_current;
+        self.base_current = base_current;
+    }
+
+    /// Gets the power dissipated in both junctions.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.collector_current + self.base_emitter_voltage * self.base_current
+    }
+}
+
+impl Debug for Bjt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{vce: {}, vbe: {}, ic: {}, ib: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_base_emitter_voltage(),
+            self.get_current(),
+            self.get_base_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for Bjt {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::Bjt(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,21 +1,104 @@
 use std::fmt::Debug;
 
-use crate::components::Component;
+use crate::components::{Component, Shared};
+
+/// Forward bias coefficient past which the junction capacitance is linearly extrapolated (SPICE
+/// FC).
+const JUNCTION_FORWARD_BIAS_COEFFICIENT: f64 = 0.5;
+
+/// Describes how the charge stored in a capacitor depends on the voltage across it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapacitanceModel {
+    /// A constant capacitance, q = C*v.
+    Linear(f64),
+    /// A reverse biased junction (varactor, MOSFET gate-drain, ...),
+    /// C(v) = C0/(1 - v/phi)^m.
+    Junction {
+        zero_bias_capacitance: f64,
+        junction_potential: f64,
+        grading_coefficient: f64,
+    },
+    /// A capacitance polynomial in the voltage, C(v) = c0 + c1*v + c2*v^2.
+    Polynomial { c0: f64, c1: f64, c2: f64 },
+}
+
+impl CapacitanceModel {
+    /// Computes the charge stored at the given voltage.
+    pub fn charge(&self, voltage: f64) -> f64 {
+        match *self {
+            Self::Linear(c) => c * voltage,
+            Self::Junction {
+                zero_bias_capacitance: c0,
+                junction_potential: phi,
+                grading_coefficient: m,
+            } => {
+                let fc = JUNCTION_FORWARD_BIAS_COEFFICIENT;
+                if voltage < fc * phi {
+                    c0 * phi * (1.0 - (1.0 - voltage / phi).powf(1.0 - m)) / (1.0 - m)
+                } else {
+                    // Past fc*phi the capacitance is linearly extrapolated to avoid the
+                    // singularity at v = phi.
+                    let f1 = phi * (1.0 - (1.0 - fc).powf(1.0 - m)) / (1.0 - m);
+                    let f2 = (1.0 - fc).powf(1.0 + m);
+                    let f3 = 1.0 - fc * (1.0 + m);
+                    let v0 = fc * phi;
+                    c0 * (f1
+                        + (f3 * (voltage - v0) + m / (2.0 * phi) * (voltage * voltage - v0 * v0))
+                            / f2)
+                }
+            }
+            Self::Polynomial { c0, c1, c2 } => {
+                voltage * (c0 + voltage * (c1 / 2.0 + voltage * c2 / 3.0))
+            }
+        }
+    }
+
+    /// Computes the small signal capacitance dq/dv at the given voltage.
+    pub fn capacitance(&self, voltage: f64) -> f64 {
+        match *self {
+            Self::Linear(c) => c,
+            Self::Junction {
+                zero_bias_capacitance: c0,
+                junction_potential: phi,
+                grading_coefficient: m,
+            } => {
+                let fc = JUNCTION_FORWARD_BIAS_COEFFICIENT;
+                if voltage < fc * phi {
+                    c0 * (1.0 - voltage / phi).powf(-m)
+                } else {
+                    let f2 = (1.0 - fc).powf(1.0 + m);
+                    let f3 = 1.0 - fc * (1.0 + m);
+                    c0 * (f3 + m * voltage / phi) / f2
+                }
+            }
+            Self::Polynomial { c0, c1, c2 } => c0 + voltage * (c1 + voltage * c2),
+        }
+    }
+}
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Capacitor {
     // Static variables
-    positive_node: usize,
-    negative_node: usize,
-    capacitance: f64,
+    pub(super) shared: Shared<Parameters>,
 
     // State variables
     voltage: f64,
+    charge: f64,
+    previous_charge: Option<(f64, f64)>,
 
     // Computed variables
     current: f64,
 }
 
+/// The static variables of a [`Capacitor`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    model: CapacitanceModel,
+    branch_current: bool,
+}
+
 impl Capacitor {
     pub fn new(
         positive_node: usize,
@@ -23,29 +106,77 @@ impl Capacitor {
         capacitance: f64,
         initial_voltage: f64,
     ) -> Self {
-        Self {
+        Self::nonlinear(
             positive_node,
             negative_node,
-            capacitance,
+            CapacitanceModel::Linear(capacitance),
+            initial_voltage,
+        )
+    }
+
+    /// Creates a capacitor whose charge follows the given model.
+    pub fn nonlinear(
+        positive_node: usize,
+        negative_node: usize,
+        model: CapacitanceModel,
+        initial_voltage: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                model,
+                branch_current: false,
+            }),
             voltage: initial_voltage,
+            charge: model.charge(initial_voltage),
+            previous_charge: None,
             current: 0.0,
         }
     }
 
+    /// Makes the solver allocate a variable for the current through the capacitor so it is
+    /// solved for directly instead of being reconstructed from the voltage change.
+    pub fn with_branch_current(mut self) -> Self {
+        self.shared.branch_current = true;
+        self
+    }
+
+    pub fn has_branch_current(&self) -> bool {
+        self.shared.branch_current
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
     pub fn max_node(&self) -> usize {
         self.get_positive_node().max(self.get_negative_node())
     }
 
     pub fn get_positive_node(&self) -> usize {
-        self.positive_node
+        self.shared.positive_node
     }
 
     pub fn get_negative_node(&self) -> usize {
-        self.negative_node
+        self.shared.negative_node
     }
 
+    pub fn get_model(&self) -> CapacitanceModel {
+        self.shared.model
+    }
+
+    /// Gets the small signal capacitance at the present voltage.
     pub fn get_capacitance(&self) -> f64 {
-        self.capacitance
+        self.shared.model.capacitance(self.voltage)
+    }
+
+    /// Makes the capacitor a linear capacitor with the given capacitance, keeping its present
+    /// voltage.
+    pub fn set_capacitance(&mut self, capacitance: f64) {
+        self.shared.model = CapacitanceModel::Linear(capacitance);
+        self.charge = self.shared.model.charge(self.voltage);
     }
 
     pub fn get_voltage(&self) -> f64 {
@@ -54,6 +185,21 @@ impl Capacitor {
 
     pub fn set_voltage(&mut self, voltage: f64) {
         self.voltage = voltage;
+        self.charge = self.shared.model.charge(voltage);
+    }
+
+    pub fn get_charge(&self) -> f64 {
+        self.charge
+    }
+
+    /// Gets the charge before the last timestep along with the length of that timestep, the
+    /// history of two-step integration methods, or None before the first timestep.
+    pub fn get_previous_charge(&self) -> Option<(f64, f64)> {
+        self.previous_charge
+    }
+
+    pub fn set_previous_charge(&mut self, previous_charge: Option<(f64, f64)>) {
+        self.previous_charge = previous_charge;
     }
 
     pub fn get_current(&self) -> f64 {
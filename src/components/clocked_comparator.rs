@@ -0,0 +1,318 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared, sample_and_hold::event_reached};
+
+/// A behavioral clocked (regenerative latch) comparator, e.g. the decision element of a SAR
+/// ADC.
+///
+/// The aperture delay after every rising edge of the clock it samples the voltage between its
+/// positive and negative inputs, less the input offset. The latch then regenerates the sample
+/// exponentially with the regeneration time constant, so the decision reaches the output
+/// voltage after tau*ln(V_out/|dv|): small inputs take longer to decide. Samples within the
+/// metastability window don't resolve before the next sample, leaving the output stuck halfway
+/// (see [`ClockedComparator::is_metastable`]). The output holds the last decision, driven high
+/// (to the output voltage, through the output resistance) or low (to the ground node), until
+/// the next one.
+///
+/// The clock is high while its voltage above the ground node is above the threshold. The
+/// comparator is updated between timesteps from the voltages the timestep ended with, so a
+/// clock edge is seen at the end of the timestep it happened in. The sampling and decision
+/// instants are breakpoints, so timesteps end on them (see
+/// [`BESolver::solve`](crate::BESolver::solve)).
+#[derive(Clone, PartialEq)]
+pub struct ClockedComparator {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+    clock_high: bool,
+    pending_sample: Option<f64>,
+    pending_decision: Option<(f64, bool)>,
+    high: bool,
+    metastable: bool,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`ClockedComparator`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_input_node: usize,
+    negative_input_node: usize,
+    clock_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    offset: f64,
+    threshold: f64,
+    aperture_delay: f64,
+    regeneration_time_constant: f64,
+    metastability_window: f64,
+    output_voltage: f64,
+    output_resistance: f64,
+}
+
+impl ClockedComparator {
+    /// Creates an ideal comparator: no offset, aperture delay, regeneration time or
+    /// metastability window, with a 0.5V clock threshold. It drives its output to 1V through
+    /// 1Ω, starting low.
+    pub fn new(
+        positive_input_node: usize,
+        negative_input_node: usize,
+        clock_node: usize,
+        output_node: usize,
+        ground_node: usize,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_input_node,
+                negative_input_node,
+                clock_node,
+                output_node,
+                ground_node,
+                offset: 0.0,
+                threshold: 0.5,
+                aperture_delay: 0.0,
+                regeneration_time_constant: 0.0,
+                metastability_window: 0.0,
+                output_voltage: 1.0,
+                output_resistance: 1.0,
+            }),
+            time: 0.0,
+            clock_high: false,
+            pending_sample: None,
+            pending_decision: None,
+            high: false,
+            metastable: false,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Sets the input offset voltage, the input voltage at which the decision flips.
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.shared.offset = offset;
+        self
+    }
+
+    /// Sets the clock voltage above which the clock is high.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.shared.threshold = threshold;
+        self
+    }
+
+    /// Sets the delay between a rising edge of the clock and the sampling instant.
+    pub fn with_aperture_delay(mut self, aperture_delay: f64) -> Self {
+        self.shared.aperture_delay = aperture_delay;
+        self
+    }
+
+    /// Sets the time constant of the regeneration and the input voltage below which a sample
+    /// doesn't resolve.
+    pub fn with_regeneration(mut self, time_constant: f64, metastability_window: f64) -> Self {
+        self.shared.regeneration_time_constant = time_constant;
+        self.shared.metastability_window = metastability_window;
+        self
+    }
+
+    /// Sets the voltage the output is driven to while high and the resistance it is driven
+    /// through.
+    pub fn with_output_drive(mut self, voltage: f64, resistance: f64) -> Self {
+        self.shared.output_voltage = voltage;
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_input_node = map(self.shared.positive_input_node);
+        self.shared.negative_input_node = map(self.shared.negative_input_node);
+        self.shared.clock_node = map(self.shared.clock_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .positive_input_node
+            .max(self.shared.negative_input_node)
+            .max(self.shared.clock_node)
+            .max(self.shared.output_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the output node.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_positive_input_node(&self) -> usize {
+        self.shared.positive_input_node
+    }
+
+    pub fn get_negative_input_node(&self) -> usize {
+        self.shared.negative_input_node
+    }
+
+    pub fn get_clock_node(&self) -> usize {
+        self.shared.clock_node
+    }
+
+    pub fn get_offset(&self) -> f64 {
+        self.shared.offset
+    }
+
+    pub fn set_offset(&mut self, offset: f64) {
+        self.shared.offset = offset;
+    }
+
+    pub fn get_aperture_delay(&self) -> f64 {
+        self.shared.aperture_delay
+    }
+
+    /// Gets the regeneration time constant and the metastability window.
+    pub fn get_regeneration(&self) -> (f64, f64) {
+        (
+            self.shared.regeneration_time_constant,
+            self.shared.metastability_window,
+        )
+    }
+
+    pub fn get_output_drive(&self) -> (f64, f64) {
+        (self.shared.output_voltage, self.shared.output_resistance)
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns whether the last decision was high.
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    /// Returns whether the last sample fell within the metastability window, leaving the
+    /// output halfway between low and high.
+    pub fn is_metastable(&self) -> bool {
+        self.metastable
+    }
+
+    /// Gets the voltage the output is driven to in the present state.
+    pub fn get_drive_voltage(&self) -> f64 {
+        if self.metastable {
+            self.shared.output_voltage / 2.0
+        } else if self.high {
+            self.shared.output_voltage
+        } else {
+            0.0
+        }
+    }
+
+    /// Computes the time the latch takes to regenerate a sample to a full decision.
+    pub fn resolution_time(&self, input_voltage: f64) -> f64 {
+        let difference = (input_voltage - self.shared.offset).abs();
+        if self.shared.regeneration_time_constant <= 0.0 || difference >= self.shared.output_voltage
+        {
+            return 0.0;
+        }
+        self.shared.regeneration_time_constant * (self.shared.output_voltage / difference).ln()
+    }
+
+    /// Gets the time of the next pending sample or decision.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        let decision = self.pending_decision.map(|(time, _)| time);
+        match (self.pending_sample, decision) {
+            (Some(sample), Some(decision)) => Some(sample.min(decision)),
+            (sample, decision) => sample.or(decision),
+        }
+    }
+
+    /// Advances over a timestep dt, from the clock and input voltages it ended with.
+    pub fn advance(&mut self, dt: f64, clock_voltage: f64, input_voltage: f64) {
+        self.time += dt;
+
+        let clock_high = clock_voltage > self.shared.threshold;
+        if clock_high && !self.clock_high {
+            self.pending_sample = Some(self.time + self.shared.aperture_delay);
+        }
+        self.clock_high = clock_high;
+
+        if self
+            .pending_sample
+            .is_some_and(|sample| event_reached(self.time, sample))
+        {
+            self.pending_sample = None;
+            let difference = input_voltage - self.shared.offset;
+            if difference.abs() < self.shared.metastability_window {
+                self.metastable = true;
+                self.pending_decision = None;
+            } else {
+                self.pending_decision = Some((
+                    self.time + self.resolution_time(input_voltage),
+                    difference > 0.0,
+                ));
+            }
+        }
+
+        if let Some((time, high)) = self.pending_decision
+            && event_reached(self.time, time)
+        {
+            self.pending_decision = None;
+            self.high = high;
+            self.metastable = false;
+        }
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the output node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for ClockedComparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, high: {}, metastable: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_high(),
+            self.is_metastable()
+        )
+    }
+}
+
+impl TryFrom<Component> for ClockedComparator {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::ClockedComparator(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
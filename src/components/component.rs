@@ -1,12 +1,44 @@
-use crate::components::{Capacitor, CurrentSource, Inductor, Resistor, VoltageSource};
+use crate::components::{
+    BenchSupply, Bjt, CapacitanceModel, Capacitor, ClockedComparator, ControlledSource,
+    ControlledSourceKind, CurrentProbe, CurrentSource, CurrentTransformer, Diode, ElectronicLoad,
+    FittedImpedance, FrequencyDivider, HallSensor, Inductor, NoiseSource, NonOverlappingClock,
+    PhaseFrequencyDetector, PulseGenerator, PvModule, PwmController, RandlesCell, Relay, Resistor,
+    SampleAndHold, ShuntReference, Switch, ThermoelectricModule, TransmissionGate, Vco,
+    VoltageSource,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Component {
     Resistor(Resistor),
     Capacitor(Capacitor),
     Inductor(Inductor),
     VoltageSource(VoltageSource),
     CurrentSource(CurrentSource),
+    Diode(Diode),
+    Switch(Switch),
+    CurrentProbe(CurrentProbe),
+    ElectronicLoad(ElectronicLoad),
+    PvModule(PvModule),
+    RandlesCell(RandlesCell),
+    ThermoelectricModule(ThermoelectricModule),
+    HallSensor(HallSensor),
+    CurrentTransformer(CurrentTransformer),
+    ControlledSource(ControlledSource),
+    ShuntReference(ShuntReference),
+    Relay(Relay),
+    BenchSupply(BenchSupply),
+    PwmController(PwmController),
+    Vco(Vco),
+    FrequencyDivider(FrequencyDivider),
+    PhaseFrequencyDetector(PhaseFrequencyDetector),
+    NonOverlappingClock(NonOverlappingClock),
+    TransmissionGate(TransmissionGate),
+    SampleAndHold(SampleAndHold),
+    ClockedComparator(ClockedComparator),
+    NoiseSource(NoiseSource),
+    PulseGenerator(PulseGenerator),
+    FittedImpedance(FittedImpedance),
+    Bjt(Bjt),
 }
 
 impl Component {
@@ -17,6 +49,414 @@ impl Component {
             Self::Inductor(c) => c.max_node(),
             Self::VoltageSource(c) => c.max_node(),
             Self::CurrentSource(c) => c.max_node(),
+            Self::Diode(c) => c.max_node(),
+            Self::Switch(c) => c.max_node(),
+            Self::CurrentProbe(c) => c.max_node(),
+            Self::ElectronicLoad(c) => c.max_node(),
+            Self::PvModule(c) => c.max_node(),
+            Self::RandlesCell(c) => c.max_node(),
+            Self::ThermoelectricModule(c) => c.max_node(),
+            Self::HallSensor(c) => c.max_node(),
+            Self::CurrentTransformer(c) => c.max_node(),
+            Self::ControlledSource(c) => c.max_node(),
+            Self::ShuntReference(c) => c.max_node(),
+            Self::Relay(c) => c.max_node(),
+            Self::BenchSupply(c) => c.max_node(),
+            Self::PwmController(c) => c.max_node(),
+            Self::Vco(c) => c.max_node(),
+            Self::FrequencyDivider(c) => c.max_node(),
+            Self::PhaseFrequencyDetector(c) => c.max_node(),
+            Self::NonOverlappingClock(c) => c.max_node(),
+            Self::TransmissionGate(c) => c.max_node(),
+            Self::SampleAndHold(c) => c.max_node(),
+            Self::ClockedComparator(c) => c.max_node(),
+            Self::NoiseSource(c) => c.max_node(),
+            Self::PulseGenerator(c) => c.max_node(),
+            Self::FittedImpedance(c) => c.max_node(),
+            Self::Bjt(c) => c.max_node(),
+        }
+    }
+
+    /// Reconnects each electrical terminal of the component to the node the map returns for the
+    /// node it is connected to.
+    pub(crate) fn map_nodes(&mut self, map: impl FnMut(usize) -> usize) {
+        match self {
+            Self::Resistor(c) => c.map_nodes(map),
+            Self::Capacitor(c) => c.map_nodes(map),
+            Self::Inductor(c) => c.map_nodes(map),
+            Self::VoltageSource(c) => c.map_nodes(map),
+            Self::CurrentSource(c) => c.map_nodes(map),
+            Self::Diode(c) => c.map_nodes(map),
+            Self::Switch(c) => c.map_nodes(map),
+            Self::CurrentProbe(c) => c.map_nodes(map),
+            Self::ElectronicLoad(c) => c.map_nodes(map),
+            Self::PvModule(c) => c.map_nodes(map),
+            Self::RandlesCell(c) => c.map_nodes(map),
+            Self::ThermoelectricModule(c) => c.map_nodes(map),
+            Self::HallSensor(c) => c.map_nodes(map),
+            Self::CurrentTransformer(c) => c.map_nodes(map),
+            Self::ControlledSource(c) => c.map_nodes(map),
+            Self::ShuntReference(c) => c.map_nodes(map),
+            Self::Relay(c) => c.map_nodes(map),
+            Self::BenchSupply(c) => c.map_nodes(map),
+            Self::PwmController(c) => c.map_nodes(map),
+            Self::Vco(c) => c.map_nodes(map),
+            Self::FrequencyDivider(c) => c.map_nodes(map),
+            Self::PhaseFrequencyDetector(c) => c.map_nodes(map),
+            Self::NonOverlappingClock(c) => c.map_nodes(map),
+            Self::TransmissionGate(c) => c.map_nodes(map),
+            Self::SampleAndHold(c) => c.map_nodes(map),
+            Self::ClockedComparator(c) => c.map_nodes(map),
+            Self::NoiseSource(c) => c.map_nodes(map),
+            Self::PulseGenerator(c) => c.map_nodes(map),
+            Self::FittedImpedance(c) => c.map_nodes(map),
+            Self::Bjt(c) => c.map_nodes(map),
+        }
+    }
+
+    /// Gets the SPICE style letter identifying the kind of component.
+    pub fn get_prefix(&self) -> &'static str {
+        match self {
+            Self::Resistor(_) => "R",
+            Self::Capacitor(_) => "C",
+            Self::Inductor(_) => "L",
+            Self::VoltageSource(_) => "V",
+            Self::CurrentSource(_) => "I",
+            Self::Diode(_) => "D",
+            Self::Switch(_) => "S",
+            // Ammeters are zero volt sources in SPICE.
+            Self::CurrentProbe(_) => "V",
+            // Electronic loads are behavioral sources in SPICE.
+            Self::ElectronicLoad(_) => "B",
+            // PV modules are subcircuits in SPICE.
+            Self::PvModule(_) => "X",
+            Self::RandlesCell(_) => "X",
+            Self::ThermoelectricModule(_) => "X",
+            Self::HallSensor(_) => "X",
+            Self::CurrentTransformer(_) => "X",
+            Self::ControlledSource(c) => match c.get_kind() {
+                ControlledSourceKind::VoltageControlledVoltage => "E",
+                ControlledSourceKind::VoltageControlledCurrent => "G",
+                ControlledSourceKind::CurrentControlledVoltage => "H",
+                ControlledSourceKind::CurrentControlledCurrent => "F",
+            },
+            Self::ShuntReference(_) => "X",
+            Self::Relay(_) => "K",
+            Self::BenchSupply(_) => "V",
+            Self::PwmController(_) => "X",
+            Self::Vco(_) => "X",
+            Self::FrequencyDivider(_) => "X",
+            Self::PhaseFrequencyDetector(_) => "X",
+            Self::NonOverlappingClock(_) => "X",
+            Self::TransmissionGate(_) => "S",
+            Self::SampleAndHold(_) => "X",
+            Self::ClockedComparator(_) => "X",
+            Self::NoiseSource(_) => "V",
+            Self::PulseGenerator(_) => "V",
+            Self::FittedImpedance(_) => "X",
+            Self::Bjt(_) => "Q",
+        }
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        match self {
+            Self::Resistor(c) => c.get_positive_node(),
+            Self::Capacitor(c) => c.get_positive_node(),
+            Self::Inductor(c) => c.get_positive_node(),
+            Self::VoltageSource(c) => c.get_positive_node(),
+            Self::CurrentSource(c) => c.get_positive_node(),
+            Self::Diode(c) => c.get_positive_node(),
+            Self::Switch(c) => c.get_positive_node(),
+            Self::CurrentProbe(c) => c.get_positive_node(),
+            Self::ElectronicLoad(c) => c.get_positive_node(),
+            Self::PvModule(c) => c.get_positive_node(),
+            Self::RandlesCell(c) => c.get_positive_node(),
+            Self::ThermoelectricModule(c) => c.get_positive_node(),
+            Self::HallSensor(c) => c.get_positive_node(),
+            Self::CurrentTransformer(c) => c.get_positive_node(),
+            Self::ControlledSource(c) => c.get_positive_node(),
+            Self::ShuntReference(c) => c.get_positive_node(),
+            Self::Relay(c) => c.get_positive_node(),
+            Self::BenchSupply(c) => c.get_positive_node(),
+            Self::PwmController(c) => c.get_positive_node(),
+            Self::Vco(c) => c.get_positive_node(),
+            Self::FrequencyDivider(c) => c.get_positive_node(),
+            Self::PhaseFrequencyDetector(c) => c.get_positive_node(),
+            Self::NonOverlappingClock(c) => c.get_positive_node(),
+            Self::TransmissionGate(c) => c.get_positive_node(),
+            Self::SampleAndHold(c) => c.get_positive_node(),
+            Self::ClockedComparator(c) => c.get_positive_node(),
+            Self::NoiseSource(c) => c.get_positive_node(),
+            Self::PulseGenerator(c) => c.get_positive_node(),
+            Self::FittedImpedance(c) => c.get_positive_node(),
+            Self::Bjt(c) => c.get_positive_node(),
+        }
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        match self {
+            Self::Resistor(c) => c.get_negative_node(),
+            Self::Capacitor(c) => c.get_negative_node(),
+            Self::Inductor(c) => c.get_negative_node(),
+            Self::VoltageSource(c) => c.get_negative_node(),
+            Self::CurrentSource(c) => c.get_negative_node(),
+            Self::Diode(c) => c.get_negative_node(),
+            Self::Switch(c) => c.get_negative_node(),
+            Self::CurrentProbe(c) => c.get_negative_node(),
+            Self::ElectronicLoad(c) => c.get_negative_node(),
+            Self::PvModule(c) => c.get_negative_node(),
+            Self::RandlesCell(c) => c.get_negative_node(),
+            Self::ThermoelectricModule(c) => c.get_negative_node(),
+            Self::HallSensor(c) => c.get_negative_node(),
+            Self::CurrentTransformer(c) => c.get_negative_node(),
+            Self::ControlledSource(c) => c.get_negative_node(),
+            Self::ShuntReference(c) => c.get_negative_node(),
+            Self::Relay(c) => c.get_negative_node(),
+            Self::BenchSupply(c) => c.get_negative_node(),
+            Self::PwmController(c) => c.get_negative_node(),
+            Self::Vco(c) => c.get_negative_node(),
+            Self::FrequencyDivider(c) => c.get_negative_node(),
+            Self::PhaseFrequencyDetector(c) => c.get_negative_node(),
+            Self::NonOverlappingClock(c) => c.get_negative_node(),
+            Self::TransmissionGate(c) => c.get_negative_node(),
+            Self::SampleAndHold(c) => c.get_negative_node(),
+            Self::ClockedComparator(c) => c.get_negative_node(),
+            Self::NoiseSource(c) => c.get_negative_node(),
+            Self::PulseGenerator(c) => c.get_negative_node(),
+            Self::FittedImpedance(c) => c.get_negative_node(),
+            Self::Bjt(c) => c.get_negative_node(),
+        }
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        match self {
+            Self::Resistor(c) => c.get_voltage(),
+            Self::Capacitor(c) => c.get_voltage(),
+            Self::Inductor(c) => c.get_voltage(),
+            Self::VoltageSource(c) => c.get_terminal_voltage(),
+            Self::CurrentSource(c) => c.get_voltage(),
+            Self::Diode(c) => c.get_voltage(),
+            Self::Switch(c) => c.get_voltage(),
+            Self::CurrentProbe(c) => c.get_voltage(),
+            Self::ElectronicLoad(c) => c.get_voltage(),
+            Self::PvModule(c) => c.get_voltage(),
+            Self::RandlesCell(c) => c.get_voltage(),
+            Self::ThermoelectricModule(c) => c.get_voltage(),
+            Self::HallSensor(c) => c.get_voltage(),
+            Self::CurrentTransformer(c) => c.get_voltage(),
+            Self::ControlledSource(c) => c.get_voltage(),
+            Self::ShuntReference(c) => c.get_voltage(),
+            Self::Relay(c) => c.get_voltage(),
+            Self::BenchSupply(c) => c.get_voltage(),
+            Self::PwmController(c) => c.get_voltage(),
+            Self::Vco(c) => c.get_voltage(),
+            Self::FrequencyDivider(c) => c.get_voltage(),
+            Self::PhaseFrequencyDetector(c) => c.get_voltage(),
+            Self::NonOverlappingClock(c) => c.get_voltage(),
+            Self::TransmissionGate(c) => c.get_voltage(),
+            Self::SampleAndHold(c) => c.get_voltage(),
+            Self::ClockedComparator(c) => c.get_voltage(),
+            Self::NoiseSource(c) => c.get_voltage(),
+            Self::PulseGenerator(c) => c.get_voltage(),
+            Self::FittedImpedance(c) => c.get_voltage(),
+            Self::Bjt(c) => c.get_voltage(),
+        }
+    }
+
+    pub fn get_current(&self) -> f64 {
+        match self {
+            Self::Resistor(c) => c.get_current(),
+            Self::Capacitor(c) => c.get_current(),
+            Self::Inductor(c) => c.get_current(),
+            Self::VoltageSource(c) => c.get_current(),
+            Self::CurrentSource(c) => c.get_output_current(),
+            Self::Diode(c) => c.get_current(),
+            Self::Switch(c) => c.get_current(),
+            Self::CurrentProbe(c) => c.get_current(),
+            Self::ElectronicLoad(c) => c.get_current(),
+            Self::PvModule(c) => c.get_current(),
+            Self::RandlesCell(c) => c.get_current(),
+            Self::ThermoelectricModule(c) => c.get_current(),
+            Self::HallSensor(c) => c.get_current(),
+            Self::CurrentTransformer(c) => c.get_current(),
+            Self::ControlledSource(c) => c.get_current(),
+            Self::ShuntReference(c) => c.get_current(),
+            Self::Relay(c) => c.get_current(),
+            Self::BenchSupply(c) => c.get_current(),
+            Self::PwmController(c) => c.get_current(),
+            Self::Vco(c) => c.get_current(),
+            Self::FrequencyDivider(c) => c.get_current(),
+            Self::PhaseFrequencyDetector(c) => c.get_current(),
+            Self::NonOverlappingClock(c) => c.get_current(),
+            Self::TransmissionGate(c) => c.get_current(),
+            Self::SampleAndHold(c) => c.get_current(),
+            Self::ClockedComparator(c) => c.get_current(),
+            Self::NoiseSource(c) => c.get_current(),
+            Self::PulseGenerator(c) => c.get_current(),
+            Self::FittedImpedance(c) => c.get_current(),
+            Self::Bjt(c) => c.get_current(),
+        }
+    }
+
+    pub fn get_power(&self) -> f64 {
+        match self {
+            Self::Resistor(c) => c.get_power(),
+            Self::Capacitor(c) => c.get_power(),
+            Self::Inductor(c) => c.get_power(),
+            Self::VoltageSource(c) => c.get_power(),
+            Self::CurrentSource(c) => c.get_power(),
+            Self::Diode(c) => c.get_power(),
+            Self::Switch(c) => c.get_power(),
+            Self::CurrentProbe(c) => c.get_power(),
+            Self::ElectronicLoad(c) => c.get_power(),
+            Self::PvModule(c) => c.get_power(),
+            Self::RandlesCell(c) => c.get_power(),
+            Self::ThermoelectricModule(c) => c.get_power(),
+            Self::HallSensor(c) => c.get_power(),
+            Self::CurrentTransformer(c) => c.get_power(),
+            Self::ControlledSource(c) => c.get_power(),
+            Self::ShuntReference(c) => c.get_power(),
+            Self::Relay(c) => c.get_power(),
+            Self::BenchSupply(c) => c.get_power(),
+            Self::PwmController(c) => c.get_power(),
+            Self::Vco(c) => c.get_power(),
+            Self::FrequencyDivider(c) => c.get_power(),
+            Self::PhaseFrequencyDetector(c) => c.get_power(),
+            Self::NonOverlappingClock(c) => c.get_power(),
+            Self::TransmissionGate(c) => c.get_power(),
+            Self::SampleAndHold(c) => c.get_power(),
+            Self::ClockedComparator(c) => c.get_power(),
+            Self::NoiseSource(c) => c.get_power(),
+            Self::PulseGenerator(c) => c.get_power(),
+            Self::FittedImpedance(c) => c.get_power(),
+            Self::Bjt(c) => c.get_power(),
+        }
+    }
+
+    /// Gets the primary value of the component (resistance, capacitance, inductance or source
+    /// value), if it has one.
+    pub fn get_value(&self) -> Option<f64> {
+        match self {
+            Self::Resistor(c) => Some(c.get_resistance()),
+            Self::Capacitor(c) => match c.get_model() {
+                CapacitanceModel::Linear(capacitance) => Some(capacitance),
+                _ => None,
+            },
+            Self::Inductor(c) => Some(c.get_inductance()),
+            Self::VoltageSource(c) => Some(c.get_voltage()),
+            Self::CurrentSource(c) => Some(c.get_current()),
+            Self::Diode(_) => None,
+            Self::Switch(_) => None,
+            Self::CurrentProbe(_) => None,
+            Self::ElectronicLoad(_) => None,
+            Self::PvModule(_) => None,
+            Self::RandlesCell(_) => None,
+            Self::ThermoelectricModule(_) => None,
+            Self::HallSensor(_) => None,
+            Self::CurrentTransformer(_) => None,
+            Self::ControlledSource(_) => None,
+            Self::ShuntReference(_) => None,
+            Self::Relay(_) => None,
+            Self::BenchSupply(c) => Some(c.get_voltage_setpoint()),
+            Self::PwmController(c) => Some(c.get_reference()),
+            Self::Vco(c) => Some(c.get_center_frequency()),
+            Self::FrequencyDivider(c) => Some(c.get_ratio() as f64),
+            Self::PhaseFrequencyDetector(c) => Some(c.get_pump_current()),
+            Self::NonOverlappingClock(c) => Some(c.get_frequency()),
+            Self::TransmissionGate(c) => Some(c.get_on_resistance()),
+            Self::SampleAndHold(_) => None,
+            Self::ClockedComparator(c) => Some(c.get_offset()),
+            Self::NoiseSource(c) => Some(c.get_offset()),
+            Self::PulseGenerator(c) => Some(c.get_battery_voltage()),
+            Self::FittedImpedance(_) => None,
+            Self::Bjt(_) => None,
+        }
+    }
+
+    /// Sets the primary value of the component (see [`Component::get_value`]).
+    ///
+    /// Returns false if the component has no primary value.
+    pub fn set_value(&mut self, value: f64) -> bool {
+        match self {
+            Self::Resistor(c) => c.set_resistance(value),
+            Self::Capacitor(c) => c.set_capacitance(value),
+            Self::Inductor(c) => c.set_inductance(value),
+            Self::VoltageSource(c) => c.set_voltage(value),
+            Self::CurrentSource(c) => c.set_current(value),
+            Self::Diode(_) => return false,
+            Self::Switch(_) => return false,
+            Self::CurrentProbe(_) => return false,
+            Self::ElectronicLoad(_) => return false,
+            Self::PvModule(_) => return false,
+            Self::RandlesCell(_) => return false,
+            Self::ThermoelectricModule(_) => return false,
+            Self::HallSensor(_) => return false,
+            Self::CurrentTransformer(_) => return false,
+            Self::ControlledSource(_) => return false,
+            Self::ShuntReference(_) => return false,
+            Self::Relay(_) => return false,
+            Self::BenchSupply(c) => c.set_voltage_setpoint(value),
+            Self::PwmController(c) => c.set_reference(value),
+            Self::Vco(c) => c.set_center_frequency(value),
+            Self::FrequencyDivider(c) => c.set_ratio(value.round().max(1.0) as usize),
+            Self::PhaseFrequencyDetector(c) => c.set_pump_current(value),
+            Self::NonOverlappingClock(c) => c.set_frequency(value),
+            Self::TransmissionGate(c) => c.set_on_resistance(value),
+            Self::SampleAndHold(_) => return false,
+            Self::ClockedComparator(c) => c.set_offset(value),
+            Self::NoiseSource(c) => c.set_offset(value),
+            Self::PulseGenerator(c) => c.set_battery_voltage(value),
+            Self::FittedImpedance(_) => return false,
+            Self::Bjt(_) => return false,
+        }
+
+        true
+    }
+
+    /// Returns whether the component shares its static variables with `other`, a clone of it
+    /// (see [`Shared`](crate::components::Shared)).
+    #[cfg(test)]
+    pub(crate) fn shares_parameters(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Resistor(a), Self::Resistor(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Capacitor(a), Self::Capacitor(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Inductor(a), Self::Inductor(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::VoltageSource(a), Self::VoltageSource(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::CurrentSource(a), Self::CurrentSource(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Diode(a), Self::Diode(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Switch(a), Self::Switch(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::CurrentProbe(a), Self::CurrentProbe(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::ElectronicLoad(a), Self::ElectronicLoad(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::PvModule(a), Self::PvModule(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::RandlesCell(a), Self::RandlesCell(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::ThermoelectricModule(a), Self::ThermoelectricModule(b)) => {
+                a.shared.ptr_eq(&b.shared)
+            }
+            (Self::HallSensor(a), Self::HallSensor(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::CurrentTransformer(a), Self::CurrentTransformer(b)) => {
+                a.shared.ptr_eq(&b.shared)
+            }
+            (Self::ControlledSource(a), Self::ControlledSource(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::ShuntReference(a), Self::ShuntReference(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Relay(a), Self::Relay(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::BenchSupply(a), Self::BenchSupply(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::PwmController(a), Self::PwmController(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Vco(a), Self::Vco(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::FrequencyDivider(a), Self::FrequencyDivider(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::PhaseFrequencyDetector(a), Self::PhaseFrequencyDetector(b)) => {
+                a.shared.ptr_eq(&b.shared)
+            }
+            (Self::NonOverlappingClock(a), Self::NonOverlappingClock(b)) => {
+                a.shared.ptr_eq(&b.shared)
+            }
+            (Self::TransmissionGate(a), Self::TransmissionGate(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::SampleAndHold(a), Self::SampleAndHold(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::ClockedComparator(a), Self::ClockedComparator(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::NoiseSource(a), Self::NoiseSource(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::PulseGenerator(a), Self::PulseGenerator(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::FittedImpedance(a), Self::FittedImpedance(b)) => a.shared.ptr_eq(&b.shared),
+            (Self::Bjt(a), Self::Bjt(b)) => a.shared.ptr_eq(&b.shared),
+            _ => false,
         }
     }
 }
@@ -50,3 +490,153 @@ impl From<CurrentSource> for Component {
         Self::CurrentSource(value)
     }
 }
+
+impl From<Diode> for Component {
+    fn from(value: Diode) -> Self {
+        Self::Diode(value)
+    }
+}
+
+impl From<Switch> for Component {
+    fn from(value: Switch) -> Self {
+        Self::Switch(value)
+    }
+}
+
+impl From<CurrentProbe> for Component {
+    fn from(value: CurrentProbe) -> Self {
+        Self::CurrentProbe(value)
+    }
+}
+
+impl From<ElectronicLoad> for Component {
+    fn from(value: ElectronicLoad) -> Self {
+        Self::ElectronicLoad(value)
+    }
+}
+
+impl From<PvModule> for Component {
+    fn from(value: PvModule) -> Self {
+        Self::PvModule(value)
+    }
+}
+
+impl From<RandlesCell> for Component {
+    fn from(value: RandlesCell) -> Self {
+        Self::RandlesCell(value)
+    }
+}
+
+impl From<ThermoelectricModule> for Component {
+    fn from(value: ThermoelectricModule) -> Self {
+        Self::ThermoelectricModule(value)
+    }
+}
+
+impl From<HallSensor> for Component {
+    fn from(value: HallSensor) -> Self {
+        Self::HallSensor(value)
+    }
+}
+
+impl From<CurrentTransformer> for Component {
+    fn from(value: CurrentTransformer) -> Self {
+        Self::CurrentTransformer(value)
+    }
+}
+
+impl From<ControlledSource> for Component {
+    fn from(value: ControlledSource) -> Self {
+        Self::ControlledSource(value)
+    }
+}
+
+impl From<ShuntReference> for Component {
+    fn from(value: ShuntReference) -> Self {
+        Self::ShuntReference(value)
+    }
+}
+
+impl From<Relay> for Component {
+    fn from(value: Relay) -> Self {
+        Self::Relay(value)
+    }
+}
+
+impl From<BenchSupply> for Component {
+    fn from(value: BenchSupply) -> Self {
+        Self::BenchSupply(value)
+    }
+}
+
+impl From<PwmController> for Component {
+    fn from(value: PwmController) -> Self {
+        Self::PwmController(value)
+    }
+}
+
+impl From<Vco> for Component {
+    fn from(value: Vco) -> Self {
+        Self::Vco(value)
+    }
+}
+
+impl From<FrequencyDivider> for Component {
+    fn from(value: FrequencyDivider) -> Self {
+        Self::FrequencyDivider(value)
+    }
+}
+
+impl From<PhaseFrequencyDetector> for Component {
+    fn from(value: PhaseFrequencyDetector) -> Self {
+        Self::PhaseFrequencyDetector(value)
+    }
+}
+
+impl From<NonOverlappingClock> for Component {
+    fn from(value: NonOverlappingClock) -> Self {
+        Self::NonOverlappingClock(value)
+    }
+}
+
+impl From<TransmissionGate> for Component {
+    fn from(value: TransmissionGate) -> Self {
+        Self::TransmissionGate(value)
+    }
+}
+
+impl From<SampleAndHold> for Component {
+    fn from(value: SampleAndHold) -> Self {
+        Self::SampleAndHold(value)
+    }
+}
+
+impl From<ClockedComparator> for Component {
+    fn from(value: ClockedComparator) -> Self {
+        Self::ClockedComparator(value)
+    }
+}
+
+impl From<NoiseSource> for Component {
+    fn from(value: NoiseSource) -> Self {
+        Self::NoiseSource(value)
+    }
+}
+
+impl From<PulseGenerator> for Component {
+    fn from(value: PulseGenerator) -> Self {
+        Self::PulseGenerator(value)
+    }
+}
+
+impl From<FittedImpedance> for Component {
+    fn from(value: FittedImpedance) -> Self {
+        Self::FittedImpedance(value)
+    }
+}
+
+impl From<Bjt> for Component {
+    fn from(value: Bjt) -> Self {
+        Self::Bjt(value)
+    }
+}
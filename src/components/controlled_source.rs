@@ -0,0 +1,398 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// What a [`ControlledSource`] senses and what it outputs, as in the E, F, G and H elements of
+/// SPICE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlledSourceKind {
+    VoltageControlledVoltage,
+    VoltageControlledCurrent,
+    CurrentControlledVoltage,
+    CurrentControlledCurrent,
+}
+
+impl ControlledSourceKind {
+    pub fn is_current_controlled(&self) -> bool {
+        matches!(
+            self,
+            Self::CurrentControlledVoltage | Self::CurrentControlledCurrent
+        )
+    }
+
+    pub fn has_voltage_output(&self) -> bool {
+        matches!(
+            self,
+            Self::VoltageControlledVoltage | Self::CurrentControlledVoltage
+        )
+    }
+}
+
+/// A linear controlled source: its output is the gain times the voltage between the control
+/// nodes, or times the current flowing through a zero volt sense branch between them (into the
+/// positive control node, like a [`CurrentProbe`](crate::components::CurrentProbe)).
+///
+/// Voltage outputs set the voltage of the positive node above the negative node. Current
+/// outputs deliver the current out of the positive node, like a
+/// [`CurrentSource`](crate::components::CurrentSource).
+///
+/// The output can be limited to a range with [`ControlledSource::with_limits`], which makes the
+/// source saturate smoothly (following a tanh) so high gain sources behave as comparators.
+/// Current outputs can also be given a saturation voltage with
+/// [`ControlledSource::with_saturation_voltage`], so they behave like the collector of a
+/// transistor rather than an ideal current source.
+///
+/// With [`ControlledSource::with_hysteresis`] the source instead acts as a Schmitt trigger
+/// whose state is only updated between timesteps, which makes it suitable as a latch.
+#[derive(Clone, PartialEq)]
+pub struct ControlledSource {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    triggered: bool,
+
+    // Computed variables
+    control: f64,
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`ControlledSource`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    kind: ControlledSourceKind,
+    positive_node: usize,
+    negative_node: usize,
+    control_positive_node: usize,
+    control_negative_node: usize,
+    gain: f64,
+    limits: Option<(f64, f64)>,
+    saturation_voltage: Option<f64>,
+    hysteresis: Option<(f64, f64)>,
+}
+
+impl ControlledSource {
+    pub fn new(
+        kind: ControlledSourceKind,
+        positive_node: usize,
+        negative_node: usize,
+        control_positive_node: usize,
+        control_negative_node: usize,
+        gain: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                kind,
+                positive_node,
+                negative_node,
+                control_positive_node,
+                control_negative_node,
+                gain,
+                limits: None,
+                saturation_voltage: None,
+                hysteresis: None,
+            }),
+            triggered: false,
+            control: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Creates a voltage controlled voltage source (SPICE E element).
+    pub fn vcvs(
+        positive_node: usize,
+        negative_node: usize,
+        control_positive_node: usize,
+        control_negative_node: usize,
+        gain: f64,
+    ) -> Self {
+        Self::new(
+            ControlledSourceKind::VoltageControlledVoltage,
+            positive_node,
+            negative_node,
+            control_positive_node,
+            control_negative_node,
+            gain,
+        )
+    }
+
+    /// Creates a voltage controlled current source (SPICE G element).
+    pub fn vccs(
+        positive_node: usize,
+        negative_node: usize,
+        control_positive_node: usize,
+        control_negative_node: usize,
+        transconductance: f64,
+    ) -> Self {
+        Self::new(
+            ControlledSourceKind::VoltageControlledCurrent,
+            positive_node,
+            negative_node,
+            control_positive_node,
+            control_negative_node,
+            transconductance,
+        )
+    }
+
+    /// Creates a current controlled voltage source (SPICE H element).
+    pub fn ccvs(
+        positive_node: usize,
+        negative_node: usize,
+        control_positive_node: usize,
+        control_negative_node: usize,
+        transresistance: f64,
+    ) -> Self {
+        Self::new(
+            ControlledSourceKind::CurrentControlledVoltage,
+            positive_node,
+            negative_node,
+            control_positive_node,
+            control_negative_node,
+            transresistance,
+        )
+    }
+
+    /// Creates a current controlled current source (SPICE F element).
+    pub fn cccs(
+        positive_node: usize,
+        negative_node: usize,
+        control_positive_node: usize,
+        control_negative_node: usize,
+        gain: f64,
+    ) -> Self {
+        Self::new(
+            ControlledSourceKind::CurrentControlledCurrent,
+            positive_node,
+            negative_node,
+            control_positive_node,
+            control_negative_node,
+            gain,
+        )
+    }
+
+    /// Limits the output between `min` and `max`. The output follows
+    /// `mid + half*tanh((gain*x - mid)/half)`, where `mid` and `half` are the middle and half
+    /// the width of the range, so it keeps the gain around the middle of the range.
+    ///
+    /// One of the limits can be infinite, in which case the output stays linear on that side
+    /// and approaches the other limit exponentially past half its value (so that limit must not
+    /// be zero). Inside a feedback loop this converges much more reliably than saturating on
+    /// both sides.
+    pub fn with_limits(mut self, min: f64, max: f64) -> Self {
+        self.shared.limits = Some((min, max));
+        self
+    }
+
+    /// Scales a current output by tanh(v/v_sat), where v is the voltage across the output
+    /// against the direction of the current (from the negative to the positive node), so the
+    /// current collapses as that voltage falls to zero. Below zero the output behaves as a
+    /// conductance of output/v_sat instead. Has no effect on voltage outputs, or while the
+    /// output is negative.
+    pub fn with_saturation_voltage(mut self, saturation_voltage: f64) -> Self {
+        self.shared.saturation_voltage = Some(saturation_voltage);
+        self
+    }
+
+    /// Turns the source into a Schmitt trigger: the output switches to the upper limit once the
+    /// control rises above `high` and back to the lower limit once it falls below `low`,
+    /// keeping its state in between. Without limits the output switches between zero and the
+    /// gain. The output starts low.
+    ///
+    /// The state is only updated at the end of each timestep, delaying the output by a
+    /// timestep, so positive feedback around the source can't make Newton-Raphson hunt between
+    /// two states within a timestep.
+    pub fn with_hysteresis(mut self, low: f64, high: f64) -> Self {
+        self.shared.hysteresis = Some((low, high));
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+        self.shared.control_positive_node = map(self.shared.control_positive_node);
+        self.shared.control_negative_node = map(self.shared.control_negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .positive_node
+            .max(self.shared.negative_node)
+            .max(self.shared.control_positive_node)
+            .max(self.shared.control_negative_node)
+    }
+
+    pub fn get_kind(&self) -> ControlledSourceKind {
+        self.shared.kind
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_control_positive_node(&self) -> usize {
+        self.shared.control_positive_node
+    }
+
+    pub fn get_control_negative_node(&self) -> usize {
+        self.shared.control_negative_node
+    }
+
+    pub fn get_gain(&self) -> f64 {
+        self.shared.gain
+    }
+
+    pub fn set_gain(&mut self, gain: f64) {
+        self.shared.gain = gain;
+    }
+
+    pub fn get_limits(&self) -> Option<(f64, f64)> {
+        self.shared.limits
+    }
+
+    pub fn get_hysteresis(&self) -> Option<(f64, f64)> {
+        self.shared.hysteresis
+    }
+
+    /// Returns whether a Schmitt trigger output is high (see
+    /// [`ControlledSource::with_hysteresis`]).
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// Updates the Schmitt trigger state from the control at the end of a timestep.
+    pub fn set_triggered_from(&mut self, control: f64) {
+        if let Some((low, high)) = self.shared.hysteresis {
+            if control > high {
+                self.triggered = true;
+            } else if control < low {
+                self.triggered = false;
+            }
+        }
+    }
+
+    /// Computes the output for the given control voltage or current, and its derivative with
+    /// respect to it.
+    pub fn evaluate(&self, control: f64) -> (f64, f64) {
+        if self.shared.hysteresis.is_some() {
+            let (low, high) = self.shared.limits.unwrap_or((0.0, self.shared.gain));
+            return (if self.triggered { high } else { low }, 0.0);
+        }
+
+        let linear = self.shared.gain * control;
+        match self.shared.limits {
+            None => (linear, self.shared.gain),
+            Some((min, max)) if min == f64::NEG_INFINITY && max == f64::INFINITY => {
+                (linear, self.shared.gain)
+            }
+            Some((min, f64::INFINITY)) => {
+                let width = min.abs() / 2.0;
+                let knee = min + width;
+                if linear >= knee {
+                    (linear, self.shared.gain)
+                } else {
+                    let e = ((linear - knee) / width).exp();
+                    (min + width * e, self.shared.gain * e)
+                }
+            }
+            Some((f64::NEG_INFINITY, max)) => {
+                let width = max.abs() / 2.0;
+                let knee = max - width;
+                if linear <= knee {
+                    (linear, self.shared.gain)
+                } else {
+                    let e = (-(linear - knee) / width).exp();
+                    (max - width * e, self.shared.gain * e)
+                }
+            }
+            Some((min, max)) => {
+                let mid = (max + min) / 2.0;
+                let half = (max - min) / 2.0;
+                let t = ((linear - mid) / half).tanh();
+                (mid + half * t, self.shared.gain * (1.0 - t * t))
+            }
+        }
+    }
+
+    pub fn get_saturation_voltage(&self) -> Option<f64> {
+        self.shared.saturation_voltage
+    }
+
+    /// Computes the factor a current output is scaled by for the given unscaled output and
+    /// voltage from its negative to its positive node, and its derivative with respect to that
+    /// voltage. Negative outputs aren't scaled, as they don't flow the way the output saturates.
+    pub fn compliance(&self, output: f64, voltage: f64) -> (f64, f64) {
+        match self.shared.saturation_voltage {
+            Some(saturation_voltage) if !self.shared.kind.has_voltage_output() && output > 0.0 => {
+                // Continuing linearly below zero, rather than saturating in reverse, keeps
+                // Newton-Raphson from bouncing between the two saturated states.
+                if voltage < 0.0 {
+                    return (voltage / saturation_voltage, 1.0 / saturation_voltage);
+                }
+                let t = (voltage / saturation_voltage).tanh();
+                (t, (1.0 - t * t) / saturation_voltage)
+            }
+            _ => (1.0, 0.0),
+        }
+    }
+
+    /// Gets the control voltage or current.
+    pub fn get_control(&self) -> f64 {
+        self.control
+    }
+
+    pub fn set_control(&mut self, control: f64) {
+        self.control = control;
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the positive node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    /// Gets the power delivered by the output.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for ControlledSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, x: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_control()
+        )
+    }
+}
+
+impl TryFrom<Component> for ControlledSource {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::ControlledSource(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
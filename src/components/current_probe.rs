@@ -0,0 +1,93 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// An ammeter: a zero volt source between two nodes whose branch current is solved for
+/// directly.
+///
+/// The current is positive when it flows into the positive node, through the probe, and out of
+/// the negative node.
+#[derive(Clone, PartialEq)]
+pub struct CurrentProbe {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    current: f64,
+}
+
+/// The static variables of a [`CurrentProbe`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+}
+
+impl CurrentProbe {
+    pub fn new(positive_node: usize, negative_node: usize) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+            }),
+            current: 0.0,
+        }
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    /// The voltage across an ideal ammeter is always zero.
+    pub fn get_voltage(&self) -> f64 {
+        0.0
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        0.0
+    }
+}
+
+impl Debug for CurrentProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for CurrentProbe {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::CurrentProbe(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,42 +1,117 @@
 use std::fmt::Debug;
 
-use crate::components::Component;
+use nalgebra::Complex;
 
-#[derive(Clone, Copy, PartialEq)]
+use crate::components::{Component, Shared};
+
+#[derive(Clone, PartialEq)]
 pub struct CurrentSource {
     // Static variables
-    positive_node: usize,
-    negative_node: usize,
-    current: f64,
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
 
     // Computed variables
     voltage: f64,
 }
 
+/// The static variables of a [`CurrentSource`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    current: f64,
+    ac_magnitude: f64,
+    ac_phase: f64,
+    ramp_time: Option<f64>,
+}
+
 impl CurrentSource {
     pub fn new(positive_node: usize, negative_node: usize, current: f64) -> Self {
         Self {
-            positive_node,
-            negative_node,
-            current,
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                current,
+                ac_magnitude: 0.0,
+                ac_phase: 0.0,
+                ramp_time: None,
+            }),
+            time: 0.0,
             voltage: 0.0,
         }
     }
 
+    /// Sets the small signal excitation of the source used in AC analysis. The phase is in
+    /// degrees.
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.shared.ac_magnitude = magnitude;
+        self.shared.ac_phase = phase;
+        self
+    }
+
+    /// Gets the small signal excitation of the source used in AC analysis as a phasor.
+    pub fn get_ac_phasor(&self) -> Complex<f64> {
+        Complex::from_polar(self.shared.ac_magnitude, self.shared.ac_phase.to_radians())
+    }
+
+    /// Ramps the source linearly from zero to its current over the given time at the start of the
+    /// simulation (soft start), instead of stepping to it instantly.
+    pub fn with_ramp(mut self, ramp_time: f64) -> Self {
+        self.shared.ramp_time = Some(ramp_time);
+        self
+    }
+
+    pub fn get_ramp_time(&self) -> Option<f64> {
+        self.shared.ramp_time
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the current the source outputs at the given time, taking the ramp into account.
+    pub fn get_current_at(&self, time: f64) -> f64 {
+        match self.shared.ramp_time {
+            Some(ramp_time) if time < ramp_time => self.shared.current * time / ramp_time,
+            _ => self.shared.current,
+        }
+    }
+
+    /// Gets the current the source outputs at present.
+    pub fn get_output_current(&self) -> f64 {
+        self.get_current_at(self.get_time())
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
     pub fn max_node(&self) -> usize {
         self.get_positive_node().max(self.get_negative_node())
     }
 
     pub fn get_positive_node(&self) -> usize {
-        self.positive_node
+        self.shared.positive_node
     }
 
     pub fn get_negative_node(&self) -> usize {
-        self.negative_node
+        self.shared.negative_node
     }
 
     pub fn get_current(&self) -> f64 {
-        self.current
+        self.shared.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.shared.current = current;
     }
 
     pub fn get_voltage(&self) -> f64 {
@@ -48,7 +123,7 @@ impl CurrentSource {
     }
 
     pub fn get_power(&self) -> f64 {
-        self.get_voltage() * self.get_current()
+        self.get_output_current() * self.get_voltage()
     }
 }
 
@@ -58,7 +133,7 @@ impl Debug for CurrentSource {
             f,
             "{{v: {}, i: {}, p: {}}}",
             self.get_voltage(),
-            self.get_current(),
+            self.get_output_current(),
             self.get_power()
         )
     }
@@ -0,0 +1,202 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A current transformer: a single turn primary carrying the sensed current between the
+/// primary nodes, coupled to a secondary winding of `turns` turns between the secondary nodes.
+///
+/// The secondary delivers the primary current divided by the turns ratio out of its positive
+/// node, less the magnetizing current drawn by the magnetizing inductance (referred to the
+/// secondary), which is what makes a current transformer droop at low frequencies and with
+/// large burdens. The burden can be connected externally or built in with
+/// [`CurrentTransformer::with_burden`].
+#[derive(Clone, PartialEq)]
+pub struct CurrentTransformer {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    magnetizing_current: f64,
+
+    // Computed variables
+    primary_voltage: f64,
+    primary_current: f64,
+    secondary_voltage: f64,
+    secondary_current: f64,
+}
+
+/// The static variables of a [`CurrentTransformer`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    primary_positive_node: usize,
+    primary_negative_node: usize,
+    secondary_positive_node: usize,
+    secondary_negative_node: usize,
+    turns: f64,
+    magnetizing_inductance: f64,
+    winding_resistance: f64,
+    burden: Option<f64>,
+}
+
+impl CurrentTransformer {
+    pub fn new(
+        primary_positive_node: usize,
+        primary_negative_node: usize,
+        secondary_positive_node: usize,
+        secondary_negative_node: usize,
+        turns: f64,
+        magnetizing_inductance: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                primary_positive_node,
+                primary_negative_node,
+                secondary_positive_node,
+                secondary_negative_node,
+                turns,
+                magnetizing_inductance,
+                winding_resistance: 0.0,
+                burden: None,
+            }),
+            magnetizing_current: 0.0,
+            primary_voltage: 0.0,
+            primary_current: 0.0,
+            secondary_voltage: 0.0,
+            secondary_current: 0.0,
+        }
+    }
+
+    /// Sets the resistance of the secondary winding.
+    pub fn with_winding_resistance(mut self, resistance: f64) -> Self {
+        self.shared.winding_resistance = resistance;
+        self
+    }
+
+    /// Connects a burden resistor across the secondary nodes.
+    pub fn with_burden(mut self, resistance: f64) -> Self {
+        self.shared.burden = Some(resistance);
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.primary_positive_node = map(self.shared.primary_positive_node);
+        self.shared.primary_negative_node = map(self.shared.primary_negative_node);
+        self.shared.secondary_positive_node = map(self.shared.secondary_positive_node);
+        self.shared.secondary_negative_node = map(self.shared.secondary_negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .primary_positive_node
+            .max(self.shared.primary_negative_node)
+            .max(self.shared.secondary_positive_node)
+            .max(self.shared.secondary_negative_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.primary_positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.primary_negative_node
+    }
+
+    pub fn get_secondary_positive_node(&self) -> usize {
+        self.shared.secondary_positive_node
+    }
+
+    pub fn get_secondary_negative_node(&self) -> usize {
+        self.shared.secondary_negative_node
+    }
+
+    pub fn get_turns(&self) -> f64 {
+        self.shared.turns
+    }
+
+    pub fn get_magnetizing_inductance(&self) -> f64 {
+        self.shared.magnetizing_inductance
+    }
+
+    pub fn get_winding_resistance(&self) -> f64 {
+        self.shared.winding_resistance
+    }
+
+    pub fn get_burden(&self) -> Option<f64> {
+        self.shared.burden
+    }
+
+    pub fn get_magnetizing_current(&self) -> f64 {
+        self.magnetizing_current
+    }
+
+    pub fn set_magnetizing_current(&mut self, current: f64) {
+        self.magnetizing_current = current;
+    }
+
+    /// Gets the voltage across the primary.
+    pub fn get_voltage(&self) -> f64 {
+        self.primary_voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.primary_voltage = voltage;
+    }
+
+    /// Gets the sensed primary current.
+    pub fn get_current(&self) -> f64 {
+        self.primary_current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.primary_current = current;
+    }
+
+    /// Gets the voltage across the secondary nodes.
+    pub fn get_secondary_voltage(&self) -> f64 {
+        self.secondary_voltage
+    }
+
+    pub fn set_secondary_voltage(&mut self, voltage: f64) {
+        self.secondary_voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the positive secondary node, including the current
+    /// through the built in burden.
+    pub fn get_secondary_current(&self) -> f64 {
+        self.secondary_current
+    }
+
+    pub fn set_secondary_current(&mut self, current: f64) {
+        self.secondary_current = current;
+    }
+
+    /// Gets the power drawn from the primary circuit.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for CurrentTransformer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, vs: {}, is: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_secondary_voltage(),
+            self.get_secondary_current()
+        )
+    }
+}
+
+impl TryFrom<Component> for CurrentTransformer {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::CurrentTransformer(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Thermal voltage kT/q at 300K.
+const THERMAL_VOLTAGE: f64 = 0.025852;
+
+/// Maximum exponent the Shockley equation is evaluated at. Past this point the exponential is
+/// extended linearly so that wild Newton-Raphson guesses don't overflow.
+const MAX_EXPONENT: f64 = 40.0;
+
+/// Resistance of an ideal diode when it is forward biased.
+const IDEAL_ON_RESISTANCE: f64 = 1e-3;
+
+/// Conductance of an ideal diode when it is reverse biased.
+const IDEAL_OFF_CONDUCTANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiodeModel {
+    /// A piecewise linear diode that conducts with a very small resistance when forward biased and
+    /// blocks when reverse biased.
+    Ideal,
+    /// A diode following the Shockley diode equation i = Is*(exp(v/(n*Vt)) - 1).
+    Shockley {
+        saturation_current: f64,
+        emission_coefficient: f64,
+    },
+}
+
+impl DiodeModel {
+    /// Computes the Taylor coefficients [g1, g2, g3] of the current around the given voltage,
+    /// i(v + dv) = i(v) + g1*dv + g2*dv^2 + g3*dv^3 + ...
+    pub fn taylor_coefficients(&self, voltage: f64) -> [f64; 3] {
+        match *self {
+            Self::Ideal => [self.evaluate(voltage).1, 0.0, 0.0],
+            Self::Shockley {
+                saturation_current,
+                emission_coefficient,
+            } => {
+                let nvt = emission_coefficient * THERMAL_VOLTAGE;
+                if voltage / nvt > MAX_EXPONENT {
+                    return [self.evaluate(voltage).1, 0.0, 0.0];
+                }
+
+                let g1 = saturation_current * (voltage / nvt).exp() / nvt;
+                [g1, g1 / (2.0 * nvt), g1 / (6.0 * nvt * nvt)]
+            }
+        }
+    }
+
+    /// Limits the voltage proposed by a Newton-Raphson iteration from the voltage of the
+    /// previous one (SPICE pnjlim): above the critical voltage, where the current turns
+    /// exponential, a large step is replaced by a logarithmic one, so the junction creeps up
+    /// its exponential instead of overshooting it. The ideal diode isn't limited.
+    pub fn limit_voltage(&self, proposed: f64, previous: f64) -> f64 {
+        let Self::Shockley {
+            saturation_current,
+            emission_coefficient,
+        } = *self
+        else {
+            return proposed;
+        };
+
+        let nvt = emission_coefficient * THERMAL_VOLTAGE;
+        let critical = nvt * (nvt / (2f64.sqrt() * saturation_current)).ln();
+        if proposed <= critical || (proposed - previous).abs() <= 2.0 * nvt {
+            return proposed;
+        }
+
+        if previous > 0.0 {
+            let arg = 1.0 + (proposed - previous) / nvt;
+            if arg > 0.0 {
+                previous + nvt * arg.ln()
+            } else {
+                critical
+            }
+        } else {
+            nvt * (proposed / nvt).ln()
+        }
+    }
+
+    /// Computes the current through the diode and its derivative with respect to the voltage
+    /// across it.
+    pub fn evaluate(&self, voltage: f64) -> (f64, f64) {
+        match *self {
+            Self::Ideal => {
+                if voltage > 0.0 {
+                    (voltage / IDEAL_ON_RESISTANCE, 1.0 / IDEAL_ON_RESISTANCE)
+                } else {
+                    (voltage * IDEAL_OFF_CONDUCTANCE, IDEAL_OFF_CONDUCTANCE)
+                }
+            }
+            Self::Shockley {
+                saturation_current,
+                emission_coefficient,
+            } => {
+                let nvt = emission_coefficient * THERMAL_VOLTAGE;
+                let exponent = voltage / nvt;
+
+                if exponent > MAX_EXPONENT {
+                    let e = MAX_EXPONENT.exp();
+                    (
+                        saturation_current * (e * (1.0 + exponent - MAX_EXPONENT) - 1.0),
+                        saturation_current * e / nvt,
+                    )
+                } else {
+                    let e = exponent.exp();
+                    (saturation_current * (e - 1.0), saturation_current * e / nvt)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Diode {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`Diode`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    model: DiodeModel,
+}
+
+impl Diode {
+    /// Creates a new diode conducting from the positive node (anode) to the negative node
+    /// (cathode).
+    pub fn new(positive_node: usize, negative_node: usize, model: DiodeModel) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                model,
+            }),
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_model(&self) -> DiodeModel {
+        self.shared.model
+    }
+
+    pub fn set_model(&mut self, model: DiodeModel) {
+        self.shared.model = model;
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for Diode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for Diode {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::Diode(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Maximum number of points of a piecewise linear [`Setpoint`].
+pub const MAX_PWL_POINTS: usize = 16;
+
+/// How the setpoint of a value changes over time.
+// The PWL points are stored inline so components stay Copy.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setpoint {
+    Constant(f64),
+    /// Steps from `initial` to `pulsed` after `delay`, with linear edges, staying there for
+    /// `width` and repeating every `period` (or once if the period is zero), like a SPICE PULSE.
+    Pulse {
+        initial: f64,
+        pulsed: f64,
+        delay: f64,
+        rise_time: f64,
+        fall_time: f64,
+        width: f64,
+        period: f64,
+    },
+    /// Linear interpolation between (time, value) points, holding the first and last values
+    /// outside them. Stored inline so components stay `Copy`; create with [`Setpoint::pwl`].
+    Pwl {
+        points: [(f64, f64); MAX_PWL_POINTS],
+        len: usize,
+    },
+}
+
+impl Setpoint {
+    /// Creates a piecewise linear setpoint from points sorted by time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no points or more than [`MAX_PWL_POINTS`].
+    pub fn pwl(points: &[(f64, f64)]) -> Self {
+        assert!(
+            !points.is_empty() && points.len() <= MAX_PWL_POINTS,
+            "A PWL setpoint needs between 1 and {MAX_PWL_POINTS} points"
+        );
+
+        let mut stored = [(0.0, 0.0); MAX_PWL_POINTS];
+        stored[..points.len()].copy_from_slice(points);
+        Self::Pwl {
+            points: stored,
+            len: points.len(),
+        }
+    }
+
+    pub fn value_at(&self, time: f64) -> f64 {
+        match *self {
+            Self::Constant(value) => value,
+            Self::Pulse {
+                initial,
+                pulsed,
+                delay,
+                rise_time,
+                fall_time,
+                width,
+                period,
+            } => {
+                if time < delay {
+                    return initial;
+                }
+
+                let mut t = time - delay;
+                if period > 0.0 {
+                    t %= period;
+                }
+
+                let fraction = if t < rise_time {
+                    t / rise_time
+                } else if t < rise_time + width {
+                    1.0
+                } else if t < rise_time + width + fall_time {
+                    1.0 - (t - rise_time - width) / fall_time
+                } else {
+                    0.0
+                };
+                initial + (pulsed - initial) * fraction
+            }
+            Self::Pwl { points, len } => {
+                let points = &points[..len];
+                let k = points.partition_point(|&(t, _)| t <= time);
+                if k == 0 {
+                    return points[0].1;
+                }
+                if k == len {
+                    return points[len - 1].1;
+                }
+
+                let ((t0, v0), (t1, v1)) = (points[k - 1], points[k]);
+                v0 + (v1 - v0) * (time - t0) / (t1 - t0)
+            }
+        }
+    }
+}
+
+/// What the setpoint of an [`ElectronicLoad`] regulates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Sinks the setpoint in amps.
+    ConstantCurrent,
+    /// Sinks the setpoint in watts, drawing more current as the voltage falls.
+    ConstantPower,
+    /// Behaves as a resistor of the setpoint in ohms.
+    ConstantResistance,
+}
+
+/// A programmable electronic load for testing the transient response of power supplies.
+///
+/// Current flows into the positive node, through the load, and out of the negative node. In
+/// constant power mode the current is P/v, which goes to infinity as the voltage collapses, so
+/// below the dropout voltage the load turns into the resistance drawing the setpoint power at
+/// the dropout voltage, as real loads do. A constant power load on a source with some internal
+/// resistance has two operating points, so like a real load its setpoint should be slewed up from
+/// zero, or the simulation may settle on the collapsed one.
+#[derive(Clone, PartialEq)]
+pub struct ElectronicLoad {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of an [`ElectronicLoad`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    mode: LoadMode,
+    setpoint: Setpoint,
+    dropout_voltage: f64,
+}
+
+impl ElectronicLoad {
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        mode: LoadMode,
+        setpoint: Setpoint,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                mode,
+                setpoint,
+                dropout_voltage: 0.1,
+            }),
+            time: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Sets the voltage below which a constant power load acts as a resistance (0.1V by
+    /// default).
+    pub fn with_dropout_voltage(mut self, dropout_voltage: f64) -> Self {
+        self.shared.dropout_voltage = dropout_voltage;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_mode(&self) -> LoadMode {
+        self.shared.mode
+    }
+
+    pub fn get_setpoint(&self) -> Setpoint {
+        self.shared.setpoint
+    }
+
+    pub fn set_setpoint(&mut self, setpoint: Setpoint) {
+        self.shared.setpoint = setpoint;
+    }
+
+    pub fn get_dropout_voltage(&self) -> f64 {
+        self.shared.dropout_voltage
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the current drawn at the given voltage and time and its derivative with respect to
+    /// the voltage.
+    pub fn evaluate(&self, voltage: f64, time: f64) -> (f64, f64) {
+        let setpoint = self.shared.setpoint.value_at(time);
+        match self.shared.mode {
+            LoadMode::ConstantCurrent => (setpoint, 0.0),
+            LoadMode::ConstantResistance => (voltage / setpoint, 1.0 / setpoint),
+            LoadMode::ConstantPower => {
+                if voltage.abs() < self.shared.dropout_voltage {
+                    let g = setpoint / (self.shared.dropout_voltage * self.shared.dropout_voltage);
+                    (g * voltage, g)
+                } else {
+                    (setpoint / voltage, -setpoint / (voltage * voltage))
+                }
+            }
+        }
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for ElectronicLoad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for ElectronicLoad {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::ElectronicLoad(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,595 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use nalgebra::{Complex, DMatrix, DVector};
+
+use crate::components::{Component, Shared};
+
+/// Maximum order (number of poles) of a [`RationalAdmittance`].
+pub const MAX_FITTED_ORDER: usize = 8;
+
+/// Number of pole relocation iterations of the vector fitting.
+const FIT_ITERATIONS: usize = 10;
+
+/// Why fitting a [`RationalAdmittance`] to measurements failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FitError {
+    /// The order is zero or above [`MAX_FITTED_ORDER`].
+    InvalidOrder { order: usize },
+    /// There are fewer measurements than unknowns of the fit, each measurement giving two.
+    TooFewPoints { points: usize, unknowns: usize },
+    /// The measurements are inconsistent (mismatched lengths, nonpositive frequencies or
+    /// magnitudes, values that aren't finite) or the least squares problem couldn't be solved.
+    InvalidData,
+}
+
+/// A rational approximation of the admittance of a two terminal network,
+/// Y(s) = d + s*e + sum of r/(s - p) over its poles, each complex pole standing for itself and
+/// its conjugate.
+///
+/// Fitted to measurements by vector fitting (Gustavsen and Semlyen): starting from complex poles
+/// spread over the measured band, each iteration fits the measurements scaled by a rational
+/// weighting function sharing the poles, whose zeros become the poles of the next iteration.
+/// Unstable poles are reflected into the left half plane. With the poles settled, the residues,
+/// d and e are fitted by linear least squares, relative to the magnitude of each measurement.
+///
+/// A fit can come out slightly active (negative conductance at some frequencies) between
+/// measurements or outside of the band, which can make a transient simulation grow without
+/// bound. The fit is made passive by clipping a negative e to zero and raising d just enough
+/// for the real part of the admittance to be nonnegative at every frequency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RationalAdmittance {
+    constant: f64,
+    proportional: f64,
+    poles: [Complex<f64>; MAX_FITTED_ORDER],
+    residues: [Complex<f64>; MAX_FITTED_ORDER],
+    num_terms: usize,
+}
+
+impl RationalAdmittance {
+    /// Fits an admittance of the given order to impedances measured at the given frequencies
+    /// in hertz and makes it passive.
+    pub fn fit(
+        frequencies: &[f64],
+        impedances: &[Complex<f64>],
+        order: usize,
+    ) -> Result<Self, FitError> {
+        if order == 0 || order > MAX_FITTED_ORDER {
+            return Err(FitError::InvalidOrder { order });
+        }
+        let unknowns = 2 * order + 2;
+        if frequencies.len() != impedances.len()
+            || frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0)
+            || impedances
+                .iter()
+                .any(|z| !z.re.is_finite() || !z.im.is_finite() || z.norm() == 0.0)
+        {
+            return Err(FitError::InvalidData);
+        }
+        if 2 * frequencies.len() < unknowns {
+            return Err(FitError::TooFewPoints {
+                points: frequencies.len(),
+                unknowns,
+            });
+        }
+
+        let samples: Vec<(Complex<f64>, Complex<f64>)> = frequencies
+            .iter()
+            .zip(impedances)
+            .map(|(&f, &z)| (Complex::new(0.0, 2.0 * PI * f), 1.0 / z))
+            .collect();
+
+        let mut poles = Self::starting_poles(frequencies, order);
+        for _ in 0..FIT_ITERATIONS {
+            poles = Self::relocate(&samples, &poles)?;
+        }
+
+        let mut fit = Self::identify(&samples, &poles)?;
+        fit.enforce_passivity(frequencies);
+        Ok(fit)
+    }
+
+    /// Fits an admittance to impedance magnitudes in ohms and phases in degrees measured at the
+    /// given frequencies in hertz, as from an impedance analyzer.
+    pub fn from_measurements(
+        frequencies: &[f64],
+        magnitudes: &[f64],
+        phases: &[f64],
+        order: usize,
+    ) -> Result<Self, FitError> {
+        if magnitudes.len() != phases.len() {
+            return Err(FitError::InvalidData);
+        }
+        let impedances: Vec<Complex<f64>> = magnitudes
+            .iter()
+            .zip(phases)
+            .map(|(&m, &phase)| Complex::from_polar(m, phase.to_radians()))
+            .collect();
+        Self::fit(frequencies, &impedances, order)
+    }
+
+    /// Gets the constant conductance d.
+    pub fn get_constant(&self) -> f64 {
+        self.constant
+    }
+
+    /// Gets the capacitance e multiplying s.
+    pub fn get_proportional(&self) -> f64 {
+        self.proportional
+    }
+
+    /// Gets the poles and residues, complex poles having a positive imaginary part and standing
+    /// for a conjugate pair.
+    pub fn get_terms(&self) -> impl Iterator<Item = (Complex<f64>, Complex<f64>)> + '_ {
+        self.poles[..self.num_terms]
+            .iter()
+            .copied()
+            .zip(self.residues[..self.num_terms].iter().copied())
+    }
+
+    pub fn admittance(&self, omega: f64) -> Complex<f64> {
+        let s = Complex::new(0.0, omega);
+        let mut y = Complex::new(self.constant, omega * self.proportional);
+        for (p, r) in self.get_terms() {
+            y += r / (s - p);
+            if p.im > 0.0 {
+                y += r.conj() / (s - p.conj());
+            }
+        }
+        y
+    }
+
+    pub fn impedance(&self, omega: f64) -> Complex<f64> {
+        1.0 / self.admittance(omega)
+    }
+
+    /// Gets the largest error of the fitted impedance relative to the magnitude of the
+    /// impedances measured at the given frequencies in hertz.
+    pub fn max_relative_error(&self, frequencies: &[f64], impedances: &[Complex<f64>]) -> f64 {
+        frequencies
+            .iter()
+            .zip(impedances)
+            .map(|(&f, &z)| (self.impedance(2.0 * PI * f) - z).norm() / z.norm())
+            .fold(0.0, f64::max)
+    }
+
+    /// Complex poles with imaginary parts log spaced over the band and small real parts, as
+    /// recommended for vector fitting, plus a real pole in the middle of the band for odd orders.
+    fn starting_poles(frequencies: &[f64], order: usize) -> Vec<Complex<f64>> {
+        let min = 2.0 * PI * frequencies.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = 2.0 * PI * frequencies.iter().copied().fold(0.0, f64::max);
+        let pairs = order / 2;
+
+        let mut poles: Vec<Complex<f64>> = (0..pairs)
+            .map(|k| {
+                let fraction = if pairs > 1 {
+                    k as f64 / (pairs - 1) as f64
+                } else {
+                    0.5
+                };
+                let beta = min * (max / min).powf(fraction);
+                Complex::new(-beta / 100.0, beta)
+            })
+            .collect();
+        if order % 2 == 1 {
+            poles.push(Complex::from(-(min * max).sqrt()));
+        }
+        poles
+    }
+
+    /// Evaluates the real basis functions of the poles at s: 1/(s - p) for a real pole, and
+    /// 1/(s - p) + 1/(s - p*) and j/(s - p) - j/(s - p*) for a complex pair, whose coefficients
+    /// are the real and imaginary parts of the residue.
+    fn basis(poles: &[Complex<f64>], s: Complex<f64>) -> Vec<Complex<f64>> {
+        let mut basis = Vec::with_capacity(2 * poles.len());
+        for &p in poles {
+            if p.im == 0.0 {
+                basis.push(1.0 / (s - p));
+            } else {
+                let (a, b) = (1.0 / (s - p), 1.0 / (s - p.conj()));
+                basis.push(a + b);
+                basis.push(Complex::new(0.0, 1.0) * (a - b));
+            }
+        }
+        basis
+    }
+
+    /// Solves the linear least squares problem of the complex equations, each row of
+    /// coefficients equal to its right hand side, splitting them into their real and imaginary
+    /// parts. Each row is weighted by the inverse of the magnitude of its admittance and each
+    /// column scaled to unit norm, for the fit to be relative over the whole band.
+    fn least_squares(
+        rows: &[(Vec<Complex<f64>>, Complex<f64>)],
+        weights: &[f64],
+    ) -> Result<DVector<f64>, FitError> {
+        let columns = rows[0].0.len();
+        let mut a = DMatrix::zeros(2 * rows.len(), columns);
+        let mut b = DVector::zeros(2 * rows.len());
+        for (i, ((coefficients, rhs), weight)) in rows.iter().zip(weights).enumerate() {
+            for (j, c) in coefficients.iter().enumerate() {
+                a[(2 * i, j)] = c.re * weight;
+                a[(2 * i + 1, j)] = c.im * weight;
+            }
+            b[2 * i] = rhs.re * weight;
+            b[2 * i + 1] = rhs.im * weight;
+        }
+
+        let norms: Vec<f64> = a
+            .column_iter()
+            .map(|c| {
+                let norm = c.norm();
+                if norm > 0.0 { norm } else { 1.0 }
+            })
+            .collect();
+        for (j, norm) in norms.iter().enumerate() {
+            a.column_mut(j).scale_mut(1.0 / norm);
+        }
+
+        let mut x = a
+            .svd(true, true)
+            .solve(&b, 1e-14)
+            .map_err(|_| FitError::InvalidData)?;
+        for (j, norm) in norms.iter().enumerate() {
+            x[j] /= norm;
+        }
+        if x.iter().all(|v| v.is_finite()) {
+            Ok(x)
+        } else {
+            Err(FitError::InvalidData)
+        }
+    }
+
+    fn weights(samples: &[(Complex<f64>, Complex<f64>)]) -> Vec<f64> {
+        samples.iter().map(|(_, y)| 1.0 / y.norm()).collect()
+    }
+
+    /// One vector fitting iteration: fits sigma*Y = sum(c*phi) + d + s*e with
+    /// sigma = 1 + sum(c'*phi) and returns the zeros of sigma, the eigenvalues of A - b*c'^T for
+    /// the state space realization (A, b) of the basis functions, as the new poles.
+    fn relocate(
+        samples: &[(Complex<f64>, Complex<f64>)],
+        poles: &[Complex<f64>],
+    ) -> Result<Vec<Complex<f64>>, FitError> {
+        let rows: Vec<(Vec<Complex<f64>>, Complex<f64>)> = samples
+            .iter()
+            .map(|&(s, y)| {
+                let basis = Self::basis(poles, s);
+                let mut coefficients = basis.clone();
+                coefficients.push(Complex::from(1.0));
+                coefficients.push(s);
+                coefficients.extend(basis.iter().map(|phi| -y * phi));
+                (coefficients, y)
+            })
+            .collect();
+        let x = Self::least_squares(&rows, &Self::weights(samples))?;
+
+        let order = rows[0].0.len() / 2 - 1;
+        let sigma = x.rows(order + 2, order);
+        let mut a = DMatrix::zeros(order, order);
+        let mut b = DVector::zeros(order);
+        let mut k = 0;
+        for &p in poles {
+            if p.im == 0.0 {
+                a[(k, k)] = p.re;
+                b[k] = 1.0;
+                k += 1;
+            } else {
+                a[(k, k)] = p.re;
+                a[(k, k + 1)] = p.im;
+                a[(k + 1, k)] = -p.im;
+                a[(k + 1, k + 1)] = p.re;
+                b[k] = 2.0;
+                k += 2;
+            }
+        }
+        let zeros = (a - b * sigma.transpose()).complex_eigenvalues();
+
+        let mut relocated = Vec::with_capacity(poles.len());
+        for z in zeros.iter() {
+            // Unstable poles are reflected into the left half plane.
+            let z = Complex::new(-z.re.abs(), z.im);
+            if z.im.abs() <= 1e-9 * z.norm() {
+                relocated.push(Complex::from(z.re));
+            } else if z.im > 0.0 {
+                relocated.push(z);
+            }
+        }
+        if relocated
+            .iter()
+            .all(|p| p.re.is_finite() && p.im.is_finite())
+        {
+            Ok(relocated)
+        } else {
+            Err(FitError::InvalidData)
+        }
+    }
+
+    /// Fits the residues, d and e for the settled poles.
+    fn identify(
+        samples: &[(Complex<f64>, Complex<f64>)],
+        poles: &[Complex<f64>],
+    ) -> Result<Self, FitError> {
+        let rows: Vec<(Vec<Complex<f64>>, Complex<f64>)> = samples
+            .iter()
+            .map(|&(s, y)| {
+                let mut coefficients = Self::basis(poles, s);
+                coefficients.push(Complex::from(1.0));
+                coefficients.push(s);
+                (coefficients, y)
+            })
+            .collect();
+        let x = Self::least_squares(&rows, &Self::weights(samples))?;
+
+        let mut fit = Self {
+            constant: 0.0,
+            proportional: 0.0,
+            poles: [Complex::from(0.0); MAX_FITTED_ORDER],
+            residues: [Complex::from(0.0); MAX_FITTED_ORDER],
+            num_terms: poles.len(),
+        };
+        let mut k = 0;
+        for (term, &p) in poles.iter().enumerate() {
+            fit.poles[term] = p;
+            if p.im == 0.0 {
+                fit.residues[term] = Complex::from(x[k]);
+                k += 1;
+            } else {
+                fit.residues[term] = Complex::new(x[k], x[k + 1]);
+                k += 2;
+            }
+        }
+        fit.constant = x[k];
+        fit.proportional = x[k + 1];
+        Ok(fit)
+    }
+
+    /// Makes the admittance passive: a negative capacitance e is clipped to zero, and d raised
+    /// by the most negative real part of the admittance, checked at DC, at the frequencies of
+    /// the poles and log spaced from a hundredth of the lowest to a hundred times the highest
+    /// measured frequency.
+    fn enforce_passivity(&mut self, frequencies: &[f64]) {
+        self.proportional = self.proportional.max(0.0);
+
+        let min = 2.0 * PI * frequencies.iter().copied().fold(f64::INFINITY, f64::min) / 100.0;
+        let max = 2.0 * PI * frequencies.iter().copied().fold(0.0, f64::max) * 100.0;
+        let points = 2000;
+        let mut omegas: Vec<f64> = (0..=points)
+            .map(|k| min * (max / min).powf(k as f64 / points as f64))
+            .collect();
+        omegas.push(0.0);
+        omegas.extend(self.get_terms().map(|(p, _)| p.im));
+
+        let worst = omegas
+            .iter()
+            .map(|&omega| self.admittance(omega).re)
+            .fold(f64::INFINITY, f64::min)
+            // At infinite frequency only d is left.
+            .min(self.constant);
+        if worst < 0.0 {
+            self.constant -= worst;
+        }
+    }
+}
+
+/// A two terminal element with the impedance of measurements, fitted as a
+/// [`RationalAdmittance`]: a motor winding, an electrode, an antenna feed or any other network
+/// known only from impedance analyzer sweeps.
+///
+/// AC analysis uses the fitted admittance. In transient simulation each real pole of the fit is
+/// a state x with dx/dt = p*x + v contributing the current r*x, and each complex pair one
+/// complex state contributing 2*Re(r*x), all integrated with backward Euler, and e is a
+/// capacitor.
+#[derive(Clone, PartialEq)]
+pub struct FittedImpedance {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    states: [Complex<f64>; MAX_FITTED_ORDER],
+    previous_voltage: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`FittedImpedance`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    model: RationalAdmittance,
+}
+
+impl FittedImpedance {
+    pub fn new(positive_node: usize, negative_node: usize, model: RationalAdmittance) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                model,
+            }),
+            states: [Complex::from(0.0); MAX_FITTED_ORDER],
+            previous_voltage: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Creates the element in one call from impedance magnitudes in ohms and phases in degrees
+    /// measured at the given frequencies in hertz, fitted with the given order.
+    pub fn from_measurements(
+        positive_node: usize,
+        negative_node: usize,
+        frequencies: &[f64],
+        magnitudes: &[f64],
+        phases: &[f64],
+        order: usize,
+    ) -> Result<Self, FitError> {
+        let model = RationalAdmittance::from_measurements(frequencies, magnitudes, phases, order)?;
+        Ok(Self::new(positive_node, negative_node, model))
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_model(&self) -> RationalAdmittance {
+        self.shared.model
+    }
+
+    /// Gets the states of the poles of the fit, in the order of
+    /// [`RationalAdmittance::get_terms`].
+    pub fn get_states(&self) -> [Complex<f64>; MAX_FITTED_ORDER] {
+        self.states
+    }
+
+    pub fn set_states(&mut self, states: [Complex<f64>; MAX_FITTED_ORDER]) {
+        self.states = states;
+    }
+
+    /// Gets the voltage at the end of the previous timestep, which the capacitance e is
+    /// integrated from.
+    pub fn get_previous_voltage(&self) -> f64 {
+        self.previous_voltage
+    }
+
+    pub fn set_previous_voltage(&mut self, voltage: f64) {
+        self.previous_voltage = voltage;
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for FittedImpedance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for FittedImpedance {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::FittedImpedance(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    /// A resonant network: 10 ohms in parallel with 1 ohm, 1mH and 1uF in series.
+    fn resonant(omega: f64) -> Complex<f64> {
+        let s = Complex::new(0.0, omega);
+        let series = 1.0 + s * 1e-3 + 1.0 / (s * 1e-6);
+        1.0 / (0.1 + 1.0 / series)
+    }
+
+    fn band() -> Vec<f64> {
+        (0..=60)
+            .map(|k| 10f64.powf(2.0 + k as f64 / 20.0))
+            .collect()
+    }
+
+    #[test]
+    fn fits_rational_network() {
+        let frequencies = band();
+        let impedances: Vec<Complex<f64>> =
+            frequencies.iter().map(|f| resonant(2.0 * PI * f)).collect();
+        let fit = RationalAdmittance::fit(&frequencies, &impedances, 2).unwrap();
+
+        assert!(fit.max_relative_error(&frequencies, &impedances) < 1e-6);
+        let (pole, _) = fit.get_terms().next().unwrap();
+        // The poles of 1/(1 + s*1e-3 + 1/(s*1e-6)) are -500 +- j*sqrt(1e9 - 500^2).
+        assert_relative_eq!(pole.re, -500.0, max_relative = 1e-6);
+        assert_relative_eq!(
+            pole.im,
+            (1e9 - 500.0f64.powi(2)).sqrt(),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(fit.get_constant(), 0.1, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn enforces_passivity() {
+        // A constant phase element isn't rational, so a low order fit oscillates around it and
+        // has to be made passive.
+        let frequencies = band();
+        let impedances: Vec<Complex<f64>> = frequencies
+            .iter()
+            .map(|f| 1.0 / (1e-3 * Complex::new(0.0, 2.0 * PI * f).powf(0.7)))
+            .collect();
+        let fit = RationalAdmittance::fit(&frequencies, &impedances, 8).unwrap();
+
+        assert!(fit.max_relative_error(&frequencies, &impedances) < 5e-2);
+        assert!(fit.get_terms().all(|(p, _)| p.re < 0.0));
+        for k in 0..=1000 {
+            let omega = 2.0 * PI * 10f64.powf(k as f64 / 100.0 - 1.0);
+            assert!(fit.admittance(omega).re >= 0.0);
+        }
+    }
+
+    #[test]
+    fn invalid_fits() {
+        let frequencies = band();
+        let impedances = vec![Complex::from(1.0); frequencies.len()];
+        assert_eq!(
+            RationalAdmittance::fit(&frequencies, &impedances, 0),
+            Err(FitError::InvalidOrder { order: 0 })
+        );
+        assert_eq!(
+            RationalAdmittance::fit(&frequencies[..4], &impedances[..4], 6),
+            Err(FitError::TooFewPoints {
+                points: 4,
+                unknowns: 14
+            })
+        );
+        assert_eq!(
+            RationalAdmittance::fit(&frequencies, &impedances[1..], 2),
+            Err(FitError::InvalidData)
+        );
+    }
+}
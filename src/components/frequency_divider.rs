@@ -0,0 +1,187 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A behavioral frequency divider: counts the edges of the square wave on its input node and
+/// drives a square wave at the input frequency over the division ratio out of the output node,
+/// e.g. in the feedback path of a PLL.
+///
+/// The input is high while its voltage above the ground node is above the threshold. The
+/// output rises with every ratio-th rising edge of the input and stays high for ratio input
+/// half cycles. It is driven high (to the output voltage, through the output resistance) or low
+/// (to the ground node).
+///
+/// The divider is updated between timesteps from the input voltage the timestep ended with, so
+/// the output lags the input by a timestep.
+#[derive(Clone, PartialEq)]
+pub struct FrequencyDivider {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    input_high: bool,
+    count: usize,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`FrequencyDivider`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    ratio: usize,
+    threshold: f64,
+    output_voltage: f64,
+    output_resistance: f64,
+}
+
+impl FrequencyDivider {
+    /// Creates a divider with a 0.5V input threshold, driving its output to 1V through 1Ω. The
+    /// output starts low, rising with the first rising edge of the input.
+    pub fn new(input_node: usize, output_node: usize, ground_node: usize, ratio: usize) -> Self {
+        let ratio = ratio.max(1);
+        Self {
+            shared: Shared::new(Parameters {
+                input_node,
+                output_node,
+                ground_node,
+                ratio,
+                threshold: 0.5,
+                output_voltage: 1.0,
+                output_resistance: 1.0,
+            }),
+            input_high: false,
+            count: 2 * ratio - 1,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.shared.threshold = threshold;
+        self
+    }
+
+    /// Sets the voltage the output is driven to while high and the resistance it is driven
+    /// through.
+    pub fn with_output_drive(mut self, voltage: f64, resistance: f64) -> Self {
+        self.shared.output_voltage = voltage;
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.input_node = map(self.shared.input_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .input_node
+            .max(self.shared.output_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the output node.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_input_node(&self) -> usize {
+        self.shared.input_node
+    }
+
+    pub fn get_ratio(&self) -> usize {
+        self.shared.ratio
+    }
+
+    /// Sets the division ratio, restarting the count with the output low.
+    pub fn set_ratio(&mut self, ratio: usize) {
+        self.shared.ratio = ratio.max(1);
+        self.count = 2 * self.shared.ratio - 1;
+    }
+
+    pub fn get_threshold(&self) -> f64 {
+        self.shared.threshold
+    }
+
+    pub fn get_output_drive(&self) -> (f64, f64) {
+        (self.shared.output_voltage, self.shared.output_resistance)
+    }
+
+    /// Returns whether the output is driven high.
+    pub fn is_high(&self) -> bool {
+        self.count < self.shared.ratio
+    }
+
+    /// Counts the input edge, if any, from the input voltage a timestep ended with.
+    pub fn advance(&mut self, input_voltage: f64) {
+        let input_high = input_voltage > self.shared.threshold;
+        if input_high != self.input_high {
+            self.input_high = input_high;
+            // Counting input half cycles, the output is high for the first ratio of every
+            // 2*ratio, the count wrapping around on a rising edge.
+            self.count = if input_high {
+                (self.count + 1) % (2 * self.shared.ratio)
+            } else {
+                self.count + 1
+            };
+        }
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the output node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for FrequencyDivider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, count: {}, high: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.count,
+            self.is_high()
+        )
+    }
+}
+
+impl TryFrom<Component> for FrequencyDivider {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::FrequencyDivider(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A Hall effect current sensor, such as an ACS712: the sensed current flows through a low
+/// resistance primary conductor between the primary nodes, and the output node is driven to
+/// `offset + sensitivity*i_primary` volts above the reference node through the output
+/// resistance.
+///
+/// The primary current is positive when it flows into the positive primary node, the same as
+/// a [`CurrentProbe`](crate::components::CurrentProbe).
+#[derive(Clone, PartialEq)]
+pub struct HallSensor {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    primary_voltage: f64,
+    primary_current: f64,
+    output_voltage: f64,
+}
+
+/// The static variables of a [`HallSensor`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    primary_positive_node: usize,
+    primary_negative_node: usize,
+    output_node: usize,
+    reference_node: usize,
+    sensitivity: f64,
+    offset: f64,
+    primary_resistance: f64,
+    output_resistance: f64,
+}
+
+impl HallSensor {
+    /// Creates a sensor with the given sensitivity in V/A, no offset, an ideal output and a
+    /// 1mΩ primary.
+    pub fn new(
+        primary_positive_node: usize,
+        primary_negative_node: usize,
+        output_node: usize,
+        reference_node: usize,
+        sensitivity: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                primary_positive_node,
+                primary_negative_node,
+                output_node,
+                reference_node,
+                sensitivity,
+                offset: 0.0,
+                primary_resistance: 1e-3,
+                output_resistance: 0.0,
+            }),
+            primary_voltage: 0.0,
+            primary_current: 0.0,
+            output_voltage: 0.0,
+        }
+    }
+
+    /// Sets the output voltage at zero current, e.g. half the supply for bidirectional sensors.
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.shared.offset = offset;
+        self
+    }
+
+    pub fn with_primary_resistance(mut self, resistance: f64) -> Self {
+        self.shared.primary_resistance = resistance;
+        self
+    }
+
+    pub fn with_output_resistance(mut self, resistance: f64) -> Self {
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.primary_positive_node = map(self.shared.primary_positive_node);
+        self.shared.primary_negative_node = map(self.shared.primary_negative_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.reference_node = map(self.shared.reference_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .primary_positive_node
+            .max(self.shared.primary_negative_node)
+            .max(self.shared.output_node)
+            .max(self.shared.reference_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.primary_positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.primary_negative_node
+    }
+
+    pub fn get_output_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_reference_node(&self) -> usize {
+        self.shared.reference_node
+    }
+
+    pub fn get_sensitivity(&self) -> f64 {
+        self.shared.sensitivity
+    }
+
+    pub fn get_offset(&self) -> f64 {
+        self.shared.offset
+    }
+
+    pub fn get_primary_resistance(&self) -> f64 {
+        self.shared.primary_resistance
+    }
+
+    pub fn get_output_resistance(&self) -> f64 {
+        self.shared.output_resistance
+    }
+
+    /// Gets the voltage dropped across the primary.
+    pub fn get_voltage(&self) -> f64 {
+        self.primary_voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.primary_voltage = voltage;
+    }
+
+    /// Gets the sensed primary current.
+    pub fn get_current(&self) -> f64 {
+        self.primary_current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.primary_current = current;
+    }
+
+    /// Gets the output voltage relative to the reference node.
+    pub fn get_output_voltage(&self) -> f64 {
+        self.output_voltage
+    }
+
+    pub fn set_output_voltage(&mut self, voltage: f64) {
+        self.output_voltage = voltage;
+    }
+
+    /// Gets the power dissipated in the primary.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for HallSensor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, vout: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_output_voltage()
+        )
+    }
+}
+
+impl TryFrom<Component> for HallSensor {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::HallSensor(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,21 +1,50 @@
 use std::fmt::Debug;
 
-use crate::components::Component;
+use crate::components::{Component, Shared};
+
+/// Describes how the inductance of a cored inductor falls off as its core saturates.
+///
+/// The incremental inductance is L(i) = L_sat + (L - L_sat)/(1 + (i/I_sat)^2), dropping from the
+/// nominal inductance L at zero current towards the saturated inductance L_sat past the
+/// saturation current I_sat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InductorSaturation {
+    pub saturation_current: f64,
+    pub saturated_inductance: f64,
+}
 
-#[derive(Clone, Copy, PartialEq)]
+impl InductorSaturation {
+    pub fn new(saturation_current: f64, saturated_inductance: f64) -> Self {
+        Self {
+            saturation_current,
+            saturated_inductance,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Inductor {
     // Static variables
-    positive_node: usize,
-    negative_node: usize,
-    inductance: f64,
+    pub(super) shared: Shared<Parameters>,
 
     // State variables
     current: f64,
+    previous_current: Option<(f64, f64)>,
 
     // Computed variables
     voltage: f64,
 }
 
+/// The static variables of an [`Inductor`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    inductance: f64,
+    saturation: Option<InductorSaturation>,
+    branch_current: bool,
+}
+
 impl Inductor {
     pub fn new(
         positive_node: usize,
@@ -24,28 +53,96 @@ impl Inductor {
         initial_current: f64,
     ) -> Self {
         Self {
-            positive_node,
-            negative_node,
-            inductance,
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                inductance,
+                saturation: None,
+                branch_current: false,
+            }),
             current: initial_current,
+            previous_current: None,
             voltage: 0.0,
         }
     }
 
+    /// Makes the solver allocate a variable for the current through the inductor so it is solved
+    /// for directly instead of being reconstructed from the voltage.
+    pub fn with_branch_current(mut self) -> Self {
+        self.shared.branch_current = true;
+        self
+    }
+
+    /// Makes the inductance fall off with current as the core saturates.
+    pub fn with_saturation(mut self, saturation: InductorSaturation) -> Self {
+        self.shared.saturation = Some(saturation);
+        self
+    }
+
+    /// Returns true if the current through the inductor is a variable of the system. Saturable
+    /// inductors always solve for their current directly.
+    pub fn has_branch_current(&self) -> bool {
+        self.shared.branch_current || self.shared.saturation.is_some()
+    }
+
+    pub fn get_saturation(&self) -> Option<InductorSaturation> {
+        self.shared.saturation
+    }
+
+    /// Computes the flux linkage at the given current.
+    pub fn flux(&self, current: f64) -> f64 {
+        match self.shared.saturation {
+            Some(InductorSaturation {
+                saturation_current,
+                saturated_inductance,
+            }) => {
+                saturated_inductance * current
+                    + (self.shared.inductance - saturated_inductance)
+                        * saturation_current
+                        * (current / saturation_current).atan()
+            }
+            None => self.shared.inductance * current,
+        }
+    }
+
+    /// Computes the incremental inductance dflux/di at the given current.
+    pub fn incremental_inductance(&self, current: f64) -> f64 {
+        match self.shared.saturation {
+            Some(InductorSaturation {
+                saturation_current,
+                saturated_inductance,
+            }) => {
+                let x = current / saturation_current;
+                saturated_inductance
+                    + (self.shared.inductance - saturated_inductance) / (1.0 + x * x)
+            }
+            None => self.shared.inductance,
+        }
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
     pub fn max_node(&self) -> usize {
         self.get_positive_node().max(self.get_negative_node())
     }
 
     pub fn get_positive_node(&self) -> usize {
-        self.positive_node
+        self.shared.positive_node
     }
 
     pub fn get_negative_node(&self) -> usize {
-        self.negative_node
+        self.shared.negative_node
     }
 
     pub fn get_inductance(&self) -> f64 {
-        self.inductance
+        self.shared.inductance
+    }
+
+    pub fn set_inductance(&mut self, inductance: f64) {
+        self.shared.inductance = inductance;
     }
 
     pub fn get_current(&self) -> f64 {
@@ -56,6 +153,16 @@ impl Inductor {
         self.current = current;
     }
 
+    /// Gets the current before the last timestep along with the length of that timestep, the
+    /// history of two-step integration methods, or None before the first timestep.
+    pub fn get_previous_current(&self) -> Option<(f64, f64)> {
+        self.previous_current
+    }
+
+    pub fn set_previous_current(&mut self, previous_current: Option<(f64, f64)>) {
+        self.previous_current = previous_current;
+    }
+
     pub fn get_voltage(&self) -> f64 {
         self.voltage
     }
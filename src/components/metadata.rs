@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Information about a component which the simulation doesn't use, kept by the
+/// [`Netlist`](crate::components::Netlist) for reports and BOM-aware analyses: key-value fields
+/// (part number, footprint, cost...) and tags grouping components across the circuit (e.g.
+/// "output-stage").
+///
+/// The well known keys ([`Metadata::PART_NUMBER`], [`Metadata::FOOTPRINT`], [`Metadata::COST`]
+/// and [`Metadata::TOLERANCE_CLASS`]) have a conventional meaning, any other key is free form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    fields: BTreeMap<String, String>,
+    tags: BTreeSet<String>,
+}
+
+impl Metadata {
+    /// The manufacturer's part number.
+    pub const PART_NUMBER: &'static str = "part_number";
+    /// The PCB footprint.
+    pub const FOOTPRINT: &'static str = "footprint";
+    /// The unit cost, as a number.
+    pub const COST: &'static str = "cost";
+    /// The tolerance class, e.g. "E96" or "X7R".
+    pub const TOLERANCE_CLASS: &'static str = "tolerance_class";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_field(key, value);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Sets a field, replacing any previous value of the key.
+    pub fn set_field(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.fields.insert(key.into(), value.into());
+    }
+
+    pub fn get_field(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    /// Gets every field, ordered by key.
+    pub fn get_fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn add_tag(&mut self, tag: impl Into<String>) {
+        self.tags.insert(tag.into());
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    /// Gets every tag in alphabetical order.
+    pub fn get_tags(&self) -> &BTreeSet<String> {
+        &self.tags
+    }
+
+    /// Gets the cost field as a number, None if it's missing or isn't a number.
+    pub fn get_cost(&self) -> Option<f64> {
+        self.get_field(Self::COST)?.trim().parse().ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.tags.is_empty()
+    }
+
+    /// Encodes the metadata as text, for formats which only store strings: one line per field
+    /// as `key=value` and per tag as `#tag`. Keys can't contain `=`, nor keys and values
+    /// newlines, and keys and tags can't start with `#`.
+    pub fn encode(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"));
+        let tags = self.tags.iter().map(|tag| format!("#{tag}"));
+        fields.chain(tags).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Decodes metadata encoded by [`Metadata::encode`], skipping blank lines and lines that are
+    /// neither fields nor tags.
+    pub fn decode(text: &str) -> Self {
+        let mut metadata = Self::new();
+        for line in text.lines() {
+            if let Some(tag) = line.strip_prefix('#') {
+                metadata.add_tag(tag);
+            } else if let Some((key, value)) = line.split_once('=') {
+                metadata.set_field(key, value);
+            }
+        }
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metadata_encoding() {
+        let metadata = Metadata::new()
+            .with_field(Metadata::PART_NUMBER, "RC0603FR-071KL")
+            .with_field(Metadata::COST, " 0.002")
+            .with_field("note", "a=b")
+            .with_tag("output-stage")
+            .with_tag("power");
+        assert_eq!(metadata.get_cost(), Some(0.002));
+        assert!(metadata.has_tag("power"));
+        assert!(!metadata.has_tag("input"));
+
+        let encoded = metadata.encode();
+        assert_eq!(
+            encoded,
+            "cost= 0.002\nnote=a=b\npart_number=RC0603FR-071KL\n#output-stage\n#power"
+        );
+        assert_eq!(Metadata::decode(&encoded), metadata);
+        assert!(Metadata::decode("").is_empty());
+    }
+}
@@ -2,10 +2,10 @@ mod resistor;
 pub use resistor::Resistor;
 
 mod capacitor;
-pub use capacitor::Capacitor;
+pub use capacitor::{CapacitanceModel, Capacitor};
 
 mod inductor;
-pub use inductor::Inductor;
+pub use inductor::{Inductor, InductorSaturation};
 
 mod voltage_source;
 pub use voltage_source::VoltageSource;
@@ -13,8 +13,99 @@ pub use voltage_source::VoltageSource;
 mod current_source;
 pub use current_source::CurrentSource;
 
+mod diode;
+pub use diode::{Diode, DiodeModel};
+
+mod switch;
+pub use switch::{ContactBounce, Switch};
+
+mod current_probe;
+pub use current_probe::CurrentProbe;
+
+mod electronic_load;
+pub use electronic_load::{ElectronicLoad, LoadMode, MAX_PWL_POINTS, Setpoint};
+
+mod pv_module;
+pub use pv_module::{PvDatasheet, PvModule, PvParameters, STC_IRRADIANCE, STC_TEMPERATURE};
+
+mod randles_cell;
+pub use randles_cell::{CPE_BRANCHES, ConstantPhaseElement, RandlesCell};
+
+mod thermoelectric;
+pub use thermoelectric::{HeatFlow, ThermoelectricModule, ThermoelectricParameters};
+
+mod hall_sensor;
+pub use hall_sensor::HallSensor;
+
+mod current_transformer;
+pub use current_transformer::CurrentTransformer;
+
+mod controlled_source;
+pub use controlled_source::{ControlledSource, ControlledSourceKind};
+
+mod shunt_reference;
+pub use shunt_reference::ShuntReference;
+
+mod relay;
+pub use relay::Relay;
+
+mod bench_supply;
+pub use bench_supply::BenchSupply;
+
+mod pwm_controller;
+pub use pwm_controller::{Compensator, PwmController, PwmMode};
+
+mod vco;
+pub use vco::Vco;
+
+mod frequency_divider;
+pub use frequency_divider::FrequencyDivider;
+
+mod phase_frequency_detector;
+pub use phase_frequency_detector::PhaseFrequencyDetector;
+
+mod non_overlapping_clock;
+pub use non_overlapping_clock::NonOverlappingClock;
+
+mod transmission_gate;
+pub use transmission_gate::TransmissionGate;
+
+mod sample_and_hold;
+pub use sample_and_hold::SampleAndHold;
+
+mod clocked_comparator;
+pub use clocked_comparator::ClockedComparator;
+
+mod noise_source;
+pub use noise_source::{MAX_PSD_POINTS, NOISE_BANDS, NoiseSource, PowerSpectralDensity};
+
+mod pulse_generator;
+pub use pulse_generator::{AutomotivePulse, PulseGenerator};
+
+mod fitted_impedance;
+pub use fitted_impedance::{FitError, FittedImpedance, MAX_FITTED_ORDER, RationalAdmittance};
+
+mod bjt;
+pub use bjt::{Bjt, BjtPolarity};
+
+mod ratings;
+pub use ratings::{RatedQuantity, RatingViolation, Ratings};
+
+mod tolerance;
+pub(crate) use tolerance::normal_cdf;
+pub use tolerance::{Bin, Distribution, Tolerance};
+
+mod metadata;
+pub use metadata::Metadata;
+
+mod param_change;
+pub use param_change::{ParamChange, ParamError};
+
+mod shared;
+pub(crate) use shared::Shared;
+
 mod component;
 pub use component::Component;
 
 mod netlist;
-pub use netlist::Netlist;
+pub use netlist::{DirtyRegion, Netlist};
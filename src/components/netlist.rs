@@ -1,20 +1,83 @@
-use crate::components::Component;
+use std::collections::HashMap;
+use std::sync::Arc;
 
-#[derive(Debug)]
+use crate::{
+    components::{Component, Metadata, RatingViolation, Ratings, Tolerance},
+    library::Subcircuit,
+};
+
+/// The components of a circuit and the nodes they connect.
+///
+/// Netlists are `Send` and `Sync`, as are the solvers and results made from them, so circuits
+/// can be simulated on worker threads and their results sent back over channels.
+///
+/// Cloning a netlist is cheap: clones share their components and annotations (names, ratings,
+/// tolerances and metadata) until one of them changes, which copies just the changed part. The
+/// components in turn share their nodes and parameters with their clones, so varying or
+/// simulating a trial only copies the state of its components (voltages, currents, charges)
+/// and the parameters it varies. Spawning many trials of a large circuit therefore leaves the
+/// parameters of every component a trial doesn't vary shared by all of them.
+#[derive(Debug, Clone)]
 pub struct Netlist {
-    components: Vec<Component>,
+    components: Arc<Vec<Component>>,
+    annotations: Arc<Annotations>,
+    reference_node: usize,
+    local_references: Vec<usize>,
+    last_allocated_node: usize,
+    dirty: DirtyRegion,
+}
+
+/// The edits made to a netlist since they were last taken (see
+/// [`Netlist::take_dirty_region`]), so a solver can update its layout of the system instead of
+/// setting it up from scratch, e.g. after a small edit in an interactive editor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirtyRegion {
+    /// Indices of the components added or edited, in increasing order.
+    pub components: Vec<usize>,
+    /// Whether components were removed or the reference node or local references changed,
+    /// which moves the equations of components that weren't edited.
+    pub restructured: bool,
+}
+
+impl DirtyRegion {
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty() && !self.restructured
+    }
+
+    fn mark(&mut self, component: usize) {
+        if let Err(position) = self.components.binary_search(&component) {
+            self.components.insert(position, component);
+        }
+    }
+}
+
+/// What a netlist records about its components and nodes besides the components themselves,
+/// which doesn't change while simulating.
+#[derive(Debug, Clone, Default)]
+struct Annotations {
+    ratings: HashMap<usize, Ratings>,
+    tolerances: HashMap<usize, Tolerance>,
+    component_names: HashMap<usize, String>,
+    node_names: HashMap<usize, String>,
+    metadata: HashMap<usize, Metadata>,
 }
 
 impl Netlist {
     pub fn new() -> Self {
         Self {
-            components: Vec::new(),
+            components: Arc::new(Vec::new()),
+            annotations: Arc::new(Annotations::default()),
+            reference_node: 0,
+            local_references: Vec::new(),
+            last_allocated_node: 0,
+            dirty: DirtyRegion::default(),
         }
     }
 
     /// Adds a single component to the netlist.
     pub fn add_component(&mut self, component: impl Into<Component>) -> &mut Self {
-        self.components.push(component.into());
+        Arc::make_mut(&mut self.components).push(component.into());
+        self.dirty.mark(self.components.len() - 1);
         self
     }
 
@@ -23,19 +86,379 @@ impl Netlist {
         &mut self,
         components: impl Iterator<Item = impl Into<Component>>,
     ) -> &mut Self {
-        self.components.extend(components.map(|c| c.into()));
+        let start = self.components.len();
+        Arc::make_mut(&mut self.components).extend(components.map(|c| c.into()));
+        for index in start..self.components.len() {
+            self.dirty.mark(index);
+        }
         self
     }
 
+    /// Adds all the components of a prebuilt subcircuit to the netlist.
+    pub fn add_subcircuit(&mut self, subcircuit: &impl Subcircuit) -> &mut Self {
+        subcircuit.add_to(self);
+        self
+    }
+
+    /// Allocates a node above every node used so far, e.g. for the internal nodes of a
+    /// subcircuit. Nodes added to the netlist afterwards must not reuse it, so subcircuits with
+    /// internal nodes are best added after the rest of the circuit.
+    pub fn add_node(&mut self) -> usize {
+        self.last_allocated_node = self.last_allocated_node.max(self.get_num_nodes()) + 1;
+        self.last_allocated_node
+    }
+
+    /// Marks every node up to the given one as used, so [`Netlist::add_node`] allocates above
+    /// them even if no component is connected to them yet, e.g. the pins of a subcircuit.
+    pub fn reserve_nodes(&mut self, max_node: usize) {
+        self.last_allocated_node = self.last_allocated_node.max(max_node);
+    }
+
     /// Gets all the components in the netlist in the order they were added.
     pub fn get_components(&self) -> &Vec<Component> {
         &self.components
     }
 
     /// Gets mutatable references to all the components in the netlist in the order they were
-    /// added.
+    /// added. Copies the components first if they are shared with a clone of the netlist.
+    ///
+    /// Meant for updating the state of the components while simulating, so changes made through
+    /// it aren't recorded in the [`DirtyRegion`]; edits should go through
+    /// [`Netlist::get_component_mut`] or [`Netlist::replace_component`] instead.
     pub fn get_components_mut(&mut self) -> &mut Vec<Component> {
-        &mut self.components
+        Arc::make_mut(&mut self.components)
+    }
+
+    /// Gets a mutable reference to the component at the given index to edit it, recording the
+    /// edit in the [`DirtyRegion`].
+    pub fn get_component_mut(&mut self, index: usize) -> &mut Component {
+        self.dirty.mark(index);
+        &mut Arc::make_mut(&mut self.components)[index]
+    }
+
+    /// Replaces the component at the given index, returning the one it replaced.
+    pub fn replace_component(
+        &mut self,
+        index: usize,
+        component: impl Into<Component>,
+    ) -> Component {
+        std::mem::replace(self.get_component_mut(index), component.into())
+    }
+
+    /// Removes the component at the given index, returning it. The components after it move
+    /// down an index, keeping their names, ratings, tolerances and metadata.
+    pub fn remove_component(&mut self, index: usize) -> Component {
+        let component = Arc::make_mut(&mut self.components).remove(index);
+
+        fn shift<T>(map: &mut HashMap<usize, T>, removed: usize) {
+            map.remove(&removed);
+            *map = std::mem::take(map)
+                .into_iter()
+                .map(|(k, v)| (if k > removed { k - 1 } else { k }, v))
+                .collect();
+        }
+        let annotations = Arc::make_mut(&mut self.annotations);
+        shift(&mut annotations.ratings, index);
+        shift(&mut annotations.tolerances, index);
+        shift(&mut annotations.component_names, index);
+        shift(&mut annotations.metadata, index);
+
+        self.dirty.components.retain(|&k| k != index);
+        for k in &mut self.dirty.components {
+            if *k > index {
+                *k -= 1;
+            }
+        }
+        self.dirty.restructured = true;
+        component
+    }
+
+    fn check_index(&self, index: usize) {
+        assert!(
+            index < self.components.len(),
+            "no component at index {index} of {}",
+            self.components.len()
+        );
+    }
+
+    /// Gets the edits made since they were last taken.
+    pub fn get_dirty_region(&self) -> &DirtyRegion {
+        &self.dirty
+    }
+
+    /// Takes the edits made since they were last taken, leaving the netlist clean.
+    pub fn take_dirty_region(&mut self) -> DirtyRegion {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Names the component at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn set_component_name(&mut self, index: usize, name: impl Into<String>) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .component_names
+            .insert(index, name.into());
+        self
+    }
+
+    /// Gets the name of the component at the given index. Unnamed components are named by their
+    /// SPICE prefix and index, for example "R2".
+    pub fn get_component_name(&self, index: usize) -> String {
+        match self.annotations.component_names.get(&index) {
+            Some(name) => name.clone(),
+            None => format!("{}{}", self.components[index].get_prefix(), index),
+        }
+    }
+
+    /// Finds the index of the component with the given name (see
+    /// [`Netlist::get_component_name`]).
+    pub fn find_component(&self, name: &str) -> Option<usize> {
+        (0..self.components.len()).find(|&index| self.get_component_name(index) == name)
+    }
+
+    /// Names a node.
+    pub fn set_node_name(&mut self, node: usize, name: impl Into<String>) -> &mut Self {
+        Arc::make_mut(&mut self.annotations)
+            .node_names
+            .insert(node, name.into());
+        self
+    }
+
+    /// Gets the name of a node. Unnamed nodes are named by their number.
+    pub fn get_node_name(&self, node: usize) -> String {
+        match self.annotations.node_names.get(&node) {
+            Some(name) => name.clone(),
+            None => node.to_string(),
+        }
+    }
+
+    /// Sets the maximum ratings of the component at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn set_ratings(&mut self, index: usize, ratings: Ratings) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .ratings
+            .insert(index, ratings);
+        self
+    }
+
+    /// Gets the maximum ratings of the component at the given index, if it has any.
+    pub fn get_ratings(&self, index: usize) -> Option<Ratings> {
+        self.annotations.ratings.get(&index).copied()
+    }
+
+    /// Sets the tolerance of the value of the component at the given index, which must have a
+    /// primary value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn set_tolerance(&mut self, index: usize, tolerance: Tolerance) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .tolerances
+            .insert(index, tolerance);
+        self
+    }
+
+    /// Gets the tolerance of the component at the given index, if it has one.
+    pub fn get_tolerance(&self, index: usize) -> Option<&Tolerance> {
+        self.annotations.tolerances.get(&index)
+    }
+
+    /// Gets the indices of every component with a tolerance, in increasing order.
+    pub fn get_toleranced_components(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.annotations.tolerances.keys().copied().collect();
+        indices.sort();
+        indices
+    }
+
+    /// Replaces the metadata of the component at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn set_metadata(&mut self, index: usize, metadata: Metadata) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .metadata
+            .insert(index, metadata);
+        self
+    }
+
+    /// Gets the metadata of the component at the given index, if it has any.
+    pub fn get_metadata(&self, index: usize) -> Option<&Metadata> {
+        self.annotations.metadata.get(&index)
+    }
+
+    /// Sets a metadata field of the component at the given index (see [`Metadata`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn set_metadata_field(
+        &mut self,
+        index: usize,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .metadata
+            .entry(index)
+            .or_default()
+            .set_field(key, value);
+        self
+    }
+
+    /// Tags the component at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no component at the index.
+    pub fn add_tag(&mut self, index: usize, tag: impl Into<String>) -> &mut Self {
+        self.check_index(index);
+        Arc::make_mut(&mut self.annotations)
+            .metadata
+            .entry(index)
+            .or_default()
+            .add_tag(tag);
+        self
+    }
+
+    /// Gets the indices of every component with the given tag, in increasing order.
+    pub fn find_tagged(&self, tag: &str) -> Vec<usize> {
+        self.find_by_metadata(|metadata| metadata.has_tag(tag))
+    }
+
+    /// Gets the indices of every component whose metadata has the given field value, in
+    /// increasing order.
+    pub fn find_by_field(&self, key: &str, value: &str) -> Vec<usize> {
+        self.find_by_metadata(|metadata| metadata.get_field(key) == Some(value))
+    }
+
+    /// Sums a quantity over every component with the given tag, for example the power of the
+    /// output stage with `netlist.sum_tagged("output-stage", Component::get_power)`.
+    pub fn sum_tagged(&self, tag: &str, quantity: impl Fn(&Component) -> f64) -> f64 {
+        self.find_tagged(tag)
+            .into_iter()
+            .map(|index| quantity(&self.components[index]))
+            .sum()
+    }
+
+    fn find_by_metadata(&self, predicate: impl Fn(&Metadata) -> bool) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .annotations
+            .metadata
+            .iter()
+            .filter(|(_, metadata)| predicate(metadata))
+            .map(|(&index, _)| index)
+            .collect();
+        indices.sort();
+        indices
+    }
+
+    /// Checks every rated component against its ratings, returning the violations found.
+    pub fn check_ratings(&self, time: f64) -> Vec<RatingViolation> {
+        let mut violations: Vec<RatingViolation> = self
+            .annotations
+            .ratings
+            .iter()
+            .flat_map(|(&index, ratings)| {
+                ratings.check(&self.components[index]).into_iter().map(
+                    move |(quantity, value, limit)| RatingViolation {
+                        component: index,
+                        time,
+                        quantity,
+                        value,
+                        limit,
+                    },
+                )
+            })
+            .collect();
+
+        violations.sort_by_key(|v| v.component);
+        violations
+    }
+
+    /// Sets the node all voltages are measured against. Defaults to node 0.
+    pub fn set_reference_node(&mut self, node: usize) -> &mut Self {
+        self.reference_node = node;
+        self.dirty.restructured = true;
+        self
+    }
+
+    pub fn get_reference_node(&self) -> usize {
+        self.reference_node
+    }
+
+    /// Designates a node as the local reference of a floating part of the circuit (for example
+    /// the secondary side of an isolated supply). The solver ties it to the reference node
+    /// through a very large resistance so the floating part has a defined potential without
+    /// noticeably loading the circuit.
+    pub fn add_local_reference(&mut self, node: usize) -> &mut Self {
+        self.local_references.push(node);
+        self.dirty.restructured = true;
+        self
+    }
+
+    pub fn get_local_references(&self) -> &Vec<usize> {
+        &self.local_references
+    }
+
+    /// Finds the nodes with no DC path to the reference node or a local reference, which would
+    /// make the circuit unsolvable. Capacitors and current sources don't conduct DC.
+    pub fn find_floating_nodes(&self) -> Vec<usize> {
+        let num_nodes = self.get_num_nodes();
+
+        // Union-find over the nodes joined by DC conducting components.
+        let mut parents: Vec<usize> = (0..=num_nodes).collect();
+        fn find(parents: &mut [usize], node: usize) -> usize {
+            let mut root = node;
+            while parents[root] != root {
+                root = parents[root];
+            }
+            parents[node] = root;
+            root
+        }
+
+        for component in self.components.iter() {
+            if matches!(
+                component,
+                Component::Capacitor(_) | Component::CurrentSource(_)
+            ) {
+                continue;
+            }
+
+            let a = find(&mut parents, component.get_positive_node());
+            let b = find(&mut parents, component.get_negative_node());
+            parents[a] = b;
+        }
+
+        let anchors: Vec<usize> = std::iter::once(self.reference_node)
+            .chain(self.local_references.iter().copied())
+            .map(|node| find(&mut parents, node))
+            .collect();
+
+        let used: Vec<bool> = (0..=num_nodes)
+            .map(|node| {
+                node == self.reference_node
+                    || self
+                        .components
+                        .iter()
+                        .any(|c| c.get_positive_node() == node || c.get_negative_node() == node)
+            })
+            .collect();
+
+        (0..=num_nodes)
+            .filter(|&node| used[node] && !anchors.contains(&find(&mut parents, node)))
+            .collect()
     }
 
     pub fn get_num_nodes(&self) -> usize {
@@ -44,6 +467,7 @@ impl Netlist {
             .map(|c| c.max_node())
             .max()
             .unwrap_or(0)
+            .max(self.reference_node)
     }
 }
 
@@ -55,8 +479,11 @@ impl Default for Netlist {
 
 #[cfg(test)]
 mod test {
+    use approx::assert_relative_eq;
+
     use super::*;
-    use crate::components::{Resistor, VoltageSource};
+    use crate::BESolver;
+    use crate::components::{Capacitor, Distribution, ParamChange, Resistor, VoltageSource};
 
     #[test]
     fn test_get_num_nodes() {
@@ -71,5 +498,120 @@ mod test {
             .add_component(VoltageSource::new(1, 2, 1.0))
             .add_component(Resistor::new(3, 4, 1.0));
         assert_eq!(netlist.get_num_nodes(), 4);
+        assert_eq!(netlist.add_node(), 5);
+        assert_eq!(netlist.add_node(), 6);
+        assert_eq!(netlist.get_num_nodes(), 4);
+    }
+
+    #[test]
+    fn test_find_floating_nodes() {
+        // A secondary coupled to the primary only through a capacitor.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Capacitor::new(1, 2, 1e-6, 0.0))
+            .add_component(VoltageSource::new(3, 2, 5.0))
+            .add_component(Resistor::new(3, 2, 1e3));
+        assert_eq!(netlist.find_floating_nodes(), vec![2, 3]);
+
+        netlist.add_local_reference(2);
+        assert!(netlist.find_floating_nodes().is_empty());
+
+        netlist.set_reference_node(5);
+        assert_eq!(netlist.get_num_nodes(), 5);
+        assert_eq!(netlist.find_floating_nodes(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .set_component_name(1, "R_load")
+            .set_tolerance(1, Tolerance::new(Distribution::Uniform(0.01)));
+
+        let mut trial = netlist.clone();
+        assert!(Arc::ptr_eq(&netlist.components, &trial.components));
+        assert!(Arc::ptr_eq(&netlist.annotations, &trial.annotations));
+
+        // Varying the trial copies its components, but only the parameters of the varied one,
+        // and still shares the annotations.
+        ParamChange::new(1, 2e3).apply(&mut trial).unwrap();
+        assert!(!Arc::ptr_eq(&netlist.components, &trial.components));
+        assert!(Arc::ptr_eq(&netlist.annotations, &trial.annotations));
+        assert!(netlist.get_components()[0].shares_parameters(&trial.get_components()[0]));
+        assert!(!netlist.get_components()[1].shares_parameters(&trial.get_components()[1]));
+        assert_eq!(netlist.get_components()[1].get_value(), Some(1e3));
+        assert_eq!(trial.get_components()[1].get_value(), Some(2e3));
+
+        // Simulating a trial copies the state of its components, not their parameters.
+        let mut simulated = netlist.clone();
+        BESolver::new(&mut simulated).solve(1e-3).unwrap();
+        assert!(!Arc::ptr_eq(&netlist.components, &simulated.components));
+        assert!(Arc::ptr_eq(&netlist.annotations, &simulated.annotations));
+        for (original, simulated) in netlist
+            .get_components()
+            .iter()
+            .zip(simulated.get_components())
+        {
+            assert!(original.shares_parameters(simulated));
+        }
+        assert_eq!(simulated.get_components()[1].get_voltage(), 10.0);
+        assert_eq!(netlist.get_components()[1].get_voltage(), 0.0);
+
+        trial.set_component_name(1, "R_trial");
+        assert_eq!(netlist.get_component_name(1), "R_load");
+        assert_eq!(trial.get_component_name(1), "R_trial");
+        assert!(trial.get_tolerance(1).is_some());
+    }
+
+    #[test]
+    fn test_metadata_queries() {
+        // A 10V source driving two 10Ω output resistors and a 1kΩ bias resistor.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .add_component(Resistor::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 10.0))
+            .set_metadata(
+                1,
+                Metadata::new().with_field(Metadata::PART_NUMBER, "RC0603FR-071KL"),
+            )
+            .add_tag(2, "output-stage")
+            .add_tag(3, "output-stage")
+            .set_metadata_field(3, Metadata::FOOTPRINT, "2512");
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        let netlist = solver.get_netlist();
+        assert_eq!(netlist.find_tagged("output-stage"), vec![2, 3]);
+        assert_relative_eq!(
+            netlist.sum_tagged("output-stage", Component::get_power),
+            20.0,
+            max_relative = 1e-9
+        );
+        assert_eq!(netlist.find_by_field(Metadata::FOOTPRINT, "2512"), vec![3]);
+        assert!(netlist.get_metadata(0).is_none());
+
+        // The metadata moves with its component when one before it is removed.
+        let mut netlist = netlist.clone();
+        netlist.remove_component(1);
+        assert_eq!(netlist.find_tagged("output-stage"), vec![1, 2]);
+        assert_eq!(
+            netlist.get_metadata(2).unwrap().get_field("footprint"),
+            Some("2512")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no component at index 2")]
+    fn test_ratings_of_missing_component() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 1e3))
+            .set_ratings(2, Ratings::new().with_max_power(0.25));
     }
 }
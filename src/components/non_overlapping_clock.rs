@@ -0,0 +1,227 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Fraction of a period within which the time counts as reaching an edge of a
+/// [`NonOverlappingClock`], so timesteps ending on the edge switch the phases despite rounding.
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+/// A two-phase non-overlapping clock, the timing of switched-capacitor circuits (see
+/// [`ClockedSwitchPair`](crate::library::ClockedSwitchPair)).
+///
+/// Phase 1 is high over the first half of each period and phase 2 over the second, each
+/// falling the dead time before the other rises so the switches they drive are never closed
+/// together. Each phase output is driven high (to the output voltage, through the output
+/// resistance) or low (to the ground node) as the phases stood at the start of the timestep.
+/// Every edge is a breakpoint, which the solver lands on (see
+/// [`BESolver::solve`](crate::BESolver::solve)).
+///
+/// The voltage and current of the component are those of the phase 1 output.
+#[derive(Clone, PartialEq)]
+pub struct NonOverlappingClock {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+    phase2_voltage: f64,
+}
+
+/// The static variables of a [`NonOverlappingClock`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    phase1_node: usize,
+    phase2_node: usize,
+    ground_node: usize,
+    frequency: f64,
+    dead_time: f64,
+    output_voltage: f64,
+    output_resistance: f64,
+}
+
+impl NonOverlappingClock {
+    /// Creates a clock with a dead time of 5% of the period, starting with phase 1 rising. It
+    /// drives its outputs to 1V through 1Ω.
+    pub fn new(phase1_node: usize, phase2_node: usize, ground_node: usize, frequency: f64) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                phase1_node,
+                phase2_node,
+                ground_node,
+                frequency,
+                dead_time: 0.05 / frequency,
+                output_voltage: 1.0,
+                output_resistance: 1.0,
+            }),
+            time: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+            phase2_voltage: 0.0,
+        }
+    }
+
+    /// Sets the time between one phase falling and the other rising, at most half the period.
+    pub fn with_dead_time(mut self, dead_time: f64) -> Self {
+        self.shared.dead_time = dead_time.clamp(0.0, 0.5 / self.shared.frequency);
+        self
+    }
+
+    /// Sets the voltage the phases are driven to while high and the resistance they are driven
+    /// through.
+    pub fn with_output_drive(mut self, voltage: f64, resistance: f64) -> Self {
+        self.shared.output_voltage = voltage;
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.phase1_node = map(self.shared.phase1_node);
+        self.shared.phase2_node = map(self.shared.phase2_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .phase1_node
+            .max(self.shared.phase2_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the phase 1 output node.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.phase1_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_phase2_node(&self) -> usize {
+        self.shared.phase2_node
+    }
+
+    pub fn get_frequency(&self) -> f64 {
+        self.shared.frequency
+    }
+
+    pub fn set_frequency(&mut self, frequency: f64) {
+        self.shared.frequency = frequency;
+        self.shared.dead_time = self.shared.dead_time.min(0.5 / frequency);
+    }
+
+    pub fn get_dead_time(&self) -> f64 {
+        self.shared.dead_time
+    }
+
+    pub fn get_output_drive(&self) -> (f64, f64) {
+        (self.shared.output_voltage, self.shared.output_resistance)
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the edges within a period, as fractions of the period: phase 1 falling, phase 2
+    /// rising, phase 2 falling and phase 1 rising again.
+    fn edges(&self) -> [f64; 4] {
+        let gap = self.shared.dead_time * self.shared.frequency;
+        [0.5 - gap, 0.5, 1.0 - gap, 1.0]
+    }
+
+    /// Gets the position within the period at the present time, as a fraction of the period,
+    /// snapped onto an edge within the edge tolerance.
+    fn position(&self) -> f64 {
+        let position = (self.time * self.shared.frequency).rem_euclid(1.0);
+        match self
+            .edges()
+            .into_iter()
+            .find(|edge| (position - edge).abs() < EDGE_TOLERANCE)
+        {
+            Some(edge) => edge.rem_euclid(1.0),
+            None => position,
+        }
+    }
+
+    /// Returns whether phase 1 is driven high.
+    pub fn is_phase1_high(&self) -> bool {
+        self.position() < self.edges()[0]
+    }
+
+    /// Returns whether phase 2 is driven high.
+    pub fn is_phase2_high(&self) -> bool {
+        let position = self.position();
+        let edges = self.edges();
+        position >= edges[1] && position < edges[2]
+    }
+
+    /// Gets the time of the next edge.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        let position = self.position();
+        let edge = self.edges().into_iter().find(|&edge| edge > position)?;
+        Some(self.time + (edge - position) / self.shared.frequency)
+    }
+
+    /// Gets the phase 1 output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the phase 1 output.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_phase2_voltage(&self) -> f64 {
+        self.phase2_voltage
+    }
+
+    pub fn set_phase2_voltage(&mut self, voltage: f64) {
+        self.phase2_voltage = voltage;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for NonOverlappingClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, phase 1: {}, phase 2: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_phase1_high(),
+            self.is_phase2_high()
+        )
+    }
+}
+
+impl TryFrom<Component> for NonOverlappingClock {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::NonOverlappingClock(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
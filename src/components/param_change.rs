@@ -0,0 +1,73 @@
+use crate::components::Netlist;
+
+/// Why a [`ParamChange`] can't be applied to a netlist.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamError {
+    /// The change refers to a component the netlist doesn't have.
+    UnknownComponent(usize),
+    /// The changed component has no primary value.
+    NoValue(usize),
+}
+
+/// A change to the primary value (see
+/// [`Component::get_value`](crate::components::Component::get_value)) of a component.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamChange {
+    /// Index of the component in the netlist.
+    pub component: usize,
+    pub value: f64,
+}
+
+impl ParamChange {
+    pub fn new(component: usize, value: f64) -> Self {
+        Self { component, value }
+    }
+
+    /// Checks that the change can be applied to the netlist, without applying it.
+    pub fn check(&self, netlist: &Netlist) -> Result<(), ParamError> {
+        let mut component = netlist
+            .get_components()
+            .get(self.component)
+            .cloned()
+            .ok_or(ParamError::UnknownComponent(self.component))?;
+        if component.set_value(self.value) {
+            Ok(())
+        } else {
+            Err(ParamError::NoValue(self.component))
+        }
+    }
+
+    /// Applies the change to the netlist, leaving it untouched if the change can't be applied.
+    pub fn apply(&self, netlist: &mut Netlist) -> Result<(), ParamError> {
+        self.check(netlist)?;
+        netlist
+            .get_component_mut(self.component)
+            .set_value(self.value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{CurrentProbe, Resistor};
+
+    #[test]
+    fn test_apply() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 1e3))
+            .add_component(CurrentProbe::new(1, 0));
+
+        assert_eq!(ParamChange::new(0, 2e3).apply(&mut netlist), Ok(()));
+        assert_eq!(netlist.get_components()[0].get_value(), Some(2e3));
+        assert_eq!(
+            ParamChange::new(1, 1.0).apply(&mut netlist),
+            Err(ParamError::NoValue(1))
+        );
+        assert_eq!(
+            ParamChange::new(2, 1.0).apply(&mut netlist),
+            Err(ParamError::UnknownComponent(2))
+        );
+    }
+}
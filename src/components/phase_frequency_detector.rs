@@ -0,0 +1,205 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A behavioral phase-frequency detector driving a charge pump, the front end of a PLL.
+///
+/// A rising edge of the reference input sets the UP state, a rising edge of the feedback input
+/// the DOWN state, and once both are set they reset (a tri-state PFD without reset delay).
+/// While only UP is set the charge pump sources the pump current out of the output node into
+/// the loop filter, while only DOWN is set it sinks it, so the average current is proportional
+/// to the phase error over a full cycle of phase error either way and pulls the loop towards
+/// the reference frequency when out of lock.
+///
+/// The inputs are high while their voltage above the ground node is above the threshold. The
+/// detector is updated between timesteps from the input voltages the timestep ended with, so
+/// the phase error is resolved to a timestep: timesteps should be small against the reference
+/// period.
+#[derive(Clone, PartialEq)]
+pub struct PhaseFrequencyDetector {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    reference_high: bool,
+    feedback_high: bool,
+    up: bool,
+    down: bool,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`PhaseFrequencyDetector`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    reference_node: usize,
+    feedback_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    pump_current: f64,
+    threshold: f64,
+}
+
+impl PhaseFrequencyDetector {
+    /// Creates a detector with a 0.5V input threshold.
+    pub fn new(
+        reference_node: usize,
+        feedback_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        pump_current: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                reference_node,
+                feedback_node,
+                output_node,
+                ground_node,
+                pump_current,
+                threshold: 0.5,
+            }),
+            reference_high: false,
+            feedback_high: false,
+            up: false,
+            down: false,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.shared.threshold = threshold;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.reference_node = map(self.shared.reference_node);
+        self.shared.feedback_node = map(self.shared.feedback_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .reference_node
+            .max(self.shared.feedback_node)
+            .max(self.shared.output_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the output node of the charge pump.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_reference_node(&self) -> usize {
+        self.shared.reference_node
+    }
+
+    pub fn get_feedback_node(&self) -> usize {
+        self.shared.feedback_node
+    }
+
+    pub fn get_pump_current(&self) -> f64 {
+        self.shared.pump_current
+    }
+
+    pub fn set_pump_current(&mut self, current: f64) {
+        self.shared.pump_current = current;
+    }
+
+    pub fn get_threshold(&self) -> f64 {
+        self.shared.threshold
+    }
+
+    /// Returns whether the UP state is set, the reference leading the feedback.
+    pub fn is_up(&self) -> bool {
+        self.up
+    }
+
+    /// Returns whether the DOWN state is set, the feedback leading the reference.
+    pub fn is_down(&self) -> bool {
+        self.down
+    }
+
+    /// Gets the current the charge pump drives out of the output node in the present state.
+    pub fn get_pump_output(&self) -> f64 {
+        match (self.up, self.down) {
+            (true, false) => self.shared.pump_current,
+            (false, true) => -self.shared.pump_current,
+            _ => 0.0,
+        }
+    }
+
+    /// Updates the UP and DOWN states from the input voltages a timestep ended with.
+    pub fn advance(&mut self, reference_voltage: f64, feedback_voltage: f64) {
+        let reference_high = reference_voltage > self.shared.threshold;
+        let feedback_high = feedback_voltage > self.shared.threshold;
+        if reference_high && !self.reference_high {
+            self.up = true;
+        }
+        if feedback_high && !self.feedback_high {
+            self.down = true;
+        }
+        if self.up && self.down {
+            self.up = false;
+            self.down = false;
+        }
+        self.reference_high = reference_high;
+        self.feedback_high = feedback_high;
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current the charge pump delivered out of the output node over the last
+    /// timestep.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for PhaseFrequencyDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, up: {}, down: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_up(),
+            self.is_down()
+        )
+    }
+}
+
+impl TryFrom<Component> for PhaseFrequencyDetector {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::PhaseFrequencyDetector(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use std::fmt::Debug;
+
+use crate::components::relay::reached;
+use crate::components::{Component, Shared};
+
+/// A standard automotive supply transient, the test pulses input protection networks are
+/// validated against. The constructors give the parameters the standards specify for 12V
+/// systems, with the severity set by the amplitude, and the internal resistance of the test
+/// generator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutomotivePulse {
+    /// Rises linearly by `amplitude` over `rise_time`, then decays exponentially, falling to
+    /// 10% of the amplitude `duration` after it started rising, as the pulses of ISO 7637-2
+    /// and the load dump of ISO 16750-2 are specified. Repeats every `period` (or once if the
+    /// period is zero), and if `burst_length` is nonzero only within the first `burst_length`
+    /// of every `burst_period`.
+    Exponential {
+        amplitude: f64,
+        rise_time: f64,
+        duration: f64,
+        period: f64,
+        burst_length: f64,
+        burst_period: f64,
+        internal_resistance: f64,
+    },
+    /// The starting profile (cold crank) of ISO 16750-2: the supply falls from the battery
+    /// voltage to `low_voltage` over `fall_time` and stays there for `low_time` as the starter
+    /// engages, rises to `cranking_voltage` over `rise_time` and stays there for
+    /// `cranking_time` as the engine turns, then recovers to the battery voltage over
+    /// `recovery_time`. Happens once.
+    Crank {
+        low_voltage: f64,
+        cranking_voltage: f64,
+        fall_time: f64,
+        low_time: f64,
+        rise_time: f64,
+        cranking_time: f64,
+        recovery_time: f64,
+        internal_resistance: f64,
+    },
+}
+
+impl AutomotivePulse {
+    /// ISO 7637-2 pulse 1, the negative transient of an inductive load disconnected from the
+    /// supply, of amplitude -75V to -150V: 1µs rise, 2ms duration, every 0.5s, from 10Ω.
+    pub fn iso7637_pulse_1(amplitude: f64) -> Self {
+        Self::exponential(amplitude, 1e-6, 2e-3, 0.5, 10.0)
+    }
+
+    /// ISO 7637-2 pulse 2a, the positive transient of the current of a parallel load
+    /// interrupted by the wiring inductance, of amplitude 37V to 112V: 1µs rise, 50µs
+    /// duration, every 0.5s, from 2Ω.
+    pub fn iso7637_pulse_2a(amplitude: f64) -> Self {
+        Self::exponential(amplitude, 1e-6, 50e-6, 0.5, 2.0)
+    }
+
+    /// ISO 7637-2 pulse 3a, the negative fast transients of switching arcs, of amplitude -112V
+    /// to -220V: 5ns rise, 150ns duration, every 100µs in bursts of 10ms every 100ms, from 50Ω.
+    pub fn iso7637_pulse_3a(amplitude: f64) -> Self {
+        Self::Exponential {
+            amplitude,
+            rise_time: 5e-9,
+            duration: 150e-9,
+            period: 100e-6,
+            burst_length: 10e-3,
+            burst_period: 100e-3,
+            internal_resistance: 50.0,
+        }
+    }
+
+    /// ISO 7637-2 pulse 3b, the positive counterpart of pulse 3a, of amplitude 75V to 150V.
+    pub fn iso7637_pulse_3b(amplitude: f64) -> Self {
+        Self::iso7637_pulse_3a(amplitude)
+    }
+
+    /// The unsuppressed load dump of ISO 16750-2 (test A), the alternator disconnected from a
+    /// discharged battery while charging, of amplitude 79V to 101V and duration 40ms to
+    /// 400ms: 10ms rise, once, from the internal resistance of the alternator (0.5Ω to 4Ω).
+    pub fn load_dump(amplitude: f64, duration: f64, internal_resistance: f64) -> Self {
+        Self::exponential(amplitude, 10e-3, duration, 0.0, internal_resistance)
+    }
+
+    /// The starting profile of ISO 16750-2 with the given low and cranking voltages (e.g. 8V
+    /// and 9.5V for level I, 4.5V and 6.5V for level II) and cranking time (1s or 10s): 5ms
+    /// fall, 15ms low, 50ms rise and 100ms recovery, from the 10mΩ of a battery.
+    pub fn cold_crank(low_voltage: f64, cranking_voltage: f64, cranking_time: f64) -> Self {
+        Self::Crank {
+            low_voltage,
+            cranking_voltage,
+            fall_time: 5e-3,
+            low_time: 15e-3,
+            rise_time: 50e-3,
+            cranking_time,
+            recovery_time: 100e-3,
+            internal_resistance: 0.01,
+        }
+    }
+
+    fn exponential(
+        amplitude: f64,
+        rise_time: f64,
+        duration: f64,
+        period: f64,
+        internal_resistance: f64,
+    ) -> Self {
+        Self::Exponential {
+            amplitude,
+            rise_time,
+            duration,
+            period,
+            burst_length: 0.0,
+            burst_period: 0.0,
+            internal_resistance,
+        }
+    }
+
+    /// Gets the internal resistance of the test generator.
+    pub fn get_internal_resistance(&self) -> f64 {
+        match *self {
+            Self::Exponential {
+                internal_resistance,
+                ..
+            }
+            | Self::Crank {
+                internal_resistance,
+                ..
+            } => internal_resistance,
+        }
+    }
+
+    /// Gets the start of the last pulse started by the given time since the first one, or
+    /// None before the first.
+    fn pulse_start(&self, time: f64) -> Option<f64> {
+        if time < 0.0 {
+            return None;
+        }
+
+        match *self {
+            Self::Exponential {
+                period,
+                burst_length,
+                burst_period,
+                ..
+            } if period > 0.0 => {
+                if burst_length > 0.0 && burst_period > 0.0 {
+                    let burst_start = (time / burst_period).floor() * burst_period;
+                    let last = (burst_length / period - 1e-9).ceil() - 1.0;
+                    let k = ((time - burst_start) / period).floor().min(last);
+                    Some(burst_start + k * period)
+                } else {
+                    Some((time / period).floor() * period)
+                }
+            }
+            _ => Some(0.0),
+        }
+    }
+
+    /// Gets the start of the pulse following the one started at the given time.
+    fn next_pulse_start(&self, start: f64) -> Option<f64> {
+        match *self {
+            Self::Exponential {
+                period,
+                burst_length,
+                burst_period,
+                ..
+            } if period > 0.0 => {
+                let next = start + period;
+                if burst_length > 0.0 && burst_period > 0.0 {
+                    let burst_start = (start / burst_period + 1e-9).floor() * burst_period;
+                    if next - burst_start >= burst_length * (1.0 - 1e-9) {
+                        return Some(burst_start + burst_period);
+                    }
+                }
+                Some(next)
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the times at which the waveform has corners, relative to the start of a pulse.
+    fn corners(&self) -> Vec<f64> {
+        match *self {
+            Self::Exponential { rise_time, .. } => vec![0.0, rise_time],
+            Self::Crank {
+                fall_time,
+                low_time,
+                rise_time,
+                cranking_time,
+                recovery_time,
+                ..
+            } => {
+                let mut corners = vec![0.0];
+                for length in [fall_time, low_time, rise_time, cranking_time, recovery_time] {
+                    corners.push(corners[corners.len() - 1] + length);
+                }
+                corners
+            }
+        }
+    }
+
+    /// Gets the open circuit voltage of the generator at the given time since the first pulse
+    /// started, on top of the battery voltage.
+    pub fn voltage_at(&self, time: f64, battery_voltage: f64) -> f64 {
+        let Some(start) = self.pulse_start(time) else {
+            return battery_voltage;
+        };
+        let t = time - start;
+
+        match *self {
+            Self::Exponential {
+                amplitude,
+                rise_time,
+                duration,
+                ..
+            } => {
+                let fraction = if t < rise_time {
+                    t / rise_time
+                } else {
+                    (-(t - rise_time) * 10f64.ln() / (duration - rise_time)).exp()
+                };
+                battery_voltage + amplitude * fraction
+            }
+            Self::Crank {
+                low_voltage,
+                cranking_voltage,
+                ..
+            } => {
+                let corners = self.corners();
+                let levels = [
+                    battery_voltage,
+                    low_voltage,
+                    low_voltage,
+                    cranking_voltage,
+                    cranking_voltage,
+                    battery_voltage,
+                ];
+                let k = corners.partition_point(|&corner| corner <= t);
+                if k == corners.len() {
+                    return battery_voltage;
+                }
+
+                let (t0, t1) = (corners[k - 1], corners[k]);
+                levels[k - 1] + (levels[k] - levels[k - 1]) * (t - t0) / (t1 - t0)
+            }
+        }
+    }
+
+    /// Gets the first corner of the waveform not yet reached at the given time since the first
+    /// pulse started.
+    fn next_corner(&self, time: f64) -> Option<f64> {
+        let corners = self.corners();
+        let Some(start) = self.pulse_start(time) else {
+            return Some(0.0);
+        };
+
+        // The corners of the next pulse too, in case the time is on its start.
+        let next = self.next_pulse_start(start);
+        [Some(start)]
+            .into_iter()
+            .chain([next])
+            .flatten()
+            .flat_map(|start| corners.iter().map(move |corner| start + corner))
+            .find(|&corner| !reached(time, corner))
+    }
+}
+
+/// A test generator applying an [`AutomotivePulse`] to the supply of a circuit, as in
+/// automotive immunity tests: a voltage source of the battery voltage, with the pulse on top of
+/// it starting after the delay, behind the internal resistance of the generator.
+///
+/// The corners of the waveform are breakpoints, so timesteps end on them (see
+/// [`BESolver::solve`](crate::BESolver::solve)) and don't skip the fast pulses. The generator
+/// is a battery for AC analysis.
+#[derive(Clone, PartialEq)]
+pub struct PulseGenerator {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+
+    // Computed variables
+    current: f64,
+}
+
+/// The static variables of a [`PulseGenerator`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    battery_voltage: f64,
+    pulse: AutomotivePulse,
+    delay: f64,
+    internal_resistance: f64,
+}
+
+impl PulseGenerator {
+    /// Creates a generator of the pulse on top of the battery voltage, with the internal
+    /// resistance of the pulse.
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        battery_voltage: f64,
+        pulse: AutomotivePulse,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                battery_voltage,
+                pulse,
+                delay: 0.0,
+                internal_resistance: pulse.get_internal_resistance(),
+            }),
+            time: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Starts the first pulse after the given delay rather than at the start of the simulation.
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.shared.delay = delay;
+        self
+    }
+
+    /// Overrides the internal resistance of the generator.
+    pub fn with_internal_resistance(mut self, internal_resistance: f64) -> Self {
+        self.shared.internal_resistance = internal_resistance;
+        self
+    }
+
+    pub fn get_pulse(&self) -> AutomotivePulse {
+        self.shared.pulse
+    }
+
+    pub fn get_delay(&self) -> f64 {
+        self.shared.delay
+    }
+
+    pub fn get_internal_resistance(&self) -> f64 {
+        self.shared.internal_resistance
+    }
+
+    pub fn get_battery_voltage(&self) -> f64 {
+        self.shared.battery_voltage
+    }
+
+    pub fn set_battery_voltage(&mut self, battery_voltage: f64) {
+        self.shared.battery_voltage = battery_voltage;
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the open circuit voltage of the generator at the given time.
+    pub fn get_voltage_at(&self, time: f64) -> f64 {
+        self.shared
+            .pulse
+            .voltage_at(time - self.shared.delay, self.shared.battery_voltage)
+    }
+
+    /// Gets the open circuit voltage of the generator at present.
+    pub fn get_output_voltage(&self) -> f64 {
+        self.get_voltage_at(self.time)
+    }
+
+    /// Gets the time of the next corner of the waveform.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        self.shared
+            .pulse
+            .next_corner(self.time - self.shared.delay)
+            .map(|corner| corner + self.shared.delay)
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared.positive_node.max(self.shared.negative_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    /// Gets the voltage at the terminals of the generator, its open circuit voltage less the
+    /// drop across its internal resistance.
+    pub fn get_voltage(&self) -> f64 {
+        self.get_output_voltage() - self.shared.internal_resistance * self.current
+    }
+
+    /// Gets the current the generator delivers out of its positive node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    /// Gets the power delivered to the circuit at the terminals of the generator.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for PulseGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for PulseGenerator {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::PulseGenerator(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_exponential_pulses() {
+        let pulse = AutomotivePulse::iso7637_pulse_2a(50.0);
+        assert_eq!(pulse.voltage_at(-1e-3, 13.5), 13.5);
+        assert_relative_eq!(pulse.voltage_at(0.5e-6, 13.5), 38.5, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(1e-6, 13.5), 63.5, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(50e-6, 13.5), 18.5, max_relative = 1e-9);
+        assert_relative_eq!(
+            pulse.voltage_at(0.5 + 1e-6, 13.5),
+            63.5,
+            max_relative = 1e-9
+        );
+
+        // The fast pulses come every 100µs for 10ms, then stop until the next burst.
+        let pulse = AutomotivePulse::iso7637_pulse_3a(-150.0);
+        assert_relative_eq!(
+            pulse.voltage_at(9.9e-3 + 5e-9, 0.0),
+            -150.0,
+            max_relative = 1e-6
+        );
+        assert!(pulse.voltage_at(10e-3 + 5e-9, 0.0).abs() < 1e-9);
+        assert_relative_eq!(
+            pulse.voltage_at(0.1 + 5e-9, 0.0),
+            -150.0,
+            max_relative = 1e-6
+        );
+        assert_eq!(pulse.next_corner(9.9e-3 + 5e-9 + 1e-12), Some(0.1));
+
+        let pulse = AutomotivePulse::load_dump(87.0, 0.2, 1.0);
+        assert_relative_eq!(pulse.voltage_at(10e-3, 13.5), 100.5, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(0.2, 13.5), 22.2, max_relative = 1e-9);
+        assert_eq!(pulse.next_corner(11e-3), None);
+    }
+
+    #[test]
+    fn test_cold_crank() {
+        let pulse = AutomotivePulse::cold_crank(8.0, 9.5, 1.0);
+        assert_relative_eq!(pulse.voltage_at(2.5e-3, 12.0), 10.0, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(10e-3, 12.0), 8.0, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(0.5, 12.0), 9.5, max_relative = 1e-12);
+        assert_relative_eq!(pulse.voltage_at(1.12, 12.0), 10.75, max_relative = 1e-9);
+        assert_eq!(pulse.voltage_at(2.0, 12.0), 12.0);
+        assert_relative_eq!(pulse.next_corner(0.03).unwrap(), 0.07, max_relative = 1e-12);
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt::Debug;
+
+use nalgebra::{Matrix3, Vector3};
+
+use crate::components::{Component, DiodeModel, Setpoint, Shared};
+
+/// Boltzmann constant in eV/K.
+const BOLTZMANN: f64 = 8.617333e-5;
+
+/// Temperature the diode model's thermal voltage is defined at, in kelvin.
+const MODEL_TEMPERATURE: f64 = 300.0;
+
+/// Standard test condition temperature, in degrees Celsius.
+pub const STC_TEMPERATURE: f64 = 25.0;
+
+/// Standard test condition irradiance, in W/m^2.
+pub const STC_IRRADIANCE: f64 = 1000.0;
+
+/// Band gap of silicon in eV, which sets how fast the saturation current rises with temperature.
+const SILICON_BAND_GAP: f64 = 1.12;
+
+fn kelvin(celsius: f64) -> f64 {
+    celsius + 273.15
+}
+
+/// The figures of a PV module datasheet, at standard test conditions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PvDatasheet {
+    pub short_circuit_current: f64,
+    pub open_circuit_voltage: f64,
+    /// Current at the maximum power point.
+    pub mpp_current: f64,
+    /// Voltage at the maximum power point.
+    pub mpp_voltage: f64,
+    pub cells_in_series: usize,
+    /// Change of the short circuit current with temperature, in A/K.
+    pub current_temperature_coefficient: f64,
+}
+
+/// Parameters of the single or two diode model of a PV module at standard test conditions:
+///
+/// i = Iph - I01*(exp(vj/(n1*Ns*Vt)) - 1) - I02*(exp(vj/(2*Ns*Vt)) - 1) - vj/Rsh, vj = v + i*Rs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PvParameters {
+    pub photo_current: f64,
+    pub saturation_current: f64,
+    pub ideality: f64,
+    /// Saturation current of the recombination diode (ideality 2) of the two diode model, zero
+    /// for the single diode model.
+    pub second_saturation_current: f64,
+    pub series_resistance: f64,
+    pub shunt_resistance: f64,
+    pub cells_in_series: usize,
+    /// Change of the photo current with temperature, in A/K.
+    pub current_temperature_coefficient: f64,
+}
+
+impl PvParameters {
+    /// Derives single diode parameters from a datasheet for the given ideality (typically 1 to
+    /// 1.5), so the model passes through the short circuit, open circuit and maximum power
+    /// points with the maximum power point at the peak of the power curve.
+    ///
+    /// For a series resistance the three points give linear equations in Iph, I0 and 1/Rsh;
+    /// the series resistance is then found by bisection so the power peaks at the maximum power
+    /// point. Returns None if no positive parameters fit the datasheet.
+    pub fn from_datasheet(datasheet: &PvDatasheet, ideality: f64) -> Option<Self> {
+        let PvDatasheet {
+            short_circuit_current: isc,
+            open_circuit_voltage: voc,
+            mpp_current: imp,
+            mpp_voltage: vmp,
+            cells_in_series,
+            current_temperature_coefficient,
+        } = *datasheet;
+
+        let a = ideality * cells_in_series as f64 * BOLTZMANN * kelvin(STC_TEMPERATURE);
+
+        // Iph - I0*(exp(vj/a) - 1) - vj/Rsh = i at each point.
+        let fit = |rs: f64| -> Option<(f64, f64, f64)> {
+            let points = [(0.0, isc), (voc, 0.0), (vmp, imp)];
+            let m = Matrix3::from_fn(|row, column| {
+                let (v, i) = points[row];
+                let vj = v + i * rs;
+                [1.0, -((vj / a).exp() - 1.0), -vj][column]
+            });
+            let rhs = Vector3::new(isc, 0.0, imp);
+            let solution = m.lu().solve(&rhs)?;
+            Some((solution[0], solution[1], solution[2]))
+        };
+
+        // dI/dV = -g/(1 + g*Rs) must equal -Imp/Vmp at the maximum power point.
+        let mpp_error = |rs: f64| -> Option<f64> {
+            let (_, i0, gsh) = fit(rs)?;
+            let g = i0 * ((vmp + imp * rs) / a).exp() / a + gsh;
+            Some(g / (1.0 + g * rs) - imp / vmp)
+        };
+
+        // At the upper bound the maximum power point would have the junction voltage of the open
+        // circuit point.
+        let (mut low, mut high) = (0.0, 0.999 * (voc - vmp) / imp);
+        let (mut error_low, error_high) = (mpp_error(low)?, mpp_error(high)?);
+        if error_low.signum() == error_high.signum() {
+            return None;
+        }
+
+        for _ in 0..100 {
+            let middle = (low + high) / 2.0;
+            let error = mpp_error(middle)?;
+            if error.signum() == error_low.signum() {
+                low = middle;
+                error_low = error;
+            } else {
+                high = middle;
+            }
+        }
+
+        let rs = (low + high) / 2.0;
+        let (photo_current, saturation_current, shunt_conductance) = fit(rs)?;
+        if photo_current <= 0.0 || saturation_current <= 0.0 || shunt_conductance <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            photo_current,
+            saturation_current,
+            ideality,
+            second_saturation_current: 0.0,
+            series_resistance: rs,
+            shunt_resistance: 1.0 / shunt_conductance,
+            cells_in_series,
+            current_temperature_coefficient,
+        })
+    }
+
+    /// Adds a recombination diode (ideality 2) to make a two diode model.
+    pub fn with_second_diode(mut self, saturation_current: f64) -> Self {
+        self.second_saturation_current = saturation_current;
+        self
+    }
+}
+
+/// A photovoltaic module with time varying irradiance, for developing MPPT converters.
+///
+/// Current flows out of the positive node into the circuit, like a source. The photo current
+/// scales with irradiance and temperature, and the saturation currents follow the usual cubic
+/// and band gap dependence on temperature.
+#[derive(Clone, PartialEq)]
+pub struct PvModule {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`PvModule`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    parameters: PvParameters,
+    irradiance: Setpoint,
+    temperature: f64,
+}
+
+impl PvModule {
+    /// Creates a module at standard test conditions.
+    pub fn new(positive_node: usize, negative_node: usize, parameters: PvParameters) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                parameters,
+                irradiance: Setpoint::Constant(STC_IRRADIANCE),
+                temperature: STC_TEMPERATURE,
+            }),
+            time: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Sets the irradiance in W/m^2 over time.
+    pub fn with_irradiance(mut self, irradiance: Setpoint) -> Self {
+        self.shared.irradiance = irradiance;
+        self
+    }
+
+    /// Sets the cell temperature in degrees Celsius.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.shared.temperature = temperature;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_parameters(&self) -> PvParameters {
+        self.shared.parameters
+    }
+
+    pub fn get_irradiance(&self) -> Setpoint {
+        self.shared.irradiance
+    }
+
+    pub fn get_temperature(&self) -> f64 {
+        self.shared.temperature
+    }
+
+    pub fn set_temperature(&mut self, temperature: f64) {
+        self.shared.temperature = temperature;
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the photo current at the given time.
+    pub fn get_photo_current_at(&self, time: f64) -> f64 {
+        let p = &self.shared.parameters;
+        (p.photo_current
+            + p.current_temperature_coefficient * (self.shared.temperature - STC_TEMPERATURE))
+            * self.shared.irradiance.value_at(time)
+            / STC_IRRADIANCE
+    }
+
+    /// Gets the diodes of the model at the module temperature.
+    fn diodes(&self) -> [DiodeModel; 2] {
+        let p = &self.shared.parameters;
+        let (t, t_stc) = (kelvin(self.shared.temperature), kelvin(STC_TEMPERATURE));
+        let scale = |ideality: f64| {
+            (t / t_stc).powi(3)
+                * (SILICON_BAND_GAP / (ideality * BOLTZMANN) * (1.0 / t_stc - 1.0 / t)).exp()
+        };
+
+        // The diode model's thermal voltage is fixed, so the temperature and the cells in series
+        // are folded into the emission coefficient.
+        let emission = |ideality: f64| ideality * p.cells_in_series as f64 * t / MODEL_TEMPERATURE;
+        [
+            DiodeModel::Shockley {
+                saturation_current: p.saturation_current * scale(p.ideality),
+                emission_coefficient: emission(p.ideality),
+            },
+            DiodeModel::Shockley {
+                saturation_current: p.second_saturation_current * scale(2.0),
+                emission_coefficient: emission(2.0),
+            },
+        ]
+    }
+
+    /// Evaluates the implicit model equation f(v, i) = 0 at the given time, returning f and its
+    /// derivatives with respect to v and i.
+    pub fn evaluate(&self, voltage: f64, current: f64, time: f64) -> (f64, f64, f64) {
+        let p = &self.shared.parameters;
+        let vj = voltage + current * p.series_resistance;
+
+        let (diode_current, g) = self
+            .diodes()
+            .iter()
+            .map(|d| d.evaluate(vj))
+            .fold((0.0, 1.0 / p.shunt_resistance), |(i, g), (di, dg)| {
+                (i + di, g + dg)
+            });
+
+        let f = self.get_photo_current_at(time) - diode_current - vj / p.shunt_resistance - current;
+        (f, -g, -g * p.series_resistance - 1.0)
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for PvModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for PvModule {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::PvModule(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
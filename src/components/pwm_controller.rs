@@ -0,0 +1,395 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use crate::components::relay::reached;
+use crate::components::{Component, Shared};
+
+/// The control law turning the gate of a [`PwmController`] off within each switching period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PwmMode {
+    /// Voltage mode: the gate turns off once a ramp, rising from zero to the ramp amplitude
+    /// over the period, reaches the control voltage, so the duty cycle is the control voltage
+    /// over the ramp amplitude.
+    VoltageMode { ramp_amplitude: f64 },
+    /// Peak current mode: the gate turns off once the current sense voltage, plus the slope
+    /// compensation ramp (in V/s) rising from zero at the start of the period, reaches the
+    /// control voltage. The control voltage is clamped to the maximum sense voltage, which sets
+    /// the peak current limit.
+    PeakCurrentMode {
+        sense_node: usize,
+        max_sense_voltage: f64,
+        slope_compensation: f64,
+    },
+}
+
+/// The compensator of the error amplifier of a [`PwmController`], from the error (the reference
+/// less the feedback voltage) to the control voltage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compensator {
+    /// A plain gain, leaving a steady state error.
+    Proportional { gain: f64 },
+    /// A type II compensator, gain*(1 + wz/s)/(1 + s/wp): an integrator up to the zero, a flat
+    /// gain between the zero and the pole, rolling off above the pole.
+    TypeII {
+        gain: f64,
+        zero_frequency: f64,
+        pole_frequency: f64,
+    },
+}
+
+/// A fixed frequency PWM controller for switch-mode converters: an error amplifier with a
+/// compensator compares the feedback voltage to the reference, and a modulator turns its
+/// control voltage into the gate drive of the converter's switch (see [`Switch::with_gate`]).
+///
+/// A clock turns the gate on at the start of each period, and the [`PwmMode`] turns it off,
+/// at the latest at the maximum duty cycle. The gate is driven to the gate voltage or to the
+/// ground node through the gate resistance.
+///
+/// The controller is updated between timesteps from the voltages the timestep ended with, so
+/// the control voltage lags a timestep behind the feedback. The clock edges and, in voltage
+/// mode, the instant the ramp crosses the control voltage are breakpoints, so timesteps cut to
+/// land on them (see [`BESolver::step_to_breakpoint`](crate::BESolver::step_to_breakpoint))
+/// switch exactly. In peak current mode the gate turns off at the end of the timestep the sense
+/// voltage crosses in, so timesteps should be small against the period.
+///
+/// [`Switch::with_gate`]: crate::components::Switch::with_gate
+#[derive(Clone, PartialEq)]
+pub struct PwmController {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+    period_start: f64,
+    on: bool,
+    integral: f64,
+    control: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`PwmController`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    feedback_node: usize,
+    gate_node: usize,
+    ground_node: usize,
+    reference: f64,
+    frequency: f64,
+    mode: PwmMode,
+    compensator: Compensator,
+    max_duty: f64,
+    gate_voltage: f64,
+    gate_resistance: f64,
+}
+
+impl PwmController {
+    /// Creates a voltage mode controller. It uses a type II compensator with a gain of 1, a
+    /// zero at a fiftieth of the switching frequency and a pole at half of it, a maximum duty
+    /// cycle of 95% and drives the gate to 10V through 1Ω.
+    pub fn voltage_mode(
+        feedback_node: usize,
+        gate_node: usize,
+        ground_node: usize,
+        reference: f64,
+        frequency: f64,
+        ramp_amplitude: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                feedback_node,
+                gate_node,
+                ground_node,
+                reference,
+                frequency,
+                mode: PwmMode::VoltageMode { ramp_amplitude },
+                compensator: Compensator::TypeII {
+                    gain: 1.0,
+                    zero_frequency: frequency / 50.0,
+                    pole_frequency: frequency / 2.0,
+                },
+                max_duty: 0.95,
+                gate_voltage: 10.0,
+                gate_resistance: 1.0,
+            }),
+            time: 0.0,
+            period_start: 0.0,
+            on: false,
+            integral: 0.0,
+            control: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Creates a peak current mode controller sensing the switch current as the voltage of the
+    /// sense node above the ground node, without slope compensation. It otherwise defaults like
+    /// [`PwmController::voltage_mode`].
+    pub fn peak_current_mode(
+        feedback_node: usize,
+        sense_node: usize,
+        gate_node: usize,
+        ground_node: usize,
+        reference: f64,
+        frequency: f64,
+        max_sense_voltage: f64,
+    ) -> Self {
+        let mut controller = Self::voltage_mode(
+            feedback_node,
+            gate_node,
+            ground_node,
+            reference,
+            frequency,
+            max_sense_voltage,
+        );
+        controller.shared.mode = PwmMode::PeakCurrentMode {
+            sense_node,
+            max_sense_voltage,
+            slope_compensation: 0.0,
+        };
+        controller
+    }
+
+    pub fn with_compensator(mut self, compensator: Compensator) -> Self {
+        self.shared.compensator = compensator;
+        self
+    }
+
+    /// Sets the slope of the compensation ramp of a peak current mode controller in V/s, which
+    /// keeps it stable above 50% duty cycle. Has no effect in voltage mode.
+    pub fn with_slope_compensation(mut self, slope: f64) -> Self {
+        if let PwmMode::PeakCurrentMode {
+            slope_compensation, ..
+        } = &mut self.shared.mode
+        {
+            *slope_compensation = slope;
+        }
+        self
+    }
+
+    pub fn with_max_duty(mut self, duty: f64) -> Self {
+        self.shared.max_duty = duty;
+        self
+    }
+
+    /// Sets the voltage the gate is driven to while on and the resistance it is driven through.
+    pub fn with_gate_drive(mut self, voltage: f64, resistance: f64) -> Self {
+        self.shared.gate_voltage = voltage;
+        self.shared.gate_resistance = resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.feedback_node = map(self.shared.feedback_node);
+        self.shared.gate_node = map(self.shared.gate_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+        if let PwmMode::PeakCurrentMode { sense_node, .. } = &mut self.shared.mode {
+            *sense_node = map(*sense_node);
+        }
+    }
+
+    pub fn max_node(&self) -> usize {
+        let sense_node = match self.shared.mode {
+            PwmMode::PeakCurrentMode { sense_node, .. } => sense_node,
+            PwmMode::VoltageMode { .. } => 0,
+        };
+        self.shared
+            .feedback_node
+            .max(self.shared.gate_node)
+            .max(self.shared.ground_node)
+            .max(sense_node)
+    }
+
+    /// Gets the gate node, the output of the controller.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.gate_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_feedback_node(&self) -> usize {
+        self.shared.feedback_node
+    }
+
+    pub fn get_reference(&self) -> f64 {
+        self.shared.reference
+    }
+
+    pub fn set_reference(&mut self, reference: f64) {
+        self.shared.reference = reference;
+    }
+
+    pub fn get_frequency(&self) -> f64 {
+        self.shared.frequency
+    }
+
+    pub fn get_mode(&self) -> PwmMode {
+        self.shared.mode
+    }
+
+    pub fn get_compensator(&self) -> Compensator {
+        self.shared.compensator
+    }
+
+    pub fn get_max_duty(&self) -> f64 {
+        self.shared.max_duty
+    }
+
+    pub fn get_gate_drive(&self) -> (f64, f64) {
+        (self.shared.gate_voltage, self.shared.gate_resistance)
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns whether the gate is driven on.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Gets the control voltage at the output of the compensator.
+    pub fn get_control(&self) -> f64 {
+        self.control
+    }
+
+    /// Gets the highest control voltage: the ramp amplitude in voltage mode, the maximum sense
+    /// voltage in peak current mode.
+    pub fn get_max_control(&self) -> f64 {
+        match self.shared.mode {
+            PwmMode::VoltageMode { ramp_amplitude } => ramp_amplitude,
+            PwmMode::PeakCurrentMode {
+                max_sense_voltage, ..
+            } => max_sense_voltage,
+        }
+    }
+
+    fn period(&self) -> f64 {
+        1.0 / self.shared.frequency
+    }
+
+    /// Gets the time at which the gate turns off in the present period, unless the current
+    /// sense turns it off earlier.
+    fn turn_off_time(&self) -> f64 {
+        let duty = match self.shared.mode {
+            PwmMode::VoltageMode { ramp_amplitude } => {
+                (self.control / ramp_amplitude).min(self.shared.max_duty)
+            }
+            PwmMode::PeakCurrentMode { .. } => self.shared.max_duty,
+        };
+        self.period_start + duty * self.period()
+    }
+
+    /// Gets the next clock edge or, in voltage mode, turn off instant.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        let next_period = self.period_start + self.period();
+        let turn_off = self.turn_off_time();
+        if self.on && turn_off > self.time {
+            Some(turn_off.min(next_period))
+        } else {
+            Some(next_period)
+        }
+    }
+
+    /// Updates the compensator and the gate at the end of a timestep from the feedback and sense
+    /// voltages it ended with.
+    pub fn advance(&mut self, dt: f64, feedback_voltage: f64, sense_voltage: f64) {
+        self.time += dt;
+
+        let error = self.shared.reference - feedback_voltage;
+        let max_control = self.get_max_control();
+        self.control = match self.shared.compensator {
+            Compensator::Proportional { gain } => (gain * error).clamp(0.0, max_control),
+            Compensator::TypeII {
+                gain,
+                zero_frequency,
+                pole_frequency,
+            } => {
+                // Backward Euler on the integrator and the pole, the integrator stopping where
+                // the control voltage saturates so it doesn't wind up.
+                let proportional = gain * error;
+                self.integral = (self.integral + dt * 2.0 * PI * zero_frequency * proportional)
+                    .clamp(-proportional, max_control - proportional);
+                let k = dt * 2.0 * PI * pole_frequency;
+                ((self.control + k * (self.integral + proportional)) / (1.0 + k))
+                    .clamp(0.0, max_control)
+            }
+        };
+
+        if self.on {
+            let tripped = match self.shared.mode {
+                PwmMode::VoltageMode { .. } => false,
+                PwmMode::PeakCurrentMode {
+                    slope_compensation, ..
+                } => {
+                    sense_voltage + slope_compensation * (self.time - self.period_start)
+                        >= self.control
+                }
+            };
+            if tripped || reached(self.time, self.turn_off_time()) {
+                self.on = false;
+            }
+        }
+
+        if reached(self.time, self.period_start + self.period()) {
+            self.period_start += self.period();
+            // Timesteps longer than the period skip whole periods.
+            while reached(self.time, self.period_start + self.period()) {
+                self.period_start += self.period();
+            }
+            self.on = self.control > 0.0;
+        }
+    }
+
+    /// Gets the gate voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the gate node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for PwmController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, control: {}, on: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_control(),
+            self.is_on()
+        )
+    }
+}
+
+impl TryFrom<Component> for PwmController {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::PwmController(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use nalgebra::Complex;
+
+use crate::components::{Component, Shared};
+
+/// Number of series RC branches approximating each constant phase element in transient
+/// simulation.
+pub const CPE_BRANCHES: usize = 14;
+
+/// A constant phase element, Z = 1/(Q*(jw)^alpha): a capacitor for alpha = 1, a resistor for
+/// alpha = 0, and the distributed, "leaky" capacitance of real electrodes in between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantPhaseElement {
+    pub q: f64,
+    pub alpha: f64,
+}
+
+impl ConstantPhaseElement {
+    pub fn new(q: f64, alpha: f64) -> Self {
+        Self { q, alpha }
+    }
+
+    /// Creates the semi-infinite Warburg element Z = sigma*(1 - j)/sqrt(w), a constant phase
+    /// element with alpha = 0.5.
+    pub fn warburg(sigma: f64) -> Self {
+        Self::new(1.0 / (sigma * 2f64.sqrt()), 0.5)
+    }
+
+    pub fn admittance(&self, omega: f64) -> Complex<f64> {
+        self.q * Complex::new(0.0, omega).powf(self.alpha)
+    }
+
+    pub fn impedance(&self, omega: f64) -> Complex<f64> {
+        1.0 / self.admittance(omega)
+    }
+
+    /// Approximates the element between `min_frequency` and `max_frequency` hertz by
+    /// [`CPE_BRANCHES`] series RC branches in parallel, returning the conductance and time
+    /// constant of each. Alpha must be strictly between 0 and 1.
+    ///
+    /// Uses s^alpha = sin(alpha*pi)/pi * integral of x^(alpha - 1) * s/(s + x) dx over x > 0,
+    /// where each x = 1/tau term is a series RC branch of admittance g*s/(s + x). The band is
+    /// integrated with log spaced branches, and the parts of the integral outside of it are
+    /// lumped into one branch at each edge: a resistance below the band and a capacitance
+    /// above it.
+    pub fn branches(&self, min_frequency: f64, max_frequency: f64) -> [(f64, f64); CPE_BRANCHES] {
+        let (low, high) = (
+            (2.0 * PI * min_frequency).ln(),
+            (2.0 * PI * max_frequency).ln(),
+        );
+        let interior = CPE_BRANCHES - 2;
+        let step = (high - low) / interior as f64;
+        let weight = self.q * (self.alpha * PI).sin() / PI;
+
+        let mut branches = [(0.0, 0.0); CPE_BRANCHES];
+        for (k, branch) in branches.iter_mut().take(interior).enumerate() {
+            let x = (low + (k as f64 + 0.5) * step).exp();
+            *branch = (weight * step * x.powf(self.alpha), 1.0 / x);
+        }
+
+        let (x_low, x_high) = (low.exp(), high.exp());
+        branches[interior] = (weight * x_low.powf(self.alpha) / self.alpha, 1.0 / x_low);
+        branches[interior + 1] = (
+            weight * x_high.powf(self.alpha) / (1.0 - self.alpha),
+            1.0 / x_high,
+        );
+        branches
+    }
+}
+
+/// A Randles equivalent circuit of an electrochemical cell, for battery diagnostics and EIS
+/// fitting: a series (electrolyte) resistance followed by the double layer in parallel with the
+/// charge transfer resistance and an optional Warburg diffusion element in series with it.
+///
+/// ```text
+/// + --Rs--+--Rct--W-- -
+///         |         |
+///         +---CPE---+
+/// ```
+///
+/// AC analysis uses the exact constant phase elements. Transient simulation approximates them
+/// over a frequency band (1mHz to 10kHz by default) with [`CPE_BRANCHES`] RC branches each.
+/// The series resistance must be nonzero.
+#[derive(Clone, PartialEq)]
+pub struct RandlesCell {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    /// Capacitor voltages of the branches approximating the double layer and the Warburg
+    /// element.
+    double_layer_voltages: [f64; CPE_BRANCHES],
+    warburg_voltages: [f64; CPE_BRANCHES],
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`RandlesCell`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    series_resistance: f64,
+    charge_transfer_resistance: f64,
+    double_layer: ConstantPhaseElement,
+    warburg: Option<ConstantPhaseElement>,
+    min_frequency: f64,
+    max_frequency: f64,
+}
+
+impl RandlesCell {
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        series_resistance: f64,
+        charge_transfer_resistance: f64,
+        double_layer: ConstantPhaseElement,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                series_resistance,
+                charge_transfer_resistance,
+                double_layer,
+                warburg: None,
+                min_frequency: 1e-3,
+                max_frequency: 1e4,
+            }),
+            double_layer_voltages: [0.0; CPE_BRANCHES],
+            warburg_voltages: [0.0; CPE_BRANCHES],
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Adds a Warburg diffusion element with the given coefficient in ohms per root second in
+    /// series with the charge transfer resistance.
+    pub fn with_warburg(mut self, sigma: f64) -> Self {
+        self.shared.warburg = Some(ConstantPhaseElement::warburg(sigma));
+        self
+    }
+
+    /// Sets the frequency band over which the constant phase elements are approximated in
+    /// transient simulation.
+    pub fn with_band(mut self, min_frequency: f64, max_frequency: f64) -> Self {
+        self.shared.min_frequency = min_frequency;
+        self.shared.max_frequency = max_frequency;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.get_positive_node().max(self.get_negative_node())
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_series_resistance(&self) -> f64 {
+        self.shared.series_resistance
+    }
+
+    pub fn get_charge_transfer_resistance(&self) -> f64 {
+        self.shared.charge_transfer_resistance
+    }
+
+    pub fn get_double_layer(&self) -> ConstantPhaseElement {
+        self.shared.double_layer
+    }
+
+    pub fn get_warburg(&self) -> Option<ConstantPhaseElement> {
+        self.shared.warburg
+    }
+
+    pub fn get_band(&self) -> (f64, f64) {
+        (self.shared.min_frequency, self.shared.max_frequency)
+    }
+
+    /// Gets the capacitor voltages of the branches approximating the double layer.
+    pub fn get_double_layer_voltages(&self) -> [f64; CPE_BRANCHES] {
+        self.double_layer_voltages
+    }
+
+    pub fn set_double_layer_voltages(&mut self, voltages: [f64; CPE_BRANCHES]) {
+        self.double_layer_voltages = voltages;
+    }
+
+    /// Gets the capacitor voltages of the branches approximating the Warburg element.
+    pub fn get_warburg_voltages(&self) -> [f64; CPE_BRANCHES] {
+        self.warburg_voltages
+    }
+
+    pub fn set_warburg_voltages(&mut self, voltages: [f64; CPE_BRANCHES]) {
+        self.warburg_voltages = voltages;
+    }
+
+    /// Gets the exact impedance of the cell at the given angular frequency, e.g. for fitting
+    /// to EIS measurements.
+    pub fn impedance(&self, omega: f64) -> Complex<f64> {
+        let faradaic = self.shared.charge_transfer_resistance
+            + self
+                .shared
+                .warburg
+                .map_or(Complex::from(0.0), |w| w.impedance(omega));
+        self.shared.series_resistance
+            + 1.0 / (1.0 / faradaic + self.shared.double_layer.admittance(omega))
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for RandlesCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for RandlesCell {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::RandlesCell(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
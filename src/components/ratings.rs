@@ -0,0 +1,73 @@
+use crate::components::Component;
+
+/// A quantity a component can be rated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RatedQuantity {
+    Voltage,
+    Current,
+    Power,
+}
+
+/// Maximum ratings of a component. Limits apply to the magnitude of each quantity.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Ratings {
+    pub max_voltage: Option<f64>,
+    pub max_current: Option<f64>,
+    pub max_power: Option<f64>,
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_voltage(mut self, max_voltage: f64) -> Self {
+        self.max_voltage = Some(max_voltage);
+        self
+    }
+
+    pub fn with_max_current(mut self, max_current: f64) -> Self {
+        self.max_current = Some(max_current);
+        self
+    }
+
+    pub fn with_max_power(mut self, max_power: f64) -> Self {
+        self.max_power = Some(max_power);
+        self
+    }
+
+    /// Returns every rated quantity of the component that exceeds its limit, along with its
+    /// value and limit.
+    pub fn check(&self, component: &Component) -> Vec<(RatedQuantity, f64, f64)> {
+        [
+            (
+                RatedQuantity::Voltage,
+                component.get_voltage(),
+                self.max_voltage,
+            ),
+            (
+                RatedQuantity::Current,
+                component.get_current(),
+                self.max_current,
+            ),
+            (RatedQuantity::Power, component.get_power(), self.max_power),
+        ]
+        .into_iter()
+        .filter_map(|(quantity, value, limit)| {
+            let limit = limit?;
+            (value.abs() > limit).then_some((quantity, value, limit))
+        })
+        .collect()
+    }
+}
+
+/// Records a component exceeding one of its ratings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatingViolation {
+    /// Index of the component in the netlist.
+    pub component: usize,
+    pub time: f64,
+    pub quantity: RatedQuantity,
+    pub value: f64,
+    pub limit: f64,
+}
@@ -0,0 +1,316 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Relative tolerance within which the end of a timestep counts as reaching a scheduled
+/// change, so timesteps cut to land on it (see [`Relay::get_breakpoint`]) switch it.
+const BREAKPOINT_TOLERANCE: f64 = 1e-9;
+
+/// Returns whether the given time has reached the breakpoint.
+pub(crate) fn reached(time: f64, breakpoint: f64) -> bool {
+    time >= breakpoint - BREAKPOINT_TOLERANCE * breakpoint.abs().max(1.0)
+}
+
+/// An electromechanical relay: a coil (a resistance in series with an inductance) between the
+/// coil nodes operates a contact between the contact nodes.
+///
+/// The armature pulls in once the magnitude of the coil current rises to the pull-in current
+/// and drops out once it falls to the drop-out current. The contact then changes state after
+/// the operate or release time, at an instant found by interpolating the coil current within
+/// the timestep it crossed. That instant is exposed as a breakpoint so the timestep can be cut
+/// to land on it (see [`BESolver::step_to_breakpoint`](crate::BESolver::step_to_breakpoint)).
+#[derive(Clone, PartialEq)]
+pub struct Relay {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+    coil_current: f64,
+    pulled_in: bool,
+    pending_change: Option<f64>,
+
+    // Computed variables
+    coil_voltage: f64,
+    contact_voltage: f64,
+}
+
+/// The static variables of a [`Relay`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    coil_positive_node: usize,
+    coil_negative_node: usize,
+    contact_positive_node: usize,
+    contact_negative_node: usize,
+    coil_resistance: f64,
+    coil_inductance: f64,
+    pull_in_current: f64,
+    drop_out_current: f64,
+    operate_time: f64,
+    release_time: f64,
+    on_resistance: f64,
+    off_resistance: f64,
+    normally_closed: bool,
+}
+
+impl Relay {
+    /// Creates a normally open relay with instant contacts, a 50mΩ closed and 1GΩ open contact.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        coil_positive_node: usize,
+        coil_negative_node: usize,
+        contact_positive_node: usize,
+        contact_negative_node: usize,
+        coil_resistance: f64,
+        coil_inductance: f64,
+        pull_in_current: f64,
+        drop_out_current: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                coil_positive_node,
+                coil_negative_node,
+                contact_positive_node,
+                contact_negative_node,
+                coil_resistance,
+                coil_inductance,
+                pull_in_current,
+                drop_out_current,
+                operate_time: 0.0,
+                release_time: 0.0,
+                on_resistance: 50e-3,
+                off_resistance: 1e9,
+                normally_closed: false,
+            }),
+            time: 0.0,
+            coil_current: 0.0,
+            pulled_in: false,
+            pending_change: None,
+            coil_voltage: 0.0,
+            contact_voltage: 0.0,
+        }
+    }
+
+    /// Sets the mechanical delays between the coil current crossing the pull-in or drop-out
+    /// current and the contact changing state.
+    pub fn with_delays(mut self, operate_time: f64, release_time: f64) -> Self {
+        self.shared.operate_time = operate_time;
+        self.shared.release_time = release_time;
+        self
+    }
+
+    pub fn with_contact_resistances(mut self, on_resistance: f64, off_resistance: f64) -> Self {
+        self.shared.on_resistance = on_resistance;
+        self.shared.off_resistance = off_resistance;
+        self
+    }
+
+    /// Makes the contact closed while the relay is released and open while it is operated.
+    pub fn with_normally_closed(mut self) -> Self {
+        self.shared.normally_closed = true;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.coil_positive_node = map(self.shared.coil_positive_node);
+        self.shared.coil_negative_node = map(self.shared.coil_negative_node);
+        self.shared.contact_positive_node = map(self.shared.contact_positive_node);
+        self.shared.contact_negative_node = map(self.shared.contact_negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .coil_positive_node
+            .max(self.shared.coil_negative_node)
+            .max(self.shared.contact_positive_node)
+            .max(self.shared.contact_negative_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.coil_positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.coil_negative_node
+    }
+
+    pub fn get_contact_positive_node(&self) -> usize {
+        self.shared.contact_positive_node
+    }
+
+    pub fn get_contact_negative_node(&self) -> usize {
+        self.shared.contact_negative_node
+    }
+
+    pub fn get_coil_resistance(&self) -> f64 {
+        self.shared.coil_resistance
+    }
+
+    pub fn get_coil_inductance(&self) -> f64 {
+        self.shared.coil_inductance
+    }
+
+    pub fn get_pull_in_current(&self) -> f64 {
+        self.shared.pull_in_current
+    }
+
+    pub fn get_drop_out_current(&self) -> f64 {
+        self.shared.drop_out_current
+    }
+
+    pub fn get_operate_time(&self) -> f64 {
+        self.shared.operate_time
+    }
+
+    pub fn get_release_time(&self) -> f64 {
+        self.shared.release_time
+    }
+
+    pub fn is_normally_closed(&self) -> bool {
+        self.shared.normally_closed
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Returns whether the armature is pulled in, which the contact follows after the operate
+    /// or release time.
+    pub fn is_pulled_in(&self) -> bool {
+        self.pulled_in
+    }
+
+    /// Gets the time at which the contact is scheduled to change state, if it is.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        self.pending_change
+    }
+
+    /// Returns whether the contact is closed at the given time, which must not be before the
+    /// current time.
+    pub fn is_closed_at(&self, time: f64) -> bool {
+        let operated = match self.pending_change {
+            Some(change) if reached(time, change) => self.pulled_in,
+            Some(_) => !self.pulled_in,
+            None => self.pulled_in,
+        };
+        operated != self.shared.normally_closed
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed_at(self.get_time())
+    }
+
+    /// Gets the conductance of the contact at the given time.
+    pub fn get_contact_conductance_at(&self, time: f64) -> f64 {
+        if self.is_closed_at(time) {
+            1.0 / self.shared.on_resistance
+        } else {
+            1.0 / self.shared.off_resistance
+        }
+    }
+
+    /// Updates the armature and the contact at the end of a timestep from the coil current it
+    /// ended with.
+    pub fn advance(&mut self, dt: f64, coil_current: f64) {
+        let previous = self.coil_current.abs();
+        let current = coil_current.abs();
+        let start = self.time;
+
+        self.time += dt;
+        self.coil_current = coil_current;
+
+        // The instant within the timestep the coil current crossed the given level.
+        let crossing = |level: f64| {
+            if current == previous {
+                start + dt
+            } else {
+                start + dt * ((level - previous) / (current - previous)).clamp(0.0, 1.0)
+            }
+        };
+
+        if !self.pulled_in && current >= self.shared.pull_in_current {
+            self.pulled_in = true;
+            self.schedule(crossing(self.shared.pull_in_current) + self.shared.operate_time);
+        } else if self.pulled_in && current <= self.shared.drop_out_current {
+            self.pulled_in = false;
+            self.schedule(crossing(self.shared.drop_out_current) + self.shared.release_time);
+        }
+
+        if self
+            .pending_change
+            .is_some_and(|change| reached(self.time, change))
+        {
+            self.pending_change = None;
+        }
+    }
+
+    /// Schedules the contact to follow the armature at the given time. A change still pending
+    /// in the other direction is cancelled, the contact never having moved.
+    fn schedule(&mut self, time: f64) {
+        self.pending_change = match self.pending_change {
+            Some(_) => None,
+            None => Some(time),
+        };
+    }
+
+    /// Gets the coil current, positive flowing into the positive coil node.
+    pub fn get_current(&self) -> f64 {
+        self.coil_current
+    }
+
+    /// Gets the coil voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.coil_voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.coil_voltage = voltage;
+    }
+
+    pub fn get_contact_voltage(&self) -> f64 {
+        self.contact_voltage
+    }
+
+    pub fn set_contact_voltage(&mut self, voltage: f64) {
+        self.contact_voltage = voltage;
+    }
+
+    pub fn get_contact_current(&self) -> f64 {
+        self.get_contact_voltage() * self.get_contact_conductance_at(self.get_time())
+    }
+
+    /// Gets the power dissipated in the coil and the contact.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+            + self.get_contact_voltage() * self.get_contact_current()
+    }
+}
+
+impl Debug for Relay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, closed: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_closed()
+        )
+    }
+}
+
+impl TryFrom<Component> for Relay {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::Relay(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,42 +1,59 @@
 use std::fmt::Debug;
 
-use crate::components::Component;
+use crate::components::{Component, Shared};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Resistor {
     // Static variables
-    positive_node: usize,
-    negative_node: usize,
-    resistance: f64,
+    pub(super) shared: Shared<Parameters>,
 
     // Computed variables
     voltage: f64,
 }
 
+/// The static variables of a [`Resistor`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    resistance: f64,
+}
+
 impl Resistor {
     pub fn new(positive_node: usize, negative_node: usize, resistance: f64) -> Self {
         Self {
-            positive_node,
-            negative_node,
-            resistance,
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                resistance,
+            }),
             voltage: 0.0,
         }
     }
 
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
     pub fn max_node(&self) -> usize {
         self.get_positive_node().max(self.get_negative_node())
     }
 
     pub fn get_positive_node(&self) -> usize {
-        self.positive_node
+        self.shared.positive_node
     }
 
     pub fn get_negative_node(&self) -> usize {
-        self.negative_node
+        self.shared.negative_node
     }
 
     pub fn get_resistance(&self) -> f64 {
-        self.resistance
+        self.shared.resistance
+    }
+
+    pub fn set_resistance(&mut self, resistance: f64) {
+        self.shared.resistance = resistance;
     }
 
     pub fn get_voltage(&self) -> f64 {
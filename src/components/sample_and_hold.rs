@@ -0,0 +1,219 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Relative tolerance within which the end of a timestep counts as reaching a scheduled event
+/// of a [`SampleAndHold`] or [`ClockedComparator`](crate::components::ClockedComparator). Tighter
+/// than that of a relay, as apertures and regeneration times can be picoseconds.
+const EVENT_TOLERANCE: f64 = 1e-12;
+
+/// Returns whether the given time has reached the scheduled event.
+pub(crate) fn event_reached(time: f64, event: f64) -> bool {
+    time >= event - EVENT_TOLERANCE * event.abs().max(1e-6)
+}
+
+/// A behavioral sample-and-hold: samples the voltage of its input node above the ground node
+/// the aperture delay after every rising edge of the clock, and drives the sample out of the
+/// output node (through the output resistance) until the next one, e.g. the front end of an
+/// ADC.
+///
+/// The clock is high while its voltage above the ground node is above the threshold. The
+/// sample-and-hold is updated between timesteps from the voltages the timestep ended with, so
+/// a clock edge is seen at the end of the timestep it happened in. The sampling instant is a
+/// breakpoint, so the timestep ends on it (see [`BESolver::solve`](crate::BESolver::solve)).
+#[derive(Clone, PartialEq)]
+pub struct SampleAndHold {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+    clock_high: bool,
+    pending_sample: Option<f64>,
+    held_voltage: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`SampleAndHold`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    input_node: usize,
+    output_node: usize,
+    clock_node: usize,
+    ground_node: usize,
+    threshold: f64,
+    aperture_delay: f64,
+    output_resistance: f64,
+}
+
+impl SampleAndHold {
+    /// Creates a sample-and-hold with a 0.5V clock threshold, no aperture delay and a 1Ω output
+    /// resistance, holding 0V until the first sample.
+    pub fn new(
+        input_node: usize,
+        output_node: usize,
+        clock_node: usize,
+        ground_node: usize,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                input_node,
+                output_node,
+                clock_node,
+                ground_node,
+                threshold: 0.5,
+                aperture_delay: 0.0,
+                output_resistance: 1.0,
+            }),
+            time: 0.0,
+            clock_high: false,
+            pending_sample: None,
+            held_voltage: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.shared.threshold = threshold;
+        self
+    }
+
+    /// Sets the delay between a rising edge of the clock and the sampling instant.
+    pub fn with_aperture_delay(mut self, aperture_delay: f64) -> Self {
+        self.shared.aperture_delay = aperture_delay;
+        self
+    }
+
+    pub fn with_output_resistance(mut self, output_resistance: f64) -> Self {
+        self.shared.output_resistance = output_resistance;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.input_node = map(self.shared.input_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.clock_node = map(self.shared.clock_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .input_node
+            .max(self.shared.output_node)
+            .max(self.shared.clock_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the output node.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_input_node(&self) -> usize {
+        self.shared.input_node
+    }
+
+    pub fn get_clock_node(&self) -> usize {
+        self.shared.clock_node
+    }
+
+    pub fn get_threshold(&self) -> f64 {
+        self.shared.threshold
+    }
+
+    pub fn get_aperture_delay(&self) -> f64 {
+        self.shared.aperture_delay
+    }
+
+    pub fn get_output_resistance(&self) -> f64 {
+        self.shared.output_resistance
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Gets the voltage held since the last sample.
+    pub fn get_held_voltage(&self) -> f64 {
+        self.held_voltage
+    }
+
+    /// Gets the time of the pending sample, if a clock edge is waiting out the aperture delay.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        self.pending_sample
+    }
+
+    /// Advances over a timestep dt, from the clock and input voltages it ended with.
+    pub fn advance(&mut self, dt: f64, clock_voltage: f64, input_voltage: f64) {
+        self.time += dt;
+
+        let clock_high = clock_voltage > self.shared.threshold;
+        if clock_high && !self.clock_high {
+            self.pending_sample = Some(self.time + self.shared.aperture_delay);
+        }
+        self.clock_high = clock_high;
+
+        if self
+            .pending_sample
+            .is_some_and(|sample| event_reached(self.time, sample))
+        {
+            self.pending_sample = None;
+            self.held_voltage = input_voltage;
+        }
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the output node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for SampleAndHold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, held: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_held_voltage()
+        )
+    }
+}
+
+impl TryFrom<Component> for SampleAndHold {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::SampleAndHold(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
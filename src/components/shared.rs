@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// The static variables of a component (its nodes and parameters), shared between the clones of
+/// the component until one of them changes them, which then gets its own copy.
+///
+/// Cloning a component therefore only copies its state, so the many trials of a Monte Carlo
+/// analysis share the parameters of every component they don't vary, however long they are
+/// simulated for.
+#[derive(Clone, PartialEq)]
+pub(crate) struct Shared<T>(Arc<T>);
+
+impl<T> Shared<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Returns whether both share the same static variables.
+    #[cfg(test)]
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for Shared<T> {
+    /// Copies the static variables first if they are shared with a clone.
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T: Debug> Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn copy_on_write() {
+        let shared = Shared::new([1.0, 2.0]);
+        let mut clone = shared.clone();
+        assert!(clone.ptr_eq(&shared));
+        assert_eq!(clone[1], 2.0);
+
+        clone[1] = 3.0;
+        assert!(!clone.ptr_eq(&shared));
+        assert_eq!(shared[1], 2.0);
+        assert_eq!(clone[1], 3.0);
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Current scale over which the cathode current of a [`ShuntReference`] fades out as the
+/// reference pin falls below the reference voltage.
+const KNEE_CURRENT: f64 = 1e-4;
+
+/// A TL431 style programmable shunt reference: sinks current from the cathode to the anode to
+/// hold the reference pin at the reference voltage above the anode.
+///
+/// The error between the reference pin and the reference voltage goes through the frequency
+/// compensation, a single pole, and is multiplied by the open-loop gain (a transconductance) to
+/// give the cathode current. The current can't reverse: it fades out smoothly over about 100uA
+/// as the error goes negative, so the model converges from any starting point. The reference
+/// pin draws no current, and neither the minimum cathode voltage nor reverse conduction from
+/// the anode are modelled.
+#[derive(Clone, PartialEq)]
+pub struct ShuntReference {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    error: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`ShuntReference`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    cathode_node: usize,
+    anode_node: usize,
+    reference_node: usize,
+    reference_voltage: f64,
+    transconductance: f64,
+    pole_frequency: Option<f64>,
+}
+
+impl ShuntReference {
+    /// Creates a TL431 with a 2.495V reference, an open-loop gain of 2A/V (a 0.5Ω dynamic
+    /// impedance with the reference pin tied to the cathode) and a compensation pole at 1kHz.
+    pub fn new(cathode_node: usize, anode_node: usize, reference_node: usize) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                cathode_node,
+                anode_node,
+                reference_node,
+                reference_voltage: 2.495,
+                transconductance: 2.0,
+                pole_frequency: Some(1e3),
+            }),
+            error: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Sets the reference voltage, e.g. 1.24V for a TLV431.
+    pub fn with_reference_voltage(mut self, voltage: f64) -> Self {
+        self.shared.reference_voltage = voltage;
+        self
+    }
+
+    /// Sets the open-loop gain, as the transconductance from the reference pin voltage to the
+    /// cathode current in A/V.
+    pub fn with_open_loop_gain(mut self, transconductance: f64) -> Self {
+        self.shared.transconductance = transconductance;
+        self
+    }
+
+    /// Sets the frequency of the compensation pole, or removes it with None so the cathode
+    /// current responds instantly.
+    pub fn with_compensation(mut self, pole_frequency: Option<f64>) -> Self {
+        self.shared.pole_frequency = pole_frequency;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.cathode_node = map(self.shared.cathode_node);
+        self.shared.anode_node = map(self.shared.anode_node);
+        self.shared.reference_node = map(self.shared.reference_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .cathode_node
+            .max(self.shared.anode_node)
+            .max(self.shared.reference_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.cathode_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.anode_node
+    }
+
+    pub fn get_reference_node(&self) -> usize {
+        self.shared.reference_node
+    }
+
+    pub fn get_reference_voltage(&self) -> f64 {
+        self.shared.reference_voltage
+    }
+
+    pub fn get_open_loop_gain(&self) -> f64 {
+        self.shared.transconductance
+    }
+
+    pub fn get_pole_frequency(&self) -> Option<f64> {
+        self.shared.pole_frequency
+    }
+
+    /// Gets the time constant of the compensation pole, zero without one.
+    pub fn get_time_constant(&self) -> f64 {
+        self.shared
+            .pole_frequency
+            .map_or(0.0, |f| 1.0 / (2.0 * std::f64::consts::PI * f))
+    }
+
+    /// Computes the cathode current for the given compensated error voltage, and its derivative
+    /// with respect to it.
+    pub fn cathode_current(&self, error: f64) -> (f64, f64) {
+        // A softplus: linear above zero, fading out exponentially below.
+        let x = self.shared.transconductance * error / KNEE_CURRENT;
+        if x > 30.0 {
+            return (KNEE_CURRENT * x, self.shared.transconductance);
+        }
+        let e = x.exp();
+        (
+            KNEE_CURRENT * e.ln_1p(),
+            self.shared.transconductance * e / (1.0 + e),
+        )
+    }
+
+    /// Gets the compensated error between the reference pin and the reference voltage.
+    pub fn get_error(&self) -> f64 {
+        self.error
+    }
+
+    pub fn set_error(&mut self, error: f64) {
+        self.error = error;
+    }
+
+    /// Gets the cathode to anode voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current sunk from the cathode to the anode.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for ShuntReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, error: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_error()
+        )
+    }
+}
+
+impl TryFrom<Component> for ShuntReference {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::ShuntReference(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
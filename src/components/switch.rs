@@ -0,0 +1,337 @@
+use std::fmt::Debug;
+
+use crate::Rng;
+use crate::components::{Component, Shared};
+
+/// Describes how a switch contact bounces when it closes.
+///
+/// After first touching, the contact stays closed for `closed_duration`, then bounces open for
+/// `open_duration`, and so on for `bounces` bounces before settling closed. Each bounce is
+/// shorter than the previous by a factor of `decay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactBounce {
+    /// Number of times the contact bounces open before settling.
+    pub bounces: usize,
+    /// Duration the contact stays closed before the first bounce.
+    pub closed_duration: f64,
+    /// Duration the contact is open during the first bounce.
+    pub open_duration: f64,
+    /// Ratio between the durations of consecutive bounces.
+    pub decay: f64,
+    /// Relative random variation applied to every duration. Zero gives a fully scripted bounce.
+    pub jitter: f64,
+    /// Seed used to generate the random variation.
+    pub seed: u64,
+}
+
+impl ContactBounce {
+    /// Creates a scripted (non random) bounce profile.
+    pub fn new(bounces: usize, closed_duration: f64, open_duration: f64, decay: f64) -> Self {
+        Self {
+            bounces,
+            closed_duration,
+            open_duration,
+            decay,
+            jitter: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Randomly varies every bounce duration by up to `jitter` (relative) using the given seed.
+    pub fn with_jitter(mut self, jitter: f64, seed: u64) -> Self {
+        self.jitter = jitter;
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the scale factor applied to the `index`th duration.
+    fn jitter_factor(&self, index: u64) -> f64 {
+        if self.jitter == 0.0 {
+            return 1.0;
+        }
+
+        // Jump straight to the index so the bounce can be recomputed at any time without storing
+        // it.
+        let mut rng = Rng::new(self.seed);
+        rng.skip(index);
+        let uniform = rng.uniform();
+
+        1.0 + self.jitter * (2.0 * uniform - 1.0)
+    }
+
+    /// Returns whether the contact is closed `time` seconds after first touching and the time
+    /// since it last changed state.
+    fn state(&self, time: f64) -> (bool, f64) {
+        let mut start = 0.0;
+        let mut scale = 1.0;
+        for bounce in 0..self.bounces as u64 {
+            let closed_end = start + self.closed_duration * scale * self.jitter_factor(2 * bounce);
+            if time < closed_end {
+                return (true, time - start);
+            }
+
+            let open_end =
+                closed_end + self.open_duration * scale * self.jitter_factor(2 * bounce + 1);
+            if time < open_end {
+                return (false, time - closed_end);
+            }
+
+            start = open_end;
+            scale *= self.decay;
+        }
+
+        (true, time - start)
+    }
+}
+
+/// A switch, either closing at a given time (possibly bouncing, and drawing an arc when it
+/// bounces open) or, with [`Switch::with_gate`], controlled by a gate voltage like a MOSFET
+/// driven by a [`PwmController`](crate::components::PwmController).
+#[derive(Clone, PartialEq)]
+pub struct Switch {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+
+    // Computed variables
+    voltage: f64,
+    gate_voltage: f64,
+}
+
+/// The static variables of a [`Switch`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    on_resistance: f64,
+    off_resistance: f64,
+    closing_time: f64,
+    bounce: Option<ContactBounce>,
+    arc_time_constant: Option<f64>,
+    gate: Option<(usize, usize, f64)>,
+}
+
+impl Switch {
+    /// Creates a new switch that is open until `closing_time` and closed after.
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        on_resistance: f64,
+        off_resistance: f64,
+        closing_time: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                on_resistance,
+                off_resistance,
+                closing_time,
+                bounce: None,
+                arc_time_constant: None,
+                gate: None,
+            }),
+            time: 0.0,
+            voltage: 0.0,
+            gate_voltage: 0.0,
+        }
+    }
+
+    /// Makes the contact bounce when it closes.
+    pub fn with_bounce(mut self, bounce: ContactBounce) -> Self {
+        self.shared.bounce = Some(bounce);
+        self
+    }
+
+    /// Models the arc drawn when the contact opens. The arc conductance decays from the on
+    /// conductance to the off conductance with the given time constant.
+    pub fn with_arc(mut self, time_constant: f64) -> Self {
+        self.shared.arc_time_constant = Some(time_constant);
+        self
+    }
+
+    /// Controls the switch by the voltage between the gate nodes instead of the closing time:
+    /// it is closed while that voltage is above the threshold. The bounce and arc are ignored.
+    ///
+    /// The gate is followed within the timestep, so the switch shouldn't drive its own gate
+    /// without some delay in between, or Newton-Raphson may hunt between the two states.
+    pub fn with_gate(
+        mut self,
+        gate_positive_node: usize,
+        gate_negative_node: usize,
+        threshold: f64,
+    ) -> Self {
+        self.shared.gate = Some((gate_positive_node, gate_negative_node, threshold));
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+        if let Some((positive, negative, threshold)) = self.shared.gate {
+            self.shared.gate = Some((map(positive), map(negative), threshold));
+        }
+    }
+
+    pub fn max_node(&self) -> usize {
+        let (gate_positive, gate_negative, _) = self.shared.gate.unwrap_or_default();
+        self.get_positive_node()
+            .max(self.get_negative_node())
+            .max(gate_positive)
+            .max(gate_negative)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_on_resistance(&self) -> f64 {
+        self.shared.on_resistance
+    }
+
+    pub fn get_off_resistance(&self) -> f64 {
+        self.shared.off_resistance
+    }
+
+    pub fn get_closing_time(&self) -> f64 {
+        self.shared.closing_time
+    }
+
+    pub fn get_bounce(&self) -> Option<ContactBounce> {
+        self.shared.bounce
+    }
+
+    pub fn get_arc_time_constant(&self) -> Option<f64> {
+        self.shared.arc_time_constant
+    }
+
+    /// Gets the gate nodes and threshold, see [`Switch::with_gate`].
+    pub fn get_gate(&self) -> Option<(usize, usize, f64)> {
+        self.shared.gate
+    }
+
+    /// Returns whether a gated switch is closed for the given gate voltage.
+    pub fn is_closed_for_gate(&self, gate_voltage: f64) -> bool {
+        self.shared
+            .gate
+            .is_some_and(|(_, _, threshold)| gate_voltage > threshold)
+    }
+
+    /// Gets the conductance of a gated switch for the given gate voltage.
+    pub fn get_conductance_for_gate(&self, gate_voltage: f64) -> f64 {
+        if self.is_closed_for_gate(gate_voltage) {
+            1.0 / self.shared.on_resistance
+        } else {
+            1.0 / self.shared.off_resistance
+        }
+    }
+
+    /// Gets the gate voltage the switch last saw.
+    pub fn get_gate_voltage(&self) -> f64 {
+        self.gate_voltage
+    }
+
+    pub fn set_gate_voltage(&mut self, voltage: f64) {
+        self.gate_voltage = voltage;
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Returns whether the contact is closed at the given time. A gated switch is closed as
+    /// long as the gate it last saw is above the threshold.
+    pub fn is_closed_at(&self, time: f64) -> bool {
+        if self.shared.gate.is_some() {
+            return self.is_closed_for_gate(self.gate_voltage);
+        }
+        self.contact_state(time).0
+    }
+
+    /// Returns whether the contact is closed at the given time, and if it is open after having
+    /// been closed, how long ago it opened.
+    fn contact_state(&self, time: f64) -> (bool, Option<f64>) {
+        if time < self.shared.closing_time {
+            return (false, None);
+        }
+
+        match self.shared.bounce {
+            Some(bounce) => {
+                let (closed, since) = bounce.state(time - self.shared.closing_time);
+                (closed, (!closed).then_some(since))
+            }
+            None => (true, None),
+        }
+    }
+
+    /// Gets the conductance of the switch at the given time, that of a gated switch being
+    /// given by the gate it last saw.
+    pub fn get_conductance_at(&self, time: f64) -> f64 {
+        if self.shared.gate.is_some() {
+            return self.get_conductance_for_gate(self.gate_voltage);
+        }
+
+        let g_on = 1.0 / self.shared.on_resistance;
+        let g_off = 1.0 / self.shared.off_resistance;
+
+        match (self.contact_state(time), self.shared.arc_time_constant) {
+            ((true, _), _) => g_on,
+            ((false, Some(since)), Some(tau)) => g_off + (g_on - g_off) * (-since / tau).exp(),
+            _ => g_off,
+        }
+    }
+
+    pub fn get_conductance(&self) -> f64 {
+        self.get_conductance_at(self.get_time())
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.get_voltage() * self.get_conductance()
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for Switch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power()
+        )
+    }
+}
+
+impl TryFrom<Component> for Switch {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::Switch(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
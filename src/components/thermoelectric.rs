@@ -0,0 +1,232 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A heat flow in watts followed by its derivatives with respect to the current, the hot side
+/// temperature and the cold side temperature.
+pub type HeatFlow = (f64, f64, f64, f64);
+
+/// Parameters of a thermoelectric module, lumped over all of its couples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermoelectricParameters {
+    /// Seebeck coefficient in V/K.
+    pub seebeck: f64,
+    /// Electrical resistance in ohms.
+    pub resistance: f64,
+    /// Thermal conductance between the hot and cold sides in W/K.
+    pub thermal_conductance: f64,
+}
+
+impl ThermoelectricParameters {
+    /// Derives the parameters from the maximum ratings of a Peltier module datasheet, given at
+    /// the hot side temperature `hot_temperature` in kelvin: the current and voltage giving the
+    /// largest temperature difference, and that difference with no heat load.
+    pub fn from_ratings(
+        max_current: f64,
+        max_voltage: f64,
+        max_temperature_difference: f64,
+        hot_temperature: f64,
+    ) -> Self {
+        let cold_temperature = hot_temperature - max_temperature_difference;
+        Self {
+            seebeck: max_voltage / hot_temperature,
+            resistance: cold_temperature * max_voltage / (hot_temperature * max_current),
+            thermal_conductance: cold_temperature * max_voltage * max_current
+                / (2.0 * hot_temperature * max_temperature_difference),
+        }
+    }
+}
+
+/// A thermoelectric module, usable as a Peltier cooler (TEC) or a Seebeck generator (TEG).
+///
+/// The thermal domain is modelled by its electrical analogy: the hot and cold sides are
+/// ordinary nodes whose voltage is the temperature in kelvin and into which heat flows as a
+/// current in watts. Thermal resistances are then resistors, heat capacities capacitors and a
+/// fixed ambient temperature a voltage source, all referenced to node 0 as absolute zero.
+///
+/// Electrically the module is its Seebeck voltage S*(T_hot - T_cold) in series with its
+/// resistance. Current flowing in the positive node pumps S*I*T_cold out of the cold side
+/// (Peltier effect), half of the Joule heating flows to each side and heat leaks back from the
+/// hot to the cold side through the thermal conductance.
+#[derive(Clone, PartialEq)]
+pub struct ThermoelectricModule {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+    hot_temperature: f64,
+    cold_temperature: f64,
+}
+
+/// The static variables of a [`ThermoelectricModule`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    hot_node: usize,
+    cold_node: usize,
+    parameters: ThermoelectricParameters,
+}
+
+impl ThermoelectricModule {
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        hot_node: usize,
+        cold_node: usize,
+        parameters: ThermoelectricParameters,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                hot_node,
+                cold_node,
+                parameters,
+            }),
+            voltage: 0.0,
+            current: 0.0,
+            hot_temperature: 0.0,
+            cold_temperature: 0.0,
+        }
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        // The hot and cold nodes carry heat rather than current, so they are left as they are.
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .positive_node
+            .max(self.shared.negative_node)
+            .max(self.shared.hot_node)
+            .max(self.shared.cold_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_hot_node(&self) -> usize {
+        self.shared.hot_node
+    }
+
+    pub fn get_cold_node(&self) -> usize {
+        self.shared.cold_node
+    }
+
+    pub fn get_parameters(&self) -> ThermoelectricParameters {
+        self.shared.parameters
+    }
+
+    /// Computes the heat absorbed from the cold side and the heat released into the hot side
+    /// for the given current and side temperatures.
+    pub fn heat_flows(
+        &self,
+        current: f64,
+        hot_temperature: f64,
+        cold_temperature: f64,
+    ) -> (HeatFlow, HeatFlow) {
+        let ThermoelectricParameters {
+            seebeck: s,
+            resistance: r,
+            thermal_conductance: k,
+        } = self.shared.parameters;
+        let (i, th, tc) = (current, hot_temperature, cold_temperature);
+
+        let absorbed = (
+            s * i * tc - 0.5 * i * i * r - k * (th - tc),
+            s * tc - i * r,
+            -k,
+            s * i + k,
+        );
+        let released = (
+            s * i * th + 0.5 * i * i * r - k * (th - tc),
+            s * th + i * r,
+            s * i - k,
+            k,
+        );
+        (absorbed, released)
+    }
+
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_hot_temperature(&self) -> f64 {
+        self.hot_temperature
+    }
+
+    pub fn get_cold_temperature(&self) -> f64 {
+        self.cold_temperature
+    }
+
+    pub fn set_temperatures(&mut self, hot_temperature: f64, cold_temperature: f64) {
+        self.hot_temperature = hot_temperature;
+        self.cold_temperature = cold_temperature;
+    }
+
+    /// Gets the heat pumped out of the cold side, in watts.
+    pub fn get_heat_absorbed(&self) -> f64 {
+        let (absorbed, _) =
+            self.heat_flows(self.current, self.hot_temperature, self.cold_temperature);
+        absorbed.0
+    }
+
+    /// Gets the heat released into the hot side, in watts.
+    pub fn get_heat_released(&self) -> f64 {
+        let (_, released) =
+            self.heat_flows(self.current, self.hot_temperature, self.cold_temperature);
+        released.0
+    }
+
+    /// Gets the electrical power consumed, negative when generating.
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for ThermoelectricModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, qc: {}, qh: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_heat_absorbed(),
+            self.get_heat_released()
+        )
+    }
+}
+
+impl TryFrom<Component> for ThermoelectricModule {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::ThermoelectricModule(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
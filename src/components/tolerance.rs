@@ -0,0 +1,150 @@
+/// A range of relative deviations from the nominal value, drawn with the given weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub low: f64,
+    pub high: f64,
+    pub weight: f64,
+}
+
+impl Bin {
+    pub fn new(low: f64, high: f64, weight: f64) -> Self {
+        Self { low, high, weight }
+    }
+}
+
+/// How the value of a component is spread around its nominal value. Deviations are relative, so
+/// a 5% resistor is `Uniform(0.05)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distribution {
+    /// Uniform between -tolerance and +tolerance.
+    Uniform(f64),
+    /// Normal with the given standard deviation, for parts specified as e.g. 1% at 3 sigma
+    /// (`Gaussian(0.01 / 3.0)`).
+    Gaussian(f64),
+    /// The value is the nominal value times e^(sigma*z) for a standard normal z, so it is always
+    /// positive and its median is the nominal value. Suits values spread over a wide range,
+    /// like leakage currents.
+    LogNormal(f64),
+    /// One of the bins drawn by weight, then uniform within it. Models parts sorted by the
+    /// manufacturer, e.g. 5% resistors with the 1% parts taken out.
+    Bins(Vec<Bin>),
+}
+
+impl Distribution {
+    /// Maps a standard normal deviate to a relative deviation from the nominal value.
+    ///
+    /// Every distribution is drawn from a normal deviate (through its cumulative probability
+    /// for the uniform and binned ones) so deviates correlated by a group stay correlated
+    /// whatever the distribution of each member.
+    pub fn deviation(&self, z: f64) -> f64 {
+        match self {
+            Self::Uniform(tolerance) => tolerance * (2.0 * normal_cdf(z) - 1.0),
+            Self::Gaussian(sigma) => sigma * z,
+            Self::LogNormal(sigma) => (sigma * z).exp() - 1.0,
+            Self::Bins(bins) => {
+                let total: f64 = bins.iter().map(|b| b.weight).sum();
+                let mut position = normal_cdf(z) * total;
+                for bin in bins {
+                    if position < bin.weight {
+                        return bin.low + (bin.high - bin.low) * position / bin.weight;
+                    }
+                    position -= bin.weight;
+                }
+                bins.last().map_or(0.0, |b| b.high)
+            }
+        }
+    }
+}
+
+/// The manufacturing tolerance of a component's primary value (see
+/// [`Component::get_value`](crate::components::Component::get_value)), drawn from by
+/// [`MonteCarlo`](crate::analysis::MonteCarlo).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tolerance {
+    pub distribution: Distribution,
+    /// The correlation group the component belongs to, and its correlation with the other
+    /// members.
+    pub group: Option<(usize, f64)>,
+}
+
+impl Tolerance {
+    pub fn new(distribution: Distribution) -> Self {
+        Self {
+            distribution,
+            group: None,
+        }
+    }
+
+    /// Puts the component in a correlation group, for parts that track each other like the
+    /// resistors of one array or the capacitors of one reel.
+    ///
+    /// Members of a group with correlation 1 deviate together, with 0 independently. The
+    /// correlation is that of the underlying normal deviates, between members with the same
+    /// correlation.
+    pub fn with_group(mut self, group: usize, correlation: f64) -> Self {
+        self.group = Some((group, correlation.clamp(0.0, 1.0)));
+        self
+    }
+}
+
+/// The cumulative distribution function of the standard normal distribution.
+pub(crate) fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+/// The complementary error function, with a fractional error below 1.2e-7 (Numerical Recipes
+/// erfcc).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, &c| acc * t + c);
+    let r = t * (-z * z + polynomial).exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_deviations() {
+        assert_relative_eq!(normal_cdf(0.0), 0.5, epsilon = 1e-7);
+        assert_relative_eq!(normal_cdf(1.0), 0.841344746, epsilon = 1e-7);
+        assert_relative_eq!(normal_cdf(-2.0), 0.022750132, epsilon = 1e-7);
+
+        assert_relative_eq!(
+            Distribution::Uniform(0.05).deviation(0.0),
+            0.0,
+            epsilon = 1e-7
+        );
+        assert!(Distribution::Uniform(0.05).deviation(10.0) <= 0.05);
+        assert_relative_eq!(Distribution::Gaussian(0.01).deviation(-2.0), -0.02);
+        assert_relative_eq!(
+            Distribution::LogNormal(1.0).deviation(1.0),
+            std::f64::consts::E - 1.0
+        );
+
+        // 5% parts with the 1% parts taken out never land within 1%.
+        let bins = Distribution::Bins(vec![Bin::new(-0.05, -0.01, 1.0), Bin::new(0.01, 0.05, 1.0)]);
+        assert_relative_eq!(bins.deviation(-10.0), -0.05, epsilon = 1e-7);
+        assert_relative_eq!(bins.deviation(-1e-3), -0.01, epsilon = 1e-4);
+        assert_relative_eq!(bins.deviation(1e-3), 0.01, epsilon = 1e-4);
+        assert_relative_eq!(bins.deviation(10.0), 0.05, epsilon = 1e-7);
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// A behavioral CMOS transmission gate: an NMOS and a PMOS switch in parallel between two
+/// terminals, turned on by the voltage of the control node above the ground node.
+///
+/// While on, each device conducts in proportion to its overdrive (square law devices in their
+/// linear region, with matched devices and threshold voltage): the NMOS turns off as the signal
+/// level (the average of the terminal voltages above the ground node) approaches the positive
+/// rail, the PMOS as it approaches the negative rail. The on-resistance is therefore flat over
+/// the middle of the supply, where both devices conduct, and lower towards the rails, where one
+/// device conducts with a larger overdrive. While off, only the off-resistance and the
+/// feedthrough capacitance (see [`TransmissionGate::with_off_capacitance`]) connect the
+/// terminals, setting the off isolation.
+///
+/// The control is high while above the middle of the supply, and followed within the timestep
+/// like [`Switch::with_gate`](crate::components::Switch::with_gate). When the gate turns off,
+/// the charge injected by its channels (see [`TransmissionGate::with_charge_injection`]) is
+/// split equally between the terminals over that timestep.
+#[derive(Clone, PartialEq)]
+pub struct TransmissionGate {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    control_voltage: f64,
+    voltage: f64,
+
+    // Computed variables
+    signal_voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`TransmissionGate`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    control_node: usize,
+    ground_node: usize,
+    on_resistance: f64,
+    off_resistance: f64,
+    positive_rail: f64,
+    negative_rail: f64,
+    device_threshold: f64,
+    active_low: bool,
+    off_capacitance: f64,
+    charge_injection: f64,
+}
+
+impl TransmissionGate {
+    /// Creates a transmission gate on a 5V supply with 0.7V device thresholds, a 1GΩ
+    /// off-resistance and no feedthrough capacitance or charge injection, on while the control
+    /// is high. The on-resistance is that over the middle of the supply.
+    pub fn new(
+        positive_node: usize,
+        negative_node: usize,
+        control_node: usize,
+        ground_node: usize,
+        on_resistance: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                control_node,
+                ground_node,
+                on_resistance,
+                off_resistance: 1e9,
+                positive_rail: 5.0,
+                negative_rail: 0.0,
+                device_threshold: 0.7,
+                active_low: false,
+                off_capacitance: 0.0,
+                charge_injection: 0.0,
+            }),
+            control_voltage: 0.0,
+            voltage: 0.0,
+            signal_voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Sets the supply rails above the ground node and the threshold voltage of the devices.
+    /// The supply must span more than twice the threshold voltage.
+    pub fn with_supply(
+        mut self,
+        positive_rail: f64,
+        negative_rail: f64,
+        device_threshold: f64,
+    ) -> Self {
+        self.shared.positive_rail = positive_rail;
+        self.shared.negative_rail = negative_rail;
+        self.shared.device_threshold = device_threshold;
+        self
+    }
+
+    pub fn with_off_resistance(mut self, off_resistance: f64) -> Self {
+        self.shared.off_resistance = off_resistance;
+        self
+    }
+
+    /// Turns the gate on while the control is low instead.
+    pub fn with_active_low(mut self) -> Self {
+        self.shared.active_low = true;
+        self
+    }
+
+    /// Adds the capacitance between the terminals through which signals feed through the gate
+    /// while off, limiting its off isolation at high frequencies.
+    pub fn with_off_capacitance(mut self, capacitance: f64) -> Self {
+        self.shared.off_capacitance = capacitance;
+        self
+    }
+
+    /// Sets the charge released by the channels when the gate turns off, half of it into each
+    /// terminal (negative for gates dominated by the electrons of the NMOS channel).
+    pub fn with_charge_injection(mut self, charge: f64) -> Self {
+        self.shared.charge_injection = charge;
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+        self.shared.control_node = map(self.shared.control_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .positive_node
+            .max(self.shared.negative_node)
+            .max(self.shared.control_node)
+            .max(self.shared.ground_node)
+    }
+
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.positive_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.negative_node
+    }
+
+    pub fn get_control_node(&self) -> usize {
+        self.shared.control_node
+    }
+
+    pub fn get_ground_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    /// Gets the on-resistance over the middle of the supply.
+    pub fn get_on_resistance(&self) -> f64 {
+        self.shared.on_resistance
+    }
+
+    pub fn set_on_resistance(&mut self, on_resistance: f64) {
+        self.shared.on_resistance = on_resistance;
+    }
+
+    pub fn get_off_resistance(&self) -> f64 {
+        self.shared.off_resistance
+    }
+
+    /// Gets the positive and negative rails and the threshold voltage of the devices.
+    pub fn get_supply(&self) -> (f64, f64, f64) {
+        (
+            self.shared.positive_rail,
+            self.shared.negative_rail,
+            self.shared.device_threshold,
+        )
+    }
+
+    pub fn get_off_capacitance(&self) -> f64 {
+        self.shared.off_capacitance
+    }
+
+    pub fn get_charge_injection(&self) -> f64 {
+        self.shared.charge_injection
+    }
+
+    /// Returns whether the gate is on for the given control voltage.
+    pub fn is_on_for_control(&self, control_voltage: f64) -> bool {
+        let high = control_voltage > (self.shared.positive_rail + self.shared.negative_rail) / 2.0;
+        high != self.shared.active_low
+    }
+
+    /// Returns whether the gate is on, as of the end of the last timestep.
+    pub fn is_on(&self) -> bool {
+        self.is_on_for_control(self.control_voltage)
+    }
+
+    /// Computes the conductance of the channels at the given signal level and its derivative
+    /// with respect to the signal level, zero while the gate is off. Signal levels beyond the
+    /// rails are clamped to them.
+    pub fn channel_conductance(&self, control_voltage: f64, signal_voltage: f64) -> (f64, f64) {
+        if !self.is_on_for_control(control_voltage) {
+            return (0.0, 0.0);
+        }
+        let vt = self.shared.device_threshold;
+        // Matched devices whose conductances add up to the on-conductance over the middle of
+        // the supply.
+        let k = 1.0
+            / (self.shared.on_resistance
+                * (self.shared.positive_rail - self.shared.negative_rail - 2.0 * vt));
+        let clamped = signal_voltage.clamp(self.shared.negative_rail, self.shared.positive_rail);
+        let slope = if clamped == signal_voltage { k } else { 0.0 };
+
+        let nmos_overdrive = self.shared.positive_rail - vt - clamped;
+        let pmos_overdrive = clamped - self.shared.negative_rail - vt;
+        let mut conductance = 0.0;
+        let mut derivative = 0.0;
+        if nmos_overdrive > 0.0 {
+            conductance += k * nmos_overdrive;
+            derivative -= slope;
+        }
+        if pmos_overdrive > 0.0 {
+            conductance += k * pmos_overdrive;
+            derivative += slope;
+        }
+        (conductance, derivative)
+    }
+
+    /// Gets the resistance between the terminals at the given signal level while on.
+    pub fn on_resistance_at(&self, signal_voltage: f64) -> f64 {
+        let control = if self.shared.active_low {
+            self.shared.negative_rail
+        } else {
+            self.shared.positive_rail
+        };
+        let (conductance, _) = self.channel_conductance(control, signal_voltage);
+        1.0 / (conductance + 1.0 / self.shared.off_resistance)
+    }
+
+    /// Gets the control voltage the gate last saw.
+    pub fn get_control_voltage(&self) -> f64 {
+        self.control_voltage
+    }
+
+    pub fn set_control_voltage(&mut self, voltage: f64) {
+        self.control_voltage = voltage;
+    }
+
+    /// Gets the signal level, the average of the terminal voltages above the ground node.
+    pub fn get_signal_voltage(&self) -> f64 {
+        self.signal_voltage
+    }
+
+    pub fn set_signal_voltage(&mut self, voltage: f64) {
+        self.signal_voltage = voltage;
+    }
+
+    /// Gets the voltage between the terminals.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current through the gate from the positive to the negative terminal.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for TransmissionGate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, on: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.is_on()
+        )
+    }
+}
+
+impl TryFrom<Component> for TransmissionGate {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::TransmissionGate(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt::Debug;
+
+use crate::components::{Component, Shared};
+
+/// Fraction of a cycle within which the phase of a [`Vco`] counts as reaching an edge, so
+/// timesteps cut to land on the edge (see [`Vco::get_breakpoint`]) switch the output despite
+/// rounding.
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+/// A behavioral voltage controlled oscillator: its frequency is the center frequency plus the
+/// gain (in Hz/V) times the voltage of the control node above the ground node, clamped to the
+/// tuning range, and it drives a square wave out of the output node.
+///
+/// The phase is integrated between timesteps at the frequency the timestep ended with, and the
+/// output is driven high (to the output voltage, through the output resistance) over the first
+/// half of each cycle and low (to the ground node) over the second. Each edge is a breakpoint,
+/// so timesteps cut to land on them (see
+/// [`BESolver::step_to_breakpoint`](crate::BESolver::step_to_breakpoint)) switch exactly.
+///
+/// With a gain of zero it is a fixed frequency clock, e.g. the reference of a PLL (see
+/// [`PhaseFrequencyDetector`](crate::components::PhaseFrequencyDetector)).
+#[derive(Clone, PartialEq)]
+pub struct Vco {
+    // Static variables
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
+    phase: f64,
+    frequency: f64,
+
+    // Computed variables
+    voltage: f64,
+    current: f64,
+}
+
+/// The static variables of a [`Vco`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    control_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    center_frequency: f64,
+    gain: f64,
+    min_frequency: f64,
+    max_frequency: f64,
+    output_voltage: f64,
+    output_resistance: f64,
+}
+
+impl Vco {
+    /// Creates a VCO with an unlimited tuning range above 0Hz, starting at the center
+    /// frequency on a rising edge. It drives its output to 1V through 1Ω.
+    pub fn new(
+        control_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        center_frequency: f64,
+        gain: f64,
+    ) -> Self {
+        Self {
+            shared: Shared::new(Parameters {
+                control_node,
+                output_node,
+                ground_node,
+                center_frequency,
+                gain,
+                min_frequency: 0.0,
+                max_frequency: f64::INFINITY,
+                output_voltage: 1.0,
+                output_resistance: 1.0,
+            }),
+            time: 0.0,
+            phase: 0.0,
+            frequency: center_frequency,
+            voltage: 0.0,
+            current: 0.0,
+        }
+    }
+
+    /// Creates a fixed frequency clock between the output and ground nodes.
+    pub fn clock(output_node: usize, ground_node: usize, frequency: f64) -> Self {
+        Self::new(ground_node, output_node, ground_node, frequency, 0.0)
+    }
+
+    /// Limits the frequency to the tuning range.
+    pub fn with_frequency_range(mut self, min_frequency: f64, max_frequency: f64) -> Self {
+        self.shared.min_frequency = min_frequency;
+        self.shared.max_frequency = max_frequency;
+        self
+    }
+
+    /// Sets the voltage the output is driven to while high and the resistance it is driven
+    /// through.
+    pub fn with_output_drive(mut self, voltage: f64, resistance: f64) -> Self {
+        self.shared.output_voltage = voltage;
+        self.shared.output_resistance = resistance;
+        self
+    }
+
+    /// Starts the oscillator at the given phase, in cycles.
+    pub fn with_phase(mut self, phase: f64) -> Self {
+        self.phase = phase.rem_euclid(1.0);
+        self
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.control_node = map(self.shared.control_node);
+        self.shared.output_node = map(self.shared.output_node);
+        self.shared.ground_node = map(self.shared.ground_node);
+    }
+
+    pub fn max_node(&self) -> usize {
+        self.shared
+            .control_node
+            .max(self.shared.output_node)
+            .max(self.shared.ground_node)
+    }
+
+    /// Gets the output node.
+    pub fn get_positive_node(&self) -> usize {
+        self.shared.output_node
+    }
+
+    pub fn get_negative_node(&self) -> usize {
+        self.shared.ground_node
+    }
+
+    pub fn get_control_node(&self) -> usize {
+        self.shared.control_node
+    }
+
+    pub fn get_center_frequency(&self) -> f64 {
+        self.shared.center_frequency
+    }
+
+    pub fn set_center_frequency(&mut self, frequency: f64) {
+        self.shared.center_frequency = frequency;
+    }
+
+    /// Gets the gain from the control voltage to the frequency, in Hz/V.
+    pub fn get_gain(&self) -> f64 {
+        self.shared.gain
+    }
+
+    pub fn get_frequency_range(&self) -> (f64, f64) {
+        (self.shared.min_frequency, self.shared.max_frequency)
+    }
+
+    pub fn get_output_drive(&self) -> (f64, f64) {
+        (self.shared.output_voltage, self.shared.output_resistance)
+    }
+
+    /// Computes the frequency at the given control voltage.
+    pub fn frequency_at(&self, control_voltage: f64) -> f64 {
+        (self.shared.center_frequency + self.shared.gain * control_voltage)
+            .min(self.shared.max_frequency)
+            .max(self.shared.min_frequency)
+    }
+
+    /// Gets the frequency the oscillator ran at over the last timestep.
+    pub fn get_frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Gets the phase within the present cycle, in cycles.
+    pub fn get_phase(&self) -> f64 {
+        self.phase
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns whether the output is driven high.
+    pub fn is_high(&self) -> bool {
+        self.phase < 0.5
+    }
+
+    /// Gets the time of the next edge at the present frequency.
+    pub fn get_breakpoint(&self) -> Option<f64> {
+        if self.frequency <= 0.0 {
+            return None;
+        }
+        let edge = if self.is_high() { 0.5 } else { 1.0 };
+        Some(self.time + (edge - self.phase) / self.frequency)
+    }
+
+    /// Advances the phase over a timestep dt at the frequency of the control voltage it ended
+    /// with.
+    pub fn advance(&mut self, dt: f64, control_voltage: f64) {
+        self.time += dt;
+        self.frequency = self.frequency_at(control_voltage);
+
+        let edge = if self.is_high() { 0.5 } else { 1.0 };
+        self.phase += self.frequency * dt;
+        if self.phase < edge && edge - self.phase < EDGE_TOLERANCE {
+            self.phase = edge;
+        }
+        self.phase = self.phase.rem_euclid(1.0);
+    }
+
+    /// Gets the output voltage.
+    pub fn get_voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.voltage = voltage;
+    }
+
+    /// Gets the current delivered out of the output node.
+    pub fn get_current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    pub fn get_power(&self) -> f64 {
+        self.get_voltage() * self.get_current()
+    }
+}
+
+impl Debug for Vco {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{v: {}, i: {}, p: {}, f: {}, phase: {}}}",
+            self.get_voltage(),
+            self.get_current(),
+            self.get_power(),
+            self.get_frequency(),
+            self.get_phase()
+        )
+    }
+}
+
+impl TryFrom<Component> for Vco {
+    type Error = ();
+
+    fn try_from(value: Component) -> Result<Self, Self::Error> {
+        match value {
+            Component::Vco(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
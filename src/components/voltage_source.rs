@@ -1,42 +1,156 @@
 use std::fmt::Debug;
 
-use crate::components::Component;
+use nalgebra::Complex;
 
-#[derive(Clone, Copy, PartialEq)]
+use crate::components::{Component, Shared};
+
+#[derive(Clone, PartialEq)]
 pub struct VoltageSource {
     // Static variables
-    positive_node: usize,
-    negative_node: usize,
-    voltage: f64,
+    pub(super) shared: Shared<Parameters>,
+
+    // State variables
+    time: f64,
 
     // Computed variables
     current: f64,
 }
 
+/// The static variables of a [`VoltageSource`] (see [`Shared`]).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Parameters {
+    positive_node: usize,
+    negative_node: usize,
+    voltage: f64,
+    ac_magnitude: f64,
+    ac_phase: f64,
+    ramp_time: Option<f64>,
+    series_resistance: f64,
+}
+
 impl VoltageSource {
     pub fn new(positive_node: usize, negative_node: usize, voltage: f64) -> Self {
         Self {
-            positive_node,
-            negative_node,
-            voltage,
+            shared: Shared::new(Parameters {
+                positive_node,
+                negative_node,
+                voltage,
+                ac_magnitude: 0.0,
+                ac_phase: 0.0,
+                ramp_time: None,
+                series_resistance: 0.0,
+            }),
+            time: 0.0,
             current: 0.0,
         }
     }
 
+    /// Creates the Thevenin equivalent of a Norton source: a current source in parallel with a
+    /// resistance becomes a voltage source of `current * resistance` with that resistance in
+    /// series.
+    pub fn from_norton(
+        positive_node: usize,
+        negative_node: usize,
+        current: f64,
+        resistance: f64,
+    ) -> Self {
+        Self::new(positive_node, negative_node, current * resistance)
+            .with_series_resistance(resistance)
+    }
+
+    /// Gives the source an internal resistance in series with its output. The source is ideal
+    /// (zero resistance) by default.
+    pub fn with_series_resistance(mut self, resistance: f64) -> Self {
+        self.shared.series_resistance = resistance;
+        self
+    }
+
+    pub fn get_series_resistance(&self) -> f64 {
+        self.shared.series_resistance
+    }
+
+    /// Gets the current of the Norton equivalent of the source, the current it drives into a
+    /// short circuit. Returns None for an ideal source, which has no Norton equivalent.
+    pub fn get_norton_current(&self) -> Option<f64> {
+        (self.shared.series_resistance != 0.0)
+            .then(|| self.shared.voltage / self.shared.series_resistance)
+    }
+
+    /// Sets the small signal excitation of the source used in AC analysis. The phase is in
+    /// degrees.
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.shared.ac_magnitude = magnitude;
+        self.shared.ac_phase = phase;
+        self
+    }
+
+    /// Gets the small signal excitation of the source used in AC analysis as a phasor.
+    pub fn get_ac_phasor(&self) -> Complex<f64> {
+        Complex::from_polar(self.shared.ac_magnitude, self.shared.ac_phase.to_radians())
+    }
+
+    /// Ramps the source linearly from zero to its voltage over the given time at the start of the
+    /// simulation (soft start), instead of stepping to it instantly.
+    pub fn with_ramp(mut self, ramp_time: f64) -> Self {
+        self.shared.ramp_time = Some(ramp_time);
+        self
+    }
+
+    pub fn get_ramp_time(&self) -> Option<f64> {
+        self.shared.ramp_time
+    }
+
+    /// Gets the time elapsed since the start of the simulation.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Gets the voltage the source outputs at the given time, taking the ramp into account.
+    pub fn get_voltage_at(&self, time: f64) -> f64 {
+        match self.shared.ramp_time {
+            Some(ramp_time) if time < ramp_time => self.shared.voltage * time / ramp_time,
+            _ => self.shared.voltage,
+        }
+    }
+
+    /// Gets the voltage the source outputs at present.
+    pub fn get_output_voltage(&self) -> f64 {
+        self.get_voltage_at(self.get_time())
+    }
+
+    /// Gets the voltage at the terminals of the source at present, its output voltage less the
+    /// drop across its series resistance.
+    pub fn get_terminal_voltage(&self) -> f64 {
+        self.get_output_voltage() - self.shared.series_resistance * self.get_current()
+    }
+
+    pub(crate) fn map_nodes(&mut self, mut map: impl FnMut(usize) -> usize) {
+        self.shared.positive_node = map(self.shared.positive_node);
+        self.shared.negative_node = map(self.shared.negative_node);
+    }
+
     pub fn max_node(&self) -> usize {
         self.get_positive_node().max(self.get_negative_node())
     }
 
     pub fn get_positive_node(&self) -> usize {
-        self.positive_node
+        self.shared.positive_node
     }
 
     pub fn get_negative_node(&self) -> usize {
-        self.negative_node
+        self.shared.negative_node
     }
 
     pub fn get_voltage(&self) -> f64 {
-        self.voltage
+        self.shared.voltage
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.shared.voltage = voltage;
     }
 
     pub fn get_current(&self) -> f64 {
@@ -47,8 +161,9 @@ impl VoltageSource {
         self.current = current;
     }
 
+    /// Gets the power delivered to the circuit at the terminals of the source.
     pub fn get_power(&self) -> f64 {
-        self.get_voltage() * self.get_current()
+        self.get_terminal_voltage() * self.get_current()
     }
 }
 
@@ -57,7 +172,7 @@ impl Debug for VoltageSource {
         write!(
             f,
             "{{v: {}, i: {}, p: {}}}",
-            self.get_voltage(),
+            self.get_terminal_voltage(),
             self.get_current(),
             self.get_power()
         )
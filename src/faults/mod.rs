@@ -0,0 +1,370 @@
+use crate::components::{Netlist, Resistor};
+
+/// Resistance used to replace a component that has failed open.
+const OPEN_RESISTANCE: f64 = 1e12;
+
+/// Resistance used to short two nodes together.
+const SHORT_RESISTANCE: f64 = 1e-6;
+
+/// What happens to the circuit when a fault occurs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaultKind {
+    /// The component at the given index fails open. It is replaced by a very large resistance
+    /// so the indices of the other components stay the same.
+    Open(usize),
+    /// The two nodes are shorted together by a very small resistance.
+    Short(usize, usize),
+    /// The primary value of the component at the given index (see
+    /// [`Component::get_value`](crate::components::Component::get_value)) is multiplied by the
+    /// given factor. For example, a capacitor degrading to 10% of its value is
+    /// `ScaleValue(index, 0.1)`.
+    ScaleValue(usize, f64),
+}
+
+impl FaultKind {
+    /// Checks that the fault can be injected into the netlist. Faults keep the indices of the
+    /// components in place, so a schedule checked before a simulation stays valid throughout.
+    pub fn check(&self, netlist: &Netlist) -> Result<(), FaultError> {
+        match *self {
+            Self::Open(index) if index >= netlist.get_components().len() => {
+                Err(FaultError::UnknownComponent(index))
+            }
+            Self::ScaleValue(index, _) => match netlist.get_components().get(index) {
+                None => Err(FaultError::UnknownComponent(index)),
+                Some(component) if component.get_value().is_none() => {
+                    Err(FaultError::NoValue(index))
+                }
+                Some(_) => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Why a fault can't be injected into a netlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultError {
+    /// The fault refers to a component the netlist doesn't have.
+    UnknownComponent(usize),
+    /// The fault scales the value of a component without a primary value.
+    NoValue(usize),
+}
+
+/// What causes a fault to occur.
+pub enum FaultTrigger {
+    /// The fault occurs once the simulation time reaches the given time.
+    Time(f64),
+    /// The fault occurs the first time the condition holds for the netlist.
+    Condition(Box<dyn Fn(&Netlist) -> bool + Send + Sync>),
+}
+
+/// A named fault that is injected into the circuit when its trigger fires.
+pub struct Fault {
+    name: String,
+    trigger: FaultTrigger,
+    kind: FaultKind,
+}
+
+impl Fault {
+    pub fn new(name: impl Into<String>, trigger: FaultTrigger, kind: FaultKind) -> Self {
+        Self {
+            name: name.into(),
+            trigger,
+            kind,
+        }
+    }
+
+    /// Creates a fault that occurs at the given time.
+    pub fn at(name: impl Into<String>, time: f64, kind: FaultKind) -> Self {
+        Self::new(name, FaultTrigger::Time(time), kind)
+    }
+
+    /// Creates a fault that occurs the first time the condition holds.
+    pub fn when(
+        name: impl Into<String>,
+        condition: impl Fn(&Netlist) -> bool + Send + Sync + 'static,
+        kind: FaultKind,
+    ) -> Self {
+        Self::new(name, FaultTrigger::Condition(Box::new(condition)), kind)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_trigger(&self) -> &FaultTrigger {
+        &self.trigger
+    }
+
+    pub fn get_kind(&self) -> FaultKind {
+        self.kind
+    }
+
+    fn is_triggered(&self, netlist: &Netlist, time: f64) -> bool {
+        match &self.trigger {
+            FaultTrigger::Time(t) => time >= *t,
+            FaultTrigger::Condition(condition) => condition(netlist),
+        }
+    }
+
+    /// Modifies the netlist to inject the fault, leaving it as it was if the fault can't be
+    /// injected (see [`FaultKind::check`]).
+    pub fn inject(&self, netlist: &mut Netlist) -> Result<(), FaultError> {
+        self.kind.check(netlist)?;
+        match self.kind {
+            FaultKind::Open(index) => {
+                let component = &netlist.get_components()[index];
+                let open = Resistor::new(
+                    component.get_positive_node(),
+                    component.get_negative_node(),
+                    OPEN_RESISTANCE,
+                );
+                netlist.replace_component(index, open);
+            }
+            FaultKind::Short(a, b) => {
+                netlist.add_component(Resistor::new(a, b, SHORT_RESISTANCE));
+            }
+            FaultKind::ScaleValue(index, factor) => {
+                let component = netlist.get_component_mut(index);
+                if let Some(value) = component.get_value() {
+                    component.set_value(value * factor);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records when a fault was injected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultActivation {
+    pub name: String,
+    pub time: f64,
+}
+
+/// A set of faults to inject over the course of a simulation.
+///
+/// A [`TransientAnalysis`](crate::analysis::TransientAnalysis) run with
+/// [`run_with_faults`](crate::analysis::TransientAnalysis::run_with_faults) injects the faults
+/// and records their activations in its result. Driving a solver by hand,
+/// [`FaultSchedule::apply`] should be called before every timestep with the current simulation
+/// time.
+#[derive(Default)]
+pub struct FaultSchedule {
+    faults: Vec<Fault>,
+    injected: Vec<bool>,
+    activations: Vec<FaultActivation>,
+}
+
+impl FaultSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a fault to the schedule.
+    pub fn add_fault(&mut self, fault: Fault) -> &mut Self {
+        self.faults.push(fault);
+        self.injected.push(false);
+        self
+    }
+
+    /// Checks that every fault can be injected into the netlist.
+    pub fn check(&self, netlist: &Netlist) -> Result<(), FaultError> {
+        self.faults
+            .iter()
+            .try_for_each(|fault| fault.get_kind().check(netlist))
+    }
+
+    /// Injects every fault whose trigger has fired and which hasn't been injected yet.
+    ///
+    /// Returns true if any fault was injected, or the error of the first fault that couldn't
+    /// be, leaving it and the faults after it to be injected.
+    pub fn apply(&mut self, netlist: &mut Netlist, time: f64) -> Result<bool, FaultError> {
+        let mut any = false;
+        for (fault, injected) in self.faults.iter().zip(self.injected.iter_mut()) {
+            if *injected || !fault.is_triggered(netlist, time) {
+                continue;
+            }
+
+            fault.inject(netlist)?;
+            *injected = true;
+            any = true;
+            self.activations.push(FaultActivation {
+                name: fault.get_name().to_string(),
+                time,
+            });
+        }
+
+        Ok(any)
+    }
+
+    /// Gets the earliest time after the given one at which a fault that hasn't been injected
+    /// yet is scheduled, so a timestep can end on it.
+    pub fn next_time(&self, time: f64) -> Option<f64> {
+        self.faults
+            .iter()
+            .zip(&self.injected)
+            .filter(|&(_, &injected)| !injected)
+            .filter_map(|(fault, _)| match fault.get_trigger() {
+                FaultTrigger::Time(t) if *t > time => Some(*t),
+                _ => None,
+            })
+            .min_by(f64::total_cmp)
+    }
+
+    /// Gets the names of the faults that have been injected so far.
+    pub fn get_active_faults(&self) -> impl Iterator<Item = &str> {
+        self.activations.iter().map(|a| a.name.as_str())
+    }
+
+    /// Gets the record of when each fault was injected, in the order they were injected.
+    pub fn get_activations(&self) -> &Vec<FaultActivation> {
+        &self.activations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Capacitor, CurrentProbe, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    fn divider() -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0));
+        netlist
+    }
+
+    fn run(netlist: &mut Netlist, faults: &mut FaultSchedule, steps: usize) -> Resistor {
+        let mut solver = BESolver::new(netlist);
+        for _ in 0..steps {
+            let time = solver.get_time();
+            faults.apply(solver.get_netlist_mut(), time).unwrap();
+            solver.solve(0.001).unwrap();
+        }
+
+        solver.get_netlist().get_components()[1]
+            .clone()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_open_fault() {
+        let mut netlist = divider();
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("R3 open", 0.0045, FaultKind::Open(2)));
+
+        let r = run(&mut netlist, &mut faults, 4);
+        assert_relative_eq!(r.get_voltage(), 5.0, max_relative = 0.001);
+        assert_eq!(faults.get_active_faults().count(), 0);
+
+        let mut netlist = divider();
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("R3 open", 0.0045, FaultKind::Open(2)));
+
+        let r = run(&mut netlist, &mut faults, 6);
+        assert_relative_eq!(r.get_voltage(), 0.0, epsilon = 0.001);
+        assert_eq!(faults.get_activations().len(), 1);
+        assert_eq!(faults.get_activations()[0].name, "R3 open");
+        assert_relative_eq!(
+            faults.get_activations()[0].time,
+            0.005,
+            max_relative = 0.001
+        );
+    }
+
+    #[test]
+    fn test_open_voltage_source() {
+        // Node 2 is held at 5V until its source fails open, removing the source's current from
+        // the system mid-run.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(VoltageSource::new(2, 0, 5.0));
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("V2 open", 0.002, FaultKind::Open(2)));
+
+        let mut solver = BESolver::new(&mut netlist);
+        let mut voltages = Vec::new();
+        for _ in 0..4 {
+            let time = solver.get_time();
+            faults.apply(solver.get_netlist_mut(), time).unwrap();
+            solver.solve(0.001).unwrap();
+            voltages.push(solver.get_node_voltage(2));
+        }
+        assert_relative_eq!(voltages[1], 5.0, max_relative = 1e-9);
+        assert_relative_eq!(voltages[3], 10.0, max_relative = 1e-6);
+        assert_eq!(solver.get_layout().get_dimension(), 3);
+    }
+
+    #[test]
+    fn test_invalid_fault() {
+        let mut netlist = divider();
+        netlist.add_component(CurrentProbe::new(2, 3));
+        let original = netlist.get_components().clone();
+
+        let fault = Fault::at("R9 open", 0.0, FaultKind::Open(9));
+        assert_eq!(
+            fault.inject(&mut netlist),
+            Err(FaultError::UnknownComponent(9))
+        );
+        let fault = Fault::at("probe drift", 0.0, FaultKind::ScaleValue(3, 2.0));
+        assert_eq!(fault.inject(&mut netlist), Err(FaultError::NoValue(3)));
+        assert_eq!(netlist.get_components(), &original);
+
+        let mut faults = FaultSchedule::new();
+        faults
+            .add_fault(Fault::at("R3 open", 0.0, FaultKind::Open(2)))
+            .add_fault(fault);
+        assert_eq!(faults.check(&netlist), Err(FaultError::NoValue(3)));
+        assert_eq!(faults.apply(&mut netlist, 0.0), Err(FaultError::NoValue(3)));
+        assert_eq!(faults.get_activations().len(), 1);
+    }
+
+    #[test]
+    fn test_short_fault() {
+        let mut netlist = divider();
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::at("node 2 short", 0.0, FaultKind::Short(2, 0)));
+
+        let r = run(&mut netlist, &mut faults, 1);
+        assert_relative_eq!(r.get_voltage(), 10.0, max_relative = 0.001);
+    }
+
+    #[test]
+    fn test_conditional_scale_fault() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1.0))
+            .add_component(Capacitor::new(2, 0, 1.0, 0.0));
+
+        let mut faults = FaultSchedule::new();
+        faults.add_fault(Fault::when(
+            "R2 degrades",
+            |netlist| {
+                let c: Capacitor = netlist.get_components()[2].clone().try_into().unwrap();
+                c.get_voltage() > 0.5
+            },
+            FaultKind::ScaleValue(1, 10.0),
+        ));
+
+        run(&mut netlist, &mut faults, 1000);
+
+        let r: Resistor = netlist.get_components()[1].clone().try_into().unwrap();
+        assert_relative_eq!(r.get_resistance(), 10.0);
+        assert_eq!(
+            faults.get_active_faults().collect::<Vec<_>>(),
+            ["R2 degrades"]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec;
+
+use crate::fmi::FmuError;
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Version 2.0 of the zip format, the first with deflate.
+const VERSION: u16 = 20;
+/// 1980-01-01, the earliest date a zip archive holds.
+const DATE: u16 = 0x21;
+
+/// Writes a zip archive of the files, given by their path in the archive and their contents,
+/// each deflated.
+pub(crate) fn write_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut directory = Vec::new();
+
+    for &(path, contents) in files {
+        let offset = bytes.len() as u32;
+        let compressed = compress_to_vec(contents, 6);
+        let crc = crc32(contents);
+
+        // The fields common to the local and central headers, from the version needed.
+        let mut fields = Vec::new();
+        put_u16(&mut fields, VERSION);
+        put_u16(&mut fields, 0);
+        put_u16(&mut fields, DEFLATED);
+        put_u16(&mut fields, 0);
+        put_u16(&mut fields, DATE);
+        put_u32(&mut fields, crc);
+        put_u32(&mut fields, compressed.len() as u32);
+        put_u32(&mut fields, contents.len() as u32);
+        put_u16(&mut fields, path.len() as u16);
+        put_u16(&mut fields, 0);
+
+        put_u32(&mut bytes, LOCAL_HEADER);
+        bytes.extend_from_slice(&fields);
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.extend_from_slice(&compressed);
+
+        put_u32(&mut directory, CENTRAL_HEADER);
+        put_u16(&mut directory, VERSION);
+        directory.extend_from_slice(&fields);
+        // No comment, on the first disk, with no attributes.
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u32(&mut directory, 0);
+        put_u32(&mut directory, offset);
+        directory.extend_from_slice(path.as_bytes());
+    }
+
+    let offset = bytes.len() as u32;
+    bytes.extend_from_slice(&directory);
+    put_u32(&mut bytes, END_OF_CENTRAL_DIRECTORY);
+    put_u16(&mut bytes, 0);
+    put_u16(&mut bytes, 0);
+    put_u16(&mut bytes, files.len() as u16);
+    put_u16(&mut bytes, files.len() as u16);
+    put_u32(&mut bytes, directory.len() as u32);
+    put_u32(&mut bytes, offset);
+    put_u16(&mut bytes, 0);
+    bytes
+}
+
+/// Reads the files of a zip archive, stored or deflated, as their path in the archive and
+/// their contents. Directories are left out.
+pub(crate) fn read_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, FmuError> {
+    // The end of central directory record is followed by a comment of at most 64kB.
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|&k| get_u32(bytes, k) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or(FmuError::InvalidArchive)?;
+    let count = get_u16(bytes, end + 10).ok_or(FmuError::InvalidArchive)?;
+    let mut entry = get_u32(bytes, end + 16).ok_or(FmuError::InvalidArchive)? as usize;
+
+    let mut files = Vec::new();
+    for _ in 0..count {
+        if get_u32(bytes, entry) != Some(CENTRAL_HEADER) {
+            return Err(FmuError::InvalidArchive);
+        }
+        let field = |offset: usize| get_u32(bytes, entry + offset).ok_or(FmuError::InvalidArchive);
+        let short = |offset: usize| get_u16(bytes, entry + offset).ok_or(FmuError::InvalidArchive);
+        let flags = short(8)?;
+        let method = short(10)?;
+        let crc = field(16)?;
+        let compressed_size = field(20)? as usize;
+        let size = field(24)? as usize;
+        let name_length = short(28)? as usize;
+        let extra_length = short(30)? as usize;
+        let comment_length = short(32)? as usize;
+        let header = field(42)? as usize;
+        let name = bytes
+            .get(entry + 46..entry + 46 + name_length)
+            .ok_or(FmuError::InvalidArchive)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        entry += 46 + name_length + extra_length + comment_length;
+
+        // Encrypted files aren't supported, nor ZIP64 archives, whose sizes are all ones.
+        if flags & 1 != 0 || compressed_size == u32::MAX as usize {
+            return Err(FmuError::InvalidArchive);
+        }
+        if name.ends_with('/') {
+            continue;
+        }
+
+        if get_u32(bytes, header) != Some(LOCAL_HEADER) {
+            return Err(FmuError::InvalidArchive);
+        }
+        let local_name_length = get_u16(bytes, header + 26).ok_or(FmuError::InvalidArchive)?;
+        let local_extra_length = get_u16(bytes, header + 28).ok_or(FmuError::InvalidArchive)?;
+        let start = header + 30 + local_name_length as usize + local_extra_length as usize;
+        let data = bytes
+            .get(start..start + compressed_size)
+            .ok_or(FmuError::InvalidArchive)?;
+
+        let contents = match method {
+            STORED => data.to_vec(),
+            DEFLATED => decompress_to_vec(data).map_err(|_| FmuError::InvalidArchive)?,
+            _ => return Err(FmuError::UnsupportedCompression(method)),
+        };
+        if contents.len() != size || crc32(&contents) != crc {
+            return Err(FmuError::InvalidArchive);
+        }
+        files.push((name, contents));
+    }
+    Ok(files)
+}
+
+/// Computes the CRC-32 of the bytes, as zip archives check their files with.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn get_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let description = b"<fmiModelDescription/>".repeat(100);
+        let archive = write_zip(&[
+            ("modelDescription.xml", &description),
+            ("resources/empty", &[]),
+        ]);
+        assert_eq!(
+            read_zip(&archive),
+            Ok(vec![
+                ("modelDescription.xml".to_string(), description),
+                ("resources/empty".to_string(), Vec::new()),
+            ])
+        );
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+        // A flipped bit of the compressed contents fails the CRC, a cut archive has no end of
+        // central directory record.
+        let mut corrupted = archive.clone();
+        corrupted[60] ^= 1;
+        assert!(read_zip(&corrupted).is_err());
+        assert_eq!(
+            read_zip(&archive[..archive.len() - 10]),
+            Err(FmuError::InvalidArchive)
+        );
+    }
+}
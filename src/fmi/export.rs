@@ -0,0 +1,561 @@
+//! The implementation of the `fmi2*` functions [`export_fmu!`](crate::export_fmu) defines.
+//!
+//! The functions take the pointers of the FMI 2.0 C API, which must be valid as the standard
+//! requires, so their safety isn't documented one by one.
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{CStr, CString};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::slice;
+
+use crate::fmi::archive::write_zip;
+use crate::fmi::ffi::*;
+use crate::fmi::{CircuitSlave, CoSimulationSlave, FmiError, binary_path, model_identifier};
+
+/// Defines the `fmi2*` functions of the FMI 2.0 C API for co-simulation in a library built as
+/// a `cdylib`, exporting a slave as the binary of an FMU.
+///
+/// The first argument is the GUID of the model description the binary is packaged with (see
+/// [`package_fmu`](crate::fmi::package_fmu)), which importers pass back when instantiating it.
+/// The second is a function creating the slave, called for every instance and when one is
+/// reset. Only real variables are supported and the FMU state can't be saved.
+///
+/// ```ignore
+/// fn rc_filter() -> CircuitSlave {
+///     CircuitSlave::new(&netlist(), 10e-6)
+///         .with_input("u", 0)
+///         .with_output("y", Probe::NodeVoltage(2))
+/// }
+///
+/// rice::export_fmu!("{8c4e810f-3df3-4a00-8276-176fa3c9f000}", rc_filter);
+/// ```
+#[macro_export]
+macro_rules! export_fmu {
+    ($guid:expr, $slave:path) => {
+        const _: () = {
+            use $crate::fmi::export;
+            use $crate::fmi::ffi::*;
+
+            #[unsafe(no_mangle)]
+            extern "C" fn fmi2GetTypesPlatform() -> Fmi2String {
+                c"default".as_ptr()
+            }
+
+            #[unsafe(no_mangle)]
+            extern "C" fn fmi2GetVersion() -> Fmi2String {
+                c"2.0".as_ptr()
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetDebugLogging(
+                c: Fmi2Component,
+                _logging_on: Fmi2Boolean,
+                _n_categories: usize,
+                _categories: *const Fmi2String,
+            ) -> Fmi2Status {
+                unsafe { export::call(c, "fmi2SetDebugLogging", |_| Ok(())) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2Instantiate(
+                instance_name: Fmi2String,
+                fmu_type: Fmi2Type,
+                fmu_guid: Fmi2String,
+                _resource_location: Fmi2String,
+                functions: *const Fmi2CallbackFunctions,
+                _visible: Fmi2Boolean,
+                _logging_on: Fmi2Boolean,
+            ) -> Fmi2Component {
+                unsafe {
+                    export::instantiate(
+                        $guid,
+                        || ::std::boxed::Box::new($slave()),
+                        instance_name,
+                        fmu_type,
+                        fmu_guid,
+                        functions,
+                    )
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2FreeInstance(c: Fmi2Component) {
+                unsafe { export::free_instance(c) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetupExperiment(
+                c: Fmi2Component,
+                _tolerance_defined: Fmi2Boolean,
+                _tolerance: f64,
+                start_time: f64,
+                _stop_time_defined: Fmi2Boolean,
+                _stop_time: f64,
+            ) -> Fmi2Status {
+                unsafe { export::setup_experiment(c, start_time) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2EnterInitializationMode(c: Fmi2Component) -> Fmi2Status {
+                unsafe { export::call(c, "fmi2EnterInitializationMode", |_| Ok(())) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2ExitInitializationMode(c: Fmi2Component) -> Fmi2Status {
+                unsafe { export::call(c, "fmi2ExitInitializationMode", |_| Ok(())) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2Terminate(c: Fmi2Component) -> Fmi2Status {
+                unsafe { export::call(c, "fmi2Terminate", |_| Ok(())) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2Reset(c: Fmi2Component) -> Fmi2Status {
+                unsafe { export::reset(c) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetReal(
+                c: Fmi2Component,
+                vr: *const Fmi2ValueReference,
+                nvr: usize,
+                value: *mut f64,
+            ) -> Fmi2Status {
+                unsafe { export::get_real(c, vr, nvr, value) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetInteger(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *mut Fmi2Integer,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2GetInteger", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetBoolean(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *mut Fmi2Boolean,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2GetBoolean", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetString(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *mut Fmi2String,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2GetString", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetReal(
+                c: Fmi2Component,
+                vr: *const Fmi2ValueReference,
+                nvr: usize,
+                value: *const f64,
+            ) -> Fmi2Status {
+                unsafe { export::set_real(c, vr, nvr, value) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetInteger(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *const Fmi2Integer,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2SetInteger", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetBoolean(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *const Fmi2Boolean,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2SetBoolean", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetString(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                nvr: usize,
+                _value: *const Fmi2String,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported_values(c, "fmi2SetString", nvr) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetFMUstate(
+                c: Fmi2Component,
+                _state: *mut Fmi2FmuState,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2GetFMUstate") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetFMUstate(
+                c: Fmi2Component,
+                _state: Fmi2FmuState,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2SetFMUstate") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2FreeFMUstate(
+                c: Fmi2Component,
+                _state: *mut Fmi2FmuState,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2FreeFMUstate") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SerializedFMUstateSize(
+                c: Fmi2Component,
+                _state: Fmi2FmuState,
+                _size: *mut usize,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2SerializedFMUstateSize") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SerializeFMUstate(
+                c: Fmi2Component,
+                _state: Fmi2FmuState,
+                _bytes: *mut Fmi2Byte,
+                _size: usize,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2SerializeFMUstate") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2DeSerializeFMUstate(
+                c: Fmi2Component,
+                _bytes: *const Fmi2Byte,
+                _size: usize,
+                _state: *mut Fmi2FmuState,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2DeSerializeFMUstate") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetDirectionalDerivative(
+                c: Fmi2Component,
+                _unknown: *const Fmi2ValueReference,
+                _n_unknown: usize,
+                _known: *const Fmi2ValueReference,
+                _n_known: usize,
+                _known_seed: *const f64,
+                _unknown_sensitivity: *mut f64,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2GetDirectionalDerivative") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2SetRealInputDerivatives(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                _nvr: usize,
+                _order: *const Fmi2Integer,
+                _value: *const f64,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2SetRealInputDerivatives") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetRealOutputDerivatives(
+                c: Fmi2Component,
+                _vr: *const Fmi2ValueReference,
+                _nvr: usize,
+                _order: *const Fmi2Integer,
+                _value: *mut f64,
+            ) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2GetRealOutputDerivatives") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2DoStep(
+                c: Fmi2Component,
+                current_communication_point: f64,
+                communication_step_size: f64,
+                _no_set_fmu_state_prior_to_current_point: Fmi2Boolean,
+            ) -> Fmi2Status {
+                unsafe { export::do_step(c, current_communication_point, communication_step_size) }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2CancelStep(c: Fmi2Component) -> Fmi2Status {
+                unsafe { export::unsupported(c, "fmi2CancelStep") }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetStatus(
+                _c: Fmi2Component,
+                _kind: Fmi2StatusKind,
+                _value: *mut Fmi2Status,
+            ) -> Fmi2Status {
+                FMI2_DISCARD
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetRealStatus(
+                _c: Fmi2Component,
+                _kind: Fmi2StatusKind,
+                _value: *mut f64,
+            ) -> Fmi2Status {
+                FMI2_DISCARD
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetIntegerStatus(
+                _c: Fmi2Component,
+                _kind: Fmi2StatusKind,
+                _value: *mut Fmi2Integer,
+            ) -> Fmi2Status {
+                FMI2_DISCARD
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetBooleanStatus(
+                _c: Fmi2Component,
+                _kind: Fmi2StatusKind,
+                _value: *mut Fmi2Boolean,
+            ) -> Fmi2Status {
+                FMI2_DISCARD
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn fmi2GetStringStatus(
+                _c: Fmi2Component,
+                _kind: Fmi2StatusKind,
+                _value: *mut Fmi2String,
+            ) -> Fmi2Status {
+                FMI2_DISCARD
+            }
+        };
+    };
+}
+
+/// Creates the slave of an instance.
+pub type SlaveFactory = fn() -> Box<dyn CoSimulationSlave>;
+
+/// An instance of an exported FMU, which `fmi2Component`s point to.
+pub struct Instance {
+    slave: Box<dyn CoSimulationSlave>,
+    factory: SlaveFactory,
+    name: CString,
+    callbacks: Fmi2CallbackFunctions,
+}
+
+impl Instance {
+    fn log(&self, status: Fmi2Status, message: &str) {
+        log(&self.callbacks, &self.name, status, message);
+    }
+}
+
+/// Logs the message through the logger of the importer, if there is one. The message is passed
+/// as the format with its `%` escaped, as loggers are `printf`-like.
+fn log(callbacks: &Fmi2CallbackFunctions, name: &CStr, status: Fmi2Status, message: &str) {
+    let Some(logger) = callbacks.logger else {
+        return;
+    };
+    let category = if status == FMI2_FATAL {
+        c"logStatusFatal"
+    } else {
+        c"logStatusError"
+    };
+    let message = CString::new(message.replace('%', "%%").replace('\0', "")).unwrap();
+    unsafe {
+        logger(
+            callbacks.component_environment,
+            name.as_ptr(),
+            status,
+            category.as_ptr(),
+            message.as_ptr(),
+        )
+    };
+}
+
+/// Reads `count` elements, allowing a null pointer when there are none.
+unsafe fn elements<'a, T>(pointer: *const T, count: usize) -> &'a [T] {
+    if count == 0 {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(pointer, count) }
+    }
+}
+
+unsafe fn string(pointer: Fmi2String) -> String {
+    if pointer.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(pointer) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Runs the call on the instance, logging errors and panics and turning them into statuses.
+pub unsafe fn call(
+    c: Fmi2Component,
+    call: &str,
+    f: impl FnOnce(&mut Instance) -> Result<(), FmiError>,
+) -> Fmi2Status {
+    let Some(instance) = (unsafe { c.cast::<Instance>().as_mut() }) else {
+        return FMI2_ERROR;
+    };
+    match catch_unwind(AssertUnwindSafe(|| f(instance))) {
+        Ok(Ok(())) => FMI2_OK,
+        Ok(Err(error)) => {
+            instance.log(FMI2_ERROR, &format!("{call} failed: {error:?}"));
+            FMI2_ERROR
+        }
+        Err(_) => {
+            instance.log(FMI2_FATAL, &format!("{call} panicked"));
+            FMI2_FATAL
+        }
+    }
+}
+
+/// Creates an instance simulating a new slave, or returns null if the instance isn't for
+/// co-simulation or the GUID isn't the one the binary was exported with.
+pub unsafe fn instantiate(
+    guid: &str,
+    factory: SlaveFactory,
+    instance_name: Fmi2String,
+    fmu_type: Fmi2Type,
+    fmu_guid: Fmi2String,
+    functions: *const Fmi2CallbackFunctions,
+) -> Fmi2Component {
+    let Some(&callbacks) = (unsafe { functions.as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+    let name = CString::new(unsafe { string(instance_name) }).unwrap();
+
+    let error = if fmu_type != FMI2_CO_SIMULATION {
+        Some("the FMU only supports co-simulation".to_string())
+    } else if unsafe { string(fmu_guid) } != guid {
+        Some(format!("the GUID isn't the FMU's {guid}"))
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        log(&callbacks, &name, FMI2_ERROR, &error);
+        return std::ptr::null_mut();
+    }
+
+    match catch_unwind(factory) {
+        Ok(slave) => Box::into_raw(Box::new(Instance {
+            slave,
+            factory,
+            name,
+            callbacks,
+        }))
+        .cast(),
+        Err(_) => {
+            log(&callbacks, &name, FMI2_FATAL, "creating the slave panicked");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+pub unsafe fn free_instance(c: Fmi2Component) {
+    if !c.is_null() {
+        drop(unsafe { Box::from_raw(c.cast::<Instance>()) });
+    }
+}
+
+pub unsafe fn setup_experiment(c: Fmi2Component, start_time: f64) -> Fmi2Status {
+    unsafe {
+        call(c, "fmi2SetupExperiment", |instance| {
+            instance.slave.setup_experiment(start_time)
+        })
+    }
+}
+
+/// Replaces the slave with a new one.
+pub unsafe fn reset(c: Fmi2Component) -> Fmi2Status {
+    unsafe {
+        call(c, "fmi2Reset", |instance| {
+            instance.slave = (instance.factory)();
+            Ok(())
+        })
+    }
+}
+
+pub unsafe fn get_real(
+    c: Fmi2Component,
+    vr: *const Fmi2ValueReference,
+    nvr: usize,
+    value: *mut f64,
+) -> Fmi2Status {
+    let references = unsafe { elements(vr, nvr) };
+    unsafe {
+        call(c, "fmi2GetReal", |instance| {
+            let values = instance.slave.get_real(references)?;
+            if nvr > 0 {
+                slice::from_raw_parts_mut(value, nvr).copy_from_slice(&values);
+            }
+            Ok(())
+        })
+    }
+}
+
+pub unsafe fn set_real(
+    c: Fmi2Component,
+    vr: *const Fmi2ValueReference,
+    nvr: usize,
+    value: *const f64,
+) -> Fmi2Status {
+    let (references, values) = unsafe { (elements(vr, nvr), elements(value, nvr)) };
+    unsafe {
+        call(c, "fmi2SetReal", |instance| {
+            instance.slave.set_real(references, values)
+        })
+    }
+}
+
+pub unsafe fn do_step(c: Fmi2Component, current_time: f64, step: f64) -> Fmi2Status {
+    unsafe {
+        call(c, "fmi2DoStep", |instance| {
+            instance.slave.do_step(current_time, step)
+        })
+    }
+}
+
+/// Fails for variables other than reals, which the FMU doesn't have.
+pub unsafe fn unsupported_values(c: Fmi2Component, call: &str, nvr: usize) -> Fmi2Status {
+    if nvr == 0 {
+        return unsafe { self::call(c, call, |_| Ok(())) };
+    }
+    unsafe { unsupported(c, call) }
+}
+
+pub unsafe fn unsupported(c: Fmi2Component, call: &str) -> Fmi2Status {
+    let Some(instance) = (unsafe { c.cast::<Instance>().as_ref() }) else {
+        return FMI2_ERROR;
+    };
+    instance.log(FMI2_ERROR, &format!("{call} isn't supported"));
+    FMI2_ERROR
+}
+
+/// Packages an FMU for co-simulation of the slave: its model description and the binary for
+/// this platform, a library built with [`export_fmu!`](crate::export_fmu) for the same slave
+/// and GUID.
+pub fn package_fmu(slave: &CircuitSlave, model_name: &str, guid: &str, library: &[u8]) -> Vec<u8> {
+    let description = slave.model_description(model_name, guid);
+    write_zip(&[
+        ("modelDescription.xml", description.as_bytes()),
+        (&binary_path(&model_identifier(model_name)), library),
+    ])
+}
@@ -0,0 +1,79 @@
+//! The types of the FMI 2.0 C API for co-simulation, as declared by `fmi2TypesPlatform.h` and
+//! `fmi2FunctionTypes.h`.
+
+use std::ffi::{c_char, c_int, c_uint, c_void};
+
+/// An instance of an FMU, opaque to the importer.
+pub type Fmi2Component = *mut c_void;
+/// A pointer the importer passes to the callbacks, opaque to the FMU.
+pub type Fmi2ComponentEnvironment = *mut c_void;
+pub type Fmi2ValueReference = c_uint;
+pub type Fmi2Integer = c_int;
+pub type Fmi2Boolean = c_int;
+pub type Fmi2String = *const c_char;
+pub type Fmi2Byte = c_char;
+
+pub const FMI2_TRUE: Fmi2Boolean = 1;
+pub const FMI2_FALSE: Fmi2Boolean = 0;
+
+/// The status returned by every call (`fmi2Status`).
+pub type Fmi2Status = c_int;
+pub const FMI2_OK: Fmi2Status = 0;
+pub const FMI2_WARNING: Fmi2Status = 1;
+pub const FMI2_DISCARD: Fmi2Status = 2;
+pub const FMI2_ERROR: Fmi2Status = 3;
+pub const FMI2_FATAL: Fmi2Status = 4;
+pub const FMI2_PENDING: Fmi2Status = 5;
+
+/// The interface an FMU is instantiated for (`fmi2Type`).
+pub type Fmi2Type = c_int;
+pub const FMI2_MODEL_EXCHANGE: Fmi2Type = 0;
+pub const FMI2_CO_SIMULATION: Fmi2Type = 1;
+
+/// The status asked for by `fmi2GetStatus` and its variants (`fmi2StatusKind`).
+pub type Fmi2StatusKind = c_int;
+
+/// An FMU state saved by `fmi2GetFMUstate`, opaque to the importer.
+pub type Fmi2FmuState = *mut c_void;
+
+/// Logs a message, formatted like `printf` from the message and the variadic arguments.
+pub type Fmi2CallbackLogger = unsafe extern "C" fn(
+    Fmi2ComponentEnvironment,
+    Fmi2String,
+    Fmi2Status,
+    Fmi2String,
+    Fmi2String,
+    ...
+);
+
+/// The callbacks the importer hands to `fmi2Instantiate` (`fmi2CallbackFunctions`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Fmi2CallbackFunctions {
+    pub logger: Option<Fmi2CallbackLogger>,
+    pub allocate_memory: Option<unsafe extern "C" fn(usize, usize) -> *mut c_void>,
+    pub free_memory: Option<unsafe extern "C" fn(*mut c_void)>,
+    pub step_finished: Option<unsafe extern "C" fn(Fmi2ComponentEnvironment, Fmi2Status)>,
+    pub component_environment: Fmi2ComponentEnvironment,
+}
+
+pub type Fmi2InstantiateFn = unsafe extern "C" fn(
+    Fmi2String,
+    Fmi2Type,
+    Fmi2String,
+    Fmi2String,
+    *const Fmi2CallbackFunctions,
+    Fmi2Boolean,
+    Fmi2Boolean,
+) -> Fmi2Component;
+pub type Fmi2FreeInstanceFn = unsafe extern "C" fn(Fmi2Component);
+pub type Fmi2SetupExperimentFn =
+    unsafe extern "C" fn(Fmi2Component, Fmi2Boolean, f64, f64, Fmi2Boolean, f64) -> Fmi2Status;
+/// `fmi2EnterInitializationMode`, `fmi2ExitInitializationMode`, `fmi2Terminate` and
+/// `fmi2Reset`.
+pub type Fmi2ComponentFn = unsafe extern "C" fn(Fmi2Component) -> Fmi2Status;
+pub type Fmi2SetRealFn =
+    unsafe extern "C" fn(Fmi2Component, *const Fmi2ValueReference, usize, *const f64) -> Fmi2Status;
+pub type Fmi2GetRealFn =
+    unsafe extern "C" fn(Fmi2Component, *const Fmi2ValueReference, usize, *mut f64) -> Fmi2Status;
+pub type Fmi2DoStepFn = unsafe extern "C" fn(Fmi2Component, f64, f64, Fmi2Boolean) -> Fmi2Status;
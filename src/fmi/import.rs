@@ -0,0 +1,456 @@
+use std::ffi::{CStr, CString, c_void};
+use std::fmt::Debug;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, mem, process};
+
+use libloading::Library;
+
+use crate::fmi::archive::read_zip;
+use crate::fmi::ffi::*;
+use crate::fmi::{
+    Causality, CoSimulationSlave, FmiError, FmuError, ModelDescription, ValueReference, binary_path,
+};
+
+unsafe extern "C" {
+    fn calloc(count: usize, size: usize) -> *mut c_void;
+    fn free(pointer: *mut c_void);
+}
+
+/// The functions of the binary of an FMU that a slave calls after instantiating it.
+struct Functions {
+    free_instance: Fmi2FreeInstanceFn,
+    setup_experiment: Fmi2SetupExperimentFn,
+    enter_initialization_mode: Fmi2ComponentFn,
+    exit_initialization_mode: Fmi2ComponentFn,
+    terminate: Fmi2ComponentFn,
+    reset: Fmi2ComponentFn,
+    set_real: Fmi2SetRealFn,
+    get_real: Fmi2GetRealFn,
+    do_step: Fmi2DoStepFn,
+}
+
+/// An FMU for co-simulation loaded as a slave, such as a mechanical or thermal model exported by
+/// another tool.
+///
+/// The archive is extracted to a temporary folder, removed when the slave is dropped, and the
+/// binary for this platform is loaded and instantiated from it. Only the real inputs and outputs
+/// of the model description can be set and read.
+pub struct FmuSlave {
+    description: ModelDescription,
+    component: Fmi2Component,
+    functions: Functions,
+    /// The messages the FMU logged, which its callbacks point to.
+    messages: Box<Mutex<Vec<String>>>,
+    /// The callbacks the FMU was instantiated with, which it may keep a pointer to.
+    _callbacks: Box<Fmi2CallbackFunctions>,
+    initialized: bool,
+    library: Option<Library>,
+    directory: PathBuf,
+}
+
+// The FMU is only called through the slave, which owns the instance.
+unsafe impl Send for FmuSlave {}
+
+impl FmuSlave {
+    /// Extracts the archive of an FMU and instantiates its binary with the given instance name.
+    pub fn load(archive: &[u8], instance_name: &str) -> Result<Self, FmuError> {
+        let files = read_zip(archive)?;
+        let (_, xml) = files
+            .iter()
+            .find(|(path, _)| path == "modelDescription.xml")
+            .ok_or_else(|| FmuError::MissingFile("modelDescription.xml".to_string()))?;
+        let description = ModelDescription::parse(&String::from_utf8_lossy(xml))?;
+        let identifier = description
+            .model_identifier
+            .as_deref()
+            .ok_or(FmuError::NotCoSimulation)?;
+        let binary = binary_path(identifier);
+        if !files.iter().any(|(path, _)| *path == binary) {
+            return Err(FmuError::MissingFile(binary));
+        }
+
+        let directory = extract(&files)?;
+        let slave = unsafe { Self::instantiate(description, &directory, &binary, instance_name) };
+        if slave.is_err() {
+            let _ = fs::remove_dir_all(&directory);
+        }
+        slave
+    }
+
+    unsafe fn instantiate(
+        description: ModelDescription,
+        directory: &Path,
+        binary: &str,
+        instance_name: &str,
+    ) -> Result<Self, FmuError> {
+        let library = unsafe { Library::new(directory.join(binary)) }
+            .map_err(|error| FmuError::Library(error.to_string()))?;
+        let functions = unsafe {
+            Functions {
+                free_instance: symbol(&library, "fmi2FreeInstance")?,
+                setup_experiment: symbol(&library, "fmi2SetupExperiment")?,
+                enter_initialization_mode: symbol(&library, "fmi2EnterInitializationMode")?,
+                exit_initialization_mode: symbol(&library, "fmi2ExitInitializationMode")?,
+                terminate: symbol(&library, "fmi2Terminate")?,
+                reset: symbol(&library, "fmi2Reset")?,
+                set_real: symbol(&library, "fmi2SetReal")?,
+                get_real: symbol(&library, "fmi2GetReal")?,
+                do_step: symbol(&library, "fmi2DoStep")?,
+            }
+        };
+        let instantiate: Fmi2InstantiateFn = unsafe { symbol(&library, "fmi2Instantiate")? };
+
+        let messages = Box::new(Mutex::new(Vec::new()));
+        // Loggers are variadic, which Rust can't define. The arguments after the message are
+        // passed after the fixed ones on every platform, so a logger taking the fixed ones only
+        // receives the message unformatted.
+        let logger = unsafe {
+            mem::transmute::<
+                unsafe extern "C" fn(
+                    Fmi2ComponentEnvironment,
+                    Fmi2String,
+                    Fmi2Status,
+                    Fmi2String,
+                    Fmi2String,
+                ),
+                Fmi2CallbackLogger,
+            >(log)
+        };
+        let callbacks = Box::new(Fmi2CallbackFunctions {
+            logger: Some(logger),
+            allocate_memory: Some(calloc),
+            free_memory: Some(free),
+            step_finished: None,
+            component_environment: (&*messages as *const Mutex<Vec<String>>).cast_mut().cast(),
+        });
+
+        let to_c_string = |text: &str| CString::new(text.replace('\0', "")).unwrap();
+        let name = to_c_string(instance_name);
+        let guid = to_c_string(&description.guid);
+        let resources = to_c_string(&file_uri(&directory.join("resources")));
+        let component = unsafe {
+            instantiate(
+                name.as_ptr(),
+                FMI2_CO_SIMULATION,
+                guid.as_ptr(),
+                resources.as_ptr(),
+                &*callbacks,
+                FMI2_FALSE,
+                FMI2_FALSE,
+            )
+        };
+        if component.is_null() {
+            let message = messages.lock().unwrap().pop();
+            return Err(FmuError::InstantiationFailed(message));
+        }
+
+        Ok(Self {
+            description,
+            component,
+            functions,
+            messages,
+            _callbacks: callbacks,
+            initialized: false,
+            library: Some(library),
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    pub fn get_model_description(&self) -> &ModelDescription {
+        &self.description
+    }
+
+    /// Finds the value reference of the real variable with the given name.
+    pub fn find_variable(&self, name: &str) -> Option<ValueReference> {
+        self.description
+            .variables
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| v.reference)
+    }
+
+    fn causality(&self, reference: ValueReference) -> Result<Causality, FmiError> {
+        self.description
+            .variables
+            .iter()
+            .find(|v| v.reference == reference)
+            .map(|v| v.causality)
+            .ok_or(FmiError::UnknownReference(reference))
+    }
+
+    /// Checks the status of a call, with the last message the FMU logged if it failed.
+    fn check(&self, call: &'static str, status: Fmi2Status) -> Result<(), FmiError> {
+        let mut messages = self.messages.lock().unwrap();
+        let message = messages.pop();
+        messages.clear();
+        if status <= FMI2_WARNING {
+            Ok(())
+        } else {
+            Err(FmiError::Rejected { call, message })
+        }
+    }
+}
+
+impl CoSimulationSlave for FmuSlave {
+    /// Initializes the FMU to start at the given time, resetting it first if it already was.
+    fn setup_experiment(&mut self, start_time: f64) -> Result<(), FmiError> {
+        let (functions, component) = (&self.functions, self.component);
+        if self.initialized {
+            self.check("fmi2Reset", unsafe { (functions.reset)(component) })?;
+            self.initialized = false;
+        }
+        let status = unsafe {
+            (functions.setup_experiment)(component, FMI2_FALSE, 0.0, start_time, FMI2_FALSE, 0.0)
+        };
+        self.check("fmi2SetupExperiment", status)?;
+        let status = unsafe { (functions.enter_initialization_mode)(component) };
+        self.check("fmi2EnterInitializationMode", status)?;
+        let status = unsafe { (functions.exit_initialization_mode)(component) };
+        self.check("fmi2ExitInitializationMode", status)?;
+        self.initialized = true;
+        Ok(())
+    }
+
+    fn set_real(&mut self, references: &[ValueReference], values: &[f64]) -> Result<(), FmiError> {
+        for &reference in references {
+            if self.causality(reference)? != Causality::Input {
+                return Err(FmiError::NotAnInput(reference));
+            }
+        }
+        let count = references.len().min(values.len());
+        let status = unsafe {
+            (self.functions.set_real)(self.component, references.as_ptr(), count, values.as_ptr())
+        };
+        self.check("fmi2SetReal", status)
+    }
+
+    fn get_real(&self, references: &[ValueReference]) -> Result<Vec<f64>, FmiError> {
+        for &reference in references {
+            self.causality(reference)?;
+        }
+        let mut values = vec![0.0; references.len()];
+        let status = unsafe {
+            (self.functions.get_real)(
+                self.component,
+                references.as_ptr(),
+                references.len(),
+                values.as_mut_ptr(),
+            )
+        };
+        self.check("fmi2GetReal", status)?;
+        Ok(values)
+    }
+
+    fn do_step(&mut self, current_time: f64, step: f64) -> Result<(), FmiError> {
+        let status =
+            unsafe { (self.functions.do_step)(self.component, current_time, step, FMI2_TRUE) };
+        self.check("fmi2DoStep", status)
+            .map_err(|_| FmiError::StepFailed { time: current_time })
+    }
+}
+
+impl Drop for FmuSlave {
+    fn drop(&mut self) {
+        unsafe {
+            if self.initialized {
+                (self.functions.terminate)(self.component);
+            }
+            (self.functions.free_instance)(self.component);
+        }
+        // The binary is unloaded before removing it, which some platforms require.
+        drop(self.library.take());
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+impl Debug for FmuSlave {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FmuSlave")
+            .field("description", &self.description)
+            .field("initialized", &self.initialized)
+            .field("directory", &self.directory)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Records a message the FMU logged in the messages its environment points to.
+unsafe extern "C" fn log(
+    environment: Fmi2ComponentEnvironment,
+    _instance_name: Fmi2String,
+    _status: Fmi2Status,
+    _category: Fmi2String,
+    message: Fmi2String,
+) {
+    let Some(messages) = (unsafe { environment.cast::<Mutex<Vec<String>>>().as_ref() }) else {
+        return;
+    };
+    if message.is_null() {
+        return;
+    }
+    // The message is a format, so escaped percent signs are unescaped.
+    let message = unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .replace("%%", "%");
+    if let Ok(mut messages) = messages.lock() {
+        messages.push(message);
+    }
+}
+
+/// Resolves a function of the binary.
+unsafe fn symbol<T: Copy>(library: &Library, name: &'static str) -> Result<T, FmuError> {
+    unsafe { library.get::<T>(name.as_bytes()) }
+        .map(|symbol| *symbol)
+        .map_err(|_| FmuError::MissingFunction(name))
+}
+
+/// Extracts the files of an FMU to a new temporary folder.
+fn extract(files: &[(String, Vec<u8>)]) -> Result<PathBuf, FmuError> {
+    static EXTRACTED: AtomicUsize = AtomicUsize::new(0);
+    let directory = env::temp_dir().join(format!(
+        "rice-fmu-{}-{}",
+        process::id(),
+        EXTRACTED.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let write = |path: &str, contents: &[u8]| {
+        // Paths leaving the folder, such as absolute ones, are rejected.
+        if !Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(FmuError::InvalidArchive);
+        }
+        let target = directory.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|error| FmuError::Io(error.to_string()))?;
+        }
+        fs::write(target, contents).map_err(|error| FmuError::Io(error.to_string()))
+    };
+    for (path, contents) in files {
+        if let Err(error) = write(path, contents) {
+            let _ = fs::remove_dir_all(&directory);
+            return Err(error);
+        }
+    }
+    Ok(directory)
+}
+
+/// The `file` URI of an absolute path.
+fn file_uri(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace('%', "%25")
+        .replace(' ', "%20");
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Capacitor, Netlist, Resistor, VoltageSource};
+    use crate::fmi::archive::write_zip;
+    use crate::fmi::{CircuitSlave, package_fmu};
+    use crate::results::Probe;
+
+    /// The GUID of the `rc_filter_fmu` example.
+    const GUID: &str = "{8c4e810f-3df3-4a00-8276-176fa3c9f000}";
+
+    /// Packages the `rc_filter_fmu` example, built alongside the tests, as an FMU.
+    fn rc_filter_fmu(guid: &str) -> Vec<u8> {
+        let examples = env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("examples");
+        let library = fs::read(examples.join(format!("{DLL_PREFIX}rc_filter_fmu{DLL_SUFFIX}")))
+            .expect("the rc_filter_fmu example is built by cargo test --features fmu");
+
+        // The slave of the example, for its model description.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        let slave = CircuitSlave::new(&netlist, 10e-6)
+            .with_input("u", 0)
+            .with_output("y", Probe::NodeVoltage(2));
+        package_fmu(&slave, "RC filter", guid, &library)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut slave = FmuSlave::load(&rc_filter_fmu(GUID), "filter").unwrap();
+        assert_eq!(slave.get_model_description().model_name, "RC filter");
+        let directory = slave.directory.clone();
+        assert!(directory.join("binaries").is_dir());
+        let (u, y) = (
+            slave.find_variable("u").unwrap(),
+            slave.find_variable("y").unwrap(),
+        );
+
+        slave.setup_experiment(0.0).unwrap();
+        slave.set_real(&[u], &[1.0]).unwrap();
+        slave.do_step(0.0, 1e-3).unwrap();
+        assert_relative_eq!(slave.get_real(&[y]).unwrap()[0], 0.632, epsilon = 0.01);
+        slave.do_step(1e-3, 4e-3).unwrap();
+        assert_relative_eq!(slave.get_real(&[y]).unwrap()[0], 0.993, epsilon = 0.01);
+        assert_eq!(slave.get_real(&[u]).unwrap(), vec![1.0]);
+
+        // Setting up again resets the FMU to the initial conditions of the circuit.
+        slave.setup_experiment(0.0).unwrap();
+        slave.do_step(0.0, 1e-3).unwrap();
+        assert_eq!(slave.get_real(&[u, y]).unwrap(), vec![0.0, 0.0]);
+
+        assert_eq!(slave.set_real(&[y], &[1.0]), Err(FmiError::NotAnInput(y)));
+        assert_eq!(slave.get_real(&[7]), Err(FmiError::UnknownReference(7)));
+
+        drop(slave);
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        // The binary checks the GUID of the model description it is instantiated with.
+        let error = FmuSlave::load(&rc_filter_fmu("{0}"), "filter").unwrap_err();
+        let FmuError::InstantiationFailed(Some(message)) = error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert!(message.contains("GUID"));
+
+        let description = "<fmiModelDescription fmiVersion=\"2.0\" modelName=\"m\" guid=\"g\"/>";
+        assert_eq!(
+            FmuSlave::load(
+                &write_zip(&[("modelDescription.xml", description.as_bytes())]),
+                "m"
+            )
+            .unwrap_err(),
+            FmuError::NotCoSimulation
+        );
+        let description = description.replace("/>", "><CoSimulation modelIdentifier=\"m\"/>");
+        assert_eq!(
+            FmuSlave::load(
+                &write_zip(&[("modelDescription.xml", description.as_bytes())]),
+                "m"
+            )
+            .unwrap_err(),
+            FmuError::MissingFile(binary_path("m"))
+        );
+        assert_eq!(
+            FmuSlave::load(&write_zip(&[]), "m").unwrap_err(),
+            FmuError::MissingFile("modelDescription.xml".to_string())
+        );
+    }
+}
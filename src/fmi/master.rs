@@ -0,0 +1,192 @@
+use crate::BESolver;
+use crate::components::ParamChange;
+use crate::fmi::{CoSimulationSlave, FmiError, ValueReference};
+use crate::results::{Probe, TransientResult};
+
+/// Couples a circuit with co-simulation slaves, such as other circuits, acting as the master
+/// algorithm.
+///
+/// Every communication step the circuit's probes are written to the slave inputs connected to
+/// them, the slaves are stepped, their outputs are applied to the primary values (see
+/// [`Component::get_value`](crate::components::Component::get_value)) of the components
+/// connected to them, and then the circuit is stepped. Slave outputs therefore lag the circuit
+/// by a communication step, which should be short next to the time constants of the coupling.
+///
+/// Slaves must be `Send` so a co-simulation can run on a worker thread.
+pub struct CoSimulation {
+    slaves: Vec<Box<dyn CoSimulationSlave + Send>>,
+    inputs: Vec<(Probe, usize, ValueReference)>,
+    outputs: Vec<(usize, ValueReference, usize)>,
+}
+
+impl CoSimulation {
+    pub fn new() -> Self {
+        Self {
+            slaves: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Adds a slave, referred to by its index in the order slaves are added.
+    pub fn with_slave(mut self, slave: impl CoSimulationSlave + Send + 'static) -> Self {
+        self.slaves.push(Box::new(slave));
+        self
+    }
+
+    /// Connects a probe of the circuit to an input of a slave.
+    pub fn with_input(mut self, probe: Probe, slave: usize, reference: ValueReference) -> Self {
+        self.inputs.push((probe, slave, reference));
+        self
+    }
+
+    /// Connects an output of a slave to the primary value of a component.
+    pub fn with_output(
+        mut self,
+        slave: usize,
+        reference: ValueReference,
+        component: usize,
+    ) -> Self {
+        self.outputs.push((slave, reference, component));
+        self
+    }
+
+    pub fn get_slave(&self, slave: usize) -> &dyn CoSimulationSlave {
+        self.slaves[slave].as_ref()
+    }
+
+    /// Co-simulates for the duration with the given communication step, recording the circuit
+    /// after every step. The slaves are set up to start at the solver's time.
+    pub fn run(
+        &mut self,
+        solver: &mut BESolver,
+        step: f64,
+        duration: f64,
+        result: &mut TransientResult,
+    ) -> Result<(), FmiError> {
+        let start = solver.get_time();
+        for slave in &mut self.slaves {
+            slave.setup_experiment(start)?;
+        }
+
+        let end = start + duration;
+        while solver.get_time() < end - step * 1e-9 {
+            let time = solver.get_time();
+            let step = step.min(end - time);
+
+            for (probe, slave, reference) in &self.inputs {
+                let value = probe.evaluate(solver.get_netlist(), solver.get_node_voltages());
+                self.slaves[*slave].set_real(&[*reference], &[value])?;
+            }
+            for slave in &mut self.slaves {
+                slave.do_step(time, step)?;
+            }
+            for &(slave, reference, component) in &self.outputs {
+                let value = self.slaves[slave].get_real(&[reference])?[0];
+                ParamChange::new(component, value)
+                    .apply(solver.get_netlist_mut())
+                    .map_err(FmiError::InvalidChange)?;
+            }
+
+            if solver.try_solve(step).is_err() {
+                return Err(FmiError::StepFailed { time });
+            }
+            result.record(solver);
+        }
+
+        result.finish();
+        Ok(())
+    }
+}
+
+impl Default for CoSimulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Netlist, Resistor, VoltageSource};
+
+    /// A flywheel on a DC motor with a torque constant of 0.1Nm/A and 1g.m² of inertia, with
+    /// the winding current as its input and its speed and back EMF as outputs, as an imported
+    /// FMU would be.
+    struct Flywheel {
+        current: f64,
+        speed: f64,
+    }
+
+    impl CoSimulationSlave for Flywheel {
+        fn setup_experiment(&mut self, _start_time: f64) -> Result<(), FmiError> {
+            self.speed = 0.0;
+            Ok(())
+        }
+
+        fn set_real(
+            &mut self,
+            references: &[ValueReference],
+            values: &[f64],
+        ) -> Result<(), FmiError> {
+            for (&reference, &value) in references.iter().zip(values) {
+                match reference {
+                    0 => self.current = value,
+                    1 | 2 => return Err(FmiError::NotAnInput(reference)),
+                    _ => return Err(FmiError::UnknownReference(reference)),
+                }
+            }
+            Ok(())
+        }
+
+        fn get_real(&self, references: &[ValueReference]) -> Result<Vec<f64>, FmiError> {
+            references
+                .iter()
+                .map(|&reference| match reference {
+                    0 => Ok(self.current),
+                    1 => Ok(self.speed),
+                    2 => Ok(0.1 * self.speed),
+                    _ => Err(FmiError::UnknownReference(reference)),
+                })
+                .collect()
+        }
+
+        fn do_step(&mut self, _current_time: f64, step: f64) -> Result<(), FmiError> {
+            self.speed += 0.1 * self.current / 1e-3 * step;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_motor_spin_up() {
+        // 12V across the 1 ohm winding and the back EMF, spinning up to 120rad/s with a 100ms
+        // time constant.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(1, 2, 1.0))
+            .add_component(VoltageSource::new(2, 0, 0.0));
+
+        let mut cosimulation = CoSimulation::new()
+            .with_slave(Flywheel {
+                current: 0.0,
+                speed: 0.0,
+            })
+            .with_input(Probe::ComponentCurrent(1), 0, 0)
+            .with_output(0, 2, 2);
+
+        let mut result = TransientResult::new();
+        result.add_probe("current", Probe::ComponentCurrent(1));
+        let mut solver = BESolver::new(&mut netlist);
+        cosimulation
+            .run(&mut solver, 1e-3, 0.5, &mut result)
+            .unwrap();
+
+        let speed = cosimulation.get_slave(0).get_real(&[1]).unwrap()[0];
+        assert_relative_eq!(speed, 120.0 * (1.0 - (-5.0f64).exp()), max_relative = 0.02);
+        assert_relative_eq!(result.get_values(0)[0], 12.0, max_relative = 1e-3);
+        assert!(result.get_values(0).last().unwrap().abs() < 0.2);
+    }
+}
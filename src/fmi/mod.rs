@@ -0,0 +1,127 @@
+//! Co-simulation modelled on the Functional Mock-up Interface (FMI 2.0), for coupling circuits
+//! with system models.
+//!
+//! A circuit is wrapped as a co-simulation slave by a [`CircuitSlave`], which implements the
+//! co-simulation calls of an FMU in Rust and generates its `modelDescription.xml`. Slaves,
+//! whether circuits or other models, are coupled with a circuit through a [`CoSimulation`],
+//! which exchanges their inputs and outputs with the circuit every communication step.
+//!
+//! With the `fmu` feature circuits are exchanged with other FMI tools as FMUs for
+//! co-simulation. A library defines the `fmi2*` functions of the FMI 2.0 C API for a slave
+//! with [`export_fmu!`](crate::export_fmu), and [`package_fmu`] zips it into a `.fmu` archive
+//! with the model description of the slave. An [`FmuSlave`] loads the binary of an FMU, from
+//! rice or any other tool, as a slave.
+
+use crate::components::ParamError;
+
+mod slave;
+#[cfg(feature = "fmu")]
+use slave::model_identifier;
+pub use slave::{CircuitSlave, ScalarVariable};
+
+mod master;
+pub use master::CoSimulation;
+
+mod model_description;
+pub use model_description::ModelDescription;
+
+#[cfg(feature = "fmu")]
+mod archive;
+
+#[cfg(feature = "fmu")]
+pub mod ffi;
+
+#[cfg(feature = "fmu")]
+#[doc(hidden)]
+pub mod export;
+#[cfg(feature = "fmu")]
+pub use export::package_fmu;
+
+#[cfg(feature = "fmu")]
+mod import;
+#[cfg(feature = "fmu")]
+pub use import::FmuSlave;
+
+/// Identifies a variable of a slave, as in the `valueReference` of its model description.
+pub type ValueReference = u32;
+
+/// Whether a variable is set by the environment or computed by the slave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Causality {
+    Input,
+    Output,
+}
+
+/// Why a call to a slave failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FmiError {
+    /// The slave has no variable with the value reference.
+    UnknownReference(ValueReference),
+    /// The variable is an output, which can't be set.
+    NotAnInput(ValueReference),
+    /// The slave couldn't complete the communication step at the given time.
+    StepFailed { time: f64 },
+    /// A value can't be applied to the component it is bound to.
+    InvalidChange(ParamError),
+    /// The FMU returned an error from the call, with the last message it logged during it.
+    Rejected {
+        call: &'static str,
+        message: Option<String>,
+    },
+}
+
+/// Why an FMU couldn't be read or loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FmuError {
+    /// The archive isn't a valid zip archive.
+    InvalidArchive,
+    /// A file of the archive is compressed with a method other than stored or deflated.
+    UnsupportedCompression(u16),
+    /// The archive has no file with the given path, such as the binary for this platform.
+    MissingFile(String),
+    InvalidModelDescription(String),
+    /// The FMU only supports model exchange.
+    NotCoSimulation,
+    /// The archive couldn't be extracted.
+    Io(String),
+    /// The binary couldn't be loaded.
+    Library(String),
+    /// The binary doesn't define the function.
+    MissingFunction(&'static str),
+    /// `fmi2Instantiate` failed, with the last message the FMU logged during it.
+    InstantiationFailed(Option<String>),
+}
+
+/// The folder of the binaries of an FMU for the platform this is compiled for.
+#[cfg(feature = "fmu")]
+const PLATFORM: &str = match (cfg!(target_os = "windows"), cfg!(target_os = "macos")) {
+    (true, _) if cfg!(target_pointer_width = "64") => "win64",
+    (true, _) => "win32",
+    (_, true) if cfg!(target_pointer_width = "64") => "darwin64",
+    (_, true) => "darwin32",
+    _ if cfg!(target_pointer_width = "64") => "linux64",
+    _ => "linux32",
+};
+
+/// The path of the binary of an FMU in its archive for this platform.
+#[cfg(feature = "fmu")]
+fn binary_path(model_identifier: &str) -> String {
+    format!(
+        "binaries/{PLATFORM}/{model_identifier}{}",
+        std::env::consts::DLL_SUFFIX
+    )
+}
+
+/// The co-simulation calls of an FMU (`fmi2SetupExperiment`, `fmi2SetReal`, `fmi2GetReal` and
+/// `fmi2DoStep`) for real variables.
+pub trait CoSimulationSlave {
+    /// Prepares the slave to start at the given time.
+    fn setup_experiment(&mut self, start_time: f64) -> Result<(), FmiError>;
+
+    fn set_real(&mut self, references: &[ValueReference], values: &[f64]) -> Result<(), FmiError>;
+
+    fn get_real(&self, references: &[ValueReference]) -> Result<Vec<f64>, FmiError>;
+
+    /// Advances the slave from the current communication point by the step.
+    fn do_step(&mut self, current_time: f64, step: f64) -> Result<(), FmiError>;
+}
@@ -0,0 +1,273 @@
+use crate::fmi::{Causality, FmuError, ScalarVariable, ValueReference};
+
+/// The parts of the `modelDescription.xml` of an FMU needed to co-simulate it.
+///
+/// Only real inputs and outputs are kept. Any other variables are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDescription {
+    pub model_name: String,
+    pub guid: String,
+    /// Names the binary implementing co-simulation. It is `None` for FMUs that only support
+    /// model exchange.
+    pub model_identifier: Option<String>,
+    /// The default communication step, if there is one.
+    pub step_size: Option<f64>,
+    pub variables: Vec<ScalarVariable>,
+}
+
+/// An XML tag with its attributes unescaped.
+struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, String)>,
+    closing: bool,
+}
+
+impl Tag<'_> {
+    fn get(&self, attribute: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == attribute)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn require(&self, attribute: &str) -> Result<&str, FmuError> {
+        self.get(attribute).ok_or_else(|| {
+            FmuError::InvalidModelDescription(format!("{} has no {attribute}", self.name))
+        })
+    }
+}
+
+impl ModelDescription {
+    /// Parses a `modelDescription.xml` of FMI 2.0.
+    pub fn parse(xml: &str) -> Result<Self, FmuError> {
+        let mut description: Option<Self> = None;
+        // The name, value reference and causality of the scalar variable being read.
+        let mut variable: Option<(String, ValueReference, Option<Causality>)> = None;
+
+        for tag in tags(xml)? {
+            match (tag.name, tag.closing) {
+                ("fmiModelDescription", false) => {
+                    let version = tag.require("fmiVersion")?;
+                    if version != "2.0" {
+                        return Err(FmuError::InvalidModelDescription(format!(
+                            "FMI {version} isn't supported"
+                        )));
+                    }
+                    description = Some(Self {
+                        model_name: tag.require("modelName")?.to_string(),
+                        guid: tag.require("guid")?.to_string(),
+                        model_identifier: None,
+                        step_size: None,
+                        variables: Vec::new(),
+                    });
+                }
+                ("CoSimulation", false) => {
+                    if let Some(description) = &mut description {
+                        description.model_identifier =
+                            Some(tag.require("modelIdentifier")?.to_string());
+                    }
+                }
+                ("DefaultExperiment", false) => {
+                    if let Some(description) = &mut description {
+                        description.step_size =
+                            tag.get("stepSize").and_then(|step| step.parse().ok());
+                    }
+                }
+                ("ScalarVariable", false) => {
+                    let reference = tag.require("valueReference")?;
+                    let reference = reference.parse().map_err(|_| {
+                        FmuError::InvalidModelDescription(format!(
+                            "invalid value reference {reference}"
+                        ))
+                    })?;
+                    let causality = match tag.get("causality") {
+                        Some("input") => Some(Causality::Input),
+                        Some("output") => Some(Causality::Output),
+                        _ => None,
+                    };
+                    variable = Some((tag.require("name")?.to_string(), reference, causality));
+                }
+                ("ScalarVariable", true) => variable = None,
+                ("Real", false) => {
+                    if let (Some(description), Some((name, reference, Some(causality)))) =
+                        (&mut description, &variable)
+                    {
+                        description.variables.push(ScalarVariable {
+                            name: name.clone(),
+                            reference: *reference,
+                            causality: *causality,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        description.ok_or_else(|| {
+            FmuError::InvalidModelDescription("no fmiModelDescription element".to_string())
+        })
+    }
+}
+
+/// Splits the XML into its tags, leaving out the declaration, comments and text.
+fn tags(xml: &str) -> Result<Vec<Tag<'_>>, FmuError> {
+    let unterminated = || FmuError::InvalidModelDescription("unterminated tag".to_string());
+
+    let mut tags = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            let end = comment.find("-->").ok_or_else(unterminated)?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+
+        // Attribute values may contain '>', so the end is the first one outside quotes.
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match quote {
+                    Some(q) if c == q => quote = None,
+                    None if c == '"' || c == '\'' => quote = Some(c),
+                    None if c == '>' => return true,
+                    _ => {}
+                }
+                false
+            })
+            .ok_or_else(unterminated)?
+            .0;
+        let inner = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = inner.strip_prefix('/') {
+            tags.push(Tag {
+                name: name.trim(),
+                attributes: Vec::new(),
+                closing: true,
+            });
+            continue;
+        }
+
+        let inner = inner.strip_suffix('/').unwrap_or(inner);
+        let (name, mut attributes_text) =
+            inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+        let mut attributes = Vec::new();
+        loop {
+            attributes_text = attributes_text.trim_start();
+            if attributes_text.is_empty() {
+                break;
+            }
+            let invalid =
+                || FmuError::InvalidModelDescription(format!("invalid attributes of {name}"));
+            let (attribute, value) = attributes_text.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|&c| c == '"' || c == '\'');
+            let quote = quote.ok_or_else(invalid)?;
+            let end = value[1..].find(quote).ok_or_else(invalid)?;
+            attributes.push((attribute.trim(), unescape(&value[1..end + 1])));
+            attributes_text = &value[end + 2..];
+        }
+        tags.push(Tag {
+            name,
+            attributes,
+            closing: false,
+        });
+    }
+    Ok(tags)
+}
+
+/// Replaces the predefined entities of XML with their characters.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::{Netlist, Resistor, VoltageSource};
+    use crate::fmi::CircuitSlave;
+    use crate::results::Probe;
+
+    #[test]
+    fn test_parse() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- A <commented> tag -->
+            <fmiModelDescription fmiVersion="2.0" modelName="Motor &amp; load" guid='{42}'>
+              <ModelExchange modelIdentifier="motor_me"/>
+              <CoSimulation modelIdentifier="motor" canHandleVariableCommunicationStepSize="true"/>
+              <DefaultExperiment startTime="0" stepSize="1e-4"/>
+              <ModelVariables>
+                <ScalarVariable name="current" valueReference="0" causality="input">
+                  <Real start="0"/>
+                </ScalarVariable>
+                <ScalarVariable name="gear" valueReference="0" causality="input">
+                  <Integer start="1"/>
+                </ScalarVariable>
+                <ScalarVariable name="inertia" valueReference="1" causality="parameter">
+                  <Real start="1e-3"/>
+                </ScalarVariable>
+                <ScalarVariable name="speed > 0" valueReference="2" causality="output">
+                  <Real/>
+                </ScalarVariable>
+              </ModelVariables>
+            </fmiModelDescription>"#;
+        assert_eq!(
+            ModelDescription::parse(xml),
+            Ok(ModelDescription {
+                model_name: "Motor & load".to_string(),
+                guid: "{42}".to_string(),
+                model_identifier: Some("motor".to_string()),
+                step_size: Some(1e-4),
+                variables: vec![
+                    ScalarVariable {
+                        name: "current".to_string(),
+                        reference: 0,
+                        causality: Causality::Input,
+                    },
+                    ScalarVariable {
+                        name: "speed > 0".to_string(),
+                        reference: 2,
+                        causality: Causality::Output,
+                    },
+                ],
+            })
+        );
+
+        assert!(matches!(
+            ModelDescription::parse("<fmiModelDescription fmiVersion=\"1.0\"/>"),
+            Err(FmuError::InvalidModelDescription(_))
+        ));
+        assert!(matches!(
+            ModelDescription::parse("<fmiModelDescription fmiVersion=\"2.0\""),
+            Err(FmuError::InvalidModelDescription(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_circuit_slave() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 0, 1e3));
+        let slave = CircuitSlave::new(&netlist, 1e-5)
+            .with_input("u", 0)
+            .with_output("i", Probe::ComponentCurrent(1));
+
+        let description =
+            ModelDescription::parse(&slave.model_description("Divider \"A\"", "{7}")).unwrap();
+        assert_eq!(description.model_name, "Divider \"A\"");
+        assert_eq!(description.guid, "{7}");
+        assert_eq!(description.model_identifier.as_deref(), Some("Divider__A_"));
+        assert_eq!(description.step_size, Some(1e-5));
+        assert_eq!(&description.variables, slave.get_variables());
+    }
+}
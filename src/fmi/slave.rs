@@ -0,0 +1,296 @@
+use std::fmt::Write;
+
+use crate::components::{Netlist, ParamChange};
+use crate::fmi::{Causality, CoSimulationSlave, FmiError, ValueReference};
+use crate::results::Probe;
+use crate::{BESolver, SolverOptions};
+
+/// A variable of a slave, as listed in its model description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalarVariable {
+    pub name: String,
+    pub reference: ValueReference,
+    pub causality: Causality,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Binding {
+    /// The primary value of a component.
+    Component(usize),
+    Probe(Probe),
+}
+
+/// A circuit wrapped as an FMI-style co-simulation slave.
+///
+/// Inputs set the primary value (see
+/// [`Component::get_value`](crate::components::Component::get_value)) of components, such as
+/// the voltage of a source driven by a mechanical model, and outputs are probes of the
+/// circuit. Each communication step is solved with timesteps of at most the internal timestep.
+#[derive(Debug, Clone)]
+pub struct CircuitSlave {
+    netlist: Netlist,
+    options: SolverOptions,
+    dt: f64,
+    variables: Vec<ScalarVariable>,
+    bindings: Vec<Binding>,
+    node_voltages: Vec<f64>,
+    time: f64,
+}
+
+impl CircuitSlave {
+    /// Creates a slave solving a copy of the netlist with the given internal timestep.
+    pub fn new(netlist: &Netlist, dt: f64) -> Self {
+        Self {
+            netlist: netlist.clone(),
+            options: SolverOptions::default(),
+            dt,
+            variables: Vec::new(),
+            bindings: Vec::new(),
+            node_voltages: Vec::new(),
+            time: 0.0,
+        }
+    }
+
+    pub fn with_options(mut self, options: SolverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Adds an input setting the primary value of the component, with the next value
+    /// reference.
+    pub fn with_input(self, name: impl Into<String>, component: usize) -> Self {
+        self.with_variable(name.into(), Causality::Input, Binding::Component(component))
+    }
+
+    /// Adds an output evaluating the probe, with the next value reference.
+    pub fn with_output(self, name: impl Into<String>, probe: Probe) -> Self {
+        self.with_variable(name.into(), Causality::Output, Binding::Probe(probe))
+    }
+
+    fn with_variable(mut self, name: String, causality: Causality, binding: Binding) -> Self {
+        self.variables.push(ScalarVariable {
+            name,
+            reference: self.variables.len() as ValueReference,
+            causality,
+        });
+        self.bindings.push(binding);
+        self
+    }
+
+    pub fn get_variables(&self) -> &Vec<ScalarVariable> {
+        &self.variables
+    }
+
+    /// Finds the value reference of the variable with the given name.
+    pub fn find_variable(&self, name: &str) -> Option<ValueReference> {
+        self.variables
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| v.reference)
+    }
+
+    pub fn get_netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    /// Gets the communication point the slave has reached.
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Generates the `modelDescription.xml` of the FMU, declaring every variable and the
+    /// internal timestep as the default step size. The model identifier is the model name with
+    /// everything but letters, digits and underscores replaced by underscores.
+    pub fn model_description(&self, model_name: &str, guid: &str) -> String {
+        let identifier = model_identifier(model_name);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            xml,
+            "<fmiModelDescription fmiVersion=\"2.0\" modelName=\"{}\" guid=\"{}\" \
+             generationTool=\"rice\" variableNamingConvention=\"flat\" \
+             numberOfEventIndicators=\"0\">",
+            escape(model_name),
+            escape(guid)
+        )
+        .unwrap();
+        writeln!(
+            xml,
+            "  <CoSimulation modelIdentifier=\"{identifier}\" \
+             canHandleVariableCommunicationStepSize=\"true\"/>"
+        )
+        .unwrap();
+        writeln!(xml, "  <DefaultExperiment stepSize=\"{}\"/>", self.dt).unwrap();
+
+        xml.push_str("  <ModelVariables>\n");
+        for variable in &self.variables {
+            let causality = match variable.causality {
+                Causality::Input => "input",
+                Causality::Output => "output",
+            };
+            writeln!(
+                xml,
+                "    <ScalarVariable name=\"{}\" valueReference=\"{}\" causality=\"{causality}\" \
+                 variability=\"continuous\">",
+                escape(&variable.name),
+                variable.reference
+            )
+            .unwrap();
+            match variable.causality {
+                Causality::Input => {
+                    let start = self.get_value(variable.reference as usize);
+                    writeln!(xml, "      <Real start=\"{start}\"/>").unwrap()
+                }
+                Causality::Output => xml.push_str("      <Real/>\n"),
+            }
+            xml.push_str("    </ScalarVariable>\n");
+        }
+        xml.push_str("  </ModelVariables>\n");
+
+        // Outputs are listed by their 1-based position in the model variables.
+        xml.push_str("  <ModelStructure>\n    <Outputs>\n");
+        for (k, variable) in self.variables.iter().enumerate() {
+            if variable.causality == Causality::Output {
+                writeln!(xml, "      <Unknown index=\"{}\"/>", k + 1).unwrap();
+            }
+        }
+        xml.push_str("    </Outputs>\n  </ModelStructure>\n</fmiModelDescription>\n");
+        xml
+    }
+
+    fn get_value(&self, index: usize) -> f64 {
+        match &self.bindings[index] {
+            Binding::Component(component) => self.netlist.get_components()[*component]
+                .get_value()
+                .unwrap_or(f64::NAN),
+            Binding::Probe(probe) => probe.evaluate(&self.netlist, &self.node_voltages),
+        }
+    }
+
+    fn index(&self, reference: ValueReference) -> Result<usize, FmiError> {
+        let index = reference as usize;
+        if index < self.variables.len() {
+            Ok(index)
+        } else {
+            Err(FmiError::UnknownReference(reference))
+        }
+    }
+}
+
+impl CoSimulationSlave for CircuitSlave {
+    /// Sets the communication point the slave starts from. The circuit itself always starts
+    /// from its initial conditions.
+    fn setup_experiment(&mut self, start_time: f64) -> Result<(), FmiError> {
+        self.time = start_time;
+        Ok(())
+    }
+
+    fn set_real(&mut self, references: &[ValueReference], values: &[f64]) -> Result<(), FmiError> {
+        for (&reference, &value) in references.iter().zip(values) {
+            let index = self.index(reference)?;
+            let Binding::Component(component) = self.bindings[index] else {
+                return Err(FmiError::NotAnInput(reference));
+            };
+            ParamChange::new(component, value)
+                .apply(&mut self.netlist)
+                .map_err(FmiError::InvalidChange)?;
+        }
+        Ok(())
+    }
+
+    fn get_real(&self, references: &[ValueReference]) -> Result<Vec<f64>, FmiError> {
+        references
+            .iter()
+            .map(|&reference| Ok(self.get_value(self.index(reference)?)))
+            .collect()
+    }
+
+    fn do_step(&mut self, current_time: f64, step: f64) -> Result<(), FmiError> {
+        let mut solver = BESolver::new(&mut self.netlist).with_options(self.options);
+        let mut elapsed = 0.0;
+        while elapsed < step - self.dt * 1e-9 {
+            let dt = self.dt.min(step - elapsed);
+            if solver.try_solve(dt).is_err() {
+                return Err(FmiError::StepFailed {
+                    time: current_time + elapsed,
+                });
+            }
+            elapsed += dt;
+        }
+
+        self.node_voltages = solver.get_node_voltages().clone();
+        self.time = current_time + step;
+        Ok(())
+    }
+}
+
+/// The model identifier of a model name, which names the binary of the FMU.
+pub(crate) fn model_identifier(model_name: &str) -> String {
+    model_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escapes the characters XML attribute values can't contain.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::components::{Capacitor, Resistor, VoltageSource};
+
+    fn rc_slave() -> CircuitSlave {
+        // A 1ms RC low pass driven by its input.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+        CircuitSlave::new(&netlist, 10e-6)
+            .with_input("u", 0)
+            .with_output("y", Probe::NodeVoltage(2))
+    }
+
+    #[test]
+    fn test_circuit_slave() {
+        let mut slave = rc_slave();
+        let (u, y) = (
+            slave.find_variable("u").unwrap(),
+            slave.find_variable("y").unwrap(),
+        );
+        assert_eq!((u, y), (0, 1));
+
+        slave.setup_experiment(0.0).unwrap();
+        slave.set_real(&[u], &[1.0]).unwrap();
+        slave.do_step(0.0, 1e-3).unwrap();
+        assert_relative_eq!(slave.get_real(&[y]).unwrap()[0], 0.632, epsilon = 0.01);
+        slave.do_step(1e-3, 4e-3).unwrap();
+        assert_relative_eq!(slave.get_real(&[y]).unwrap()[0], 0.993, epsilon = 0.01);
+        assert_relative_eq!(slave.get_time(), 5e-3);
+        assert_eq!(slave.get_real(&[u]).unwrap(), vec![1.0]);
+
+        assert_eq!(slave.set_real(&[y], &[1.0]), Err(FmiError::NotAnInput(y)));
+        assert_eq!(slave.get_real(&[7]), Err(FmiError::UnknownReference(7)));
+    }
+
+    #[test]
+    fn test_model_description() {
+        let xml = rc_slave().model_description("RC filter", "{1234}");
+        assert!(xml.contains("modelName=\"RC filter\" guid=\"{1234}\""));
+        assert!(xml.contains("<CoSimulation modelIdentifier=\"RC_filter\""));
+        assert!(xml.contains(
+            "<ScalarVariable name=\"u\" valueReference=\"0\" causality=\"input\" \
+             variability=\"continuous\">\n      <Real start=\"0\"/>"
+        ));
+        assert!(xml.contains("valueReference=\"1\" causality=\"output\""));
+        assert!(xml.contains("<Outputs>\n      <Unknown index=\"2\"/>\n    </Outputs>"));
+    }
+}
@@ -1,4 +1,114 @@
 mod be_solver;
-pub use be_solver::BESolver;
+pub use be_solver::{
+    AdaptiveTimestep, BESolver, ConductanceRatio, ConvergenceReport, ConvergenceStrategy, Culprit,
+    Damping, GminStepping, IntegrationMethod, IterationChange, ModelCheck, ModelReport,
+    ModelViolation, OptionPresets, Refinement, Remedy, SolverError, SolverOptions,
+    SolverStatistics, SourceStepping, StampMagnitude, StepRejection, SystemLayout, Variable,
+};
+
+mod ac_solver;
+pub use ac_solver::{
+    ACSolution, ACSolver, AdaptiveSweep, SmallSignalError, StateSpace, log_frequencies,
+    reflection_coefficient, vswr,
+};
+
+mod rng;
+pub use rng::Rng;
 
 pub mod components;
+
+pub mod library;
+
+pub mod synthesis;
+
+pub mod magnetics;
+
+pub mod faults;
+
+pub mod parasitics;
+
+pub mod reports;
+
+pub mod analysis;
+
+pub mod results;
+
+pub mod fmi;
+
+pub mod matlab;
+
+pub mod backend;
+
+#[cfg(feature = "units")]
+pub mod units;
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::analysis::{
+        BatchResults, BatchRunner, DiscreteController, LockstepBatch, LongHorizon, MonteCarlo,
+        ResourceLimits, ThermalWatchdog, Trigger, YieldAnalysis,
+    };
+    use crate::backend::CpuBackend;
+    use crate::components::{Component, Netlist, Resistor, VoltageSource};
+    use crate::faults::Fault;
+    use crate::fmi::{CircuitSlave, CoSimulation};
+    use crate::matlab::MatValue;
+    use crate::results::{Probe, Trace, TransientResult};
+    use crate::{ACSolver, BESolver, ConvergenceReport, SolverOptions};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_thread_safety() {
+        assert_send_sync::<Netlist>();
+        assert_send_sync::<Component>();
+        assert_send_sync::<BESolver>();
+        assert_send_sync::<ACSolver>();
+        assert_send_sync::<SolverOptions>();
+        assert_send_sync::<ConvergenceReport>();
+        assert_send_sync::<TransientResult>();
+        assert_send_sync::<Trace>();
+        assert_send_sync::<BatchRunner>();
+        assert_send_sync::<BatchResults<TransientResult>>();
+        assert_send_sync::<MonteCarlo>();
+        assert_send_sync::<YieldAnalysis<TransientResult>>();
+        assert_send_sync::<LockstepBatch>();
+        assert_send_sync::<CpuBackend>();
+        assert_send_sync::<ResourceLimits>();
+        assert_send_sync::<ThermalWatchdog>();
+        assert_send_sync::<LongHorizon>();
+        assert_send_sync::<Trigger>();
+        assert_send_sync::<Fault>();
+        assert_send_sync::<CircuitSlave>();
+        assert_send_sync::<MatValue>();
+        assert_send::<CoSimulation>();
+        assert_send::<DiscreteController>();
+        #[cfg(feature = "gpu")]
+        assert_send_sync::<crate::backend::GpuBackend>();
+        #[cfg(feature = "fmu")]
+        assert_send::<crate::fmi::FmuSlave>();
+
+        // Simulating on a worker thread and sending the result to another.
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 1.0))
+                .add_component(Resistor::new(1, 0, 1e3));
+            let mut result = TransientResult::new();
+            result.add_probe("i", Probe::ComponentCurrent(1));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+            result.record(&solver);
+            sender.send(result).unwrap();
+        });
+
+        let result = receiver.recv().unwrap();
+        worker.join().unwrap();
+        assert!((result.get_values(0)[0] - 1e-3).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    components::{Capacitor, Inductor, Netlist, Resistor, Switch},
+    library::Subcircuit,
+};
+
+/// Resistance of the relay of an [`EsdGenerator`] once it has closed.
+const ESD_RELAY_ON_RESISTANCE: f64 = 1e-3;
+
+/// Resistance of the relay of an [`EsdGenerator`] before it closes, holding the charge.
+const ESD_RELAY_OFF_RESISTANCE: f64 = 1e15;
+
+/// A line impedance stabilization network (artificial mains network) for conducted emissions
+/// measurements: the 50µH || 50Ω V-network of CISPR 16-1-2 for one line.
+///
+/// The supply feeds the equipment under test through the 50µH inductor, with the supply side
+/// decoupled by 1µF. The noise current of the equipment flows through 0.1µF into the 50Ω input
+/// of the receiver, across which the measurement node sits, so each line of the equipment
+/// sees about 50Ω above 1MHz. A 1kΩ resistor discharges the coupling capacitor when no receiver
+/// is connected, and is in parallel with it otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lisn {
+    supply_node: usize,
+    eut_node: usize,
+    measurement_node: usize,
+    ground_node: usize,
+    receiver: bool,
+}
+
+impl Lisn {
+    /// Creates a network feeding the equipment under test at the EUT node from the supply
+    /// node, with a 50Ω receiver connected across the measurement node.
+    pub fn new(
+        supply_node: usize,
+        eut_node: usize,
+        measurement_node: usize,
+        ground_node: usize,
+    ) -> Self {
+        Self {
+            supply_node,
+            eut_node,
+            measurement_node,
+            ground_node,
+            receiver: true,
+        }
+    }
+
+    /// Leaves the measurement port unterminated, e.g. for the line not being measured.
+    pub fn without_receiver(mut self) -> Self {
+        self.receiver = false;
+        self
+    }
+}
+
+impl Subcircuit for Lisn {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist
+            .add_component(Inductor::new(self.supply_node, self.eut_node, 50e-6, 0.0))
+            .add_component(Capacitor::new(
+                self.supply_node,
+                self.ground_node,
+                1e-6,
+                0.0,
+            ))
+            .add_component(Capacitor::new(
+                self.eut_node,
+                self.measurement_node,
+                0.1e-6,
+                0.0,
+            ))
+            .add_component(Resistor::new(self.measurement_node, self.ground_node, 1e3));
+
+        if self.receiver {
+            netlist.add_component(Resistor::new(self.measurement_node, self.ground_node, 50.0));
+        }
+    }
+}
+
+/// A standardized ESD discharge network: a storage capacitor charged to the test voltage and
+/// discharged through a resistor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EsdModel {
+    /// The human body model of ANSI/ESDA/JEDEC JS-001 for component qualification: 100pF
+    /// through 1.5kΩ.
+    HumanBody,
+    /// The ESD generator of IEC 61000-4-2 for system level immunity: 150pF through 330Ω.
+    Iec61000,
+    /// The machine model of JESD22-A115: 200pF through 0.75µH, without resistance, so the
+    /// discharge rings.
+    Machine,
+}
+
+impl EsdModel {
+    /// Gets the capacitance, resistance and series inductance of the network.
+    pub fn get_network(&self) -> (f64, f64, f64) {
+        match self {
+            Self::HumanBody => (100e-12, 1.5e3, 0.0),
+            Self::Iec61000 => (150e-12, 330.0, 0.0),
+            Self::Machine => (200e-12, 0.0, 0.75e-6),
+        }
+    }
+}
+
+/// An ESD simulator discharging into the output node, for simulating the clamping and residual
+/// voltages of protection networks.
+///
+/// The storage capacitor, charged to the test voltage (negative for a negative discharge), is
+/// connected by a relay closing at the discharge time to the discharge resistor and any series
+/// inductance, whose other end is the output. The inductance stands for the discharge path
+/// (the return cable of a gun, the fixture of a tester), which sets the rise time of the
+/// current. The discharge is at the start of the simulation by default; a later discharge
+/// time should fall on a timestep, as the relay doesn't cut timesteps short.
+///
+/// Uses an internal node for the capacitor, and one each for the resistor and the inductance
+/// if there are any (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EsdGenerator {
+    output_node: usize,
+    ground_node: usize,
+    voltage: f64,
+    capacitance: f64,
+    resistance: f64,
+    inductance: f64,
+    discharge_time: f64,
+}
+
+impl EsdGenerator {
+    /// Creates a generator of the standardized network charged to the test voltage.
+    pub fn new(output_node: usize, ground_node: usize, model: EsdModel, voltage: f64) -> Self {
+        let (capacitance, resistance, inductance) = model.get_network();
+        Self {
+            output_node,
+            ground_node,
+            voltage,
+            capacitance,
+            resistance,
+            inductance,
+            discharge_time: 0.0,
+        }
+    }
+
+    /// Discharges at the given time rather than at the start of the simulation.
+    pub fn with_discharge_time(mut self, discharge_time: f64) -> Self {
+        self.discharge_time = discharge_time;
+        self
+    }
+
+    /// Sets the inductance of the discharge path.
+    pub fn with_inductance(mut self, inductance: f64) -> Self {
+        self.inductance = inductance;
+        self
+    }
+
+    /// Gets the current the network discharges into a short circuit at its peak, ignoring the
+    /// inductance (infinite without a resistance).
+    pub fn get_short_circuit_current(&self) -> f64 {
+        self.voltage / self.resistance
+    }
+
+    /// Gets the energy stored in the network before the discharge.
+    pub fn get_energy(&self) -> f64 {
+        0.5 * self.capacitance * self.voltage * self.voltage
+    }
+}
+
+impl Subcircuit for EsdGenerator {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.output_node.max(self.ground_node));
+        let charged = netlist.add_node();
+        netlist.add_component(Capacitor::new(
+            charged,
+            self.ground_node,
+            self.capacitance,
+            self.voltage,
+        ));
+
+        // Chains the relay, the resistor and the inductor, leaving out the missing ones.
+        let mut end = self.output_node;
+        if self.inductance > 0.0 {
+            let inductor = netlist.add_node();
+            netlist.add_component(Inductor::new(inductor, end, self.inductance, 0.0));
+            end = inductor;
+        }
+        if self.resistance > 0.0 {
+            let resistor = netlist.add_node();
+            netlist.add_component(Resistor::new(resistor, end, self.resistance));
+            end = resistor;
+        }
+        netlist.add_component(Switch::new(
+            charged,
+            end,
+            ESD_RELAY_ON_RESISTANCE,
+            ESD_RELAY_OFF_RESISTANCE,
+            self.discharge_time,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::{ACSolver, BESolver, components::CurrentSource};
+
+    #[test]
+    fn test_esd_generator() {
+        // 8kV from the IEC network into a 2Ω current target: an exponential of the network's
+        // time constant.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 2.0))
+            .add_subcircuit(&EsdGenerator::new(1, 0, EsdModel::Iec61000, 8e3));
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..300 {
+            solver.solve(0.1e-9).unwrap();
+        }
+        let target: Resistor = solver.get_netlist().get_components()[0]
+            .clone()
+            .try_into()
+            .unwrap();
+        let expected = 8e3 / 332.0 * (-30e-9 / (332.0 * 150e-12f64)).exp();
+        assert_relative_eq!(target.get_current(), expected, max_relative = 1e-2);
+
+        // The machine model rings through its inductance into a short, reversing polarity.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 1e-3))
+            .add_subcircuit(&EsdGenerator::new(1, 0, EsdModel::Machine, 200.0));
+
+        let mut solver = BESolver::new(&mut netlist);
+        let half_period = PI * (0.75e-6 * 200e-12f64).sqrt();
+        let mut peak: f64 = 0.0;
+        let mut reversed = false;
+        while solver.get_time() < 1.5 * half_period {
+            solver.solve(0.1e-9).unwrap();
+            let short: Resistor = solver.get_netlist().get_components()[0]
+                .clone()
+                .try_into()
+                .unwrap();
+            peak = peak.max(short.get_current().abs());
+            reversed |= short.get_current() < -1.0;
+        }
+        assert_relative_eq!(
+            peak,
+            200.0 / (0.75e-6 / 200e-12f64).sqrt(),
+            max_relative = 5e-2
+        );
+        assert!(reversed);
+        assert_relative_eq!(
+            EsdGenerator::new(1, 0, EsdModel::HumanBody, 2e3).get_short_circuit_current(),
+            4.0 / 3.0
+        );
+    }
+
+    #[test]
+    fn test_lisn_impedance() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(Resistor::new(1, 0, 1e-3))
+            .add_component(CurrentSource::new(0, 2, 0.0).with_ac(1.0, 0.0))
+            .add_subcircuit(&Lisn::new(1, 2, 3, 0));
+
+        // The equipment sees the receiver in parallel with the discharge resistor at 10MHz,
+        // and the inductor shunting it at 10kHz.
+        let ac = ACSolver::new(&netlist);
+        let high = ac.solve(10e6).unwrap();
+        assert_relative_eq!(
+            high.get_node_voltage(2).norm(),
+            50.0 * 1e3 / 1050.0,
+            max_relative = 1e-2
+        );
+        assert_relative_eq!(
+            high.get_node_voltage(3).norm(),
+            high.get_node_voltage(2).norm(),
+            max_relative = 1e-2
+        );
+        assert!(ac.solve(10e3).unwrap().get_node_voltage(2).norm() < 5.0);
+    }
+}
@@ -0,0 +1,665 @@
+use crate::{
+    components::{
+        Capacitor, ControlledSource, CurrentSource, Diode, DiodeModel, Netlist, Resistor,
+        VoltageSource,
+    },
+    library::Subcircuit,
+};
+
+/// Transconductance of the comparators in the macromodels, in A/V. Their outputs saturate
+/// smoothly (see [`ControlledSource::with_limits`]) over a few tens of millivolts, which keeps
+/// Newton-Raphson out of the flat saturated regions.
+const COMPARATOR_TRANSCONDUCTANCE: f64 = 1.0;
+
+/// Transconductance of the error amplifiers of the regulator macromodels, in A/V.
+const ERROR_AMPLIFIER_TRANSCONDUCTANCE: f64 = 100.0;
+
+/// Voltage scale over which the open collector outputs of the macromodels saturate.
+const OPEN_COLLECTOR_SATURATION_VOLTAGE: f64 = 0.1;
+
+/// An LM393 style comparator with an open collector output to ground, sinking up to 16mA when
+/// the inverting input is above the non-inverting input. The supply pin is left out, the
+/// output needs a pullup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lm393 {
+    non_inverting_node: usize,
+    inverting_node: usize,
+    output_node: usize,
+    ground_node: usize,
+}
+
+impl Lm393 {
+    pub fn new(
+        non_inverting_node: usize,
+        inverting_node: usize,
+        output_node: usize,
+        ground_node: usize,
+    ) -> Self {
+        Self {
+            non_inverting_node,
+            inverting_node,
+            output_node,
+            ground_node,
+        }
+    }
+}
+
+impl Subcircuit for Lm393 {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.add_component(
+            ControlledSource::vccs(
+                self.ground_node,
+                self.output_node,
+                self.inverting_node,
+                self.non_inverting_node,
+                COMPARATOR_TRANSCONDUCTANCE,
+            )
+            .with_limits(0.0, 16e-3)
+            .with_saturation_voltage(OPEN_COLLECTOR_SATURATION_VOLTAGE),
+        );
+    }
+}
+
+/// A TL431 style programmable shunt reference: sinks current from the cathode to the anode
+/// (up to 100mA) to hold the reference pin 2.495V above the anode.
+///
+/// Uses one internal node (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tl431 {
+    cathode_node: usize,
+    anode_node: usize,
+    reference_node: usize,
+}
+
+impl Tl431 {
+    pub fn new(cathode_node: usize, anode_node: usize, reference_node: usize) -> Self {
+        Self {
+            cathode_node,
+            anode_node,
+            reference_node,
+        }
+    }
+}
+
+impl Subcircuit for Tl431 {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(
+            self.cathode_node
+                .max(self.anode_node)
+                .max(self.reference_node),
+        );
+        let reference = netlist.add_node();
+
+        netlist
+            .add_component(VoltageSource::new(reference, self.anode_node, 2.495))
+            .add_component(
+                ControlledSource::vccs(
+                    self.anode_node,
+                    self.cathode_node,
+                    self.reference_node,
+                    reference,
+                    ERROR_AMPLIFIER_TRANSCONDUCTANCE,
+                )
+                .with_limits(f64::NEG_INFINITY, 0.1)
+                .with_saturation_voltage(OPEN_COLLECTOR_SATURATION_VOLTAGE),
+            );
+    }
+}
+
+/// Voltage scale over which the LM317 pass device saturates.
+const PASS_SATURATION_VOLTAGE: f64 = 0.5;
+
+/// An LM317 style adjustable regulator: drives the output to hold it 1.25V above the adjust
+/// pin, which sources 50uA, with a 1.5A current limit. Dropout is only modelled roughly, by
+/// the pass device saturating over the last few hundred millivolts of headroom.
+///
+/// Uses one internal node (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lm317 {
+    input_node: usize,
+    output_node: usize,
+    adjust_node: usize,
+}
+
+impl Lm317 {
+    pub fn new(input_node: usize, output_node: usize, adjust_node: usize) -> Self {
+        Self {
+            input_node,
+            output_node,
+            adjust_node,
+        }
+    }
+}
+
+impl Subcircuit for Lm317 {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.adjust_node));
+        let reference = netlist.add_node();
+
+        netlist
+            .add_component(VoltageSource::new(reference, self.adjust_node, 1.25))
+            .add_component(CurrentSource::new(self.adjust_node, self.input_node, 50e-6))
+            .add_component(
+                ControlledSource::vccs(
+                    self.output_node,
+                    self.input_node,
+                    reference,
+                    self.output_node,
+                    ERROR_AMPLIFIER_TRANSCONDUCTANCE,
+                )
+                .with_limits(f64::NEG_INFINITY, 1.5)
+                .with_saturation_voltage(PASS_SATURATION_VOLTAGE),
+            );
+    }
+}
+
+/// Resistance through which the target output of a [`LinearRegulator`] is clamped below its
+/// input less the dropout voltage.
+const DROPOUT_CLAMP_RESISTANCE: f64 = 1e3;
+
+/// Resistance of the low-pass filter setting the supply rejection pole of a
+/// [`LinearRegulator`].
+const PSRR_FILTER_RESISTANCE: f64 = 1e3;
+
+/// Hysteresis of the thermal shutdown of a [`LinearRegulator`], in kelvin.
+const THERMAL_SHUTDOWN_HYSTERESIS: f64 = 20.0;
+
+/// A fixed three-terminal linear regulator, such as a 7805 or an LDO, described by its
+/// datasheet parameters rather than its internals:
+///
+/// - the output regulates to the nominal voltage with an input at the dropout voltage above
+///   it, rising with the input by the line regulation (in V/V),
+/// - it falls with the load current by the load regulation (in ohms),
+/// - the pass device can't pull the output closer to the input than the dropout voltage, and
+///   limits the output current,
+/// - the input ripple reaching the output is attenuated by the line regulation up to the supply
+///   rejection pole, and 20dB/decade less above it,
+/// - the ground pin draws the quiescent current from the input.
+///
+/// The pass device sinks current back into the input whenever the output is above its target,
+/// e.g. once the input falls below the output plus the dropout voltage, where real regulators
+/// only do once the input falls below the output.
+///
+/// Uses seven internal nodes (four without a supply rejection pole), and two more for the
+/// thermal shutdown (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRegulator {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    voltage: f64,
+    dropout_voltage: f64,
+    line_regulation: f64,
+    load_regulation: f64,
+    psrr_pole: Option<f64>,
+    current_limit: f64,
+    quiescent_current: f64,
+    thermal_shutdown: Option<(usize, f64)>,
+}
+
+impl LinearRegulator {
+    /// Creates a 78xx style regulator: 2V dropout, 70dB line rejection rolling off above 1kHz,
+    /// 10mOhm load regulation, 1.5A current limit and 5mA quiescent current.
+    pub fn new(input_node: usize, output_node: usize, ground_node: usize, voltage: f64) -> Self {
+        Self {
+            input_node,
+            output_node,
+            ground_node,
+            voltage,
+            dropout_voltage: 2.0,
+            line_regulation: 3e-4,
+            load_regulation: 1e-2,
+            psrr_pole: Some(1e3),
+            current_limit: 1.5,
+            quiescent_current: 5e-3,
+            thermal_shutdown: None,
+        }
+    }
+
+    /// Creates a low dropout regulator: 200mV dropout, 60dB line rejection rolling off above
+    /// 1kHz, 10mOhm load regulation, 500mA current limit and 50uA quiescent current.
+    pub fn ldo(input_node: usize, output_node: usize, ground_node: usize, voltage: f64) -> Self {
+        Self::new(input_node, output_node, ground_node, voltage)
+            .with_dropout_voltage(0.2)
+            .with_line_regulation(1e-3)
+            .with_current_limit(0.5)
+            .with_quiescent_current(50e-6)
+    }
+
+    pub fn with_dropout_voltage(mut self, voltage: f64) -> Self {
+        self.dropout_voltage = voltage;
+        self
+    }
+
+    /// Sets the change of the output voltage per volt of input, which is also the supply
+    /// rejection below its pole.
+    pub fn with_line_regulation(mut self, gain: f64) -> Self {
+        self.line_regulation = gain;
+        self
+    }
+
+    /// Sets the drop of the output voltage per amp of load current, in ohms.
+    pub fn with_load_regulation(mut self, resistance: f64) -> Self {
+        self.load_regulation = resistance;
+        self
+    }
+
+    /// Sets the frequency above which the supply rejection degrades, or removes the pole with
+    /// None so it stays at the line regulation at every frequency.
+    pub fn with_psrr_pole(mut self, frequency: Option<f64>) -> Self {
+        self.psrr_pole = frequency;
+        self
+    }
+
+    pub fn with_current_limit(mut self, current: f64) -> Self {
+        self.current_limit = current;
+        self
+    }
+
+    pub fn with_quiescent_current(mut self, current: f64) -> Self {
+        self.quiescent_current = current;
+        self
+    }
+
+    /// Shuts the output down while the junction temperature, the voltage of a thermal node in
+    /// kelvin (see [`ThermoelectricModule`](crate::components::ThermoelectricModule) for the
+    /// analogy), is above the shutdown temperature, until it cools 20K below it. The shutdown
+    /// lags a timestep behind the temperature.
+    ///
+    /// The regulator doesn't heat the node itself, the sources it is built from being linear:
+    /// its dissipation has to be fed into the thermal network by the circuit.
+    pub fn with_thermal_shutdown(mut self, junction_node: usize, temperature: f64) -> Self {
+        self.thermal_shutdown = Some((junction_node, temperature));
+        self
+    }
+}
+
+impl Subcircuit for LinearRegulator {
+    fn add_to(&self, netlist: &mut Netlist) {
+        let (input, output, ground) = (self.input_node, self.output_node, self.ground_node);
+        let junction = self.thermal_shutdown.map_or(0, |(node, _)| node);
+        netlist.reserve_nodes(input.max(output).max(ground).max(junction));
+        let offset = netlist.add_node();
+        let mut target = netlist.add_node();
+        let pass = netlist.add_node();
+
+        // The target output stacks a source setting the nominal output at the edge of dropout
+        // on top of the input coupled through the line regulation. Above the rejection pole,
+        // the coupling is the input less the line rejected part of its low-passed copy.
+        let a = self.line_regulation;
+        netlist.add_component(VoltageSource::new(
+            offset,
+            ground,
+            self.voltage - a * (self.voltage + self.dropout_voltage),
+        ));
+        match self.psrr_pole {
+            Some(frequency) => {
+                let buffer = netlist.add_node();
+                let filtered = netlist.add_node();
+                let coupled = netlist.add_node();
+                let time_constant = a / (2.0 * std::f64::consts::PI * frequency);
+                netlist
+                    .add_component(ControlledSource::vcvs(buffer, ground, input, ground, 1.0))
+                    .add_component(Resistor::new(buffer, filtered, PSRR_FILTER_RESISTANCE))
+                    .add_component(Capacitor::new(
+                        filtered,
+                        ground,
+                        time_constant / PSRR_FILTER_RESISTANCE,
+                        0.0,
+                    ))
+                    .add_component(ControlledSource::vcvs(coupled, offset, input, ground, 1.0))
+                    .add_component(ControlledSource::vcvs(
+                        target,
+                        coupled,
+                        filtered,
+                        ground,
+                        a - 1.0,
+                    ));
+            }
+            None => {
+                netlist.add_component(ControlledSource::vcvs(target, offset, input, ground, a));
+            }
+        }
+
+        // Once tripped, the thermal shutdown pulls the target down by the nominal voltage.
+        if let Some((junction, temperature)) = self.thermal_shutdown {
+            let threshold = netlist.add_node();
+            let enabled = netlist.add_node();
+            netlist
+                .add_component(VoltageSource::new(threshold, 0, temperature))
+                .add_component(
+                    ControlledSource::vcvs(target, enabled, junction, threshold, 1.0)
+                        .with_limits(0.0, self.voltage)
+                        .with_hysteresis(-THERMAL_SHUTDOWN_HYSTERESIS, 0.0),
+                );
+            target = enabled;
+        }
+
+        // The error amplifier drives the pass device, whose transconductance sets the load
+        // regulation, from the input less the dropout voltage. The target is clamped below
+        // that, rather than the pass device saturating, so the amplifier never drives it
+        // against the input.
+        let clamped = netlist.add_node();
+        netlist
+            .add_component(CurrentSource::new(ground, input, self.quiescent_current))
+            .add_component(VoltageSource::new(input, pass, self.dropout_voltage))
+            .add_component(Resistor::new(target, clamped, DROPOUT_CLAMP_RESISTANCE))
+            .add_component(Diode::new(clamped, pass, DiodeModel::Ideal))
+            .add_component(
+                ControlledSource::vccs(output, pass, clamped, output, 1.0 / self.load_regulation)
+                    .with_limits(f64::NEG_INFINITY, self.current_limit),
+            );
+    }
+}
+
+/// Saturation voltage of the 555 output and discharge transistors. Up to a few volts across
+/// them they behave as switches of about 5 ohms (2A over 10V) rather than saturating current
+/// sources, which Newton-Raphson handles much better when the output switches.
+const SWITCH_SATURATION_VOLTAGE: f64 = 10.0;
+
+/// An NE555 style timer with the usual pin out. The threshold comparator resets and the
+/// trigger comparator sets an internal latch, the reset pin (active below 0.7V) overrides both.
+/// While the latch is set the output is switched to the supply; while it is reset the output
+/// and discharge pins are switched to ground.
+///
+/// The latch is a Schmitt trigger (see [`ControlledSource::with_hysteresis`]), so the outputs
+/// respond a timestep after the comparators. It starts reset.
+///
+/// Uses four internal nodes (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timer555 {
+    ground_node: usize,
+    trigger_node: usize,
+    output_node: usize,
+    reset_node: usize,
+    control_node: usize,
+    threshold_node: usize,
+    discharge_node: usize,
+    supply_node: usize,
+}
+
+impl Timer555 {
+    /// Creates a timer from its pins in the order of the 8 pin package.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ground_node: usize,
+        trigger_node: usize,
+        output_node: usize,
+        reset_node: usize,
+        control_node: usize,
+        threshold_node: usize,
+        discharge_node: usize,
+        supply_node: usize,
+    ) -> Self {
+        Self {
+            ground_node,
+            trigger_node,
+            output_node,
+            reset_node,
+            control_node,
+            threshold_node,
+            discharge_node,
+            supply_node,
+        }
+    }
+}
+
+impl Subcircuit for Timer555 {
+    fn add_to(&self, netlist: &mut Netlist) {
+        let ground = self.ground_node;
+        netlist.reserve_nodes(
+            [
+                ground,
+                self.trigger_node,
+                self.output_node,
+                self.reset_node,
+                self.control_node,
+                self.threshold_node,
+                self.discharge_node,
+                self.supply_node,
+            ]
+            .into_iter()
+            .max()
+            .unwrap(),
+        );
+        let trigger_reference = netlist.add_node();
+        let reset_reference = netlist.add_node();
+        let latch_sum = netlist.add_node();
+        let latch = netlist.add_node();
+
+        // The divider sets the threshold at 2/3 and the trigger at 1/3 of the supply.
+        netlist
+            .add_component(Resistor::new(self.supply_node, self.control_node, 5e3))
+            .add_component(Resistor::new(self.control_node, trigger_reference, 5e3))
+            .add_component(Resistor::new(trigger_reference, ground, 5e3))
+            .add_component(VoltageSource::new(reset_reference, ground, 0.7));
+
+        // The latch is 1 while set and -1 while reset. It is set and reset by the comparator
+        // currents summed through 1k, holding its state while none of them flow.
+        netlist
+            .add_component(Resistor::new(latch_sum, ground, 1e3))
+            .add_component(
+                ControlledSource::vccs(
+                    latch_sum,
+                    ground,
+                    trigger_reference,
+                    self.trigger_node,
+                    COMPARATOR_TRANSCONDUCTANCE,
+                )
+                .with_limits(0.0, 4e-3),
+            )
+            .add_component(
+                ControlledSource::vccs(
+                    ground,
+                    latch_sum,
+                    self.threshold_node,
+                    self.control_node,
+                    COMPARATOR_TRANSCONDUCTANCE,
+                )
+                .with_limits(0.0, 4e-3),
+            )
+            .add_component(
+                ControlledSource::vccs(
+                    ground,
+                    latch_sum,
+                    reset_reference,
+                    self.reset_node,
+                    COMPARATOR_TRANSCONDUCTANCE,
+                )
+                .with_limits(0.0, 8e-3),
+            )
+            .add_component(
+                ControlledSource::vcvs(latch, ground, latch_sum, ground, 1.0)
+                    .with_limits(-1.0, 1.0)
+                    .with_hysteresis(-1.0, 1.0),
+            );
+
+        // The output is switched to the supply while the latch is set, the output and
+        // discharge to ground while it is reset.
+        netlist.add_component(
+            ControlledSource::vccs(self.output_node, self.supply_node, latch, ground, 5.0)
+                .with_limits(0.0, 2.0)
+                .with_saturation_voltage(SWITCH_SATURATION_VOLTAGE),
+        );
+        for pin in [self.output_node, self.discharge_node] {
+            netlist.add_component(
+                ControlledSource::vccs(ground, pin, ground, latch, 5.0)
+                    .with_limits(0.0, 2.0)
+                    .with_saturation_voltage(SWITCH_SATURATION_VOLTAGE),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ACSolver, BESolver};
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_lm393() {
+        for (v_plus, low) in [(1.0, true), (3.0, false)] {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 5.0))
+                .add_component(VoltageSource::new(2, 0, v_plus))
+                .add_component(VoltageSource::new(3, 0, 2.0))
+                .add_component(Resistor::new(1, 4, 10e3))
+                .add_subcircuit(&Lm393::new(2, 3, 4, 0));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+
+            let v = solver.get_node_voltage(4);
+            if low {
+                assert!(v < 0.4);
+            } else {
+                assert_relative_eq!(v, 5.0, epsilon = 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tl431() {
+        // A 5V shunt regulator: 10k/10k divider on the reference pin, fed from 12V through
+        // 470 ohms.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(1, 2, 470.0))
+            .add_component(Resistor::new(2, 3, 10e3))
+            .add_component(Resistor::new(3, 0, 10e3))
+            .add_subcircuit(&Tl431::new(2, 0, 3));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+
+        assert_relative_eq!(solver.get_node_voltage(2), 4.99, max_relative = 1e-3);
+    }
+
+    #[test]
+    fn test_lm317() {
+        let output_voltage = |load: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 12.0))
+                .add_component(Resistor::new(2, 3, 240.0))
+                .add_component(Resistor::new(3, 0, 720.0))
+                .add_component(Resistor::new(2, 0, load))
+                .add_subcircuit(&Lm317::new(1, 2, 3));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+            solver.get_node_voltage(2)
+        };
+
+        // 240 ohms and 720 ohms program 1.25*(1 + 720/240) + 50uA*720 = 5.036V.
+        assert_relative_eq!(output_voltage(50.0), 5.036, max_relative = 1e-3);
+        // Shorted, the output is current limited.
+        assert_relative_eq!(output_voltage(0.1) / 0.1, 1.5, max_relative = 1e-2);
+    }
+
+    #[test]
+    fn test_linear_regulator() {
+        let solve = |regulator: LinearRegulator, input: f64, load: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, input))
+                .add_component(Resistor::new(2, 0, load))
+                .add_subcircuit(&regulator);
+            let mut solver = BESolver::new(&mut netlist);
+            for _ in 0..3 {
+                solver.solve(1e-3).unwrap();
+            }
+            (
+                solver.get_node_voltage(2),
+                netlist.get_components()[0].get_current(),
+            )
+        };
+
+        // A 7805 from 12V into 50 ohms: 5V, plus 3e-4 * (12 - 7)V of line regulation, less
+        // 100mA * 10mOhm of load regulation. The input supplies the 5mA quiescent current on
+        // top of the load.
+        let regulator = LinearRegulator::new(1, 2, 0, 5.0);
+        let (v, i) = solve(regulator, 12.0, 50.0);
+        assert_relative_eq!(v, 5.0 + 1.5e-3 - 1e-3, epsilon = 1e-4);
+        assert_relative_eq!(i, v / 50.0 + 5e-3, max_relative = 1e-3);
+
+        // In dropout the output follows the input.
+        let (v, _) = solve(regulator, 6.0, 50.0);
+        assert_relative_eq!(v, 4.0, epsilon = 1e-2);
+        let (v, _) = solve(LinearRegulator::ldo(1, 2, 0, 3.3), 3.4, 33.0);
+        assert_relative_eq!(v, 3.2, epsilon = 1e-2);
+
+        // Shorted, the output is current limited.
+        let (v, _) = solve(regulator, 12.0, 0.1);
+        assert_relative_eq!(v / 0.1, 1.5, max_relative = 1e-2);
+
+        // The input ripple is rejected by 70dB at low frequencies, 20dB less a decade above the
+        // pole.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(2, 0, 50.0))
+            .add_subcircuit(&regulator);
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        let ac = ACSolver::new(&netlist);
+        assert_relative_eq!(
+            ac.solve(10.0).unwrap().get_node_voltage(2).norm(),
+            3e-4,
+            max_relative = 1e-2
+        );
+        assert_relative_eq!(
+            ac.solve(10e3).unwrap().get_node_voltage(2).norm(),
+            3e-3,
+            max_relative = 2e-2
+        );
+
+        // Overheated, the output shuts down a timestep later.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(2, 0, 50.0))
+            .add_component(VoltageSource::new(3, 0, 300.0))
+            .add_subcircuit(&regulator.with_thermal_shutdown(3, 423.0));
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(2), 5.0, epsilon = 1e-2);
+        netlist.get_components_mut()[2].set_value(450.0);
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        solver.solve(1e-3).unwrap();
+        assert!(solver.get_node_voltage(2) < 0.1);
+    }
+
+    #[test]
+    fn test_555_astable() {
+        // Ra = 1k, Rb = 10k, C = 10nF oscillates at 1.44/((Ra + 2*Rb)*C) ~ 6.86kHz.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 9.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Resistor::new(2, 3, 10e3))
+            .add_component(Capacitor::new(3, 0, 10e-9, 0.0))
+            .add_component(Resistor::new(4, 0, 10e3))
+            .add_component(Resistor::new(1, 5, 1e3))
+            .add_subcircuit(&Timer555::new(0, 3, 4, 5, 6, 3, 2, 1));
+        let mut solver = BESolver::new(&mut netlist);
+
+        let mut rising_edges = Vec::new();
+        let mut high = false;
+        while solver.get_time() < 1e-3 {
+            solver.solve(2e-7).unwrap();
+            let v = solver.get_node_voltage(4);
+            if !high && v > 4.5 {
+                rising_edges.push(solver.get_time());
+            }
+            high = v > 4.5;
+        }
+
+        // The first cycle starts from a discharged capacitor, so it is skipped.
+        let periods = rising_edges.len() - 2;
+        let period = (rising_edges.last().unwrap() - rising_edges[1]) / periods as f64;
+        assert_relative_eq!(1.0 / period, 1.44 / (21e3 * 10e-9), max_relative = 2e-2);
+    }
+}
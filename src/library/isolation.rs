@@ -0,0 +1,264 @@
+use crate::{
+    components::{
+        Capacitor, ControlledSource, CurrentTransformer, Diode, DiodeModel, Inductor, Netlist,
+    },
+    library::Subcircuit,
+};
+
+/// Voltage scale over which the optocoupler output saturates.
+const OUTPUT_SATURATION_VOLTAGE: f64 = 0.1;
+
+/// An optocoupler: an LED whose current drives a phototransistor through the current transfer
+/// ratio (CTR).
+///
+/// The output transistor sinks CTR times the LED current from the collector into the emitter,
+/// collapsing as the collector falls to within about 0.1V of the emitter like a saturated
+/// transistor.
+///
+/// Uses one internal node (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Optocoupler {
+    anode_node: usize,
+    cathode_node: usize,
+    collector_node: usize,
+    emitter_node: usize,
+    current_transfer_ratio: f64,
+    led_model: DiodeModel,
+    output_capacitance: Option<f64>,
+}
+
+impl Optocoupler {
+    /// Creates an optocoupler with the given current transfer ratio (1.0 for 100%) and an LED
+    /// dropping about 1.2V at 10mA.
+    pub fn new(
+        anode_node: usize,
+        cathode_node: usize,
+        collector_node: usize,
+        emitter_node: usize,
+        current_transfer_ratio: f64,
+    ) -> Self {
+        Self {
+            anode_node,
+            cathode_node,
+            collector_node,
+            emitter_node,
+            current_transfer_ratio,
+            led_model: DiodeModel::Shockley {
+                saturation_current: 1e-12,
+                emission_coefficient: 2.0,
+            },
+            output_capacitance: None,
+        }
+    }
+
+    pub fn with_led_model(mut self, model: DiodeModel) -> Self {
+        self.led_model = model;
+        self
+    }
+
+    /// Adds a capacitance between the collector and emitter, which together with the load
+    /// resistance sets the bandwidth of the output.
+    pub fn with_output_capacitance(mut self, capacitance: f64) -> Self {
+        self.output_capacitance = Some(capacitance);
+        self
+    }
+}
+
+impl Subcircuit for Optocoupler {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(
+            self.anode_node
+                .max(self.cathode_node)
+                .max(self.collector_node)
+                .max(self.emitter_node),
+        );
+        let led_cathode = netlist.add_node();
+
+        netlist
+            .add_component(Diode::new(self.anode_node, led_cathode, self.led_model))
+            // The LED current is sensed on its way to the cathode and mirrored into the output.
+            .add_component(
+                ControlledSource::cccs(
+                    self.emitter_node,
+                    self.collector_node,
+                    led_cathode,
+                    self.cathode_node,
+                    self.current_transfer_ratio,
+                )
+                .with_saturation_voltage(OUTPUT_SATURATION_VOLTAGE),
+            );
+
+        if let Some(capacitance) = self.output_capacitance {
+            netlist.add_component(Capacitor::new(
+                self.collector_node,
+                self.emitter_node,
+                capacitance,
+                0.0,
+            ));
+        }
+    }
+}
+
+/// A gate drive transformer: a 1:N transformer with magnetizing and leakage inductance, and
+/// optionally a DC blocking capacitor in series with the primary so the core doesn't walk
+/// towards saturation with the duty cycle.
+///
+/// Inductances are referred to the primary. Uses up to two internal nodes (see
+/// [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateDriveTransformer {
+    primary_positive_node: usize,
+    primary_negative_node: usize,
+    secondary_positive_node: usize,
+    secondary_negative_node: usize,
+    turns: f64,
+    magnetizing_inductance: f64,
+    leakage_inductance: Option<f64>,
+    winding_resistance: f64,
+    blocking_capacitance: Option<f64>,
+}
+
+impl GateDriveTransformer {
+    /// Creates a transformer with `turns` secondary turns per primary turn and the given
+    /// magnetizing inductance.
+    pub fn new(
+        primary_positive_node: usize,
+        primary_negative_node: usize,
+        secondary_positive_node: usize,
+        secondary_negative_node: usize,
+        turns: f64,
+        magnetizing_inductance: f64,
+    ) -> Self {
+        Self {
+            primary_positive_node,
+            primary_negative_node,
+            secondary_positive_node,
+            secondary_negative_node,
+            turns,
+            magnetizing_inductance,
+            leakage_inductance: None,
+            winding_resistance: 0.0,
+            blocking_capacitance: None,
+        }
+    }
+
+    pub fn with_leakage_inductance(mut self, inductance: f64) -> Self {
+        self.leakage_inductance = Some(inductance);
+        self
+    }
+
+    /// Sets the resistance of the secondary winding.
+    pub fn with_winding_resistance(mut self, resistance: f64) -> Self {
+        self.winding_resistance = resistance;
+        self
+    }
+
+    /// Adds a DC blocking capacitor (initially discharged) in series with the primary.
+    pub fn with_blocking_capacitor(mut self, capacitance: f64) -> Self {
+        self.blocking_capacitance = Some(capacitance);
+        self
+    }
+}
+
+impl Subcircuit for GateDriveTransformer {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(
+            self.primary_positive_node
+                .max(self.primary_negative_node)
+                .max(self.secondary_positive_node)
+                .max(self.secondary_negative_node),
+        );
+        let mut primary = self.primary_positive_node;
+
+        if let Some(capacitance) = self.blocking_capacitance {
+            let node = netlist.add_node();
+            netlist.add_component(Capacitor::new(primary, node, capacitance, 0.0));
+            primary = node;
+        }
+
+        if let Some(inductance) = self.leakage_inductance {
+            let node = netlist.add_node();
+            netlist.add_component(Inductor::new(primary, node, inductance, 0.0));
+            primary = node;
+        }
+
+        netlist.add_component(
+            CurrentTransformer::new(
+                primary,
+                self.primary_negative_node,
+                self.secondary_positive_node,
+                self.secondary_negative_node,
+                self.turns,
+                self.turns * self.turns * self.magnetizing_inductance,
+            )
+            .with_winding_resistance(self.winding_resistance),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{CurrentProbe, Resistor, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_optocoupler_transfer() {
+        // 5V through 390 ohms into the LED, the output pulled up to 5V through 1k.
+        let output_voltage = |pullup: f64| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 5.0))
+                .add_component(Resistor::new(1, 2, 390.0))
+                .add_component(CurrentProbe::new(3, 0))
+                .add_component(Resistor::new(1, 4, pullup))
+                .add_subcircuit(&Optocoupler::new(2, 3, 4, 0, 0.5));
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(1e-3).unwrap();
+            let v = solver.get_node_voltage(4);
+
+            let led: CurrentProbe = netlist.get_components()[2].clone().try_into().unwrap();
+            (led.get_current(), v)
+        };
+
+        // In the active region the collector current is half the LED current.
+        let (led_current, v) = output_voltage(100.0);
+        assert!(led_current > 9e-3 && led_current < 10e-3);
+        assert_relative_eq!((5.0 - v) / 100.0, 0.5 * led_current, max_relative = 1e-6);
+
+        // A large pullup saturates the output.
+        let (_, v) = output_voltage(10e3);
+        assert!(v > 0.0 && v < 0.2);
+    }
+
+    #[test]
+    fn test_gate_drive_transformer() {
+        // A 1:2 transformer driven with 12V through a blocking capacitor, into a 10 ohm load.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 12.0))
+            .add_component(Resistor::new(2, 0, 10.0))
+            .add_subcircuit(
+                &GateDriveTransformer::new(1, 0, 2, 0, 2.0, 1e-3)
+                    .with_leakage_inductance(1e-7)
+                    .with_blocking_capacitor(10e-6),
+            );
+        let mut solver = BESolver::new(&mut netlist);
+
+        // Right after the step the secondary reflects the primary voltage.
+        for _ in 0..20 {
+            solver.solve(1e-8).unwrap();
+        }
+        assert_relative_eq!(solver.get_node_voltage(2), 24.0, max_relative = 2e-2);
+
+        // The blocking capacitor eventually takes all of the DC.
+        for _ in 0..2000 {
+            solver.solve(1e-5).unwrap();
+        }
+        assert!(solver.get_node_voltage(2).abs() < 0.1);
+    }
+}
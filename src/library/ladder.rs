@@ -0,0 +1,629 @@
+use std::f64::consts::PI;
+
+use nalgebra::Complex;
+
+use crate::{
+    components::{Capacitor, Inductor, Netlist, Resistor},
+    library::Subcircuit,
+};
+
+/// An RC ladder: sections of a series resistor followed by a capacitor to ground, e.g. to model
+/// a distributed RC line or to benchmark the solver on circuits of growing size.
+///
+/// Uses `sections - 1` internal nodes (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RcLadder {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    sections: usize,
+    resistance: f64,
+    capacitance: f64,
+}
+
+impl RcLadder {
+    /// Creates a ladder of the given number of sections, each of the given resistance and
+    /// capacitance.
+    pub fn new(
+        input_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        sections: usize,
+        resistance: f64,
+        capacitance: f64,
+    ) -> Self {
+        Self {
+            input_node,
+            output_node,
+            ground_node,
+            sections,
+            resistance,
+            capacitance,
+        }
+    }
+
+    pub fn get_sections(&self) -> usize {
+        self.sections
+    }
+
+    /// Gets the Elmore delay from the input to the output, the sum over the capacitors of each
+    /// times the resistance between it and the input: R*C*n*(n + 1)/2.
+    pub fn get_elmore_delay(&self) -> f64 {
+        let n = self.sections as f64;
+        self.resistance * self.capacitance * n * (n + 1.0) / 2.0
+    }
+}
+
+impl Subcircuit for RcLadder {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.ground_node));
+        let mut node = self.input_node;
+        for section in 0..self.sections {
+            let next = if section + 1 == self.sections {
+                self.output_node
+            } else {
+                netlist.add_node()
+            };
+            netlist
+                .add_component(Resistor::new(node, next, self.resistance))
+                .add_component(Capacitor::new(
+                    next,
+                    self.ground_node,
+                    self.capacitance,
+                    0.0,
+                ));
+            node = next;
+        }
+    }
+}
+
+/// A transmission line approximated by a chain of LC sections: a series inductor (and
+/// resistor, if lossy) followed by a capacitor (and conductance, if lossy) to ground.
+///
+/// Each section delays by sqrt(L*C) of the section, so a line is followed faithfully up to
+/// frequencies whose wavelength spans several sections, and ringing appears on edges faster
+/// than a section's delay. Uses an internal node per section and one more per section with a
+/// series resistance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransmissionLineChain {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    sections: usize,
+    inductance: f64,
+    capacitance: f64,
+    resistance: f64,
+    conductance: f64,
+}
+
+impl TransmissionLineChain {
+    /// Creates a lossless line of the given total inductance and capacitance split into the
+    /// given number of sections.
+    pub fn new(
+        input_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        sections: usize,
+        inductance: f64,
+        capacitance: f64,
+    ) -> Self {
+        Self {
+            input_node,
+            output_node,
+            ground_node,
+            sections,
+            inductance,
+            capacitance,
+            resistance: 0.0,
+            conductance: 0.0,
+        }
+    }
+
+    /// Creates a lossless line of the given characteristic impedance and delay in seconds.
+    pub fn from_impedance(
+        input_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        sections: usize,
+        characteristic_impedance: f64,
+        delay: f64,
+    ) -> Self {
+        Self::new(
+            input_node,
+            output_node,
+            ground_node,
+            sections,
+            characteristic_impedance * delay,
+            delay / characteristic_impedance,
+        )
+    }
+
+    /// Adds losses: the total series resistance of the conductors and the total conductance of
+    /// the dielectric, split over the sections like the inductance and the capacitance.
+    pub fn with_losses(mut self, resistance: f64, conductance: f64) -> Self {
+        self.resistance = resistance;
+        self.conductance = conductance;
+        self
+    }
+
+    pub fn get_sections(&self) -> usize {
+        self.sections
+    }
+
+    /// Gets the characteristic impedance of the lossless line, sqrt(L/C).
+    pub fn get_characteristic_impedance(&self) -> f64 {
+        (self.inductance / self.capacitance).sqrt()
+    }
+
+    /// Gets the delay of the lossless line, sqrt(L*C).
+    pub fn get_delay(&self) -> f64 {
+        (self.inductance * self.capacitance).sqrt()
+    }
+}
+
+impl Subcircuit for TransmissionLineChain {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.ground_node));
+        let n = self.sections as f64;
+        let mut node = self.input_node;
+        for section in 0..self.sections {
+            let next = if section + 1 == self.sections {
+                self.output_node
+            } else {
+                netlist.add_node()
+            };
+            if self.resistance > 0.0 {
+                let inductor = netlist.add_node();
+                netlist.add_component(Resistor::new(node, inductor, self.resistance / n));
+                node = inductor;
+            }
+            netlist
+                .add_component(Inductor::new(node, next, self.inductance / n, 0.0))
+                .add_component(Capacitor::new(
+                    next,
+                    self.ground_node,
+                    self.capacitance / n,
+                    0.0,
+                ));
+            if self.conductance > 0.0 {
+                netlist.add_component(Resistor::new(next, self.ground_node, n / self.conductance));
+            }
+            node = next;
+        }
+    }
+}
+
+/// The response of a low-pass filter prototype.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterResponse {
+    /// Maximally flat passband, 3dB down at the cutoff.
+    Butterworth,
+    /// Equiripple passband with the given ripple in decibels, down by the ripple at the cutoff,
+    /// for a steeper rolloff than Butterworth of the same order.
+    Chebyshev { ripple: f64 },
+}
+
+/// The band a filter passes, in hertz, into which a low-pass prototype is transformed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterBand {
+    LowPass {
+        cutoff: f64,
+    },
+    HighPass {
+        cutoff: f64,
+    },
+    /// Passes the band of the given width around the geometric center frequency.
+    BandPass {
+        center: f64,
+        bandwidth: f64,
+    },
+    /// Stops the band of the given width around the geometric center frequency.
+    BandStop {
+        center: f64,
+        bandwidth: f64,
+    },
+}
+
+impl FilterBand {
+    /// Maps the complex frequency s in rad/s to the frequency of the normalized low-pass
+    /// prototype: s/wc for a low-pass, wc/s for a high-pass, (s^2 + w0^2)/(B*s) for a band-pass
+    /// and B*s/(s^2 + w0^2) for a band-stop of bandwidth B.
+    pub fn to_prototype(&self, s: Complex<f64>) -> Complex<f64> {
+        match *self {
+            Self::LowPass { cutoff } => s / (2.0 * PI * cutoff),
+            Self::HighPass { cutoff } => 2.0 * PI * cutoff / s,
+            Self::BandPass { center, bandwidth } => {
+                (s * s + (2.0 * PI * center).powi(2)) / (2.0 * PI * bandwidth * s)
+            }
+            Self::BandStop { center, bandwidth } => {
+                2.0 * PI * bandwidth * s / (s * s + (2.0 * PI * center).powi(2))
+            }
+        }
+    }
+}
+
+/// A normalized low-pass ladder prototype, terminated in 1Ω with a cutoff of 1rad/s: the
+/// element values g1 to gn of the alternating shunt capacitors and series inductors, and the
+/// load gn+1, as tabulated by Matthaei, Young and Jones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterPrototype {
+    response: FilterResponse,
+    order: usize,
+}
+
+impl FilterPrototype {
+    pub fn new(response: FilterResponse, order: usize) -> Self {
+        Self { response, order }
+    }
+
+    pub fn get_response(&self) -> FilterResponse {
+        self.response
+    }
+
+    pub fn get_order(&self) -> usize {
+        self.order
+    }
+
+    /// Gets the element values g1 to gn.
+    pub fn get_elements(&self) -> Vec<f64> {
+        let n = self.order as f64;
+        let a = |k: usize| ((2 * k - 1) as f64 * PI / (2.0 * n)).sin();
+        match self.response {
+            FilterResponse::Butterworth => (1..=self.order).map(|k| 2.0 * a(k)).collect(),
+            FilterResponse::Chebyshev { .. } => {
+                let gamma = (self.beta() / (2.0 * n)).sinh();
+                let b = |k: usize| gamma.powi(2) + (k as f64 * PI / n).sin().powi(2);
+
+                let mut elements = vec![2.0 * a(1) / gamma];
+                for k in 2..=self.order {
+                    let previous = elements[k - 2];
+                    elements.push(4.0 * a(k - 1) * a(k) / (b(k - 1) * previous));
+                }
+                elements
+            }
+        }
+    }
+
+    /// Gets the poles of the prototype's transfer function, with the cutoff at 1rad/s.
+    pub fn get_poles(&self) -> Vec<Complex<f64>> {
+        let n = self.order as f64;
+        (1..=self.order)
+            .map(|k| {
+                let theta = (2 * k - 1) as f64 * PI / (2.0 * n);
+                match self.response {
+                    FilterResponse::Butterworth => Complex::new(-theta.sin(), theta.cos()),
+                    FilterResponse::Chebyshev { ripple } => {
+                        let epsilon = (10f64.powf(ripple / 10.0) - 1.0).sqrt();
+                        let v = (1.0 / epsilon).asinh() / n;
+                        Complex::new(-v.sinh() * theta.sin(), v.cosh() * theta.cos())
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Gets the load gn+1: a resistance if gn is a shunt capacitor, a conductance if it is a
+    /// series inductor. Chebyshev prototypes of even order aren't terminated in 1.
+    pub fn get_load(&self) -> f64 {
+        match self.response {
+            FilterResponse::Chebyshev { .. } if self.order.is_multiple_of(2) => {
+                (self.beta() / 4.0).tanh().powi(-2)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Gets ln(coth(ripple/17.37)) of a Chebyshev response.
+    fn beta(&self) -> f64 {
+        match self.response {
+            FilterResponse::Chebyshev { ripple } => {
+                (1.0 / (ripple / (40.0 / 10f64.ln())).tanh()).ln()
+            }
+            FilterResponse::Butterworth => 0.0,
+        }
+    }
+}
+
+/// A branch of a transformed ladder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Branch {
+    Capacitor(f64),
+    Inductor(f64),
+    /// An inductor and a capacitor in series.
+    SeriesLc(f64, f64),
+    /// An inductor and a capacitor in parallel.
+    ParallelLc(f64, f64),
+}
+
+/// A doubly terminated LC ladder filter instantiated from a [`FilterPrototype`], scaled to a
+/// termination impedance and transformed into a [`FilterBand`], a low-pass by default.
+///
+/// The source resistance runs from the input node to the first shunt branch, followed by
+/// alternating series and shunt branches, and the load resistance is across the output node, so
+/// an ideal voltage source at the input sees the filter as designed. The passband gain is the
+/// divider of the terminations, a half for equal terminations. The capacitors and inductors of
+/// the low-pass become inductors and capacitors in a high-pass, parallel and series LC
+/// resonators in a band-pass, and series and parallel resonators in a band-stop. Uses an
+/// internal node per series branch and per series resonator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LcLadderFilter {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    prototype: FilterPrototype,
+    band: FilterBand,
+    impedance: f64,
+}
+
+impl LcLadderFilter {
+    /// Creates the low-pass filter of the prototype with the cutoff in hertz and the source
+    /// resistance in ohms.
+    pub fn new(
+        input_node: usize,
+        output_node: usize,
+        ground_node: usize,
+        prototype: FilterPrototype,
+        cutoff: f64,
+        impedance: f64,
+    ) -> Self {
+        Self {
+            input_node,
+            output_node,
+            ground_node,
+            prototype,
+            band: FilterBand::LowPass { cutoff },
+            impedance,
+        }
+    }
+
+    /// Transforms the prototype into the given band rather than a low-pass.
+    pub fn with_band(mut self, band: FilterBand) -> Self {
+        self.band = band;
+        self
+    }
+
+    pub fn get_prototype(&self) -> FilterPrototype {
+        self.prototype
+    }
+
+    pub fn get_band(&self) -> FilterBand {
+        self.band
+    }
+
+    pub fn get_source_resistance(&self) -> f64 {
+        self.impedance
+    }
+
+    pub fn get_load_resistance(&self) -> f64 {
+        if self.prototype.get_order() % 2 == 1 {
+            self.impedance * self.prototype.get_load()
+        } else {
+            self.impedance / self.prototype.get_load()
+        }
+    }
+
+    /// Gets the branches from the input to the output, alternately shunt and series.
+    fn branches(&self) -> Vec<Branch> {
+        let r = self.impedance;
+        self.prototype
+            .get_elements()
+            .iter()
+            .enumerate()
+            .map(|(k, &g)| {
+                let shunt = k % 2 == 0;
+                match (self.band, shunt) {
+                    (FilterBand::LowPass { cutoff }, true) => {
+                        Branch::Capacitor(g / (2.0 * PI * cutoff * r))
+                    }
+                    (FilterBand::LowPass { cutoff }, false) => {
+                        Branch::Inductor(g * r / (2.0 * PI * cutoff))
+                    }
+                    (FilterBand::HighPass { cutoff }, true) => {
+                        Branch::Inductor(r / (2.0 * PI * cutoff * g))
+                    }
+                    (FilterBand::HighPass { cutoff }, false) => {
+                        Branch::Capacitor(1.0 / (2.0 * PI * cutoff * g * r))
+                    }
+                    (FilterBand::BandPass { center, bandwidth }, shunt) => {
+                        let (w0, b) = (2.0 * PI * center, 2.0 * PI * bandwidth);
+                        if shunt {
+                            Branch::ParallelLc(b * r / (w0 * w0 * g), g / (b * r))
+                        } else {
+                            Branch::SeriesLc(g * r / b, b / (w0 * w0 * g * r))
+                        }
+                    }
+                    (FilterBand::BandStop { center, bandwidth }, shunt) => {
+                        let (w0, b) = (2.0 * PI * center, 2.0 * PI * bandwidth);
+                        if shunt {
+                            Branch::SeriesLc(r / (g * b), g * b / (r * w0 * w0))
+                        } else {
+                            Branch::ParallelLc(g * r * b / (w0 * w0), 1.0 / (g * r * b))
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl Subcircuit for LcLadderFilter {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.ground_node));
+        // The nodes of the shunt branches, one more than the series branches, the last one
+        // being the output.
+        let series = self.prototype.get_order() / 2;
+        let mut rail: Vec<usize> = (0..series).map(|_| netlist.add_node()).collect();
+        rail.push(self.output_node);
+
+        netlist.add_component(Resistor::new(self.input_node, rail[0], self.impedance));
+        for (k, branch) in self.branches().into_iter().enumerate() {
+            let (from, to) = if k % 2 == 0 {
+                (rail[k / 2], self.ground_node)
+            } else {
+                (rail[k / 2], rail[k / 2 + 1])
+            };
+            match branch {
+                Branch::Capacitor(c) => {
+                    netlist.add_component(Capacitor::new(from, to, c, 0.0));
+                }
+                Branch::Inductor(l) => {
+                    netlist.add_component(Inductor::new(from, to, l, 0.0));
+                }
+                Branch::SeriesLc(l, c) => {
+                    let middle = netlist.add_node();
+                    netlist
+                        .add_component(Inductor::new(from, middle, l, 0.0))
+                        .add_component(Capacitor::new(middle, to, c, 0.0));
+                }
+                Branch::ParallelLc(l, c) => {
+                    netlist
+                        .add_component(Inductor::new(from, to, l, 0.0))
+                        .add_component(Capacitor::new(from, to, c, 0.0));
+                }
+            }
+        }
+        netlist.add_component(Resistor::new(
+            self.output_node,
+            self.ground_node,
+            self.get_load_resistance(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::{ACSolver, BESolver, components::VoltageSource};
+
+    #[test]
+    fn test_filter_prototypes() {
+        let butterworth = FilterPrototype::new(FilterResponse::Butterworth, 3);
+        for (g, expected) in butterworth.get_elements().iter().zip([1.0, 2.0, 1.0]) {
+            assert_relative_eq!(*g, expected, epsilon = 1e-12);
+        }
+        assert_eq!(butterworth.get_load(), 1.0);
+
+        // The tabulated Chebyshev prototypes of 0.5dB and 0.1dB ripple.
+        let chebyshev = FilterPrototype::new(FilterResponse::Chebyshev { ripple: 0.5 }, 3);
+        for (g, expected) in chebyshev
+            .get_elements()
+            .iter()
+            .zip([1.5963, 1.0967, 1.5963])
+        {
+            assert_relative_eq!(*g, expected, epsilon = 1e-4);
+        }
+        assert_eq!(chebyshev.get_load(), 1.0);
+        let chebyshev = FilterPrototype::new(FilterResponse::Chebyshev { ripple: 0.1 }, 2);
+        for (g, expected) in chebyshev.get_elements().iter().zip([0.8431, 0.6220]) {
+            assert_relative_eq!(*g, expected, epsilon = 1e-4);
+        }
+        assert_relative_eq!(chebyshev.get_load(), 1.3554, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_lc_ladder_filter() {
+        let response = |response, order, frequency| {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+                .add_subcircuit(&LcLadderFilter::new(
+                    1,
+                    2,
+                    0,
+                    FilterPrototype::new(response, order),
+                    1e3,
+                    50.0,
+                ));
+            ACSolver::new(&netlist)
+                .solve(frequency)
+                .unwrap()
+                .get_node_voltage(2)
+                .norm()
+        };
+
+        // A fifth order Butterworth filter is flat at half the source voltage, 3dB down at the
+        // cutoff and rolls off by 100dB per decade.
+        assert_relative_eq!(
+            response(FilterResponse::Butterworth, 5, 10.0),
+            0.5,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            response(FilterResponse::Butterworth, 5, 1e3),
+            0.5 / 2f64.sqrt(),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            response(FilterResponse::Butterworth, 5, 1e4),
+            0.5 / (1.0 + 1e10f64).sqrt(),
+            max_relative = 1e-6
+        );
+
+        // A fourth order Chebyshev filter is down by its ripple from the most power its
+        // terminations can transfer at DC and at the cutoff.
+        let ripple = 1.0;
+        let chebyshev = FilterResponse::Chebyshev { ripple };
+        let load = LcLadderFilter::new(1, 2, 0, FilterPrototype::new(chebyshev, 4), 1e3, 50.0)
+            .get_load_resistance();
+        let down = 0.5 * (load / 50.0).sqrt() * 10f64.powf(-ripple / 20.0);
+        assert_relative_eq!(response(chebyshev, 4, 1e-3), down, epsilon = 1e-6);
+        assert_relative_eq!(response(chebyshev, 4, 1e3), down, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_rc_ladder() {
+        // A single section is a plain RC lowpass.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_subcircuit(&RcLadder::new(1, 2, 0, 1, 1e3, 1e-6));
+        let corner = 1.0 / (2.0 * PI * 1e-3);
+        let output = ACSolver::new(&netlist)
+            .solve(corner)
+            .unwrap()
+            .get_node_voltage(2);
+        assert_relative_eq!(output.norm(), 0.5f64.sqrt(), epsilon = 1e-9);
+
+        // A long ladder charges in the order of its Elmore delay.
+        let ladder = RcLadder::new(1, 2, 0, 50, 10.0, 1e-9);
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_subcircuit(&ladder);
+        assert_eq!(netlist.get_num_nodes(), 51);
+
+        let mut solver = BESolver::new(&mut netlist);
+        while solver.get_node_voltage(2) < 0.5 {
+            solver.solve(ladder.get_elmore_delay() / 100.0).unwrap();
+        }
+        let delay = solver.get_time() / ladder.get_elmore_delay();
+        assert!(delay > 0.5 && delay < 1.0);
+    }
+
+    #[test]
+    fn test_transmission_line_chain() {
+        // A matched 50Ω line delays a step by its delay, arriving at half the source voltage.
+        let line = TransmissionLineChain::from_impedance(2, 3, 0, 25, 50.0, 10e-9);
+        assert_relative_eq!(line.get_characteristic_impedance(), 50.0, epsilon = 1e-9);
+        assert_relative_eq!(line.get_delay(), 10e-9, epsilon = 1e-18);
+
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 50.0))
+            .add_component(Resistor::new(3, 0, 50.0))
+            .add_subcircuit(&line);
+
+        let mut solver = BESolver::new(&mut netlist);
+        while solver.get_node_voltage(3) < 0.25 {
+            solver.solve(20e-12).unwrap();
+        }
+        assert_relative_eq!(solver.get_time(), 10e-9, max_relative = 5e-2);
+        for _ in 0..500 {
+            solver.solve(20e-12).unwrap();
+        }
+        assert_relative_eq!(solver.get_node_voltage(3), 0.5, epsilon = 1e-2);
+    }
+}
@@ -0,0 +1,24 @@
+mod emc;
+mod ic;
+mod isolation;
+mod ladder;
+mod multiplexer;
+mod rectifier;
+mod switched_capacitor;
+pub use emc::{EsdGenerator, EsdModel, Lisn};
+pub use ic::{LinearRegulator, Lm317, Lm393, Timer555, Tl431};
+pub use isolation::{GateDriveTransformer, Optocoupler};
+pub use ladder::{
+    FilterBand, FilterPrototype, FilterResponse, LcLadderFilter, RcLadder, TransmissionLineChain,
+};
+pub use multiplexer::AnalogMultiplexer;
+pub use rectifier::{FullBridgeRectifier, HalfBridgeRectifier};
+pub use switched_capacitor::ClockedSwitchPair;
+
+use crate::components::Netlist;
+
+/// A prebuilt group of components that can be instantiated into a netlist in one call.
+pub trait Subcircuit {
+    /// Adds the components making up the subcircuit to the netlist.
+    fn add_to(&self, netlist: &mut Netlist);
+}
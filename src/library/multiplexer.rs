@@ -0,0 +1,164 @@
+use crate::{
+    components::{Netlist, TransmissionGate},
+    library::Subcircuit,
+};
+
+/// A CMOS analog multiplexer connecting one of its inputs to the output, selected by the
+/// binary address on the address nodes (least significant bit first), e.g. the input of a
+/// multichannel data-acquisition front end.
+///
+/// It is built as a tree of [`TransmissionGate`]s, each level choosing between pairs of
+/// inputs by one address bit, so the selected channel passes through one gate per address bit
+/// and every other channel is isolated by at least one off gate. The on-resistance of a channel
+/// is split evenly over its gates. Inputs beyond the reach of the address are left unconnected.
+///
+/// Uses one internal node per gate below the last level (see [`Netlist::add_node`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalogMultiplexer {
+    input_nodes: Vec<usize>,
+    output_node: usize,
+    address_nodes: Vec<usize>,
+    ground_node: usize,
+    on_resistance: f64,
+    off_resistance: f64,
+    supply: (f64, f64, f64),
+    off_capacitance: f64,
+}
+
+impl AnalogMultiplexer {
+    /// Creates a multiplexer with channels of the given on-resistance over the middle of the
+    /// supply, and gates as built by [`TransmissionGate::new`].
+    pub fn new(
+        input_nodes: &[usize],
+        output_node: usize,
+        address_nodes: &[usize],
+        ground_node: usize,
+        on_resistance: f64,
+    ) -> Self {
+        let gate = TransmissionGate::new(0, 0, 0, 0, on_resistance);
+        Self {
+            input_nodes: input_nodes.to_vec(),
+            output_node,
+            address_nodes: address_nodes.to_vec(),
+            ground_node,
+            on_resistance,
+            off_resistance: gate.get_off_resistance(),
+            supply: gate.get_supply(),
+            off_capacitance: gate.get_off_capacitance(),
+        }
+    }
+
+    /// Sets the supply of the gates, see [`TransmissionGate::with_supply`]. The address is
+    /// high while above the middle of the supply.
+    pub fn with_supply(
+        mut self,
+        positive_rail: f64,
+        negative_rail: f64,
+        device_threshold: f64,
+    ) -> Self {
+        self.supply = (positive_rail, negative_rail, device_threshold);
+        self
+    }
+
+    pub fn with_off_resistance(mut self, off_resistance: f64) -> Self {
+        self.off_resistance = off_resistance;
+        self
+    }
+
+    /// Sets the feedthrough capacitance of each gate, see
+    /// [`TransmissionGate::with_off_capacitance`].
+    pub fn with_off_capacitance(mut self, capacitance: f64) -> Self {
+        self.off_capacitance = capacitance;
+        self
+    }
+
+    fn gate(&self, input_node: usize, output_node: usize, address_node: usize) -> TransmissionGate {
+        let (positive_rail, negative_rail, device_threshold) = self.supply;
+        TransmissionGate::new(
+            input_node,
+            output_node,
+            address_node,
+            self.ground_node,
+            self.on_resistance / self.address_nodes.len() as f64,
+        )
+        .with_supply(positive_rail, negative_rail, device_threshold)
+        .with_off_resistance(self.off_resistance)
+        .with_off_capacitance(self.off_capacitance)
+    }
+}
+
+impl Subcircuit for AnalogMultiplexer {
+    fn add_to(&self, netlist: &mut Netlist) {
+        let pins = self.input_nodes.iter().chain(&self.address_nodes);
+        netlist.reserve_nodes(
+            pins.copied()
+                .chain([self.output_node, self.ground_node])
+                .max()
+                .unwrap_or(0),
+        );
+
+        let reach = 1usize
+            .checked_shl(self.address_nodes.len() as u32)
+            .unwrap_or(usize::MAX);
+        let mut level: Vec<usize> = self.input_nodes.iter().copied().take(reach).collect();
+        for (bit, &address_node) in self.address_nodes.iter().enumerate() {
+            let last = bit + 1 == self.address_nodes.len();
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let output_node = if last {
+                        self.output_node
+                    } else {
+                        netlist.add_node()
+                    };
+                    // The first of each pair is selected by a low address bit, the second by a
+                    // high one.
+                    netlist.add_component(
+                        self.gate(pair[0], output_node, address_node)
+                            .with_active_low(),
+                    );
+                    if let Some(&input_node) = pair.get(1) {
+                        netlist.add_component(self.gate(input_node, output_node, address_node));
+                    }
+                    output_node
+                })
+                .collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::BESolver;
+    use crate::components::{Resistor, VoltageSource};
+
+    #[test]
+    fn test_analog_multiplexer() {
+        // Four channels at 1V to 4V into a 1MΩ load, with channel 2 selected. The 100Ω
+        // on-resistance and the 1GΩ off gates each shift the output by about 0.1%.
+        let mut netlist = Netlist::new();
+        for channel in 0..4 {
+            netlist.add_component(VoltageSource::new(channel + 1, 0, channel as f64 + 1.0));
+        }
+        netlist
+            .add_component(VoltageSource::new(6, 0, 0.0))
+            .add_component(VoltageSource::new(7, 0, 5.0))
+            .add_component(Resistor::new(5, 0, 1e6));
+        AnalogMultiplexer::new(&[1, 2, 3, 4], 5, &[6, 7], 0, 100.0).add_to(&mut netlist);
+        // Two gates for each pair at the first level and one pair at the second.
+        assert_eq!(netlist.get_components().len(), 7 + 6);
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-6).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(5), 3.0, max_relative = 1e-3);
+
+        // Changing the address selects channel 1.
+        solver.get_netlist_mut().get_component_mut(4).set_value(5.0);
+        solver.get_netlist_mut().get_component_mut(5).set_value(0.0);
+        solver.solve(1e-6).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(5), 2.0, max_relative = 1e-3);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{
+    components::{Capacitor, Diode, DiodeModel, Netlist, Resistor},
+    library::Subcircuit,
+};
+
+/// Adds the optional smoothing capacitor and bleed resistor across a rectifier output.
+fn add_output_filter(
+    netlist: &mut Netlist,
+    dc_positive_node: usize,
+    dc_negative_node: usize,
+    smoothing_capacitance: Option<f64>,
+    bleed_resistance: Option<f64>,
+) {
+    if let Some(capacitance) = smoothing_capacitance {
+        netlist.add_component(Capacitor::new(
+            dc_positive_node,
+            dc_negative_node,
+            capacitance,
+            0.0,
+        ));
+    }
+
+    if let Some(resistance) = bleed_resistance {
+        netlist.add_component(Resistor::new(
+            dc_positive_node,
+            dc_negative_node,
+            resistance,
+        ));
+    }
+}
+
+/// A single rectifier leg: one diode conducting from the AC node to the positive DC rail and one
+/// conducting from the negative DC rail to the AC node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HalfBridgeRectifier {
+    ac_node: usize,
+    dc_positive_node: usize,
+    dc_negative_node: usize,
+    diode_model: DiodeModel,
+    smoothing_capacitance: Option<f64>,
+    bleed_resistance: Option<f64>,
+}
+
+impl HalfBridgeRectifier {
+    pub fn new(
+        ac_node: usize,
+        dc_positive_node: usize,
+        dc_negative_node: usize,
+        diode_model: DiodeModel,
+    ) -> Self {
+        Self {
+            ac_node,
+            dc_positive_node,
+            dc_negative_node,
+            diode_model,
+            smoothing_capacitance: None,
+            bleed_resistance: None,
+        }
+    }
+
+    /// Adds a smoothing capacitor (initially discharged) across the DC output.
+    pub fn with_smoothing_capacitor(mut self, capacitance: f64) -> Self {
+        self.smoothing_capacitance = Some(capacitance);
+        self
+    }
+
+    /// Adds a bleed resistor across the DC output.
+    pub fn with_bleed_resistor(mut self, resistance: f64) -> Self {
+        self.bleed_resistance = Some(resistance);
+        self
+    }
+}
+
+impl Subcircuit for HalfBridgeRectifier {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist
+            .add_component(Diode::new(
+                self.ac_node,
+                self.dc_positive_node,
+                self.diode_model,
+            ))
+            .add_component(Diode::new(
+                self.dc_negative_node,
+                self.ac_node,
+                self.diode_model,
+            ));
+
+        add_output_filter(
+            netlist,
+            self.dc_positive_node,
+            self.dc_negative_node,
+            self.smoothing_capacitance,
+            self.bleed_resistance,
+        );
+    }
+}
+
+/// A four diode full-bridge rectifier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FullBridgeRectifier {
+    ac_positive_node: usize,
+    ac_negative_node: usize,
+    dc_positive_node: usize,
+    dc_negative_node: usize,
+    diode_model: DiodeModel,
+    smoothing_capacitance: Option<f64>,
+    bleed_resistance: Option<f64>,
+}
+
+impl FullBridgeRectifier {
+    pub fn new(
+        ac_positive_node: usize,
+        ac_negative_node: usize,
+        dc_positive_node: usize,
+        dc_negative_node: usize,
+        diode_model: DiodeModel,
+    ) -> Self {
+        Self {
+            ac_positive_node,
+            ac_negative_node,
+            dc_positive_node,
+            dc_negative_node,
+            diode_model,
+            smoothing_capacitance: None,
+            bleed_resistance: None,
+        }
+    }
+
+    /// Adds a smoothing capacitor (initially discharged) across the DC output.
+    pub fn with_smoothing_capacitor(mut self, capacitance: f64) -> Self {
+        self.smoothing_capacitance = Some(capacitance);
+        self
+    }
+
+    /// Adds a bleed resistor across the DC output.
+    pub fn with_bleed_resistor(mut self, resistance: f64) -> Self {
+        self.bleed_resistance = Some(resistance);
+        self
+    }
+}
+
+impl Subcircuit for FullBridgeRectifier {
+    fn add_to(&self, netlist: &mut Netlist) {
+        // The bridge is built from two legs, one for each AC terminal.
+        HalfBridgeRectifier::new(
+            self.ac_positive_node,
+            self.dc_positive_node,
+            self.dc_negative_node,
+            self.diode_model,
+        )
+        .add_to(netlist);
+        HalfBridgeRectifier::new(
+            self.ac_negative_node,
+            self.dc_positive_node,
+            self.dc_negative_node,
+            self.diode_model,
+        )
+        .add_to(netlist);
+
+        add_output_filter(
+            netlist,
+            self.dc_positive_node,
+            self.dc_negative_node,
+            self.smoothing_capacitance,
+            self.bleed_resistance,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BESolver, components::VoltageSource};
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_full_bridge_ideal() {
+        for v_in in [10.0, -10.0] {
+            let mut netlist = Netlist::new();
+            netlist
+                .add_component(VoltageSource::new(1, 2, v_in))
+                .add_subcircuit(&FullBridgeRectifier::new(1, 2, 3, 0, DiodeModel::Ideal))
+                .add_component(Resistor::new(3, 0, 10.0));
+
+            let mut solver = BESolver::new(&mut netlist);
+            solver.solve(0.001).unwrap();
+
+            let r: Resistor = netlist
+                .get_components()
+                .last()
+                .unwrap()
+                .clone()
+                .try_into()
+                .unwrap();
+
+            assert_relative_eq!(r.get_voltage(), 10.0, max_relative = 0.001);
+        }
+    }
+
+    #[test]
+    fn test_full_bridge_components() {
+        let mut netlist = Netlist::new();
+        netlist.add_subcircuit(
+            &FullBridgeRectifier::new(1, 2, 3, 0, DiodeModel::Ideal)
+                .with_smoothing_capacitor(1e-3)
+                .with_bleed_resistor(1e3),
+        );
+
+        assert_eq!(netlist.get_components().len(), 6);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    components::{Capacitor, Netlist, Switch},
+    library::Subcircuit,
+};
+
+/// A pair of switches around a common node, clocked by the two phases of a
+/// [`NonOverlappingClock`](crate::components::NonOverlappingClock): the first switch connects
+/// the first node to the common node during phase 1, the second connects the common node to
+/// the second node during phase 2.
+///
+/// With a capacitor from the common node to ground (see [`ClockedSwitchPair::with_capacitor`])
+/// this is the switched-capacitor resistor at the heart of SC filters, moving a charge C*dV
+/// from the first node to the second every clock period, like a resistance of 1/(f*C).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockedSwitchPair {
+    first_node: usize,
+    common_node: usize,
+    second_node: usize,
+    phase1_node: usize,
+    phase2_node: usize,
+    ground_node: usize,
+    on_resistance: f64,
+    off_resistance: f64,
+    threshold: f64,
+    capacitance: Option<f64>,
+}
+
+impl ClockedSwitchPair {
+    /// Creates a switch pair with 100Ω closed and 1GΩ open switches, closing while their phase
+    /// is above 0.5V (half the default clock output).
+    pub fn new(
+        first_node: usize,
+        common_node: usize,
+        second_node: usize,
+        phase1_node: usize,
+        phase2_node: usize,
+        ground_node: usize,
+    ) -> Self {
+        Self {
+            first_node,
+            common_node,
+            second_node,
+            phase1_node,
+            phase2_node,
+            ground_node,
+            on_resistance: 100.0,
+            off_resistance: 1e9,
+            threshold: 0.5,
+            capacitance: None,
+        }
+    }
+
+    pub fn with_switch_resistances(mut self, on_resistance: f64, off_resistance: f64) -> Self {
+        self.on_resistance = on_resistance;
+        self.off_resistance = off_resistance;
+        self
+    }
+
+    /// Sets the phase voltage above which the switches close.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Adds a capacitor (initially discharged) between the common node and the ground node.
+    pub fn with_capacitor(mut self, capacitance: f64) -> Self {
+        self.capacitance = Some(capacitance);
+        self
+    }
+
+    /// Switches modeled as gated switches, closing during the given phase.
+    fn switch(&self, positive_node: usize, negative_node: usize, phase_node: usize) -> Switch {
+        Switch::new(
+            positive_node,
+            negative_node,
+            self.on_resistance,
+            self.off_resistance,
+            0.0,
+        )
+        .with_gate(phase_node, self.ground_node, self.threshold)
+    }
+}
+
+impl Subcircuit for ClockedSwitchPair {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist
+            .add_component(self.switch(self.first_node, self.common_node, self.phase1_node))
+            .add_component(self.switch(self.common_node, self.second_node, self.phase2_node));
+
+        if let Some(capacitance) = self.capacitance {
+            netlist.add_component(Capacitor::new(
+                self.common_node,
+                self.ground_node,
+                capacitance,
+                0.0,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::BESolver;
+    use crate::components::{NonOverlappingClock, VoltageSource};
+
+    #[test]
+    fn test_switched_capacitor_integrator() {
+        // A 1pF switched-capacitor resistor clocked at 1MHz charging 100pF from 1V: each period
+        // shares the sampled charge, 1/101 of the remaining step, over to the output.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(NonOverlappingClock::new(2, 3, 0, 1e6))
+            .add_component(Capacitor::new(5, 0, 100e-12, 0.0));
+        ClockedSwitchPair::new(1, 4, 5, 2, 3, 0)
+            .with_capacitor(1e-12)
+            .add_to(&mut netlist);
+
+        // Timesteps much longer than the clock period still land on every clock edge.
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..10 {
+            solver.solve(10e-6).unwrap();
+        }
+
+        let expected = 1.0 - (100.0f64 / 101.0).powi(100);
+        assert_relative_eq!(solver.get_node_voltage(5), expected, max_relative = 0.01);
+        assert_relative_eq!(solver.get_time(), 100e-6, max_relative = 1e-9);
+    }
+}
@@ -0,0 +1,391 @@
+use std::f64::consts::PI;
+
+use crate::components::{CurrentTransformer, Inductor, InductorSaturation, Netlist};
+use crate::library::Subcircuit;
+
+/// Permeability of free space in H/m.
+const VACUUM_PERMEABILITY: f64 = 4e-7 * PI;
+
+/// Magnetizing inductance of the ideal transformer of a [`SaturableTransformer`] relative to
+/// its saturable magnetizing inductance, large enough to draw no current of its own.
+const IDEAL_MAGNETIZING_FACTOR: f64 = 1e6;
+
+/// A magnetic core, as described by its datasheet: the inductance factor AL (inductance per
+/// turn squared, in H), the effective area (m^2) and magnetic path length (m), and the flux
+/// density at which it saturates (T).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Core {
+    inductance_factor: f64,
+    effective_area: f64,
+    effective_length: f64,
+    saturation_flux_density: f64,
+}
+
+impl Core {
+    pub fn new(
+        inductance_factor: f64,
+        effective_area: f64,
+        effective_length: f64,
+        saturation_flux_density: f64,
+    ) -> Self {
+        Self {
+            inductance_factor,
+            effective_area,
+            effective_length,
+            saturation_flux_density,
+        }
+    }
+
+    pub fn get_inductance_factor(&self) -> f64 {
+        self.inductance_factor
+    }
+
+    pub fn get_effective_area(&self) -> f64 {
+        self.effective_area
+    }
+
+    pub fn get_effective_length(&self) -> f64 {
+        self.effective_length
+    }
+
+    pub fn get_saturation_flux_density(&self) -> f64 {
+        self.saturation_flux_density
+    }
+
+    /// Gets the flux through the core at saturation, in webers.
+    pub fn get_saturation_flux(&self) -> f64 {
+        self.saturation_flux_density * self.effective_area
+    }
+
+    /// Gets the effective relative permeability of the core (including any gap) implied by its
+    /// inductance factor.
+    pub fn get_effective_permeability(&self) -> f64 {
+        self.inductance_factor * self.effective_length / (VACUUM_PERMEABILITY * self.effective_area)
+    }
+}
+
+/// Named cores, so a design can pick a core by its part name, with a few common ungapped ferrite
+/// and iron powder cores built in and custom cores (e.g. gapped ones) added on top.
+///
+/// The built in values are nominal datasheet values: the saturation flux density of the ferrites
+/// is taken at 100°C, and AL has a tolerance of about 25%.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreDatabase {
+    cores: Vec<(String, Core)>,
+}
+
+impl Default for CoreDatabase {
+    fn default() -> Self {
+        Self {
+            cores: vec![
+                (
+                    "E25/13/7-N87".to_string(),
+                    Core::new(1900e-9, 52.5e-6, 57.5e-3, 0.39),
+                ),
+                (
+                    "ETD29-N87".to_string(),
+                    Core::new(2350e-9, 76e-6, 72e-3, 0.39),
+                ),
+                (
+                    "ETD34-N87".to_string(),
+                    Core::new(2700e-9, 97.1e-6, 78.6e-3, 0.39),
+                ),
+                (
+                    "T106-26".to_string(),
+                    Core::new(93e-9, 65.9e-6, 64.9e-3, 1.38),
+                ),
+            ],
+        }
+    }
+}
+
+impl CoreDatabase {
+    /// Creates the database of built in cores.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a core, replacing any existing core with the same name.
+    pub fn define(&mut self, name: impl Into<String>, core: Core) -> &mut Self {
+        let name = name.into();
+        match self.cores.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = core,
+            None => self.cores.push((name, core)),
+        }
+        self
+    }
+
+    /// Gets the core with the given name.
+    pub fn get(&self, name: &str) -> Option<Core> {
+        self.cores
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, core)| *core)
+    }
+
+    /// Gets the names of every core in the order they were defined.
+    pub fn names(&self) -> Vec<&str> {
+        self.cores.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+/// A winding of the given number of turns on a core, from which the parameters of the inductor
+/// it makes are computed.
+///
+/// The inductance is AL*N^2. The saturation current is the current at which the flux density
+/// would reach the saturation flux density of the core, N*B_sat*A_e/L, which the
+/// [`InductorSaturation`] model takes as the current at which the incremental inductance has
+/// fallen halfway towards the saturated inductance, that of the winding with the permeability of
+/// the core gone, mu_0*N^2*A_e/l_e.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindingDesign {
+    core: Core,
+    turns: f64,
+}
+
+impl WindingDesign {
+    pub fn new(core: Core, turns: f64) -> Self {
+        Self { core, turns }
+    }
+
+    pub fn get_core(&self) -> Core {
+        self.core
+    }
+
+    pub fn get_turns(&self) -> f64 {
+        self.turns
+    }
+
+    pub fn get_inductance(&self) -> f64 {
+        self.core.inductance_factor * self.turns * self.turns
+    }
+
+    pub fn get_saturation_current(&self) -> f64 {
+        self.turns * self.core.get_saturation_flux() / self.get_inductance()
+    }
+
+    pub fn get_saturated_inductance(&self) -> f64 {
+        VACUUM_PERMEABILITY * self.turns * self.turns * self.core.effective_area
+            / self.core.effective_length
+    }
+
+    /// Gets the volt-seconds the winding can take from zero current before the core saturates.
+    pub fn get_max_volt_seconds(&self) -> f64 {
+        self.turns * self.core.get_saturation_flux()
+    }
+
+    pub fn get_saturation(&self) -> InductorSaturation {
+        InductorSaturation::new(
+            self.get_saturation_current(),
+            self.get_saturated_inductance(),
+        )
+    }
+
+    /// Instantiates the saturable inductor the winding makes between two nodes.
+    pub fn to_inductor(&self, positive_node: usize, negative_node: usize) -> Inductor {
+        Inductor::new(positive_node, negative_node, self.get_inductance(), 0.0)
+            .with_saturation(self.get_saturation())
+    }
+}
+
+/// A transformer of a primary and a secondary winding on a core. The magnetizing inductance and
+/// its saturation are those of the primary winding (see [`WindingDesign`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformerDesign {
+    core: Core,
+    primary_turns: f64,
+    secondary_turns: f64,
+}
+
+impl TransformerDesign {
+    pub fn new(core: Core, primary_turns: f64, secondary_turns: f64) -> Self {
+        Self {
+            core,
+            primary_turns,
+            secondary_turns,
+        }
+    }
+
+    pub fn get_primary(&self) -> WindingDesign {
+        WindingDesign::new(self.core, self.primary_turns)
+    }
+
+    pub fn get_secondary(&self) -> WindingDesign {
+        WindingDesign::new(self.core, self.secondary_turns)
+    }
+
+    /// Gets the secondary turns per primary turn.
+    pub fn get_turns_ratio(&self) -> f64 {
+        self.secondary_turns / self.primary_turns
+    }
+
+    pub fn get_magnetizing_inductance(&self) -> f64 {
+        self.get_primary().get_inductance()
+    }
+
+    /// Gets the magnetizing current, in the primary, at which the core saturates.
+    pub fn get_saturation_current(&self) -> f64 {
+        self.get_primary().get_saturation_current()
+    }
+
+    /// Gets the volt-seconds the primary can take from zero magnetizing current before the core
+    /// saturates.
+    pub fn get_max_volt_seconds(&self) -> f64 {
+        self.get_primary().get_max_volt_seconds()
+    }
+
+    /// Instantiates the transformer between the primary and secondary nodes.
+    pub fn to_transformer(
+        &self,
+        primary_positive_node: usize,
+        primary_negative_node: usize,
+        secondary_positive_node: usize,
+        secondary_negative_node: usize,
+    ) -> SaturableTransformer {
+        SaturableTransformer {
+            primary_positive_node,
+            primary_negative_node,
+            secondary_positive_node,
+            secondary_negative_node,
+            design: *self,
+        }
+    }
+}
+
+/// A transformer whose core saturates: the saturable magnetizing inductance of its design
+/// across the primary, coupled to the secondary by an ideal transformer (a
+/// [`CurrentTransformer`] whose own magnetizing inductance is too large to matter).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaturableTransformer {
+    primary_positive_node: usize,
+    primary_negative_node: usize,
+    secondary_positive_node: usize,
+    secondary_negative_node: usize,
+    design: TransformerDesign,
+}
+
+impl SaturableTransformer {
+    pub fn get_design(&self) -> TransformerDesign {
+        self.design
+    }
+}
+
+impl Subcircuit for SaturableTransformer {
+    fn add_to(&self, netlist: &mut Netlist) {
+        let n = self.design.get_turns_ratio();
+        netlist
+            .add_component(
+                self.design
+                    .get_primary()
+                    .to_inductor(self.primary_positive_node, self.primary_negative_node),
+            )
+            .add_component(CurrentTransformer::new(
+                self.primary_positive_node,
+                self.primary_negative_node,
+                self.secondary_positive_node,
+                self.secondary_negative_node,
+                n,
+                IDEAL_MAGNETIZING_FACTOR * n * n * self.design.get_magnetizing_inductance(),
+            ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::BESolver;
+    use crate::components::{Resistor, VoltageSource};
+
+    #[test]
+    fn test_winding_design() {
+        let cores = CoreDatabase::new();
+        let core = cores.get("ETD34-N87").unwrap();
+        assert!(cores.get("missing").is_none());
+        assert!(core.get_effective_permeability() > 1000.0);
+
+        // 20 turns: 1.08mH, saturating at 20 * 0.39T * 97.1mm^2 / 1.08mH = 0.701A.
+        let winding = WindingDesign::new(core, 20.0);
+        assert_relative_eq!(winding.get_inductance(), 1.08e-3, max_relative = 1e-12);
+        assert_relative_eq!(
+            winding.get_saturation_current(),
+            0.7013,
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(
+            winding.get_saturated_inductance(),
+            winding.get_inductance() / core.get_effective_permeability(),
+            max_relative = 1e-12
+        );
+
+        // The inductor it makes has the design's inductance, halved at the saturation current
+        // and falling to the saturated inductance well past it.
+        let inductor = winding.to_inductor(1, 0);
+        let current = winding.get_saturation_current();
+        assert_eq!(
+            inductor.incremental_inductance(0.0),
+            winding.get_inductance()
+        );
+        assert_relative_eq!(
+            inductor.incremental_inductance(current),
+            0.5 * (winding.get_inductance() + winding.get_saturated_inductance()),
+            max_relative = 1e-12
+        );
+        assert!(inductor.incremental_inductance(100.0 * current) < 0.01 * winding.get_inductance());
+    }
+
+    #[test]
+    fn test_saturable_transformer() {
+        // A 10:20 turn transformer on an ungapped E25 into a 100Ω load, its primary driven by
+        // 10V for long enough to saturate the core.
+        let design =
+            TransformerDesign::new(CoreDatabase::new().get("E25/13/7-N87").unwrap(), 10.0, 20.0);
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 0.1))
+            .add_component(Resistor::new(3, 0, 100.0))
+            .add_subcircuit(&design.to_transformer(2, 0, 3, 0));
+
+        // The core saturates after about the volt-seconds of the primary over 10V.
+        let saturation_time = design.get_max_volt_seconds() / 10.0;
+        let dt = saturation_time / 100.0;
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(dt).unwrap();
+        // The secondary is twice the primary.
+        assert_relative_eq!(
+            solver.get_node_voltage(3),
+            2.0 * solver.get_node_voltage(2),
+            max_relative = 1e-6
+        );
+
+        let magnetizing_current = |solver: &BESolver| {
+            let inductor: Inductor = solver.get_netlist().get_components()[3]
+                .clone()
+                .try_into()
+                .unwrap();
+            inductor.get_current()
+        };
+        for _ in 0..49 {
+            solver.solve(dt).unwrap();
+        }
+        // Half way to saturation, the flux linkage L*I_sat*atan(i/I_sat) has reached half the
+        // volt-seconds.
+        let (inductance, saturation_current) = (
+            design.get_magnetizing_inductance(),
+            design.get_saturation_current(),
+        );
+        assert_relative_eq!(
+            magnetizing_current(&solver),
+            saturation_current
+                * (0.5 * saturation_time * 10.0 / (inductance * saturation_current)).tan(),
+            max_relative = 0.02
+        );
+        for _ in 0..150 {
+            solver.solve(dt).unwrap();
+        }
+        // Past saturation it has run away.
+        assert!(magnetizing_current(&solver) > 10.0 * saturation_current);
+    }
+}
@@ -0,0 +1,502 @@
+use crate::matlab::MatError;
+
+// Data types of the elements of a MAT-file.
+const MI_INT8: u32 = 1;
+const MI_UINT8: u32 = 2;
+const MI_INT16: u32 = 3;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_SINGLE: u32 = 7;
+const MI_DOUBLE: u32 = 9;
+const MI_INT64: u32 = 12;
+const MI_UINT64: u32 = 13;
+const MI_MATRIX: u32 = 14;
+const MI_COMPRESSED: u32 = 15;
+const MI_UTF8: u32 = 16;
+
+// Classes of the arrays of a MAT-file.
+const MX_CELL_CLASS: u8 = 1;
+const MX_STRUCT_CLASS: u8 = 2;
+const MX_CHAR_CLASS: u8 = 4;
+const MX_DOUBLE_CLASS: u8 = 6;
+const MX_UINT64_CLASS: u8 = 15;
+
+/// Length reserved for each field name of a struct, including its terminating zero.
+const FIELD_NAME_LENGTH: usize = 32;
+
+/// A MATLAB array, as read from or written to a MAT-file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatValue {
+    /// A real matrix of any numeric or logical class, stored column by column. Only the real
+    /// part of complex arrays is read.
+    Numeric {
+        rows: usize,
+        columns: usize,
+        data: Vec<f64>,
+    },
+    /// A character array, read as one string (column by column for several rows).
+    Char(String),
+    /// A cell array, stored column by column.
+    Cell {
+        rows: usize,
+        columns: usize,
+        cells: Vec<MatValue>,
+    },
+    /// A scalar struct, with its fields in order.
+    Struct(Vec<(String, MatValue)>),
+}
+
+impl MatValue {
+    pub fn scalar(value: f64) -> Self {
+        Self::Numeric {
+            rows: 1,
+            columns: 1,
+            data: vec![value],
+        }
+    }
+
+    /// Creates a column vector.
+    pub fn column(values: &[f64]) -> Self {
+        Self::Numeric {
+            rows: values.len(),
+            columns: 1,
+            data: values.to_vec(),
+        }
+    }
+
+    /// Creates a matrix from its columns, failing on the first one that isn't as long as the
+    /// first.
+    pub fn from_columns(columns: &[Vec<f64>]) -> Result<Self, MatError> {
+        let rows = columns.first().map_or(0, |c| c.len());
+        if let Some(column) = columns.iter().position(|c| c.len() != rows) {
+            return Err(MatError::UnequalColumns(column));
+        }
+        Ok(Self::Numeric {
+            rows,
+            columns: columns.len(),
+            data: columns.concat(),
+        })
+    }
+
+    pub fn string(text: &str) -> Self {
+        Self::Char(text.to_string())
+    }
+
+    /// Creates a row cell array of strings.
+    pub fn strings<S: AsRef<str>>(texts: &[S]) -> Self {
+        Self::Cell {
+            rows: 1,
+            columns: texts.len(),
+            cells: texts.iter().map(|t| Self::string(t.as_ref())).collect(),
+        }
+    }
+
+    /// Gets a field of a struct.
+    pub fn field(&self, name: &str) -> Option<&MatValue> {
+        match self {
+            Self::Struct(fields) => fields.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Gets the elements of a numeric array, column by column.
+    pub fn as_numeric(&self) -> Option<&Vec<f64>> {
+        match self {
+            Self::Numeric { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Char(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Gets the strings of a cell array of strings, None if any cell isn't one.
+    pub fn as_strings(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Cell { cells, .. } => cells.iter().map(|c| c.as_str()).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Writes variables to a level 5 MAT-file, uncompressed, which both MATLAB and Octave load.
+///
+/// Fails on the first struct with a field name that is too long or repeated, which MATLAB
+/// would otherwise read as a different or clashing field.
+pub fn write_mat(variables: &[(&str, MatValue)]) -> Result<Vec<u8>, MatError> {
+    let mut bytes = Vec::new();
+
+    let mut text = format!(
+        "MATLAB 5.0 MAT-file, written by rice {}",
+        env!("CARGO_PKG_VERSION")
+    )
+    .into_bytes();
+    text.resize(116, b' ');
+    bytes.extend(text);
+    // No subsystem data, version 0x0100 and the little endian indicator.
+    bytes.extend([0; 8]);
+    bytes.extend(0x0100u16.to_le_bytes());
+    bytes.extend(b"IM");
+
+    for (name, value) in variables {
+        write_matrix(&mut bytes, name, value)?;
+    }
+    Ok(bytes)
+}
+
+/// Writes an element padded to a multiple of 8 bytes.
+fn write_element(bytes: &mut Vec<u8>, data_type: u32, data: &[u8]) {
+    bytes.extend(data_type.to_le_bytes());
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes.extend(data);
+    bytes.resize(bytes.len().next_multiple_of(8), 0);
+}
+
+fn write_matrix(bytes: &mut Vec<u8>, name: &str, value: &MatValue) -> Result<(), MatError> {
+    let mut body = Vec::new();
+    let (class, rows, columns) = match value {
+        MatValue::Numeric { rows, columns, .. } => (MX_DOUBLE_CLASS, *rows, *columns),
+        MatValue::Char(text) => (MX_CHAR_CLASS, 1, text.encode_utf16().count()),
+        MatValue::Cell { rows, columns, .. } => (MX_CELL_CLASS, *rows, *columns),
+        MatValue::Struct(_) => (MX_STRUCT_CLASS, 1, 1),
+    };
+
+    let flags: Vec<u8> = [class as u32, 0]
+        .iter()
+        .flat_map(|f| f.to_le_bytes())
+        .collect();
+    write_element(&mut body, MI_UINT32, &flags);
+    let dimensions: Vec<u8> = [rows as i32, columns as i32]
+        .iter()
+        .flat_map(|d| d.to_le_bytes())
+        .collect();
+    write_element(&mut body, MI_INT32, &dimensions);
+    write_element(&mut body, MI_INT8, name.as_bytes());
+
+    match value {
+        MatValue::Numeric { data, .. } => {
+            let data: Vec<u8> = data.iter().flat_map(|v| v.to_le_bytes()).collect();
+            write_element(&mut body, MI_DOUBLE, &data);
+        }
+        MatValue::Char(text) => {
+            let data: Vec<u8> = text.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+            write_element(&mut body, MI_UINT16, &data);
+        }
+        MatValue::Cell { cells, .. } => {
+            for cell in cells {
+                write_matrix(&mut body, "", cell)?;
+            }
+        }
+        MatValue::Struct(fields) => {
+            write_element(
+                &mut body,
+                MI_INT32,
+                &(FIELD_NAME_LENGTH as i32).to_le_bytes(),
+            );
+            let mut names = Vec::new();
+            for (k, (name, _)) in fields.iter().enumerate() {
+                // Names are null terminated within their fixed length.
+                if name.len() >= FIELD_NAME_LENGTH || fields[..k].iter().any(|(n, _)| n == name) {
+                    return Err(MatError::InvalidFieldName(name.clone()));
+                }
+                let mut name = name.as_bytes().to_vec();
+                name.resize(FIELD_NAME_LENGTH, 0);
+                names.extend(name);
+            }
+            write_element(&mut body, MI_INT8, &names);
+            for (_, field) in fields {
+                write_matrix(&mut body, "", field)?;
+            }
+        }
+    }
+
+    write_element(bytes, MI_MATRIX, &body);
+    Ok(())
+}
+
+/// Reads the variables of a level 5 MAT-file.
+///
+/// Only uncompressed files can be read, as saved by MATLAB with `save -v6` or by Octave with
+/// `save -mat` or `save -v6`. Struct arrays other than scalar structs, sparse matrices and
+/// objects aren't supported.
+pub fn read_mat(bytes: &[u8]) -> Result<Vec<(String, MatValue)>, MatError> {
+    if bytes.len() < 128 || !bytes.starts_with(b"MATLAB 5.0 MAT-file") {
+        return Err(MatError::InvalidHeader);
+    }
+    if &bytes[126..128] != b"IM" {
+        // Only little endian files are read.
+        return Err(MatError::InvalidHeader);
+    }
+
+    let mut reader = Reader {
+        bytes,
+        position: 128,
+    };
+    let mut variables = Vec::new();
+    while reader.position < bytes.len() {
+        let (data_type, data) = reader.element()?;
+        match data_type {
+            MI_MATRIX => variables.push(read_matrix(data)?),
+            MI_COMPRESSED => return Err(MatError::Compressed),
+            _ => return Err(MatError::UnsupportedType(data_type)),
+        }
+    }
+    Ok(variables)
+}
+
+/// Reads elements one after the other.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the next element, returning its data type and data.
+    fn element(&mut self) -> Result<(u32, &'a [u8]), MatError> {
+        let tag = self
+            .bytes
+            .get(self.position..self.position + 8)
+            .ok_or(MatError::Truncated)?;
+        let first = u32::from_le_bytes(tag[0..4].try_into().unwrap());
+
+        // Elements of up to 4 bytes may be packed with their tag in 8 bytes.
+        if first >> 16 != 0 {
+            let size = (first >> 16) as usize;
+            if size > 4 {
+                return Err(MatError::Truncated);
+            }
+            self.position += 8;
+            return Ok((first & 0xffff, &tag[4..4 + size]));
+        }
+
+        let size = u32::from_le_bytes(tag[4..8].try_into().unwrap()) as usize;
+        let start = self.position + 8;
+        let data = self
+            .bytes
+            .get(start..start + size)
+            .ok_or(MatError::Truncated)?;
+        self.position = (start + size).next_multiple_of(8);
+        Ok((first, data))
+    }
+
+    fn numbers(&mut self) -> Result<Vec<f64>, MatError> {
+        let (data_type, data) = self.element()?;
+        numbers(data_type, data)
+    }
+}
+
+/// Converts the data of a numeric element to numbers.
+fn numbers(data_type: u32, data: &[u8]) -> Result<Vec<f64>, MatError> {
+    fn convert<const N: usize>(data: &[u8], f: impl Fn([u8; N]) -> f64) -> Vec<f64> {
+        data.chunks_exact(N)
+            .map(|c| f(c.try_into().unwrap()))
+            .collect()
+    }
+
+    Ok(match data_type {
+        MI_INT8 => convert(data, |b: [u8; 1]| i8::from_le_bytes(b) as f64),
+        MI_UINT8 | MI_UTF8 => convert(data, |b: [u8; 1]| b[0] as f64),
+        MI_INT16 => convert(data, |b| i16::from_le_bytes(b) as f64),
+        MI_UINT16 => convert(data, |b| u16::from_le_bytes(b) as f64),
+        MI_INT32 => convert(data, |b| i32::from_le_bytes(b) as f64),
+        MI_UINT32 => convert(data, |b| u32::from_le_bytes(b) as f64),
+        MI_SINGLE => convert(data, |b| f32::from_le_bytes(b) as f64),
+        MI_DOUBLE => convert(data, f64::from_le_bytes),
+        MI_INT64 => convert(data, |b| i64::from_le_bytes(b) as f64),
+        MI_UINT64 => convert(data, |b| u64::from_le_bytes(b) as f64),
+        _ => return Err(MatError::UnsupportedType(data_type)),
+    })
+}
+
+/// Reads the body of a matrix element, returning its name and value.
+fn read_matrix(body: &[u8]) -> Result<(String, MatValue), MatError> {
+    let mut reader = Reader {
+        bytes: body,
+        position: 0,
+    };
+
+    let flags = reader.numbers()?;
+    let flags = *flags.first().ok_or(MatError::Truncated)? as u32;
+    let class = (flags & 0xff) as u8;
+
+    let dimensions = reader.numbers()?;
+    let rows = dimensions.first().copied().unwrap_or(0.0) as usize;
+    let columns = dimensions.iter().skip(1).product::<f64>() as usize;
+
+    let (_, name) = reader.element()?;
+    let name = String::from_utf8_lossy(name).into_owned();
+
+    let value = match class {
+        MX_DOUBLE_CLASS..=MX_UINT64_CLASS => {
+            // The imaginary part of complex arrays follows and is left out.
+            let data = reader.numbers()?;
+            if data.len() != rows * columns {
+                return Err(MatError::Truncated);
+            }
+            MatValue::Numeric {
+                rows,
+                columns,
+                data,
+            }
+        }
+        MX_CHAR_CLASS => {
+            let (data_type, data) = reader.element()?;
+            let text = if data_type == MI_UTF8 {
+                String::from_utf8_lossy(data).into_owned()
+            } else {
+                let units: Vec<u16> = numbers(data_type, data)?
+                    .into_iter()
+                    .map(|c| c as u16)
+                    .collect();
+                String::from_utf16_lossy(&units)
+            };
+            MatValue::Char(text)
+        }
+        MX_CELL_CLASS => {
+            let mut cells = Vec::with_capacity(rows * columns);
+            for _ in 0..rows * columns {
+                let (data_type, data) = reader.element()?;
+                if data_type != MI_MATRIX {
+                    return Err(MatError::UnsupportedType(data_type));
+                }
+                cells.push(read_matrix(data)?.1);
+            }
+            MatValue::Cell {
+                rows,
+                columns,
+                cells,
+            }
+        }
+        MX_STRUCT_CLASS => {
+            if rows * columns != 1 {
+                return Err(MatError::UnsupportedClass(class));
+            }
+
+            let length = *reader.numbers()?.first().ok_or(MatError::Truncated)? as usize;
+            let (_, names) = reader.element()?;
+            let mut fields = Vec::new();
+            for name in names.chunks_exact(length.max(1)) {
+                let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                let (data_type, data) = reader.element()?;
+                if data_type != MI_MATRIX {
+                    return Err(MatError::UnsupportedType(data_type));
+                }
+                fields.push((
+                    String::from_utf8_lossy(&name[..end]).into_owned(),
+                    read_matrix(data)?.1,
+                ));
+            }
+            MatValue::Struct(fields)
+        }
+        _ => return Err(MatError::UnsupportedClass(class)),
+    };
+
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = MatValue::Struct(vec![
+            (
+                "matrix".to_string(),
+                MatValue::from_columns(&[vec![1.0, 2.0], vec![3.0, -4.5]]).unwrap(),
+            ),
+            ("text".to_string(), MatValue::string("Ω rice")),
+            ("cells".to_string(), MatValue::strings(&["a", ""])),
+            ("empty".to_string(), MatValue::column(&[])),
+        ]);
+        let bytes = write_mat(&[("s", value.clone()), ("x", MatValue::scalar(2.0))]).unwrap();
+        assert_eq!(bytes.len() % 8, 0);
+        assert_eq!(&bytes[126..128], b"IM");
+
+        let variables = read_mat(&bytes).unwrap();
+        assert_eq!(variables[0], ("s".to_string(), value));
+        assert_eq!(variables[1], ("x".to_string(), MatValue::scalar(2.0)));
+        assert_eq!(
+            read_mat(&bytes[..bytes.len() - 8]),
+            Err(MatError::Truncated)
+        );
+        assert_eq!(read_mat(b"not a MAT-file"), Err(MatError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert_eq!(
+            MatValue::from_columns(&[vec![1.0, 2.0], vec![3.0], vec![4.0, 5.0]]),
+            Err(MatError::UnequalColumns(1))
+        );
+
+        // Names that would be cut short to the same 31 bytes.
+        let long = |suffix: &str| format!("{}{suffix}", "a".repeat(31));
+        let value = MatValue::Struct(vec![
+            (long("b"), MatValue::scalar(1.0)),
+            (long("c"), MatValue::scalar(2.0)),
+        ]);
+        assert_eq!(
+            write_mat(&[("s", value)]),
+            Err(MatError::InvalidFieldName(long("b")))
+        );
+        let value = MatValue::Struct(vec![
+            ("x".to_string(), MatValue::scalar(1.0)),
+            ("x".to_string(), MatValue::scalar(2.0)),
+        ]);
+        assert_eq!(
+            write_mat(&[("s", value)]),
+            Err(MatError::InvalidFieldName("x".to_string()))
+        );
+        let value = MatValue::Struct(vec![("a".repeat(31), MatValue::scalar(1.0))]);
+        assert!(write_mat(&[("s", value)]).is_ok());
+    }
+
+    #[test]
+    fn test_packed_elements() {
+        // As MATLAB saves a = uint8([1 2 3]): small elements packed in their tags, and the
+        // values stored as bytes.
+        let mut bytes = write_mat(&[]).unwrap();
+        let body: Vec<u32> = vec![
+            // Array flags, uint8 class.
+            MI_UINT32,
+            8,
+            9,
+            0,
+            // Dimensions.
+            MI_INT32,
+            8,
+            1,
+            3,
+            // Name "a" packed in its tag.
+            (1 << 16) | MI_INT8,
+            b'a' as u32,
+            // Data packed in its tag.
+            (3 << 16) | MI_UINT8,
+            0x0003_0201,
+        ];
+        bytes.extend(MI_MATRIX.to_le_bytes());
+        bytes.extend((4 * body.len() as u32).to_le_bytes());
+        bytes.extend(body.iter().flat_map(|w| w.to_le_bytes()));
+
+        let variables = read_mat(&bytes).unwrap();
+        assert_eq!(variables[0].0, "a");
+        assert_eq!(
+            variables[0].1,
+            MatValue::Numeric {
+                rows: 1,
+                columns: 3,
+                data: vec![1.0, 2.0, 3.0]
+            }
+        );
+
+        let mut compressed = write_mat(&[]).unwrap();
+        compressed.extend([15, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(read_mat(&compressed), Err(MatError::Compressed));
+    }
+}
@@ -0,0 +1,39 @@
+//! Interchange with MATLAB and GNU Octave through level 5 MAT-files: transient results are
+//! written with their probe names (see
+//! [`TransientResult::to_mat`](crate::results::TransientResult::to_mat)) and circuits are
+//! read from and written to a struct format (see [`import_netlist`]).
+
+mod mat_file;
+pub use mat_file::{MatValue, read_mat, write_mat};
+
+mod netlist;
+pub use netlist::{export_netlist, import_netlist};
+
+/// Why a MAT-file or a circuit in it couldn't be read or written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatError {
+    /// The file doesn't start with a little endian level 5 MAT-file header.
+    InvalidHeader,
+    /// The file ends in the middle of an element.
+    Truncated,
+    /// The file is compressed, as MATLAB saves by default; save it with `-v6` instead.
+    Compressed,
+    UnsupportedType(u32),
+    /// An array class other than numeric, char, cell and scalar struct arrays.
+    UnsupportedClass(u8),
+    MissingVariable(String),
+    /// A field of the circuit struct is missing or isn't of the right class and size.
+    InvalidField(&'static str),
+    /// A component type other than the ones of the struct format, by its position.
+    UnknownType {
+        component: usize,
+        kind: String,
+    },
+    /// A component that can't be described in the struct format, by its index.
+    UnsupportedComponent(usize),
+    /// A column of a matrix isn't as long as the first one, by its index.
+    UnequalColumns(usize),
+    /// A struct field name longer than the 31 bytes a MAT-file holds, or the name of two
+    /// fields of the same struct.
+    InvalidFieldName(String),
+}
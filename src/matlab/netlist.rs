@@ -0,0 +1,257 @@
+use crate::components::{
+    Capacitor, Component, CurrentSource, Inductor, Metadata, Netlist, Resistor, VoltageSource,
+};
+use crate::matlab::{MatError, MatValue, read_mat};
+
+/// Reads the circuit stored in the given struct variable of a MAT-file.
+///
+/// The struct describes N two terminal components with the fields:
+///
+/// - `type`: cell array of the component letters, `'R'`, `'C'`, `'L'`, `'V'` or `'I'`.
+/// - `nodes`: N by 2 matrix of the positive and negative node of each component, node 0 being
+///   the reference.
+/// - `value`: vector of the resistance, capacitance, inductance, voltage or current of each
+///   component.
+/// - `initial` (optional): vector of the initial voltage of each capacitor and initial current
+///   of each inductor, ignored for other components.
+/// - `name` (optional): cell array of the component names.
+/// - `metadata` (optional): cell array of the component metadata, each encoded as text by
+///   [`Metadata::encode`].
+///
+/// For example, in MATLAB or Octave:
+///
+/// ```text
+/// circuit.type = {'V', 'R', 'C'};
+/// circuit.nodes = [1 0; 1 2; 2 0];
+/// circuit.value = [5 1e3 1e-6];
+/// save('-v6', 'rc.mat', 'circuit');
+/// ```
+pub fn import_netlist(bytes: &[u8], variable: &str) -> Result<Netlist, MatError> {
+    let circuit = read_mat(bytes)?
+        .into_iter()
+        .find(|(name, _)| name == variable)
+        .map(|(_, value)| value)
+        .ok_or_else(|| MatError::MissingVariable(variable.to_string()))?;
+
+    let kinds = circuit
+        .field("type")
+        .and_then(|t| t.as_strings())
+        .ok_or(MatError::InvalidField("type"))?;
+    let count = kinds.len();
+    let nodes = circuit
+        .field("nodes")
+        .and_then(|n| n.as_numeric())
+        .filter(|n| n.len() == 2 * count)
+        .ok_or(MatError::InvalidField("nodes"))?;
+    let values = circuit
+        .field("value")
+        .and_then(|v| v.as_numeric())
+        .filter(|v| v.len() == count)
+        .ok_or(MatError::InvalidField("value"))?;
+    let initial = match circuit.field("initial") {
+        Some(initial) => Some(
+            initial
+                .as_numeric()
+                .filter(|i| i.len() == count)
+                .ok_or(MatError::InvalidField("initial"))?,
+        ),
+        None => None,
+    };
+    let names = match circuit.field("name") {
+        Some(names) => Some(
+            names
+                .as_strings()
+                .filter(|n| n.len() == count)
+                .ok_or(MatError::InvalidField("name"))?,
+        ),
+        None => None,
+    };
+    let metadata = match circuit.field("metadata") {
+        Some(metadata) => Some(
+            metadata
+                .as_strings()
+                .filter(|m| m.len() == count)
+                .ok_or(MatError::InvalidField("metadata"))?,
+        ),
+        None => None,
+    };
+
+    if nodes.iter().any(|&n| n < 0.0 || n.fract() != 0.0) {
+        return Err(MatError::InvalidField("nodes"));
+    }
+
+    let mut netlist = Netlist::new();
+    for (k, kind) in kinds.iter().enumerate() {
+        // The nodes matrix is stored column by column.
+        let (positive, negative) = (nodes[k] as usize, nodes[count + k] as usize);
+        let (value, initial) = (values[k], initial.map_or(0.0, |i| i[k]));
+
+        let component: Component = match kind.to_ascii_uppercase().as_str() {
+            "R" => Resistor::new(positive, negative, value).into(),
+            "C" => Capacitor::new(positive, negative, value, initial).into(),
+            "L" => Inductor::new(positive, negative, value, initial).into(),
+            "V" => VoltageSource::new(positive, negative, value).into(),
+            "I" => CurrentSource::new(positive, negative, value).into(),
+            _ => {
+                return Err(MatError::UnknownType {
+                    component: k,
+                    kind: kind.to_string(),
+                });
+            }
+        };
+        netlist.add_component(component);
+    }
+
+    if let Some(names) = names {
+        for (k, name) in names.into_iter().enumerate() {
+            netlist.set_component_name(k, name);
+        }
+    }
+    if let Some(metadata) = metadata {
+        for (k, text) in metadata.into_iter().enumerate() {
+            let metadata = Metadata::decode(text);
+            if !metadata.is_empty() {
+                netlist.set_metadata(k, metadata);
+            }
+        }
+    }
+    Ok(netlist)
+}
+
+/// Describes the circuit as a struct of the format read by [`import_netlist`], with the present
+/// voltage of capacitors and current of inductors as their initial values, to be written with
+/// [`write_mat`](crate::matlab::write_mat).
+///
+/// Fails on the first component that isn't a resistor, linear capacitor, inductor, ideal
+/// voltage source or current source.
+pub fn export_netlist(netlist: &Netlist) -> Result<MatValue, MatError> {
+    let components = netlist.get_components();
+
+    let mut kinds = Vec::new();
+    let (mut positive, mut negative) = (Vec::new(), Vec::new());
+    let (mut values, mut initial) = (Vec::new(), Vec::new());
+    for (k, component) in components.iter().enumerate() {
+        let state = match component {
+            Component::Resistor(_) | Component::CurrentSource(_) => 0.0,
+            Component::VoltageSource(s) if s.get_series_resistance() == 0.0 => 0.0,
+            Component::Capacitor(c) => c.get_voltage(),
+            Component::Inductor(c) => c.get_current(),
+            _ => return Err(MatError::UnsupportedComponent(k)),
+        };
+        let value = component
+            .get_value()
+            .ok_or(MatError::UnsupportedComponent(k))?;
+
+        kinds.push(component.get_prefix());
+        positive.push(component.get_positive_node() as f64);
+        negative.push(component.get_negative_node() as f64);
+        values.push(value);
+        initial.push(state);
+    }
+
+    let names: Vec<String> = (0..components.len())
+        .map(|k| netlist.get_component_name(k))
+        .collect();
+    let metadata: Vec<String> = (0..components.len())
+        .map(|k| {
+            netlist
+                .get_metadata(k)
+                .map_or_else(String::new, Metadata::encode)
+        })
+        .collect();
+
+    Ok(MatValue::Struct(vec![
+        ("type".to_string(), MatValue::strings(&kinds)),
+        (
+            "nodes".to_string(),
+            MatValue::from_columns(&[positive, negative])?,
+        ),
+        ("value".to_string(), MatValue::column(&values)),
+        ("initial".to_string(), MatValue::column(&initial)),
+        ("name".to_string(), MatValue::strings(&names)),
+        ("metadata".to_string(), MatValue::strings(&metadata)),
+    ]))
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::BESolver;
+    use crate::components::{Diode, DiodeModel};
+    use crate::matlab::write_mat;
+
+    #[test]
+    fn test_netlist_round_trip() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 1.0))
+            .add_component(Inductor::new(2, 3, 1e-3, 0.0))
+            .add_component(CurrentSource::new(3, 0, 1e-3))
+            .set_component_name(1, "Rload")
+            .set_metadata_field(1, Metadata::PART_NUMBER, "RC0603FR-071KL")
+            .add_tag(1, "load");
+
+        let bytes = write_mat(&[("circuit", export_netlist(&netlist).unwrap())]).unwrap();
+        let mut imported = import_netlist(&bytes, "circuit").unwrap();
+        assert_eq!(imported.get_components(), netlist.get_components());
+        assert_eq!(imported.find_component("Rload"), Some(1));
+        assert_eq!(imported.get_metadata(1), netlist.get_metadata(1));
+        assert!(imported.get_metadata(0).is_none());
+
+        let mut solver = BESolver::new(&mut imported);
+        solver.solve(1e-3).unwrap();
+        assert!(solver.get_node_voltage(2) > 1.0);
+
+        assert_eq!(
+            import_netlist(&bytes, "other").unwrap_err(),
+            MatError::MissingVariable("other".to_string())
+        );
+        netlist.add_component(Diode::new(3, 0, DiodeModel::Ideal));
+        assert_eq!(
+            export_netlist(&netlist),
+            Err(MatError::UnsupportedComponent(5))
+        );
+    }
+
+    #[test]
+    fn test_import_struct() {
+        let circuit = MatValue::Struct(vec![
+            ("type".to_string(), MatValue::strings(&["V", "r", "R"])),
+            (
+                "nodes".to_string(),
+                MatValue::from_columns(&[vec![1.0, 1.0, 2.0], vec![0.0, 2.0, 0.0]]).unwrap(),
+            ),
+            ("value".to_string(), MatValue::column(&[10.0, 1.0, 3.0])),
+        ]);
+        let bytes = write_mat(&[("circuit", circuit.clone())]).unwrap();
+        let mut netlist = import_netlist(&bytes, "circuit").unwrap();
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        assert_relative_eq!(solver.get_node_voltage(2), 7.5, max_relative = 1e-9);
+
+        let MatValue::Struct(mut fields) = circuit else {
+            unreachable!()
+        };
+        fields[0].1 = MatValue::strings(&["V", "Q", "R"]);
+        let bytes = write_mat(&[("circuit", MatValue::Struct(fields.clone()))]).unwrap();
+        assert_eq!(
+            import_netlist(&bytes, "circuit").unwrap_err(),
+            MatError::UnknownType {
+                component: 1,
+                kind: "Q".to_string()
+            }
+        );
+
+        fields[0].1 = MatValue::strings(&["V", "R", "R"]);
+        fields[2].1 = MatValue::column(&[10.0, 1.0]);
+        let bytes = write_mat(&[("circuit", MatValue::Struct(fields))]).unwrap();
+        assert_eq!(
+            import_netlist(&bytes, "circuit").unwrap_err(),
+            MatError::InvalidField("value")
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::components::{Component, Inductor, Netlist, Resistor};
+
+/// The series resistance and inductance of the wiring from a net to each terminal on it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Parasitics {
+    pub resistance: f64,
+    pub inductance: f64,
+}
+
+impl Parasitics {
+    pub fn new(resistance: f64, inductance: f64) -> Self {
+        Self {
+            resistance,
+            inductance,
+        }
+    }
+
+    /// Returns whether the parasitics add nothing to the circuit.
+    pub fn is_zero(&self) -> bool {
+        self.resistance == 0.0 && self.inductance == 0.0
+    }
+}
+
+/// An overlay adding wiring parasitics to the nets of a circuit, to study how sensitive it is to
+/// layout without editing the netlist.
+///
+/// Every terminal connected to an annotated net is moved to a node of its own and joined back to
+/// the net through the parasitic resistance and inductance, so the net becomes a star of wires
+/// meeting at the original node. Terminals carrying no current, like the control inputs of a
+/// controlled source, see no drop across their wire.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParasiticAnnotation {
+    all_nets: Option<Parasitics>,
+    nets: HashMap<usize, Parasitics>,
+    enabled: bool,
+}
+
+impl ParasiticAnnotation {
+    /// Creates an enabled annotation of no nets.
+    pub fn new() -> Self {
+        Self {
+            all_nets: None,
+            nets: HashMap::new(),
+            enabled: true,
+        }
+    }
+
+    /// Annotates every net but the reference node, which can still be annotated with
+    /// [`ParasiticAnnotation::with_net`]. Nets given parasitics of their own keep them.
+    ///
+    /// Thermal networks, like the hot and cold sides of a thermoelectric module, aren't wiring,
+    /// so circuits with them are best annotated net by net.
+    pub fn with_all_nets(mut self, parasitics: Parasitics) -> Self {
+        self.all_nets = Some(parasitics);
+        self
+    }
+
+    /// Annotates the given net.
+    pub fn with_net(mut self, node: usize, parasitics: Parasitics) -> Self {
+        self.nets.insert(node, parasitics);
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Turns the annotation on or off. While off, [`ParasiticAnnotation::apply`] returns the
+    /// netlist unchanged.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Gets the parasitics added to the wiring of the given net in the given netlist, if any.
+    pub fn get_parasitics(&self, netlist: &Netlist, node: usize) -> Option<Parasitics> {
+        self.nets
+            .get(&node)
+            .copied()
+            .or(self
+                .all_nets
+                .filter(|_| node != netlist.get_reference_node()))
+            .filter(|p| !p.is_zero())
+    }
+
+    /// Returns a copy of the netlist with the parasitics added.
+    ///
+    /// The components of the netlist keep their indices, names and ratings, and the parasitic
+    /// components are added after them. The new nodes are allocated with [`Netlist::add_node`].
+    pub fn apply(&self, netlist: &Netlist) -> Netlist {
+        let mut annotated = netlist.clone();
+        if !self.enabled {
+            return annotated;
+        }
+
+        let mut components = annotated.get_components().clone();
+        let mut wires = Vec::new();
+
+        for component in components.iter_mut() {
+            // Every terminal of a component on the same net shares a wire.
+            let mut terminals: HashMap<usize, usize> = HashMap::new();
+            component.map_nodes(|node| {
+                let Some(parasitics) = self.get_parasitics(netlist, node) else {
+                    return node;
+                };
+                *terminals.entry(node).or_insert_with(|| {
+                    let terminal = annotated.add_node();
+                    wires.push((node, terminal, parasitics));
+                    terminal
+                })
+            });
+        }
+
+        *annotated.get_components_mut() = components;
+
+        for (net, terminal, parasitics) in wires {
+            let wire: Vec<Component> = match (parasitics.resistance, parasitics.inductance) {
+                (r, 0.0) => vec![Resistor::new(net, terminal, r).into()],
+                (0.0, l) => vec![Inductor::new(net, terminal, l, 0.0).into()],
+                (r, l) => {
+                    let middle = annotated.add_node();
+                    vec![
+                        Resistor::new(net, middle, r).into(),
+                        Inductor::new(middle, terminal, l, 0.0).into(),
+                    ]
+                }
+            };
+            annotated.add_components(wire.into_iter());
+        }
+
+        annotated
+    }
+}
+
+impl Default for ParasiticAnnotation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use super::*;
+    use crate::{ACSolver, BESolver, components::VoltageSource};
+
+    fn divider() -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0).with_ac(1.0, 0.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0));
+        netlist
+    }
+
+    fn divider_voltage(mut netlist: Netlist) -> f64 {
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(1e-3).unwrap();
+        let r: Resistor = solver.get_netlist().get_components()[2]
+            .clone()
+            .try_into()
+            .unwrap();
+        r.get_voltage()
+    }
+
+    #[test]
+    fn test_net_resistance() {
+        let netlist = divider();
+
+        // The source and the top resistor each get 10 ohms of wire on net 1.
+        let annotation = ParasiticAnnotation::new().with_net(1, Parasitics::new(10.0, 0.0));
+        let annotated = annotation.apply(&netlist);
+        assert_eq!(annotated.get_components().len(), 5);
+        assert_eq!(netlist.get_components().len(), 3);
+        assert_relative_eq!(
+            divider_voltage(annotated),
+            10.0 * 1000.0 / 2020.0,
+            max_relative = 1e-6
+        );
+
+        // Switching the annotation off leaves the circuit as it was.
+        let annotated = annotation.with_enabled(false).apply(&netlist);
+        assert_eq!(annotated.get_components(), netlist.get_components());
+        assert_relative_eq!(divider_voltage(annotated), 5.0, max_relative = 1e-6);
+
+        // Every net but the reference, with net 2 overridden to nothing, only wires net 1.
+        let annotation = ParasiticAnnotation::new()
+            .with_all_nets(Parasitics::new(10.0, 0.0))
+            .with_net(2, Parasitics::default());
+        assert_eq!(annotation.get_parasitics(&netlist, 0), None);
+        assert_relative_eq!(
+            divider_voltage(annotation.apply(&netlist)),
+            10.0 * 1000.0 / 2020.0,
+            max_relative = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_net_inductance() {
+        let netlist = divider();
+        let annotated = ParasiticAnnotation::new()
+            .with_net(1, Parasitics::new(10.0, 1e-3))
+            .apply(&netlist);
+
+        // Each of the two wires is a resistor and an inductor through a node of their own.
+        assert_eq!(annotated.get_components().len(), 7);
+        assert_eq!(annotated.get_num_nodes(), 6);
+
+        let solution = ACSolver::new(&annotated).solve(10e3).unwrap();
+        let z = Complex::new(2020.0, 2.0 * 2.0 * PI * 10e3 * 1e-3);
+        assert_relative_eq!(
+            solution.get_node_voltage(2).norm(),
+            (1000.0 / z).norm(),
+            max_relative = 1e-6
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::components::{Metadata, Netlist};
+use crate::reports::operating_point::{json_number, json_string};
+use crate::reports::{ComponentStress, StressReport};
+
+/// A line of the bill of materials: every component sharing a part number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BomLine {
+    pub part_number: String,
+    pub footprint: Option<String>,
+    /// Names of the components using the part, in netlist order.
+    pub designators: Vec<String>,
+    pub unit_cost: Option<f64>,
+    /// Total average power dissipated by the components using the part, None without a
+    /// simulation.
+    pub average_power: Option<f64>,
+    /// Smallest margin of the components using the part against their ratings, as the fraction
+    /// of the rating left unused at the peak (negative when overstressed). None without a
+    /// simulation or ratings.
+    pub stress_margin: Option<f64>,
+}
+
+impl BomLine {
+    pub fn get_count(&self) -> usize {
+        self.designators.len()
+    }
+
+    /// Gets the cost of every component of the line, None if the part has no cost.
+    pub fn get_total_cost(&self) -> Option<f64> {
+        Some(self.unit_cost? * self.get_count() as f64)
+    }
+}
+
+/// A bill of materials built from the metadata of a netlist (see [`Metadata`]), optionally with
+/// the power and stress each part saw in a simulation.
+///
+/// Components are grouped by their [`Metadata::PART_NUMBER`]. Components without one (sources,
+/// probes and other idealized parts) aren't part of the build and are left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillOfMaterials {
+    lines: Vec<BomLine>,
+}
+
+impl BillOfMaterials {
+    /// Builds the bill of materials of a netlist without simulation results.
+    pub fn new(netlist: &Netlist) -> Self {
+        Self::build(netlist, None)
+    }
+
+    /// Builds the bill of materials of a netlist along with the average power and stress margin
+    /// of every part from the stress report of a transient run.
+    pub fn from_stress(netlist: &Netlist, stress: &StressReport) -> Self {
+        Self::build(netlist, Some(&stress.get_table()))
+    }
+
+    fn build(netlist: &Netlist, stress: Option<&[ComponentStress]>) -> Self {
+        let mut lines: BTreeMap<String, BomLine> = BTreeMap::new();
+        for index in 0..netlist.get_components().len() {
+            let Some(metadata) = netlist.get_metadata(index) else {
+                continue;
+            };
+            let Some(part_number) = metadata.get_field(Metadata::PART_NUMBER) else {
+                continue;
+            };
+
+            let line = lines
+                .entry(part_number.to_string())
+                .or_insert_with(|| BomLine {
+                    part_number: part_number.to_string(),
+                    footprint: metadata.get_field(Metadata::FOOTPRINT).map(str::to_string),
+                    designators: Vec::new(),
+                    unit_cost: metadata.get_cost(),
+                    average_power: stress.map(|_| 0.0),
+                    stress_margin: None,
+                });
+            line.designators.push(netlist.get_component_name(index));
+
+            let Some(row) = stress.and_then(|stress| stress.get(index)) else {
+                continue;
+            };
+            if let Some(power) = &mut line.average_power {
+                *power += row.average_power;
+            }
+            if let Some(margin) = stress_margin(netlist, index, row) {
+                line.stress_margin = Some(line.stress_margin.map_or(margin, |m| m.min(margin)));
+            }
+        }
+
+        Self {
+            lines: lines.into_values().collect(),
+        }
+    }
+
+    /// Gets every line, ordered by part number.
+    pub fn get_lines(&self) -> &Vec<BomLine> {
+        &self.lines
+    }
+
+    /// Gets the number of components in the build.
+    pub fn get_component_count(&self) -> usize {
+        self.lines.iter().map(BomLine::get_count).sum()
+    }
+
+    /// Gets the cost of the build, leaving out the parts without a cost.
+    pub fn get_total_cost(&self) -> f64 {
+        self.lines.iter().filter_map(BomLine::get_total_cost).sum()
+    }
+
+    /// Gets the total average power dissipated by the parts of the build, zero without a
+    /// simulation.
+    pub fn get_total_power(&self) -> f64 {
+        self.lines
+            .iter()
+            .filter_map(|line| line.average_power)
+            .sum()
+    }
+
+    /// Gets the smallest stress margin across the build, None if no part was simulated with
+    /// ratings.
+    pub fn get_min_stress_margin(&self) -> Option<f64> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.stress_margin)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Exports the bill of materials as CSV, one row per part. Designators are separated by
+    /// spaces and missing values left empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "part_number,footprint,count,designators,unit_cost,total_cost,average_power,stress_margin\n",
+        );
+        let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        for line in &self.lines {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(&line.part_number),
+                csv_field(line.footprint.as_deref().unwrap_or("")),
+                line.get_count(),
+                csv_field(&line.designators.join(" ")),
+                optional(line.unit_cost),
+                optional(line.get_total_cost()),
+                optional(line.average_power),
+                optional(line.stress_margin)
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// Exports the bill of materials as JSON, with the totals of the build:
+    ///
+    /// ```json
+    /// {
+    ///   "parts": [
+    ///     {"part_number": "RC0603FR-071KL", "footprint": "0603", "count": 2,
+    ///      "designators": ["R1", "R2"], "unit_cost": 0.002, "total_cost": 0.004,
+    ///      "average_power": 0.05, "stress_margin": 0.5}
+    ///   ],
+    ///   "component_count": 2, "total_cost": 0.004, "total_power": 0.05
+    /// }
+    /// ```
+    ///
+    /// Missing values are null.
+    pub fn to_json(&self) -> String {
+        let optional_string =
+            |value: &Option<String>| value.as_deref().map_or("null".to_string(), json_string);
+        let optional_number = |value: Option<f64>| value.map_or("null".to_string(), json_number);
+        let parts: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let designators: Vec<String> =
+                    line.designators.iter().map(|d| json_string(d)).collect();
+                format!(
+                    "{{\"part_number\": {}, \"footprint\": {}, \"count\": {}, \"designators\": [{}], \
+                     \"unit_cost\": {}, \"total_cost\": {}, \"average_power\": {}, \
+                     \"stress_margin\": {}}}",
+                    json_string(&line.part_number),
+                    optional_string(&line.footprint),
+                    line.get_count(),
+                    designators.join(", "),
+                    optional_number(line.unit_cost),
+                    optional_number(line.get_total_cost()),
+                    optional_number(line.average_power),
+                    optional_number(line.stress_margin)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"parts\": [\n    {}\n  ],\n  \"component_count\": {}, \"total_cost\": {}, \"total_power\": {}\n}}\n",
+            parts.join(",\n    "),
+            self.get_component_count(),
+            json_number(self.get_total_cost()),
+            json_number(self.get_total_power())
+        )
+    }
+}
+
+/// Gets the margin of a component against its ratings at the peaks of a simulation, the
+/// smallest fraction of a rating left unused.
+fn stress_margin(netlist: &Netlist, index: usize, stress: &ComponentStress) -> Option<f64> {
+    let ratings = netlist.get_ratings(index)?;
+    [
+        (stress.peak_voltage, ratings.max_voltage),
+        (stress.peak_current, ratings.max_current),
+        (stress.peak_power, ratings.max_power),
+    ]
+    .into_iter()
+    .filter_map(|(peak, limit)| Some(1.0 - peak / limit?))
+    .min_by(f64::total_cmp)
+}
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Ratings, Resistor, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_bill_of_materials() {
+        let resistor = |footprint: &str| {
+            Metadata::new()
+                .with_field(Metadata::PART_NUMBER, "RC0603FR-07100RL")
+                .with_field(Metadata::FOOTPRINT, footprint)
+                .with_field(Metadata::COST, "0.002")
+        };
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 100.0))
+            .add_component(Resistor::new(2, 0, 100.0))
+            .add_component(Resistor::new(1, 0, 1e3));
+        netlist
+            .set_metadata(1, resistor("0603"))
+            .set_metadata(2, resistor("0603"))
+            .set_metadata(
+                3,
+                Metadata::new().with_field(Metadata::PART_NUMBER, "ERJ-8ENF1001V, 1k"),
+            )
+            .set_ratings(1, Ratings::new().with_max_power(0.5))
+            .set_ratings(2, Ratings::new().with_max_power(0.1).with_max_voltage(50.0));
+        netlist.set_component_name(3, "Rload");
+
+        let bom = BillOfMaterials::new(&netlist);
+        assert_eq!(bom.get_lines().len(), 2);
+        assert_eq!(bom.get_component_count(), 3);
+        assert_relative_eq!(bom.get_total_cost(), 0.004, max_relative = 1e-12);
+        assert_eq!(bom.get_lines()[1].designators, ["R1", "R2"]);
+        assert_eq!(bom.get_lines()[1].average_power, None);
+        assert_eq!(bom.get_min_stress_margin(), None);
+
+        let mut stress = StressReport::new();
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..10 {
+            solver.solve(1e-3).unwrap();
+            stress.record(solver.get_netlist(), 1e-3);
+        }
+        let bom = BillOfMaterials::from_stress(&netlist, &stress);
+
+        // Each 100 ohm resistor dissipates 0.25W, which is over the rating of R2.
+        let resistors = &bom.get_lines()[1];
+        assert_relative_eq!(resistors.average_power.unwrap(), 0.5, max_relative = 1e-6);
+        assert_relative_eq!(resistors.stress_margin.unwrap(), -1.5, max_relative = 1e-6);
+        assert_eq!(bom.get_lines()[0].stress_margin, None);
+        assert_relative_eq!(bom.get_total_power(), 0.6, max_relative = 1e-6);
+        assert_relative_eq!(
+            bom.get_min_stress_margin().unwrap(),
+            -1.5,
+            max_relative = 1e-6
+        );
+
+        let csv = bom.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains("\n\"ERJ-8ENF1001V, 1k\",,1,Rload,,,"));
+        assert!(csv.contains("\nRC0603FR-07100RL,0603,2,R1 R2,0.002,0.004,"));
+
+        let json = bom.to_json();
+        assert!(json.contains("\"designators\": [\"R1\", \"R2\"]"));
+        assert!(json.contains("\"footprint\": null, \"count\": 1"));
+        assert!(json.contains("\"component_count\": 3, \"total_cost\": 0.004"));
+    }
+}
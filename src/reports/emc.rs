@@ -0,0 +1,284 @@
+use std::f64::consts::{PI, SQRT_2};
+
+use nalgebra::Complex;
+
+use crate::results::Trace;
+
+/// Number of first order stages approximating the gaussian IF filter of the receiver.
+const IF_FILTER_STAGES: usize = 4;
+
+/// Samples per reciprocal resolution bandwidth kept of the detected envelope.
+const ENVELOPE_OVERSAMPLING: f64 = 4.0;
+
+/// A CISPR 16 frequency band, setting the resolution bandwidth and the time constants of the
+/// quasi-peak detector of the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CisprBand {
+    /// 9kHz to 150kHz.
+    A,
+    /// 150kHz to 30MHz, where conducted emissions of mains powered equipment are limited.
+    B,
+}
+
+impl CisprBand {
+    /// Gets the band a frequency falls in, band B above 30MHz.
+    pub fn of(frequency: f64) -> Self {
+        if frequency < 150e3 { Self::A } else { Self::B }
+    }
+
+    /// Gets the 6dB resolution bandwidth.
+    pub fn resolution_bandwidth(&self) -> f64 {
+        match self {
+            Self::A => 200.0,
+            Self::B => 9e3,
+        }
+    }
+
+    /// Gets the charge and discharge time constants of the quasi-peak detector.
+    pub fn quasi_peak_time_constants(&self) -> (f64, f64) {
+        match self {
+            Self::A => (45e-3, 500e-3),
+            Self::B => (1e-3, 160e-3),
+        }
+    }
+}
+
+/// The readings of a receiver tuned to one frequency, in dBµV.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmissionReading {
+    pub frequency: f64,
+    pub peak: f64,
+    pub quasi_peak: f64,
+    pub average: f64,
+}
+
+/// Estimates the readings of an EMI receiver from the voltage across its input, e.g. the
+/// measurement node of a [`Lisn`](crate::library::Lisn), for each of the given frequencies.
+///
+/// At each frequency the trace is mixed down and filtered to the resolution bandwidth of its
+/// band, and the envelope goes through peak, quasi-peak (charge and discharge time constants of
+/// the band) and average detectors, all calibrated to the RMS value of a sine wave. The trace
+/// is taken to repeat, so one or a few periods of steady state operation are enough, but it
+/// must span several times the reciprocal of the resolution bandwidth for the filter to
+/// resolve it (e.g. 1ms in band B). It is resampled to its mean timestep, which must be short
+/// enough to sample the highest frequency.
+pub fn conducted_emissions(trace: &Trace, frequencies: &[f64]) -> Vec<EmissionReading> {
+    let times = trace.get_times();
+    if times.len() < 2 {
+        return Vec::new();
+    }
+
+    let start = times[0];
+    let duration = times[times.len() - 1] - start;
+    let dt = duration / (times.len() - 1) as f64;
+    let samples: Vec<f64> = (0..times.len())
+        .map(|k| trace.value_at(start + dt * k as f64).unwrap())
+        .collect();
+
+    frequencies
+        .iter()
+        .map(|&frequency| {
+            let band = CisprBand::of(frequency);
+            let (envelope, envelope_dt) =
+                envelope(&samples, dt, frequency, band.resolution_bandwidth());
+
+            let peak = envelope.iter().copied().fold(0.0, f64::max);
+            let average = envelope.iter().sum::<f64>() / envelope.len() as f64;
+            let quasi_peak = quasi_peak(&envelope, envelope_dt, band);
+
+            EmissionReading {
+                frequency,
+                peak: dbuv(peak),
+                quasi_peak: dbuv(quasi_peak),
+                average: dbuv(average),
+            }
+        })
+        .collect()
+}
+
+/// Converts a voltage to dBµV.
+pub fn dbuv(voltage: f64) -> f64 {
+    20.0 * (voltage / 1e-6).log10()
+}
+
+/// Computes the envelope of the samples filtered to the given bandwidth around the frequency,
+/// scaled to the RMS value of a sine wave and decimated to a few samples per reciprocal
+/// bandwidth, along with its timestep.
+fn envelope(samples: &[f64], dt: f64, frequency: f64, bandwidth: f64) -> (Vec<f64>, f64) {
+    // Each stage has its cutoff placed so the cascade is 6dB down at half the bandwidth
+    // either side of the frequency.
+    let stages = IF_FILTER_STAGES as f64;
+    let cutoff = bandwidth / 2.0 / (2f64.powf(2.0 / stages) - 1.0).sqrt();
+    let alpha = 1.0 - (-2.0 * PI * cutoff * dt).exp();
+
+    let decimation =
+        ((1.0 / (ENVELOPE_OVERSAMPLING * bandwidth * dt)) as usize).clamp(1, samples.len());
+    let step = Complex::from_polar(1.0, -2.0 * PI * frequency * dt);
+
+    // The trace repeats, so the first pass only brings the filter to its steady state.
+    let mut state = [Complex::new(0.0, 0.0); IF_FILTER_STAGES];
+    let mut envelope = Vec::with_capacity(samples.len() / decimation);
+    for pass in 0..2 {
+        let mut oscillator = Complex::new(1.0, 0.0);
+        for (k, &sample) in samples.iter().enumerate() {
+            let mut x = oscillator * sample;
+            for s in state.iter_mut() {
+                *s += (x - *s) * alpha;
+                x = *s;
+            }
+            oscillator *= step;
+
+            // Mixing halves a sine wave of amplitude A to A/2, which reads A/sqrt(2).
+            if pass == 1 && k % decimation == 0 {
+                envelope.push(SQRT_2 * x.norm());
+            }
+        }
+    }
+    (envelope, dt * decimation as f64)
+}
+
+/// Runs the quasi-peak detector over the repeating envelope until it settles, returning its
+/// average output over a repetition (as the slow meter of the receiver would show).
+fn quasi_peak(envelope: &[f64], dt: f64, band: CisprBand) -> f64 {
+    let (charge, discharge) = band.quasi_peak_time_constants();
+    let (charge, discharge) = (1.0 - (-dt / charge).exp(), 1.0 - (-dt / discharge).exp());
+
+    let mut output = 0.0;
+    let mut previous = f64::NAN;
+    let repetitions = (20.0 * band.quasi_peak_time_constants().1 / (dt * envelope.len() as f64))
+        .ceil()
+        .max(1.0) as usize;
+    for _ in 0..repetitions {
+        let mut sum = 0.0;
+        for &e in envelope {
+            if e > output {
+                output += (e - output) * charge;
+            } else {
+                output -= output * discharge;
+            }
+            sum += output;
+        }
+
+        let average = sum / envelope.len() as f64;
+        if (average - previous).abs() <= 1e-9 * average {
+            return average;
+        }
+        previous = average;
+    }
+    previous
+}
+
+/// A conducted emissions limit line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmissionLimit {
+    /// CISPR 32 class A (industrial) mains terminal limits.
+    Cispr32ClassA,
+    /// CISPR 32 class B (residential) mains terminal limits.
+    Cispr32ClassB,
+}
+
+impl EmissionLimit {
+    /// Gets the quasi-peak and average limits in dBµV at the frequency, None outside 150kHz to
+    /// 30MHz.
+    pub fn limits(&self, frequency: f64) -> Option<(f64, f64)> {
+        if !(150e3..=30e6).contains(&frequency) {
+            return None;
+        }
+
+        Some(match self {
+            Self::Cispr32ClassA if frequency < 500e3 => (79.0, 66.0),
+            Self::Cispr32ClassA => (73.0, 60.0),
+            Self::Cispr32ClassB if frequency < 500e3 => {
+                // Falling linearly with the logarithm of the frequency.
+                let fall = 10.0 * (frequency / 150e3).log10() / (500e3f64 / 150e3).log10();
+                (66.0 - fall, 56.0 - fall)
+            }
+            Self::Cispr32ClassB if frequency <= 5e6 => (56.0, 46.0),
+            Self::Cispr32ClassB => (60.0, 50.0),
+        })
+    }
+
+    /// Gets the smallest margin of the readings below the limits in dB, negative if a
+    /// reading exceeds its limit, comparing quasi-peak and average readings with their limits.
+    /// None if no reading is within the limited range.
+    pub fn margin(&self, readings: &[EmissionReading]) -> Option<f64> {
+        readings
+            .iter()
+            .filter_map(|r| {
+                let (quasi_peak, average) = self.limits(r.frequency)?;
+                Some((quasi_peak - r.quasi_peak).min(average - r.average))
+            })
+            .min_by(f64::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn sampled(duration: f64, dt: f64, f: impl Fn(f64) -> f64) -> Trace {
+        let times: Vec<f64> = (0..=(duration / dt).round() as usize)
+            .map(|k| k as f64 * dt)
+            .collect();
+        let values = times.iter().map(|&t| f(t)).collect();
+        Trace::new(times, values)
+    }
+
+    #[test]
+    fn test_sine_readings() {
+        // 1mV peak at 500kHz reads 57dBµV on every detector, and nothing 100kHz away.
+        let trace = sampled(2e-3, 50e-9, |t| 1e-3 * (2.0 * PI * 500e3 * t).sin());
+        let readings = conducted_emissions(&trace, &[500e3, 600e3]);
+
+        let expected = dbuv(1e-3 / SQRT_2);
+        assert_relative_eq!(readings[0].peak, expected, epsilon = 0.2);
+        assert_relative_eq!(readings[0].quasi_peak, expected, epsilon = 0.2);
+        assert_relative_eq!(readings[0].average, expected, epsilon = 0.2);
+        assert!(readings[1].peak < expected - 40.0);
+    }
+
+    #[test]
+    fn test_burst_readings() {
+        // The same tone on for 10% of every 2ms reads its level on the peak detector, 20dB
+        // lower on the average detector and in between on the quasi-peak detector.
+        let trace = sampled(2e-3, 50e-9, |t| {
+            if t < 0.2e-3 {
+                1e-3 * (2.0 * PI * 500e3 * t).sin()
+            } else {
+                0.0
+            }
+        });
+        let reading = conducted_emissions(&trace, &[500e3])[0];
+
+        let expected = dbuv(1e-3 / SQRT_2);
+        assert_relative_eq!(reading.peak, expected, epsilon = 0.5);
+        assert_relative_eq!(reading.average, expected - 20.0, epsilon = 1.0);
+        assert!(reading.quasi_peak < reading.peak - 1.0);
+        assert!(reading.quasi_peak > reading.average + 5.0);
+    }
+
+    #[test]
+    fn test_limits() {
+        let class_b = EmissionLimit::Cispr32ClassB;
+        assert_eq!(class_b.limits(100e3), None);
+        assert_relative_eq!(class_b.limits(150e3).unwrap().0, 66.0);
+        assert_relative_eq!(class_b.limits(499.999e3).unwrap().0, 56.0, epsilon = 1e-3);
+        assert_eq!(class_b.limits(1e6), Some((56.0, 46.0)));
+        assert_eq!(class_b.limits(10e6), Some((60.0, 50.0)));
+        assert_eq!(
+            EmissionLimit::Cispr32ClassA.limits(200e3),
+            Some((79.0, 66.0))
+        );
+
+        let reading = EmissionReading {
+            frequency: 1e6,
+            peak: 55.0,
+            quasi_peak: 50.0,
+            average: 44.0,
+        };
+        assert_eq!(class_b.margin(&[reading]), Some(2.0));
+        assert_eq!(class_b.margin(&[]), None);
+    }
+}
@@ -0,0 +1,293 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+/// Timing jitter of the threshold crossings of an eye diagram.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterMetrics {
+    /// Mean crossing time within the bit period.
+    pub mean_crossing: f64,
+    /// Standard deviation of the crossing times around the mean.
+    pub rms: f64,
+    /// Spread between the earliest and latest crossing.
+    pub peak_to_peak: f64,
+    /// Number of crossings found.
+    pub crossings: usize,
+}
+
+/// Folds a waveform into an eye diagram, overlaying every bit period on top of each other.
+///
+/// [`EyeDiagram::record`] should be called with every sample of the waveform in time order. The
+/// samples are accumulated into a 2D histogram of time within the bit period against value, and
+/// the crossings of the decision threshold are tracked to measure jitter and the eye opening.
+#[derive(Debug, Clone)]
+pub struct EyeDiagram {
+    bit_period: f64,
+    min_value: f64,
+    max_value: f64,
+    threshold: f64,
+    offset: f64,
+    time_bins: usize,
+    value_bins: usize,
+
+    histogram: Vec<Vec<usize>>,
+    lowest_high: Vec<f64>,
+    highest_low: Vec<f64>,
+    crossings: Vec<f64>,
+    previous: Option<(f64, f64)>,
+}
+
+impl EyeDiagram {
+    /// Creates an eye diagram for the given bit period whose histogram spans values from
+    /// `min_value` to `max_value`. The decision threshold defaults to the middle of the range.
+    pub fn new(bit_period: f64, min_value: f64, max_value: f64) -> Self {
+        Self {
+            bit_period,
+            min_value,
+            max_value,
+            threshold: (min_value + max_value) / 2.0,
+            offset: 0.0,
+            time_bins: 0,
+            value_bins: 0,
+            histogram: Vec::new(),
+            lowest_high: Vec::new(),
+            highest_low: Vec::new(),
+            crossings: Vec::new(),
+            previous: None,
+        }
+        .with_resolution(64, 64)
+    }
+
+    /// Sets the number of histogram bins along the time and value axes.
+    pub fn with_resolution(mut self, time_bins: usize, value_bins: usize) -> Self {
+        self.time_bins = time_bins;
+        self.value_bins = value_bins;
+        self.histogram = vec![vec![0; value_bins]; time_bins];
+        self.lowest_high = vec![f64::INFINITY; time_bins];
+        self.highest_low = vec![f64::NEG_INFINITY; time_bins];
+        self
+    }
+
+    /// Sets the value separating a one from a zero.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Ignores samples before the given time (e.g. while the circuit settles) and starts the
+    /// bit periods from it.
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn get_bit_period(&self) -> f64 {
+        self.bit_period
+    }
+
+    /// Returns the time of a sample within its bit period.
+    fn fold(&self, time: f64) -> f64 {
+        (time - self.offset).rem_euclid(self.bit_period)
+    }
+
+    fn time_bin(&self, phase: f64) -> usize {
+        ((phase / self.bit_period * self.time_bins as f64) as usize).min(self.time_bins - 1)
+    }
+
+    /// Records a sample of the waveform.
+    pub fn record(&mut self, time: f64, value: f64) {
+        if time < self.offset {
+            return;
+        }
+
+        if let Some((previous_time, previous_value)) = self.previous {
+            let below_before = previous_value < self.threshold;
+            let below_now = value < self.threshold;
+            if below_before != below_now {
+                let crossing = previous_time
+                    + (time - previous_time) * (self.threshold - previous_value)
+                        / (value - previous_value);
+                self.crossings.push(self.fold(crossing));
+            }
+        }
+        self.previous = Some((time, value));
+
+        let time_bin = self.time_bin(self.fold(time));
+        if value >= self.threshold {
+            self.lowest_high[time_bin] = self.lowest_high[time_bin].min(value);
+        } else {
+            self.highest_low[time_bin] = self.highest_low[time_bin].max(value);
+        }
+
+        if value >= self.min_value && value < self.max_value {
+            let value_bin = ((value - self.min_value) / (self.max_value - self.min_value)
+                * self.value_bins as f64) as usize;
+            self.histogram[time_bin][value_bin.min(self.value_bins - 1)] += 1;
+        }
+    }
+
+    /// Records a whole waveform of (time, value) samples.
+    pub fn record_waveform(&mut self, samples: &[(f64, f64)]) {
+        for &(time, value) in samples {
+            self.record(time, value);
+        }
+    }
+
+    /// Gets the number of samples falling in each bin, indexed by time bin then value bin.
+    pub fn get_histogram(&self) -> &Vec<Vec<usize>> {
+        &self.histogram
+    }
+
+    /// Gets the time within the bit period of every threshold crossing.
+    pub fn get_crossings(&self) -> &Vec<f64> {
+        &self.crossings
+    }
+
+    /// Measures the jitter of the threshold crossings. Returns None if the waveform never
+    /// crossed the threshold.
+    pub fn jitter(&self) -> Option<JitterMetrics> {
+        if self.crossings.is_empty() {
+            return None;
+        }
+
+        // The crossings wrap around the bit period, so their mean is taken on the circle.
+        let (sin, cos) = self.crossings.iter().fold((0.0, 0.0), |(s, c), &t| {
+            let angle = 2.0 * PI * t / self.bit_period;
+            (s + angle.sin(), c + angle.cos())
+        });
+        let mean_crossing =
+            (f64::atan2(sin, cos) / (2.0 * PI) * self.bit_period).rem_euclid(self.bit_period);
+
+        let deviations: Vec<f64> = self
+            .crossings
+            .iter()
+            .map(|&t| {
+                (t - mean_crossing + self.bit_period / 2.0).rem_euclid(self.bit_period)
+                    - self.bit_period / 2.0
+            })
+            .collect();
+
+        let rms = (deviations.iter().map(|d| d * d).sum::<f64>() / deviations.len() as f64).sqrt();
+        let earliest = deviations.iter().copied().fold(f64::INFINITY, f64::min);
+        let latest = deviations.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        Some(JitterMetrics {
+            mean_crossing,
+            rms,
+            peak_to_peak: latest - earliest,
+            crossings: deviations.len(),
+        })
+    }
+
+    /// Gets the horizontal eye opening, the bit period minus the peak to peak jitter.
+    pub fn eye_width(&self) -> f64 {
+        match self.jitter() {
+            Some(jitter) => (self.bit_period - jitter.peak_to_peak).max(0.0),
+            None => self.bit_period,
+        }
+    }
+
+    /// Gets the vertical eye opening at the center of the eye, half a bit period from the mean
+    /// crossing: the lowest one minus the highest zero seen there.
+    pub fn eye_height(&self) -> f64 {
+        let mean_crossing = self.jitter().map_or(0.0, |j| j.mean_crossing);
+        let center = (mean_crossing + self.bit_period / 2.0).rem_euclid(self.bit_period);
+        let bin = self.time_bin(center);
+
+        (self.lowest_high[bin] - self.highest_low[bin]).max(0.0)
+    }
+
+    /// Exports the histogram as CSV with one row per time bin and one column per value bin.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time");
+        for value_bin in 0..self.value_bins {
+            let value = self.min_value
+                + (value_bin as f64 + 0.5) * (self.max_value - self.min_value)
+                    / self.value_bins as f64;
+            write!(csv, ",{value}").unwrap();
+        }
+        csv.push('\n');
+
+        for (time_bin, counts) in self.histogram.iter().enumerate() {
+            let time = (time_bin as f64 + 0.5) * self.bit_period / self.time_bins as f64;
+            write!(csv, "{time}").unwrap();
+            for count in counts {
+                write!(csv, ",{count}").unwrap();
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    /// An NRZ waveform with linear edges of the given rise time, every other edge delayed by
+    /// `jitter`.
+    fn nrz(
+        bits: &[bool],
+        bit_period: f64,
+        rise_time: f64,
+        jitter: f64,
+        dt: f64,
+    ) -> Vec<(f64, f64)> {
+        let level = |b: bool| if b { 1.0 } else { 0.0 };
+        let steps = (bits.len() as f64 * bit_period / dt) as usize;
+
+        (0..steps)
+            .map(|k| {
+                let t = k as f64 * dt;
+                let bit = ((t / bit_period) as usize).min(bits.len() - 1);
+                let edge = bit as f64 * bit_period + if bit % 2 == 1 { jitter } else { 0.0 };
+                let previous = if bit == 0 { bits[0] } else { bits[bit - 1] };
+
+                let x = ((t - edge) / rise_time).clamp(0.0, 1.0);
+                (
+                    t,
+                    level(previous) + (level(bits[bit]) - level(previous)) * x,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clean_eye() {
+        let bits = [
+            false, true, false, true, true, false, false, true, false, true,
+        ];
+        let samples = nrz(&bits, 1.0, 0.1, 0.0, 1e-3);
+
+        let mut eye = EyeDiagram::new(1.0, -0.5, 1.5);
+        eye.record_waveform(&samples);
+
+        let jitter = eye.jitter().unwrap();
+        assert_eq!(jitter.crossings, 7);
+        assert_relative_eq!(jitter.mean_crossing, 0.05, epsilon = 1e-3);
+        assert_relative_eq!(jitter.peak_to_peak, 0.0, epsilon = 1e-6);
+        assert_relative_eq!(eye.eye_width(), 1.0, epsilon = 1e-6);
+        assert_relative_eq!(eye.eye_height(), 1.0);
+
+        let total: usize = eye.get_histogram().iter().flatten().sum();
+        assert_eq!(total, samples.len());
+    }
+
+    #[test]
+    fn jittery_eye() {
+        let bits = [false, true, false, true, false, true, false, true, false];
+        let samples = nrz(&bits, 1.0, 0.1, 0.04, 1e-3);
+
+        let mut eye = EyeDiagram::new(1.0, -0.5, 1.5).with_resolution(100, 20);
+        eye.record_waveform(&samples);
+
+        let jitter = eye.jitter().unwrap();
+        assert_relative_eq!(jitter.peak_to_peak, 0.04, epsilon = 1e-6);
+        assert_relative_eq!(jitter.rms, 0.02, epsilon = 2e-3);
+        assert_relative_eq!(eye.eye_width(), 0.96, epsilon = 1e-6);
+        assert_relative_eq!(eye.eye_height(), 1.0);
+        assert_eq!(eye.to_csv().lines().count(), 101);
+    }
+}
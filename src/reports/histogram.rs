@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+/// Counts of values falling into equally wide bins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The bin edges, one more than there are bins.
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Sorts the values into the given number of bins spanning them. Values that aren't finite
+    /// are left out.
+    pub fn new(values: &[f64], bins: usize) -> Self {
+        let bins = bins.max(1);
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let (low, high) = finite
+            .clone()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| {
+                (low.min(v), high.max(v))
+            });
+        if low > high {
+            return Self {
+                edges: Vec::new(),
+                counts: Vec::new(),
+            };
+        }
+
+        // All equal values still get a bin of some width.
+        let width = if high > low {
+            (high - low) / bins as f64
+        } else {
+            low.abs().max(1.0) * 1e-9
+        };
+
+        let mut counts = vec![0; bins];
+        for v in finite {
+            let bin = ((v - low) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+
+        Self {
+            edges: (0..=bins).map(|k| low + width * k as f64).collect(),
+            counts,
+        }
+    }
+
+    /// Exports the histogram as CSV, one bin per line.
+    pub fn export_csv(&self) -> String {
+        let mut csv = String::from("low,high,count\n");
+        for (edges, count) in self.edges.windows(2).zip(&self.counts) {
+            writeln!(csv, "{},{},{}", edges[0], edges[1], count).unwrap();
+        }
+        csv
+    }
+}
+
+/// Gets the given percentile (0 to 100) of the values, interpolating linearly between the
+/// values closest to it. NaN if there are no finite values.
+pub fn percentile(values: &[f64], percentile: f64) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    sorted.sort_by(f64::total_cmp);
+
+    let position = (percentile / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn histogram_and_percentiles() {
+        let values = [1.0, 2.0, 2.5, 3.0, 5.0, f64::NAN];
+        let histogram = Histogram::new(&values, 4);
+        assert_eq!(histogram.edges, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(histogram.counts, vec![1, 2, 1, 1]);
+        assert_eq!(
+            histogram.export_csv(),
+            "low,high,count\n1,2,1\n2,3,2\n3,4,1\n4,5,1\n"
+        );
+
+        assert_eq!(Histogram::new(&[2.0, 2.0], 3).counts, vec![2, 0, 0]);
+        assert!(Histogram::new(&[], 3).counts.is_empty());
+
+        assert_relative_eq!(percentile(&values, 0.0), 1.0);
+        assert_relative_eq!(percentile(&values, 50.0), 2.5);
+        assert_relative_eq!(percentile(&values, 100.0), 5.0);
+        assert_relative_eq!(percentile(&values, 87.5), 4.0);
+        assert!(percentile(&[], 50.0).is_nan());
+    }
+}
@@ -0,0 +1,23 @@
+mod operating_point;
+pub use operating_point::export_operating_point;
+
+mod stress;
+pub use stress::{ComponentStress, StressColumn, StressReport};
+
+mod bom;
+pub use bom::{BillOfMaterials, BomLine};
+
+mod eye;
+pub use eye::{EyeDiagram, JitterMetrics};
+
+mod smith;
+pub use smith::{
+    FrequencyUnit, SmithPoint, TouchstoneFormat, TouchstoneOptions, export_smith_chart,
+    export_touchstone, smith_chart,
+};
+
+mod histogram;
+pub use histogram::{Histogram, percentile};
+
+mod emc;
+pub use emc::{CisprBand, EmissionLimit, EmissionReading, conducted_emissions, dbuv};
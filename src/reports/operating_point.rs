@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use crate::components::Netlist;
+
+/// Formats a string as a JSON string literal.
+pub(super) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Formats a number as a JSON number, using null for values JSON can't represent.
+pub(super) fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value}")
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Exports the operating point of a circuit as JSON for annotating schematics.
+///
+/// `node_voltages` holds the voltage of every node, indexed by node number (see
+/// [`BESolver::get_node_voltages`](crate::BESolver::get_node_voltages)). Nodes and components
+/// are keyed by their names:
+///
+/// ```json
+/// {
+///   "nodes": {"0": 0, "out": 2.5},
+///   "components": {"R1": {"v": 2.5, "i": 0.0025, "p": 0.00625}}
+/// }
+/// ```
+pub fn export_operating_point(netlist: &Netlist, node_voltages: &[f64]) -> String {
+    let nodes: Vec<String> = node_voltages
+        .iter()
+        .enumerate()
+        .map(|(node, voltage)| {
+            format!(
+                "{}: {}",
+                json_string(&netlist.get_node_name(node)),
+                json_number(*voltage)
+            )
+        })
+        .collect();
+
+    let components: Vec<String> = netlist
+        .get_components()
+        .iter()
+        .enumerate()
+        .map(|(index, c)| {
+            format!(
+                "{}: {{\"v\": {}, \"i\": {}, \"p\": {}}}",
+                json_string(&netlist.get_component_name(index)),
+                json_number(c.get_voltage()),
+                json_number(c.get_current()),
+                json_number(c.get_power())
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\n  \"nodes\": {{{}}},\n  \"components\": {{\n    {}\n  }}\n}}\n",
+        nodes.join(", "),
+        components.join(",\n    ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Resistor, VoltageSource},
+    };
+
+    #[test]
+    fn test_export_voltage_divider() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 5.0))
+            .add_component(Resistor::new(1, 2, 1000.0))
+            .add_component(Resistor::new(2, 0, 1000.0))
+            .set_component_name(0, "VIN")
+            .set_component_name(2, "R\"low\"")
+            .set_node_name(0, "gnd")
+            .set_node_name(2, "out");
+
+        let mut solver = BESolver::new(&mut netlist);
+        solver.solve(0.001).unwrap();
+        let node_voltages = solver.get_node_voltages().clone();
+
+        let json = export_operating_point(&netlist, &node_voltages);
+        assert!(json.contains("\"nodes\": {\"gnd\": 0, \"1\": 5, \"out\": 2.5}"));
+        assert!(json.contains("\"VIN\": {\"v\": 5, \"i\": 0.0025, \"p\": 0.0125}"));
+        assert!(json.contains("\"R1\": {\"v\": 2.5, \"i\": 0.0025, \"p\": 0.00625}"));
+        assert!(json.contains("\"R\\\"low\\\"\": {"));
+    }
+}
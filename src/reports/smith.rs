@@ -0,0 +1,270 @@
+use std::fmt::Write;
+
+use nalgebra::{Complex, DMatrix};
+
+use crate::reflection_coefficient;
+
+/// A point of a Smith chart trace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmithPoint {
+    pub frequency: f64,
+    /// Reflection coefficient, the position on the chart.
+    pub reflection: Complex<f64>,
+    /// Impedance divided by the reference impedance.
+    pub normalized_impedance: Complex<f64>,
+}
+
+/// Converts port impedances, one per frequency (see
+/// [`ACSolver::port_impedance`](crate::ACSolver::port_impedance)), into Smith chart points
+/// against the given reference impedance.
+pub fn smith_chart(
+    frequencies: &[f64],
+    impedances: &[Complex<f64>],
+    reference: f64,
+) -> Vec<SmithPoint> {
+    frequencies
+        .iter()
+        .zip(impedances)
+        .map(|(&frequency, &impedance)| SmithPoint {
+            frequency,
+            reflection: reflection_coefficient(impedance, reference),
+            normalized_impedance: impedance / reference,
+        })
+        .collect()
+}
+
+/// Exports Smith chart points as CSV.
+pub fn export_smith_chart(points: &[SmithPoint]) -> String {
+    let mut csv = String::from("frequency,gamma_re,gamma_im,gamma_mag,gamma_deg,z_re,z_im\n");
+    for point in points {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            point.frequency,
+            point.reflection.re,
+            point.reflection.im,
+            point.reflection.norm(),
+            point.reflection.arg().to_degrees(),
+            point.normalized_impedance.re,
+            point.normalized_impedance.im,
+        )
+        .unwrap();
+    }
+    csv
+}
+
+/// Unit the frequencies of a Touchstone file are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyUnit {
+    Hz,
+    KHz,
+    MHz,
+    GHz,
+}
+
+impl FrequencyUnit {
+    fn scale(&self) -> f64 {
+        match self {
+            Self::Hz => 1.0,
+            Self::KHz => 1e3,
+            Self::MHz => 1e6,
+            Self::GHz => 1e9,
+        }
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self {
+            Self::Hz => "Hz",
+            Self::KHz => "kHz",
+            Self::MHz => "MHz",
+            Self::GHz => "GHz",
+        }
+    }
+}
+
+/// How each complex parameter of a Touchstone file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchstoneFormat {
+    /// Real and imaginary parts.
+    RealImaginary,
+    /// Magnitude and angle in degrees.
+    MagnitudeAngle,
+    /// Magnitude in decibels and angle in degrees.
+    DecibelAngle,
+}
+
+impl TouchstoneFormat {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Self::RealImaginary => "RI",
+            Self::MagnitudeAngle => "MA",
+            Self::DecibelAngle => "DB",
+        }
+    }
+
+    fn format(&self, value: Complex<f64>) -> (f64, f64) {
+        match self {
+            Self::RealImaginary => (value.re, value.im),
+            Self::MagnitudeAngle => (value.norm(), value.arg().to_degrees()),
+            Self::DecibelAngle => (20.0 * value.norm().log10(), value.arg().to_degrees()),
+        }
+    }
+}
+
+/// Options of a Touchstone file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchstoneOptions {
+    pub reference: f64,
+    pub frequency_unit: FrequencyUnit,
+    pub format: TouchstoneFormat,
+}
+
+impl Default for TouchstoneOptions {
+    fn default() -> Self {
+        Self {
+            reference: 50.0,
+            frequency_unit: FrequencyUnit::Hz,
+            format: TouchstoneFormat::RealImaginary,
+        }
+    }
+}
+
+impl TouchstoneOptions {
+    pub fn with_reference(mut self, reference: f64) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    pub fn with_frequency_unit(mut self, frequency_unit: FrequencyUnit) -> Self {
+        self.frequency_unit = frequency_unit;
+        self
+    }
+
+    pub fn with_format(mut self, format: TouchstoneFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// Exports network parameters as a Touchstone (.sNp) file, with one square matrix of
+/// S-parameters per frequency (see [`ACSolver::s_parameters`](crate::ACSolver::s_parameters)).
+///
+/// Follows the version 1 layout: two-port data is written on a single line in the order S11 S21
+/// S12 S22, larger networks one matrix row at a time with at most four entries per line.
+pub fn export_touchstone(
+    frequencies: &[f64],
+    parameters: &[DMatrix<Complex<f64>>],
+    options: TouchstoneOptions,
+) -> String {
+    let mut out = format!(
+        "# {} S {} R {}\n",
+        options.frequency_unit.keyword(),
+        options.format.keyword(),
+        options.reference
+    );
+
+    for (frequency, s) in frequencies.iter().zip(parameters) {
+        let ports = s.nrows();
+        write!(out, "{}", frequency / options.frequency_unit.scale()).unwrap();
+
+        if ports <= 2 {
+            // Column major: S11 S21 S12 S22.
+            for &value in s.iter() {
+                let (a, b) = options.format.format(value);
+                write!(out, " {a} {b}").unwrap();
+            }
+            out.push('\n');
+            continue;
+        }
+
+        for row in 0..ports {
+            for column in 0..ports {
+                if column > 0 && column % 4 == 0 {
+                    out.push('\n');
+                }
+                let (a, b) = options.format.format(s[(row, column)]);
+                write!(out, " {a} {b}").unwrap();
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn smith_points() {
+        let points = smith_chart(
+            &[1.0, 2.0],
+            &[Complex::from(50.0), Complex::new(0.0, 50.0)],
+            50.0,
+        );
+
+        assert_relative_eq!(points[0].reflection.norm(), 0.0);
+        // A reactance equal to the reference sits on the top of the unit circle.
+        assert_relative_eq!(points[1].reflection.norm(), 1.0, epsilon = 1e-12);
+        assert_relative_eq!(points[1].reflection.im, 1.0, epsilon = 1e-12);
+        assert_eq!(points[1].normalized_impedance, Complex::new(0.0, 1.0));
+
+        let csv = export_smith_chart(&points);
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.lines().nth(1).unwrap().starts_with("1,0,0,0,"));
+    }
+
+    #[test]
+    fn touchstone_layout() {
+        let two_port = DMatrix::from_row_slice(
+            2,
+            2,
+            &[
+                Complex::new(0.1, 0.0),
+                Complex::new(0.2, 0.0),
+                Complex::new(0.3, 0.0),
+                Complex::new(0.4, 0.0),
+            ],
+        );
+        assert_eq!(
+            export_touchstone(&[1e6], &[two_port], TouchstoneOptions::default()),
+            "# Hz S RI R 50\n1000000 0.1 0 0.3 0 0.2 0 0.4 0\n"
+        );
+
+        let five_port = DMatrix::from_element(5, 5, Complex::new(1.0, 0.0));
+        let touchstone = export_touchstone(
+            &[1.0],
+            &[five_port],
+            TouchstoneOptions::default().with_reference(75.0),
+        );
+        // Every row of five entries is split over two lines.
+        assert_eq!(touchstone.lines().count(), 11);
+        assert_eq!(touchstone.lines().nth(2).unwrap(), " 1 0");
+    }
+
+    #[test]
+    fn touchstone_formats() {
+        let one_port = [DMatrix::from_element(1, 1, Complex::new(0.0, 0.1))];
+        let options = TouchstoneOptions::default().with_frequency_unit(FrequencyUnit::MHz);
+
+        assert_eq!(
+            export_touchstone(
+                &[2e6],
+                &one_port,
+                options.with_format(TouchstoneFormat::MagnitudeAngle)
+            ),
+            "# MHz S MA R 50\n2 0.1 90\n"
+        );
+        assert_eq!(
+            export_touchstone(
+                &[2e6],
+                &one_port,
+                options.with_format(TouchstoneFormat::DecibelAngle)
+            ),
+            "# MHz S DB R 50\n2 -20 90\n"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use crate::components::Netlist;
+
+/// Stress seen by a single component over a transient run.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ComponentStress {
+    /// Index of the component in the netlist.
+    pub component: usize,
+    pub peak_voltage: f64,
+    pub peak_current: f64,
+    pub rms_current: f64,
+    pub peak_power: f64,
+    pub average_power: f64,
+}
+
+impl ComponentStress {
+    pub fn get(&self, column: StressColumn) -> f64 {
+        match column {
+            StressColumn::PeakVoltage => self.peak_voltage,
+            StressColumn::PeakCurrent => self.peak_current,
+            StressColumn::RmsCurrent => self.rms_current,
+            StressColumn::PeakPower => self.peak_power,
+            StressColumn::AveragePower => self.average_power,
+        }
+    }
+}
+
+/// A column of the stress report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressColumn {
+    PeakVoltage,
+    PeakCurrent,
+    RmsCurrent,
+    PeakPower,
+    AveragePower,
+}
+
+/// Running sums for one component.
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    peak_voltage: f64,
+    peak_current: f64,
+    peak_power: f64,
+    current_squared_integral: f64,
+    energy: f64,
+}
+
+/// Aggregates the stress on every component over a transient run (derating analysis).
+///
+/// [`StressReport::record`] should be called after every timestep. Peaks are of magnitudes,
+/// RMS and average values are weighted by the timestep.
+#[derive(Debug, Clone, Default)]
+pub struct StressReport {
+    accumulators: Vec<Accumulator>,
+    duration: f64,
+}
+
+impl StressReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the state of every component after a timestep of length dt.
+    pub fn record(&mut self, netlist: &Netlist, dt: f64) {
+        let components = netlist.get_components();
+        if self.accumulators.len() < components.len() {
+            self.accumulators
+                .resize(components.len(), Accumulator::default());
+        }
+
+        for (accumulator, component) in self.accumulators.iter_mut().zip(components) {
+            let voltage = component.get_voltage();
+            let current = component.get_current();
+            let power = component.get_power();
+
+            accumulator.peak_voltage = accumulator.peak_voltage.max(voltage.abs());
+            accumulator.peak_current = accumulator.peak_current.max(current.abs());
+            accumulator.peak_power = accumulator.peak_power.max(power.abs());
+            accumulator.current_squared_integral += current * current * dt;
+            accumulator.energy += power * dt;
+        }
+
+        self.duration += dt;
+    }
+
+    /// Gets the total time recorded.
+    pub fn get_duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Gets the stress of every component, in netlist order.
+    pub fn get_table(&self) -> Vec<ComponentStress> {
+        self.accumulators
+            .iter()
+            .enumerate()
+            .map(|(component, a)| ComponentStress {
+                component,
+                peak_voltage: a.peak_voltage,
+                peak_current: a.peak_current,
+                rms_current: (a.current_squared_integral / self.duration).sqrt(),
+                peak_power: a.peak_power,
+                average_power: a.energy / self.duration,
+            })
+            .collect()
+    }
+
+    /// Gets the stress of every component, most stressed first by the given column.
+    pub fn get_sorted_table(&self, column: StressColumn) -> Vec<ComponentStress> {
+        let mut table = self.get_table();
+        table.sort_by(|a, b| b.get(column).total_cmp(&a.get(column)));
+        table
+    }
+
+    /// Exports the table as CSV.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "component,peak_voltage,peak_current,rms_current,peak_power,average_power\n",
+        );
+        for row in self.get_table() {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                row.component,
+                row.peak_voltage,
+                row.peak_current,
+                row.rms_current,
+                row.peak_power,
+                row.average_power
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BESolver,
+        components::{Resistor, Switch, VoltageSource},
+    };
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_switched_resistor_stress() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Switch::new(1, 2, 1e-9, 1e12, 0.0055))
+            .add_component(Resistor::new(2, 0, 10.0))
+            .add_component(Resistor::new(1, 0, 100.0));
+
+        let mut report = StressReport::new();
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..10 {
+            solver.solve(0.001).unwrap();
+            report.record(solver.get_netlist(), 0.001);
+        }
+
+        // The 10 ohm resistor conducts 1A for half the run.
+        let table = report.get_table();
+        assert_relative_eq!(report.get_duration(), 0.01, max_relative = 1e-9);
+        assert_relative_eq!(table[2].peak_voltage, 10.0, max_relative = 0.001);
+        assert_relative_eq!(table[2].peak_current, 1.0, max_relative = 0.001);
+        assert_relative_eq!(table[2].rms_current, 0.5f64.sqrt(), max_relative = 0.001);
+        assert_relative_eq!(table[2].peak_power, 10.0, max_relative = 0.001);
+        assert_relative_eq!(table[2].average_power, 5.0, max_relative = 0.001);
+        assert_relative_eq!(table[3].average_power, 1.0, max_relative = 0.001);
+
+        let sorted = report.get_sorted_table(StressColumn::AveragePower);
+        assert_eq!(
+            sorted.iter().map(|s| s.component).collect::<Vec<_>>(),
+            [0, 2, 3, 1]
+        );
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("component,peak_voltage"));
+    }
+}
@@ -0,0 +1,97 @@
+/// How values between the stored samples of a waveform are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Straight lines between samples.
+    #[default]
+    Linear,
+    /// Cubic Hermite splines with slopes estimated from the neighbouring samples, which follows
+    /// smooth waveforms much closer than straight lines when the samples are sparse.
+    Cubic,
+}
+
+impl Interpolation {
+    /// Interpolates the samples at the given time, which must be sorted by time. Times outside
+    /// the samples take the value of the nearest end. Returns None if there are no samples.
+    pub fn evaluate(&self, times: &[f64], values: &[f64], time: f64) -> Option<f64> {
+        let n = times.len();
+        if n == 0 {
+            return None;
+        }
+        if n == 1 || time <= times[0] {
+            return Some(values[0]);
+        }
+        if time >= times[n - 1] {
+            return Some(values[n - 1]);
+        }
+
+        let k = times.partition_point(|&t| t <= time).clamp(1, n - 1);
+        let h = times[k] - times[k - 1];
+        let x = (time - times[k - 1]) / h;
+
+        Some(match self {
+            Self::Linear => values[k - 1] + x * (values[k] - values[k - 1]),
+            Self::Cubic => {
+                let m0 = slope(times, values, k - 1) * h;
+                let m1 = slope(times, values, k) * h;
+
+                let x2 = x * x;
+                let x3 = x2 * x;
+                (2.0 * x3 - 3.0 * x2 + 1.0) * values[k - 1]
+                    + (x3 - 2.0 * x2 + x) * m0
+                    + (-2.0 * x3 + 3.0 * x2) * values[k]
+                    + (x3 - x2) * m1
+            }
+        })
+    }
+}
+
+/// Estimates the derivative at a sample from its neighbours, weighting each side by the width of
+/// the other so unevenly spaced samples are handled.
+fn slope(times: &[f64], values: &[f64], k: usize) -> f64 {
+    let n = times.len();
+    let secant = |a: usize, b: usize| (values[b] - values[a]) / (times[b] - times[a]);
+
+    if k == 0 {
+        secant(0, 1)
+    } else if k == n - 1 {
+        secant(n - 2, n - 1)
+    } else {
+        let (h0, h1) = (times[k] - times[k - 1], times[k + 1] - times[k]);
+        (secant(k - 1, k) * h1 + secant(k, k + 1) * h0) / (h0 + h1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn interpolates_sine() {
+        let times: Vec<f64> = [0.0, 0.1, 0.25, 0.4, 0.5, 0.7, 0.8, 1.0]
+            .iter()
+            .map(|t| t * std::f64::consts::PI)
+            .collect();
+        let values: Vec<f64> = times.iter().map(|t| t.sin()).collect();
+
+        let time = 0.6 * std::f64::consts::PI;
+        let linear = Interpolation::Linear
+            .evaluate(&times, &values, time)
+            .unwrap();
+        let cubic = Interpolation::Cubic
+            .evaluate(&times, &values, time)
+            .unwrap();
+        assert!((cubic - time.sin()).abs() < (linear - time.sin()).abs() / 5.0);
+
+        // Samples are reproduced exactly and the ends are held.
+        for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+            assert_relative_eq!(
+                interpolation.evaluate(&times, &values, times[3]).unwrap(),
+                values[3]
+            );
+            assert_eq!(interpolation.evaluate(&times, &values, -1.0), Some(0.0));
+            assert_eq!(interpolation.evaluate(&[], &[], 0.0), None);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+mod probe;
+pub use probe::{Probe, StoredProbe};
+
+mod transient;
+pub use transient::TransientResult;
+
+mod storage;
+pub use storage::{ProbeStatistics, StoragePolicy};
+
+mod interpolation;
+pub use interpolation::Interpolation;
+
+mod trace;
+pub use trace::Trace;
+
+mod unit;
+pub use unit::Unit;
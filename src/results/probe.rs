@@ -0,0 +1,97 @@
+use crate::components::Netlist;
+use crate::results::{StoragePolicy, Unit};
+
+/// A signal recorded from a transient simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Probe {
+    /// Voltage of a node relative to the reference node.
+    NodeVoltage(usize),
+    /// Voltage of the first node relative to the second, v(a) - v(b).
+    DifferentialVoltage(usize, usize),
+    /// Voltage across the component at the given index.
+    ComponentVoltage(usize),
+    /// Current through the component at the given index.
+    ComponentCurrent(usize),
+    /// Instantaneous power of the component at the given index.
+    ComponentPower(usize),
+    /// Sum of other probes each scaled by a factor.
+    ScaledSum(Vec<(f64, Probe)>),
+}
+
+impl Probe {
+    /// Creates a probe of the difference between two probes.
+    pub fn difference(a: Probe, b: Probe) -> Self {
+        Self::ScaledSum(vec![(1.0, a), (-1.0, b)])
+    }
+
+    /// Creates a probe of another probe scaled by a factor.
+    pub fn scaled(factor: f64, probe: Probe) -> Self {
+        Self::ScaledSum(vec![(factor, probe)])
+    }
+
+    /// Gives the probe a storage policy of its own, overriding the one of the
+    /// [`TransientResult`](super::TransientResult) it is added to, e.g. to keep every sample
+    /// of a few critical signals while decimating the rest.
+    pub fn with_storage(self, storage: StoragePolicy) -> StoredProbe {
+        StoredProbe {
+            probe: self,
+            storage: Some(storage),
+        }
+    }
+
+    /// Gets the unit of the probed quantity. Scaled sums are taken to be in the unit their terms
+    /// share, so scale factors are pure gains, and are dimensionless if the terms don't share one.
+    pub fn get_unit(&self) -> Unit {
+        match self {
+            Self::NodeVoltage(_) | Self::DifferentialVoltage(..) | Self::ComponentVoltage(_) => {
+                Unit::Volt
+            }
+            Self::ComponentCurrent(_) => Unit::Ampere,
+            Self::ComponentPower(_) => Unit::Watt,
+            Self::ScaledSum(terms) => {
+                let mut units = terms.iter().map(|(_, probe)| probe.get_unit());
+                let first = units.next().unwrap_or_default();
+                if units.all(|unit| unit == first) {
+                    first
+                } else {
+                    Unit::Dimensionless
+                }
+            }
+        }
+    }
+
+    /// Evaluates the probe on a solved netlist, given the voltage of every node indexed by node
+    /// number.
+    pub fn evaluate(&self, netlist: &Netlist, node_voltages: &[f64]) -> f64 {
+        let node_voltage = |node: usize| node_voltages.get(node).copied().unwrap_or(0.0);
+
+        match self {
+            Self::NodeVoltage(node) => node_voltage(*node),
+            Self::DifferentialVoltage(a, b) => node_voltage(*a) - node_voltage(*b),
+            Self::ComponentVoltage(index) => netlist.get_components()[*index].get_voltage(),
+            Self::ComponentCurrent(index) => netlist.get_components()[*index].get_current(),
+            Self::ComponentPower(index) => netlist.get_components()[*index].get_power(),
+            Self::ScaledSum(terms) => terms
+                .iter()
+                .map(|(factor, probe)| factor * probe.evaluate(netlist, node_voltages))
+                .sum(),
+        }
+    }
+}
+
+/// A probe along with how its samples are stored, None to store them as the result it is added
+/// to stores the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredProbe {
+    pub probe: Probe,
+    pub storage: Option<StoragePolicy>,
+}
+
+impl From<Probe> for StoredProbe {
+    fn from(probe: Probe) -> Self {
+        Self {
+            probe,
+            storage: None,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+/// How the samples of a [`TransientResult`](super::TransientResult) are stored.
+///
+/// All probes share one time axis, so a sample is kept whenever any probe needs it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StoragePolicy {
+    /// Keeps every sample.
+    #[default]
+    All,
+    /// Keeps every Nth sample, and the last one.
+    EveryNth(usize),
+    /// Keeps, for every interval of the given length, the samples at which each probe reached
+    /// its minimum and maximum, so peaks and envelopes survive decimation.
+    MinMax { interval: f64 },
+    /// Keeps a sample only when linearly interpolating between the kept samples would otherwise
+    /// be off by more than the tolerance on some probe.
+    ErrorBounded { tolerance: f64 },
+    /// Keeps no samples, only the [`ProbeStatistics`] every probe gets.
+    StatisticsOnly,
+}
+
+/// Running statistics of a probe over every recorded sample, whatever its storage policy.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProbeStatistics {
+    /// Number of samples recorded.
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    /// Average over time, integrating linearly between samples.
+    pub mean: f64,
+    /// RMS value over time, integrating linearly between samples.
+    pub rms: f64,
+    pub start: f64,
+    pub stop: f64,
+
+    // Integrals of the value and of its square, and the last sample.
+    integral: f64,
+    square_integral: f64,
+    last: f64,
+}
+
+impl ProbeStatistics {
+    pub(crate) fn push(&mut self, time: f64, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+            self.start = time;
+        } else {
+            let dt = time - self.stop;
+            self.integral += (value + self.last) / 2.0 * dt;
+            self.square_integral +=
+                (value * value + value * self.last + self.last * self.last) / 3.0 * dt;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.stop = time;
+        self.last = value;
+
+        let duration = self.stop - self.start;
+        if duration > 0.0 {
+            self.mean = self.integral / duration;
+            self.rms = (self.square_integral / duration).max(0.0).sqrt();
+        } else {
+            self.mean = value;
+            self.rms = value.abs();
+        }
+    }
+}
+
+/// A sample time and the value of every probe at that time.
+pub(crate) type Sample = (f64, Vec<f64>);
+
+/// Applies a [`StoragePolicy`] to a stream of samples.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Compressor {
+    policy: StoragePolicy,
+    count: usize,
+
+    /// Every Nth and error bounded: the last sample seen and whether it was kept.
+    previous: Option<(Sample, bool)>,
+
+    /// Min/max: the samples of the current interval and when it ends.
+    bin: Vec<Sample>,
+    bin_start: f64,
+    bin_end: f64,
+
+    /// Error bounded: the last kept sample and the range of slopes from it, for every probe,
+    /// which stay within the tolerance of all the samples since.
+    anchor: Option<Sample>,
+    slopes: Vec<(f64, f64)>,
+}
+
+impl Compressor {
+    pub(crate) fn new(policy: StoragePolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Takes a new sample, returning the samples to store.
+    pub(crate) fn push(&mut self, sample: Sample) -> Vec<Sample> {
+        self.count += 1;
+
+        match self.policy {
+            StoragePolicy::All => vec![sample],
+            StoragePolicy::EveryNth(n) => {
+                let keep = (self.count - 1).is_multiple_of(n.max(1));
+                self.previous = Some((sample.clone(), keep));
+                if keep { vec![sample] } else { Vec::new() }
+            }
+            StoragePolicy::MinMax { interval } => {
+                let time = sample.0;
+                let mut stored = Vec::new();
+
+                if self.count == 1 {
+                    self.bin_start = time;
+                    self.bin_end = time + interval;
+                } else if time >= self.bin_end {
+                    stored = self.flush_bin();
+                    let bins = ((time - self.bin_start) / interval).floor() + 1.0;
+                    self.bin_end = self.bin_start + bins * interval;
+                }
+
+                self.bin.push(sample);
+                stored
+            }
+            StoragePolicy::ErrorBounded { tolerance } => self.push_error_bounded(sample, tolerance),
+            StoragePolicy::StatisticsOnly => Vec::new(),
+        }
+    }
+
+    /// Returns any samples still held back which should be stored once recording ends.
+    pub(crate) fn finish(&mut self) -> Vec<Sample> {
+        match self.policy {
+            StoragePolicy::All | StoragePolicy::StatisticsOnly => Vec::new(),
+            StoragePolicy::MinMax { .. } => self.flush_bin(),
+            StoragePolicy::EveryNth(_) | StoragePolicy::ErrorBounded { .. } => {
+                match &mut self.previous {
+                    Some((sample, kept)) if !*kept => {
+                        *kept = true;
+                        vec![sample.clone()]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        }
+    }
+
+    fn flush_bin(&mut self) -> Vec<Sample> {
+        if self.bin.is_empty() {
+            return Vec::new();
+        }
+
+        let probes = self.bin[0].1.len();
+        let mut keep = vec![false; self.bin.len()];
+        for probe in 0..probes {
+            let mut min = 0;
+            let mut max = 0;
+            for (index, (_, values)) in self.bin.iter().enumerate() {
+                if values[probe] < self.bin[min].1[probe] {
+                    min = index;
+                }
+                if values[probe] > self.bin[max].1[probe] {
+                    max = index;
+                }
+            }
+            keep[min] = true;
+            keep[max] = true;
+        }
+
+        self.bin
+            .drain(..)
+            .zip(keep)
+            .filter_map(|(sample, keep)| keep.then_some(sample))
+            .collect()
+    }
+
+    fn push_error_bounded(&mut self, sample: Sample, tolerance: f64) -> Vec<Sample> {
+        let Some((anchor_time, anchor_values)) = &self.anchor else {
+            self.anchor = Some(sample.clone());
+            self.slopes = vec![(f64::NEG_INFINITY, f64::INFINITY); sample.1.len()];
+            self.previous = Some((sample.clone(), true));
+            return vec![sample];
+        };
+
+        let dt = sample.0 - anchor_time;
+        let slope = |probe: usize| (sample.1[probe] - anchor_values[probe]) / dt;
+
+        // The new sample can end the segment if the line to it passes within the tolerance of
+        // every sample since the anchor.
+        let reachable = (0..sample.1.len()).all(|probe| {
+            let (low, high) = self.slopes[probe];
+            let s = slope(probe);
+            s >= low && s <= high
+        });
+
+        let mut stored = Vec::new();
+        if !reachable {
+            // Close the segment on the previous sample, which was still reachable.
+            let (previous, _) = self.previous.take().unwrap();
+            self.anchor = Some(previous.clone());
+            stored.push(previous);
+        }
+
+        let (anchor_time, anchor_values) = self.anchor.as_ref().unwrap();
+        let dt = sample.0 - anchor_time;
+        if !reachable {
+            self.slopes = vec![(f64::NEG_INFINITY, f64::INFINITY); sample.1.len()];
+        }
+        for (probe, (low, high)) in self.slopes.iter_mut().enumerate() {
+            let delta = sample.1[probe] - anchor_values[probe];
+            *low = low.max((delta - tolerance) / dt);
+            *high = high.min((delta + tolerance) / dt);
+        }
+
+        self.previous = Some((sample, false));
+        stored
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compress(policy: StoragePolicy, samples: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let mut compressor = Compressor::new(policy);
+        let mut stored: Vec<Sample> = Vec::new();
+        for &(time, value) in samples {
+            stored.extend(compressor.push((time, vec![value])));
+        }
+        stored.extend(compressor.finish());
+        stored.into_iter().map(|(t, v)| (t, v[0])).collect()
+    }
+
+    #[test]
+    fn every_nth() {
+        let samples: Vec<(f64, f64)> = (0..10).map(|k| (k as f64, k as f64)).collect();
+        let stored = compress(StoragePolicy::EveryNth(4), &samples);
+        assert_eq!(stored, vec![(0.0, 0.0), (4.0, 4.0), (8.0, 8.0), (9.0, 9.0)]);
+    }
+
+    #[test]
+    fn min_max() {
+        let samples: Vec<(f64, f64)> = (0..10)
+            .map(|k| (k as f64, [0.0, 3.0, 1.0, -2.0, 5.0][k % 5]))
+            .collect();
+        let stored = compress(StoragePolicy::MinMax { interval: 5.0 }, &samples);
+        assert_eq!(
+            stored,
+            vec![(3.0, -2.0), (4.0, 5.0), (8.0, -2.0), (9.0, 5.0)]
+        );
+    }
+
+    #[test]
+    fn error_bounded() {
+        // Two straight segments with a corner at t = 50.
+        let samples: Vec<(f64, f64)> = (0..=100)
+            .map(|k| {
+                let t = k as f64;
+                (t, if t <= 50.0 { t } else { 100.0 - t })
+            })
+            .collect();
+        let stored = compress(StoragePolicy::ErrorBounded { tolerance: 1e-6 }, &samples);
+        assert_eq!(stored, vec![(0.0, 0.0), (50.0, 50.0), (100.0, 0.0)]);
+
+        // Noise below the tolerance is dropped, noise above it is kept.
+        let noisy: Vec<(f64, f64)> = (0..100)
+            .map(|k| (k as f64, if k % 2 == 0 { 0.0 } else { 0.1 }))
+            .collect();
+        assert_eq!(
+            compress(StoragePolicy::ErrorBounded { tolerance: 0.1 }, &noisy).len(),
+            2
+        );
+        assert_eq!(
+            compress(StoragePolicy::ErrorBounded { tolerance: 0.01 }, &noisy).len(),
+            100
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::results::{Interpolation, Unit};
+
+/// A waveform: values sampled at increasing times, linearly interpolated in between.
+///
+/// Operations return new traces so they can be chained into custom measurements, e.g.
+/// `v.multiply(&i).moving_average(period)` for the average power.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    times: Vec<f64>,
+    values: Vec<f64>,
+    unit: Unit,
+}
+
+impl Trace {
+    /// Creates a dimensionless trace from samples, whose times must be increasing.
+    pub fn new(times: Vec<f64>, values: Vec<f64>) -> Self {
+        assert_eq!(
+            times.len(),
+            values.len(),
+            "Every time needs exactly one value"
+        );
+        Self {
+            times,
+            values,
+            unit: Unit::Dimensionless,
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Gets the unit of the values. Operations keep track of it where they can: sums, scaling,
+    /// averages and clips keep it, products and integrals combine it (e.g. volts times amperes
+    /// are watts), and anything else is dimensionless.
+    pub fn get_unit(&self) -> Unit {
+        self.unit
+    }
+
+    pub fn get_times(&self) -> &Vec<f64> {
+        &self.times
+    }
+
+    pub fn get_values(&self) -> &Vec<f64> {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Gets the value at any time, holding the end values outside the trace.
+    pub fn value_at(&self, time: f64) -> Option<f64> {
+        Interpolation::Linear.evaluate(&self.times, &self.values, time)
+    }
+
+    /// Applies a function to every value.
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(
+            self.times.clone(),
+            self.values.iter().map(|&v| f(v)).collect(),
+        )
+    }
+
+    /// Combines with another trace sample by sample, on the time axis of this trace.
+    pub fn zip_with(&self, other: &Trace, f: impl Fn(f64, f64) -> f64) -> Self {
+        let values = self
+            .times
+            .iter()
+            .zip(&self.values)
+            .map(|(&t, &v)| f(v, other.value_at(t).unwrap_or(0.0)))
+            .collect();
+        Self::new(self.times.clone(), values)
+    }
+
+    pub fn add(&self, other: &Trace) -> Self {
+        self.zip_with(other, |a, b| a + b).with_unit(self.unit)
+    }
+
+    pub fn subtract(&self, other: &Trace) -> Self {
+        self.zip_with(other, |a, b| a - b).with_unit(self.unit)
+    }
+
+    pub fn multiply(&self, other: &Trace) -> Self {
+        self.zip_with(other, |a, b| a * b)
+            .with_unit(self.unit.multiply(other.unit))
+    }
+
+    pub fn scale(&self, factor: f64) -> Self {
+        self.map(|v| v * factor).with_unit(self.unit)
+    }
+
+    /// Differentiates with respect to time, using central differences weighted for uneven
+    /// spacing inside the trace and one sided differences at its ends.
+    pub fn differentiate(&self) -> Self {
+        let n = self.len();
+        let secant = |a: usize, b: usize| {
+            (self.values[b] - self.values[a]) / (self.times[b] - self.times[a])
+        };
+
+        let values = (0..n)
+            .map(|k| {
+                if n < 2 {
+                    0.0
+                } else if k == 0 {
+                    secant(0, 1)
+                } else if k == n - 1 {
+                    secant(n - 2, n - 1)
+                } else {
+                    let h0 = self.times[k] - self.times[k - 1];
+                    let h1 = self.times[k + 1] - self.times[k];
+                    (secant(k - 1, k) * h1 + secant(k, k + 1) * h0) / (h0 + h1)
+                }
+            })
+            .collect();
+        Self::new(self.times.clone(), values)
+    }
+
+    /// Integrates over time from the start of the trace using the trapezoidal rule, which is
+    /// exact for the linear interpolation between samples.
+    pub fn integrate(&self) -> Self {
+        let mut total = 0.0;
+        let values = (0..self.len())
+            .map(|k| {
+                if k > 0 {
+                    total += (self.values[k] + self.values[k - 1]) / 2.0
+                        * (self.times[k] - self.times[k - 1]);
+                }
+                total
+            })
+            .collect();
+        Self::new(self.times.clone(), values).with_unit(self.unit.multiply(Unit::Second))
+    }
+
+    /// Gets the integral from the start of the trace to the given time, given the integral at
+    /// every sample.
+    fn integral_to(&self, integral: &[f64], time: f64) -> f64 {
+        let k = self.times.partition_point(|&t| t <= time);
+        if k == 0 {
+            return 0.0;
+        }
+        if k == self.len() {
+            return integral[k - 1];
+        }
+
+        let d = time - self.times[k - 1];
+        let slope = (self.values[k] - self.values[k - 1]) / (self.times[k] - self.times[k - 1]);
+        integral[k - 1] + self.values[k - 1] * d + slope * d * d / 2.0
+    }
+
+    /// Averages over the trailing window of the given length at every sample. Near the start,
+    /// where less than a window is available, averages over what there is.
+    pub fn moving_average(&self, window: f64) -> Self {
+        let integral = self.integrate();
+        let start = self.times.first().copied().unwrap_or(0.0);
+
+        let values = self
+            .times
+            .iter()
+            .zip(&self.values)
+            .zip(integral.get_values())
+            .map(|((&t, &v), &total)| {
+                let from = (t - window).max(start);
+                if t - from <= 0.0 {
+                    return v;
+                }
+                (total - self.integral_to(integral.get_values(), from)) / (t - from)
+            })
+            .collect();
+        Self::new(self.times.clone(), values).with_unit(self.unit)
+    }
+
+    /// Computes the RMS value over the trailing window of the given length at every sample.
+    pub fn moving_rms(&self, window: f64) -> Self {
+        self.map(|v| v * v)
+            .moving_average(window)
+            .map(|v| v.max(0.0).sqrt())
+            .with_unit(self.unit)
+    }
+
+    /// Keeps the part of the trace from `start` to `stop`, with interpolated samples added at
+    /// both ends if they fall between samples.
+    pub fn clip(&self, start: f64, stop: f64) -> Self {
+        let mut times = Vec::new();
+        let mut values = Vec::new();
+
+        if let (Some(&first), Some(&last)) = (self.times.first(), self.times.last()) {
+            let (start, stop) = (start.max(first), stop.min(last));
+            if start <= stop {
+                times.push(start);
+                values.push(self.value_at(start).unwrap());
+
+                for (&t, &v) in self.times.iter().zip(&self.values) {
+                    if t > start && t < stop {
+                        times.push(t);
+                        values.push(v);
+                    }
+                }
+
+                if stop > start {
+                    times.push(stop);
+                    values.push(self.value_at(stop).unwrap());
+                }
+            }
+        }
+
+        Self::new(times, values).with_unit(self.unit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn sine(frequency: f64, amplitude: f64, points: usize, duration: f64) -> Trace {
+        let times: Vec<f64> = (0..points)
+            .map(|k| k as f64 * duration / (points - 1) as f64)
+            .collect();
+        let values = times
+            .iter()
+            .map(|t| amplitude * (2.0 * PI * frequency * t).sin())
+            .collect();
+        Trace::new(times, values)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Trace::new(vec![0.0, 1.0, 2.0], vec![1.0, 2.0, 3.0]);
+        let b = Trace::new(vec![0.0, 2.0], vec![0.0, 4.0]);
+
+        assert_eq!(a.add(&b).get_values(), &vec![1.0, 4.0, 7.0]);
+        assert_eq!(a.subtract(&b).get_values(), &vec![1.0, 0.0, -1.0]);
+        assert_eq!(a.multiply(&b).get_values(), &vec![0.0, 4.0, 12.0]);
+        assert_eq!(a.scale(2.0).get_values(), &vec![2.0, 4.0, 6.0]);
+
+        let clipped = a.clip(0.5, 1.5);
+        assert_eq!(clipped.get_times(), &vec![0.5, 1.0, 1.5]);
+        assert_eq!(clipped.get_values(), &vec![1.5, 2.0, 2.5]);
+    }
+
+    #[test]
+    fn units() {
+        let v = Trace::new(vec![0.0, 1.0], vec![1.0, 2.0]).with_unit(Unit::Volt);
+        let i = Trace::new(vec![0.0, 1.0], vec![0.5, 0.5]).with_unit(Unit::Ampere);
+
+        let p = v.multiply(&i);
+        assert_eq!(p.get_unit(), Unit::Watt);
+        assert_eq!(p.integrate().get_unit(), Unit::Joule);
+        assert_eq!(p.moving_average(0.5).clip(0.0, 0.5).get_unit(), Unit::Watt);
+        assert_eq!(v.scale(2.0).subtract(&v).get_unit(), Unit::Volt);
+        assert_eq!(v.differentiate().get_unit(), Unit::Dimensionless);
+        assert_eq!(v.multiply(&v).get_unit(), Unit::Dimensionless);
+    }
+
+    #[test]
+    fn calculus() {
+        let trace = sine(1.0, 2.0, 2001, 1.0);
+
+        let derivative = trace.differentiate();
+        assert_relative_eq!(derivative.get_values()[500], 0.0, epsilon = 1e-2);
+        assert_relative_eq!(
+            derivative.get_values()[1000],
+            -4.0 * PI,
+            max_relative = 1e-4
+        );
+
+        // The integral of sin over half a period is 2/(2*pi) times the amplitude.
+        let integral = trace.integrate();
+        assert_relative_eq!(integral.get_values()[1000], 2.0 / PI, max_relative = 1e-5);
+
+        let rms = trace.moving_rms(0.5);
+        assert_relative_eq!(rms.get_values()[2000], 2f64.sqrt(), max_relative = 1e-5);
+        let average = trace.moving_average(1.0);
+        assert_relative_eq!(average.get_values()[2000], 0.0, epsilon = 1e-9);
+        assert_relative_eq!(average.get_values()[0], 0.0);
+    }
+}
@@ -0,0 +1,542 @@
+use std::fmt::Write;
+
+use crate::BESolver;
+use crate::components::Netlist;
+use crate::faults::FaultActivation;
+use crate::matlab::{MatValue, write_mat};
+use crate::results::storage::{Compressor, ProbeStatistics, Sample, StoragePolicy};
+use crate::results::{Interpolation, Probe, StoredProbe, Trace, Unit};
+
+/// Probed signals recorded over a transient simulation.
+///
+/// Probes are added before the simulation starts and [`TransientResult::record`] is called after
+/// every timestep, evaluating every probe (including derived ones) as it goes. Samples are stored
+/// according to the [`StoragePolicy`], which keeps everything by default; with any other policy
+/// [`TransientResult::finish`] should be called after the last timestep.
+///
+/// Probes given a policy of their own (see [`Probe::with_storage`]) are stored on time axes of
+/// their own, while the others share the time axis of the result. Statistics are kept for every
+/// probe over every recorded sample, whatever its policy.
+#[derive(Debug, Clone, Default)]
+pub struct TransientResult {
+    names: Vec<String>,
+    probes: Vec<Probe>,
+    units: Vec<Unit>,
+    locations: Vec<Location>,
+    statistics: Vec<ProbeStatistics>,
+    compressor: Compressor,
+    interpolation: Interpolation,
+    recorded: usize,
+
+    times: Vec<f64>,
+    values: Vec<Vec<f64>>,
+    channels: Vec<Channel>,
+    faults: Vec<FaultActivation>,
+}
+
+/// Where the samples of a probe are stored.
+#[derive(Debug, Clone, Copy)]
+enum Location {
+    /// On the shared time axis, by index in the shared values.
+    Shared(usize),
+    /// On a time axis of its own, by channel.
+    Own(usize),
+}
+
+/// The samples of a probe with a storage policy of its own.
+#[derive(Debug, Clone)]
+struct Channel {
+    compressor: Compressor,
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl Channel {
+    fn store(&mut self, samples: Vec<Sample>) {
+        for (time, sample) in samples {
+            self.times.push(time);
+            self.values.push(sample[0]);
+        }
+    }
+}
+
+impl TransientResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_storage(mut self, policy: StoragePolicy) -> Self {
+        self.compressor = Compressor::new(policy);
+        self
+    }
+
+    /// Sets how [`TransientResult::value_at`] and [`TransientResult::resample`] interpolate
+    /// between stored samples.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Adds a named probe, stored with the policy of the result unless it has one of its own.
+    /// Probes must be added before the first sample is recorded.
+    pub fn add_probe(
+        &mut self,
+        name: impl Into<String>,
+        probe: impl Into<StoredProbe>,
+    ) -> &mut Self {
+        assert!(self.recorded == 0, "Probes must be added before recording");
+
+        let StoredProbe { probe, storage } = probe.into();
+        let location = match storage {
+            Some(policy) => {
+                self.channels.push(Channel {
+                    compressor: Compressor::new(policy),
+                    times: Vec::new(),
+                    values: Vec::new(),
+                });
+                Location::Own(self.channels.len() - 1)
+            }
+            None => {
+                self.values.push(Vec::new());
+                Location::Shared(self.values.len() - 1)
+            }
+        };
+
+        self.names.push(name.into());
+        self.units.push(probe.get_unit());
+        self.probes.push(probe);
+        self.locations.push(location);
+        self.statistics.push(ProbeStatistics::default());
+        self
+    }
+
+    /// Records every probe after a timestep.
+    pub fn record(&mut self, solver: &BESolver) {
+        self.record_state(
+            solver.get_time(),
+            solver.get_netlist(),
+            solver.get_node_voltages(),
+        );
+    }
+
+    /// Records every probe from the state of a netlist solved without a [`BESolver`].
+    pub(crate) fn record_state(&mut self, time: f64, netlist: &Netlist, node_voltages: &[f64]) {
+        let mut shared = Vec::with_capacity(self.values.len());
+        for (k, probe) in self.probes.iter().enumerate() {
+            let value = probe.evaluate(netlist, node_voltages);
+            self.statistics[k].push(time, value);
+            match self.locations[k] {
+                Location::Shared(_) => shared.push(value),
+                Location::Own(channel) => {
+                    let channel = &mut self.channels[channel];
+                    let stored = channel.compressor.push((time, vec![value]));
+                    channel.store(stored);
+                }
+            }
+        }
+
+        self.recorded += 1;
+        let stored = self.compressor.push((time, shared));
+        self.store(stored);
+    }
+
+    /// Records that a fault was injected, see [`FaultSchedule`](crate::faults::FaultSchedule).
+    pub fn record_fault(&mut self, activation: FaultActivation) {
+        self.faults.push(activation);
+    }
+
+    /// Stores any samples held back by the storage policies. Should be called once after the
+    /// last timestep.
+    pub fn finish(&mut self) {
+        let stored = self.compressor.finish();
+        self.store(stored);
+        for channel in &mut self.channels {
+            let stored = channel.compressor.finish();
+            channel.store(stored);
+        }
+    }
+
+    fn store(&mut self, samples: Vec<Sample>) {
+        for (time, sample) in samples {
+            self.times.push(time);
+            for (values, value) in self.values.iter_mut().zip(sample) {
+                values.push(value);
+            }
+        }
+    }
+
+    /// Gets the number of samples recorded, including those the storage policy dropped.
+    pub fn get_num_recorded(&self) -> usize {
+        self.recorded
+    }
+
+    /// Gets the memory taken by the stored samples, in bytes, counting the capacity reserved for
+    /// more.
+    pub fn get_memory_usage(&self) -> usize {
+        let mut floats = self.times.capacity();
+        floats += self.values.iter().map(|v| v.capacity()).sum::<usize>();
+        floats += self
+            .channels
+            .iter()
+            .map(|c| c.times.capacity() + c.values.capacity())
+            .sum::<usize>();
+        floats * std::mem::size_of::<f64>()
+    }
+
+    pub fn get_probes(&self) -> &Vec<Probe> {
+        &self.probes
+    }
+
+    pub fn get_names(&self) -> &Vec<String> {
+        &self.names
+    }
+
+    /// Overrides the unit of the probe at the given index, which is otherwise the one of the
+    /// probed quantity (see [`Probe::get_unit`]), e.g. for the temperature of a thermal node.
+    pub fn set_unit(&mut self, probe: usize, unit: Unit) -> &mut Self {
+        self.units[probe] = unit;
+        self
+    }
+
+    pub fn get_unit(&self, probe: usize) -> Unit {
+        self.units[probe]
+    }
+
+    /// Finds the index of the probe with the given name.
+    pub fn find_probe(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Gets every fault injected during the simulation, in the order they were injected.
+    pub fn get_fault_activations(&self) -> &Vec<FaultActivation> {
+        &self.faults
+    }
+
+    /// Gets the names of the faults active at the given time, those injected at or before it.
+    pub fn get_active_faults(&self, time: f64) -> Vec<&str> {
+        self.faults
+            .iter()
+            .filter(|activation| activation.time <= time)
+            .map(|activation| activation.name.as_str())
+            .collect()
+    }
+
+    /// Gets the time of every sample stored on the shared time axis.
+    pub fn get_times(&self) -> &Vec<f64> {
+        &self.times
+    }
+
+    /// Gets the times of the stored samples of the probe at the given index, which are the
+    /// shared ones unless it has a storage policy of its own.
+    pub fn get_probe_times(&self, probe: usize) -> &Vec<f64> {
+        match self.locations[probe] {
+            Location::Shared(_) => &self.times,
+            Location::Own(channel) => &self.channels[channel].times,
+        }
+    }
+
+    /// Gets the stored samples of the probe at the given index.
+    pub fn get_values(&self, probe: usize) -> &Vec<f64> {
+        match self.locations[probe] {
+            Location::Shared(index) => &self.values[index],
+            Location::Own(channel) => &self.channels[channel].values,
+        }
+    }
+
+    /// Gets the statistics of the probe at the given index over every recorded sample,
+    /// including those its storage policy dropped.
+    pub fn get_statistics(&self, probe: usize) -> ProbeStatistics {
+        self.statistics[probe]
+    }
+
+    /// Gets the stored samples of the probe at the given index as a trace for post-processing.
+    pub fn get_trace(&self, probe: usize) -> Trace {
+        Trace::new(
+            self.get_probe_times(probe).clone(),
+            self.get_values(probe).clone(),
+        )
+        .with_unit(self.units[probe])
+    }
+
+    /// Gets the value of the probe at the given index at any time, interpolating between the
+    /// stored samples. Returns None if nothing was stored.
+    pub fn value_at(&self, probe: usize, time: f64) -> Option<f64> {
+        self.interpolation
+            .evaluate(self.get_probe_times(probe), self.get_values(probe), time)
+    }
+
+    /// Gets the values of the probe at the given index at the shared times, interpolating
+    /// probes stored on time axes of their own, NaN where nothing was stored.
+    fn shared_values(&self, probe: usize) -> Vec<f64> {
+        match self.locations[probe] {
+            Location::Shared(index) => self.values[index].clone(),
+            Location::Own(_) => self
+                .times
+                .iter()
+                .map(|&time| self.value_at(probe, time).unwrap_or(f64::NAN))
+                .collect(),
+        }
+    }
+
+    /// Resamples the probe at the given index onto a uniform grid of step `dt`, starting at the
+    /// first stored sample and ending at or before the last one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dt is zero, negative or not finite.
+    pub fn resample(&self, probe: usize, dt: f64) -> Vec<(f64, f64)> {
+        assert!(
+            dt > 0.0 && dt.is_finite(),
+            "the resampling step must be positive and finite, got {dt}"
+        );
+        let times = self.get_probe_times(probe);
+        let (Some(&start), Some(&stop)) = (times.first(), times.last()) else {
+            return Vec::new();
+        };
+
+        // Counted rather than accumulated so rounding errors don't add up over long grids.
+        let points = ((stop - start) / dt + 1e-9).floor() as usize + 1;
+        (0..points)
+            .map(|k| {
+                let time = start + k as f64 * dt;
+                let value = self
+                    .value_at(probe, time)
+                    .expect("the probe has stored samples");
+                (time, value)
+            })
+            .collect()
+    }
+
+    /// Exports every probe as CSV, one column per probe after the time, with the unit of each
+    /// column in brackets after its name. Probes stored on time axes of their own are
+    /// interpolated at the shared times.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time [s]");
+        for (name, unit) in self.names.iter().zip(&self.units) {
+            match unit {
+                Unit::Dimensionless => write!(csv, ",{name}").unwrap(),
+                unit => write!(csv, ",{name} [{}]", unit.symbol()).unwrap(),
+            }
+        }
+        csv.push('\n');
+
+        let values: Vec<Vec<f64>> = (0..self.probes.len())
+            .map(|probe| self.shared_values(probe))
+            .collect();
+        for (sample, time) in self.times.iter().enumerate() {
+            write!(csv, "{time}").unwrap();
+            for values in &values {
+                write!(csv, ",{}", values[sample]).unwrap();
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Exports the result as a level 5 MAT-file holding a struct named `result`, with the
+    /// `time` column, the `values` matrix with one column per probe, and the probe `names` and
+    /// `units` (SI symbols, empty when dimensionless). Probes stored on time axes of their own
+    /// are interpolated at the shared times.
+    pub fn to_mat(&self) -> Vec<u8> {
+        let units: Vec<&str> = self.units.iter().map(|u| u.symbol()).collect();
+        let values: Vec<Vec<f64>> = (0..self.probes.len())
+            .map(|probe| self.shared_values(probe))
+            .collect();
+        let result = MatValue::Struct(vec![
+            ("time".to_string(), MatValue::column(&self.times)),
+            (
+                "values".to_string(),
+                MatValue::from_columns(&values).expect("every probe has a value per time"),
+            ),
+            ("names".to_string(), MatValue::strings(&self.names)),
+            ("units".to_string(), MatValue::strings(&units)),
+        ]);
+        write_mat(&[("result", result)]).expect("the field names are short and distinct")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::components::{Capacitor, Netlist, Resistor, VoltageSource};
+
+    use super::*;
+
+    #[test]
+    fn derived_probes() {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 10.0))
+            .add_component(Resistor::new(1, 2, 3.0))
+            .add_component(Resistor::new(2, 0, 2.0));
+
+        let mut result = TransientResult::new();
+        result
+            .add_probe("v_r1", Probe::DifferentialVoltage(1, 2))
+            .add_probe("p_r2", Probe::ComponentPower(2))
+            .add_probe(
+                "p_total",
+                Probe::ScaledSum(vec![
+                    (1.0, Probe::ComponentPower(1)),
+                    (1.0, Probe::ComponentPower(2)),
+                ]),
+            )
+            .add_probe(
+                "v_out_mv",
+                Probe::scaled(
+                    1e3,
+                    Probe::difference(Probe::NodeVoltage(1), Probe::ComponentVoltage(1)),
+                ),
+            );
+
+        // Millivolts aren't a unit of their own, so the scaled probe is left without one.
+        result.set_unit(3, Unit::Dimensionless);
+        assert_eq!(result.get_unit(0), Unit::Volt);
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..3 {
+            solver.solve(1e-3).unwrap();
+            result.record(&solver);
+        }
+
+        assert_eq!(result.get_times().len(), 3);
+        assert_relative_eq!(result.get_values(0)[2], 6.0, max_relative = 1e-9);
+        assert_relative_eq!(result.get_values(1)[2], 8.0, max_relative = 1e-9);
+        assert_relative_eq!(result.get_values(2)[2], 20.0, max_relative = 1e-9);
+        assert_relative_eq!(result.get_values(3)[2], 4000.0, max_relative = 1e-9);
+        assert_eq!(result.find_probe("p_r2"), Some(1));
+
+        let csv = result.to_csv();
+        assert_eq!(
+            csv.lines().next().unwrap(),
+            "time [s],v_r1 [V],p_r2 [W],p_total [W],v_out_mv"
+        );
+        assert_eq!(csv.lines().count(), 4);
+
+        let variables = crate::matlab::read_mat(&result.to_mat()).unwrap();
+        assert_eq!(variables[0].0, "result");
+        let mat = &variables[0].1;
+        assert_eq!(
+            mat.field("names").unwrap().as_strings().unwrap(),
+            vec!["v_r1", "p_r2", "p_total", "v_out_mv"]
+        );
+        assert_eq!(
+            mat.field("units").unwrap().as_strings().unwrap(),
+            vec!["V", "W", "W", ""]
+        );
+        assert_eq!(
+            mat.field("time").unwrap().as_numeric(),
+            Some(result.get_times())
+        );
+        let values = mat.field("values").unwrap().as_numeric().unwrap();
+        assert_relative_eq!(values[3 + 2], 8.0, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn compressed_storage() {
+        // An RC charging towards 1V: the error bounded policy keeps few samples, but the waveform
+        // is still reproduced within the tolerance.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let mut full = TransientResult::new();
+        full.add_probe("v", Probe::NodeVoltage(2));
+        let mut compressed =
+            TransientResult::new().with_storage(StoragePolicy::ErrorBounded { tolerance: 1e-3 });
+        compressed.add_probe("v", Probe::NodeVoltage(2));
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..1000 {
+            solver.solve(1e-5).unwrap();
+            full.record(&solver);
+            compressed.record(&solver);
+        }
+        full.finish();
+        compressed.finish();
+
+        assert_eq!(compressed.get_num_recorded(), 1000);
+        assert_eq!(full.get_times().len(), 1000);
+        assert!(compressed.get_times().len() < 100);
+        assert_eq!(compressed.get_times().last(), full.get_times().last());
+
+        for (&time, &value) in full.get_times().iter().zip(full.get_values(0)) {
+            assert!((compressed.value_at(0, time).unwrap() - value).abs() <= 1e-3 + 1e-12);
+        }
+
+        let compressed = compressed.with_interpolation(Interpolation::Cubic);
+        let resampled = compressed.resample(0, 1e-4);
+        assert_eq!(resampled.len(), 100);
+        for (time, value) in resampled {
+            let exact = full.value_at(0, time).unwrap();
+            assert!((value - exact).abs() <= 1e-3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "resampling step must be positive")]
+    fn resample_zero_step() {
+        let mut result = TransientResult::new();
+        result.add_probe("v", Probe::NodeVoltage(1));
+        result.resample(0, 0.0);
+    }
+
+    #[test]
+    fn per_probe_storage() {
+        // An RC charging towards 1V: the input is kept in full, the output decimated and the
+        // current only summarised.
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 1.0))
+            .add_component(Resistor::new(1, 2, 1e3))
+            .add_component(Capacitor::new(2, 0, 1e-6, 0.0));
+
+        let mut result = TransientResult::new();
+        result
+            .add_probe("in", Probe::NodeVoltage(1))
+            .add_probe(
+                "out",
+                Probe::NodeVoltage(2).with_storage(StoragePolicy::EveryNth(10)),
+            )
+            .add_probe(
+                "i",
+                Probe::ComponentCurrent(1).with_storage(StoragePolicy::StatisticsOnly),
+            );
+
+        let mut solver = BESolver::new(&mut netlist);
+        for _ in 0..1000 {
+            solver.solve(1e-5).unwrap();
+            result.record(&solver);
+        }
+        result.finish();
+
+        assert_eq!(result.get_times().len(), 1000);
+        assert_eq!(result.get_values(0).len(), 1000);
+        assert!((100..=101).contains(&result.get_values(1).len()));
+        assert_eq!(result.get_probe_times(1).len(), result.get_values(1).len());
+        assert!(result.get_values(2).is_empty());
+        assert_eq!(result.value_at(2, 5e-3), None);
+
+        // Charging through the resistor for 10 time constants: the current starts at 1mA and
+        // carries the capacitor's 1uC of charge.
+        let current = result.get_statistics(2);
+        assert_eq!(current.count, 1000);
+        assert_relative_eq!(current.max, 1e-3, max_relative = 0.02);
+        assert!(current.min >= 0.0 && current.min < 1e-6);
+        let charge = current.mean * (current.stop - current.start);
+        assert_relative_eq!(charge, 1e-6, max_relative = 0.02);
+        assert_relative_eq!(result.get_statistics(0).rms, 1.0, max_relative = 1e-9);
+        assert_relative_eq!(result.get_statistics(1).max, 1.0, max_relative = 1e-3);
+
+        // Exports stay on the shared time axis, interpolating the decimated probe.
+        let csv = result.to_csv();
+        assert_eq!(csv.lines().count(), 1001);
+        let last: Vec<&str> = csv.lines().last().unwrap().split(',').collect();
+        assert_relative_eq!(last[2].parse::<f64>().unwrap(), 1.0, max_relative = 1e-3);
+        assert_eq!(last[3], "NaN");
+    }
+}
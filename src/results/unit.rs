@@ -0,0 +1,55 @@
+/// The unit a recorded quantity is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unit {
+    Volt,
+    Ampere,
+    Watt,
+    Ohm,
+    Siemens,
+    Farad,
+    Henry,
+    Second,
+    Hertz,
+    Joule,
+    Coulomb,
+    /// Temperatures, e.g. the node voltages of thermal networks.
+    Kelvin,
+    /// Ratios and quantities whose unit isn't known.
+    #[default]
+    Dimensionless,
+}
+
+impl Unit {
+    /// Gets the SI symbol, empty for dimensionless quantities.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Volt => "V",
+            Self::Ampere => "A",
+            Self::Watt => "W",
+            Self::Ohm => "Ω",
+            Self::Siemens => "S",
+            Self::Farad => "F",
+            Self::Henry => "H",
+            Self::Second => "s",
+            Self::Hertz => "Hz",
+            Self::Joule => "J",
+            Self::Coulomb => "C",
+            Self::Kelvin => "K",
+            Self::Dimensionless => "",
+        }
+    }
+
+    /// Gets the unit of the product of two quantities, dimensionless when it isn't one of the
+    /// units here.
+    pub fn multiply(&self, other: Unit) -> Unit {
+        match (*self, other) {
+            (unit, Self::Dimensionless) | (Self::Dimensionless, unit) => unit,
+            (Self::Volt, Self::Ampere) | (Self::Ampere, Self::Volt) => Self::Watt,
+            (Self::Ampere, Self::Ohm) | (Self::Ohm, Self::Ampere) => Self::Volt,
+            (Self::Volt, Self::Siemens) | (Self::Siemens, Self::Volt) => Self::Ampere,
+            (Self::Watt, Self::Second) | (Self::Second, Self::Watt) => Self::Joule,
+            (Self::Ampere, Self::Second) | (Self::Second, Self::Ampere) => Self::Coulomb,
+            _ => Self::Dimensionless,
+        }
+    }
+}
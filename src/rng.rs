@@ -0,0 +1,89 @@
+/// Increment of the SplitMix64 state, the golden ratio in 64 bit fixed point.
+const GOLDEN_GAMMA: u64 = 0x9E3779B97F4A7C15;
+
+/// Deterministic random number generator used by every stochastic feature, so a whole run can be
+/// reproduced from a single seed (see [`SolverOptions::seed`](crate::SolverOptions::seed)).
+///
+/// This is SplitMix64: small, fast, and able to jump ahead or split off independent streams in
+/// constant time. Features should draw from their own [`Rng::stream`] rather than sharing one
+/// generator, so the numbers one gets don't depend on how many another drew.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Derives an independent generator identified by the key (e.g. a component index) without
+    /// advancing this one.
+    pub fn stream(&self, key: u64) -> Self {
+        Self::new(mix(
+            self.state ^ mix(key.wrapping_add(1).wrapping_mul(GOLDEN_GAMMA))
+        ))
+    }
+
+    /// Advances the generator as if `n` numbers were drawn.
+    pub fn skip(&mut self, n: u64) {
+        self.state = self.state.wrapping_add(n.wrapping_mul(GOLDEN_GAMMA));
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    /// Draws a number uniformly distributed in [0, 1).
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Draws a number uniformly distributed in [low, high).
+    pub fn uniform_range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.uniform()
+    }
+
+    /// Draws a normally distributed number with zero mean and unit standard deviation.
+    pub fn gaussian(&mut self) -> f64 {
+        // Box-Muller, with 1 - u so the logarithm never sees zero.
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        let angle = 2.0 * std::f64::consts::PI * self.uniform();
+        radius * angle.cos()
+    }
+}
+
+/// The SplitMix64 output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reproducible_streams() {
+        let rng = Rng::new(42);
+        let draw = |mut rng: Rng| (0..4).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        assert_eq!(draw(rng.stream(3)), draw(Rng::new(42).stream(3)));
+        assert_ne!(draw(rng.stream(3)), draw(rng.stream(4)));
+        assert_ne!(draw(rng), draw(Rng::new(43)));
+
+        let mut skipped = rng;
+        skipped.skip(2);
+        assert_eq!(draw(skipped)[..2], draw(rng)[2..]);
+
+        let mut rng = Rng::new(7);
+        let samples: Vec<f64> = (0..20000).map(|_| rng.gaussian()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.03);
+        assert!((variance - 1.0).abs() < 0.05);
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.uniform())));
+    }
+}
@@ -0,0 +1,486 @@
+use std::f64::consts::PI;
+
+use nalgebra::Complex;
+
+use crate::components::{Capacitor, CurrentProbe, Inductor, Netlist, Resistor, VoltageSource};
+use crate::library::Subcircuit;
+use crate::reports::{SmithPoint, smith_chart};
+use crate::{ACSolver, SolverError};
+
+/// The topology of a matching network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchingTopology {
+    /// A series and a shunt element, the simplest match, with a Q set by the ratio of the
+    /// resistances.
+    LSection,
+    /// Shunt, series, shunt: two L-sections back to back through a virtual resistance below
+    /// both, the larger resistance divided by 1 + q^2, for a higher Q (narrower band) than an
+    /// L-section.
+    Pi { q: f64 },
+    /// Series, shunt, series: two L-sections back to back through a virtual resistance above
+    /// both, the smaller resistance multiplied by 1 + q^2.
+    T { q: f64 },
+}
+
+/// Why a matching network couldn't be designed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchingError {
+    /// The impedance has a nonpositive or infinite resistance, or isn't finite.
+    InvalidImpedance { impedance: Complex<f64> },
+    /// The frequency is nonpositive or not finite.
+    InvalidFrequency { frequency: f64 },
+    /// The Q of a Pi or T network is nonpositive or not finite.
+    InvalidQ { q: f64 },
+    /// No network of the topology matches the impedances, for example a Pi or T network whose Q
+    /// is lower than the L-section's.
+    Unreachable,
+}
+
+/// An element of a matching network, its value in henries or farads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchingElement {
+    SeriesInductor(f64),
+    SeriesCapacitor(f64),
+    ShuntInductor(f64),
+    ShuntCapacitor(f64),
+}
+
+/// An element of a matching network as a reactance or susceptance, before it's realized at the
+/// frequency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Series(f64),
+    Shunt(f64),
+}
+
+impl Step {
+    /// Realizes the step as an inductor or capacitor, None for a zero reactance or susceptance
+    /// (a short or an open).
+    fn realize(self, omega: f64) -> Option<MatchingElement> {
+        match self {
+            Self::Series(x) if x > 0.0 => Some(MatchingElement::SeriesInductor(x / omega)),
+            Self::Series(x) if x < 0.0 => {
+                Some(MatchingElement::SeriesCapacitor(-1.0 / (omega * x)))
+            }
+            Self::Shunt(b) if b > 0.0 => Some(MatchingElement::ShuntCapacitor(b / omega)),
+            Self::Shunt(b) if b < 0.0 => Some(MatchingElement::ShuntInductor(-1.0 / (omega * b))),
+            _ => None,
+        }
+    }
+}
+
+/// The L-sections with the shunt element across the load, series element first, transforming
+/// the load into the target impedance.
+fn shunt_at_load(load: Complex<f64>, target: Complex<f64>) -> Vec<[Step; 2]> {
+    let y = 1.0 / load;
+    let discriminant = y.re / target.re - y.re * y.re;
+    roots(discriminant)
+        .into_iter()
+        .map(|b| {
+            let z = 1.0 / Complex::new(y.re, b);
+            [Step::Series(target.im - z.im), Step::Shunt(b - y.im)]
+        })
+        .collect()
+}
+
+/// The L-sections with the series element at the load, shunt element first, transforming the
+/// load into the target impedance.
+fn series_at_load(load: Complex<f64>, target: Complex<f64>) -> Vec<[Step; 2]> {
+    let y_target = 1.0 / target;
+    let discriminant = load.re / y_target.re - load.re * load.re;
+    roots(discriminant)
+        .into_iter()
+        .map(|x| {
+            let y = 1.0 / Complex::new(load.re, x);
+            [Step::Shunt(y_target.im - y.im), Step::Series(x - load.im)]
+        })
+        .collect()
+}
+
+/// Gets the distinct square roots of a discriminant, none if it's negative.
+fn roots(discriminant: f64) -> Vec<f64> {
+    if discriminant < 0.0 {
+        Vec::new()
+    } else if discriminant == 0.0 {
+        vec![0.0]
+    } else {
+        vec![discriminant.sqrt(), -discriminant.sqrt()]
+    }
+}
+
+/// The specification of a network matching a load impedance to a source impedance at a
+/// frequency, as an L-section by default.
+///
+/// The load is matched when the source sees the conjugate of its own impedance, delivering its
+/// available power into the load. Each topology has several solutions (e.g. an L-section
+/// high-pass or low-pass), all of which are synthesized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchingSpec {
+    source: Complex<f64>,
+    load: Complex<f64>,
+    frequency: f64,
+    topology: MatchingTopology,
+}
+
+impl MatchingSpec {
+    pub fn new(source: Complex<f64>, load: Complex<f64>, frequency: f64) -> Self {
+        Self {
+            source,
+            load,
+            frequency,
+            topology: MatchingTopology::LSection,
+        }
+    }
+
+    pub fn with_topology(mut self, topology: MatchingTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn get_topology(&self) -> MatchingTopology {
+        self.topology
+    }
+
+    /// Designs every network of the topology matching the load to the source.
+    pub fn synthesize(&self) -> Result<Vec<MatchingNetwork>, MatchingError> {
+        for impedance in [self.source, self.load] {
+            if !impedance.re.is_finite() || !impedance.im.is_finite() || impedance.re <= 0.0 {
+                return Err(MatchingError::InvalidImpedance { impedance });
+            }
+        }
+        if !self.frequency.is_finite() || self.frequency <= 0.0 {
+            return Err(MatchingError::InvalidFrequency {
+                frequency: self.frequency,
+            });
+        }
+
+        let target = self.source.conj();
+        let solutions: Vec<Vec<Step>> = match self.topology {
+            MatchingTopology::LSection => shunt_at_load(self.load, target)
+                .into_iter()
+                .chain(series_at_load(self.load, target))
+                .map(Vec::from)
+                .collect(),
+            MatchingTopology::Pi { q } => {
+                check_q(q)?;
+                let virtual_resistance =
+                    Complex::from(self.source.re.max(self.load.re) / (1.0 + q * q));
+                let sources = series_at_load(virtual_resistance, target);
+                let loads = shunt_at_load(self.load, virtual_resistance);
+                join(&sources, &loads)
+            }
+            MatchingTopology::T { q } => {
+                check_q(q)?;
+                let virtual_resistance =
+                    Complex::from(self.source.re.min(self.load.re) * (1.0 + q * q));
+                let sources = shunt_at_load(virtual_resistance, target);
+                let loads = series_at_load(self.load, virtual_resistance);
+                join(&sources, &loads)
+            }
+        };
+        if solutions.is_empty() {
+            return Err(MatchingError::Unreachable);
+        }
+
+        let omega = 2.0 * PI * self.frequency;
+        Ok(solutions
+            .into_iter()
+            .map(|steps| MatchingNetwork {
+                input_node: 1,
+                output_node: 2,
+                ground_node: 0,
+                elements: steps.into_iter().filter_map(|s| s.realize(omega)).collect(),
+                source: self.source,
+                load: self.load,
+                frequency: self.frequency,
+            })
+            .collect())
+    }
+}
+
+/// Joins every L-section on the source side to every L-section on the load side, the two
+/// elements meeting in the middle (both series or both shunt) merging into one.
+fn join(sources: &[[Step; 2]], loads: &[[Step; 2]]) -> Vec<Vec<Step>> {
+    sources
+        .iter()
+        .flat_map(|&[first, a]| {
+            loads.iter().map(move |&[b, last]| {
+                let middle = match (a, b) {
+                    (Step::Series(a), Step::Series(b)) => Step::Series(a + b),
+                    (Step::Shunt(a), Step::Shunt(b)) => Step::Shunt(a + b),
+                    _ => unreachable!("The L-sections meet on elements of different kinds"),
+                };
+                vec![first, middle, last]
+            })
+        })
+        .collect()
+}
+
+fn check_q(q: f64) -> Result<(), MatchingError> {
+    if q.is_finite() && q > 0.0 {
+        Ok(())
+    } else {
+        Err(MatchingError::InvalidQ { q })
+    }
+}
+
+/// A matching network designed by [`MatchingSpec::synthesize`], between the input node facing
+/// the source and the output node facing the load (nodes 1 and 2 over ground by default).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchingNetwork {
+    input_node: usize,
+    output_node: usize,
+    ground_node: usize,
+    elements: Vec<MatchingElement>,
+    source: Complex<f64>,
+    load: Complex<f64>,
+    frequency: f64,
+}
+
+impl MatchingNetwork {
+    /// Places the network between other nodes.
+    pub fn with_nodes(mut self, input_node: usize, output_node: usize, ground_node: usize) -> Self {
+        self.input_node = input_node;
+        self.output_node = output_node;
+        self.ground_node = ground_node;
+        self
+    }
+
+    /// Gets the elements from the source to the load.
+    pub fn get_elements(&self) -> &Vec<MatchingElement> {
+        &self.elements
+    }
+
+    pub fn get_source_impedance(&self) -> Complex<f64> {
+        self.source
+    }
+
+    pub fn get_load_impedance(&self) -> Complex<f64> {
+        self.load
+    }
+
+    pub fn get_frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Builds the network into a netlist driven by a 1V AC voltage source (its first component)
+    /// behind the source impedance, with the load impedance across the output. Both impedances
+    /// are realized at the design frequency, as a resistor in series with an inductor or a
+    /// capacitor.
+    pub fn to_netlist(&self) -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.ground_node));
+        let source_node = netlist.add_node();
+        netlist.add_component(
+            VoltageSource::new(source_node, self.ground_node, 0.0).with_ac(1.0, 0.0),
+        );
+        self.add_impedance(&mut netlist, source_node, self.input_node, self.source);
+        self.add_to(&mut netlist);
+        self.add_impedance(&mut netlist, self.output_node, self.ground_node, self.load);
+        netlist
+    }
+
+    /// Simulates the impedance the source sees at the frequency, looking into the network
+    /// terminated by the load.
+    pub fn simulate_input_impedance(&self, frequency: f64) -> Result<Complex<f64>, SolverError> {
+        let mut netlist = self.to_netlist();
+        netlist.remove_component(0);
+        ACSolver::new(&netlist).port_impedance(self.input_node, self.ground_node, frequency)
+    }
+
+    /// Verifies the match with the AC engine, returning the magnitude of the reflection
+    /// coefficient of the power waves at the source, zero for a perfect match.
+    pub fn verify(&self) -> Result<f64, SolverError> {
+        let impedance = self.simulate_input_impedance(self.frequency)?;
+        Ok(((impedance - self.source.conj()) / (impedance + self.source)).norm())
+    }
+
+    /// Traces the simulated input impedance over the frequencies on a Smith chart against the
+    /// reference impedance.
+    pub fn smith_trace(
+        &self,
+        frequencies: &[f64],
+        reference: f64,
+    ) -> Result<Vec<SmithPoint>, SolverError> {
+        let impedances: Vec<Complex<f64>> = frequencies
+            .iter()
+            .map(|&frequency| self.simulate_input_impedance(frequency))
+            .collect::<Result<_, _>>()?;
+        Ok(smith_chart(frequencies, &impedances, reference))
+    }
+
+    /// Adds an impedance, realized at the design frequency, between two nodes.
+    fn add_impedance(
+        &self,
+        netlist: &mut Netlist,
+        from: usize,
+        to: usize,
+        impedance: Complex<f64>,
+    ) {
+        let omega = 2.0 * PI * self.frequency;
+        let reactance = match Step::Series(impedance.im).realize(omega) {
+            Some(element) => element,
+            None => {
+                netlist.add_component(Resistor::new(from, to, impedance.re));
+                return;
+            }
+        };
+
+        let middle = netlist.add_node();
+        netlist.add_component(Resistor::new(from, middle, impedance.re));
+        match reactance {
+            MatchingElement::SeriesInductor(l) => {
+                netlist.add_component(Inductor::new(middle, to, l, 0.0));
+            }
+            MatchingElement::SeriesCapacitor(c) => {
+                netlist.add_component(Capacitor::new(middle, to, c, 0.0));
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Subcircuit for MatchingNetwork {
+    fn add_to(&self, netlist: &mut Netlist) {
+        netlist.reserve_nodes(self.input_node.max(self.output_node).max(self.ground_node));
+        let series = self
+            .elements
+            .iter()
+            .filter(|element| {
+                matches!(
+                    element,
+                    MatchingElement::SeriesInductor(_) | MatchingElement::SeriesCapacitor(_)
+                )
+            })
+            .count();
+
+        // Each series element moves on to the next node, the last one reaching the output.
+        let mut node = self.input_node;
+        let mut remaining = series;
+        let mut next_node = |netlist: &mut Netlist| {
+            remaining -= 1;
+            if remaining == 0 {
+                self.output_node
+            } else {
+                netlist.add_node()
+            }
+        };
+        if series == 0 {
+            // Shunt elements only, the input and output are the same node.
+            netlist.add_component(CurrentProbe::new(self.input_node, self.output_node));
+        }
+        for element in &self.elements {
+            match *element {
+                MatchingElement::SeriesInductor(l) => {
+                    let next = next_node(netlist);
+                    netlist.add_component(Inductor::new(node, next, l, 0.0));
+                    node = next;
+                }
+                MatchingElement::SeriesCapacitor(c) => {
+                    let next = next_node(netlist);
+                    netlist.add_component(Capacitor::new(node, next, c, 0.0));
+                    node = next;
+                }
+                MatchingElement::ShuntInductor(l) => {
+                    netlist.add_component(Inductor::new(node, self.ground_node, l, 0.0));
+                }
+                MatchingElement::ShuntCapacitor(c) => {
+                    netlist.add_component(Capacitor::new(node, self.ground_node, c, 0.0));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_l_section() {
+        // 50Ω to 200Ω at 100MHz: Q = sqrt(200/50 - 1), a series reactance of Q*50 and a shunt
+        // susceptance of Q/200 across the load, low-pass or high-pass.
+        let frequency = 100e6;
+        let omega = 2.0 * PI * frequency;
+        let q = 3f64.sqrt();
+        let networks = MatchingSpec::new(Complex::from(50.0), Complex::from(200.0), frequency)
+            .synthesize()
+            .unwrap();
+        assert_eq!(networks.len(), 2);
+        let &[
+            MatchingElement::SeriesInductor(l),
+            MatchingElement::ShuntCapacitor(c),
+        ] = networks[0].get_elements().as_slice()
+        else {
+            panic!("expected a low-pass L-section");
+        };
+        assert_relative_eq!(l, q * 50.0 / omega, max_relative = 1e-12);
+        assert_relative_eq!(c, q / 200.0 / omega, max_relative = 1e-12);
+        assert!(matches!(
+            networks[1].get_elements().as_slice(),
+            [
+                MatchingElement::SeriesCapacitor(_),
+                MatchingElement::ShuntInductor(_)
+            ]
+        ));
+
+        // Complex impedances on either side, matched to the conjugate of the source.
+        let spec = MatchingSpec::new(Complex::new(25.0, 10.0), Complex::new(10.0, -40.0), 1e9);
+        let networks = spec.synthesize().unwrap();
+        assert!(!networks.is_empty());
+        for network in networks {
+            assert!(network.verify().unwrap() < 1e-6);
+        }
+
+        // On a Smith chart against the source resistance, the match sits at the center.
+        let network = &MatchingSpec::new(Complex::from(50.0), Complex::new(15.0, 20.0), frequency)
+            .synthesize()
+            .unwrap()[0];
+        let trace = network
+            .smith_trace(&[0.5 * frequency, frequency, 2.0 * frequency], 50.0)
+            .unwrap();
+        assert!(trace[1].reflection.norm() < 1e-6);
+        assert!(trace[0].reflection.norm() > 0.1);
+        assert!(trace[2].reflection.norm() > 0.1);
+    }
+
+    #[test]
+    fn test_pi_and_t_networks() {
+        let (source, load) = (Complex::from(50.0), Complex::new(200.0, 30.0));
+        for topology in [
+            MatchingTopology::Pi { q: 5.0 },
+            MatchingTopology::T { q: 5.0 },
+        ] {
+            let networks = MatchingSpec::new(source, load, 10e6)
+                .with_topology(topology)
+                .synthesize()
+                .unwrap();
+            assert_eq!(networks.len(), 4);
+            for network in networks {
+                assert_eq!(network.get_elements().len(), 3);
+                assert!(network.verify().unwrap() < 1e-6);
+            }
+        }
+
+        // A Pi network of lower Q than the L-section has no virtual resistance below both.
+        let spec = MatchingSpec::new(source, load, 10e6);
+        assert_eq!(
+            spec.with_topology(MatchingTopology::Pi { q: 0.5 })
+                .synthesize(),
+            Err(MatchingError::Unreachable)
+        );
+        assert_eq!(
+            spec.with_topology(MatchingTopology::T { q: -1.0 })
+                .synthesize(),
+            Err(MatchingError::InvalidQ { q: -1.0 })
+        );
+        assert_eq!(
+            MatchingSpec::new(source, Complex::new(0.0, 50.0), 10e6).synthesize(),
+            Err(MatchingError::InvalidImpedance {
+                impedance: Complex::new(0.0, 50.0)
+            })
+        );
+    }
+}
@@ -0,0 +1,261 @@
+mod matching;
+pub use matching::{
+    MatchingElement, MatchingError, MatchingNetwork, MatchingSpec, MatchingTopology,
+};
+
+use std::f64::consts::PI;
+
+use nalgebra::Complex;
+
+use crate::components::{Netlist, VoltageSource};
+use crate::library::{FilterBand, FilterPrototype, FilterResponse, LcLadderFilter};
+
+/// A rational transfer function H(s) = gain * prod(s - zero) / prod(s - pole), with s in rad/s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFunction {
+    gain: f64,
+    zeros: Vec<Complex<f64>>,
+    poles: Vec<Complex<f64>>,
+}
+
+impl TransferFunction {
+    pub fn new(gain: f64, zeros: Vec<Complex<f64>>, poles: Vec<Complex<f64>>) -> Self {
+        Self { gain, zeros, poles }
+    }
+
+    pub fn get_gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn get_zeros(&self) -> &Vec<Complex<f64>> {
+        &self.zeros
+    }
+
+    pub fn get_poles(&self) -> &Vec<Complex<f64>> {
+        &self.poles
+    }
+
+    /// Evaluates the transfer function at the complex frequency s in rad/s.
+    pub fn evaluate_at(&self, s: Complex<f64>) -> Complex<f64> {
+        let numerator: Complex<f64> = self.zeros.iter().map(|z| s - z).product();
+        let denominator: Complex<f64> = self.poles.iter().map(|p| s - p).product();
+        self.gain * numerator / denominator
+    }
+
+    /// Evaluates the frequency response at the given frequency in hertz.
+    pub fn evaluate(&self, frequency: f64) -> Complex<f64> {
+        self.evaluate_at(Complex::new(0.0, 2.0 * PI * frequency))
+    }
+}
+
+/// The specification of a filter to synthesize: its response, order and band, and the
+/// impedance of the source driving it (50Ω by default).
+///
+/// Filters are synthesized as doubly terminated passive LC ladders (see [`LcLadderFilter`]).
+/// Active topologies (Sallen-Key, multiple feedback) need an op-amp model, which rice doesn't
+/// have yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterSpec {
+    response: FilterResponse,
+    order: usize,
+    band: FilterBand,
+    termination: f64,
+}
+
+/// A synthesized filter: a netlist driven by a 1V AC voltage source (its first component) at
+/// the input node, with the load across the output node, and the transfer function from the
+/// source to the output the netlist should have.
+#[derive(Debug, Clone)]
+pub struct SynthesizedFilter {
+    pub netlist: Netlist,
+    pub input_node: usize,
+    pub output_node: usize,
+    pub transfer_function: TransferFunction,
+}
+
+impl FilterSpec {
+    pub fn new(response: FilterResponse, order: usize, band: FilterBand) -> Self {
+        Self {
+            response,
+            order,
+            band,
+            termination: 50.0,
+        }
+    }
+
+    /// Sets the impedance of the source the filter is designed for, in ohms.
+    pub fn with_termination(mut self, termination: f64) -> Self {
+        self.termination = termination;
+        self
+    }
+
+    pub fn get_prototype(&self) -> FilterPrototype {
+        FilterPrototype::new(self.response, self.order)
+    }
+
+    pub fn get_band(&self) -> FilterBand {
+        self.band
+    }
+
+    pub fn get_termination(&self) -> f64 {
+        self.termination
+    }
+
+    /// Builds the filter into a netlist with the input on node 1, the output on node 2 and
+    /// ground on node 0.
+    pub fn synthesize(&self) -> SynthesizedFilter {
+        let filter = LcLadderFilter::new(1, 2, 0, self.get_prototype(), 0.0, self.termination)
+            .with_band(self.band);
+        let mut netlist = Netlist::new();
+        netlist
+            .add_component(VoltageSource::new(1, 0, 0.0).with_ac(1.0, 0.0))
+            .add_subcircuit(&filter);
+
+        let load = filter.get_load_resistance();
+        SynthesizedFilter {
+            netlist,
+            input_node: 1,
+            output_node: 2,
+            transfer_function: self.transfer_function(load / (load + self.termination)),
+        }
+    }
+
+    /// Gets the transfer function of the filter, whose low-pass prototype has the DC gain of
+    /// the divider of the terminations.
+    ///
+    /// Each pole p of the prototype becomes wc*p in a low-pass, wc/p in a high-pass, the two
+    /// roots of s^2 - p*B*s + w0^2 in a band-pass and of p*s^2 - B*s + p*w0^2 in a band-stop,
+    /// the transformations adding zeros at 0 or at +-j*w0. The gain is matched to the prototype
+    /// at a frequency mapping into its passband.
+    fn transfer_function(&self, divider: f64) -> TransferFunction {
+        let prototype_poles = self.get_prototype().get_poles();
+        let n = prototype_poles.len();
+        let dc: Complex<f64> = prototype_poles.iter().map(|p| -p).product();
+        let prototype = TransferFunction::new(divider * dc.re, Vec::new(), prototype_poles);
+
+        let quadratic = |a: Complex<f64>, b: Complex<f64>, c: Complex<f64>| {
+            let root = (b * b - 4.0 * a * c).sqrt();
+            [(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+        };
+        let one = Complex::from(1.0);
+        let (zeros, poles, reference) = match self.band {
+            FilterBand::LowPass { cutoff } => {
+                let wc = 2.0 * PI * cutoff;
+                let poles = prototype.get_poles().iter().map(|p| wc * p).collect();
+                (Vec::new(), poles, Complex::from(0.0))
+            }
+            FilterBand::HighPass { cutoff } => {
+                let wc = 2.0 * PI * cutoff;
+                let poles = prototype.get_poles().iter().map(|p| wc / p).collect();
+                (vec![Complex::from(0.0); n], poles, Complex::new(0.0, wc))
+            }
+            FilterBand::BandPass { center, bandwidth } => {
+                let (w0, b) = (2.0 * PI * center, 2.0 * PI * bandwidth);
+                let poles = prototype
+                    .get_poles()
+                    .iter()
+                    .flat_map(|p| quadratic(one, -p * b, Complex::from(w0 * w0)))
+                    .collect();
+                (vec![Complex::from(0.0); n], poles, Complex::new(0.0, w0))
+            }
+            FilterBand::BandStop { center, bandwidth } => {
+                let (w0, b) = (2.0 * PI * center, 2.0 * PI * bandwidth);
+                let poles = prototype
+                    .get_poles()
+                    .iter()
+                    .flat_map(|&p| quadratic(p, Complex::from(-b), p * w0 * w0))
+                    .collect();
+                let zeros = (0..n)
+                    .flat_map(|_| [Complex::new(0.0, w0), Complex::new(0.0, -w0)])
+                    .collect();
+                (zeros, poles, Complex::from(0.0))
+            }
+        };
+
+        let unscaled = TransferFunction::new(1.0, zeros, poles);
+        let gain = prototype.evaluate_at(self.band.to_prototype(reference))
+            / unscaled.evaluate_at(reference);
+        TransferFunction::new(gain.re, unscaled.zeros, unscaled.poles)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::ACSolver;
+
+    #[test]
+    fn test_filter_synthesis() {
+        let specs = [
+            FilterSpec::new(
+                FilterResponse::Butterworth,
+                5,
+                FilterBand::LowPass { cutoff: 1e3 },
+            ),
+            FilterSpec::new(
+                FilterResponse::Chebyshev { ripple: 0.5 },
+                4,
+                FilterBand::HighPass { cutoff: 10e3 },
+            ),
+            FilterSpec::new(
+                FilterResponse::Butterworth,
+                3,
+                FilterBand::BandPass {
+                    center: 10.7e6,
+                    bandwidth: 200e3,
+                },
+            )
+            .with_termination(75.0),
+            FilterSpec::new(
+                FilterResponse::Chebyshev { ripple: 1.0 },
+                3,
+                FilterBand::BandStop {
+                    center: 50.0,
+                    bandwidth: 10.0,
+                },
+            )
+            .with_termination(600.0),
+        ];
+
+        for spec in specs {
+            let filter = spec.synthesize();
+            let solver = ACSolver::new(&filter.netlist);
+            let center = match spec.get_band() {
+                FilterBand::LowPass { cutoff } | FilterBand::HighPass { cutoff } => cutoff,
+                FilterBand::BandPass { center, .. } | FilterBand::BandStop { center, .. } => center,
+            };
+            for k in 0..=40 {
+                let frequency = center * 10f64.powf((k as f64 - 20.0) / 20.0);
+                let simulated = solver
+                    .solve(frequency)
+                    .unwrap()
+                    .get_node_voltage(filter.output_node);
+                let expected = filter.transfer_function.evaluate(frequency);
+                assert_relative_eq!(
+                    (simulated - expected).norm(),
+                    0.0,
+                    epsilon = 1e-6 * expected.norm().max(1e-6)
+                );
+            }
+        }
+
+        // The band edges are down by the ripple.
+        let spec = FilterSpec::new(
+            FilterResponse::Chebyshev { ripple: 0.5 },
+            3,
+            FilterBand::BandPass {
+                center: 1e6,
+                bandwidth: 100e3,
+            },
+        );
+        let transfer_function = spec.synthesize().transfer_function;
+        let upper = 0.05e6 + (0.05e6f64.powi(2) + 1e12).sqrt();
+        assert_relative_eq!(
+            transfer_function.evaluate(upper).norm(),
+            0.5 * 10f64.powf(-0.5 / 20.0),
+            max_relative = 1e-9
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Construction helpers taking [`uom`] quantities, so component values are checked for their
+//! dimensions at compile time, e.g. a capacitance can't be passed as a resistance. Enabled by
+//! the `units` feature.
+//!
+//! ```
+//! use rice::components::Resistor;
+//! use uom::si::{electrical_resistance::kiloohm, f64::ElectricalResistance};
+//!
+//! let resistor = Resistor::from_resistance(1, 0, ElectricalResistance::new::<kiloohm>(4.7));
+//! assert_eq!(resistor.get_resistance(), 4700.0);
+//! ```
+
+pub use uom;
+
+use uom::si::f64::{
+    Capacitance, ElectricCurrent, ElectricPotential, ElectricalResistance, Inductance,
+};
+use uom::si::{
+    capacitance::farad, electric_current::ampere, electric_potential::volt,
+    electrical_resistance::ohm, inductance::henry,
+};
+
+use crate::components::{Capacitor, CurrentSource, Inductor, Resistor, VoltageSource};
+
+impl Resistor {
+    pub fn from_resistance(
+        positive_node: usize,
+        negative_node: usize,
+        resistance: ElectricalResistance,
+    ) -> Self {
+        Self::new(positive_node, negative_node, resistance.get::<ohm>())
+    }
+}
+
+impl Capacitor {
+    pub fn from_capacitance(
+        positive_node: usize,
+        negative_node: usize,
+        capacitance: Capacitance,
+        initial_voltage: ElectricPotential,
+    ) -> Self {
+        Self::new(
+            positive_node,
+            negative_node,
+            capacitance.get::<farad>(),
+            initial_voltage.get::<volt>(),
+        )
+    }
+}
+
+impl Inductor {
+    pub fn from_inductance(
+        positive_node: usize,
+        negative_node: usize,
+        inductance: Inductance,
+        initial_current: ElectricCurrent,
+    ) -> Self {
+        Self::new(
+            positive_node,
+            negative_node,
+            inductance.get::<henry>(),
+            initial_current.get::<ampere>(),
+        )
+    }
+}
+
+impl VoltageSource {
+    pub fn from_voltage(
+        positive_node: usize,
+        negative_node: usize,
+        voltage: ElectricPotential,
+    ) -> Self {
+        Self::new(positive_node, negative_node, voltage.get::<volt>())
+    }
+}
+
+impl CurrentSource {
+    pub fn from_current(
+        positive_node: usize,
+        negative_node: usize,
+        current: ElectricCurrent,
+    ) -> Self {
+        Self::new(positive_node, negative_node, current.get::<ampere>())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        capacitance::microfarad, electric_current::milliampere, electric_potential::millivolt,
+        inductance::microhenry,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_quantities() {
+        let capacitor = Capacitor::from_capacitance(
+            1,
+            0,
+            Capacitance::new::<microfarad>(10.0),
+            ElectricPotential::new::<millivolt>(500.0),
+        );
+        assert_relative_eq!(capacitor.get_voltage(), 0.5);
+
+        let inductor = Inductor::from_inductance(
+            1,
+            0,
+            Inductance::new::<microhenry>(22.0),
+            ElectricCurrent::new::<milliampere>(100.0),
+        );
+        assert_relative_eq!(inductor.get_inductance(), 22e-6, max_relative = 1e-12);
+        assert_relative_eq!(inductor.get_current(), 0.1, max_relative = 1e-12);
+
+        let source = CurrentSource::from_current(0, 1, ElectricCurrent::new::<ampere>(2.0));
+        assert_eq!(source.get_current(), 2.0);
+        let source = VoltageSource::from_voltage(1, 0, ElectricPotential::new::<volt>(5.0));
+        assert_eq!(source.get_voltage(), 5.0);
+    }
+}